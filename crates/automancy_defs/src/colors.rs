@@ -1,12 +1,34 @@
 use crate::math::Vec4;
-use yakui::Color;
+
+#[cfg(feature = "ui")]
+pub use yakui::Color;
+
+/// An 8-bit RGBA color, laid out like yakui's, for builds without the ui.
+#[cfg(not(feature = "ui"))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+#[cfg(not(feature = "ui"))]
+impl Color {
+    pub const BLACK: Color = Color {
+        r: 0,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+}
 
 macro_rules! hex_color {
     ($s:literal) => {{
         let array = color_hex::color_from_hex!($s);
 
         if array.len() == 3 {
-            yakui::Color {
+            Color {
                 r: array[0],
                 g: array[1],
                 b: array[2],
@@ -14,14 +36,14 @@ macro_rules! hex_color {
             }
         } else if array.len() == 4 {
             #[allow(clippy::out_of_bounds_indexing)]
-            yakui::Color {
+            Color {
                 r: array[0],
                 g: array[1],
                 b: array[2],
                 a: array[3],
             }
         } else {
-            yakui::Color::BLACK
+            Color::BLACK
         }
     }};
 }
@@ -57,5 +79,9 @@ pub const BACKGROUND_3: Color = hex_color!("#bbbbbb");
 pub const INACTIVE: Color = hex_color!("#9a9a9a70");
 pub const TEXT_INACTIVE: Color = hex_color!("#9a9a9a");
 
+/// The default background gradient, from the top of the screen to the bottom.
+pub const SKY_TOP: Color = hex_color!("#1c2030");
+pub const SKY_BOTTOM: Color = hex_color!("#000000");
+
 pub const INPUT: Color = hex_color!("#44c8ff");
 pub const OUTPUT: Color = hex_color!("#ff9844");
@@ -175,6 +175,19 @@ impl TileBounds {
             TileBounds::Hex(v) => v.is_in_bounds(*coord),
         }
     }
+
+    /// Checks if every tile of the other bounds is in these.
+    #[inline]
+    #[must_use]
+    pub fn covers(&self, other: TileBounds) -> bool {
+        match (self, other) {
+            (_, TileBounds::Empty) => true,
+            (TileBounds::Empty, _) => false,
+            (TileBounds::Hex(a), TileBounds::Hex(b)) => {
+                a.center.unsigned_distance_to(b.center) + b.radius <= a.radius
+            }
+        }
+    }
 }
 
 impl FromIterator<TileCoord> for TileBounds {
@@ -265,3 +278,155 @@ impl IntoIterator for TileBounds {
         ExactSizeCoordIterator::new(self)
     }
 }
+
+/// One step of building a [TileBoundsSet].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TileArea {
+    Add(TileBounds),
+    Subtract(TileBounds),
+}
+
+impl TileArea {
+    #[inline]
+    #[must_use]
+    pub fn bounds(&self) -> TileBounds {
+        match self {
+            TileArea::Add(bounds) | TileArea::Subtract(bounds) => *bounds,
+        }
+    }
+}
+
+/// The most areas a [TileBoundsSet] is made of. The areas a later one covers are dropped, so only a region edited in
+/// many separate places gets there.
+pub const MAX_TILE_AREAS: usize = 64;
+
+/// A region of tiles, made of hex ranges added to and cut out of it in order. A later area wins over an earlier one,
+/// so a range cut out of the region can be added back again.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TileBoundsSet {
+    areas: Vec<TileArea>,
+}
+
+impl TileBoundsSet {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn areas(&self) -> &[TileArea] {
+        &self.areas
+    }
+
+    /// Adds the range to the region. Returns false if the region is already made of [MAX_TILE_AREAS] areas, and the
+    /// range wasn't added.
+    pub fn add(&mut self, bounds: TileBounds) -> bool {
+        if bounds == TileBounds::Empty {
+            return true;
+        }
+
+        self.push(TileArea::Add(bounds))
+    }
+
+    /// Cuts the range out of the region. Does nothing to an empty region, as there is nothing to cut. Returns false if
+    /// the region is already made of [MAX_TILE_AREAS] areas, and the range wasn't cut out.
+    pub fn subtract(&mut self, bounds: TileBounds) -> bool {
+        if bounds == TileBounds::Empty || self.areas.is_empty() {
+            return true;
+        }
+
+        self.push(TileArea::Subtract(bounds))
+    }
+
+    fn push(&mut self, area: TileArea) -> bool {
+        let bounds = area.bounds();
+
+        // the new area wins over every earlier one it covers, which then no longer matter
+        let kept = self
+            .areas
+            .iter()
+            .filter(|v| !bounds.covers(v.bounds()))
+            .count();
+
+        if kept >= MAX_TILE_AREAS {
+            return false;
+        }
+
+        self.areas.retain(|v| !bounds.covers(v.bounds()));
+        self.areas.push(area);
+
+        // neither do the subtractions with nothing added before them
+        let first_add = self
+            .areas
+            .iter()
+            .position(|v| matches!(v, TileArea::Add(_)))
+            .unwrap_or(self.areas.len());
+        self.areas.drain(..first_add);
+
+        true
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn contains(&self, coord: TileCoord) -> bool {
+        self.areas
+            .iter()
+            .rev()
+            .find_map(|area| match area {
+                TileArea::Add(bounds) => bounds.contains(coord).then_some(true),
+                TileArea::Subtract(bounds) => bounds.contains(coord).then_some(false),
+            })
+            .unwrap_or(false)
+    }
+
+    /// Gets every tile in the region, each once, in no particular order.
+    pub fn coords(&self) -> Vec<TileCoord> {
+        let coords = self
+            .areas
+            .iter()
+            .filter_map(|area| match area {
+                TileArea::Add(bounds) => Some(*bounds),
+                TileArea::Subtract(_) => None,
+            })
+            .flatten()
+            .collect::<hashbrown::HashSet<_>>();
+
+        coords
+            .into_iter()
+            .filter(|coord| self.contains(*coord))
+            .collect()
+    }
+
+    /// Checks if the region has no tiles, stopping at the first one found.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        !self
+            .areas
+            .iter()
+            .filter_map(|area| match area {
+                TileArea::Add(bounds) => Some(*bounds),
+                TileArea::Subtract(_) => None,
+            })
+            .flatten()
+            .any(|coord| self.contains(coord))
+    }
+}
+
+impl From<TileBounds> for TileBoundsSet {
+    fn from(value: TileBounds) -> Self {
+        let mut set = Self::new();
+        set.add(value);
+
+        set
+    }
+}
+
+impl IntoIterator for TileBoundsSet {
+    type Item = TileCoord;
+    type IntoIter = std::vec::IntoIter<TileCoord>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.coords().into_iter()
+    }
+}
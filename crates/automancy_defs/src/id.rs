@@ -87,11 +87,19 @@ impl IdRaw {
     }
 
     pub fn try_parse(s: &str) -> Option<Self> {
-        s.split_once(':').map(|(a, b)| Self::new(a, b))
+        s.split_once(':')
+            .filter(|(a, b)| !a.is_empty() && !b.is_empty())
+            .map(|(a, b)| Self::new(a, b))
     }
 
     pub fn parse(s: &str, fallback_namespace: Option<impl AsRef<str>>) -> Option<Self> {
-        Self::try_parse(s).or_else(|| fallback_namespace.map(|v| Self::new(v.as_ref(), s)))
+        if s.contains(':') {
+            return Self::try_parse(s);
+        }
+
+        fallback_namespace
+            .filter(|v| !v.as_ref().is_empty() && !s.is_empty())
+            .map(|v| Self::new(v.as_ref(), s))
     }
 }
 
@@ -3,6 +3,7 @@ pub use glam;
 pub use gltf;
 pub use hex;
 pub use hexx;
+#[cfg(feature = "audio")]
 pub use kira;
 pub use log;
 pub use slice_group_by;
@@ -13,8 +14,11 @@ pub mod colors;
 pub mod coord;
 pub mod id;
 pub mod math;
+pub mod path;
 pub mod rendering;
+pub mod spatial;
 pub mod stack;
+#[cfg(feature = "render")]
 pub mod window;
 use id::{Id, Interner, SharedStr};
 use stack::{ItemAmount, ItemStack};
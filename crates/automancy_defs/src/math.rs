@@ -84,6 +84,18 @@ pub fn lerp_coords_to_pixel(a: TileCoord, b: TileCoord, t: Float) -> Vec2 {
     vec2(p.x, p.y)
 }
 
+/// Interpolates between two transformation matrices, by their scale, rotation and translation.
+pub fn lerp_matrix(a: Matrix4, b: Matrix4, t: Float) -> Matrix4 {
+    let (a_scale, a_rotation, a_translation) = a.to_scale_rotation_translation();
+    let (b_scale, b_rotation, b_translation) = b.to_scale_rotation_translation();
+
+    Matrix4::from_scale_rotation_translation(
+        a_scale.lerp(b_scale, t),
+        a_rotation.slerp(b_rotation, t),
+        a_translation.lerp(b_translation, t),
+    )
+}
+
 /// Converts screen space coordinates into normalized coordinates.
 #[inline]
 pub fn screen_to_normalized((width, height): (Float, Float), c: Vec2) -> Vec2 {
@@ -0,0 +1,99 @@
+use crate::coord::TileCoord;
+use crate::math::Float;
+use hashbrown::{HashMap, HashSet};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// The most coordinates a search looks at before giving up, so that searching for a goal that can't be reached stays
+/// bounded when the open space around it can be walked on.
+pub const MAX_PATH_SEARCH: usize = 4096;
+
+/// A path found between two coordinates, and the coordinates that were looked at to find it.
+#[derive(Debug, Clone, Default)]
+pub struct PathSearch {
+    /// the coordinates from the start to the goal, both included. None if the goal can't be reached
+    pub path: Option<Vec<TileCoord>>,
+    /// every coordinate whose cost was looked at. Changing the cost of any other coordinate can't change the path
+    pub visited: HashSet<TileCoord>,
+}
+
+/// A coordinate waiting to be searched from, ordered so that the cheapest estimate is popped first.
+struct Open(Float, TileCoord);
+
+impl PartialEq for Open {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Open {}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0)
+    }
+}
+
+/// Finds the cheapest path between two coordinates with A*.
+///
+/// `cost` gets the cost of stepping onto a coordinate, or None if it can't be stepped onto. Costs below 1 count as 1,
+/// so that the distance is never more than the cost left, and the path found is always the cheapest.
+pub fn find_path(
+    from: TileCoord,
+    to: TileCoord,
+    mut cost: impl FnMut(TileCoord) -> Option<Float>,
+) -> PathSearch {
+    let mut visited = HashSet::from([from]);
+    let mut came_from = HashMap::<TileCoord, TileCoord>::new();
+    let mut costs = HashMap::<TileCoord, Float>::from([(from, 0.0)]);
+    let mut open = BinaryHeap::from([Open(0.0, from)]);
+
+    while let Some(Open(_, coord)) = open.pop() {
+        if coord == to {
+            let mut path = vec![to];
+            while let Some(&prev) = came_from.get(path.last().unwrap()) {
+                path.push(prev);
+            }
+            path.reverse();
+
+            return PathSearch {
+                path: Some(path),
+                visited,
+            };
+        }
+
+        if visited.len() >= MAX_PATH_SEARCH {
+            break;
+        }
+
+        let current = costs[&coord];
+
+        for next in coord.neighbors() {
+            visited.insert(next);
+
+            let Some(step) = cost(next) else {
+                continue;
+            };
+            let total = current + step.max(1.0);
+
+            if costs.get(&next).is_some_and(|v| *v <= total) {
+                continue;
+            }
+
+            costs.insert(next, total);
+            came_from.insert(next, coord);
+            open.push(Open(total + next.unsigned_distance_to(*to) as Float, next));
+        }
+    }
+
+    PathSearch {
+        path: None,
+        visited,
+    }
+}
@@ -1,4 +1,4 @@
-use crate::math::{direction_to_angle, Float, Matrix3, Matrix4, Quaternion, Vec2, Vec3};
+use crate::math::{direction_to_angle, Float, Matrix3, Matrix4, Quaternion, Vec2, Vec3, Vec4};
 use bytemuck::{ByteEq, ByteHash, Pod, Zeroable};
 use glam::{vec3, vec4};
 use gltf::{
@@ -6,7 +6,8 @@ use gltf::{
     Document,
 };
 use gltf::{buffer::Data, scene::Transform};
-use std::{f32::consts::PI, mem::size_of};
+use std::f32::consts::PI;
+#[cfg(feature = "render")]
 use wgpu::{vertex_attr_array, BufferAddress, VertexAttribute, VertexBufferLayout, VertexStepMode};
 
 pub const LINE_DEPTH: Float = 0.075;
@@ -35,6 +36,7 @@ pub struct Vertex {
     pub color: VertexColor,
 }
 
+#[cfg(feature = "render")]
 impl Vertex {
     pub fn desc() -> VertexBufferLayout<'static> {
         static ATTRIBUTES: &[VertexAttribute] = &vertex_attr_array![
@@ -243,6 +245,7 @@ pub struct GpuInstance {
     pub world_matrix_index: u32,
 }
 
+#[cfg(feature = "render")]
 impl GpuInstance {
     pub fn desc() -> VertexBufferLayout<'static> {
         static ATTRIBUTES: &[VertexAttribute] = &vertex_attr_array![
@@ -285,6 +288,36 @@ impl Default for GameUBO {
     }
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Zeroable, Pod)]
+pub struct BackgroundUBO {
+    pub top_color: VertexColor,
+    pub bottom_color: VertexColor,
+    /// how far through the day the map is, from 0 to 1. always 0 on maps without a day cycle
+    pub time_of_day: Float,
+    /// the seconds since the game started
+    pub time: Float,
+    pub _p0: [f32; 2],
+}
+
+impl BackgroundUBO {
+    pub fn new(top_color: Vec4, bottom_color: Vec4, time_of_day: Float, time: Float) -> Self {
+        Self {
+            top_color: top_color.to_array(),
+            bottom_color: bottom_color.to_array(),
+            time_of_day,
+            time,
+            _p0: [0.0; 2],
+        }
+    }
+}
+
+impl Default for BackgroundUBO {
+    fn default() -> Self {
+        Self::new(Vec4::ZERO, Vec4::ZERO, 0.0, 0.0)
+    }
+}
+
 pub const FLAG_SCREEN_EFFECT: u32 = 1;
 
 #[repr(C)]
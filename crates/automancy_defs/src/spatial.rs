@@ -0,0 +1,221 @@
+use crate::coord::{TileBounds, TileCoord, TileUnit};
+use hashbrown::{hash_map::IntoValues, HashMap};
+use std::iter::Flatten;
+
+/// The side length, in tiles, of the chunks a SpatialIndex buckets its entries into.
+pub const CHUNK_SIZE: TileUnit = 16;
+
+pub type ChunkKey = (TileUnit, TileUnit);
+
+/// Gets the coordinate of the corner of a chunk, from which its tiles span CHUNK_SIZE tiles along both axes.
+pub fn chunk_origin((q, r): ChunkKey) -> TileCoord {
+    TileCoord::new(q * CHUNK_SIZE, r * CHUNK_SIZE)
+}
+
+pub fn chunk_of(coord: TileCoord) -> ChunkKey {
+    (
+        coord.x.div_euclid(CHUNK_SIZE),
+        coord.y.div_euclid(CHUNK_SIZE),
+    )
+}
+
+/// A map of tiles, bucketed into chunks so that range queries only visit the chunks they overlap.
+#[derive(Debug, Clone)]
+pub struct SpatialIndex<T> {
+    chunks: HashMap<ChunkKey, HashMap<TileCoord, T>>,
+    len: usize,
+}
+
+impl<T> Default for SpatialIndex<T> {
+    fn default() -> Self {
+        Self {
+            chunks: Default::default(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> SpatialIndex<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.len = 0;
+    }
+
+    pub fn get(&self, coord: &TileCoord) -> Option<&T> {
+        self.chunks.get(&chunk_of(*coord))?.get(coord)
+    }
+
+    pub fn get_mut(&mut self, coord: &TileCoord) -> Option<&mut T> {
+        self.chunks.get_mut(&chunk_of(*coord))?.get_mut(coord)
+    }
+
+    pub fn contains_key(&self, coord: &TileCoord) -> bool {
+        self.get(coord).is_some()
+    }
+
+    /// Inserts a value at the coordinate, returning the old value if there was one.
+    pub fn insert(&mut self, coord: TileCoord, value: T) -> Option<T> {
+        let old = self
+            .chunks
+            .entry(chunk_of(coord))
+            .or_default()
+            .insert(coord, value);
+
+        if old.is_none() {
+            self.len += 1;
+        }
+
+        old
+    }
+
+    /// Removes the value at the coordinate, returning it if there was one.
+    pub fn remove(&mut self, coord: &TileCoord) -> Option<T> {
+        let key = chunk_of(*coord);
+        let chunk = self.chunks.get_mut(&key)?;
+        let old = chunk.remove(coord)?;
+
+        if chunk.is_empty() {
+            self.chunks.remove(&key);
+        }
+        self.len -= 1;
+
+        Some(old)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&TileCoord, &T)> {
+        self.chunks.values().flat_map(|chunk| chunk.iter())
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &TileCoord> {
+        self.iter().map(|(coord, _)| coord)
+    }
+
+    /// Gets the keys of the chunks that have at least one entry.
+    pub fn chunk_keys(&self) -> impl Iterator<Item = ChunkKey> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    /// Gets every entry within the given hex distance of the center.
+    pub fn in_range(
+        &self,
+        center: TileCoord,
+        radius: u32,
+    ) -> impl Iterator<Item = (TileCoord, &T)> + '_ {
+        let max_distance = radius;
+        let radius = radius.min(TileUnit::MAX as u32) as TileUnit;
+
+        // the axial bounding box of the hexagon
+        let (min_q, min_r) = chunk_of(TileCoord::new(
+            center.x.saturating_sub(radius),
+            center.y.saturating_sub(radius),
+        ));
+        let (max_q, max_r) = chunk_of(TileCoord::new(
+            center.x.saturating_add(radius),
+            center.y.saturating_add(radius),
+        ));
+
+        let box_size = (max_q - min_q + 1) as usize * (max_r - min_r + 1) as usize;
+
+        // large ranges visit fewer chunks by checking every loaded one
+        let chunks: Vec<&HashMap<TileCoord, T>> = if box_size > self.chunks.len() {
+            self.chunks
+                .iter()
+                .filter(|((q, r), _)| (min_q..=max_q).contains(q) && (min_r..=max_r).contains(r))
+                .map(|(_, chunk)| chunk)
+                .collect()
+        } else {
+            (min_q..=max_q)
+                .flat_map(|q| (min_r..=max_r).map(move |r| (q, r)))
+                .flat_map(|key| self.chunks.get(&key))
+                .collect()
+        };
+
+        chunks.into_iter().flat_map(move |chunk| {
+            chunk
+                .iter()
+                .filter(move |(coord, _)| center.unsigned_distance_to(***coord) <= max_distance)
+                .map(|(coord, v)| (*coord, v))
+        })
+    }
+
+    /// Gets every entry within the bounds.
+    pub fn in_bounds(&self, bounds: TileBounds) -> impl Iterator<Item = (TileCoord, &T)> + '_ {
+        let range = match bounds {
+            TileBounds::Empty => None,
+            TileBounds::Hex(_) => Some(self.in_range(bounds.center(), bounds.radius())),
+        };
+
+        range.into_iter().flatten()
+    }
+
+    /// Finds the closest entry to the center matching the predicate, up to the max radius. Only the chunks in range are
+    /// visited, so a large radius costs no more than the entries loaded. Ties go to the lowest coordinate, so that the
+    /// result doesn't depend on the order the entries are stored in.
+    pub fn nearest(
+        &self,
+        center: TileCoord,
+        max_radius: u32,
+        mut pred: impl FnMut(TileCoord, &T) -> bool,
+    ) -> Option<(TileCoord, &T)> {
+        self.in_range(center, max_radius)
+            .filter(|(coord, v)| pred(*coord, v))
+            .min_by_key(|(coord, _)| (center.unsigned_distance_to(**coord), coord.x, coord.y))
+    }
+
+    /// Walks the hex line from one coordinate to another, and gets the first entry on it, excluding the start.
+    pub fn first_along_line(&self, from: TileCoord, to: TileCoord) -> Option<(TileCoord, &T)> {
+        from.line_to(*to)
+            .skip(1)
+            .map(TileCoord::from)
+            .find_map(|coord| self.get(&coord).map(|v| (coord, v)))
+    }
+
+    /// Returns true if nothing is in between the two coordinates, otherwise false.
+    pub fn line_of_sight(&self, from: TileCoord, to: TileCoord) -> bool {
+        match self.first_along_line(from, to) {
+            Some((coord, _)) => coord == to,
+            None => true,
+        }
+    }
+}
+
+impl<T> FromIterator<(TileCoord, T)> for SpatialIndex<T> {
+    fn from_iter<I: IntoIterator<Item = (TileCoord, T)>>(iter: I) -> Self {
+        let mut index = Self::new();
+
+        for (coord, v) in iter {
+            index.insert(coord, v);
+        }
+
+        index
+    }
+}
+
+impl<T> Extend<(TileCoord, T)> for SpatialIndex<T> {
+    fn extend<I: IntoIterator<Item = (TileCoord, T)>>(&mut self, iter: I) {
+        for (coord, v) in iter {
+            self.insert(coord, v);
+        }
+    }
+}
+
+impl<T> IntoIterator for SpatialIndex<T> {
+    type Item = (TileCoord, T);
+    type IntoIter = Flatten<IntoValues<ChunkKey, HashMap<TileCoord, T>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chunks.into_values().flatten()
+    }
+}
@@ -0,0 +1,107 @@
+use automancy_defs::coord::{TileArea, TileBounds, TileBoundsSet, TileCoord, MAX_TILE_AREAS};
+use hashbrown::HashSet;
+
+fn coords(set: &TileBoundsSet) -> HashSet<TileCoord> {
+    set.coords().into_iter().collect()
+}
+
+#[test]
+fn test_tile_bounds_set_contains() {
+    let mut set = TileBoundsSet::from(TileBounds::new(TileCoord::ZERO, 2));
+    set.subtract(TileBounds::new(TileCoord::new(2, 0), 1));
+
+    assert!(set.contains(TileCoord::ZERO));
+    assert!(set.contains(TileCoord::new(-2, 0)));
+    assert!(!set.contains(TileCoord::new(2, 0)));
+    assert!(!set.contains(TileCoord::new(1, 0)));
+    assert!(!set.contains(TileCoord::new(3, 0)));
+
+    // added back, it wins over the earlier cut
+    set.add(TileBounds::new(TileCoord::new(2, 0), 0));
+
+    assert!(set.contains(TileCoord::new(2, 0)));
+    assert!(!set.contains(TileCoord::new(1, 0)));
+}
+
+#[test]
+fn test_tile_bounds_set_coords() {
+    let whole = TileBounds::new(TileCoord::ZERO, 2);
+    let cut = TileBounds::new(TileCoord::new(2, 0), 1);
+
+    let mut set = TileBoundsSet::from(whole);
+    set.subtract(cut);
+
+    let expected = whole
+        .into_iter()
+        .filter(|coord| !cut.contains(*coord))
+        .collect::<HashSet<_>>();
+    assert_eq!(coords(&set), expected);
+    assert_eq!(set.coords().len(), expected.len());
+
+    // re-adding past the region counts each tile once
+    set.add(cut);
+
+    let expected = whole.into_iter().chain(cut).collect::<HashSet<_>>();
+    assert_eq!(coords(&set), expected);
+    assert_eq!(set.coords().len(), expected.len());
+}
+
+#[test]
+fn test_tile_bounds_set_is_empty() {
+    let mut set = TileBoundsSet::new();
+    assert!(set.is_empty());
+
+    // nothing to cut
+    set.subtract(TileBounds::new(TileCoord::ZERO, 1));
+    assert!(set.is_empty());
+
+    set.add(TileBounds::new(TileCoord::ZERO, 1));
+    assert!(!set.is_empty());
+
+    set.subtract(TileBounds::new(TileCoord::new(1, 0), 3));
+    assert!(set.is_empty());
+
+    set.add(TileBounds::new(TileCoord::new(5, 0), 0));
+    assert!(!set.is_empty());
+}
+
+#[test]
+fn test_tile_bounds_set_drops_covered_areas() {
+    let mut set = TileBoundsSet::new();
+
+    // dragging over the same spot again and again doesn't grow the region
+    for _ in 0..MAX_TILE_AREAS * 2 {
+        assert!(set.add(TileBounds::new(TileCoord::ZERO, 1)));
+        assert!(set.subtract(TileBounds::new(TileCoord::ZERO, 0)));
+    }
+    assert_eq!(
+        set.areas(),
+        &[
+            TileArea::Add(TileBounds::new(TileCoord::ZERO, 1)),
+            TileArea::Subtract(TileBounds::new(TileCoord::ZERO, 0)),
+        ]
+    );
+
+    // a cut covering everything leaves nothing behind
+    assert!(set.subtract(TileBounds::new(TileCoord::ZERO, 1)));
+    assert!(set.areas().is_empty());
+}
+
+#[test]
+fn test_tile_bounds_set_max_areas() {
+    let mut set = TileBoundsSet::new();
+
+    for i in 0..MAX_TILE_AREAS {
+        assert!(set.add(TileBounds::new(TileCoord::new(i as i32 * 3, 0), 0)));
+    }
+
+    let full = set.clone();
+    assert!(!set.add(TileBounds::new(TileCoord::new(-3, 0), 0)));
+    assert!(!set.subtract(TileBounds::new(TileCoord::new(1, 0), 0)));
+    assert_eq!(set, full);
+
+    // still fine when it covers earlier areas, as they're dropped
+    assert!(set.add(TileBounds::new(TileCoord::ZERO, 3)));
+    assert!(set.areas().len() <= MAX_TILE_AREAS);
+    assert!(set.contains(TileCoord::new(3, 0)));
+}
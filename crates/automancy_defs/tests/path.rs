@@ -0,0 +1,87 @@
+use automancy_defs::coord::TileCoord;
+use automancy_defs::path::{find_path, MAX_PATH_SEARCH};
+use hashbrown::HashSet;
+
+fn assert_connected(path: &[TileCoord], from: TileCoord, to: TileCoord) {
+    assert_eq!(path.first(), Some(&from));
+    assert_eq!(path.last(), Some(&to));
+
+    for step in path.windows(2) {
+        assert!(step[0].neighbors().contains(&step[1]), "{path:?}");
+    }
+}
+
+#[test]
+fn test_find_path_shortest() {
+    let to = TileCoord::new(3, 0);
+    let path = find_path(TileCoord::ZERO, to, |_| Some(1.0)).path.unwrap();
+
+    assert_connected(&path, TileCoord::ZERO, to);
+    assert_eq!(path.len(), 4);
+
+    assert_eq!(
+        find_path(TileCoord::ZERO, TileCoord::ZERO, |_| Some(1.0)).path,
+        Some(vec![TileCoord::ZERO])
+    );
+}
+
+#[test]
+fn test_find_path_around() {
+    let to = TileCoord::new(2, 0);
+    let blocked = TileCoord::new(1, 0);
+
+    // around what can't be stepped on
+    let path = find_path(TileCoord::ZERO, to, |coord| {
+        (coord != blocked).then_some(1.0)
+    })
+    .path
+    .unwrap();
+    assert_connected(&path, TileCoord::ZERO, to);
+    assert!(!path.contains(&blocked));
+    assert_eq!(path.len(), 4);
+
+    // and around what costs more than going around it
+    let path = find_path(TileCoord::ZERO, to, |coord| {
+        Some(if coord == blocked { 10.0 } else { 1.0 })
+    })
+    .path
+    .unwrap();
+    assert_connected(&path, TileCoord::ZERO, to);
+    assert!(!path.contains(&blocked));
+    assert_eq!(path.len(), 4);
+}
+
+#[test]
+fn test_find_path_unreachable() {
+    let to = TileCoord::new(3, 0);
+    let walls = to.neighbors().into_iter().collect::<HashSet<_>>();
+
+    // a small island, with the goal walled off on it
+    let search = find_path(TileCoord::ZERO, to, |coord| {
+        (coord.unsigned_distance_to(*TileCoord::ZERO) <= 5 && !walls.contains(&coord))
+            .then_some(1.0)
+    });
+
+    assert_eq!(search.path, None);
+    assert!(search.visited.len() < MAX_PATH_SEARCH);
+    // every wall was looked at, so removing any of them makes the search stale
+    assert!(walls.iter().all(|wall| search.visited.contains(wall)));
+}
+
+#[test]
+fn test_find_path_search_cap() {
+    // walking in the open towards a goal that is walled off stops at the cap
+    let to = TileCoord::new(3, 0);
+    let search = find_path(TileCoord::ZERO, to, |coord| (coord != to).then_some(1.0));
+
+    assert_eq!(search.path, None);
+    assert!(search.visited.len() >= MAX_PATH_SEARCH);
+    assert!(search.visited.len() <= MAX_PATH_SEARCH + 6);
+
+    // so does a goal further away than the cap lets the search go
+    let far = TileCoord::new(MAX_PATH_SEARCH as i32 + 1, 0);
+    let search = find_path(TileCoord::ZERO, far, |_| Some(1.0));
+
+    assert_eq!(search.path, None);
+    assert!(search.visited.len() <= MAX_PATH_SEARCH + 6);
+}
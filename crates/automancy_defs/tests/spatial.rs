@@ -0,0 +1,103 @@
+use automancy_defs::coord::{TileBounds, TileCoord};
+use automancy_defs::spatial::{SpatialIndex, CHUNK_SIZE};
+
+fn index(coords: &[(i32, i32)]) -> SpatialIndex<u32> {
+    coords
+        .iter()
+        .enumerate()
+        .map(|(i, (q, r))| (TileCoord::new(*q, *r), i as u32))
+        .collect()
+}
+
+fn sorted<'a>(iter: impl Iterator<Item = (TileCoord, &'a u32)>) -> Vec<u32> {
+    let mut values = iter.map(|(_, v)| *v).collect::<Vec<_>>();
+    values.sort();
+    values
+}
+
+#[test]
+fn test_spatial_in_range() {
+    let index = index(&[
+        (0, 0),
+        (1, 0),
+        (2, -1),
+        (3, 0),
+        (-CHUNK_SIZE, 0),
+        (CHUNK_SIZE * 4, CHUNK_SIZE * 4),
+    ]);
+
+    assert_eq!(sorted(index.in_range(TileCoord::ZERO, 0)), vec![0]);
+    assert_eq!(sorted(index.in_range(TileCoord::ZERO, 2)), vec![0, 1, 2]);
+    assert_eq!(sorted(index.in_range(TileCoord::ZERO, 3)), vec![0, 1, 2, 3]);
+    // across chunk borders
+    assert_eq!(
+        sorted(index.in_range(TileCoord::ZERO, CHUNK_SIZE as u32)),
+        vec![0, 1, 2, 3, 4]
+    );
+    // a range much larger than the loaded chunks
+    assert_eq!(
+        sorted(index.in_range(TileCoord::ZERO, u32::MAX)),
+        vec![0, 1, 2, 3, 4, 5]
+    );
+    assert_eq!(
+        sorted(index.in_bounds(TileBounds::new(TileCoord::new(3, 0), 1))),
+        vec![3]
+    );
+    assert!(sorted(index.in_bounds(TileBounds::Empty)).is_empty());
+}
+
+#[test]
+fn test_spatial_nearest() {
+    let index = index(&[(0, 0), (2, 0), (0, 2), (-2, 0), (5, 0)]);
+
+    assert_eq!(
+        index.nearest(TileCoord::ZERO, 10, |_, _| true),
+        Some((TileCoord::ZERO, &0))
+    );
+    // (2, 0), (0, 2) and (-2, 0) are as far, and the lowest one is picked
+    assert_eq!(
+        index.nearest(TileCoord::ZERO, 10, |_, v| *v != 0),
+        Some((TileCoord::new(-2, 0), &3))
+    );
+    assert_eq!(index.nearest(TileCoord::ZERO, 4, |_, v| *v == 4), None);
+    assert_eq!(
+        index.nearest(TileCoord::ZERO, 5, |_, v| *v == 4),
+        Some((TileCoord::new(5, 0), &4))
+    );
+    // a huge radius with nothing matching still ends right away
+    assert_eq!(index.nearest(TileCoord::ZERO, u32::MAX, |_, _| false), None);
+}
+
+#[test]
+fn test_spatial_first_along_line() {
+    let index = index(&[(0, 0), (2, 0), (4, 0)]);
+
+    // the start doesn't count
+    assert_eq!(
+        index.first_along_line(TileCoord::ZERO, TileCoord::new(4, 0)),
+        Some((TileCoord::new(2, 0), &1))
+    );
+    assert_eq!(
+        index.first_along_line(TileCoord::new(4, 0), TileCoord::ZERO),
+        Some((TileCoord::new(2, 0), &1))
+    );
+    assert_eq!(
+        index.first_along_line(TileCoord::ZERO, TileCoord::new(0, 4)),
+        None
+    );
+    assert_eq!(
+        index.first_along_line(TileCoord::ZERO, TileCoord::ZERO),
+        None
+    );
+}
+
+#[test]
+fn test_spatial_line_of_sight() {
+    let index = index(&[(0, 0), (2, 0), (4, 0)]);
+
+    assert!(index.line_of_sight(TileCoord::ZERO, TileCoord::new(2, 0)));
+    assert!(!index.line_of_sight(TileCoord::ZERO, TileCoord::new(4, 0)));
+    assert!(index.line_of_sight(TileCoord::ZERO, TileCoord::new(1, 0)));
+    assert!(index.line_of_sight(TileCoord::ZERO, TileCoord::new(0, 4)));
+    assert!(index.line_of_sight(TileCoord::ZERO, TileCoord::ZERO));
+}
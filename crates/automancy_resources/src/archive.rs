@@ -0,0 +1,67 @@
+use anyhow::Context;
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// Where the resource packs distributed as archives are unpacked to, each into a folder named after its namespace.
+pub static UNPACKED_PATH: &str = "unpacked";
+
+/// The file extensions of the archives a resource pack can be distributed as.
+pub static ARCHIVE_EXT: [&str; 2] = ["zip", "tar.zst"];
+
+/// Checks if the name can be a namespace, i.e. it is only made of `a-z`, `0-9` and `_`. As the namespace is used as
+/// the name of the folder the archive is unpacked into, this also keeps it from being e.g. `..`.
+pub fn is_valid_namespace(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Gets the namespace of a resource pack archive, which is its file name without the extension.
+/// Returns None if the file isn't an archive, or if its name isn't a valid namespace.
+pub fn archive_namespace(path: &Path) -> Option<&str> {
+    let name = path.file_name()?.to_str()?;
+
+    ARCHIVE_EXT.iter().find_map(|ext| {
+        name.strip_suffix(*ext)?
+            .strip_suffix('.')
+            .filter(|v| is_valid_namespace(v))
+    })
+}
+
+/// Unpacks a resource pack archive into its folder under [UNPACKED_PATH], replacing what was unpacked there before.
+/// Returns the folder the pack is in, which is the only folder in the archive if it was packed along with it.
+pub fn unpack_archive(path: &Path, namespace: &str) -> anyhow::Result<PathBuf> {
+    if !is_valid_namespace(namespace) {
+        anyhow::bail!("{namespace:?} is not a valid namespace, it can only contain a-z, 0-9 and _");
+    }
+
+    let dir = Path::new(UNPACKED_PATH).join(namespace);
+
+    if dir.exists() {
+        fs::remove_dir_all(&dir).context("Could not clear the unpacked folder")?;
+    }
+    fs::create_dir_all(&dir).context("Could not create the unpacked folder")?;
+
+    let file = File::open(path).context("Could not open the archive")?;
+
+    // both check that nothing is unpacked outside of the folder
+    if path.extension().is_some_and(|v| v == "zip") {
+        zip::ZipArchive::new(file)
+            .context("Could not read the zip archive")?
+            .extract(&dir)
+            .context("Could not unpack the zip archive")?;
+    } else {
+        tar::Archive::new(zstd::Decoder::new(file).context("Could not decompress the archive")?)
+            .unpack(&dir)
+            .context("Could not unpack the tar archive")?;
+    }
+
+    let mut entries = fs::read_dir(&dir)?.flatten().map(|v| v.path());
+
+    match (entries.next(), entries.next()) {
+        (Some(only), None) if only.is_dir() => Ok(only),
+        _ => Ok(dir),
+    }
+}
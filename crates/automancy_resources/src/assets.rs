@@ -0,0 +1,418 @@
+use crate::data::{Data, DataMap};
+use crate::types::IconMode;
+use crate::ResourceManager;
+use automancy_defs::id::{Id, ModelId, TileId};
+use automancy_defs::stack::ItemStack;
+use hashbrown::{HashMap, HashSet};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// The kinds of assets the packs define, which reference each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AssetKind {
+    Tile,
+    Item,
+    Model,
+    Script,
+    Tag,
+    Category,
+    Research,
+    Agent,
+    Function,
+    Cutscene,
+}
+
+impl AssetKind {
+    pub const ALL: [AssetKind; 10] = [
+        AssetKind::Tile,
+        AssetKind::Item,
+        AssetKind::Model,
+        AssetKind::Script,
+        AssetKind::Tag,
+        AssetKind::Category,
+        AssetKind::Research,
+        AssetKind::Agent,
+        AssetKind::Function,
+        AssetKind::Cutscene,
+    ];
+
+    /// The name of the folder of the kind in the packs.
+    pub fn section(self) -> &'static str {
+        match self {
+            AssetKind::Tile => "tiles",
+            AssetKind::Item => "items",
+            AssetKind::Model => "models",
+            AssetKind::Script => "scripts",
+            AssetKind::Tag => "tags",
+            AssetKind::Category => "categories",
+            AssetKind::Research => "researches",
+            AssetKind::Agent => "agents",
+            AssetKind::Function => "functions",
+            AssetKind::Cutscene => "cutscenes",
+        }
+    }
+
+    /// Whether an asset of the kind is unused if nothing references it. Tiles, researches and categories are shown to
+    /// the player on their own, so they are always used.
+    fn needs_reference(self) -> bool {
+        !matches!(
+            self,
+            AssetKind::Tile | AssetKind::Research | AssetKind::Category
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Asset {
+    pub kind: AssetKind,
+    pub id: Id,
+}
+
+impl Asset {
+    pub fn new(kind: AssetKind, id: Id) -> Self {
+        Self { kind, id }
+    }
+}
+
+/// What each asset references, e.g. tile -> model, script -> items, research -> icon. The references to assets that
+/// aren't there are kept, to be reported as broken.
+#[derive(Debug, Clone, Default)]
+pub struct AssetGraph {
+    pub dependencies: HashMap<Asset, HashSet<Asset>>,
+    /// the assets used by the engine itself, or by the library functions, which count as used
+    pub roots: HashSet<Asset>,
+}
+
+/// The assets nothing references, and the references to assets that aren't there, as `section/id`, sorted.
+#[derive(Debug, Clone, Default)]
+pub struct AssetReport {
+    pub unused: Vec<String>,
+    /// the referencing asset, and the missing one it references
+    pub broken: Vec<(String, String)>,
+}
+
+impl AssetReport {
+    /// Formats the report as Markdown, to be cleaned up after.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# Asset Report\n");
+
+        let _ = writeln!(out, "## Broken References ({})\n", self.broken.len());
+        for (from, to) in &self.broken {
+            let _ = writeln!(out, "- `{from}` references `{to}`, which isn't there");
+        }
+
+        let _ = writeln!(out, "\n## Unused Assets ({})\n", self.unused.len());
+        for asset in &self.unused {
+            let _ = writeln!(out, "- `{asset}`");
+        }
+
+        out
+    }
+
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, self.to_markdown())?;
+
+        Ok(())
+    }
+}
+
+/// Gets the IDs in the data, any of which may be an asset.
+fn data_ids(data: &DataMap) -> Vec<Id> {
+    let mut ids = vec![];
+
+    for key in data.keys() {
+        match data.get(*key) {
+            Some(Data::Id(id)) => ids.push(*id),
+            Some(Data::VecId(v)) => ids.extend(v),
+            Some(Data::SetId(v)) => ids.extend(v),
+            Some(Data::TileMap(v)) => ids.extend(v.values()),
+            Some(Data::MapSetId(v)) => {
+                for (k, v) in v {
+                    ids.push(*k);
+                    ids.extend(v);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    ids
+}
+
+impl ResourceManager {
+    /// Checks if the asset is loaded.
+    pub fn asset_exists(&self, asset: Asset) -> bool {
+        let id = asset.id;
+
+        match asset.kind {
+            AssetKind::Tile => self.registry.tiles.contains_key(&TileId(id)),
+            AssetKind::Item => self.registry.items.contains_key(&id),
+            AssetKind::Model => self.all_meshes_anims.contains_key(&ModelId(id)),
+            AssetKind::Script => self.registry.scripts.contains_key(&id),
+            AssetKind::Tag => self.registry.tags.contains_key(&id),
+            AssetKind::Category => self.registry.categories.contains_key(&id),
+            AssetKind::Research => self.get_research(id).is_some(),
+            AssetKind::Agent => self.registry.agents.contains_key(&id),
+            AssetKind::Function => self.functions.contains_key(&id),
+            AssetKind::Cutscene => self.registry.cutscenes.contains_key(&id),
+        }
+    }
+
+    /// Gets every loaded asset with the ID, as an ID can name e.g. both an item and its model.
+    fn assets_of(&self, id: Id) -> impl Iterator<Item = Asset> + '_ {
+        AssetKind::ALL
+            .into_iter()
+            .map(move |kind| Asset::new(kind, id))
+            .filter(move |v| self.asset_exists(*v))
+    }
+
+    /// Gets the asset an item ID references, which can be a tag of items too.
+    fn item_asset(&self, id: Id) -> Asset {
+        if self.registry.tags.contains_key(&id) {
+            Asset::new(AssetKind::Tag, id)
+        } else {
+            Asset::new(AssetKind::Item, id)
+        }
+    }
+
+    fn stack_assets<'a>(
+        &'a self,
+        stacks: impl IntoIterator<Item = &'a ItemStack> + 'a,
+    ) -> impl Iterator<Item = Asset> + 'a {
+        stacks
+            .into_iter()
+            .filter(move |v| v.id != self.registry.any)
+            .map(move |v| self.item_asset(v.id))
+    }
+
+    /// Builds the graph of what each loaded asset references. Called once every pack is loaded.
+    pub fn compile_asset_graph(&mut self) {
+        let mut graph = AssetGraph::default();
+
+        for tile in self.registry.tiles.values() {
+            let deps = graph
+                .dependencies
+                .entry(Asset::new(AssetKind::Tile, *tile.id))
+                .or_default();
+
+            deps.extend(tile.function.map(|v| Asset::new(AssetKind::Function, v)));
+            deps.extend(tile.category.map(|v| Asset::new(AssetKind::Category, v)));
+            deps.extend(tile.scenery.map(|v| Asset::new(AssetKind::Model, *v)));
+
+            if let Some(connected) = &tile.connected {
+                deps.extend(connected.connects_to.map(|v| Asset::new(AssetKind::Tag, v)));
+                deps.extend(
+                    connected
+                        .variants
+                        .values()
+                        .chain([&connected.default])
+                        .map(|v| Asset::new(AssetKind::Model, **v)),
+                );
+            }
+
+            deps.extend(
+                data_ids(&tile.data)
+                    .into_iter()
+                    .flat_map(|v| self.assets_of(v)),
+            );
+        }
+
+        for item in self.registry.items.values() {
+            graph
+                .dependencies
+                .entry(Asset::new(AssetKind::Item, item.id))
+                .or_default()
+                .insert(Asset::new(AssetKind::Model, *item.model));
+        }
+
+        for script in self.registry.scripts.values() {
+            let instructions = &script.instructions;
+
+            graph
+                .dependencies
+                .entry(Asset::new(AssetKind::Script, script.id))
+                .or_default()
+                .extend(
+                    self.stack_assets(
+                        instructions
+                            .inputs
+                            .iter()
+                            .flatten()
+                            .chain(&instructions.outputs),
+                    ),
+                );
+        }
+
+        for tag in self.registry.tags.values() {
+            let deps = graph
+                .dependencies
+                .entry(Asset::new(AssetKind::Tag, tag.id))
+                .or_default();
+
+            for entry in &tag.entries {
+                let mut assets = self.assets_of(*entry).peekable();
+
+                if assets.peek().is_some() {
+                    deps.extend(assets);
+                } else {
+                    deps.insert(Asset::new(AssetKind::Item, *entry));
+                }
+            }
+        }
+
+        for category in self.registry.categories.values() {
+            let deps = graph
+                .dependencies
+                .entry(Asset::new(AssetKind::Category, category.id))
+                .or_default();
+
+            deps.insert(match category.icon_mode {
+                IconMode::Tile => Asset::new(AssetKind::Tile, category.icon),
+                IconMode::Item => Asset::new(AssetKind::Model, category.icon),
+            });
+            deps.extend(category.item.map(|v| self.item_asset(v)));
+            deps.extend(category.parent.map(|v| Asset::new(AssetKind::Category, v)));
+        }
+
+        for research in self
+            .registry
+            .researches_id_map
+            .keys()
+            .flat_map(|v| self.get_research(*v))
+        {
+            let deps = graph
+                .dependencies
+                .entry(Asset::new(AssetKind::Research, research.id))
+                .or_default();
+
+            deps.insert(Asset::new(AssetKind::Model, *research.icon));
+            deps.extend(
+                research
+                    .unlocks
+                    .iter()
+                    .map(|v| Asset::new(AssetKind::Tile, **v)),
+            );
+            deps.extend(
+                research
+                    .depends_on
+                    .map(|v| Asset::new(AssetKind::Research, v)),
+            );
+            deps.extend(self.stack_assets(research.required_items.iter().flatten()));
+            deps.extend(
+                research
+                    .attached_puzzle
+                    .as_ref()
+                    .map(|(v, _)| Asset::new(AssetKind::Function, *v)),
+            );
+            deps.extend(
+                research
+                    .cutscene
+                    .map(|v| Asset::new(AssetKind::Cutscene, v)),
+            );
+        }
+
+        for agent in self.registry.agents.values() {
+            let deps = graph
+                .dependencies
+                .entry(Asset::new(AssetKind::Agent, agent.id))
+                .or_default();
+
+            deps.insert(Asset::new(AssetKind::Model, *agent.model));
+            deps.extend(agent.function.map(|v| Asset::new(AssetKind::Function, v)));
+            deps.extend(
+                data_ids(&agent.data)
+                    .into_iter()
+                    .flat_map(|v| self.assets_of(v)),
+            );
+        }
+
+        // the functions can use any kind of ID, so only what they declare that is loaded counts
+        for (id, (_, metadata)) in &self.functions {
+            graph
+                .dependencies
+                .entry(Asset::new(AssetKind::Function, *id))
+                .or_default()
+                .extend(metadata.id_deps.iter().flat_map(|v| self.assets_of(*v)));
+        }
+
+        for cutscene in self.registry.cutscenes.keys() {
+            graph
+                .dependencies
+                .entry(Asset::new(AssetKind::Cutscene, *cutscene))
+                .or_default();
+        }
+
+        let model_ids = &self.registry.model_ids;
+        graph.roots.extend(
+            [
+                model_ids.tile_none,
+                model_ids.tile_missing,
+                model_ids.item_missing,
+                model_ids.cube1x1,
+                model_ids.puzzle_space,
+            ]
+            .into_iter()
+            .map(|v| Asset::new(AssetKind::Model, v)),
+        );
+        graph.roots.insert(Asset::new(
+            AssetKind::Cutscene,
+            self.registry.data_ids.intro_cutscene,
+        ));
+        graph
+            .roots
+            .extend(self.library_id_deps.iter().flat_map(|v| self.assets_of(*v)));
+
+        for model in self.all_meshes_anims.keys() {
+            graph
+                .dependencies
+                .entry(Asset::new(AssetKind::Model, **model))
+                .or_default();
+        }
+
+        self.asset_graph = graph;
+    }
+
+    fn asset_str(&self, asset: Asset) -> String {
+        format!(
+            "{}/{}",
+            asset.kind.section(),
+            self.interner.resolve(asset.id).unwrap_or("<?>")
+        )
+    }
+
+    /// Lists the assets that nothing references, and the references to assets that aren't loaded, for cleaning up
+    /// packs. Functions may still use an unused asset through an ID they build at runtime, so it's worth a search
+    /// before removing one.
+    pub fn asset_report(&self) -> AssetReport {
+        let graph = &self.asset_graph;
+
+        let referenced = graph
+            .dependencies
+            .values()
+            .flatten()
+            .chain(&graph.roots)
+            .collect::<HashSet<_>>();
+
+        let mut unused = graph
+            .dependencies
+            .keys()
+            .filter(|v| v.kind.needs_reference() && !referenced.contains(v))
+            .map(|v| self.asset_str(*v))
+            .collect::<Vec<_>>();
+        unused.sort();
+
+        let mut broken = graph
+            .dependencies
+            .iter()
+            .flat_map(|(from, deps)| deps.iter().map(move |to| (from, to)))
+            .filter(|(_, to)| !self.asset_exists(**to))
+            .map(|(from, to)| (self.asset_str(*from), self.asset_str(*to)))
+            .collect::<Vec<_>>();
+        broken.sort();
+
+        AssetReport { unused, broken }
+    }
+}
@@ -1,6 +1,9 @@
 use crate::inventory::{Inventory, InventoryRaw};
+use crate::registry::DataIds;
+use automancy_defs::colors::Color;
+use automancy_defs::math::Float;
 use automancy_defs::{
-    coord::{TileBounds, TileCoord, TileUnit},
+    coord::{TileBounds, TileBoundsSet, TileCoord, TileUnit},
     resolve_map_id_of, resolve_map_v_id,
     stack::{ItemAmount, ItemStack},
     try_parse_ids,
@@ -25,7 +28,6 @@ use std::{
         BTreeMap,
     },
 };
-use yakui::Color;
 
 fn offset_to_tile(a: [TileUnit; 2]) -> TileCoord {
     TileCoord::from(Hex::from_offset_coordinates(a, OffsetHexMode::EvenRows))
@@ -38,6 +40,7 @@ pub enum Data {
     Coord(TileCoord),
     VecCoord(Vec<TileCoord>),
     TileBounds(TileBounds),
+    TileBoundsSet(TileBoundsSet),
     Id(Id),
     Color(Color),
     VecId(Vec<Id>),
@@ -83,6 +86,7 @@ impl Data {
             Data::Coord(v) => Dynamic::from(v),
             Data::VecCoord(v) => Dynamic::from_iter(v),
             Data::TileBounds(v) => Dynamic::from(v),
+            Data::TileBoundsSet(v) => Dynamic::from(v),
             Data::Id(v) => Dynamic::from(v),
             Data::Color(v) => Dynamic::from(v),
             Data::VecId(v) => Dynamic::from_iter(v),
@@ -115,6 +119,8 @@ impl Data {
             Data::SetId(v.cast())
         } else if id == TypeId::of::<TileBounds>() {
             Data::TileBounds(v.cast())
+        } else if id == TypeId::of::<TileBoundsSet>() {
+            Data::TileBoundsSet(v.cast())
         } else if id == TypeId::of::<HashMap<TileCoord, Id>>() {
             Data::TileMap(v.cast())
         } else if id == TypeId::of::<HashMap<Id, HashSet<Id>>>() {
@@ -135,6 +141,7 @@ impl Data {
             Data::Bool(v) => DataRaw::Bool(*v),
             Data::Color(v) => DataRaw::Color(hex::encode([v.r, v.g, v.b, v.a])),
             Data::TileBounds(v) => DataRaw::TileBounds(*v),
+            Data::TileBoundsSet(v) => DataRaw::TileBoundsSet(v.clone()),
             Data::TileMap(v) => {
                 DataRaw::TileMap(resolve_map_v_id(v.iter().map(|(a, b)| (*a, *b)), interner))
             }
@@ -198,6 +205,24 @@ impl DataMap {
         self.0.get_mut(&id)
     }
 
+    /// Gets the progress of the current processing, as the fraction done (0..1) and the number of ticks left.
+    pub fn progress(&self, data_ids: &DataIds) -> Option<(Float, ItemAmount)> {
+        let Some(Data::Amount(progress)) = self.get(data_ids.progress) else {
+            return None;
+        };
+        let Some(Data::Amount(total)) = self.get(data_ids.progress_total) else {
+            return None;
+        };
+
+        if *total <= 0 {
+            return None;
+        }
+
+        let progress = (*progress).clamp(0, *total);
+
+        Some((progress as Float / *total as Float, *total - progress))
+    }
+
     pub fn set(&mut self, id: Id, data: Data) {
         self.0.insert(id, data);
     }
@@ -258,6 +283,7 @@ pub enum DataRaw {
     Amount(ItemAmount),
     Bool(bool),
     TileBounds(TileBounds),
+    TileBoundsSet(TileBoundsSet),
     VecOffsetCoord(Vec<IVec2>),
     TileMap(Vec<(TileCoord, String)>),
     TileMapOffsetCoord(Vec<(IVec2, String)>),
@@ -265,6 +291,27 @@ pub enum DataRaw {
 }
 
 impl DataRaw {
+    /// Sorts the entries of sets, maps and inventories, which are resolved in no particular order.
+    fn sort(&mut self) {
+        match self {
+            DataRaw::SetId(v) => v.sort(),
+            DataRaw::Inventory(v) => v.sort(),
+            DataRaw::TileMap(v) => {
+                v.sort_by(|(a, a_id), (b, b_id)| (a.x, a.y, a_id).cmp(&(b.x, b.y, b_id)))
+            }
+            DataRaw::TileMapOffsetCoord(v) => {
+                v.sort_by(|(a, a_id), (b, b_id)| (a.x, a.y, a_id).cmp(&(b.x, b.y, b_id)))
+            }
+            DataRaw::MapSetId(v) => {
+                for (_, set) in v.iter_mut() {
+                    set.sort();
+                }
+                v.sort();
+            }
+            _ => {}
+        }
+    }
+
     pub fn to_data(&self, interner: &Interner) -> Option<Data> {
         Some(match self {
             DataRaw::Id(v) => Data::Id(Id::try_parse(v, interner)?),
@@ -303,9 +350,22 @@ impl DataRaw {
                 Data::VecCoord(v.iter().map(|v| offset_to_tile(v.to_array())).collect())
             }
             DataRaw::TileBounds(v) => Data::TileBounds(*v),
+            DataRaw::TileBoundsSet(v) => Data::TileBoundsSet(v.clone()),
         })
     }
 
+    /// Converts the lists of offset coordinates that older saves were written with to tile coordinates.
+    pub fn without_offset_coords(self) -> Self {
+        match self {
+            DataRaw::VecOffsetCoord(v) => DataRaw::VecCoord(
+                v.into_iter()
+                    .map(|v| offset_to_tile(v.to_array()))
+                    .collect(),
+            ),
+            rest => rest,
+        }
+    }
+
     pub fn intern_to_data(&self, interner: &mut Interner, namespace: Option<&str>) -> Option<Data> {
         Some(match self {
             DataRaw::Id(v) => Data::Id(Id::parse(v, interner, namespace)?),
@@ -350,10 +410,24 @@ impl From<BTreeMap<String, DataRaw>> for DataMapRaw {
 }
 
 impl DataMapRaw {
+    /// Parses a data map from RON bytes. Never panics on malformed input.
+    pub fn parse(bytes: &[u8]) -> ron::error::SpannedResult<Self> {
+        ron::de::from_bytes(bytes)
+    }
+
     pub fn into_inner(self) -> BTreeMap<String, DataRaw> {
         self.0
     }
 
+    /// Sorts the sets and maps in the data, so that equal data maps are written the same no matter the order their
+    /// entries were inserted in.
+    #[must_use]
+    pub fn sorted(mut self) -> Self {
+        self.0.values_mut().for_each(DataRaw::sort);
+
+        self
+    }
+
     pub fn to_data(&self, interner: &Interner) -> DataMap {
         DataMap(
             self.0
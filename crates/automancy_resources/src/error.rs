@@ -1,43 +1,189 @@
 use crate::{format::FormatContext, ResourceManager};
 use automancy_defs::id::Id;
-use std::cell::RefCell;
+use hashbrown::HashSet;
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 
-/// An ErrorManager contains a queue of errors to be displayed.
+/// The most errors kept in the history.
+pub const MAX_ERROR_HISTORY: usize = 64;
+/// How many script errors within [SCRIPT_ERROR_SPIKE_WINDOW] count as a spike.
+pub const SCRIPT_ERROR_SPIKE: usize = 16;
+pub const SCRIPT_ERROR_SPIKE_WINDOW: Duration = Duration::from_secs(10);
+/// The least time between two screenshots taken because of errors, so that a flood of them doesn't fill the disk.
+pub const ERROR_SCREENSHOT_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ErrorSeverity {
+    Info,
+    Warn,
+    /// fatal errors are always shown, even if muted
+    Fatal,
+}
+
+/// An error recorded by the game, to be displayed to the player.
+#[derive(Debug, Clone)]
+pub struct GameError {
+    pub id: Id,
+    pub severity: ErrorSeverity,
+    pub message: String,
+    pub time: SystemTime,
+}
+
+/// An ErrorManager contains a queue of errors to be displayed, and the history of all recent errors.
 #[derive(Default)]
 struct ErrorManager {
-    queue: Vec<(Id, String)>,
+    queue: Vec<GameError>,
+    history: Vec<GameError>,
+    /// the keys of the errors the player chose to not be shown again
+    muted: HashSet<String>,
+    /// when the recent script errors happened, oldest first
+    script_errs: VecDeque<Instant>,
+    /// when a screenshot was last asked for because of errors
+    last_screenshot: Option<Instant>,
+    screenshot_requested: bool,
 }
 
-thread_local! {
-    static ERROR_MAN: RefCell<ErrorManager> = RefCell::new(ErrorManager::default());
+impl ErrorManager {
+    /// Asks for a screenshot, unless one was asked for too recently.
+    fn request_screenshot(&mut self) {
+        if self
+            .last_screenshot
+            .is_some_and(|v| v.elapsed() < ERROR_SCREENSHOT_COOLDOWN)
+        {
+            return;
+        }
+
+        self.last_screenshot = Some(Instant::now());
+        self.screenshot_requested = true;
+    }
 }
 
+// errors can be recorded from any thread, e.g. while the game loads a map
+static ERROR_MAN: LazyLock<Mutex<ErrorManager>> = LazyLock::new(Default::default);
+
 /// Gets the unlocalized key of an error's ID.
 pub fn error_to_key(id: Id, resource_man: &ResourceManager) -> &str {
     resource_man.interner.resolve(id).unwrap_or("")
 }
 
-/// Adds a new error to the queue.
-pub fn push_err(id: Id, fmt: &FormatContext, resource_man: &ResourceManager) {
-    log::error!("Recording game error: {}", error_to_key(id, resource_man));
+/// Adds a new error to the history, and to the queue unless it has been muted.
+pub fn push_err(
+    id: Id,
+    severity: ErrorSeverity,
+    fmt: &FormatContext,
+    resource_man: &ResourceManager,
+) {
+    let key = error_to_key(id, resource_man);
+
+    match severity {
+        ErrorSeverity::Info => log::info!("Recording game error: {key}"),
+        ErrorSeverity::Warn => log::warn!("Recording game error: {key}"),
+        ErrorSeverity::Fatal => log::error!("Recording game error: {key}"),
+    }
 
-    let string = interpolator::format(&resource_man.translates.error[&id], fmt)
+    let message = interpolator::format(&resource_man.translates().error[&id], fmt)
         .expect("could not format error!");
 
-    ERROR_MAN.with_borrow_mut(|error_man| error_man.queue.push((id, string)))
+    let err = GameError {
+        id,
+        severity,
+        message,
+        time: SystemTime::now(),
+    };
+
+    let mut error_man = ERROR_MAN.lock().unwrap();
+
+    if error_man.history.len() >= MAX_ERROR_HISTORY {
+        error_man.history.remove(0);
+    }
+    error_man.history.push(err.clone());
+
+    if severity == ErrorSeverity::Fatal {
+        error_man.request_screenshot();
+    }
+
+    if severity == ErrorSeverity::Fatal || !error_man.muted.contains(key) {
+        error_man.queue.push(err);
+    }
 }
 
-/// Removes the top error off of the stack and returns it or None if the queue is empty.
-pub fn pop_err() -> Option<(Id, String)> {
-    ERROR_MAN.with_borrow_mut(|error_man| error_man.queue.pop())
+/// Counts an error thrown by a script. Scripts erroring many times in a short while asks for a screenshot.
+pub fn record_script_err() {
+    let mut error_man = ERROR_MAN.lock().unwrap();
+    let now = Instant::now();
+
+    while error_man
+        .script_errs
+        .front()
+        .is_some_and(|v| now.duration_since(*v) > SCRIPT_ERROR_SPIKE_WINDOW)
+    {
+        error_man.script_errs.pop_front();
+    }
+    error_man.script_errs.push_back(now);
+
+    if error_man.script_errs.len() >= SCRIPT_ERROR_SPIKE {
+        log::warn!(
+            "{} script errors in the last {SCRIPT_ERROR_SPIKE_WINDOW:?}",
+            error_man.script_errs.len()
+        );
+
+        error_man.script_errs.clear();
+        error_man.request_screenshot();
+    }
 }
 
-/// Copies the top error of the queue and returns it, or None if the queue is empty.
-pub fn peek_err() -> Option<(Id, String)> {
-    ERROR_MAN.with_borrow(|error_man| error_man.queue.last().cloned())
+/// Returns true if a fatal error or a spike of script errors asked for a screenshot since the last call, otherwise
+/// false.
+pub fn take_screenshot_request() -> bool {
+    mem::take(&mut ERROR_MAN.lock().unwrap().screenshot_requested)
+}
+
+/// Copies the errors waiting to be displayed, oldest first.
+pub fn queued_errs() -> Vec<GameError> {
+    ERROR_MAN.lock().unwrap().queue.clone()
+}
+
+/// Takes every error waiting to be displayed out of the queue, oldest first.
+pub fn take_errs() -> Vec<GameError> {
+    mem::take(&mut ERROR_MAN.lock().unwrap().queue)
+}
+
+/// Removes the error at the given index of the queue, returning it if it existed.
+pub fn dismiss_err(index: usize) -> Option<GameError> {
+    let mut error_man = ERROR_MAN.lock().unwrap();
+
+    (index < error_man.queue.len()).then(|| error_man.queue.remove(index))
+}
+
+/// Copies the history of recorded errors, oldest first.
+pub fn err_history() -> Vec<GameError> {
+    ERROR_MAN.lock().unwrap().history.clone()
 }
 
 /// Returns true if the queue contains errors, otherwise false.
 pub fn has_err() -> bool {
-    ERROR_MAN.with_borrow(|error_man| error_man.queue.is_empty())
+    !ERROR_MAN.lock().unwrap().queue.is_empty()
+}
+
+/// Stops errors with the given ID from being queued, and removes the queued ones. Fatal errors are still shown.
+///
+/// Returns the key that was muted, so that it can be persisted.
+pub fn mute_err(id: Id, resource_man: &ResourceManager) -> String {
+    let key = error_to_key(id, resource_man).to_string();
+
+    let mut error_man = ERROR_MAN.lock().unwrap();
+
+    error_man.muted.insert(key.clone());
+    error_man
+        .queue
+        .retain(|err| err.id != id || err.severity == ErrorSeverity::Fatal);
+
+    key
+}
+
+/// Replaces the set of muted error keys, e.g. with the ones persisted in the options.
+pub fn set_muted_errs(keys: impl IntoIterator<Item = String>) {
+    ERROR_MAN.lock().unwrap().muted = keys.into_iter().collect();
 }
@@ -0,0 +1,213 @@
+use ron::{Number, Value};
+use std::iter::Peekable;
+use std::str::CharIndices;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ExprError {
+    #[error("unexpected character '{0}' at {1}")]
+    UnexpectedChar(char, usize),
+    #[error("unexpected end of expression")]
+    UnexpectedEnd,
+    #[error("the constant {0} is not defined")]
+    UnknownConstant(String),
+    #[error("the constant {0} is not a number")]
+    NotANumber(String),
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("the result overflowed")]
+    Overflow,
+}
+
+/// A number in an expression. Integers stay integers unless combined with a float.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExprNumber {
+    Int(i64),
+    Float(f64),
+}
+
+impl ExprNumber {
+    fn as_f64(self) -> f64 {
+        match self {
+            ExprNumber::Int(v) => v as f64,
+            ExprNumber::Float(v) => v,
+        }
+    }
+
+    fn apply(
+        self,
+        rhs: Self,
+        int: impl FnOnce(i64, i64) -> Option<i64>,
+        float: impl FnOnce(f64, f64) -> f64,
+    ) -> Result<Self, ExprError> {
+        match (self, rhs) {
+            (ExprNumber::Int(a), ExprNumber::Int(b)) => {
+                int(a, b).map(ExprNumber::Int).ok_or(ExprError::Overflow)
+            }
+            (a, b) => Ok(ExprNumber::Float(float(a.as_f64(), b.as_f64()))),
+        }
+    }
+
+    fn is_zero(self) -> bool {
+        self.as_f64() == 0.0
+    }
+}
+
+impl From<ExprNumber> for Value {
+    fn from(value: ExprNumber) -> Self {
+        match value {
+            ExprNumber::Int(v) => Value::Number(Number::from(v)),
+            ExprNumber::Float(v) => Value::Number(Number::from(v)),
+        }
+    }
+}
+
+struct Parser<'a, F> {
+    src: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    constant: F,
+}
+
+impl<F: FnMut(&str) -> Option<Value>> Parser<'_, F> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+
+        self.chars.peek().map(|(_, c)| *c)
+    }
+
+    /// expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<ExprNumber, ExprError> {
+        let mut lhs = self.term()?;
+
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.chars.next();
+            let rhs = self.term()?;
+
+            lhs = if op == '+' {
+                lhs.apply(rhs, i64::checked_add, |a, b| a + b)?
+            } else {
+                lhs.apply(rhs, i64::checked_sub, |a, b| a - b)?
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    /// term := unary (('*' | '/' | '%') unary)*
+    fn term(&mut self) -> Result<ExprNumber, ExprError> {
+        let mut lhs = self.unary()?;
+
+        while let Some(op @ ('*' | '/' | '%')) = self.peek() {
+            self.chars.next();
+            let rhs = self.unary()?;
+
+            lhs = match op {
+                '*' => lhs.apply(rhs, i64::checked_mul, |a, b| a * b)?,
+                _ if rhs.is_zero() => return Err(ExprError::DivisionByZero),
+                '/' => lhs.apply(rhs, i64::checked_div, |a, b| a / b)?,
+                _ => lhs.apply(rhs, i64::checked_rem, |a, b| a % b)?,
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    /// unary := '-' unary | atom
+    fn unary(&mut self) -> Result<ExprNumber, ExprError> {
+        if self.peek() == Some('-') {
+            self.chars.next();
+
+            return match self.unary()? {
+                ExprNumber::Int(v) => v
+                    .checked_neg()
+                    .map(ExprNumber::Int)
+                    .ok_or(ExprError::Overflow),
+                ExprNumber::Float(v) => Ok(ExprNumber::Float(-v)),
+            };
+        }
+
+        self.atom()
+    }
+
+    /// atom := number | constant | '(' expr ')'
+    fn atom(&mut self) -> Result<ExprNumber, ExprError> {
+        let Some(c) = self.peek() else {
+            return Err(ExprError::UnexpectedEnd);
+        };
+
+        if c == '(' {
+            self.chars.next();
+            let v = self.expr()?;
+
+            return match self.chars.next() {
+                Some((_, ')')) => Ok(v),
+                Some((i, c)) => Err(ExprError::UnexpectedChar(c, i)),
+                None => Err(ExprError::UnexpectedEnd),
+            };
+        }
+
+        let (start, _) = *self.chars.peek().unwrap();
+
+        if c.is_ascii_digit() || c == '.' {
+            let end = self.take_while(|c| c.is_ascii_digit() || c == '.' || c == '_');
+            let text = self.src[start..end].replace('_', "");
+
+            return if let Ok(v) = text.parse::<i64>() {
+                Ok(ExprNumber::Int(v))
+            } else {
+                text.parse::<f64>()
+                    .map(ExprNumber::Float)
+                    .map_err(|_| ExprError::UnexpectedChar(c, start))
+            };
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let end = self.take_while(|c| c.is_alphanumeric() || c == '_' || c == ':');
+            let name = &self.src[start..end];
+
+            return match (self.constant)(name) {
+                Some(Value::Number(Number::Integer(v))) => Ok(ExprNumber::Int(v)),
+                Some(Value::Number(Number::Float(v))) => Ok(ExprNumber::Float(v.get())),
+                Some(_) => Err(ExprError::NotANumber(name.to_string())),
+                None => Err(ExprError::UnknownConstant(name.to_string())),
+            };
+        }
+
+        Err(ExprError::UnexpectedChar(c, start))
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> usize {
+        while self.chars.next_if(|(_, c)| pred(*c)).is_some() {}
+
+        self.chars.peek().map_or(self.src.len(), |(i, _)| *i)
+    }
+}
+
+/// Evaluates an arithmetic expression, with `+ - * / %` and parentheses.
+/// Names are looked up with the given function, and must be numbers.
+///
+/// Integer division truncates, like in Rhai. Any float operand makes the result a float.
+pub fn eval_expr(
+    src: &str,
+    constant: impl FnMut(&str) -> Option<Value>,
+) -> Result<ExprNumber, ExprError> {
+    let mut parser = Parser {
+        src,
+        chars: src.char_indices().peekable(),
+        constant,
+    };
+
+    let v = parser.expr()?;
+
+    if let Some(c) = parser.peek() {
+        let (i, _) = *parser.chars.peek().unwrap();
+
+        return Err(ExprError::UnexpectedChar(c, i));
+    }
+
+    Ok(v)
+}
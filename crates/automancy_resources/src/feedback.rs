@@ -0,0 +1,55 @@
+use automancy_defs::colors::Color;
+use automancy_defs::{coord::TileCoord, id::Id, math::Float};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+/// The most effects waiting to be applied. Any more are dropped, so that a runaway script can't pile them up.
+const MAX_QUEUED_EFFECTS: usize = 64;
+
+/// A screen effect, giving the player feedback about something that happened.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScreenEffect {
+    /// shakes the camera, fading out over the duration. the strength is from 0 to 1
+    Shake { strength: Float, duration: Duration },
+    /// covers the screen with a color, fading out over the duration
+    Flash { color: Color, duration: Duration },
+    /// freezes the simulation for the duration
+    HitStop(Duration),
+}
+
+/// A screen effect, and the tile it came from. Effects of tiles out of view are ignored.
+pub type ScreenEffectRequest = (Option<TileCoord>, ScreenEffect);
+
+// effects are requested from the tile entities' threads, and applied on the main thread
+static SCREEN_EFFECTS: LazyLock<Mutex<Vec<ScreenEffectRequest>>> = LazyLock::new(Default::default);
+
+/// Queues a screen effect to be applied on the next frame.
+pub fn push_screen_effect(origin: Option<TileCoord>, effect: ScreenEffect) {
+    let mut effects = SCREEN_EFFECTS.lock().unwrap();
+
+    if effects.len() < MAX_QUEUED_EFFECTS {
+        effects.push((origin, effect));
+    }
+}
+
+/// Takes every queued screen effect.
+pub fn take_screen_effects() -> Vec<ScreenEffectRequest> {
+    std::mem::take(&mut *SCREEN_EFFECTS.lock().unwrap())
+}
+
+// the same as the effects, cutscenes are requested by scripts, and played on the main thread
+static CUTSCENES: Mutex<Vec<Id>> = Mutex::new(Vec::new());
+
+/// Queues a cutscene to be played, after the one playing if there is one.
+pub fn push_cutscene(id: Id) {
+    let mut cutscenes = CUTSCENES.lock().unwrap();
+
+    if cutscenes.len() < MAX_QUEUED_EFFECTS {
+        cutscenes.push(id);
+    }
+}
+
+/// Takes every queued cutscene, in the order they were queued.
+pub fn take_cutscenes() -> Vec<Id> {
+    std::mem::take(&mut *CUTSCENES.lock().unwrap())
+}
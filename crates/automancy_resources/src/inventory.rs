@@ -75,6 +75,20 @@ impl Inventory {
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct InventoryRaw(Vec<(String, ItemAmount)>);
 
+impl Deref for InventoryRaw {
+    type Target = Vec<(String, ItemAmount)>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for InventoryRaw {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
 impl InventoryRaw {
     pub fn try_to_inventory(&self, interner: &Interner) -> Inventory {
         Inventory(try_parse_map_id_of(
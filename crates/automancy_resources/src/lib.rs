@@ -1,22 +1,30 @@
-use crate::registry::{DataIds, ErrorIds, GuiIds, KeyIds, ModelIds, Registry};
+use crate::assets::AssetGraph;
+use crate::registry::{DataIds, ErrorIds, GuiIds, KeyIds, ModelIds, Registry, TagIds};
+use crate::rhai_module::PackModuleResolver;
+#[cfg(feature = "audio")]
+use crate::types::audio::{AudioLibrary, SoundCategory, SoundDef};
 use crate::types::font::Font;
-use crate::types::model::IndexRange;
+use crate::types::model::{AnimationEvents, IndexRange};
+use crate::types::pack::PackManifest;
 use crate::types::translate::TranslateDef;
+use anyhow::Context;
+use arc_swap::ArcSwap;
+use automancy_defs::id::{ModelId, TileId};
+#[cfg(feature = "audio")]
+use automancy_defs::kira::track::TrackHandle;
 use automancy_defs::rendering::{Animation, Mesh};
 use automancy_defs::{
     chrono::{DateTime, Local},
     id::SharedStr,
 };
 use automancy_defs::{coord::TileCoord, log};
-use automancy_defs::{id::ModelId, kira::sound::static_sound::StaticSoundData};
-use automancy_defs::{id::TileId, kira::track::TrackHandle};
 use automancy_defs::{
     id::{Id, IdRaw, Interner},
     stack::ItemStack,
 };
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use rhai::{CallFnOptions, Dynamic, Engine, AST};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::ffi::OsStr;
 use std::fmt;
 use std::fmt::{Debug, Formatter};
@@ -30,19 +38,30 @@ use walkdir::WalkDir;
 
 pub use petgraph;
 
+pub mod archive;
+pub mod assets;
 pub mod data;
 pub mod error;
+pub mod expr;
+pub mod feedback;
 pub mod inventory;
+pub mod profiler;
 
 pub mod format;
 pub mod registry;
 pub mod types;
+pub mod watch;
 
 pub mod rhai_coord;
 pub mod rhai_data;
+pub mod rhai_feedback;
+pub mod rhai_field;
+pub mod rhai_map;
 pub mod rhai_math;
+pub mod rhai_module;
 pub mod rhai_render;
 pub mod rhai_resources;
+pub mod rhai_test;
 pub mod rhai_tile;
 pub mod rhai_ui;
 pub mod rhai_utils;
@@ -98,22 +117,45 @@ pub static RESOURCE_MAN: RwLock<Option<Arc<ResourceManager>>> = RwLock::new(None
 /// Represents a resource manager, which contains all resources (apart from maps) loaded from disk dynamically.
 pub struct ResourceManager {
     pub interner: Interner,
+    /// The namespaces that were loaded, in the order they were loaded.
+    pub namespaces: Vec<String>,
+    /// the folder each namespace was loaded from, to load its translates again from
+    pub namespace_dirs: HashMap<String, PathBuf>,
+    /// the version each namespace declares in its manifest, if it does
+    pub namespace_versions: HashMap<String, String>,
+    #[cfg(feature = "audio")]
     pub track: TrackHandle,
     pub engine: Engine,
+    pub module_resolver: PackModuleResolver,
 
     pub registry: Registry,
 
-    pub translates: TranslateDef,
-    pub audio: HashMap<String, StaticSoundData>,
+    /// the text of the selected language, which is swapped out as a whole when the language is switched in game
+    pub translates: ArcSwap<TranslateDef>,
+    /// the languages the loaded packs have translates in
+    pub languages: BTreeSet<String>,
+    #[cfg(feature = "audio")]
+    pub audio: Arc<AudioLibrary>,
+    #[cfg(feature = "audio")]
+    pub sounds: HashMap<String, SoundDef>,
+    #[cfg(feature = "audio")]
+    pub sound_categories: HashMap<String, SoundCategory>,
     pub shaders: HashMap<String, SharedStr>,
     pub functions: HashMap<Id, FunctionInfo>,
+    /// the IDs the library functions declare in `id_deps`, which count as used, as any function may import them
+    pub library_id_deps: HashSet<Id>,
     pub fonts: BTreeMap<String, Font>, // yes this does need to be a BTreeMap
+    pub constants: HashMap<String, ron::Value>,
 
-    pub ordered_tiles: Vec<TileId>,
-    pub ordered_items: Vec<Id>,
+    /// the tiles and items sorted by their names, sorted again when the language is switched
+    pub ordered_tiles: ArcSwap<Vec<TileId>>,
+    pub ordered_items: ArcSwap<Vec<Id>>,
     pub ordered_categories: Vec<Id>,
     pub all_meshes_anims: HashMap<ModelId, (Vec<Option<Mesh>>, Vec<Animation>)>,
     pub all_index_ranges: HashMap<ModelId, HashMap<usize, IndexRange>>,
+    pub animation_events: HashMap<ModelId, AnimationEvents>,
+    /// what each of the loaded assets references, built once every pack is loaded
+    pub asset_graph: AssetGraph,
 }
 
 impl Debug for ResourceManager {
@@ -123,34 +165,60 @@ impl Debug for ResourceManager {
 }
 
 impl ResourceManager {
-    pub fn new(track: TrackHandle) -> Self {
-        let mut interner = Interner::new();
+    pub fn new(#[cfg(feature = "audio")] track: TrackHandle) -> Self {
+        Self::with_interner(
+            #[cfg(feature = "audio")]
+            track,
+            Interner::new(),
+        )
+    }
+
+    /// Creates a resource manager that interns its IDs into the given interner, e.g. the one of the resource manager it
+    /// replaces when the packs are reloaded, so that the IDs held by the map and the tiles stay the same.
+    pub fn with_interner(
+        #[cfg(feature = "audio")] track: TrackHandle,
+        mut interner: Interner,
+    ) -> Self {
         let none = IdRaw::new("core", "none").to_id(&mut interner);
         let any = IdRaw::new("core", "#any").to_id(&mut interner);
 
         let mut engine = Engine::new();
         engine.set_max_expr_depths(0, 0);
         engine.set_fast_operators(false);
+        profiler::SCRIPT_PROFILER.install(&mut engine);
+
+        let module_resolver = PackModuleResolver::default();
+        engine.set_module_resolver(module_resolver.clone());
 
         rhai_math::register_math_stuff(&mut engine);
         rhai_utils::register_functions(&mut engine);
         rhai_coord::register_coord_stuff(&mut engine);
         rhai_data::register_data_stuff(&mut engine);
+        rhai_map::register_map_stuff(&mut engine);
+        rhai_field::register_field_stuff(&mut engine);
+        rhai_feedback::register_feedback_stuff(&mut engine);
         rhai_resources::register_resources(&mut engine);
         rhai_tile::register_tile_stuff(&mut engine);
         rhai_ui::register_ui_stuff(&mut engine);
         rhai_render::register_render_stuff(&mut engine);
+        rhai_test::register_test_stuff(&mut engine);
 
         let data_ids = DataIds::new(&mut interner);
         let model_ids = ModelIds::new(&mut interner);
         let gui_ids = GuiIds::new(&mut interner);
         let key_ids = KeyIds::new(&mut interner);
+        let tag_ids = TagIds::new(&mut interner);
         let err_ids = ErrorIds::new(&mut interner);
 
         Self {
             interner,
+            namespaces: Default::default(),
+            namespace_dirs: Default::default(),
+            namespace_versions: Default::default(),
+            #[cfg(feature = "audio")]
             track,
             engine,
+            module_resolver,
 
             registry: Registry {
                 tiles: Default::default(),
@@ -158,7 +226,15 @@ impl ResourceManager {
                 tags: Default::default(),
                 categories: Default::default(),
                 categories_tiles_map: Default::default(),
+                categories_children: Default::default(),
                 items: Default::default(),
+                item_tags: Default::default(),
+                item_producers: Default::default(),
+                item_consumers: Default::default(),
+                script_tiles: Default::default(),
+                data_rules: Default::default(),
+                agents: Default::default(),
+                cutscenes: Default::default(),
                 researches: Default::default(),
                 researches_id_map: Default::default(),
                 researches_unlock_map: Default::default(),
@@ -171,23 +247,114 @@ impl ResourceManager {
                 gui_ids,
                 err_ids,
                 key_ids,
+                tag_ids,
             },
 
             translates: Default::default(),
+            languages: Default::default(),
+            #[cfg(feature = "audio")]
             audio: Default::default(),
+            #[cfg(feature = "audio")]
+            sounds: Default::default(),
+            #[cfg(feature = "audio")]
+            sound_categories: Default::default(),
             shaders: Default::default(),
             functions: Default::default(),
+            library_id_deps: Default::default(),
             fonts: Default::default(),
+            constants: Default::default(),
 
-            ordered_tiles: vec![],
-            ordered_items: vec![],
+            ordered_tiles: Default::default(),
+            ordered_items: Default::default(),
             ordered_categories: vec![],
             all_index_ranges: Default::default(),
+            animation_events: Default::default(),
             all_meshes_anims: Default::default(),
+            asset_graph: Default::default(),
         }
     }
 }
 
+impl ResourceManager {
+    /// The namespaces that were loaded, in the order they were loaded, with the version each declares.
+    pub fn pack_versions(&self) -> Vec<(String, Option<String>)> {
+        self.namespaces
+            .iter()
+            .map(|v| (v.clone(), self.namespace_versions.get(v).cloned()))
+            .collect()
+    }
+
+    /// Loads everything in a namespace's folder of the resource packs.
+    pub fn load_namespace(
+        &mut self,
+        dir: &Path,
+        namespace: &str,
+        selected_language: &str,
+    ) -> anyhow::Result<()> {
+        self.load_constants(dir, namespace)
+            .context("Error loading constants")?;
+        self.load_models(dir, namespace)
+            .context("Error loading models")?;
+        #[cfg(feature = "audio")]
+        self.load_audio(dir, namespace)
+            .context("Error loading audio")?;
+        self.load_tiles(dir, namespace)
+            .context("Error loading tiles")?;
+        self.load_agents(dir, namespace)
+            .context("Error loading agents")?;
+        self.load_items(dir, namespace)
+            .context("Error loading items")?;
+        self.load_tags(dir, namespace)
+            .context("Error loading tags")?;
+        self.load_categories(dir, namespace)
+            .context("Error loading categories")?;
+        self.load_data_rules(dir, namespace)
+            .context("Error loading data rules")?;
+        self.load_scripts(dir, namespace)
+            .context("Error loading scripts")?;
+        self.load_translates(dir, namespace, selected_language)
+            .context("Error loading translates")?;
+        self.load_shaders(dir).context("Error loading shaders")?;
+        self.load_fonts(dir).context("Error loading fonts")?;
+        self.load_functions(dir, namespace)
+            .context("Error loading functions")?;
+        self.load_researches(dir, namespace)
+            .context("Error loading researches")?;
+        self.load_cutscenes(dir, namespace)
+            .context("Error loading cutscenes")?;
+
+        self.namespaces.push(namespace.to_string());
+        self.namespace_dirs
+            .insert(namespace.to_string(), dir.to_path_buf());
+        // the manifest was already checked when the packs were sorted
+        if let Some(version) = PackManifest::read(dir).ok().and_then(|v| v.version) {
+            self.namespace_versions
+                .insert(namespace.to_string(), version);
+        }
+
+        Ok(())
+    }
+
+    /// Loads a resource pack distributed as a single archive, see [archive::ARCHIVE_EXT]. It is unpacked first, and then
+    /// loaded the same as a folder, with the name of the archive as its namespace. Returns the namespace.
+    pub fn load_namespace_archive(
+        &mut self,
+        path: &Path,
+        selected_language: &str,
+    ) -> anyhow::Result<String> {
+        let namespace = archive::archive_namespace(path)
+            .context("Not a resource pack archive")?
+            .to_string();
+
+        let dir = archive::unpack_archive(path, &namespace)
+            .with_context(|| format!("Error unpacking {}", path.display()))?;
+
+        self.load_namespace(&dir, &namespace, selected_language)?;
+
+        Ok(namespace)
+    }
+}
+
 pub fn rhai_call_options(state: &mut Dynamic) -> CallFnOptions {
     CallFnOptions::new()
         .eval_ast(false)
@@ -209,10 +376,12 @@ pub fn rhai_log_err(
         rhai::EvalAltResult::ErrorFunctionNotFound(name, ..) => {
             if name != called_func {
                 log::error!("At {coord}, In {function_id}, {called_func}: {err}");
+                error::record_script_err();
             }
         }
         _ => {
             log::error!("At {coord}, In {function_id}, {called_func}: {err}");
+            error::record_script_err();
         }
     }
 }
@@ -248,6 +417,7 @@ pub fn item_stack_matches(
 pub fn item_ids_of_tag(resource_man: &ResourceManager, id: Id) -> Vec<Id> {
     resource_man
         .ordered_items
+        .load()
         .iter()
         .filter(|v| item_match(resource_man, **v, id))
         .cloned()
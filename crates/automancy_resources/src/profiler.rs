@@ -0,0 +1,149 @@
+use rhai::Engine;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The profiler of the script calls, read from the debug menu.
+pub static SCRIPT_PROFILER: ScriptProfiler = ScriptProfiler::new();
+
+thread_local! {
+    /// the operations the script running on this thread has taken so far, as counted by the engine's progress callback
+    static OPERATIONS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// How long the calls to a script function took, and how many there were, since the profiler was last cleared.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FunctionProfile {
+    pub calls: u64,
+    pub total: Duration,
+    /// the longest a single call took
+    pub peak: Duration,
+    /// the operations taken in all the calls together
+    pub operations: u64,
+}
+
+impl FunctionProfile {
+    pub fn average(&self) -> Duration {
+        self.total
+            .checked_div(self.calls.max(1) as u32)
+            .unwrap_or_default()
+    }
+}
+
+/// Records how long each script function takes, by the ID of the script and the name of the function, to find the
+/// scripts stalling the ticks.
+///
+/// Operations are only counted if the engine had the progress callback installed when it was made, which is only done
+/// when the profiler is made available, as it runs on every operation of every script.
+#[derive(Debug)]
+pub struct ScriptProfiler {
+    /// whether the engines are made with the progress callback, and the debug menu offers to record
+    available: AtomicBool,
+    recording: AtomicBool,
+    profiles: Mutex<BTreeMap<(String, String), FunctionProfile>>,
+}
+
+impl ScriptProfiler {
+    const fn new() -> Self {
+        Self {
+            available: AtomicBool::new(false),
+            recording: AtomicBool::new(false),
+            profiles: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Makes the profiler available, before the resources are loaded, e.g. when the game is started with
+    /// `--profile-scripts`.
+    pub fn make_available(&self) {
+        self.available.store(true, Ordering::Relaxed);
+    }
+
+    pub fn available(&self) -> bool {
+        self.available.load(Ordering::Relaxed)
+    }
+
+    /// Installs the progress callback counting the operations of the scripts, if the profiler is available.
+    pub fn install(&self, engine: &mut Engine) {
+        if !self.available() {
+            return;
+        }
+
+        engine.on_progress(|operations| {
+            OPERATIONS.set(operations);
+            None
+        });
+    }
+
+    pub fn recording(&self) -> bool {
+        self.recording.load(Ordering::Relaxed)
+    }
+
+    /// Starts or stops recording the script calls. What was recorded is kept until it is cleared.
+    pub fn set_recording(&self, recording: bool) {
+        self.recording
+            .store(recording && self.available(), Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        self.profiles.lock().unwrap().clear();
+    }
+
+    /// Starts timing a call to a script function, if recording. The call is recorded once it's finished.
+    pub fn start(&'static self, script: &str, function: &str) -> Option<ProfiledCall> {
+        if !self.recording() {
+            return None;
+        }
+
+        OPERATIONS.set(0);
+
+        Some(ProfiledCall {
+            profiler: self,
+            script: script.to_string(),
+            function: function.to_string(),
+            start: Instant::now(),
+        })
+    }
+
+    /// Gets the profiles of the functions that took the longest in total, longest first.
+    pub fn slowest(&self, count: usize) -> Vec<((String, String), FunctionProfile)> {
+        let mut profiles = self
+            .profiles
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(k, v)| (k.clone(), *v))
+            .collect::<Vec<_>>();
+
+        profiles.sort_by(|(_, a), (_, b)| b.total.cmp(&a.total));
+        profiles.truncate(count);
+
+        profiles
+    }
+}
+
+/// A call to a script function being timed.
+#[derive(Debug)]
+pub struct ProfiledCall {
+    profiler: &'static ScriptProfiler,
+    script: String,
+    function: String,
+    start: Instant,
+}
+
+impl ProfiledCall {
+    /// Records the call, as finished now.
+    pub fn finish(self) {
+        let elapsed = self.start.elapsed();
+        let operations = OPERATIONS.get();
+
+        let mut profiles = self.profiler.profiles.lock().unwrap();
+        let profile = profiles.entry((self.script, self.function)).or_default();
+
+        profile.calls += 1;
+        profile.total += elapsed;
+        profile.peak = profile.peak.max(elapsed);
+        profile.operations += operations;
+    }
+}
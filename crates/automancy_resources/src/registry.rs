@@ -1,3 +1,6 @@
+use crate::types::agent::AgentDef;
+use crate::types::cutscene::CutsceneDef;
+use crate::types::data_rule::DataRuleDef;
 use crate::types::research::ResearchDef;
 use crate::types::script::ScriptDef;
 use crate::types::tag::TagDef;
@@ -16,7 +19,15 @@ pub struct Registry {
     pub tags: HashMap<Id, TagDef>,
     pub categories: HashMap<Id, CategoryDef>,
     pub(crate) categories_tiles_map: HashMap<Id, Vec<TileId>>,
+    pub(crate) categories_children: HashMap<Id, Vec<Id>>,
     pub items: HashMap<Id, ItemDef>,
+    pub(crate) item_tags: HashMap<Id, Vec<Id>>,
+    pub(crate) item_producers: HashMap<Id, Vec<Id>>,
+    pub(crate) item_consumers: HashMap<Id, Vec<Id>>,
+    pub(crate) script_tiles: HashMap<Id, Vec<TileId>>,
+    pub data_rules: HashMap<Id, DataRuleDef>,
+    pub agents: HashMap<Id, AgentDef>,
+    pub cutscenes: HashMap<Id, CutsceneDef>,
     pub researches: StableDiGraph<ResearchDef, ()>,
     pub(crate) researches_id_map: HashMap<Id, NodeIndex>,
     pub(crate) researches_unlock_map: HashMap<TileId, NodeIndex>,
@@ -28,6 +39,7 @@ pub struct Registry {
     pub model_ids: ModelIds,
     pub gui_ids: GuiIds,
     pub key_ids: KeyIds,
+    pub tag_ids: TagIds,
     pub err_ids: ErrorIds,
 }
 
@@ -39,6 +51,9 @@ pub struct DataIds {
     pub capacity: Id,
     pub direction: Id,
     pub link: Id,
+    /// the number of ticks the current processing has taken, out of `progress_total`
+    pub progress: Id,
+    pub progress_total: Id,
 
     pub player_inventory: Id,
     pub research_items_filled: Id,
@@ -55,10 +70,55 @@ pub struct DataIds {
     pub inactive_model: Id,
     #[namespace("core")]
     pub default_tile: Id,
+    #[namespace("core")]
+    pub locked_tile_visibility: Id,
+    /// the ticks per second of the map, overriding the options
+    #[namespace("core")]
+    pub tick_rate: Id,
+    /// how many random tiles of each chunk receive a random tick every tick
+    #[namespace("core")]
+    pub random_tick_speed: Id,
+    /// how many ticks pass between two diffusions of the fields, if the map simulates them
+    #[namespace("core")]
+    pub field_diffusion_interval: Id,
+    /// the most minutes the map catches up on when loaded, for the time it was closed
+    #[namespace("core")]
+    pub offline_progress: Id,
+    /// the function whose CONDITIONS_FUNCTION decides whether the map was won or lost
+    #[namespace("core")]
+    pub conditions: Id,
+    /// the most tile entities the map may have, if it sets a budget
+    #[namespace("core")]
+    pub budget_tiles: Id,
+    /// the most agents the map may have, if it sets a budget
+    #[namespace("core")]
+    pub budget_agents: Id,
+    /// the most items the transfer queues may hold, if the map sets a budget
+    #[namespace("core")]
+    pub budget_in_flight_items: Id,
+    /// whether the map refuses placing tiles and spawning agents over its budget
+    #[namespace("core")]
+    pub budget_block: Id,
+    /// the colors of the map's background gradient, at the top and at the bottom of the screen
+    #[namespace("core")]
+    pub background_top: Id,
+    #[namespace("core")]
+    pub background_bottom: Id,
+    /// the length of the map's day cycle in seconds, passed to the background shader
+    #[namespace("core")]
+    pub day_length: Id,
 
     #[namespace("core")]
     #[name("$none_tile")]
     pub none_tile_render_tag: Id,
+    /// the render tag the models of tiles with connected models are tracked under
+    #[namespace("core")]
+    #[name("$connected_model")]
+    pub connected_model_render_tag: Id,
+    /// the cutscene played when a new map is created, if a pack defines it, like the tutorial intro
+    #[namespace("core")]
+    #[name("intro")]
+    pub intro_cutscene: Id,
 }
 
 #[derive(Copy, Clone, IdReg)]
@@ -84,12 +144,70 @@ pub struct ModelIds {
 pub struct GuiIds {
     pub info: Id,
     pub player_menu: Id,
-    pub error_popup: Id,
+    pub error_history: Id,
     pub debug_menu: Id,
+    pub beacons: Id,
+    pub lbl_beacon_name: Id,
+    pub btn_add_beacon: Id,
+    pub btn_go_to: Id,
+    pub statistics: Id,
+    pub btn_export_csv: Id,
+    pub btn_export_json: Id,
+    pub lbl_production_per_minute: Id,
+    pub lbl_machine_counts: Id,
+    pub lbl_statistics_exported: Id,
+    pub lbl_statistics_export_failed: Id,
+    pub lbl_device_recovered: Id,
+    pub lbl_quality_lowered: Id,
+    pub lbl_quality_restored: Id,
+    pub lbl_cutscene_skip: Id,
+    pub verify_map: Id,
+    pub lbl_map_verified: Id,
+    pub lbl_map_issues: Id,
+    pub lbl_map_repaired: Id,
+    pub lbl_map_repair_failed: Id,
+    pub lbl_save_signed: Id,
+    pub lbl_save_modified: Id,
+    pub lbl_read_only: Id,
+    pub lbl_map_saved: Id,
+    pub namespaces_changed: Id,
+    pub lbl_namespaces_changed: Id,
+    pub lbl_namespace_added: Id,
+    pub lbl_namespace_removed: Id,
+    pub lbl_namespace_version_changed: Id,
+    pub whats_new: Id,
+    pub lbl_whats_new: Id,
+    pub lbl_font_missing_glyphs: Id,
+    pub lbl_registry_tiles: Id,
+    pub lbl_registry_items: Id,
+    pub lbl_registry_scripts: Id,
+    pub lbl_registry_added: Id,
+    pub lbl_registry_removed: Id,
+    pub lbl_registry_changed: Id,
+    pub btn_export_notes: Id,
+    pub lbl_notes_exported: Id,
+    pub lbl_notes_export_failed: Id,
+    pub map_search: Id,
+    pub lbl_search_tile: Id,
+    pub lbl_search_item: Id,
+    pub lbl_search_data_key: Id,
+    pub lbl_search_results: Id,
+    pub btn_search: Id,
+    pub blueprints: Id,
+    pub btn_save_blueprint: Id,
+    pub btn_place_blueprint: Id,
+    pub lbl_blueprint_copy_first: Id,
+    pub lbl_blueprint_saved: Id,
+    pub lbl_blueprint_save_failed: Id,
+    pub lbl_blueprint_load_failed: Id,
     pub load_map: Id,
     pub delete_map: Id,
+    pub delete_maps: Id,
+    pub rename_maps: Id,
+    pub create_folder: Id,
     pub create_map: Id,
     pub invalid_name: Id,
+    pub remap_ids: Id,
     pub options: Id,
     pub tile_config: Id,
 
@@ -98,16 +216,79 @@ pub struct GuiIds {
     pub options_graphics_ui_scale_small: Id,
     pub options_graphics_ui_scale_normal: Id,
     pub options_graphics_ui_scale_large: Id,
+    pub options_graphics_ui_scale_auto: Id,
+    pub options_graphics_ui_scale_custom: Id,
     pub options_audio: Id,
     pub options_gui: Id,
+    pub options_advanced: Id,
     pub options_controls: Id,
 
     pub lbl_maps_loaded: Id,
     pub lbl_pick_another_name: Id,
+    pub lbl_map_name: Id,
     pub lbl_delete_map_confirm: Id,
+    pub lbl_delete_maps_confirm: Id,
+    pub lbl_map_folder: Id,
+    pub lbl_folder_name: Id,
+    pub lbl_maps_selected: Id,
+    pub lbl_maps_exported: Id,
+    pub lbl_maps_export_failed: Id,
+    pub lbl_remap_ids: Id,
     pub lbl_cannot_place_missing_item: Id,
+    pub lbl_locked_tiles: Id,
+    pub lbl_locked_tiles_hidden: Id,
+    pub lbl_locked_tiles_greyed: Id,
+    pub lbl_locked_tiles_visible: Id,
+    pub lbl_unlock_hint: Id,
+    pub lbl_progress_eta: Id,
+    pub lbl_map_tick_rate: Id,
+    pub lbl_map_tick_rate_default: Id,
+    pub lbl_map_random_tick_speed: Id,
+    pub lbl_map_field_diffusion: Id,
+    pub lbl_map_field_diffusion_off: Id,
+    pub lbl_map_offline_progress: Id,
+    pub lbl_map_offline_progress_off: Id,
+    pub offline_progress: Id,
+    pub lbl_offline_progress: Id,
+    pub victory: Id,
+    pub defeat: Id,
+    pub lbl_map_end_ticks: Id,
+    pub lbl_map_end_tiles: Id,
+    pub lbl_map_end_produced: Id,
+    pub btn_next_map: Id,
+    pub lbl_next_map_missing: Id,
+    pub lbl_packs_reloaded: Id,
+    pub lbl_packs_reload_failed: Id,
+    pub lbl_language_switch_failed: Id,
+    pub lbl_safe_mode: Id,
+    pub lbl_options_restored: Id,
+    pub lbl_options_restore_failed: Id,
+    pub lbl_map_budget: Id,
+    pub lbl_map_budget_tiles: Id,
+    pub lbl_map_budget_agents: Id,
+    pub lbl_map_budget_in_flight_items: Id,
+    pub lbl_map_budget_off: Id,
+    pub lbl_map_budget_block: Id,
+    pub lbl_over_budget: Id,
+    pub lbl_field_condition: Id,
+    pub lbl_item_tags: Id,
+    pub lbl_item_produced_by: Id,
+    pub lbl_item_used_by: Id,
+    pub lbl_item_producible: Id,
+    pub lbl_item_not_producible: Id,
+    pub lbl_error_info: Id,
+    pub lbl_error_warn: Id,
+    pub lbl_error_fatal: Id,
+    pub lbl_deconstruct_all: Id,
+    pub lbl_deconstruct_same_tile: Id,
+    pub lbl_deconstruct_transfer: Id,
+    pub lbl_action_configure: Id,
+    pub lbl_action_copy_settings: Id,
+    pub lbl_action_delete: Id,
+    pub lbl_action_jump_link: Id,
 
     pub btn_confirm: Id,
+    pub btn_restore_options: Id,
     pub btn_exit: Id,
     pub btn_cancel: Id,
     pub btn_link_network: Id,
@@ -116,12 +297,30 @@ pub struct GuiIds {
     pub btn_fedi: Id,
     pub btn_source: Id,
     pub btn_unpause: Id,
+    pub btn_disable_adaptive_quality: Id,
     pub btn_load: Id,
+    pub btn_load_read_only: Id,
     pub btn_delete: Id,
+    pub btn_drop: Id,
     pub btn_new_map: Id,
+    pub btn_new_folder: Id,
+    pub btn_parent_folder: Id,
+    pub btn_rename_selected: Id,
+    pub btn_delete_selected: Id,
+    pub btn_export_selected: Id,
+    pub btn_dismiss: Id,
+    pub btn_dont_show_again: Id,
+    pub btn_error_history: Id,
+    pub btn_verify_map: Id,
+    pub btn_repair: Id,
+    pub btn_continue: Id,
+    pub btn_pin: Id,
+    pub btn_unpin: Id,
 
     pub research_menu_title: Id,
     pub player_inventory_title: Id,
+    pub storage_title: Id,
+    pub lbl_transfer_amount: Id,
     pub research_submit_items: Id,
 
     pub time_fmt: Id,
@@ -141,6 +340,21 @@ pub struct KeyIds {
     pub cut: Id,
     pub copy: Id,
     pub paste: Id,
+    pub deconstruct: Id,
+    pub deconstruct_filter: Id,
+    pub map_search: Id,
+    pub next_beacon: Id,
+    pub statistics_menu: Id,
+    pub follow: Id,
+    pub field_overlay: Id,
+    pub xray: Id,
+    pub blueprints: Id,
+}
+
+#[derive(Clone, Copy, IdReg)]
+pub struct TagIds {
+    #[name("#transfer")]
+    pub transfer: Id,
 }
 
 #[derive(Clone, Copy, IdReg)]
@@ -151,4 +365,10 @@ pub struct ErrorIds {
     /// This error is displayed when the options cannot be written.
     #[namespace("core")]
     pub unwritable_options: Id,
+    /// This error is displayed when the game cannot run ticks as fast as it should.
+    #[namespace("core")]
+    pub simulation_behind: Id,
+    /// This error is displayed when the map reaches a limit of its budget.
+    #[namespace("core")]
+    pub map_over_budget: Id,
 }
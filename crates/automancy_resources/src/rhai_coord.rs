@@ -1,5 +1,5 @@
 use automancy_defs::{
-    coord::{TileBounds, TileCoord, TileUnit},
+    coord::{TileBounds, TileBoundsSet, TileCoord, TileUnit},
     math::tile_direction_to_angle,
 };
 use automancy_defs::{id::Id, math::Matrix4};
@@ -44,6 +44,59 @@ pub(crate) fn register_coord_stuff(engine: &mut Engine) {
 
             Matrix4::from_rotation_z(deg.to_radians())
         })
+        .register_fn(
+            "rotate_left",
+            |v: TileCoord, center: TileCoord, steps: TileUnit| -> TileCoord {
+                TileCoord::from(v.rotate_ccw_around(*center, steps.rem_euclid(6) as u32))
+            },
+        )
+        .register_fn(
+            "rotate_right",
+            |v: TileCoord, center: TileCoord, steps: TileUnit| -> TileCoord {
+                TileCoord::from(v.rotate_cw_around(*center, steps.rem_euclid(6) as u32))
+            },
+        )
+        .register_fn(
+            "reflect_q",
+            |v: TileCoord, center: TileCoord| -> TileCoord {
+                TileCoord::from((*v - *center).reflect_q() + *center)
+            },
+        )
+        .register_fn(
+            "reflect_r",
+            |v: TileCoord, center: TileCoord| -> TileCoord {
+                TileCoord::from((*v - *center).reflect_r() + *center)
+            },
+        )
+        .register_fn(
+            "reflect_s",
+            |v: TileCoord, center: TileCoord| -> TileCoord {
+                TileCoord::from((*v - *center).reflect_s() + *center)
+            },
+        )
+        .register_fn("distance", |a: TileCoord, b: TileCoord| -> TileUnit {
+            a.distance_to(*b)
+        })
+        .register_fn("ring", |v: TileCoord, radius: TileUnit| -> Dynamic {
+            Dynamic::from_iter(v.ring(radius.max(0) as u32).map(TileCoord::from))
+        })
+        .register_fn("spiral", |v: TileCoord, radius: TileUnit| -> Dynamic {
+            Dynamic::from_iter(
+                v.spiral_range(0..=radius.max(0) as u32)
+                    .map(TileCoord::from),
+            )
+        })
+        .register_fn("direction_to", |a: TileCoord, b: TileCoord| -> TileCoord {
+            TileCoord::from(a.main_direction_to(*b).into_hex())
+        })
+        .register_fn(
+            "neighbor_direction",
+            |a: TileCoord, b: TileCoord| -> Dynamic {
+                a.neighbor_direction(*b)
+                    .map(|v| Dynamic::from(TileCoord::from(v.into_hex())))
+                    .unwrap_or(Dynamic::UNIT)
+            },
+        )
         .register_get("q", |v: &mut TileCoord| -> TileUnit { v.x })
         .register_get("r", |v: &mut TileCoord| -> TileUnit { v.y })
         .register_fn("+", TileCoord::add)
@@ -68,4 +121,26 @@ pub(crate) fn register_coord_stuff(engine: &mut Engine) {
         .register_fn("contains", |v: &mut TileBounds, coord: TileCoord| -> bool {
             v.contains(coord)
         });
+
+    engine
+        .register_type_with_name::<TileBoundsSet>("TileBoundsSet")
+        .register_iterator::<TileBoundsSet>()
+        .register_fn("TileBoundsSet", TileBoundsSet::new)
+        .register_fn("TileBoundsSet", |v: TileBounds| -> TileBoundsSet {
+            TileBoundsSet::from(v)
+        })
+        .register_fn("add", |v: &mut TileBoundsSet, bounds: TileBounds| {
+            v.add(bounds)
+        })
+        .register_fn("subtract", |v: &mut TileBoundsSet, bounds: TileBounds| {
+            v.subtract(bounds)
+        })
+        .register_fn(
+            "contains",
+            |v: &mut TileBoundsSet, coord: TileCoord| -> bool { v.contains(coord) },
+        )
+        .register_fn("coords", |v: &mut TileBoundsSet| -> Dynamic {
+            Dynamic::from_iter(v.coords())
+        })
+        .register_fn("is_empty", |v: &mut TileBoundsSet| -> bool { v.is_empty() });
 }
@@ -114,7 +114,11 @@ pub(crate) fn register_data_stuff(engine: &mut Engine) {
         })
         .register_get("outputs", |v: &mut InstructionsDef| -> Dynamic {
             Dynamic::from_iter(v.outputs.iter().cloned())
-        });
+        })
+        .register_fn(
+            "conditions_met",
+            |v: &mut InstructionsDef, coord: TileCoord| -> bool { v.conditions_met(coord) },
+        );
     engine.register_type_with_name::<TileDef>("TileDef");
     engine.register_type_with_name::<TagDef>("TagDef");
 }
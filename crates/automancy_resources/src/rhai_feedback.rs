@@ -0,0 +1,79 @@
+use rhai::{exported_module, Engine, INT};
+use std::time::Duration;
+
+fn millis(v: INT) -> Duration {
+    Duration::from_millis(v.max(0) as u64)
+}
+
+#[export_module]
+mod screen_stuff {
+    use super::millis;
+    use crate::feedback::{push_screen_effect, ScreenEffect};
+    use automancy_defs::colors::Color;
+    use automancy_defs::{coord::TileCoord, hex, math::Float};
+    use rhai::plugin::*;
+    use rhai::{Module, INT};
+
+    /// Shakes the screen if the tile is in view. The strength is a percentage.
+    pub fn shake(coord: TileCoord, strength: INT, duration_ms: INT) {
+        push_screen_effect(
+            Some(coord),
+            ScreenEffect::Shake {
+                strength: strength.clamp(0, 100) as Float / 100.0,
+                duration: millis(duration_ms),
+            },
+        );
+    }
+
+    /// Flashes the screen with a color, written as hex like "ff8800" or "ff880080", if the tile is in view.
+    #[rhai_fn(return_raw)]
+    pub fn flash(
+        coord: TileCoord,
+        color: &str,
+        duration_ms: INT,
+    ) -> Result<(), Box<EvalAltResult>> {
+        let bytes = hex::decode(color.trim_start_matches('#')).unwrap_or_default();
+
+        let [r, g, b, ref a @ ..] = bytes[..] else {
+            return Err(format!("invalid color {color}").into());
+        };
+
+        push_screen_effect(
+            Some(coord),
+            ScreenEffect::Flash {
+                color: Color {
+                    r,
+                    g,
+                    b,
+                    a: a.first().copied().unwrap_or(255),
+                },
+                duration: millis(duration_ms),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Freezes the simulation for a moment if the tile is in view.
+    pub fn hit_stop(coord: TileCoord, duration_ms: INT) {
+        push_screen_effect(Some(coord), ScreenEffect::HitStop(millis(duration_ms)));
+    }
+}
+
+#[export_module]
+mod cutscene_stuff {
+    use crate::feedback::push_cutscene;
+    use automancy_defs::id::Id;
+    use rhai::plugin::*;
+    use rhai::Module;
+
+    /// Plays the cutscene, after the one playing if there is one.
+    pub fn play(id: Id) {
+        push_cutscene(id);
+    }
+}
+
+pub(crate) fn register_feedback_stuff(engine: &mut Engine) {
+    engine.register_static_module("Screen", exported_module!(screen_stuff).into());
+    engine.register_static_module("Cutscene", exported_module!(cutscene_stuff).into());
+}
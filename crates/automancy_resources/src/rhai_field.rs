@@ -0,0 +1,119 @@
+use automancy_defs::{coord::TileCoord, id::Id, math::Float};
+use hashbrown::HashMap;
+use rhai::{Engine, INT};
+use std::sync::{LazyLock, RwLock};
+
+/// How much of its value a tile spreads evenly to its neighbors every time the fields diffuse.
+pub const FIELD_DIFFUSION_RATE: Float = 0.5;
+/// How much of their value every tile loses every time the fields diffuse, so that fields settle instead of growing.
+pub const FIELD_DECAY: Float = 0.02;
+/// Values closer to zero than this are dropped, so that fields don't spread over the whole map.
+const FIELD_EPSILON: Float = 0.01;
+
+// like the map tiles, machines write to the fields from the tile entities' threads
+static FIELDS: LazyLock<RwLock<HashMap<Id, HashMap<TileCoord, Float>>>> =
+    LazyLock::new(Default::default);
+
+/// Empties every field, e.g. when a map is loaded.
+pub fn clear_fields() {
+    FIELDS.write().unwrap().clear();
+}
+
+/// Gets the value of the field at the coordinate. Fields are zero wherever nothing has written to them.
+pub fn field_value(field: Id, coord: TileCoord) -> Float {
+    FIELDS
+        .read()
+        .unwrap()
+        .get(&field)
+        .and_then(|v| v.get(&coord))
+        .copied()
+        .unwrap_or(0.0)
+}
+
+pub fn add_to_field(field: Id, coord: TileCoord, amount: Float) {
+    *FIELDS
+        .write()
+        .unwrap()
+        .entry(field)
+        .or_default()
+        .entry(coord)
+        .or_default() += amount;
+}
+
+pub fn set_field(field: Id, coord: TileCoord, value: Float) {
+    FIELDS
+        .write()
+        .unwrap()
+        .entry(field)
+        .or_default()
+        .insert(coord, value);
+}
+
+/// Gets the fields that have any value anywhere, sorted by ID.
+pub fn field_ids() -> Vec<Id> {
+    let mut ids = FIELDS
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|(_, values)| !values.is_empty())
+        .map(|(id, _)| *id)
+        .collect::<Vec<_>>();
+
+    ids.sort();
+
+    ids
+}
+
+/// Copies every value of the field out, for drawing it.
+pub fn field_values(field: Id) -> HashMap<TileCoord, Float> {
+    FIELDS
+        .read()
+        .unwrap()
+        .get(&field)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Spreads every field out by one step: each tile keeps part of its value and splits the rest between its neighbors,
+/// then everything decays a little.
+pub fn diffuse_fields() {
+    let mut fields = FIELDS.write().unwrap();
+
+    for values in fields.values_mut() {
+        let mut next = HashMap::with_capacity(values.len());
+
+        for (coord, value) in values.iter() {
+            *next.entry(*coord).or_default() += value * (1.0 - FIELD_DIFFUSION_RATE);
+
+            let share = value * FIELD_DIFFUSION_RATE / 6.0;
+            for neighbor in coord.neighbors() {
+                *next.entry(neighbor).or_default() += share;
+            }
+        }
+
+        next.retain(|_, value: &mut Float| {
+            *value *= 1.0 - FIELD_DECAY;
+
+            value.abs() >= FIELD_EPSILON
+        });
+
+        *values = next;
+    }
+
+    fields.retain(|_, values| !values.is_empty());
+}
+
+pub(crate) fn register_field_stuff(engine: &mut Engine) {
+    // scripts have no floats, so they see the fields rounded
+    engine
+        .register_fn("field_at", |field: Id, coord: TileCoord| -> INT {
+            field_value(field, coord).round() as INT
+        })
+        .register_fn(
+            "add_to_field",
+            |field: Id, coord: TileCoord, amount: INT| add_to_field(field, coord, amount as Float),
+        )
+        .register_fn("set_field", |field: Id, coord: TileCoord, value: INT| {
+            set_field(field, coord, value as Float)
+        });
+}
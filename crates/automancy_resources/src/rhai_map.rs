@@ -0,0 +1,246 @@
+use automancy_defs::{
+    coord::{TileCoord, TileUnit},
+    id::Id,
+    math::Float,
+    path::{self, PathSearch},
+    spatial::SpatialIndex,
+};
+use hashbrown::HashMap;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, FnPtr, NativeCallContext};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex, RwLock};
+
+/// The most paths kept cached. The cache is emptied when it is full.
+const MAX_CACHED_PATHS: usize = 256;
+
+// scripts run on the tile entities' threads, so they query a copy of the map kept in sync by the game
+static MAP_TILES: LazyLock<RwLock<SpatialIndex<Id>>> = LazyLock::new(Default::default);
+/// The paths found along tiles, kept until a tile their search looked at changes.
+static PATH_CACHE: LazyLock<Mutex<HashMap<(TileCoord, TileCoord), PathSearch>>> =
+    LazyLock::new(Default::default);
+/// Bumped whenever the tiles change, so that a search that ran meanwhile isn't cached.
+static PATH_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Replaces the tiles scripts can see, e.g. when a map is loaded.
+pub fn set_map_tiles(tiles: impl IntoIterator<Item = (TileCoord, Id)>) {
+    *MAP_TILES.write().unwrap() = tiles.into_iter().collect();
+
+    let mut cache = PATH_CACHE.lock().unwrap();
+    PATH_GENERATION.fetch_add(1, Ordering::AcqRel);
+    cache.clear();
+}
+
+/// Updates the tile scripts can see at the coordinate. `None` removes it.
+pub fn update_map_tile(coord: TileCoord, id: Option<Id>) {
+    {
+        let mut tiles = MAP_TILES.write().unwrap();
+
+        if let Some(id) = id {
+            tiles.insert(coord, id);
+        } else {
+            tiles.remove(&coord);
+        }
+    }
+
+    let mut cache = PATH_CACHE.lock().unwrap();
+    PATH_GENERATION.fetch_add(1, Ordering::AcqRel);
+    cache.retain(|_, search| !search.visited.contains(&coord));
+}
+
+/// Gets the sides of the tile at the coordinate that have a neighbor it connects to, out of the given sides, as bits in
+/// the order of [TileCoord::neighbors].
+pub fn neighbor_mask(coord: TileCoord, sides: u8, connects: impl Fn(Id) -> bool) -> u8 {
+    let tiles = MAP_TILES.read().unwrap();
+
+    coord
+        .neighbors()
+        .into_iter()
+        .enumerate()
+        .filter(|(side, _)| sides & (1 << side) != 0)
+        .filter(|(_, neighbor)| tiles.get(neighbor).is_some_and(|id| connects(*id)))
+        .fold(0, |mask, (side, _)| mask | (1 << side))
+}
+
+/// Finds the shortest path from one tile to another that only steps on tiles, like the route items take between them.
+/// The paths are cached until the tiles around them change.
+pub fn find_path(from: TileCoord, to: TileCoord) -> Option<Vec<TileCoord>> {
+    let generation = {
+        let cache = PATH_CACHE.lock().unwrap();
+
+        if let Some(search) = cache.get(&(from, to)) {
+            return search.path.clone();
+        }
+
+        PATH_GENERATION.load(Ordering::Acquire)
+    };
+
+    // searched without holding the cache, so that other threads can use it meanwhile
+    let search = {
+        let tiles = MAP_TILES.read().unwrap();
+
+        path::find_path(from, to, |coord| {
+            (coord == to || tiles.contains_key(&coord)).then_some(1.0)
+        })
+    };
+    let path = search.path.clone();
+
+    let mut cache = PATH_CACHE.lock().unwrap();
+
+    // a tile changed during the search, which may have made it stale
+    if PATH_GENERATION.load(Ordering::Acquire) != generation {
+        return path;
+    }
+
+    if cache.len() >= MAX_CACHED_PATHS {
+        cache.clear();
+    }
+    cache.insert((from, to), search);
+
+    path
+}
+
+fn path_or_unit(v: Option<Vec<TileCoord>>) -> Dynamic {
+    v.map(|path| Dynamic::from_array(path.into_iter().map(Dynamic::from).collect::<Array>()))
+        .unwrap_or(Dynamic::UNIT)
+}
+
+fn coord_or_unit(v: Option<TileCoord>) -> Dynamic {
+    v.map(Dynamic::from).unwrap_or(Dynamic::UNIT)
+}
+
+pub(crate) fn register_map_stuff(engine: &mut Engine) {
+    engine
+        .register_fn("tile_at", |coord: TileCoord| -> Dynamic {
+            MAP_TILES
+                .read()
+                .unwrap()
+                .get(&coord)
+                .copied()
+                .map(Dynamic::from)
+                .unwrap_or(Dynamic::UNIT)
+        })
+        .register_fn(
+            "tiles_in_range",
+            |center: TileCoord, radius: TileUnit| -> HashMap<TileCoord, Id> {
+                MAP_TILES
+                    .read()
+                    .unwrap()
+                    .in_range(center, radius.max(0) as u32)
+                    .map(|(coord, id)| (coord, *id))
+                    .collect()
+            },
+        )
+        .register_fn(
+            "nearest_tile",
+            |center: TileCoord, radius: TileUnit, id: Id| -> Dynamic {
+                coord_or_unit(
+                    MAP_TILES
+                        .read()
+                        .unwrap()
+                        .nearest(center, radius.max(0) as u32, |_, v| *v == id)
+                        .map(|(coord, _)| coord),
+                )
+            },
+        )
+        .register_fn(
+            "nearest_tile",
+            |ctx: NativeCallContext,
+             center: TileCoord,
+             radius: TileUnit,
+             pred: FnPtr|
+             -> Result<Dynamic, Box<EvalAltResult>> {
+                // copied out first, since the predicate can query the map itself
+                let mut candidates = MAP_TILES
+                    .read()
+                    .unwrap()
+                    .in_range(center, radius.max(0) as u32)
+                    .map(|(coord, id)| (coord, *id))
+                    .collect::<Vec<_>>();
+                candidates.sort_by_key(|(coord, _)| {
+                    (center.unsigned_distance_to(**coord), coord.x, coord.y)
+                });
+
+                for (coord, id) in candidates {
+                    if pred.call_within_context::<bool>(&ctx, (coord, id))? {
+                        return Ok(Dynamic::from(coord));
+                    }
+                }
+
+                Ok(Dynamic::UNIT)
+            },
+        )
+        .register_fn(
+            "first_along_line",
+            |from: TileCoord, to: TileCoord| -> Dynamic {
+                coord_or_unit(
+                    MAP_TILES
+                        .read()
+                        .unwrap()
+                        .first_along_line(from, to)
+                        .map(|(coord, _)| coord),
+                )
+            },
+        )
+        .register_fn("line_of_sight", |from: TileCoord, to: TileCoord| -> bool {
+            MAP_TILES.read().unwrap().line_of_sight(from, to)
+        })
+        .register_fn("find_path", |from: TileCoord, to: TileCoord| -> Dynamic {
+            path_or_unit(find_path(from, to))
+        })
+        .register_fn(
+            "find_path",
+            |ctx: NativeCallContext,
+             from: TileCoord,
+             to: TileCoord,
+             cost: FnPtr|
+             -> Result<Dynamic, Box<EvalAltResult>> {
+                let mut err = None;
+
+                let search = path::find_path(from, to, |coord| {
+                    if err.is_some() {
+                        return None;
+                    }
+
+                    // looked up first, since the cost function can query the map itself
+                    let id = MAP_TILES
+                        .read()
+                        .unwrap()
+                        .get(&coord)
+                        .copied()
+                        .map(Dynamic::from)
+                        .unwrap_or(Dynamic::UNIT);
+
+                    let v = match cost.call_within_context::<Dynamic>(&ctx, (coord, id)) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            err = Some(e);
+                            return None;
+                        }
+                    };
+
+                    if v.is_unit() {
+                        None
+                    } else if let Ok(v) = v.as_int() {
+                        Some(v as Float)
+                    } else if v.is::<Float>() {
+                        // scripts have no floats of their own, but may pass on one they were given
+                        Some(v.cast::<Float>())
+                    } else {
+                        err = Some(
+                            format!(
+                                "the cost of stepping on {coord:?} must be a number or (), not {}",
+                                v.type_name()
+                            )
+                            .into(),
+                        );
+                        None
+                    }
+                });
+
+                match err {
+                    Some(err) => Err(err),
+                    None => Ok(path_or_unit(search.path)),
+                }
+            },
+        );
+}
@@ -0,0 +1,88 @@
+use hashbrown::HashMap;
+use rhai::{
+    module_resolvers::ModuleResolver, Dynamic, Engine, EvalAltResult, Module, Position, Scope,
+    Shared, AST,
+};
+use std::cell::RefCell;
+use std::sync::{Arc, RwLock};
+
+thread_local! {
+    /// The libraries being evaluated on this thread, innermost last. A library imports its own imports while it's
+    /// evaluated, on the same thread, so a path that is already in here is an import cycle. It is kept per thread, as
+    /// the tiles run their scripts on many threads at once.
+    static RESOLVING: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+#[derive(Default)]
+struct Inner {
+    sources: RwLock<HashMap<String, AST>>,
+    modules: RwLock<HashMap<String, Shared<Module>>>,
+}
+
+/// Resolves `import "namespace/lib/name"` against the library functions of the loaded packs.
+/// Each library is evaluated once and shared between importers, and import cycles are reported as errors.
+#[derive(Clone, Default)]
+pub struct PackModuleResolver(Arc<Inner>);
+
+impl PackModuleResolver {
+    pub fn import_path(namespace: &str, name: &str) -> String {
+        format!("{namespace}/lib/{name}")
+    }
+
+    pub fn constants_path(namespace: &str) -> String {
+        format!("{namespace}/constants")
+    }
+
+    /// Adds a compiled library, so that it can be imported from its path.
+    pub fn add_source(&self, path: String, ast: AST) {
+        self.0.sources.write().unwrap().insert(path, ast);
+    }
+
+    /// Adds an already built module, so that it can be imported from its path.
+    pub fn add_module(&self, path: String, module: Shared<Module>) {
+        self.0.modules.write().unwrap().insert(path, module);
+    }
+}
+
+impl ModuleResolver for PackModuleResolver {
+    fn resolve(
+        &self,
+        engine: &Engine,
+        _source: Option<&str>,
+        path: &str,
+        pos: Position,
+    ) -> Result<Shared<Module>, Box<EvalAltResult>> {
+        if let Some(module) = self.0.modules.read().unwrap().get(path) {
+            return Ok(module.clone());
+        }
+
+        let Some(ast) = self.0.sources.read().unwrap().get(path).cloned() else {
+            return Err(EvalAltResult::ErrorModuleNotFound(path.to_string(), pos).into());
+        };
+
+        if RESOLVING.with_borrow(|v| v.iter().any(|v| v == path)) {
+            return Err(EvalAltResult::ErrorInModule(
+                path.to_string(),
+                EvalAltResult::ErrorRuntime(Dynamic::from("import cycle detected"), pos).into(),
+                pos,
+            )
+            .into());
+        }
+
+        RESOLVING.with_borrow_mut(|v| v.push(path.to_string()));
+        let result = Module::eval_ast_as_new(Scope::new(), &ast, engine);
+        RESOLVING.with_borrow_mut(|v| v.pop());
+
+        let module: Shared<Module> = result
+            .map_err(|err| EvalAltResult::ErrorInModule(path.to_string(), err, pos))?
+            .into();
+
+        self.0
+            .modules
+            .write()
+            .unwrap()
+            .insert(path.to_string(), module.clone());
+
+        Ok(module)
+    }
+}
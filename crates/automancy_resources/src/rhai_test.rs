@@ -0,0 +1,290 @@
+use crate::data::DataMap;
+use crate::{rhai_call_options, rhai_field, rhai_map, ResourceManager};
+use automancy_defs::{
+    coord::TileCoord,
+    id::{Id, IdRaw, TileId},
+};
+use hashbrown::HashMap;
+use rhai::{
+    Dynamic, Engine, EvalAltResult, ImmutableString, Module, NativeCallContext, Scope, AST,
+};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use walkdir::WalkDir;
+
+/// What the names of script test files end with. They are skipped when loading functions.
+pub static SCRIPT_TEST_SUFFIX: &str = ".test.rhai";
+/// What the names of the functions in a script test file that are run as tests start with.
+pub static SCRIPT_TEST_PREFIX: &str = "test_";
+
+/// Whether the engine is made with the test API. It's left out of the engine games run on, as `Test::map` replaces the
+/// map every script sees.
+static SCRIPT_TESTS_AVAILABLE: AtomicBool = AtomicBool::new(false);
+
+/// Makes the test API available, before the resources are loaded, when the game is started with `--test-scripts`.
+pub fn make_script_tests_available() {
+    SCRIPT_TESTS_AVAILABLE.store(true, Ordering::Relaxed);
+}
+
+/// Checks if the file is a script test, rather than a function.
+pub fn is_script_test(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|v| v.to_str())
+        .is_some_and(|v| v.ends_with(SCRIPT_TEST_SUFFIX))
+}
+
+/// The tiles mocked tiles can be made of, with their function and setup data.
+#[derive(Default)]
+struct TestTiles(HashMap<TileId, (Option<AST>, DataMap)>);
+
+thread_local! {
+    // tests run one after another on the thread that runs them, which sets this up first
+    static TEST_TILES: RefCell<TestTiles> = RefCell::default();
+}
+
+/// A tile outside of any map, whose function a test can call directly.
+#[derive(Debug, Clone)]
+pub struct MockTile {
+    pub id: TileId,
+    pub coord: TileCoord,
+    pub data: DataMap,
+}
+
+impl MockTile {
+    /// Calls a function of the tile's script like the game would, with the extra arguments in its input.
+    fn call(
+        &mut self,
+        ctx: &NativeCallContext,
+        function: &str,
+        args: rhai::Map,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        let (ast, setup) = TEST_TILES.with_borrow(|tiles| {
+            tiles
+                .0
+                .get(&self.id)
+                .map(|(ast, setup)| (ast.clone(), setup.clone()))
+                .ok_or_else(|| format!("there is no tile {:?}", self.id))
+        })?;
+        let ast = ast.ok_or_else(|| format!("the tile {:?} has no function", self.id))?;
+
+        let mut state = Dynamic::from(self.data.clone());
+
+        // random is fixed, so that tests give the same result every time
+        let mut input = rhai::Map::from([
+            ("coord".into(), Dynamic::from(self.coord)),
+            ("id".into(), Dynamic::from(self.id)),
+            ("random".into(), Dynamic::from_int(0)),
+            ("setup".into(), Dynamic::from(setup)),
+        ]);
+        input.extend(args);
+
+        let result = ctx.engine().call_fn_with_options::<Dynamic>(
+            rhai_call_options(&mut state),
+            &mut Scope::new(),
+            &ast,
+            function,
+            (input,),
+        );
+
+        self.data = state.cast::<DataMap>();
+
+        result
+    }
+}
+
+fn assert_eq(ctx: NativeCallContext, a: Dynamic, b: Dynamic) -> Result<(), Box<EvalAltResult>> {
+    if ctx.call_native_fn::<bool>("==", (a.clone(), b.clone()))? {
+        Ok(())
+    } else {
+        Err(format!("assertion failed: {a} == {b}").into())
+    }
+}
+
+/// Registers the test API, if it was made available.
+pub(crate) fn register_test_stuff(engine: &mut Engine) {
+    if !SCRIPT_TESTS_AVAILABLE.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let mut module = Module::new();
+
+    module.set_native_fn("tile", |id: Id, coord: TileCoord| {
+        Ok(MockTile {
+            id: TileId(id),
+            coord,
+            data: DataMap::default(),
+        })
+    });
+    module.set_native_fn("map", |tiles: HashMap<TileCoord, Id>| {
+        rhai_map::set_map_tiles(tiles);
+
+        Ok(())
+    });
+
+    engine.register_static_module("Test", module.into());
+
+    engine
+        .register_fn("assert", |v: bool| -> Result<(), Box<EvalAltResult>> {
+            if v {
+                Ok(())
+            } else {
+                Err("assertion failed".into())
+            }
+        })
+        .register_fn(
+            "assert",
+            |v: bool, message: &str| -> Result<(), Box<EvalAltResult>> {
+                if v {
+                    Ok(())
+                } else {
+                    Err(format!("assertion failed: {message}").into())
+                }
+            },
+        )
+        .register_fn("assert_eq", assert_eq)
+        .register_type_with_name::<MockTile>("MockTile")
+        .register_get("coord", |v: &mut MockTile| -> TileCoord { v.coord })
+        .register_get_set(
+            "data",
+            |v: &mut MockTile| -> DataMap { v.data.clone() },
+            |v: &mut MockTile, data: DataMap| v.data = data,
+        )
+        .register_fn(
+            "call",
+            |ctx: NativeCallContext, v: &mut MockTile, function: &str, args: rhai::Map| {
+                v.call(&ctx, function, args)
+            },
+        )
+        .register_fn(
+            "call",
+            |ctx: NativeCallContext, v: &mut MockTile, function: &str| {
+                v.call(&ctx, function, rhai::Map::new())
+            },
+        );
+}
+
+/// The outcome of one test function.
+#[derive(Debug, Clone)]
+pub struct ScriptTestResult {
+    pub file: PathBuf,
+    pub name: String,
+    /// why the test failed, if it did
+    pub error: Option<String>,
+}
+
+impl ResourceManager {
+    /// Finds the script tests of every pack in the resources folder, with the namespace of the pack each is in.
+    pub fn find_script_tests(&self, resources: &Path) -> Vec<(String, PathBuf)> {
+        let mut tests = self
+            .namespaces
+            .iter()
+            .flat_map(|namespace| {
+                WalkDir::new(resources.join(namespace))
+                    .follow_links(false)
+                    .into_iter()
+                    .flatten()
+                    .map(|v| v.path().to_path_buf())
+                    .filter(|v| is_script_test(v))
+                    .map(|v| (namespace.clone(), v))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        tests.sort();
+
+        tests
+    }
+
+    /// Compiles a script test with the IDs it asks for in `id_deps`, like a function.
+    fn compile_script_test(&self, file: &Path, namespace: &str) -> Result<AST, String> {
+        let pre_ast = self
+            .engine
+            .compile_file(file.to_path_buf())
+            .map_err(|e| e.to_string())?;
+
+        let mut scope = Scope::new();
+
+        // tests don't have to depend on any IDs
+        if let Ok(id_deps) =
+            self.engine
+                .call_fn::<rhai::Array>(&mut Scope::new(), &pre_ast, "id_deps", ())
+        {
+            for id_dep in id_deps {
+                let v = id_dep.cast::<rhai::Array>();
+
+                let raw = v[0].clone().cast::<ImmutableString>();
+                let key = v[1].clone().cast::<ImmutableString>();
+
+                let id = IdRaw::parse(raw.as_str(), Some(namespace))
+                    .and_then(|v| v.try_to_id(&self.interner))
+                    .ok_or_else(|| format!("the ID {raw} isn't loaded"))?;
+
+                scope.push_constant(key.as_str(), id);
+            }
+        }
+
+        self.engine
+            .compile_file_with_scope(&scope, file.to_path_buf())
+            .map_err(|e| e.to_string())
+    }
+
+    /// Runs every test function of the script test. Each starts with an empty map and empty fields.
+    pub fn run_script_test(&self, file: &Path, namespace: &str) -> Vec<ScriptTestResult> {
+        TEST_TILES.with_borrow_mut(|tiles| {
+            if tiles.0.is_empty() {
+                tiles.0 = self
+                    .registry
+                    .tiles
+                    .iter()
+                    .map(|(id, tile)| {
+                        let ast = tile
+                            .function
+                            .and_then(|v| self.functions.get(&v))
+                            .map(|(ast, _)| ast.clone());
+
+                        (*id, (ast, tile.data.clone()))
+                    })
+                    .collect();
+            }
+        });
+
+        let ast = match self.compile_script_test(file, namespace) {
+            Ok(ast) => ast,
+            Err(error) => {
+                return vec![ScriptTestResult {
+                    file: file.to_path_buf(),
+                    name: "<compile>".to_string(),
+                    error: Some(error),
+                }];
+            }
+        };
+
+        let mut names = ast
+            .iter_functions()
+            .filter(|f| f.name.starts_with(SCRIPT_TEST_PREFIX) && f.params.is_empty())
+            .map(|f| f.name.to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+
+        names
+            .into_iter()
+            .map(|name| {
+                rhai_map::set_map_tiles([]);
+                rhai_field::clear_fields();
+
+                let error = self
+                    .engine
+                    .call_fn::<Dynamic>(&mut Scope::new(), &ast, &name, ())
+                    .err()
+                    .map(|e| e.to_string());
+
+                ScriptTestResult {
+                    file: file.to_path_buf(),
+                    name,
+                    error,
+                }
+            })
+            .collect()
+    }
+}
@@ -1,31 +1,52 @@
 use rhai::{exported_module, Engine};
 
 mod tile_stuff {
-    use automancy_defs::{coord::TileCoord, id::Id, stack::ItemStack};
+    use automancy_defs::{
+        coord::TileCoord,
+        id::Id,
+        stack::{ItemAmount, ItemStack},
+    };
 
     use rhai::plugin::*;
     use rhai::Module;
 
-    use crate::types::function::{OnFailAction, TileResult, TileTransactionResult};
+    use crate::types::function::{
+        AgentResult, MapOutcome, OnFailAction, TaskStep, TileResult, TileTransactionResult,
+    };
+
+    /// Gets the item stacks in the array, or an error if anything else is in it.
+    fn item_stacks(stacks: rhai::Array) -> Result<Vec<ItemStack>, Box<EvalAltResult>> {
+        stacks
+            .into_iter()
+            .map(|v| {
+                let type_name = v.type_name();
+
+                v.try_cast::<ItemStack>()
+                    .ok_or_else(|| format!("expected an ItemStack, got {type_name}").into())
+            })
+            .collect()
+    }
 
     #[allow(non_snake_case)]
     #[export_module]
     pub mod tile_result {
+        use super::item_stacks;
         use automancy_defs::id::TileId;
         use rhai::Array;
 
+        #[rhai_fn(return_raw)]
         pub fn MakeTransaction(
             coord: TileCoord,
             source_id: TileId,
             source_coord: TileCoord,
             stacks: Array,
-        ) -> TileResult {
-            TileResult::MakeTransaction {
+        ) -> Result<TileResult, Box<EvalAltResult>> {
+            Ok(TileResult::MakeTransaction {
                 coord,
                 source_id,
                 source_coord,
-                stacks: stacks.into_iter().map(Dynamic::cast::<ItemStack>).collect(),
-            }
+                stacks: item_stacks(stacks)?,
+            })
         }
         pub fn MakeExtractRequest(
             coord: TileCoord,
@@ -40,6 +61,72 @@ mod tile_stuff {
                 on_fail_action,
             }
         }
+        #[rhai_fn(return_raw)]
+        pub fn SendItems(channel: Id, stacks: Array) -> Result<TileResult, Box<EvalAltResult>> {
+            Ok(TileResult::SendItems {
+                channel,
+                stacks: item_stacks(stacks)?,
+            })
+        }
+        pub fn RequestItems(channel: Id, max: ItemAmount) -> TileResult {
+            TileResult::RequestItems { channel, max }
+        }
+        pub fn PlaceTile(coord: TileCoord, id: TileId) -> TileResult {
+            TileResult::PlaceTile { coord, id }
+        }
+        pub fn RemoveTile(coord: TileCoord) -> TileResult {
+            TileResult::RemoveTile { coord }
+        }
+        pub fn Sleep() -> TileResult {
+            TileResult::Sleep { ticks: None }
+        }
+        pub fn StartTask(steps: Array, locals: rhai::Map) -> TileResult {
+            TileResult::StartTask {
+                steps: steps.into_iter().map(|v| v.to_string()).collect(),
+                locals,
+            }
+        }
+        pub fn SleepFor(ticks: rhai::INT) -> TileResult {
+            TileResult::Sleep {
+                ticks: Some(ticks.max(1) as u32),
+            }
+        }
+        pub fn SpawnAgent(id: Id, coord: TileCoord) -> TileResult {
+            TileResult::SpawnAgent { id, coord }
+        }
+    }
+
+    #[allow(non_snake_case)]
+    #[export_module]
+    pub mod agent_result {
+        pub fn MoveTo(coord: TileCoord) -> AgentResult {
+            AgentResult::MoveTo(coord)
+        }
+        pub fn Despawn() -> AgentResult {
+            AgentResult::Despawn
+        }
+    }
+
+    #[allow(non_snake_case)]
+    #[export_module]
+    pub mod map_outcome {
+        pub fn Victory(message: &str) -> MapOutcome {
+            MapOutcome::Victory {
+                message: message.to_string(),
+                next_map: None,
+            }
+        }
+        pub fn VictoryThen(message: &str, next_map: &str) -> MapOutcome {
+            MapOutcome::Victory {
+                message: message.to_string(),
+                next_map: Some(next_map.to_string()),
+            }
+        }
+        pub fn Defeat(message: &str) -> MapOutcome {
+            MapOutcome::Defeat {
+                message: message.to_string(),
+            }
+        }
     }
 
     #[allow(non_snake_case)]
@@ -92,6 +179,23 @@ mod tile_stuff {
         }
     }
 
+    #[allow(non_snake_case)]
+    #[export_module]
+    pub mod task_step {
+        pub fn Next(locals: rhai::Map) -> TaskStep {
+            TaskStep::Next(locals)
+        }
+        pub fn Wait(ticks: rhai::INT, locals: rhai::Map) -> TaskStep {
+            TaskStep::Wait(ticks.max(0) as u32, locals)
+        }
+        pub fn Repeat(locals: rhai::Map) -> TaskStep {
+            TaskStep::Repeat(locals)
+        }
+        pub fn Done() -> TaskStep {
+            TaskStep::Done
+        }
+    }
+
     #[allow(non_snake_case)]
     #[export_module]
     pub mod on_fail_action {
@@ -116,6 +220,9 @@ pub(crate) fn register_tile_stuff(engine: &mut Engine) {
         "TransResult",
         exported_module!(tile_stuff::tile_trans_result).into(),
     );
+    engine.register_static_module("Task", exported_module!(tile_stuff::task_step).into());
+    engine.register_static_module("Agent", exported_module!(tile_stuff::agent_result).into());
+    engine.register_static_module("Outcome", exported_module!(tile_stuff::map_outcome).into());
     engine.register_static_module(
         "OnFailAction",
         exported_module!(tile_stuff::on_fail_action).into(),
@@ -48,10 +48,19 @@ pub enum RhaiUiUnit {
         id: Id,
         empty_text: Id,
     },
+    /// a two-pane window for moving items between the player's inventory and the tile's
+    Storage {
+        id: Id,
+    },
     Linkage {
         id: Id,
         button_text: Id,
     },
+    /// a button that starts editing the region stored in the data in-world
+    Region {
+        id: Id,
+        button_text: Id,
+    },
 }
 
 #[allow(non_snake_case)]
@@ -109,9 +118,15 @@ mod ui {
     pub fn Inventory(id: Id, empty_text: Id) -> RhaiUiUnit {
         RhaiUiUnit::Inventory { id, empty_text }
     }
+    pub fn Storage(id: Id) -> RhaiUiUnit {
+        RhaiUiUnit::Storage { id }
+    }
     pub fn Linkage(id: Id, button_text: Id) -> RhaiUiUnit {
         RhaiUiUnit::Linkage { id, button_text }
     }
+    pub fn Region(id: Id, button_text: Id) -> RhaiUiUnit {
+        RhaiUiUnit::Region { id, button_text }
+    }
 }
 
 pub(crate) fn register_ui_stuff(engine: &mut Engine) {
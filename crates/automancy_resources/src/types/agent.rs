@@ -0,0 +1,69 @@
+use crate::data::{DataMap, DataMapRaw};
+use crate::{load_recursively, ResourceManager, RON_EXT};
+use automancy_defs::id::{Id, ModelId};
+use automancy_defs::math::Float;
+use serde::Deserialize;
+use std::ffi::OsStr;
+use std::path::Path;
+
+/// A kind of mobile agent, like a delivery drone, which moves freely over the map instead of sitting on a tile.
+#[derive(Debug, Clone)]
+pub struct AgentDef {
+    pub id: Id,
+    pub model: ModelId,
+    /// how far the agent moves each tick, in tiles
+    pub speed: Float,
+    /// the function whose AGENT_TICK_FUNCTION decides where the agent goes next
+    pub function: Option<Id>,
+    pub data: DataMap,
+}
+
+#[derive(Debug, Deserialize)]
+struct Raw {
+    pub id: String,
+    pub model: String,
+    pub speed: Float,
+    #[serde(default)]
+    pub function: Option<String>,
+    #[serde(default)]
+    pub data: DataMapRaw,
+}
+
+impl ResourceManager {
+    fn load_agent(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
+        log::info!("Loading agent at {file:?}");
+
+        let v = self.read_ron::<Raw>(file, namespace)?;
+
+        let id = Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap();
+        let model = ModelId(Id::parse(&v.model, &mut self.interner, Some(namespace)).unwrap());
+        let function = v
+            .function
+            .map(|v| Id::parse(&v, &mut self.interner, Some(namespace)).unwrap());
+
+        let data = v.data.intern_to_data(&mut self.interner, Some(namespace));
+
+        self.registry.agents.insert(
+            id,
+            AgentDef {
+                id,
+                model,
+                speed: v.speed.max(0.0),
+                function,
+                data,
+            },
+        );
+
+        Ok(())
+    }
+
+    pub fn load_agents(&mut self, dir: &Path, namespace: &str) -> anyhow::Result<()> {
+        let agents = dir.join("agents");
+
+        for file in load_recursively(&agents, OsStr::new(RON_EXT)) {
+            self.load_agent(&file, namespace)?;
+        }
+
+        Ok(())
+    }
+}
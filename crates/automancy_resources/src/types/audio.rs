@@ -1,41 +1,369 @@
-use crate::{LoadResourceError, ResourceManager, AUDIO_EXT, COULD_NOT_GET_FILE_STEM};
-use automancy_defs::kira::sound::static_sound::StaticSoundData;
+use crate::{
+    load_recursively, LoadResourceError, ResourceManager, AUDIO_EXT, COULD_NOT_GET_FILE_STEM,
+    RON_EXT,
+};
+use automancy_defs::kira::{
+    sound::{static_sound::StaticSoundData, PlaybackRate},
+    Volume,
+};
+use hashbrown::HashMap;
+use rand::{seq::SliceRandom, thread_rng, Rng};
+use serde::Deserialize;
 use std::ffi::OsStr;
 use std::fs::read_dir;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+/// The audio files of all namespaces. Only their paths are kept at load, and each is decoded the first time it is
+/// played, then kept decoded.
+#[derive(Debug, Default)]
+pub struct AudioLibrary {
+    paths: HashMap<String, PathBuf>,
+    /// `None` is kept for the files that couldn't be decoded, so that they aren't tried again.
+    decoded: RwLock<HashMap<String, Option<StaticSoundData>>>,
+}
+
+impl AudioLibrary {
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.paths.contains_key(name)
+    }
+
+    /// Gets the number of audio files that are decoded in memory.
+    pub fn decoded_len(&self) -> usize {
+        self.decoded
+            .read()
+            .unwrap()
+            .values()
+            .filter(|v| v.is_some())
+            .count()
+    }
+
+    /// Gets the decoded audio file, decoding it now if it wasn't yet.
+    pub fn get(&self, name: &str) -> Option<StaticSoundData> {
+        if let Some(data) = self.decoded.read().unwrap().get(name) {
+            return data.clone();
+        }
+
+        let path = self.paths.get(name)?;
+
+        let data = match StaticSoundData::from_file(path) {
+            Ok(data) => {
+                log::info!("Decoded audio {name}");
+                Some(data)
+            }
+            Err(err) => {
+                log::error!("Could not decode audio at {path:?}: {err}");
+                None
+            }
+        };
+
+        self.decoded
+            .write()
+            .unwrap()
+            .insert(name.to_string(), data.clone());
+
+        data
+    }
+
+    /// Decodes the audio files on another thread, so that they are ready before they are first played.
+    pub fn decode_in_background(self: &Arc<Self>, names: Vec<String>) {
+        let this = self.clone();
+
+        thread::spawn(move || {
+            for name in names {
+                this.get(&name);
+            }
+        });
+    }
+}
+
+/// One of the audio files a sound can play, and how much its volume and pitch vary between plays.
+#[derive(Debug, Clone)]
+pub struct SoundVariant {
+    /// the name of the audio file
+    pub file: String,
+    pub weight: f64,
+    pub volume: (f64, f64),
+    pub pitch: (f64, f64),
+}
+
+/// How the volume of a sound falls off with its distance from the camera.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum Rolloff {
+    /// the sound stays at full volume until the max distance
+    None,
+    /// the volume falls evenly to nothing at the max distance
+    Linear,
+    /// the volume falls quickly near the sound, then slowly toward the max distance
+    Quadratic,
+    /// the volume halves every time the distance doubles past the reference distance, and is cut off at the max distance
+    Inverse(f64),
+}
+
+/// Spatialization settings shared by a category of sounds.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct SoundCategory {
+    /// how far from the camera, in world units, the sound can be heard
+    pub max_distance: f64,
+    pub rolloff: Rolloff,
+}
+
+impl SoundCategory {
+    /// Gets how much of its volume a sound keeps at the distance.
+    pub fn gain(&self, distance: f64) -> f64 {
+        if distance >= self.max_distance {
+            return 0.0;
+        }
+
+        let t = (distance / self.max_distance).max(0.0);
+
+        match self.rolloff {
+            Rolloff::None => 1.0,
+            Rolloff::Linear => 1.0 - t,
+            Rolloff::Quadratic => (1.0 - t).powi(2),
+            Rolloff::Inverse(reference) => {
+                let reference = reference.max(f64::EPSILON);
+
+                (reference / distance.max(reference)).min(1.0)
+            }
+        }
+    }
+}
+
+/// A sound, with the variants picked from each time it is played.
+#[derive(Debug, Clone)]
+pub struct SoundDef {
+    pub variants: Vec<SoundVariant>,
+    /// the category whose spatialization settings the sound is played with, when it comes from somewhere on the map
+    pub category: Option<String>,
+}
+
+impl SoundDef {
+    /// Picks a variant by weight, and gets its audio with a random volume and pitch in its ranges. The volume is
+    /// multiplied by `gain`.
+    pub fn pick(&self, audio: &AudioLibrary, gain: f64) -> Option<StaticSoundData> {
+        let mut rng = thread_rng();
+
+        let variant = self.variants.choose_weighted(&mut rng, |v| v.weight).ok()?;
+
+        let volume = rng.gen_range(variant.volume.0..=variant.volume.1);
+        let pitch = rng.gen_range(variant.pitch.0..=variant.pitch.1);
+
+        Some(
+            audio
+                .get(&variant.file)?
+                .volume(Volume::Amplitude(volume * gain))
+                .playback_rate(PlaybackRate::Factor(pitch)),
+        )
+    }
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+fn default_range() -> (f64, f64) {
+    (1.0, 1.0)
+}
+
+#[derive(Debug, Deserialize)]
+struct VariantRaw {
+    /// the name of the audio file, without the extension
+    pub file: String,
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    #[serde(default = "default_range")]
+    pub volume: (f64, f64),
+    #[serde(default = "default_range")]
+    pub pitch: (f64, f64),
+}
+
+#[derive(Debug, Deserialize)]
+struct Raw {
+    pub variants: Vec<VariantRaw>,
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+fn file_name(file: &Path) -> anyhow::Result<&str> {
+    Ok(file
+        .file_stem()
+        .ok_or_else(|| {
+            LoadResourceError::InvalidFileError(file.to_path_buf(), COULD_NOT_GET_FILE_STEM)
+        })?
+        .to_str()
+        .ok_or_else(|| LoadResourceError::OsStringError(file.to_path_buf()))?)
+}
+
+fn sorted_range((a, b): (f64, f64)) -> (f64, f64) {
+    (a.min(b), a.max(b))
+}
 
 impl ResourceManager {
-    pub fn load_audio(&mut self, dir: &Path) -> anyhow::Result<()> {
+    fn load_sound_category(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
+        log::info!("Loading sound category at {file:?}");
+
+        let category = self.read_ron::<SoundCategory>(file, namespace)?;
+        let name = file_name(file)?;
+
+        self.sound_categories.insert(name.into(), category);
+
+        Ok(())
+    }
+
+    fn load_sound(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
+        log::info!("Loading sound at {file:?}");
+
+        let v = self.read_ron::<Raw>(file, namespace)?;
+        let name = file_name(file)?;
+
+        let mut variants = Vec::new();
+        for variant in v.variants {
+            if !self.audio.contains(&variant.file) {
+                log::error!(
+                    "Sound {name} uses the audio file {}, which isn't loaded",
+                    variant.file
+                );
+                continue;
+            }
+
+            if variant.weight <= 0.0 {
+                continue;
+            }
+
+            variants.push(SoundVariant {
+                file: variant.file,
+                weight: variant.weight,
+                volume: sorted_range(variant.volume),
+                pitch: sorted_range(variant.pitch),
+            });
+        }
+
+        if variants.is_empty() {
+            log::error!("Sound {name} has no playable variants, skipping");
+            return Ok(());
+        }
+
+        if let Some(category) = v
+            .category
+            .as_ref()
+            .filter(|v| !self.sound_categories.contains_key(*v))
+        {
+            log::warn!("Sound {name} is in the unknown category {category}, it will be heard from anywhere");
+        }
+
+        self.sounds.insert(
+            name.into(),
+            SoundDef {
+                variants,
+                category: v.category,
+            },
+        );
+
+        log::info!("Registered sound with name {name}");
+
+        Ok(())
+    }
+
+    pub fn load_audio(&mut self, dir: &Path, namespace: &str) -> anyhow::Result<()> {
         let audio = dir.join("audio");
 
+        for file in load_recursively(&audio.join("categories"), OsStr::new(RON_EXT)) {
+            self.load_sound_category(&file, namespace)?;
+        }
+
         if let Ok(audio) = read_dir(audio) {
-            for file in audio
+            let files = audio
                 .into_iter()
                 .flatten()
                 .map(|v| v.path())
+                .collect::<Vec<PathBuf>>();
+
+            // the audio library is only shared once all resources are loaded
+            let library = Arc::get_mut(&mut self.audio).unwrap();
+
+            for file in files
+                .iter()
                 .filter(|v| v.extension() == Some(OsStr::new(AUDIO_EXT)))
             {
-                log::info!("Loading audio at {file:?}");
+                let name = file_name(file)?;
 
-                if let Ok(audio) = StaticSoundData::from_file(&file) {
-                    let name = file
-                        .file_stem()
-                        .ok_or_else(|| {
-                            LoadResourceError::InvalidFileError(
-                                file.clone(),
-                                COULD_NOT_GET_FILE_STEM,
-                            )
-                        })?
-                        .to_str()
-                        .ok_or_else(|| LoadResourceError::OsStringError(file.clone()))?;
+                library.paths.insert(name.into(), file.clone());
 
-                    self.audio.insert(name.into(), audio);
+                log::info!("Registered audio with name {name}");
+            }
 
-                    log::info!("Registered audio with name {name}");
-                }
+            // sounds are loaded after the audio files, so that they can use the ones in the same folder
+            for file in files
+                .iter()
+                .filter(|v| v.extension() == Some(OsStr::new(RON_EXT)))
+            {
+                self.load_sound(file, namespace)?;
             }
         }
 
         Ok(())
     }
+
+    /// Gets the audio to play for a sound, picking one of its variants. An audio file with no sound defined for it
+    /// is played as is. Returns None if the audio couldn't be decoded.
+    ///
+    /// Panics if there is neither a sound nor an audio file with the name.
+    pub fn sound(&self, name: &str) -> Option<StaticSoundData> {
+        if let Some(sound) = self.sounds.get(name) {
+            return sound.pick(&self.audio, 1.0);
+        }
+
+        assert!(
+            self.audio.contains(name),
+            "no sound or audio file named {name}"
+        );
+
+        self.audio.get(name)
+    }
+
+    /// Starts decoding the audio files of the sounds on another thread, for sounds that should play without delay
+    /// the first time.
+    pub fn preload_sounds(&self, names: &[&str]) {
+        let files = names
+            .iter()
+            .flat_map(|name| match self.sounds.get(*name) {
+                Some(sound) => sound.variants.iter().map(|v| v.file.clone()).collect(),
+                None => vec![name.to_string()],
+            })
+            .collect();
+
+        self.audio.decode_in_background(files);
+    }
+
+    /// Gets the audio to play for a sound coming from `distance` away from the camera, quieted as set by its
+    /// category. Returns None if it is too far to be heard. Sounds with no category are heard at full volume.
+    ///
+    /// Panics if there is neither a sound nor an audio file with the name.
+    pub fn sound_at(&self, name: &str, distance: f64) -> Option<StaticSoundData> {
+        let Some(sound) = self.sounds.get(name) else {
+            return self.sound(name);
+        };
+
+        let gain = sound
+            .category
+            .as_ref()
+            .and_then(|v| self.sound_categories.get(v))
+            .map_or(1.0, |v| v.gain(distance));
+
+        if gain <= 0.0 {
+            return None;
+        }
+
+        sound.pick(&self.audio, gain)
+    }
 }
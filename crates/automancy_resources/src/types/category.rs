@@ -3,7 +3,6 @@ use automancy_defs::id::{Id, ModelId, TileId};
 use hashbrown::HashMap;
 use serde::Deserialize;
 use std::ffi::OsStr;
-use std::fs::read_to_string;
 use std::path::Path;
 
 use super::IconMode;
@@ -15,6 +14,8 @@ pub struct CategoryDef {
     pub icon: Id,
     pub icon_mode: IconMode,
     pub item: Option<Id>,
+    /// the category this one is nested in
+    pub parent: Option<Id>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -24,13 +25,15 @@ struct Raw {
     pub icon: String,
     pub icon_mode: IconMode,
     pub item: Option<String>,
+    #[serde(default)]
+    pub parent: Option<String>,
 }
 
 impl ResourceManager {
     fn load_category(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
         log::info!("Loading category at: {file:?}");
 
-        let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
+        let v = self.read_ron::<Raw>(file, namespace)?;
 
         let id = Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap();
         let ord = v.ord;
@@ -39,6 +42,9 @@ impl ResourceManager {
         let item = v
             .item
             .map(|v| Id::parse(&v, &mut self.interner, Some(namespace)).unwrap());
+        let parent = v
+            .parent
+            .map(|v| Id::parse(&v, &mut self.interner, Some(namespace)).unwrap());
 
         self.registry.categories.insert(
             id,
@@ -48,6 +54,7 @@ impl ResourceManager {
                 icon,
                 icon_mode,
                 item,
+                parent,
             },
         );
 
@@ -69,6 +76,29 @@ impl ResourceManager {
 
         ids.sort_by_key(|v| self.registry.categories[v].ord);
 
+        let mut categories_children = HashMap::new();
+
+        for id in &ids {
+            let category = &self.registry.categories[id];
+
+            if let Some(parent) = category.parent {
+                if self.registry.categories.contains_key(&parent) {
+                    categories_children
+                        .entry(parent)
+                        .or_insert_with(Vec::new)
+                        .push(*id);
+                } else {
+                    log::error!("Category {id:?} has a parent that does not exist: {parent:?}");
+                }
+            }
+        }
+
+        ids.retain(|id| {
+            self.registry.categories[id].parent.map_or(true, |parent| {
+                !self.registry.categories.contains_key(&parent)
+            })
+        });
+
         let mut categories_tiles_map = HashMap::new();
 
         for tile in self.registry.tiles.values() {
@@ -82,6 +112,38 @@ impl ResourceManager {
 
         self.ordered_categories = ids;
         self.registry.categories_tiles_map = categories_tiles_map;
+        self.registry.categories_children = categories_children;
+    }
+
+    /// Gets the subcategories of a category, in order.
+    pub fn get_category_children(&self, id: Id) -> &[Id] {
+        self.registry
+            .categories_children
+            .get(&id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Gets the chain of categories from the top level category down to the given one.
+    pub fn get_category_path(&self, id: Id) -> Vec<Id> {
+        let mut path = vec![id];
+
+        while let Some(parent) = self
+            .registry
+            .categories
+            .get(path.last().unwrap())
+            .and_then(|v| v.parent)
+        {
+            if path.contains(&parent) || !self.registry.categories.contains_key(&parent) {
+                break;
+            }
+
+            path.push(parent);
+        }
+
+        path.reverse();
+
+        path
     }
 
     pub fn get_tiles_by_category(&self, id: Id) -> Option<&Vec<TileId>> {
@@ -0,0 +1,149 @@
+use crate::rhai_module::PackModuleResolver;
+use crate::{expr::eval_expr, ResourceManager};
+use hashbrown::HashMap;
+use rhai::{Array, Dynamic, ImmutableString, Map, Module};
+use ron::{Number, Value};
+use serde::de::DeserializeOwned;
+use std::fs::read_to_string;
+use std::path::Path;
+
+/// The prefix of a string in a RON file that should be replaced by a constant.
+pub static CONST_REF_PREFIX: &str = "$const:";
+/// The prefix of a string in a RON file that should be replaced by the result of an arithmetic expression.
+pub static EXPR_PREFIX: &str = "$expr:";
+
+fn value_to_dynamic(value: &Value) -> Option<Dynamic> {
+    Some(match value {
+        Value::Bool(v) => Dynamic::from_bool(*v),
+        Value::Char(v) => Dynamic::from_char(*v),
+        Value::String(v) => Dynamic::from(ImmutableString::from(v.as_str())),
+        Value::Number(Number::Integer(v)) => Dynamic::from_int(i32::try_from(*v).ok()?),
+        Value::Number(Number::Float(_)) => return None,
+        Value::Option(v) => match v {
+            Some(v) => value_to_dynamic(v)?,
+            None => Dynamic::UNIT,
+        },
+        Value::Seq(v) => {
+            Dynamic::from_array(v.iter().map(value_to_dynamic).collect::<Option<Array>>()?)
+        }
+        Value::Map(v) => {
+            let mut map = Map::new();
+
+            for (key, value) in v.iter() {
+                let Value::String(key) = key else {
+                    return None;
+                };
+
+                map.insert(key.as_str().into(), value_to_dynamic(value)?);
+            }
+
+            Dynamic::from_map(map)
+        }
+        Value::Unit => Dynamic::UNIT,
+    })
+}
+
+impl ResourceManager {
+    /// Loads the `constants.ron` of a namespace, and exposes it to Rhai as a read-only module, importable as `"namespace/constants"`.
+    pub fn load_constants(&mut self, dir: &Path, namespace: &str) -> anyhow::Result<()> {
+        let file = dir.join("constants.ron");
+
+        if !file.exists() {
+            return Ok(());
+        }
+
+        log::info!("Loading constants at: {file:?}");
+
+        let v = ron::from_str::<HashMap<String, Value>>(&read_to_string(&file)?)?;
+
+        let mut module = Module::new();
+
+        for (name, value) in v {
+            match value_to_dynamic(&value) {
+                Some(dynamic) => {
+                    module.set_var(name.as_str(), dynamic);
+                }
+                None => {
+                    log::warn!("Constant {namespace}:{name} cannot be represented in Rhai, it will only be available to RON files.");
+                }
+            }
+
+            self.constants.insert(format!("{namespace}:{name}"), value);
+        }
+
+        self.module_resolver
+            .add_module(PackModuleResolver::constants_path(namespace), module.into());
+
+        Ok(())
+    }
+
+    fn get_constant(&self, name: &str, namespace: &str) -> Option<&Value> {
+        if name.contains(':') {
+            self.constants.get(name)
+        } else {
+            self.constants.get(&format!("{namespace}:{name}"))
+        }
+    }
+
+    /// Replaces every `"$const:name"` string in the source with the value of the constant.
+    /// The name is looked up in the given namespace, unless it is namespaced itself (`"$const:namespace:name"`).
+    ///
+    /// Also replaces every `"$expr:..."` string with the result of the arithmetic expression, e.g. `"$expr:64 * 3"`.
+    /// Expressions can refer to numeric constants by name, looked up the same way.
+    pub fn resolve_constants(&self, src: &str, namespace: &str) -> anyhow::Result<String> {
+        let mut result = String::with_capacity(src.len());
+        let mut rest = src;
+
+        while let Some(start) = rest.find("\"$") {
+            result.push_str(&rest[..start]);
+            rest = &rest[start + 1..];
+
+            let prefix = if rest.starts_with(CONST_REF_PREFIX) {
+                CONST_REF_PREFIX
+            } else if rest.starts_with(EXPR_PREFIX) {
+                EXPR_PREFIX
+            } else {
+                // just a string starting with '$'
+                result.push('"');
+                continue;
+            };
+            rest = &rest[prefix.len()..];
+
+            let Some(end) = rest.find('"') else {
+                anyhow::bail!("unterminated constant reference");
+            };
+
+            let body = &rest[..end];
+
+            if prefix == CONST_REF_PREFIX {
+                let Some(value) = self.get_constant(body, namespace) else {
+                    anyhow::bail!("the constant {body} is not defined in {namespace}");
+                };
+
+                result.push_str(&ron::to_string(value)?);
+            } else {
+                let value = eval_expr(body, |name| self.get_constant(name, namespace).cloned())
+                    .map_err(|err| anyhow::anyhow!("could not evaluate \"{body}\": {err}"))?;
+
+                result.push_str(&ron::to_string(&Value::from(value))?);
+            }
+
+            rest = &rest[end + 1..];
+        }
+
+        result.push_str(rest);
+
+        Ok(result)
+    }
+
+    /// Reads a RON file, resolving constant references before parsing it.
+    pub(crate) fn read_ron<T: DeserializeOwned>(
+        &self,
+        file: &Path,
+        namespace: &str,
+    ) -> anyhow::Result<T> {
+        let src = self.resolve_constants(&read_to_string(file)?, namespace)?;
+
+        Ok(ron::from_str::<T>(&src)?)
+    }
+}
@@ -0,0 +1,129 @@
+use crate::{load_recursively, ResourceManager, RON_EXT};
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::Id;
+use automancy_defs::math::Float;
+use serde::Deserialize;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::time::Duration;
+
+/// A step of a cutscene. The steps are played one after another.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CutsceneStep {
+    /// glides the camera over the tile, and to the zoom if there is one (from 0.05, closest, to 4.0)
+    Camera {
+        to: TileCoord,
+        zoom: Option<Float>,
+        duration: Duration,
+    },
+    /// shows a card with the GUI text of the ID
+    Text {
+        text: Id,
+        duration: Duration,
+    },
+    /// plays a sound, and goes on to the next step right away
+    Sound(String),
+    Wait(Duration),
+}
+
+impl CutsceneStep {
+    pub fn duration(&self) -> Duration {
+        match self {
+            CutsceneStep::Camera { duration, .. } => *duration,
+            CutsceneStep::Text { duration, .. } => *duration,
+            CutsceneStep::Sound(_) => Duration::ZERO,
+            CutsceneStep::Wait(duration) => *duration,
+        }
+    }
+}
+
+/// A sequence of camera moves, text cards and sounds, played with the player's input held back, like the tutorial intro.
+#[derive(Debug, Clone)]
+pub struct CutsceneDef {
+    pub id: Id,
+    pub steps: Vec<CutsceneStep>,
+    /// whether the player can skip it with the cancel key
+    pub skippable: bool,
+}
+
+/// The durations are in seconds.
+#[derive(Debug, Deserialize)]
+enum StepRaw {
+    Camera {
+        to: TileCoord,
+        #[serde(default)]
+        zoom: Option<Float>,
+        secs: Float,
+    },
+    Text {
+        text: String,
+        secs: Float,
+    },
+    Sound(String),
+    Wait(Float),
+}
+
+#[derive(Debug, Deserialize)]
+struct Raw {
+    pub id: String,
+    pub steps: Vec<StepRaw>,
+    #[serde(default = "skippable_default")]
+    pub skippable: bool,
+}
+
+fn skippable_default() -> bool {
+    true
+}
+
+fn secs(v: Float) -> Duration {
+    Duration::from_secs_f32(v.max(0.0))
+}
+
+impl ResourceManager {
+    fn load_cutscene(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
+        log::info!("Loading cutscene at {file:?}");
+
+        let v = self.read_ron::<Raw>(file, namespace)?;
+
+        let id = Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap();
+
+        let steps = v
+            .steps
+            .into_iter()
+            .map(|step| match step {
+                StepRaw::Camera { to, zoom, secs: v } => CutsceneStep::Camera {
+                    to,
+                    zoom,
+                    duration: secs(v),
+                },
+                StepRaw::Text { text, secs: v } => CutsceneStep::Text {
+                    text: Id::parse(&text, &mut self.interner, Some(namespace)).unwrap(),
+                    duration: secs(v),
+                },
+                StepRaw::Sound(name) => CutsceneStep::Sound(name),
+                StepRaw::Wait(v) => CutsceneStep::Wait(secs(v)),
+            })
+            .collect();
+
+        self.registry.cutscenes.insert(
+            id,
+            CutsceneDef {
+                id,
+                steps,
+                skippable: v.skippable,
+            },
+        );
+
+        Ok(())
+    }
+
+    pub fn load_cutscenes(&mut self, dir: &Path, namespace: &str) -> anyhow::Result<()> {
+        let cutscenes = dir.join("cutscenes");
+
+        for file in load_recursively(&cutscenes, OsStr::new(RON_EXT)) {
+            self.load_cutscene(&file, namespace)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{load_recursively, ResourceManager, RON_EXT};
+use automancy_defs::id::Id;
+use serde::Deserialize;
+use std::ffi::OsStr;
+use std::path::Path;
+
+/// Controls how a data id is treated when tiles are copied or picked.
+#[derive(Debug, Clone)]
+pub struct DataRuleDef {
+    pub id: Id,
+    /// whether the data is carried over when copying or pasting the tile
+    pub copy: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Raw {
+    pub id: String,
+    pub copy: bool,
+}
+
+impl ResourceManager {
+    fn load_data_rule(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
+        log::info!("Loading data rule at: {file:?}");
+
+        let v = self.read_ron::<Raw>(file, namespace)?;
+
+        let id = Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap();
+
+        self.registry
+            .data_rules
+            .insert(id, DataRuleDef { id, copy: v.copy });
+
+        Ok(())
+    }
+
+    pub fn load_data_rules(&mut self, dir: &Path, namespace: &str) -> anyhow::Result<()> {
+        let data_rules = dir.join("data_rules");
+
+        for file in load_recursively(&data_rules, OsStr::new(RON_EXT)) {
+            self.load_data_rule(&file, namespace)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks if the given data id should be copied along with its tile. Falls back to the built-in defaults if no rule is declared.
+    pub fn data_copyable(&self, id: Id) -> bool {
+        if let Some(rule) = self.registry.data_rules.get(&id) {
+            return rule.copy;
+        }
+
+        let data_ids = &self.registry.data_ids;
+
+        [
+            data_ids.direction,
+            data_ids.link,
+            data_ids.script,
+            data_ids.capacity,
+            data_ids.item,
+        ]
+        .contains(&id)
+    }
+}
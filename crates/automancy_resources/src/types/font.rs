@@ -18,6 +18,21 @@ pub struct Font {
     pub data: Arc<Vec<u8>>,
 }
 
+impl Font {
+    /// Gets the characters the font has no glyph for.
+    pub fn missing_glyphs(&self, chars: &[char]) -> Vec<char> {
+        let Ok(face) = Face::parse(&self.data, 0) else {
+            return chars.to_vec();
+        };
+
+        chars
+            .iter()
+            .copied()
+            .filter(|c| face.glyph_index(*c).is_none())
+            .collect()
+    }
+}
+
 fn collect_families(name_id: u16, names: &Names) -> Vec<(String, Language)> {
     let mut families = Vec::new();
     for name in names.into_iter() {
@@ -59,6 +74,26 @@ fn parse_name(names: Names) -> Option<String> {
 }
 
 impl ResourceManager {
+    /// Gets the characters of the loaded translation that none of the fonts have a glyph for, which would render blank.
+    pub fn missing_glyphs(&self, fonts: &[String]) -> Vec<char> {
+        fonts
+            .iter()
+            .filter_map(|name| self.fonts.get(name))
+            .fold(self.translates().chars(), |missing, font| {
+                font.missing_glyphs(&missing)
+            })
+    }
+
+    /// Finds the loaded font with glyphs for the most of the characters, to fall back to. None if no font has any of them.
+    pub fn glyph_fallback_font(&self, chars: &[char]) -> Option<String> {
+        self.fonts
+            .values()
+            .map(|font| (chars.len() - font.missing_glyphs(chars).len(), &font.name))
+            .filter(|(covered, _)| *covered > 0)
+            .max_by_key(|(covered, _)| *covered)
+            .map(|(_, name)| name.clone())
+    }
+
     pub fn load_fonts(&mut self, dir: &Path) -> anyhow::Result<()> {
         let fonts = dir.join("fonts");
 
@@ -1,11 +1,13 @@
+use crate::rhai_module::PackModuleResolver;
+use crate::rhai_test::is_script_test;
 use crate::{load_recursively, ResourceManager, FUNCTION_EXT};
 use automancy_defs::{
     coord::TileCoord,
     id::{Id, IdRaw, TileId},
-    stack::ItemStack,
+    stack::{ItemAmount, ItemStack},
 };
 use hashbrown::HashSet;
-use rhai::{ImmutableString, Module, Scope};
+use rhai::{module_resolvers::ModuleResolver, ImmutableString, Position, Scope};
 use std::ffi::OsStr;
 use std::path::Path;
 
@@ -23,6 +25,69 @@ pub enum TileResult {
         requested_from_coord: TileCoord,
         on_fail_action: OnFailAction,
     },
+    SendItems {
+        channel: Id,
+        stacks: Vec<ItemStack>,
+    },
+    RequestItems {
+        channel: Id,
+        max: ItemAmount,
+    },
+    PlaceTile {
+        coord: TileCoord,
+        id: TileId,
+    },
+    RemoveTile {
+        coord: TileCoord,
+    },
+    /// the tile has no work to do, and stops being ticked until something changes around it, or for the given number of ticks
+    Sleep {
+        ticks: Option<u32>,
+    },
+    /// starts a task, which runs the functions with the given names one after another, one per tick
+    StartTask {
+        steps: Vec<String>,
+        locals: rhai::Map,
+    },
+    /// spawns an agent of the given kind at the coordinate
+    SpawnAgent {
+        id: Id,
+        coord: TileCoord,
+    },
+}
+
+/// What an agent's AGENT_TICK_FUNCTION decided to do, while it has nowhere to go.
+#[derive(Debug, Clone, Copy)]
+pub enum AgentResult {
+    /// moves in a straight line to the coordinate, and asks again once it is there
+    MoveTo(TileCoord),
+    /// removes the agent from the map
+    Despawn,
+}
+
+/// What a map's CONDITIONS_FUNCTION decided, once the map is won or lost.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapOutcome {
+    /// the map was won. scenarios name the save to continue on, if there's a next map
+    Victory {
+        message: String,
+        next_map: Option<String>,
+    },
+    /// the map was lost
+    Defeat { message: String },
+}
+
+/// What a step of a task returned, with the task's locals passed on to the next step.
+#[derive(Debug, Clone)]
+pub enum TaskStep {
+    /// runs the next step on the next tick
+    Next(rhai::Map),
+    /// waits the given number of ticks before running the next step
+    Wait(u32, rhai::Map),
+    /// runs the same step again on the next tick
+    Repeat(rhai::Map),
+    /// ends the task early
+    Done,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -57,9 +122,60 @@ pub enum OnFailAction {
     RemoveData(Id),
 }
 
+/// The name of the function called on tiles that receive a random tick.
+pub static RANDOM_TICK_FUNCTION: &str = "on_random_tick";
+/// The name of the function called on tiles whose model's animation passed one of its event markers.
+pub static ANIMATION_EVENT_FUNCTION: &str = "on_animation_event";
+/// The name of the function called on tiles that are delivered the items they requested from a transfer channel.
+pub static RECEIVE_ITEMS_FUNCTION: &str = "handle_receive_items";
+/// The name of the function called every tick on agents that have nowhere to go.
+pub static AGENT_TICK_FUNCTION: &str = "handle_agent_tick";
+/// The name of the function called every so often on the map's conditions function, to decide whether it was won or lost.
+pub static CONDITIONS_FUNCTION: &str = "check_conditions";
+
+/// A point in a tile's life the engine calls its function at, if the function defines it.
+///
+/// The hooks of one tile are called in the order the changes happen in, and always after the tile's data is set:
+/// - [LifecycleHook::Place] when the tile is placed, before its first tick.
+/// - [LifecycleHook::Load] when the map is loaded, after every tile of the map has its data.
+/// - [LifecycleHook::Remove] when the tile is removed, while it still has its data.
+/// - [LifecycleHook::NeighborChanged] at the start of the tick after tiles next to it were placed, removed or
+///   reconfigured by the player, once for all of them, after their own hooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LifecycleHook {
+    Place,
+    Remove,
+    Load,
+    NeighborChanged,
+}
+
+impl LifecycleHook {
+    pub const ALL: [Self; 4] = [Self::Place, Self::Remove, Self::Load, Self::NeighborChanged];
+
+    /// The name of the function called for the hook.
+    pub fn function(self) -> &'static str {
+        match self {
+            LifecycleHook::Place => "on_place",
+            LifecycleHook::Remove => "on_remove",
+            LifecycleHook::Load => "on_load",
+            LifecycleHook::NeighborChanged => "on_neighbor_changed",
+        }
+    }
+}
+
 pub struct FunctionMetadata {
     pub str_id: String,
     pub render_listening_to_fields: HashSet<Id>,
+    /// whether the function defines RANDOM_TICK_FUNCTION, and so takes random ticks
+    pub has_random_tick: bool,
+    /// whether the function defines ANIMATION_EVENT_FUNCTION, and so is told about animation events
+    pub has_animation_event: bool,
+    /// whether the function defines RECEIVE_ITEMS_FUNCTION, and so can take the items it requested
+    pub has_receive_items: bool,
+    /// the lifecycle hooks the function defines, which are the only ones called on it
+    pub lifecycle_hooks: HashSet<LifecycleHook>,
+    /// the IDs the function declares in `id_deps`, which it may use
+    pub id_deps: Vec<Id>,
 }
 
 impl ResourceManager {
@@ -68,8 +184,15 @@ impl ResourceManager {
 
         {
             let lib = functions.join("lib");
+            // every library is added before any is resolved, so that they can import each other regardless of the
+            // order they are found in
+            let mut libraries = Vec::new();
 
             for file in load_recursively(&lib, OsStr::new(FUNCTION_EXT)) {
+                if is_script_test(&file) {
+                    continue;
+                }
+
                 log::info!("Loading library function at {file:?}");
 
                 let Some(name) = file.file_stem().and_then(OsStr::to_str).map(str::to_string)
@@ -77,6 +200,7 @@ impl ResourceManager {
                     continue;
                 };
 
+                let path = PackModuleResolver::import_path(namespace, &name);
                 let name = format!("lib::{}::{}", namespace, name);
 
                 let pre_ast = self.engine.compile_file(file.clone())?;
@@ -101,22 +225,26 @@ impl ResourceManager {
 
                     log::info!("Adding {key} -> {id} into scope of library function {name}");
 
-                    scope.push_constant(
-                        key.as_str(),
-                        Id::parse(&id, &mut self.interner, Some(namespace)).unwrap(),
-                    );
+                    let id = Id::parse(&id, &mut self.interner, Some(namespace)).unwrap();
+                    self.library_id_deps.insert(id);
+                    scope.push_constant(key.as_str(), id);
                 }
 
-                let module = Module::eval_ast_as_new(
-                    Scope::new(),
-                    &self.engine.compile_file_with_scope(&scope, file)?,
-                    &self.engine,
+                self.module_resolver.add_source(
+                    path.clone(),
+                    self.engine.compile_file_with_scope(&scope, file)?,
                 );
+                libraries.push((name, path));
+            }
+
+            for (name, path) in libraries {
+                let module =
+                    self.module_resolver
+                        .resolve(&self.engine, None, &path, Position::NONE);
 
                 match module {
                     Ok(module) => {
-                        self.engine
-                            .register_static_module(name.clone(), module.into());
+                        self.engine.register_static_module(name.clone(), module);
                     }
                     Err(err) => {
                         log::error!("Could not register library function {name}! Error: {err:?}");
@@ -124,7 +252,9 @@ impl ResourceManager {
                     }
                 }
 
-                log::info!("Registered library function with name '{name}'!");
+                log::info!(
+                    "Registered library function with name '{name}', importable as \"{path}\"!"
+                );
             }
         }
 
@@ -132,6 +262,10 @@ impl ResourceManager {
             let src = functions.join("src");
 
             for file in load_recursively(&src, OsStr::new(FUNCTION_EXT)) {
+                if is_script_test(&file) {
+                    continue;
+                }
+
                 log::info!("Loading source function at {file:?}");
 
                 let mut scope = Scope::new();
@@ -155,6 +289,7 @@ impl ResourceManager {
                     (),
                 )?;
                 let mut scope = Scope::new();
+                let mut deps = Vec::new();
                 for id_dep in id_deps.into_iter() {
                     let v = id_dep.cast::<rhai::Array>();
 
@@ -168,10 +303,9 @@ impl ResourceManager {
 
                     log::info!("Adding {key} -> {id} into scope of source function {str_id}");
 
-                    scope.push_constant(
-                        key.as_str(),
-                        Id::parse(&id, &mut self.interner, Some(namespace)).unwrap(),
-                    );
+                    let id = Id::parse(&id, &mut self.interner, Some(namespace)).unwrap();
+                    deps.push(id);
+                    scope.push_constant(key.as_str(), id);
                 }
 
                 let ast = self.engine.compile_file_with_scope(&scope, file)?;
@@ -193,6 +327,18 @@ impl ResourceManager {
                         .into_iter()
                         .flat_map(|v| v.try_cast::<Id>())
                         .collect(),
+                    has_random_tick: ast.iter_functions().any(|f| f.name == RANDOM_TICK_FUNCTION),
+                    has_animation_event: ast
+                        .iter_functions()
+                        .any(|f| f.name == ANIMATION_EVENT_FUNCTION),
+                    has_receive_items: ast
+                        .iter_functions()
+                        .any(|f| f.name == RECEIVE_ITEMS_FUNCTION),
+                    lifecycle_hooks: LifecycleHook::ALL
+                        .into_iter()
+                        .filter(|hook| ast.iter_functions().any(|f| f.name == hook.function()))
+                        .collect(),
+                    id_deps: deps,
                 };
 
                 self.functions.insert(id, (ast, metadata));
@@ -1,9 +1,11 @@
-use crate::{load_recursively, ResourceManager, RON_EXT};
-use automancy_defs::id::{Id, ModelId};
+use crate::data::Data;
+use crate::{item_ids_of_tag, load_recursively, ResourceManager, RON_EXT};
+use automancy_defs::id::{Id, ModelId, TileId};
+use hashbrown::HashMap;
 use serde::Deserialize;
 use std::ffi::OsStr;
-use std::fs::read_to_string;
 use std::path::Path;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
 pub struct ItemDef {
@@ -21,7 +23,7 @@ impl ResourceManager {
     fn load_item(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
         log::info!("Loading item at: {file:?}");
 
-        let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
+        let v = self.read_ron::<Raw>(file, namespace)?;
 
         let id = Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap();
         let model = Id::parse(&v.model, &mut self.interner, Some(namespace)).unwrap();
@@ -47,11 +49,125 @@ impl ResourceManager {
         Ok(())
     }
 
-    pub fn ordered_items(&mut self) {
+    /// Sorts the items by their names.
+    pub fn ordered_items(&self) {
         let mut ids = self.registry.items.keys().cloned().collect::<Vec<_>>();
 
         ids.sort_by_key(|id| self.item_name(*id));
 
-        self.ordered_items = ids;
+        self.ordered_items.store(Arc::new(ids));
+    }
+
+    /// Builds the reverse indexes from items to the tags they belong to and the scripts that produce or consume them,
+    /// and from scripts to the tiles that can run them. Must be called after the items are ordered.
+    pub fn compile_item_indexes(&mut self) {
+        let mut item_tags = HashMap::new();
+
+        for tag in self.registry.tags.values() {
+            for entry in &tag.entries {
+                item_tags
+                    .entry(*entry)
+                    .or_insert_with(Vec::new)
+                    .push(tag.id);
+            }
+        }
+
+        let mut item_producers = HashMap::new();
+        let mut item_consumers = HashMap::new();
+
+        for script in self.registry.scripts.values() {
+            for output in &script.instructions.outputs {
+                for item in item_ids_of_tag(self, output.id) {
+                    item_producers
+                        .entry(item)
+                        .or_insert_with(Vec::new)
+                        .push(script.id);
+                }
+            }
+
+            for input in script.instructions.inputs.iter().flatten() {
+                for item in item_ids_of_tag(self, input.id) {
+                    item_consumers
+                        .entry(item)
+                        .or_insert_with(Vec::new)
+                        .push(script.id);
+                }
+            }
+        }
+
+        // a tile can run a script if its setup data refers to it
+        let mut script_tiles = HashMap::<Id, Vec<TileId>>::new();
+
+        for tile in self.registry.tiles.values() {
+            for key in tile.data.keys() {
+                let ids = match tile.data.get(*key) {
+                    Some(Data::Id(id)) => vec![*id],
+                    Some(Data::VecId(ids)) => ids.clone(),
+                    Some(Data::SetId(ids)) => ids.iter().cloned().collect(),
+                    _ => continue,
+                };
+
+                for id in ids {
+                    if self.registry.scripts.contains_key(&id) {
+                        script_tiles.entry(id).or_default().push(tile.id);
+                    }
+                }
+            }
+        }
+
+        for ids in item_tags
+            .values_mut()
+            .chain(item_producers.values_mut())
+            .chain(item_consumers.values_mut())
+        {
+            ids.sort();
+            ids.dedup();
+        }
+
+        for ids in script_tiles.values_mut() {
+            ids.sort();
+            ids.dedup();
+        }
+
+        self.registry.item_tags = item_tags;
+        self.registry.item_producers = item_producers;
+        self.registry.item_consumers = item_consumers;
+        self.registry.script_tiles = script_tiles;
+    }
+
+    /// Gets the tags the item is in.
+    pub fn get_item_tags(&self, id: Id) -> &[Id] {
+        self.registry
+            .item_tags
+            .get(&id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Gets the scripts that output the item.
+    pub fn get_item_producers(&self, id: Id) -> &[Id] {
+        self.registry
+            .item_producers
+            .get(&id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Gets the scripts that take the item as an input.
+    pub fn get_item_consumers(&self, id: Id) -> &[Id] {
+        self.registry
+            .item_consumers
+            .get(&id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Gets the tiles that can run the script.
+    pub fn get_script_tiles(&self, id: Id) -> &[TileId] {
+        self.registry
+            .script_tiles
+            .get(&id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
     }
 }
@@ -3,12 +3,18 @@ use automancy_defs::{glam::vec3, math::Quaternion};
 use serde::{Deserialize, Serialize};
 use std::f32::consts::FRAC_PI_4;
 
+pub mod agent;
+#[cfg(feature = "audio")]
 pub mod audio;
 pub mod category;
+pub mod constant;
+pub mod cutscene;
+pub mod data_rule;
 pub mod font;
 pub mod function;
 pub mod item;
 pub mod model;
+pub mod pack;
 pub mod research;
 pub mod script;
 pub mod shader;
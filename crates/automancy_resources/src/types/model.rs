@@ -1,4 +1,5 @@
 use crate::{load_recursively, ResourceManager, RON_EXT};
+use automancy_defs::math::Float;
 use automancy_defs::rendering::Vertex;
 use automancy_defs::rendering::{load_gltf_model, Animation};
 use automancy_defs::{gltf, log};
@@ -9,20 +10,57 @@ use automancy_defs::{
 use hashbrown::HashMap;
 use serde::Deserialize;
 use std::ffi::OsStr;
+use std::mem;
 use std::path::Path;
-use std::{fs::read_to_string, mem};
 
 #[derive(Debug, Default, Clone, Copy)]
 pub struct IndexRange {
     pub pos: u32,
     pub count: u32,
     pub base_vertex: i32,
+    /// how many vertices the mesh has, as they are moved into the shared vertex buffer
+    pub vertex_count: u32,
+}
+
+/// Named markers on the loop of a model's animation. The scripts of the tiles showing the model are told whenever the
+/// animation passes one.
+#[derive(Debug, Clone, Default)]
+pub struct AnimationEvents {
+    /// how long the animation takes to loop, in seconds
+    pub length: Float,
+    /// the markers and their time in the loop, in seconds, in order of time
+    pub markers: Vec<(Float, String)>,
+}
+
+impl AnimationEvents {
+    /// Gets the markers the animation passed between two times on the animation clock.
+    pub fn crossed(&self, from: Float, to: Float) -> impl Iterator<Item = &str> {
+        let whole_loop = to - from >= self.length;
+        let from = from % self.length;
+        let to = to % self.length;
+
+        self.markers
+            .iter()
+            .filter(move |(time, _)| {
+                if whole_loop {
+                    true
+                } else if from <= to {
+                    from < *time && *time <= to
+                } else {
+                    from < *time || *time <= to
+                }
+            })
+            .map(|(_, name)| name.as_str())
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct Raw {
     pub id: String,
     pub file: String,
+    /// the name of each marker, at its time in seconds
+    #[serde(default)]
+    pub events: Vec<(Float, String)>,
 }
 
 impl ResourceManager {
@@ -80,7 +118,7 @@ impl ResourceManager {
     fn load_model(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
         log::info!("Loading model at: {file:?}");
 
-        let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
+        let v = self.read_ron::<Raw>(file, namespace)?;
 
         let file = file.parent().unwrap().join("files").join(v.file.as_str());
 
@@ -90,8 +128,27 @@ impl ResourceManager {
 
         let id = Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap();
 
-        self.all_meshes_anims
-            .insert(ModelId(id), load_gltf_model(document, buffers));
+        let (meshes, anims) = load_gltf_model(document, buffers);
+
+        if !v.events.is_empty() {
+            let length = anims
+                .iter()
+                .flat_map(|anim| anim.inputs.last())
+                .copied()
+                .fold(0.0, Float::max);
+
+            if length > 0.0 {
+                let mut markers = v.events;
+                markers.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                self.animation_events
+                    .insert(ModelId(id), AnimationEvents { length, markers });
+            } else {
+                log::warn!("Model {} has animation events, but no animation", v.id);
+            }
+        }
+
+        self.all_meshes_anims.insert(ModelId(id), (meshes, anims));
 
         Ok(())
     }
@@ -119,6 +176,7 @@ impl ResourceManager {
                         mesh.index,
                         mem::take(&mut mesh.indices),
                         base_vertex_count,
+                        mesh.vertices.len() as u32,
                     ));
 
                     base_vertex_count += mesh.vertices.len() as i32;
@@ -134,13 +192,14 @@ impl ResourceManager {
             .map(|(id, indices)| {
                 let ranges = indices
                     .iter()
-                    .map(|(index, v, base_vertex)| {
+                    .map(|(index, v, base_vertex, vertex_count)| {
                         let size = v.len() as u32;
 
                         let range = IndexRange {
                             pos: offset_count,
                             count: size,
                             base_vertex: *base_vertex,
+                            vertex_count: *vertex_count,
                         };
 
                         offset_count += size;
@@ -0,0 +1,125 @@
+use crate::ResourceManager;
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// The file in the root of a pack declaring its version, what it needs to be loaded after, and the language it's written
+/// in.
+pub static PACK_MANIFEST: &str = "pack.ron";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PackManifest {
+    /// The version of the pack, recorded in the maps saved with it, to tell when a map is loaded with another one.
+    pub version: Option<String>,
+    /// The namespaces that have to be there, and are loaded before this one.
+    pub depends_on: Vec<String>,
+    /// The namespaces that are loaded before this one if they are there, e.g. the ones this overrides tags or tiles of.
+    pub load_after: Vec<String>,
+    /// The language the pack is written in, whose text is shown for what the selected language doesn't translate.
+    pub default_language: Option<String>,
+}
+
+impl PackManifest {
+    /// Reads the manifest of the pack in the folder. A pack without one needs nothing loaded before it.
+    pub fn read(dir: &Path) -> anyhow::Result<Self> {
+        let file = dir.join(PACK_MANIFEST);
+
+        if !file.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(ron::from_str(&read_to_string(file)?)?)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum LoadOrderError {
+    #[error("the pack {0} depends on {1}, which isn't there")]
+    MissingDependency(String, String),
+    #[error("the packs have to be loaded after each other in a cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+}
+
+/// Finds a cycle among the packs left to load, which all wait on another one of them.
+fn find_cycle(waiting: &BTreeMap<String, BTreeSet<String>>) -> Vec<String> {
+    let mut path = Vec::<String>::new();
+    let mut current = waiting.keys().next().cloned();
+
+    while let Some(namespace) = current {
+        if let Some(start) = path.iter().position(|v| *v == namespace) {
+            let mut cycle = path.split_off(start);
+            cycle.push(namespace);
+
+            return cycle;
+        }
+
+        current = waiting[&namespace].first().cloned();
+        path.push(namespace);
+    }
+
+    path
+}
+
+impl ResourceManager {
+    /// Sorts the packs, by their namespace, so that each one is loaded after the ones it depends on or is to be loaded
+    /// after. The packs that could be loaded in either order are loaded in the order of their namespaces, so that it's
+    /// always the same.
+    pub fn sort_namespaces(
+        packs: Vec<(String, PathBuf)>,
+    ) -> anyhow::Result<Vec<(String, PathBuf)>> {
+        let mut dirs = BTreeMap::new();
+        let mut manifests = BTreeMap::new();
+
+        for (namespace, dir) in packs {
+            let manifest = PackManifest::read(&dir)
+                .with_context(|| format!("Error reading the manifest of {namespace}"))?;
+
+            manifests.insert(namespace.clone(), manifest);
+            dirs.insert(namespace, dir);
+        }
+
+        // the packs that each pack waits on, out of the ones that are there
+        let mut waiting = BTreeMap::<String, BTreeSet<String>>::new();
+
+        for (namespace, manifest) in manifests {
+            if let Some(missing) = manifest.depends_on.iter().find(|v| !dirs.contains_key(*v)) {
+                return Err(LoadOrderError::MissingDependency(namespace, missing.clone()).into());
+            }
+
+            let after = manifest
+                .depends_on
+                .into_iter()
+                .chain(manifest.load_after)
+                .filter(|v| dirs.contains_key(v) && *v != namespace)
+                .collect();
+
+            waiting.insert(namespace, after);
+        }
+
+        let mut sorted = vec![];
+
+        while !waiting.is_empty() {
+            let Some(next) = waiting
+                .iter()
+                .find(|(_, after)| after.is_empty())
+                .map(|(namespace, _)| namespace.clone())
+            else {
+                return Err(LoadOrderError::Cycle(find_cycle(&waiting)).into());
+            };
+
+            waiting.remove(&next);
+            for after in waiting.values_mut() {
+                after.remove(&next);
+            }
+
+            let dir = dirs.remove(&next).unwrap();
+            sorted.push((next, dir));
+        }
+
+        Ok(sorted)
+    }
+}
@@ -12,7 +12,6 @@ use automancy_defs::{
 use petgraph::visit::IntoNodeReferences;
 use serde::Deserialize;
 use std::ffi::OsStr;
-use std::fs::read_to_string;
 use std::path::Path;
 
 #[derive(Debug, Clone)]
@@ -27,6 +26,8 @@ pub struct ResearchDef {
     pub completed_description: Id,
     pub required_items: Option<Vec<ItemStack>>,
     pub attached_puzzle: Option<(Id, DataMap)>,
+    /// the cutscene played when the research is completed
+    pub cutscene: Option<Id>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -41,13 +42,15 @@ struct Raw {
     completed_description: String,
     required_items: Option<Vec<(String, ItemAmount)>>,
     attached_puzzle: Option<(String, DataMapRaw)>,
+    #[serde(default)]
+    cutscene: Option<String>,
 }
 
 impl ResourceManager {
     fn load_research(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
         log::info!("Loading research entry at: {file:?}");
 
-        let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
+        let v = self.read_ron::<Raw>(file, namespace)?;
 
         let id = Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap();
 
@@ -82,6 +85,9 @@ impl ResourceManager {
             )
         });
         let icon_mode = v.icon_mode;
+        let cutscene = v
+            .cutscene
+            .map(|v| Id::parse(&v, &mut self.interner, Some(namespace)).unwrap());
 
         let index = self.registry.researches.add_node(ResearchDef {
             id,
@@ -94,6 +100,7 @@ impl ResourceManager {
             required_items,
             attached_puzzle,
             icon_mode,
+            cutscene,
         });
 
         self.registry.researches_id_map.insert(id, index);
@@ -1,18 +1,43 @@
-use crate::{load_recursively, ResourceManager, RON_EXT};
+use crate::{load_recursively, rhai_field, ResourceManager, RON_EXT};
 use automancy_defs::{
+    coord::TileCoord,
     id::Id,
+    math::Float,
     parse_item_stacks,
     stack::{ItemAmount, ItemStack},
 };
 use serde::Deserialize;
 use std::ffi::OsStr;
-use std::fs::read_to_string;
 use std::path::Path;
 
+/// A range a field has to be in at a tile for a script to run there.
+#[derive(Debug, Clone, Copy)]
+pub struct FieldCondition {
+    pub field: Id,
+    pub min: Option<Float>,
+    pub max: Option<Float>,
+}
+
+impl FieldCondition {
+    pub fn met(&self, coord: TileCoord) -> bool {
+        let value = rhai_field::field_value(self.field, coord);
+
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct InstructionsDef {
     pub inputs: Option<Vec<ItemStack>>,
     pub outputs: Vec<ItemStack>,
+    pub conditions: Vec<FieldCondition>,
+}
+
+impl InstructionsDef {
+    /// Checks if every field condition of the script is met at the tile.
+    pub fn conditions_met(&self, coord: TileCoord) -> bool {
+        self.conditions.iter().all(|v| v.met(coord))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -21,10 +46,19 @@ pub struct ScriptDef {
     pub instructions: InstructionsDef,
 }
 
+#[derive(Debug, Deserialize)]
+struct FieldConditionRaw {
+    pub field: String,
+    pub min: Option<Float>,
+    pub max: Option<Float>,
+}
+
 #[derive(Debug, Deserialize)]
 struct InstructionsRaw {
     pub inputs: Option<Vec<(String, ItemAmount)>>,
     pub output: Vec<(String, ItemAmount)>,
+    #[serde(default)]
+    pub conditions: Vec<FieldConditionRaw>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -37,7 +71,7 @@ impl ResourceManager {
     fn load_script(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
         log::info!("Loading script at: {file:?}");
 
-        let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
+        let v = self.read_ron::<Raw>(file, namespace)?;
 
         let id = Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap();
 
@@ -51,6 +85,16 @@ impl ResourceManager {
                 &mut self.interner,
                 Some(namespace),
             ),
+            conditions: v
+                .instructions
+                .conditions
+                .into_iter()
+                .map(|v| FieldCondition {
+                    field: Id::parse(&v.field, &mut self.interner, Some(namespace)).unwrap(),
+                    min: v.min,
+                    max: v.max,
+                })
+                .collect(),
         };
 
         let script = ScriptDef { id, instructions };
@@ -4,7 +4,6 @@ use automancy_defs::{id::Id, parse_ids};
 use hashbrown::HashSet;
 use serde::Deserialize;
 use std::ffi::OsStr;
-use std::fs::read_to_string;
 use std::path::Path;
 
 #[derive(Debug, Clone)]
@@ -33,7 +32,7 @@ impl ResourceManager {
     fn load_tag(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
         log::info!("Loading tag at: {file:?}");
 
-        let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
+        let v = self.read_ron::<Raw>(file, namespace)?;
 
         let id = Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap();
 
@@ -1,17 +1,104 @@
 use crate::data::{DataMap, DataMapRaw};
+use crate::registry::Registry;
 use crate::{load_recursively, ResourceManager, RON_EXT};
-use automancy_defs::id::{Id, TileId};
+use automancy_defs::id::{Id, ModelId, TileId};
+use hashbrown::HashMap;
 use serde::Deserialize;
 use std::ffi::OsStr;
-use std::fs::read_to_string;
 use std::path::Path;
+use std::sync::Arc;
+
+/// An action the radial menu of a tile offers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileAction {
+    /// opens the tile's config
+    Configure,
+    /// copies the tile with its settings, to be pasted elsewhere
+    CopySettings,
+    /// removes the tile
+    Delete,
+    /// moves the camera to the tile linked in the data entry
+    JumpLink(Id),
+}
+
+impl TileAction {
+    /// The actions tiles offer if their definition doesn't list any.
+    pub const DEFAULT: [TileAction; 3] = [
+        TileAction::Configure,
+        TileAction::CopySettings,
+        TileAction::Delete,
+    ];
+}
+
+/// The sides of a tile, as bits in the order of [automancy_defs::coord::TileCoord::neighbors].
+pub const ALL_SIDES: u8 = 0b111111;
+
+/// The models a tile is drawn with depending on which of its neighbors it connects to, such as belts and pipes.
+#[derive(Debug, Clone)]
+pub struct ConnectedModels {
+    /// The tag of the tiles it connects to. Without one, it only connects to tiles of the same type.
+    pub connects_to: Option<Id>,
+    /// The sides it can connect on, as a bitmask like the variants.
+    pub sides: u8,
+    /// The model for each set of connected sides, as a bitmask over the order of
+    /// [automancy_defs::coord::TileCoord::neighbors].
+    pub variants: HashMap<u8, ModelId>,
+    /// The model for the sets of connected sides without a variant.
+    pub default: ModelId,
+}
+
+impl ConnectedModels {
+    /// Checks if the tile of this type connects to a neighbor of the given type.
+    pub fn connects(&self, registry: &Registry, own: TileId, other: Id) -> bool {
+        match self.connects_to.and_then(|v| registry.tags.get(&v)) {
+            Some(tag) => tag.of(registry, other),
+            None => other == *own,
+        }
+    }
+
+    /// Gets the model for the set of connected sides.
+    pub fn model(&self, mask: u8) -> ModelId {
+        self.variants
+            .get(&(mask & self.sides))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct TileDef {
     pub id: TileId,
     pub function: Option<Id>,
     pub category: Option<Id>,
+    /// The model of a scenery tile. Scenery tiles are purely decorative: they get no tile entity, are never ticked, and only their ID is saved.
+    pub scenery: Option<ModelId>,
     pub data: DataMap,
+    /// the actions offered in the tile's radial menu, in order
+    pub actions: Vec<TileAction>,
+    /// the models the engine draws the tile with, picked by the neighbors it connects to
+    pub connected: Option<ConnectedModels>,
+}
+
+#[derive(Debug, Deserialize)]
+enum TileActionRaw {
+    Configure,
+    CopySettings,
+    Delete,
+    JumpLink(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct ConnectedModelsRaw {
+    #[serde(default)]
+    pub connects_to: Option<String>,
+    #[serde(default = "all_sides")]
+    pub sides: u8,
+    pub variants: Vec<(u8, String)>,
+    pub default: String,
+}
+
+fn all_sides() -> u8 {
+    ALL_SIDES
 }
 
 #[derive(Debug, Deserialize)]
@@ -20,14 +107,21 @@ struct Raw {
     pub function: Option<String>,
     #[serde(default)]
     pub category: Option<String>,
+    #[serde(default)]
+    pub scenery: Option<String>,
+    #[serde(default)]
     pub data: DataMapRaw,
+    #[serde(default)]
+    pub actions: Option<Vec<TileActionRaw>>,
+    #[serde(default)]
+    pub connected: Option<ConnectedModelsRaw>,
 }
 
 impl ResourceManager {
     fn load_tile(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
         log::info!("Loading tile at {file:?}");
 
-        let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
+        let v = self.read_ron::<Raw>(file, namespace)?;
 
         let id = TileId(Id::parse(&v.id, &mut self.interner, Some(namespace)).unwrap());
         let function = v
@@ -36,16 +130,57 @@ impl ResourceManager {
         let category = v
             .category
             .map(|v| Id::parse(&v, &mut self.interner, Some(namespace)).unwrap());
+        let scenery = v
+            .scenery
+            .map(|v| ModelId(Id::parse(&v, &mut self.interner, Some(namespace)).unwrap()));
 
         let data = v.data.intern_to_data(&mut self.interner, Some(namespace));
 
+        let actions = match v.actions {
+            Some(actions) => actions
+                .into_iter()
+                .map(|v| match v {
+                    TileActionRaw::Configure => TileAction::Configure,
+                    TileActionRaw::CopySettings => TileAction::CopySettings,
+                    TileActionRaw::Delete => TileAction::Delete,
+                    TileActionRaw::JumpLink(id) => TileAction::JumpLink(
+                        Id::parse(&id, &mut self.interner, Some(namespace)).unwrap(),
+                    ),
+                })
+                .collect(),
+            None => TileAction::DEFAULT.to_vec(),
+        };
+
+        let connected = v.connected.map(|v| {
+            let connects_to = v
+                .connects_to
+                .map(|v| Id::parse(&v, &mut self.interner, Some(namespace)).unwrap());
+
+            let mut model =
+                |v: &str| ModelId(Id::parse(v, &mut self.interner, Some(namespace)).unwrap());
+
+            ConnectedModels {
+                connects_to,
+                sides: v.sides & ALL_SIDES,
+                variants: v
+                    .variants
+                    .iter()
+                    .map(|(mask, id)| (*mask & ALL_SIDES, model(id)))
+                    .collect(),
+                default: model(&v.default),
+            }
+        });
+
         self.registry.tiles.insert(
             id,
             TileDef {
                 id,
                 function,
                 category,
+                scenery,
                 data,
+                actions,
+                connected,
             },
         );
 
@@ -62,7 +197,13 @@ impl ResourceManager {
         Ok(())
     }
 
-    pub fn ordered_tiles(&mut self) {
+    /// Gets the model of the tile if it is a scenery tile.
+    pub fn scenery_model(&self, id: TileId) -> Option<ModelId> {
+        self.registry.tiles.get(&id).and_then(|tile| tile.scenery)
+    }
+
+    /// Sorts the tiles by their names, with the none tile first.
+    pub fn ordered_tiles(&self) {
         let mut ids = self.registry.tiles.keys().cloned().collect::<Vec<_>>();
 
         ids.sort_by_key(|id| self.tile_name(*id));
@@ -78,6 +219,6 @@ impl ResourceManager {
             ids.insert(0, old);
         }
 
-        self.ordered_tiles = ids;
+        self.ordered_tiles.store(Arc::new(ids));
     }
 }
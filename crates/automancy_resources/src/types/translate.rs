@@ -1,15 +1,22 @@
+use crate::types::pack::PackManifest;
 use crate::{format::FormatContext, ResourceManager, RON_EXT};
-use automancy_defs::{
-    id::{Id, SharedStr, TileId},
-    parse_map_id_str,
-};
-use hashbrown::HashMap;
+use anyhow::Context;
+use automancy_defs::id::{Id, IdRaw, SharedStr, TileId};
+use hashbrown::{HashMap, HashSet};
 use interpolator::Formattable;
 use serde::Deserialize;
 use std::fs::{read_dir, read_to_string};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::{ffi::OsStr, fmt::Debug};
 
+/// The language that pseudo-localizes the base language instead of loading a translation. Only available in dev builds.
+pub const PSEUDO_LANGUAGE: &str = "pseudo";
+/// The language the pseudo-locale is made from.
+pub const PSEUDO_BASE_LANGUAGE: &str = "en_US";
+/// The language shown for what neither the selected language nor the default language of the pack translates.
+pub const FALLBACK_LANGUAGE: &str = "en_US";
+
 #[derive(Debug, Default, Clone)]
 pub struct TranslateDef {
     pub none: SharedStr,
@@ -26,6 +33,31 @@ pub struct TranslateDef {
     pub keys: HashMap<Id, SharedStr>,
 }
 
+impl TranslateDef {
+    /// Gets every character the loaded translation has text in, sorted, for checking that the fonts have glyphs for them.
+    pub fn chars(&self) -> Vec<char> {
+        let mut chars = [&self.none, &self.unnamed]
+            .into_iter()
+            .chain(self.items.values())
+            .chain(self.tiles.values())
+            .chain(self.categories.values())
+            .chain(self.scripts.values())
+            .chain(self.gui.values())
+            .chain(self.error.values())
+            .chain(self.research.values())
+            .chain(self.keys.values())
+            .flat_map(|v| v.chars())
+            .filter(|c| !c.is_whitespace() && !c.is_control())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        chars.sort();
+
+        chars
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct Raw {
     #[serde(default)]
@@ -52,81 +84,399 @@ struct Raw {
     keys: HashMap<String, String>,
 }
 
-impl ResourceManager {
-    fn load_translate(&mut self, file: &Path, namespace: &str) -> anyhow::Result<()> {
-        log::info!("Loading translate at: {file:?}");
-
-        let v = ron::from_str::<Raw>(&read_to_string(file)?)?;
-
-        let mut new = TranslateDef {
-            none: SharedStr::default(),
-            unnamed: SharedStr::default(),
-            items: parse_map_id_str(v.items.into_iter(), &mut self.interner, Some(namespace)),
-            tiles: parse_map_id_str(v.tiles.into_iter(), &mut self.interner, Some(namespace)),
-            categories: parse_map_id_str(
-                v.categories.into_iter(),
-                &mut self.interner,
-                Some(namespace),
-            ),
-            scripts: parse_map_id_str(v.scripts.into_iter(), &mut self.interner, Some(namespace)),
-            gui: parse_map_id_str(v.gui.into_iter(), &mut self.interner, Some(namespace)),
-            keys: parse_map_id_str(v.keys.into_iter(), &mut self.interner, Some(namespace)),
-            error: parse_map_id_str(v.error.into_iter(), &mut self.interner, Some(namespace)),
-            research: parse_map_id_str(v.research.into_iter(), &mut self.interner, Some(namespace)),
-        };
-        if let Some(v) = v.none {
-            new.none = v.into();
-        }
-        if let Some(v) = v.unnamed {
-            new.unnamed = v.into();
-        }
-        if self.translates.none.is_empty() {
-            self.translates.none = new.none;
-        }
-        if self.translates.unnamed.is_empty() {
-            self.translates.unnamed = new.unnamed;
-        }
-
-        self.translates.items.extend(new.items);
-        self.translates.tiles.extend(new.tiles);
-        self.translates.categories.extend(new.categories);
-        self.translates.scripts.extend(new.scripts);
-        self.translates.gui.extend(new.gui);
-        self.translates.keys.extend(new.keys);
-        self.translates.error.extend(new.error);
-        self.translates.research.extend(new.research);
+impl Raw {
+    /// Lists every key, as `section/id`, with the IDs given the namespace if they don't have one.
+    fn translated_keys(&self, namespace: &str) -> Vec<String> {
+        let mut keys = vec![];
 
-        Ok(())
+        if self.none.is_some() {
+            keys.push("none".to_string());
+        }
+        if self.unnamed.is_some() {
+            keys.push("unnamed".to_string());
+        }
+
+        for (section, map) in [
+            ("items", &self.items),
+            ("tiles", &self.tiles),
+            ("categories", &self.categories),
+            ("scripts", &self.scripts),
+            ("gui", &self.gui),
+            ("error", &self.error),
+            ("research", &self.research),
+            ("keys", &self.keys),
+        ] {
+            keys.extend(
+                map.keys()
+                    .flat_map(|id| IdRaw::parse(id, Some(namespace)))
+                    .map(|id| format!("{section}/{}", &*id)),
+            );
+        }
+
+        keys
     }
 
-    pub fn load_translates(
-        &mut self,
-        dir: &Path,
-        namespace: &str,
-        selected_language: &str,
-    ) -> anyhow::Result<()> {
-        let lang = OsStr::new(selected_language);
+    /// Pseudo-localizes every string.
+    fn pseudo_localize(&mut self) {
+        for v in self.none.iter_mut().chain(self.unnamed.iter_mut()) {
+            *v = pseudo_localize(v);
+        }
+
+        for map in [
+            &mut self.items,
+            &mut self.tiles,
+            &mut self.categories,
+            &mut self.scripts,
+            &mut self.gui,
+            &mut self.error,
+            &mut self.research,
+            &mut self.keys,
+        ] {
+            for v in map.values_mut() {
+                *v = pseudo_localize(v);
+            }
+        }
+    }
+}
+
+/// Turns a string into its pseudo-localized form: accented, about a third longer, and in brackets,
+/// so that text that isn't translated, or gets cut off, stands out. Format arguments are kept as they are.
+pub fn pseudo_localize(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() * 2);
+    let mut depth = 0usize;
+
+    out.push('[');
+    for c in s.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => {
+                let accented = match c {
+                    'a' => 'á',
+                    'e' => 'é',
+                    'i' => 'í',
+                    'o' => 'ö',
+                    'u' => 'ü',
+                    'y' => 'ý',
+                    'c' => 'ç',
+                    'n' => 'ñ',
+                    'A' => 'Á',
+                    'E' => 'É',
+                    'I' => 'Í',
+                    'O' => 'Ö',
+                    'U' => 'Ü',
+                    'Y' => 'Ý',
+                    'C' => 'Ç',
+                    'N' => 'Ñ',
+                    c => c,
+                };
+
+                out.push(accented);
+                // doubling the vowels makes english text about a third longer, like most translations of it
+                if "aeiouAEIOU".contains(c) {
+                    out.push(accented);
+                }
+
+                continue;
+            }
+            _ => {}
+        }
+
+        out.push(c);
+    }
+    out.push(']');
+
+    out
+}
+
+/// How much of the game a language translates.
+#[derive(Debug, Clone)]
+pub struct TranslationCoverage {
+    pub language: String,
+    /// how many keys there are to translate
+    pub total: usize,
+    /// the keys the language doesn't translate, as `section/id`, sorted
+    pub missing: Vec<String>,
+}
+
+/// Lists the translate files of a namespace, one per language.
+fn translate_files(dir: &Path) -> Vec<PathBuf> {
+    read_dir(dir.join("translates"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|v| v.path())
+        .filter(|v| v.extension() == Some(OsStr::new(RON_EXT)))
+        .collect()
+}
+
+fn parse_section(
+    map: HashMap<String, String>,
+    to_id: &mut impl FnMut(&str) -> Option<Id>,
+) -> HashMap<Id, SharedStr> {
+    map.into_iter()
+        .flat_map(|(k, v)| Some((to_id(&k)?, SharedStr::from(v))))
+        .collect()
+}
+
+/// Reads a translate file into the translation. The text of a fallback language only fills in the keys that aren't
+/// translated yet, while the selected language's replaces the text of the packs loaded before.
+fn read_translate(
+    file: &Path,
+    pseudo: bool,
+    fallback: bool,
+    into: &mut TranslateDef,
+    to_id: &mut impl FnMut(&str) -> Option<Id>,
+) -> anyhow::Result<()> {
+    log::info!("Loading translate at: {file:?}");
+
+    let mut v = ron::from_str::<Raw>(&read_to_string(file)?)?;
+    if pseudo {
+        v.pseudo_localize();
+    }
+
+    if into.none.is_empty() {
+        into.none = v.none.map(SharedStr::from).unwrap_or_default();
+    }
+    if into.unnamed.is_empty() {
+        into.unnamed = v.unnamed.map(SharedStr::from).unwrap_or_default();
+    }
+
+    for (map, new) in [
+        (&mut into.items, v.items),
+        (&mut into.tiles, v.tiles),
+        (&mut into.categories, v.categories),
+        (&mut into.scripts, v.scripts),
+        (&mut into.gui, v.gui),
+        (&mut into.keys, v.keys),
+        (&mut into.error, v.error),
+        (&mut into.research, v.research),
+    ] {
+        let new = parse_section(new, to_id);
+
+        if fallback {
+            for (id, v) in new {
+                map.entry(id).or_insert(v);
+            }
+        } else {
+            map.extend(new);
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the translates of a namespace into the translation, in the selected language, then in the default language
+/// of the pack, then in [FALLBACK_LANGUAGE].
+fn read_translates(
+    dir: &Path,
+    namespace: &str,
+    selected_language: &str,
+    into: &mut TranslateDef,
+    to_id: &mut impl FnMut(&str) -> Option<Id>,
+) -> anyhow::Result<()> {
+    let pseudo = cfg!(debug_assertions) && selected_language == PSEUDO_LANGUAGE;
+    let selected = if pseudo {
+        PSEUDO_BASE_LANGUAGE
+    } else {
+        selected_language
+    };
+
+    // the manifest was already checked when the packs were sorted
+    let default_language = PackManifest::read(dir)
+        .ok()
+        .and_then(|v| v.default_language);
+
+    let mut chain = vec![selected];
+    for lang in [default_language.as_deref(), Some(FALLBACK_LANGUAGE)]
+        .into_iter()
+        .flatten()
+    {
+        if !chain.contains(&lang) {
+            chain.push(lang);
+        }
+    }
+
+    let files = translate_files(dir);
+
+    log::info!("Loading the translates of {namespace} in {chain:?}");
+
+    for (idx, lang) in chain.into_iter().enumerate() {
+        for file in &files {
+            if file.file_stem() == Some(OsStr::new(lang)) {
+                read_translate(file, pseudo && idx == 0, idx > 0, into, to_id)?;
+            }
+        }
+    }
+
+    Ok(())
+}
 
-        if let Ok(dir) = read_dir(dir.join("translates")) {
-            for file in dir
+impl ResourceManager {
+    /// Lists the keys each language in the resources folder is missing. The keys to translate are the ones any language has,
+    /// and the names of everything registered.
+    pub fn translation_coverage(&self, resources: &Path) -> Vec<TranslationCoverage> {
+        let mut languages = HashMap::<String, HashSet<String>>::new();
+
+        for dir in read_dir(resources)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|v| v.path())
+        {
+            let Some(namespace) = dir.file_name().and_then(OsStr::to_str) else {
+                continue;
+            };
+
+            for file in read_dir(dir.join("translates"))
                 .into_iter()
                 .flatten()
+                .flatten()
                 .map(|v| v.path())
                 .filter(|v| v.extension() == Some(OsStr::new(RON_EXT)))
             {
-                if file.file_stem() == Some(lang) {
-                    self.load_translate(&file, namespace)?;
+                let Some(language) = file.file_stem().and_then(OsStr::to_str) else {
+                    continue;
+                };
+
+                match read_to_string(&file)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|v| Ok(ron::from_str::<Raw>(&v)?))
+                {
+                    Ok(raw) => languages
+                        .entry(language.to_string())
+                        .or_default()
+                        .extend(raw.translated_keys(namespace.trim())),
+                    Err(err) => log::error!("Could not read translate at {file:?}: {err}"),
                 }
             }
         }
 
+        let id_str = |id| self.interner.resolve(id).unwrap_or_default();
+
+        let mut all = languages
+            .values()
+            .flatten()
+            .cloned()
+            .collect::<HashSet<_>>();
+        all.extend(
+            self.registry
+                .tiles
+                .keys()
+                .map(|id| format!("tiles/{}", id_str(**id))),
+        );
+        all.extend(
+            self.registry
+                .items
+                .keys()
+                .map(|id| format!("items/{}", id_str(*id))),
+        );
+        all.extend(
+            self.registry
+                .categories
+                .keys()
+                .map(|id| format!("categories/{}", id_str(*id))),
+        );
+        all.extend(
+            self.registry
+                .scripts
+                .keys()
+                .map(|id| format!("scripts/{}", id_str(*id))),
+        );
+
+        let mut coverage = languages
+            .into_iter()
+            .map(|(language, keys)| {
+                let mut missing = all.difference(&keys).cloned().collect::<Vec<_>>();
+                missing.sort();
+
+                TranslationCoverage {
+                    language,
+                    total: all.len(),
+                    missing,
+                }
+            })
+            .collect::<Vec<_>>();
+        coverage.sort_by(|a, b| a.language.cmp(&b.language));
+
+        coverage
+    }
+
+    /// Loads the translates of a namespace, in the selected language, then falling back to the default language of
+    /// the pack and then to [FALLBACK_LANGUAGE], so that a partially translated pack shows the text it has instead of
+    /// the unnamed text.
+    pub fn load_translates(
+        &mut self,
+        dir: &Path,
+        namespace: &str,
+        selected_language: &str,
+    ) -> anyhow::Result<()> {
+        self.languages.extend(
+            translate_files(dir)
+                .iter()
+                .flat_map(|v| v.file_stem().and_then(OsStr::to_str).map(str::to_string)),
+        );
+        if cfg!(debug_assertions) {
+            self.languages.insert(PSEUDO_LANGUAGE.to_string());
+        }
+
+        let interner = &mut self.interner;
+        let mut translates = Arc::unwrap_or_clone(self.translates.swap(Default::default()));
+
+        let result = read_translates(
+            dir,
+            namespace,
+            selected_language,
+            &mut translates,
+            &mut |id| Id::parse(id, interner, Some(namespace)),
+        );
+
+        self.translates.store(Arc::new(translates));
+
+        result
+    }
+
+    /// Gets the loaded translation. It stays the same for as long as it's held, even if the language is switched in
+    /// the meantime.
+    pub fn translates(&self) -> Arc<TranslateDef> {
+        self.translates.load_full()
+    }
+
+    /// Loads the translates of every namespace again in another language, and swaps them in along with the tiles and
+    /// items sorted by their new names, so that the language can be changed without restarting. The old translation is
+    /// kept if a translate can't be read.
+    ///
+    /// IDs only get interned while the packs are loaded, so keys that name nothing loaded are left out.
+    pub fn reload_translations(&self, lang: &str) -> anyhow::Result<()> {
+        let mut new = TranslateDef::default();
+
+        for namespace in &self.namespaces {
+            let Some(dir) = self.namespace_dirs.get(namespace) else {
+                continue;
+            };
+
+            read_translates(dir, namespace, lang, &mut new, &mut |id| {
+                IdRaw::parse(id, Some(namespace)).and_then(|v| v.try_to_id(&self.interner))
+            })
+            .with_context(|| format!("Error loading the translates of {namespace}"))?;
+        }
+
+        self.translates.store(Arc::new(new));
+
+        self.ordered_tiles();
+        self.ordered_items();
+
+        log::info!("Switched the language to {lang}");
+
         Ok(())
     }
 
     pub fn item_name(&self, id: Id) -> SharedStr {
-        match self.translates.items.get(&id) {
+        let translates = self.translates();
+
+        match translates.items.get(&id) {
             Some(name) => name.clone(),
-            None => self.translates.unnamed.clone(),
+            None => translates.unnamed.clone(),
         }
     }
 
@@ -134,14 +484,16 @@ impl ResourceManager {
         if let Some(id) = id {
             self.item_name(id)
         } else {
-            self.translates.none.clone()
+            self.translates().none.clone()
         }
     }
 
     pub fn script_name(&self, id: Id) -> SharedStr {
-        match self.translates.scripts.get(&id) {
+        let translates = self.translates();
+
+        match translates.scripts.get(&id) {
             Some(name) => name.clone(),
-            None => self.translates.unnamed.clone(),
+            None => translates.unnamed.clone(),
         }
     }
 
@@ -149,14 +501,16 @@ impl ResourceManager {
         if let Some(id) = id {
             self.item_name(id)
         } else {
-            self.translates.none.clone()
+            self.translates().none.clone()
         }
     }
 
     pub fn tile_name(&self, id: TileId) -> SharedStr {
-        match self.translates.tiles.get(&*id) {
+        let translates = self.translates();
+
+        match translates.tiles.get(&*id) {
             Some(name) => name.clone(),
-            None => self.translates.unnamed.clone(),
+            None => translates.unnamed.clone(),
         }
     }
 
@@ -164,14 +518,16 @@ impl ResourceManager {
         if let Some(id) = id {
             self.tile_name(id)
         } else {
-            self.translates.none.clone()
+            self.translates().none.clone()
         }
     }
 
     pub fn category_name(&self, id: Id) -> SharedStr {
-        match self.translates.categories.get(&id) {
+        let translates = self.translates();
+
+        match translates.categories.get(&id) {
             Some(name) => name.clone(),
-            None => self.translates.unnamed.clone(),
+            None => translates.unnamed.clone(),
         }
     }
 
@@ -179,19 +535,23 @@ impl ResourceManager {
         if let Some(id) = id {
             self.category_name(id)
         } else {
-            self.translates.none.clone()
+            self.translates().none.clone()
         }
     }
 
     pub fn gui_str(&self, id: Id) -> SharedStr {
-        match self.translates.gui.get(&id) {
+        let translates = self.translates();
+
+        match translates.gui.get(&id) {
             Some(v) => v.clone(),
-            None => self.translates.unnamed.clone(),
+            None => translates.unnamed.clone(),
         }
     }
 
     pub fn gui_fmt<const LEN: usize>(&self, id: Id, fmt: [(&str, Formattable); LEN]) -> String {
-        match self.translates.gui.get(&id) {
+        let translates = self.translates();
+
+        match translates.gui.get(&id) {
             Some(v) => interpolator::format(v, &FormatContext::from(fmt.into_iter()))
                 .unwrap_or_else(|err| {
                     panic!(
@@ -200,14 +560,16 @@ impl ResourceManager {
                         fmt,
                     )
                 }),
-            None => self.translates.unnamed.to_string(),
+            None => translates.unnamed.to_string(),
         }
     }
 
     pub fn research_str(&self, id: Id) -> SharedStr {
-        match self.translates.research.get(&id) {
+        let translates = self.translates();
+
+        match translates.research.get(&id) {
             Some(v) => v.clone(),
-            None => self.translates.unnamed.clone(),
+            None => translates.unnamed.clone(),
         }
     }
 }
@@ -0,0 +1,76 @@
+use crate::archive::archive_namespace;
+use hashbrown::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use walkdir::WalkDir;
+
+/// How often the resource packs are checked for changes.
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watches the resource packs for changes while they're being worked on, by comparing the modification times of their
+/// files every [WATCH_INTERVAL].
+pub struct PackWatcher {
+    dir: PathBuf,
+    last_check: Instant,
+    files: HashMap<PathBuf, SystemTime>,
+}
+
+impl PackWatcher {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            last_check: Instant::now(),
+            files: scan(dir),
+        }
+    }
+
+    /// Checks the packs for added, changed or removed files, if it's time to. Returns the namespaces they are in,
+    /// which is empty if nothing changed.
+    pub fn poll(&mut self) -> Vec<String> {
+        if self.last_check.elapsed() < WATCH_INTERVAL {
+            return vec![];
+        }
+        self.last_check = Instant::now();
+
+        let files = scan(&self.dir);
+
+        let mut changed = files
+            .iter()
+            .filter(|(path, time)| self.files.get(*path) != Some(*time))
+            .map(|(path, _)| path)
+            .chain(self.files.keys().filter(|path| !files.contains_key(*path)))
+            .flat_map(|path| namespace_of(&self.dir, path))
+            .collect::<Vec<_>>();
+        changed.sort();
+        changed.dedup();
+
+        self.files = files;
+
+        changed
+    }
+}
+
+/// Gets the modification time of every file in the folder.
+fn scan(dir: &Path) -> HashMap<PathBuf, SystemTime> {
+    WalkDir::new(dir)
+        .follow_links(false)
+        .into_iter()
+        .flatten()
+        .filter(|v| v.file_type().is_file())
+        .flat_map(|v| {
+            let time = v.metadata().ok()?.modified().ok()?;
+
+            Some((v.into_path(), time))
+        })
+        .collect()
+}
+
+/// Gets the namespace a file of the packs is in, which is the name of the first folder under the packs' folder, or
+/// the name of the archive the pack is in.
+fn namespace_of(dir: &Path, path: &Path) -> Option<String> {
+    let first = path.strip_prefix(dir).ok()?.components().next()?;
+
+    archive_namespace(Path::new(&first))
+        .or_else(|| first.as_os_str().to_str())
+        .map(str::to_string)
+}
@@ -0,0 +1,81 @@
+use automancy_resources::expr::{eval_expr, ExprError, ExprNumber};
+use ron::{Number, Value};
+
+fn eval(src: &str) -> Result<ExprNumber, ExprError> {
+    eval_expr(src, |name| match name {
+        "core:speed" => Some(Value::Number(Number::from(4))),
+        "core:ratio" => Some(Value::Number(Number::from(0.5))),
+        "core:name" => Some(Value::String("speed".to_string())),
+        _ => None,
+    })
+}
+
+#[test]
+fn test_expr_precedence() {
+    assert_eq!(eval("1 + 2 * 3"), Ok(ExprNumber::Int(7)));
+    assert_eq!(eval("(1 + 2) * 3"), Ok(ExprNumber::Int(9)));
+    assert_eq!(eval("10 - 4 - 3"), Ok(ExprNumber::Int(3)));
+    assert_eq!(eval("2 * 9 / 4 % 3"), Ok(ExprNumber::Int(1)));
+    assert_eq!(eval("7 / 2"), Ok(ExprNumber::Int(3)));
+    assert_eq!(eval("1 + 2.5"), Ok(ExprNumber::Float(3.5)));
+    assert_eq!(eval("1_000 * 2"), Ok(ExprNumber::Int(2000)));
+}
+
+#[test]
+fn test_expr_unary_minus() {
+    assert_eq!(eval("-3"), Ok(ExprNumber::Int(-3)));
+    assert_eq!(eval("--3"), Ok(ExprNumber::Int(3)));
+    assert_eq!(eval("2 * -3"), Ok(ExprNumber::Int(-6)));
+    assert_eq!(eval("1 - -1"), Ok(ExprNumber::Int(2)));
+    assert_eq!(eval("-(1 + 2)"), Ok(ExprNumber::Int(-3)));
+    assert_eq!(eval("-0.5"), Ok(ExprNumber::Float(-0.5)));
+}
+
+#[test]
+fn test_expr_constants() {
+    assert_eq!(eval("core:speed * 2"), Ok(ExprNumber::Int(8)));
+    assert_eq!(eval("core:speed * core:ratio"), Ok(ExprNumber::Float(2.0)));
+    assert_eq!(
+        eval("core:missing + 1"),
+        Err(ExprError::UnknownConstant("core:missing".to_string()))
+    );
+    assert_eq!(
+        eval("core:name"),
+        Err(ExprError::NotANumber("core:name".to_string()))
+    );
+}
+
+#[test]
+fn test_expr_division_by_zero() {
+    assert_eq!(eval("1 / 0"), Err(ExprError::DivisionByZero));
+    assert_eq!(eval("1 % 0"), Err(ExprError::DivisionByZero));
+    assert_eq!(eval("1.5 / 0.0"), Err(ExprError::DivisionByZero));
+    assert_eq!(eval("1 / (2 - 2)"), Err(ExprError::DivisionByZero));
+}
+
+#[test]
+fn test_expr_overflow() {
+    assert_eq!(eval("9223372036854775807 + 1"), Err(ExprError::Overflow));
+    assert_eq!(eval("9223372036854775807 * 2"), Err(ExprError::Overflow));
+    assert_eq!(eval("-9223372036854775807 - 2"), Err(ExprError::Overflow));
+    assert_eq!(
+        eval("-(-9223372036854775807 - 1)"),
+        Err(ExprError::Overflow)
+    );
+    assert_eq!(
+        eval("(-9223372036854775807 - 1) / -1"),
+        Err(ExprError::Overflow)
+    );
+}
+
+#[test]
+fn test_expr_malformed() {
+    assert_eq!(eval(""), Err(ExprError::UnexpectedEnd));
+    assert_eq!(eval("1 +"), Err(ExprError::UnexpectedEnd));
+    assert_eq!(eval("(1 + 2"), Err(ExprError::UnexpectedEnd));
+    assert_eq!(eval("1 2"), Err(ExprError::UnexpectedChar('2', 2)));
+    assert_eq!(eval("1 + )"), Err(ExprError::UnexpectedChar(')', 4)));
+    assert_eq!(eval("(1 2)"), Err(ExprError::UnexpectedChar('2', 3)));
+    assert_eq!(eval("1..2"), Err(ExprError::UnexpectedChar('1', 0)));
+    assert_eq!(eval("1 $ 2"), Err(ExprError::UnexpectedChar('$', 2)));
+}
@@ -0,0 +1,117 @@
+use automancy_resources::types::pack::{LoadOrderError, PACK_MANIFEST};
+use automancy_resources::ResourceManager;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Writes the packs into a fresh folder, each with its manifest if it has one, and sorts them.
+fn sort(test: &str, packs: &[(&str, Option<&str>)]) -> anyhow::Result<Vec<String>> {
+    let root = env::temp_dir().join(format!("automancy-packs-{test}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+
+    let packs = packs
+        .iter()
+        .map(|(namespace, manifest)| {
+            let dir = root.join(namespace);
+            fs::create_dir_all(&dir).unwrap();
+
+            if let Some(manifest) = manifest {
+                fs::write(dir.join(PACK_MANIFEST), manifest).unwrap();
+            }
+
+            (namespace.to_string(), dir)
+        })
+        .collect::<Vec<(String, PathBuf)>>();
+
+    let sorted = ResourceManager::sort_namespaces(packs);
+    fs::remove_dir_all(&root).unwrap();
+
+    Ok(sorted?
+        .into_iter()
+        .map(|(namespace, _)| namespace)
+        .collect())
+}
+
+#[test]
+fn test_sort_namespaces_independent() {
+    // in the order of their namespaces, whatever order they were found in
+    assert_eq!(
+        sort("independent", &[("c", None), ("a", None), ("b", None)]).unwrap(),
+        vec!["a", "b", "c"]
+    );
+    assert_eq!(
+        sort(
+            "independent_again",
+            &[("b", None), ("c", None), ("a", None)]
+        )
+        .unwrap(),
+        vec!["a", "b", "c"]
+    );
+}
+
+#[test]
+fn test_sort_namespaces_dependencies() {
+    assert_eq!(
+        sort(
+            "dependencies",
+            &[
+                ("a", Some(r#"(depends_on: ["core"], load_after: ["z"])"#)),
+                ("core", None),
+                ("z", Some(r#"(depends_on: ["core"])"#)),
+                ("b", None),
+            ]
+        )
+        .unwrap(),
+        vec!["b", "core", "z", "a"]
+    );
+}
+
+#[test]
+fn test_sort_namespaces_missing_load_after() {
+    assert_eq!(
+        sort(
+            "missing_load_after",
+            &[("b", Some(r#"(load_after: ["gone", "a"])"#)), ("a", None)]
+        )
+        .unwrap(),
+        vec!["a", "b"]
+    );
+}
+
+#[test]
+fn test_sort_namespaces_missing_dependency() {
+    let err = sort(
+        "missing_dependency",
+        &[("a", None), ("b", Some(r#"(depends_on: ["a", "gone"])"#))],
+    )
+    .unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<LoadOrderError>(),
+        Some(LoadOrderError::MissingDependency(namespace, missing)) if namespace == "b" && missing == "gone"
+    ));
+}
+
+#[test]
+fn test_sort_namespaces_cycle() {
+    let err = sort(
+        "cycle",
+        &[
+            ("a", Some(r#"(depends_on: ["b"])"#)),
+            ("b", Some(r#"(load_after: ["c"])"#)),
+            ("c", Some(r#"(depends_on: ["b"])"#)),
+            ("d", None),
+        ],
+    )
+    .unwrap_err();
+
+    // only the packs in the cycle are reported, starting from the first one reached
+    assert!(matches!(
+        err.downcast_ref::<LoadOrderError>(),
+        Some(LoadOrderError::Cycle(cycle)) if cycle == &["b", "c", "b"]
+    ));
+    assert_eq!(
+        err.to_string(),
+        "the packs have to be loaded after each other in a cycle: b -> c -> b"
+    );
+}
@@ -0,0 +1,186 @@
+use automancy_defs::coord::{TileBounds, TileCoord};
+use automancy_defs::glam::{vec2, Vec2};
+use automancy_defs::id::{Id, Interner, ModelId};
+use automancy_defs::math::{Float, HEX_GRID_LAYOUT};
+use automancy_resources::data::{DataMap, DataMapRaw};
+use automancy_resources::profiler::SCRIPT_PROFILER;
+use automancy_resources::types::function::{AgentResult, AGENT_TICK_FUNCTION};
+use automancy_resources::{rhai_call_options, rhai_log_err, ResourceManager};
+use rand::random;
+use rhai::{Dynamic, Scope};
+use serde::{Deserialize, Serialize};
+
+/// A mobile agent on the map, like a delivery drone. It isn't bound to a tile, and moves freely between them.
+#[derive(Debug, Clone)]
+pub struct Agent {
+    /// the ID of its AgentDef
+    pub id: Id,
+    /// where it is, in world coordinates
+    pub pos: Vec2,
+    /// where it was before the last tick, to move smoothly between the two
+    pub last_pos: Vec2,
+    /// where it is moving to, if anywhere
+    pub target: Option<TileCoord>,
+    pub data: DataMap,
+}
+
+/// An agent as it is saved in a map.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentRaw {
+    pub id: String,
+    pub pos: (Float, Float),
+    #[serde(default)]
+    pub target: Option<TileCoord>,
+    #[serde(default)]
+    pub data: DataMapRaw,
+}
+
+/// What the renderer needs to draw an agent: its model, and where it moved from and to in the last tick.
+pub type AgentRender = (ModelId, Vec2, Vec2);
+
+/// The distance between the centers of two neighboring tiles, in world coordinates.
+fn tile_distance() -> Float {
+    HEX_GRID_LAYOUT
+        .hex_to_world_pos(*TileCoord::RIGHT)
+        .distance(HEX_GRID_LAYOUT.hex_to_world_pos(*TileCoord::ZERO))
+}
+
+impl Agent {
+    pub fn new(id: Id, coord: TileCoord, data: DataMap) -> Self {
+        let pos = HEX_GRID_LAYOUT.hex_to_world_pos(*coord);
+
+        Self {
+            id,
+            pos,
+            last_pos: pos,
+            target: None,
+            data,
+        }
+    }
+
+    /// Gets the tile the agent is over.
+    pub fn coord(&self) -> TileCoord {
+        HEX_GRID_LAYOUT.world_pos_to_hex(self.pos).into()
+    }
+
+    pub fn to_raw(&self, interner: &Interner) -> Option<AgentRaw> {
+        Some(AgentRaw {
+            id: interner.resolve(self.id)?.to_string(),
+            pos: (self.pos.x, self.pos.y),
+            target: self.target,
+            data: self.data.to_raw(interner),
+        })
+    }
+
+    /// Creates an agent from its saved form. Returns None if its kind no longer exists.
+    pub fn from_raw(resource_man: &ResourceManager, raw: AgentRaw) -> Option<Self> {
+        let id = resource_man
+            .interner
+            .get(raw.id.as_str())
+            .filter(|id| resource_man.registry.agents.contains_key(id))?;
+        let pos = vec2(raw.pos.0, raw.pos.1);
+
+        Some(Self {
+            id,
+            pos,
+            last_pos: pos,
+            target: raw.target,
+            data: raw.data.to_data(&resource_man.interner),
+        })
+    }
+
+    /// Moves the agent toward its target, and asks its function where to go next once it has none.
+    /// Returns false if the agent should be removed.
+    fn tick(&mut self, resource_man: &ResourceManager) -> bool {
+        self.last_pos = self.pos;
+
+        let Some(def) = resource_man.registry.agents.get(&self.id) else {
+            return false;
+        };
+
+        if let Some(target) = self.target {
+            let target_pos = HEX_GRID_LAYOUT.hex_to_world_pos(*target);
+            let step = def.speed * tile_distance();
+
+            if self.pos.distance(target_pos) <= step {
+                self.pos = target_pos;
+                self.target = None;
+            } else {
+                self.pos += (target_pos - self.pos).normalize() * step;
+            }
+
+            return true;
+        }
+
+        let Some((ast, metadata)) = def
+            .function
+            .as_ref()
+            .and_then(|v| resource_man.functions.get(v))
+        else {
+            return true;
+        };
+
+        let coord = self.coord();
+        let mut rhai_state = Dynamic::from(self.data.clone());
+
+        let input = rhai::Map::from([
+            ("id".into(), Dynamic::from(self.id)),
+            ("coord".into(), Dynamic::from(coord)),
+            ("random".into(), Dynamic::from_int(random())),
+            ("setup".into(), Dynamic::from(def.data.clone())),
+        ]);
+
+        let profiled = SCRIPT_PROFILER.start(&metadata.str_id, AGENT_TICK_FUNCTION);
+        let result = resource_man.engine.call_fn_with_options::<Dynamic>(
+            rhai_call_options(&mut rhai_state),
+            &mut Scope::new(),
+            ast,
+            AGENT_TICK_FUNCTION,
+            (input,),
+        );
+        if let Some(profiled) = profiled {
+            profiled.finish();
+        }
+
+        self.data = rhai_state.cast::<DataMap>();
+
+        match result {
+            Ok(result) => match result.try_cast::<AgentResult>() {
+                Some(AgentResult::MoveTo(target)) => {
+                    self.target = Some(target);
+                    true
+                }
+                Some(AgentResult::Despawn) => false,
+                None => true,
+            },
+            Err(err) => {
+                rhai_log_err(AGENT_TICK_FUNCTION, &metadata.str_id, &err, Some(coord));
+                true
+            }
+        }
+    }
+}
+
+/// Runs a tick of every agent, removing the ones that despawned or whose kind no longer exists.
+pub fn tick_agents(resource_man: &ResourceManager, agents: &mut Vec<Agent>) {
+    agents.retain_mut(|agent| agent.tick(resource_man));
+}
+
+/// Gets what the renderer needs to draw the agents within the culling range.
+pub fn render_agents(
+    resource_man: &ResourceManager,
+    agents: &[Agent],
+    culling_range: TileBounds,
+) -> Vec<AgentRender> {
+    agents
+        .iter()
+        .filter(|agent| culling_range.contains(agent.coord()))
+        .flat_map(|agent| {
+            resource_man
+                .registry
+                .agents
+                .get(&agent.id)
+                .map(|def| (def.model, agent.last_pos, agent.pos))
+        })
+        .collect()
+}
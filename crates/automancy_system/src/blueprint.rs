@@ -0,0 +1,106 @@
+use crate::map::find_tile;
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::{Interner, TileId};
+use automancy_resources::data::{DataMap, DataMapRaw};
+use automancy_resources::ResourceManager;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::{fs, io};
+
+/// Where blueprints are saved, each in its own file.
+pub static BLUEPRINT_PATH: &str = "blueprints";
+pub static BLUEPRINT_EXT: &str = "blueprint.ron";
+
+/// A group of tiles, in the same form as what is copied to be pasted.
+pub type BlueprintTiles = Vec<(TileCoord, TileId, Option<DataMap>)>;
+
+/// A group of tiles saved to be placed again, on any map.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlueprintRaw {
+    /// The tiles, relative to where they were copied from, by their ID strings and with their data.
+    pub tiles: Vec<(TileCoord, String, DataMapRaw)>,
+}
+
+/// Gets the path to a blueprint from its name, which should be sanitized.
+pub fn blueprint_path(name: &str) -> PathBuf {
+    PathBuf::from(BLUEPRINT_PATH).join(format!("{name}.{BLUEPRINT_EXT}"))
+}
+
+/// Lists the names of the saved blueprints, in order.
+pub fn list_blueprints() -> Vec<String> {
+    let mut names = fs::read_dir(BLUEPRINT_PATH)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .flat_map(|v| {
+            v.file_name()
+                .to_str()?
+                .strip_suffix(BLUEPRINT_EXT)?
+                .strip_suffix('.')
+                .map(str::to_string)
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+
+    names
+}
+
+/// Saves the tiles as a blueprint, relative to the origin, over the blueprint of the same name if there is one.
+pub fn save_blueprint(
+    interner: &Interner,
+    name: &str,
+    origin: TileCoord,
+    tiles: &[(TileCoord, TileId, Option<DataMap>)],
+) -> anyhow::Result<()> {
+    let raw = BlueprintRaw {
+        tiles: tiles
+            .iter()
+            .flat_map(|(coord, id, data)| {
+                Some((
+                    *coord - origin,
+                    interner.resolve(**id)?.to_string(),
+                    data.as_ref()
+                        .map(|v| v.to_raw(interner))
+                        .unwrap_or_default(),
+                ))
+            })
+            .collect(),
+    };
+
+    fs::create_dir_all(BLUEPRINT_PATH)?;
+    fs::write(
+        blueprint_path(name),
+        ron::ser::to_string_pretty(&raw, Default::default())?,
+    )?;
+
+    log::info!("Saved blueprint {name} with {} tiles", raw.tiles.len());
+
+    Ok(())
+}
+
+/// Loads a blueprint, with its tiles relative to [TileCoord::ZERO]. Tiles that are no longer registered are left out.
+pub fn load_blueprint(
+    resource_man: &ResourceManager,
+    name: &str,
+) -> anyhow::Result<BlueprintTiles> {
+    let raw: BlueprintRaw = ron::from_str(&fs::read_to_string(blueprint_path(name))?)?;
+
+    Ok(raw
+        .tiles
+        .into_iter()
+        .flat_map(|(coord, id, data)| {
+            let Some(tile) = find_tile(resource_man, &id) else {
+                log::warn!(
+                    "Leaving {id} at {coord} out of blueprint {name}, as it isn't registered"
+                );
+                return None;
+            };
+
+            Some((coord, tile, Some(data.to_data(&resource_man.interner))))
+        })
+        .collect())
+}
+
+pub fn delete_blueprint(name: &str) -> io::Result<()> {
+    fs::remove_file(blueprint_path(name))
+}
@@ -8,6 +8,12 @@ use automancy_defs::{
     math::Matrix4,
 };
 use std::ops::Mul;
+use std::time::{Duration, Instant};
+
+/// How far, in world units, the strongest camera shake moves the camera.
+const MAX_SHAKE_OFFSET: Float = 0.15;
+/// How quickly the camera catches up with what it follows, as the fraction of the distance covered per second.
+const FOLLOW_SPEED: Float = 8.0;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct GameCamera {
@@ -17,7 +23,11 @@ pub struct GameCamera {
 
     pub culling_range: TileBounds,
     pub pointing_at: TileCoord,
+    /// the tile pointed at before the current one, and when the pointer moved away from it
+    pub last_pointing_at: (TileCoord, Instant),
     matrix: Matrix4,
+    /// the strength of the current shake, how long it lasts, and when it started
+    shake: Option<(Float, Duration, Instant)>,
 }
 
 pub fn fit_z(mut z: Float) -> Float {
@@ -48,7 +58,9 @@ impl GameCamera {
 
             culling_range: math::get_culling_range((width, height), fit_pos(pos)),
             pointing_at: TileCoord::new(0, 0),
+            last_pointing_at: (TileCoord::new(0, 0), Instant::now()),
             matrix,
+            shake: None,
         }
     }
 
@@ -69,7 +81,65 @@ impl GameCamera {
             math::main_pos_to_fract_hex((width, height), main_pos, self.get_pos()).to_array(),
         );
 
-        self.pointing_at = p.into();
+        let p = p.into();
+
+        if p != self.pointing_at {
+            self.last_pointing_at = (self.pointing_at, Instant::now());
+        }
+
+        self.pointing_at = p;
+    }
+
+    /// Moves the camera over the tile, keeping the zoom.
+    pub fn jump_to(&mut self, coord: TileCoord) {
+        let p = HEX_GRID_LAYOUT.hex_to_world_pos(*coord);
+
+        self.pos.x = p.x;
+        self.pos.y = p.y;
+        self.move_vel = Vec2::ZERO;
+    }
+
+    /// Glides the camera toward a position, to follow something moving.
+    pub fn follow(&mut self, target: Vec2, elapsed: Float) {
+        let t = (elapsed * FOLLOW_SPEED).min(1.0);
+
+        self.pos.x += (target.x - self.pos.x) * t;
+        self.pos.y += (target.y - self.pos.y) * t;
+        self.move_vel = Vec2::ZERO;
+    }
+
+    /// Gets the position the camera is moved to, before it's fitted to the zoom.
+    pub fn raw_pos(&self) -> Vec3 {
+        self.pos
+    }
+
+    /// Moves the camera along the way from one position to another, before they're fitted to the zoom, easing in and
+    /// out. t is from 0 to 1.
+    pub fn glide(&mut self, from: Vec3, to: Vec3, t: Float) {
+        let t = t.clamp(0.0, 1.0);
+        let t = t * t * (3.0 - 2.0 * t);
+
+        self.pos = from.lerp(to, t);
+        self.pos.z = self.pos.z.clamp(0.05, 4.0);
+        self.move_vel = Vec2::ZERO;
+        self.scroll_vel = 0.0;
+    }
+
+    /// Shakes the camera, unless it is already shaking harder.
+    pub fn shake(&mut self, strength: Float, duration: Duration) {
+        if self.shake_strength() <= strength {
+            self.shake = Some((strength, duration, Instant::now()));
+        }
+    }
+
+    /// Gets the strength of the current shake, fading out towards its end.
+    fn shake_strength(&self) -> Float {
+        match self.shake {
+            Some((strength, duration, start)) if start.elapsed() < duration => {
+                strength * (1.0 - start.elapsed().as_secs_f32() / duration.as_secs_f32())
+            }
+            _ => 0.0,
+        }
     }
 
     /// Gets the TileCoord the camera is pointing at.
@@ -110,7 +180,18 @@ impl GameCamera {
             self.scroll_vel -= self.scroll_vel * elapsed.mul(15.0).min(0.9);
         }
 
-        self.matrix = camera_matrix(self.get_pos(), width / height);
+        let shake = self.shake_strength();
+        let shake_offset = if shake > 0.0 {
+            let t = self.shake.unwrap().2.elapsed().as_secs_f32();
+
+            vec3((t * 47.0).sin(), (t * 61.0).cos(), 0.0) * shake * MAX_SHAKE_OFFSET
+        } else {
+            self.shake = None;
+
+            Vec3::ZERO
+        };
+
+        self.matrix = camera_matrix(self.get_pos() + shake_offset, width / height);
         self.culling_range = math::get_culling_range((width, height), self.get_pos());
     }
 
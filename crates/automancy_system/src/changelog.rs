@@ -0,0 +1,259 @@
+use automancy_defs::{
+    id::{Id, Interner},
+    math::Float,
+    stack::{ItemAmount, ItemStack},
+};
+use automancy_resources::{types::tile::TileDef, ResourceManager};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Where the registry of the last launch is kept, to tell what changed since.
+pub static LAST_REGISTRY_PATH: &str = "registry.json";
+/// Where the changes are exported to from the game, as release notes.
+pub static RELEASE_NOTES_PATH: &str = "whats_new.md";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TileExport {
+    pub function: Option<String>,
+    pub category: Option<String>,
+    pub scenery: bool,
+    /// the setup data of the tile, as it is written in the tile's file
+    pub data: serde_json::Value,
+}
+
+impl TileExport {
+    pub fn new(tile: &TileDef, interner: &Interner) -> Self {
+        let id_str = |id: Id| interner.resolve(id).unwrap_or_default().to_string();
+
+        Self {
+            function: tile.function.map(id_str),
+            category: tile.category.map(id_str),
+            scenery: tile.scenery.is_some(),
+            // sorted, as the sets and maps in it are hashed, and would come out in a different order on every launch
+            data: serde_json::to_value(tile.data.to_raw(interner).sorted()).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemExport {
+    pub model: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackExport {
+    pub id: String,
+    pub amount: ItemAmount,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldConditionExport {
+    pub field: String,
+    pub min: Option<Float>,
+    pub max: Option<Float>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScriptExport {
+    pub inputs: Option<Vec<StackExport>>,
+    pub outputs: Vec<StackExport>,
+    pub conditions: Vec<FieldConditionExport>,
+}
+
+/// The tiles, items and recipes of the loaded packs, keyed by their IDs. Only IDs are written, as the numbers they are
+/// interned as differ between launches.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RegistryExport {
+    pub tiles: BTreeMap<String, TileExport>,
+    pub items: BTreeMap<String, ItemExport>,
+    pub scripts: BTreeMap<String, ScriptExport>,
+}
+
+impl RegistryExport {
+    pub fn new(resource_man: &ResourceManager) -> Self {
+        let id_str = |id: Id| {
+            resource_man
+                .interner
+                .resolve(id)
+                .unwrap_or_default()
+                .to_string()
+        };
+        let stacks = |stacks: &[ItemStack]| {
+            stacks
+                .iter()
+                .map(|stack| StackExport {
+                    id: id_str(stack.id),
+                    amount: stack.amount,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        Self {
+            tiles: resource_man
+                .registry
+                .tiles
+                .iter()
+                .map(|(id, tile)| (id_str(**id), TileExport::new(tile, &resource_man.interner)))
+                .collect(),
+            items: resource_man
+                .registry
+                .items
+                .iter()
+                .map(|(id, item)| {
+                    (
+                        id_str(*id),
+                        ItemExport {
+                            model: id_str(*item.model),
+                        },
+                    )
+                })
+                .collect(),
+            scripts: resource_man
+                .registry
+                .scripts
+                .iter()
+                .map(|(id, script)| {
+                    let instructions = &script.instructions;
+
+                    (
+                        id_str(*id),
+                        ScriptExport {
+                            inputs: instructions.inputs.as_deref().map(stacks),
+                            outputs: stacks(&instructions.outputs),
+                            conditions: instructions
+                                .conditions
+                                .iter()
+                                .map(|v| FieldConditionExport {
+                                    field: id_str(v.field),
+                                    min: v.min,
+                                    max: v.max,
+                                })
+                                .collect(),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, self).map_err(io::Error::other)?;
+        writer.flush()
+    }
+}
+
+/// The IDs of one kind of registry entry that were added, removed or changed, sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl CategoryDiff {
+    fn between<T: PartialEq>(old: &BTreeMap<String, T>, new: &BTreeMap<String, T>) -> Self {
+        Self {
+            added: new
+                .keys()
+                .filter(|id| !old.contains_key(*id))
+                .cloned()
+                .collect(),
+            removed: old
+                .keys()
+                .filter(|id| !new.contains_key(*id))
+                .cloned()
+                .collect(),
+            changed: new
+                .iter()
+                .filter(|(id, v)| old.get(*id).is_some_and(|old| old != *v))
+                .map(|(id, _)| id.clone())
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    fn write_markdown(&self, out: &mut String, heading: &str) {
+        if self.is_empty() {
+            return;
+        }
+
+        _ = writeln!(out, "## {heading}\n");
+        for (change, ids) in [
+            ("Added", &self.added),
+            ("Removed", &self.removed),
+            ("Changed", &self.changed),
+        ] {
+            for id in ids {
+                _ = writeln!(out, "- {change} `{id}`");
+            }
+        }
+        _ = writeln!(out);
+    }
+}
+
+/// What changed in the registry between two exports of it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryDiff {
+    pub tiles: CategoryDiff,
+    pub items: CategoryDiff,
+    pub scripts: CategoryDiff,
+}
+
+impl RegistryDiff {
+    pub fn between(old: &RegistryExport, new: &RegistryExport) -> Self {
+        Self {
+            tiles: CategoryDiff::between(&old.tiles, &new.tiles),
+            items: CategoryDiff::between(&old.items, &new.items),
+            scripts: CategoryDiff::between(&old.scripts, &new.scripts),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty() && self.items.is_empty() && self.scripts.is_empty()
+    }
+
+    /// Renders the changes as Markdown, to paste into the release notes of a pack.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# What's new\n\n");
+
+        if self.is_empty() {
+            out.push_str("Nothing changed.\n");
+        }
+
+        self.tiles.write_markdown(&mut out, "Tiles");
+        self.items.write_markdown(&mut out, "Items");
+        self.scripts.write_markdown(&mut out, "Recipes");
+
+        out
+    }
+}
+
+/// Compares the registry to the one of the last launch, then remembers it for the next. Returns what changed, or None
+/// on the first launch or if nothing did.
+pub fn registry_changes(resource_man: &ResourceManager) -> Option<RegistryDiff> {
+    let path = Path::new(LAST_REGISTRY_PATH);
+    let current = RegistryExport::new(resource_man);
+
+    let diff = RegistryExport::read(path)
+        .ok()
+        .map(|last| RegistryDiff::between(&last, &current))
+        .filter(|diff| !diff.is_empty());
+
+    if let Err(err) = current.write(path) {
+        log::error!("Could not write the registry to {LAST_REGISTRY_PATH}: {err}");
+    }
+
+    diff
+}
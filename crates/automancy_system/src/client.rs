@@ -0,0 +1,271 @@
+use crate::camera::GameCamera;
+use crate::game::{tick_interval, tps_to_interval, GameSystemMessage};
+use crate::input::{ActionType, InputHandler};
+use crate::map::{GameMap, LoadMapOption, MapInfo, MapInfoRaw};
+use crate::options::{GameOptions, MiscOptions};
+use crate::quality::QualityGovernor;
+use crate::signing::SaveSignature;
+use crate::tile_entity::{TileEntityMsg, TileEntityWithId};
+use crate::ui_state::{PopupState, UiState};
+use crate::util::{map_offline_progress, map_tick_rate};
+use automancy_defs::{
+    coord::TileCoord,
+    id::Id,
+    kira::manager::AudioManager,
+    math::{Float, Vec2, HEX_GRID_LAYOUT},
+    rendering::Vertex,
+};
+use automancy_resources::{data::DataMap, types::item::ItemDef, ResourceManager};
+use cosmic_text::fontdb::Source;
+use hashbrown::HashMap;
+use ractor::ActorRef;
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{atomic::AtomicBool, Arc},
+    time::{Duration, Instant, SystemTime},
+};
+use tokio::{runtime::Runtime, sync::Mutex, task::JoinHandle};
+use wgpu::{Device, Queue};
+use winit::window::Window;
+use yakui::{font::Fonts, ManagedTextureId, Yakui};
+use yakui_wgpu::YakuiWgpu;
+use yakui_winit::YakuiWinit;
+
+pub struct GameGui<YakuiResources> {
+    pub renderer: YakuiWgpu<YakuiResources>,
+    pub yak: Yakui,
+    pub window: YakuiWinit,
+    pub fonts: HashMap<String, Source>,
+}
+
+impl<T> GameGui<T> {
+    /// Sets the fonts text is rendered with. The first font is used for all text, and the rest, then the symbols font,
+    /// are fallen back to for the characters it doesn't have.
+    pub fn set_fonts(&mut self, symbols_font: &str, chain: &[String]) {
+        let Some(font) = chain.first() else {
+            log::error!("No font to set!");
+            return;
+        };
+
+        log::info!("Setting fonts to {chain:?}");
+
+        let sources = chain
+            .iter()
+            .map(String::as_str)
+            .chain([symbols_font])
+            .filter_map(|name| {
+                let source = self.fonts.get(name).cloned();
+
+                if source.is_none() {
+                    log::warn!("Font {name} isn't loaded, leaving it out");
+                }
+
+                source
+            })
+            .collect::<Vec<_>>();
+
+        let fonts = self.yak.dom().get_global_or_init(Fonts::default);
+
+        for source in sources {
+            fonts.load_font_source(source);
+        }
+
+        fonts.set_sans_serif_family(font);
+        fonts.set_serif_family(font);
+        fonts.set_monospace_family(font);
+        fonts.set_cursive_family(font);
+        fonts.set_fantasy_family(font);
+    }
+
+    pub fn new(device: &Device, queue: &Queue, window: &Window) -> Self {
+        let mut yak = Yakui::new();
+        let renderer = yakui_wgpu::YakuiWgpu::new(&mut yak, device, queue);
+        let window = yakui_winit::YakuiWinit::new(window);
+
+        Self {
+            renderer,
+            yak,
+            window,
+            fonts: Default::default(),
+        }
+    }
+}
+
+/// Stores information that lives for the entire lifetime of the session, and is not dropped at the end of one event cycle or handled elsewhere.
+#[derive(Debug, Default)]
+pub struct EventLoopStorage {
+    /// tag searching cache
+    pub tag_cache: HashMap<Id, Arc<Vec<ItemDef>>>,
+    /// the last frame's starting time
+    pub frame_start: Option<Instant>,
+    /// the elapsed time between each frame
+    pub elapsed: Duration,
+
+    pub map_infos_cache: Vec<((MapInfoRaw, Option<SystemTime>), String)>,
+    /// the folders the saves are organized into, by their paths in the map folder
+    pub map_folders_cache: Vec<String>,
+    /// whether each map in the cache was signed, and still matches its signature
+    pub map_signatures: HashMap<String, SaveSignature>,
+    pub map_info: Option<(Arc<Mutex<MapInfo>>, LoadMapOption)>,
+
+    pub config_open_cache: Arc<Mutex<Option<ActorRef<TileEntityMsg>>>>,
+    pub config_open_updating: Arc<AtomicBool>,
+    pub pointing_cache: Arc<Mutex<Option<TileEntityWithId>>>,
+    pub pointing_updating: Arc<AtomicBool>,
+
+    /// the progress bars moving from their previous fraction to the latest one, and when the latest one arrived
+    pub progress_interpolations: HashMap<TileCoord, (Float, Float, Instant)>,
+    /// how much the visual effects are cut back, following how fast the frames are
+    pub quality: QualityGovernor,
+}
+
+pub struct InnerGameState<YakuiResources, Renderer> {
+    pub ui_state: UiState,
+    pub options: GameOptions,
+    pub misc_options: MiscOptions,
+    pub resource_man: Arc<ResourceManager>,
+    pub input_handler: InputHandler,
+    pub loop_store: EventLoopStorage,
+    pub tokio: Runtime,
+    pub game: ActorRef<GameSystemMessage>,
+    pub camera: GameCamera,
+    pub audio_man: AudioManager,
+    pub start_instant: Instant,
+
+    pub gui: Option<GameGui<YakuiResources>>,
+    pub renderer: Option<Renderer>,
+    pub screenshotting: bool,
+    /// where the next screenshot is saved, instead of being copied to the clipboard
+    pub screenshot_path: Option<PathBuf>,
+
+    pub logo: Option<ManagedTextureId>,
+    pub input_hints: Vec<Vec<ActionType>>,
+    pub puzzle_state: Option<(DataMap, bool)>,
+
+    pub game_handle: Option<JoinHandle<()>>,
+
+    /// the vertices and indices of every model, kept to upload them again if the graphics device is lost
+    pub vertices_init: Option<Vec<Vertex>>,
+    pub indices_init: Option<Vec<u16>>,
+}
+
+impl<A, B> InnerGameState<A, B> {
+    pub fn ui_viewport(&self) -> Vec2 {
+        self.gui
+            .as_ref()
+            .unwrap()
+            .yak
+            .layout_dom()
+            .viewport()
+            .size()
+    }
+
+    /// Plays a sound at full volume, such as for the GUI.
+    pub fn play_sound(&mut self, name: &str) {
+        if let Some(sound) = self.resource_man.sound(name) {
+            if let Err(err) = self.audio_man.play(sound) {
+                log::warn!("Could not play sound {name}: {err}");
+            }
+        }
+    }
+
+    /// Plays a sound coming from a tile, quieter the further the tile is from the camera, as set by the sound's category.
+    pub fn play_sound_at(&mut self, name: &str, coord: TileCoord) {
+        let pos = HEX_GRID_LAYOUT.hex_to_world_pos(*coord).extend(0.0);
+        let distance = self.camera.get_pos().distance(pos) as f64;
+
+        if let Some(sound) = self.resource_man.sound_at(name, distance) {
+            if let Err(err) = self.audio_man.play(sound) {
+                log::warn!("Could not play sound {name}: {err}");
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameLoadResult {
+    Loaded,
+    LoadedMainMenu,
+    Failed,
+}
+
+/// Gets how many ticks a map catches up on when loaded, for the time since it was saved, at most as many minutes as its
+/// offline progress rule allows.
+fn offline_ticks(resource_man: &ResourceManager, game_data: &DataMap, saved_at: SystemTime) -> u64 {
+    let Some(minutes) = map_offline_progress(resource_man, game_data) else {
+        return 0;
+    };
+
+    let elapsed = SystemTime::now()
+        .duration_since(saved_at)
+        .unwrap_or_default()
+        .min(Duration::from_secs(minutes as u64 * 60));
+    let interval = map_tick_rate(resource_man, game_data)
+        .map(tps_to_interval)
+        .unwrap_or_else(tick_interval);
+
+    (elapsed.as_nanos() / interval.as_nanos()) as u64
+}
+
+pub fn game_load_map_inner<A, B>(
+    state: &mut InnerGameState<A, B>,
+    opt: LoadMapOption,
+) -> GameLoadResult {
+    // the pinned windows, the followed object and the rewound ticks are of the old map
+    state.ui_state.pinned_configs.clear();
+    state.ui_state.following = None;
+    state.ui_state.rewind = None;
+    // only saved maps can be opened read-only
+    state.ui_state.read_only &= matches!(opt, LoadMapOption::FromSave(_));
+
+    let read_only = state.ui_state.read_only;
+    state.ui_state.offline_progress = None;
+
+    // a map opened read-only can't progress, so it doesn't catch up either
+    let saved_at = GameMap::map(&opt)
+        .filter(|_| !read_only)
+        .and_then(|path| fs::metadata(path).ok())
+        .and_then(|v| v.modified().ok());
+
+    let success = match state.tokio.block_on(state.game.call(
+        |reply| GameSystemMessage::LoadMap(opt.clone(), read_only, reply),
+        None,
+    )) {
+        Ok(v) => v.unwrap(),
+        Err(_) => false,
+    };
+
+    if success {
+        state.loop_store.map_info = state
+            .tokio
+            .block_on(state.game.call(GameSystemMessage::GetMapInfoAndName, None))
+            .unwrap()
+            .unwrap();
+
+        if let (Some(saved_at), Some((info, _))) = (saved_at, &state.loop_store.map_info) {
+            let ticks = offline_ticks(&state.resource_man, &info.blocking_lock().data, saved_at);
+
+            if ticks > 0 {
+                log::info!("Catching up on {ticks} ticks since the map was saved");
+
+                state.ui_state.offline_progress = Some((0, ticks));
+                state.ui_state.push_popup(PopupState::Progress {
+                    title: state.resource_man.registry.gui_ids.offline_progress,
+                    message: String::new(),
+                    fraction: 0.0,
+                });
+            }
+        }
+
+        GameLoadResult::Loaded
+    } else if opt == LoadMapOption::MainMenu {
+        GameLoadResult::Failed
+    } else {
+        game_load_map_inner(state, LoadMapOption::MainMenu)
+    }
+}
+
+pub fn game_load_map<A, B>(state: &mut InnerGameState<A, B>, map_name: String) -> GameLoadResult {
+    game_load_map_inner(state, LoadMapOption::FromSave(map_name))
+}
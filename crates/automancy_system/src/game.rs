@@ -1,40 +1,117 @@
-use crate::map::{GameMap, MapInfo, TileEntities};
-use crate::tile_entity::{TileEntity, TileEntityMsg};
+use crate::agent::{self, Agent, AgentRender};
+use crate::map::{next_autosave_slot, GameMap, MapInfo, MapSnapshot, TileEntities};
+use crate::ownership::PlayerId;
+use crate::signing::{self, SigningKey};
+use crate::stats::{ProductionHistory, Statistics};
+use crate::tile_entity::{LifecycleEvent, TileEntity, TileEntityMsg, TILE_ENTITY_MAILBOX};
+use crate::util::{
+    actor::MailboxMetrics, map_field_diffusion_interval, map_random_tick_speed, map_tick_rate,
+    BudgetLimit, MapBudget,
+};
 use crate::{game::GameSystemMessage::*, map::LoadMapOption};
-use crate::{tile_entity::TileEntityError, util::actor::multi_call_iter};
+use crate::{tile_entity::TileEntityError, transfer::TransferQueues, util::actor::multi_call_iter};
 use arraydeque::{ArrayDeque, Wrapping};
 use automancy_defs::id::{Id, ModelId, RenderTagId};
+use automancy_defs::math::Float;
 use automancy_defs::{
     coord::{TileBounds, TileCoord},
     id::TileId,
+    spatial::{chunk_origin, CHUNK_SIZE},
+    stack::{ItemAmount, ItemStack},
+};
+use automancy_resources::error::{push_err, ErrorSeverity};
+use automancy_resources::format::{FormatContext, Formattable};
+use automancy_resources::inventory::Inventory;
+use automancy_resources::profiler::SCRIPT_PROFILER;
+use automancy_resources::types::function::{
+    LifecycleHook, MapOutcome, OnFailAction, CONDITIONS_FUNCTION,
 };
-use automancy_resources::types::function::OnFailAction;
-use automancy_resources::ResourceManager;
 use automancy_resources::{
     data::{Data, DataMap},
+    rhai_field::{clear_fields, diffuse_fields},
+    rhai_map::{set_map_tiles, update_map_tile},
     rhai_render::RenderCommand,
 };
-use hashbrown::HashMap;
+use automancy_resources::{rhai_call_options, rhai_log_err, ResourceManager};
+use hashbrown::{HashMap, HashSet};
 use ractor::rpc::CallResult;
 use ractor::{Actor, ActorProcessingErr, ActorRef, RpcReplyPort, SupervisionEvent};
+use rand::{thread_rng, Rng};
+use rhai::{Dynamic, Scope};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
-use std::{mem, sync::Arc};
+use std::{iter, mem, sync::Arc};
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 
-/// Game ticks per second
+/// Default game ticks per second
 pub const TPS: u64 = 60;
 pub const TICK_INTERVAL: Duration = Duration::from_nanos(1_000_000_000 / TPS);
-pub const MAX_ALLOWED_TICK_INTERVAL: Duration = TICK_INTERVAL.saturating_mul(5);
+/// The highest tick rate the game can be set to. The game is woken up at this rate, and runs a tick whenever one is due.
+pub const MAX_TPS: u64 = 240;
+pub const WAKE_INTERVAL: Duration = Duration::from_nanos(1_000_000_000 / MAX_TPS);
+/// The most ticks the game runs at once to catch up, before giving up on the rest of the backlog.
+pub const MAX_CATCH_UP_TICKS: u32 = 5;
+/// The most ticks run for one request to catch up on the time a map was closed, so that the game can still answer
+/// other messages in between.
+pub const OFFLINE_PROGRESS_BATCH: u64 = 20;
+/// How many ticks pass between two checks of the map's win and lose conditions.
+pub const CONDITIONS_CHECK_INTERVAL: u64 = 30;
+/// The game is overloaded when there are more ticks than this per tile entity waiting in their mailboxes.
+pub const OVERLOAD_QUEUED_TICKS_PER_TILE: usize = 2;
+/// While the game is overloaded, render commands are collected at most this often.
+pub const OVERLOADED_RENDER_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The instrumentation of the game's mailbox.
+pub static GAME_MAILBOX: MailboxMetrics = MailboxMetrics::new();
 
 pub const TRANSACTION_ANIMATION_SPEED: Duration = Duration::from_nanos(800_000_000);
 pub const TRANSACTION_MIN_INTERVAL: Duration = Duration::from_nanos(250_000_000);
 pub const TAKE_ITEM_ANIMATION_SPEED: Duration = Duration::from_nanos(300_000_000);
 
 const UNDO_CACHE_SIZE: usize = 256;
+/// The maximum amount of tiles scripts are allowed to place or remove in a single tick.
+pub const SCRIPT_PLACEMENT_BUDGET: u32 = 64;
 
 pub type TickUnit = u16;
 
 pub type FlatTiles = Vec<(TileCoord, TileId, Option<DataMap>)>;
+/// Batches of render commands, to be applied in order.
+pub type RenderDelta = Vec<HashMap<TileCoord, Vec<RenderCommand>>>;
+
+/// The interval between ticks the game currently runs at, in nanoseconds.
+static CURRENT_TICK_INTERVAL: AtomicU64 = AtomicU64::new(TICK_INTERVAL.as_nanos() as u64);
+
+/// Gets the interval between ticks the game currently runs at.
+pub fn tick_interval() -> Duration {
+    Duration::from_nanos(CURRENT_TICK_INTERVAL.load(Ordering::Relaxed))
+}
+
+pub fn tps_to_interval(tps: u64) -> Duration {
+    Duration::from_nanos(1_000_000_000 / tps.clamp(1, MAX_TPS))
+}
+
+/// How far along the visuals should be between the previous tick's state and the current one, in 0..=1,
+/// given the instant the current state arrived.
+pub fn tick_interpolation(since: Instant) -> Float {
+    (since.elapsed().as_secs_f32() / tick_interval().as_secs_f32()).min(1.0)
+}
+
+/// How well the game is keeping up with its tick rate.
+#[derive(Debug, Clone, Copy)]
+pub struct TickStats {
+    pub target_tps: Float,
+    /// the ticks run in the last second
+    pub tps: usize,
+    pub last_tick_time: Duration,
+    /// whether the game had to drop ticks recently
+    pub behind: bool,
+    /// whether non-critical requests are being deferred
+    pub overloaded: bool,
+    /// the number of tiles that aren't being ticked, as they reported having no work to do
+    pub sleeping: usize,
+}
 
 #[derive(Debug, Default)]
 pub struct GameSystemState {
@@ -47,12 +124,80 @@ pub struct GameSystemState {
     tile_entities: TileEntities,
     /// the map
     map: Option<GameMap>,
+    /// whether the map was opened read-only: it is simulated, but can't be changed by players, and isn't saved
+    read_only: bool,
 
     /// what to do to undo the last UNDO_CACHE_SIZE user events
     undo_steps: ArrayDeque<Vec<GameSystemMessage>, UNDO_CACHE_SIZE, Wrapping>,
 
     cleanup_render_commands: HashMap<TileCoord, Vec<RenderCommand>>,
     last_culling_range: TileBounds,
+
+    /// the item queues shared between maps
+    transfer_queues: TransferQueues,
+    /// how many tiles scripts have placed or removed this tick
+    script_placements: u32,
+
+    /// the interval between ticks set in the options, used unless the map overrides it
+    options_tick_interval: Option<Duration>,
+    /// when the game was last woken up to run ticks
+    last_wake: Option<Instant>,
+    /// the time passed that ticks haven't been run for yet
+    tick_debt: Duration,
+    /// when each tick of the last second started
+    recent_ticks: VecDeque<Instant>,
+    last_tick_time: Duration,
+    /// whether the game had to drop ticks, and hasn't caught up since
+    behind: bool,
+    /// until when the simulation is frozen by a hit-stop
+    hit_stop_until: Option<Instant>,
+    /// whether the simulation is paused, e.g. while the graphics device is being recovered
+    paused: bool,
+    /// the items handed off by the tiles of the map, since it was loaded
+    production: ProductionHistory,
+    /// when render commands were last collected from the tile entities
+    last_render_collection: Option<Instant>,
+    /// the tiles that reported render changes since the last collection
+    render_dirty: HashSet<TileCoord>,
+    /// the number of the last frame sent to the renderer
+    render_frame: u64,
+    /// the last delta sent to the renderer, kept until it is acknowledged
+    unacked_render_delta: Option<(u64, RenderDelta)>,
+    /// the tiles that reported they have no work to do, which aren't ticked until woken up
+    sleeping: HashSet<TileCoord>,
+    /// the tiles placed, removed or reconfigured since the last tick, whose neighbors are yet to be told
+    changed_tiles: HashSet<TileCoord>,
+    /// the ticks left until sleeping tiles with a timer wake up by themselves
+    sleep_timers: HashMap<TileCoord, u32>,
+    /// how many random tiles of each chunk receive a random tick every tick, as set by the map
+    random_tick_speed: u32,
+    /// how many ticks pass between two diffusions of the fields, as set by the map. None if it doesn't simulate them
+    field_diffusion_interval: Option<u32>,
+    /// bumped whenever the scenery changes, so that the renderer only rebuilds it then
+    scenery_version: u64,
+    /// how many ticks to keep snapshots of for the debugger to step back through, and the snapshots, oldest first.
+    /// None unless turned on, as every tile is asked for its data after each tick
+    rewind: Option<(usize, VecDeque<(TickUnit, MapSnapshot)>)>,
+    /// the key saves are signed with, if signing is turned on in the options
+    signing_key: Option<SigningKey>,
+    /// the ticks run since the map was loaded. Unlike the tick count, this doesn't wrap around
+    map_ticks: u64,
+    /// whether the map's conditions decided it was won or lost, after which they aren't checked anymore
+    outcome_decided: bool,
+    /// the outcome of the map, until the client takes it to show the end screen
+    outcome: Option<MapOutcome>,
+    /// the resources the packs were last reloaded into, if they were
+    reloaded_resources: Option<Arc<ResourceManager>>,
+    /// the soft limits set by the map
+    budget: MapBudget,
+    /// the limits of the budget the map has reached, which were already warned about
+    over_budget: HashSet<BudgetLimit>,
+    /// how often the map is autosaved, and into how many slots, as set in the options
+    autosave: Option<(Duration, u32)>,
+    /// when the map was last autosaved, or loaded
+    last_autosave: Option<Instant>,
+    /// the autosave being written to disk
+    autosave_task: Option<JoinHandle<()>>,
 }
 
 pub static COULD_NOT_LOAD_ANYTHING: &str = "??? main menu is corrupted and couldn't be emptied!";
@@ -106,17 +251,43 @@ pub enum PlaceTileResponse {
     Placed,
     Removed,
     Ignored,
+    /// the tile there belongs to another player, and the map's protection doesn't allow changing it
+    Denied,
+    /// the map reached the tile limit of its budget, and refuses placing more
+    OverBudget,
 }
 
 /// Represents a message the game receives
 #[derive(Debug)]
 pub enum GameSystemMessage {
-    /// tick the tiles once
+    /// run the ticks that are due
     Tick,
+    /// set the tick rate from the options
+    SetTickRate(u64),
+    /// sign saves with the key from now on, or stop signing them if none
+    SetSigningKey(Option<SigningKey>),
+    /// autosave the map every interval into the number of slots, or stop autosaving if none
+    SetAutosave(Option<(Duration, u32)>),
+    /// use the reloaded resource packs from now on. The tile entities keep the old ones until the map is loaded again
+    ReloadResources(Arc<ResourceManager>),
+    GetTickStats(RpcReplyPort<TickStats>),
     StopTicking,
-
-    /// load a map
-    LoadMap(LoadMapOption, RpcReplyPort<bool>),
+    /// freeze the simulation for a moment, without catching up on the ticks afterwards
+    HitStop(Duration),
+    /// pause or resume the simulation. The time paused isn't caught up on afterwards
+    SetPaused(bool),
+    /// run up to that many ticks right away, at most [OFFLINE_PROGRESS_BATCH], to catch up on the time the map was
+    /// closed. None are run while the game is overloaded. Replies with how many ran
+    CatchUpTicks(u64, RpcReplyPort<u64>),
+    /// count a stack a tile has handed off in the production history
+    RecordProduction(ItemStack),
+    /// get the production history and machine counts of the map
+    GetStatistics(RpcReplyPort<Statistics>),
+    /// take the outcome of the map, once its conditions decided it was won or lost, with the ticks it took
+    TakeMapOutcome(RpcReplyPort<Option<(MapOutcome, u64)>>),
+
+    /// load a map, read-only if the flag is set
+    LoadMap(LoadMapOption, bool, RpcReplyPort<bool>),
     /// save the map
     SaveMap(RpcReplyPort<()>),
     GetMapInfoAndName(RpcReplyPort<Option<(Arc<Mutex<MapInfo>>, LoadMapOption)>>),
@@ -129,62 +300,139 @@ pub enum GameSystemMessage {
         on_fail: OnFailAction,
     },
 
-    /// place a tile at the given position
+    /// place a tile at the given position, owned by `player`
     PlaceTile {
         coord: TileCoord,
         id: TileId,
         data: Option<DataMap>,
+        player: PlayerId,
         record: bool,
         reply: Option<RpcReplyPort<PlaceTileResponse>>,
     },
+    /// place tiles owned by `player`. Tiles refused like a single [GameSystemMessage::PlaceTile] would be are skipped
     PlaceTiles {
         tiles: FlatTiles,
+        player: PlayerId,
         reply: Option<RpcReplyPort<FlatTiles>>,
         place_over: bool,
         record: bool,
     },
     MoveTiles(Vec<TileCoord>, TileCoord, bool),
+    /// check if the player is allowed to change or configure the tile at the given position
+    CanModify(TileCoord, PlayerId, RpcReplyPort<bool>),
+    /// place (or remove, if `id` is none) a tile on behalf of the script of the tile at `source`
+    ScriptPlaceTile {
+        source: TileCoord,
+        coord: TileCoord,
+        id: TileId,
+    },
 
     Undo,
 
+    /// place (or remove, if the ID is none) scenery tiles. Tiles that aren't scenery are skipped
+    SetScenery(Vec<(TileCoord, Option<TileId>)>),
+    /// get every scenery tile, unless the scenery is still at the version the caller already has
+    GetScenery {
+        version: Option<u64>,
+        reply: RpcReplyPort<Option<(u64, Vec<(TileCoord, TileId)>)>>,
+    },
+
+    /// push items into a persistent transfer channel
+    SendItems {
+        channel: Id,
+        stacks: Vec<ItemStack>,
+    },
+    /// pop up to `max` items from a transfer channel, and deliver them to the tile at `coord`
+    RequestItems {
+        channel: Id,
+        coord: TileCoord,
+        max: ItemAmount,
+    },
+    /// put items that were delivered but couldn't be taken back to the front of a transfer channel
+    ReturnItems {
+        channel: Id,
+        stacks: Vec<ItemStack>,
+    },
+
     /// get the tile at the given position
     GetTile(TileCoord, RpcReplyPort<Option<TileId>>),
+    /// get the tiles within the bounds
+    GetTilesIn(TileBounds, RpcReplyPort<HashMap<TileCoord, TileId>>),
     /// get the tile entity at the given position
     GetTileEntity(TileCoord, RpcReplyPort<Option<ActorRef<TileEntityMsg>>>),
+    /// get both the tile and the tile entity at the given position, e.g. the one being pointed at
+    PickTile(
+        TileCoord,
+        RpcReplyPort<Option<(TileId, ActorRef<TileEntityMsg>)>>,
+    ),
     GetTiles(Vec<TileCoord>, RpcReplyPort<FlatTiles>),
-    /// get all the tiles' render commands
-    GetAllRenderCommands {
+    /// take a snapshot of every tile's ID and data
+    SnapshotAll(RpcReplyPort<MapSnapshot>),
+    /// keep snapshots of the given number of latest ticks for the debugger, or stop keeping them if none
+    SetRewind(Option<usize>),
+    /// get the snapshots kept of the latest ticks, oldest first, with the tick each was taken after
+    GetRewind(RpcReplyPort<Vec<(TickUnit, MapSnapshot)>>),
+    /// get the processing progress of every tile within the culling range that is working on something
+    GetProgress {
+        culling_range: TileBounds,
+        reply: RpcReplyPort<HashMap<TileCoord, Float>>,
+    },
+    /// get the render commands of the tiles that changed since the last acknowledged frame
+    GetRenderDelta {
         culling_range: TileBounds,
-        reply: RpcReplyPort<[HashMap<TileCoord, Vec<RenderCommand>>; 2]>,
+        /// the last frame the renderer received
+        acknowledged: Option<u64>,
+        reply: RpcReplyPort<(u64, RenderDelta)>,
+    },
+    /// a tile's rendered fields changed, so it needs to be asked for its render commands
+    MarkRenderDirty(TileCoord),
+    /// the animations of the tiles passed the named event markers
+    AnimationEvents(Vec<(TileCoord, String)>),
+    /// a tile has no work to do, so it stops being ticked until it is woken up, or for the given number of ticks
+    SleepTile {
+        coord: TileCoord,
+        ticks: Option<u32>,
+    },
+    /// a sleeping tile's surroundings or inventory changed, so it starts being ticked again
+    WakeTile(TileCoord),
+    /// a tile was placed, removed or reconfigured, so its neighbors are told about it at the start of the next tick
+    TileChanged(TileCoord),
+    /// spawn an agent of the given kind at the coordinate
+    SpawnAgent {
+        id: Id,
+        coord: TileCoord,
+    },
+    /// get the agents within the culling range to draw, with the tick they were last moved on
+    GetAgents {
+        culling_range: TileBounds,
+        reply: RpcReplyPort<(TickUnit, Vec<AgentRender>)>,
     },
 }
 
 pub struct GameSystem {
+    /// the resources the game was started with
     pub resource_man: Arc<ResourceManager>,
 }
 
-#[async_trait::async_trait]
-impl Actor for GameSystem {
-    type Msg = GameSystemMessage;
-    type State = GameSystemState;
-    type Arguments = ();
-
-    async fn pre_start(
-        &self,
-        _myself: ActorRef<Self::Msg>,
-        _args: Self::Arguments,
-    ) -> Result<Self::State, ActorProcessingErr> {
-        Ok(Self::State::default())
+impl GameSystem {
+    /// Gets the resources in use, which are the reloaded ones if the packs were reloaded.
+    fn resource_man(&self, state: &GameSystemState) -> Arc<ResourceManager> {
+        state
+            .reloaded_resources
+            .clone()
+            .unwrap_or_else(|| self.resource_man.clone())
     }
 
-    async fn handle(
+    async fn handle_message(
         &self,
-        myself: ActorRef<Self::Msg>,
-        message: Self::Msg,
-        state: &mut Self::State,
+        myself: ActorRef<GameSystemMessage>,
+        message: GameSystemMessage,
+        state: &mut GameSystemState,
     ) -> Result<(), ActorProcessingErr> {
+        let resource_man = self.resource_man(state);
+
         match message {
-            LoadMap(opt, reply) => {
+            LoadMap(opt, read_only, reply) => {
                 let last_culling_range = state.last_culling_range;
                 state.last_culling_range = TileBounds::Empty;
 
@@ -208,7 +456,7 @@ impl Actor for GameSystem {
                 match commands {
                     Ok(mut commands) => {
                         fill_map_with_none(
-                            &self.resource_man,
+                            &resource_man,
                             TileBounds::Empty,
                             last_culling_range,
                             &mut commands,
@@ -229,9 +477,23 @@ impl Actor for GameSystem {
 
                 state.map = None;
                 state.undo_steps.clear();
+                state.sleeping.clear();
+                state.changed_tiles.clear();
+                state.sleep_timers.clear();
+                state.production.clear();
+                state.map_ticks = 0;
+                state.outcome_decided = false;
+                state.outcome = None;
+                state.over_budget.clear();
+                state.last_autosave = Some(Instant::now());
+                if let Some((_, snapshots)) = &mut state.rewind {
+                    snapshots.clear();
+                }
+                set_map_tiles([]);
+                clear_fields();
 
                 let (map, tile_entities) =
-                    match GameMap::load(myself.clone(), self.resource_man.clone(), &opt).await {
+                    match GameMap::load(myself.clone(), resource_man.clone(), &opt).await {
                         Ok(v) => v,
                         Err(abort) => {
                             if abort {
@@ -243,16 +505,38 @@ impl Actor for GameSystem {
                         }
                     };
 
+                set_map_tiles(map.tiles.iter().map(|(coord, id)| (*coord, **id)));
+
                 state.map = Some(map);
                 state.tile_entities = tile_entities;
+                state.read_only = read_only;
+                state.scenery_version += 1;
 
                 log::info!("Successfully loaded map {opt}!");
                 reply.send(true)?;
             }
             SaveMap(reply) => {
+                if state.read_only {
+                    log::info!("The map is open read-only, not saving");
+                    reply.send(())?;
+
+                    return Ok(());
+                }
+
                 if let Some(map) = &state.map {
-                    map.save(&self.resource_man.interner, &state.tile_entities)
-                        .await?;
+                    map.save(
+                        &resource_man.interner,
+                        &resource_man.pack_versions(),
+                        &state.tile_entities,
+                    )
+                    .await?;
+
+                    if let Err(err) = signing::sign_map(state.signing_key.as_ref(), &map.opt) {
+                        log::error!("Could not sign the map! Error: {err:?}");
+                    }
+                }
+                if let Err(err) = state.transfer_queues.save(&resource_man.interner) {
+                    log::error!("Could not save transfer queues! Error: {err:?}");
                 }
                 reply.send(())?;
             }
@@ -265,11 +549,156 @@ impl Actor for GameSystem {
             }
 
             Tick => {
-                tick(state);
+                let interval = effective_tick_interval(&resource_man, state).await;
+                CURRENT_TICK_INTERVAL.store(interval.as_nanos() as u64, Ordering::Relaxed);
+
+                if let Some(map) = &state.map {
+                    let info = map.info.lock().await;
+
+                    state.random_tick_speed = map_random_tick_speed(&resource_man, &info.data);
+                    state.field_diffusion_interval =
+                        map_field_diffusion_interval(&resource_man, &info.data);
+                    state.budget = MapBudget::of(&resource_man, &info.data);
+                }
+
+                state.production.roll();
+
+                let tick_count = state.tick_count;
+                let map_ticks = state.map_ticks;
+                run_due_ticks(&resource_man, state, interval);
+
+                if state.tick_count != tick_count {
+                    record_rewind(state).await;
+                    check_budget(&resource_man, state);
+                }
+
+                if !state.outcome_decided
+                    && state.map_ticks / CONDITIONS_CHECK_INTERVAL
+                        != map_ticks / CONDITIONS_CHECK_INTERVAL
+                {
+                    if let Some(outcome) = check_conditions(&resource_man, state).await {
+                        log::info!("The map's conditions decided its outcome: {outcome:?}");
+
+                        state.outcome_decided = true;
+                        state.outcome = Some(outcome);
+                    }
+                }
+
+                if let Some((interval, slots)) = state.autosave {
+                    if state.last_autosave.is_some_and(|v| v.elapsed() >= interval) {
+                        state.last_autosave = Some(Instant::now());
+                        autosave(&resource_man, state, slots).await;
+                    }
+                }
+            }
+            SetTickRate(tps) => {
+                state.options_tick_interval = Some(tps_to_interval(tps));
+            }
+            SetSigningKey(key) => {
+                state.signing_key = key;
+            }
+            SetAutosave(autosave) => {
+                state.autosave = autosave;
+            }
+            ReloadResources(resource_man) => {
+                state.reloaded_resources = Some(resource_man);
+            }
+            GetTickStats(reply) => {
+                reply.send(TickStats {
+                    target_tps: 1.0 / tick_interval().as_secs_f32(),
+                    tps: state.recent_ticks.len(),
+                    last_tick_time: state.last_tick_time,
+                    behind: state.behind,
+                    overloaded: is_overloaded(state),
+                    sleeping: state.sleeping.len(),
+                })?;
             }
             StopTicking => {
                 state.stopped = true;
             }
+            HitStop(duration) => {
+                let until = Instant::now() + duration;
+
+                if state.hit_stop_until.map_or(true, |v| v < until) {
+                    state.hit_stop_until = Some(until);
+                }
+            }
+            SetPaused(paused) => {
+                state.paused = paused;
+            }
+            CatchUpTicks(ticks, reply) => {
+                // the tile entities have to get through the ticks already sent first
+                let ticks = if is_overloaded(state) || state.map.is_none() {
+                    0
+                } else {
+                    ticks.min(OFFLINE_PROGRESS_BATCH)
+                };
+
+                let interval = effective_tick_interval(&resource_man, state).await;
+                for _ in 0..ticks {
+                    tick(&resource_man, state, interval);
+                }
+
+                reply.send(ticks)?;
+            }
+            SetRewind(capacity) => {
+                state.rewind = capacity
+                    .filter(|v| *v > 0)
+                    .map(|v| (v, VecDeque::with_capacity(v)));
+            }
+            GetRewind(reply) => {
+                reply.send(
+                    state
+                        .rewind
+                        .as_ref()
+                        .map(|(_, snapshots)| snapshots.iter().cloned().collect())
+                        .unwrap_or_default(),
+                )?;
+            }
+            RecordProduction(stack) => {
+                state.production.record(stack);
+            }
+            TakeMapOutcome(reply) => {
+                reply.send(state.outcome.take().map(|v| (v, state.map_ticks)))?;
+            }
+            MarkRenderDirty(coord) => {
+                state.render_dirty.insert(coord);
+            }
+            AnimationEvents(events) => {
+                animation_events(&resource_man, state, events);
+            }
+            SleepTile { coord, ticks } => {
+                if state.tile_entities.contains_key(&coord) {
+                    state.sleeping.insert(coord);
+
+                    if let Some(ticks) = ticks {
+                        state.sleep_timers.insert(coord, ticks.max(1));
+                    } else {
+                        state.sleep_timers.remove(&coord);
+                    }
+                }
+            }
+            WakeTile(coord) => {
+                state.sleeping.remove(&coord);
+                state.sleep_timers.remove(&coord);
+            }
+            TileChanged(coord) => {
+                state.changed_tiles.insert(coord);
+
+                // connected models follow their neighbors even while the game is paused
+                if let Some(map) = &state.map {
+                    for neighbor in coord.neighbors() {
+                        if map
+                            .tiles
+                            .get(&neighbor)
+                            .and_then(|id| resource_man.registry.tiles.get(id))
+                            .is_some_and(|tile| tile.connected.is_some())
+                        {
+                            state.render_dirty.insert(neighbor);
+                        }
+                    }
+                }
+            }
 
             rest => {
                 if state.stopped {
@@ -281,98 +710,174 @@ impl Actor for GameSystem {
                 };
 
                 match rest {
-                    GetAllRenderCommands {
+                    GetRenderDelta {
                         culling_range,
+                        acknowledged,
                         reply,
                     } => {
-                        let last_culling_range = state.last_culling_range;
-                        state.last_culling_range = culling_range;
-
-                        let commands = multi_call_iter(
-                            &state.tile_entities,
-                            |reply, coord| {
-                                let loading = culling_range.contains(coord)
-                                    && !last_culling_range.contains(coord);
-                                let unloading = last_culling_range.contains(coord)
-                                    && !culling_range.contains(coord);
-
-                                TileEntityMsg::CollectRenderCommands {
-                                    reply,
-                                    loading,
-                                    unloading,
+                        let delta = if culling_range == state.last_culling_range
+                            && is_overloaded(state)
+                            && state
+                                .last_render_collection
+                                .is_some_and(|v| v.elapsed() < OVERLOADED_RENDER_INTERVAL)
+                        {
+                            // the tiles stay dirty, and render their changes together in a later collection
+                            vec![mem::take(&mut state.cleanup_render_commands)]
+                        } else {
+                            state.last_render_collection = Some(Instant::now());
+
+                            let last_culling_range = state.last_culling_range;
+                            state.last_culling_range = culling_range;
+
+                            let dirty = mem::take(&mut state.render_dirty);
+
+                            let targets = map
+                                .tiles
+                                .in_bounds(culling_range)
+                                .filter(|(coord, _)| !last_culling_range.contains(*coord))
+                                .chain(
+                                    map.tiles
+                                        .in_bounds(last_culling_range)
+                                        .filter(|(coord, _)| !culling_range.contains(*coord)),
+                                )
+                                .map(|(coord, _)| coord)
+                                .chain(dirty)
+                                .flat_map(|coord| {
+                                    state
+                                        .tile_entities
+                                        .get(&coord)
+                                        .map(|tile_entity| (coord, tile_entity.clone()))
+                                })
+                                .collect::<HashMap<_, _>>();
+
+                            let commands = multi_call_iter(
+                                &targets,
+                                |reply, coord| {
+                                    let loading = culling_range.contains(coord)
+                                        && !last_culling_range.contains(coord);
+                                    let unloading = last_culling_range.contains(coord)
+                                        && !culling_range.contains(coord);
+
+                                    TileEntityMsg::CollectRenderCommands {
+                                        reply,
+                                        loading,
+                                        unloading,
+                                    }
+                                },
+                                None,
+                            )
+                            .await
+                            .map(|commands| {
+                                commands
+                                    .into_iter()
+                                    .flat_map(|(k, v)| Some(k).zip(v))
+                                    .collect()
+                            });
+
+                            match commands {
+                                Ok(mut commands) => {
+                                    fill_map_with_none(
+                                        &resource_man,
+                                        culling_range,
+                                        last_culling_range,
+                                        &mut commands,
+                                    );
+
+                                    vec![mem::take(&mut state.cleanup_render_commands), commands]
+                                }
+                                Err(err) => {
+                                    log::error!(
+                                        "Could not collect render commands! Error: {err:?}"
+                                    );
+                                    state.render_dirty.extend(dirty);
+
+                                    return Ok(());
                                 }
-                            },
-                            None,
-                        )
-                        .await
-                        .map(|commands| {
-                            commands
-                                .into_iter()
-                                .flat_map(|(k, v)| Some(k).zip(v))
-                                .collect()
-                        });
-
-                        match commands {
-                            Ok(mut commands) => {
-                                fill_map_with_none(
-                                    &self.resource_man,
-                                    culling_range,
-                                    last_culling_range,
-                                    &mut commands,
-                                );
-
-                                reply.send([
-                                    mem::take(&mut state.cleanup_render_commands),
-                                    commands,
-                                ])?;
-                            }
-                            Err(err) => {
-                                log::error!("Could not collect render commands! Error: {err:?}")
                             }
-                        }
+                        };
+
+                        reply.send(next_render_frame(state, acknowledged, delta))?;
                     }
                     PlaceTile {
                         coord,
                         id,
                         data,
+                        player,
                         record,
                         reply,
                     } => {
-                        if let Some(old_id) = map.tiles.get(&coord) {
-                            if *old_id == id {
-                                if let Some(reply) = reply {
-                                    reply.send(PlaceTileResponse::Ignored)?;
-                                }
+                        if let Some(response) = check_placement(
+                            &resource_man,
+                            map,
+                            state.read_only,
+                            &state.budget,
+                            state.tile_entities.len(),
+                            coord,
+                            id,
+                            Some(player),
+                        )
+                        .await
+                        {
+                            if let Some(reply) = reply {
+                                reply.send(response)?;
+                            }
 
-                                return Ok(());
+                            return Ok(());
+                        }
+
+                        if resource_man.scenery_model(id).is_some() {
+                            let response = if map.scenery.insert(coord, id) == Some(id) {
+                                PlaceTileResponse::Ignored
+                            } else {
+                                state.scenery_version += 1;
+                                PlaceTileResponse::Placed
+                            };
+
+                            if let Some(reply) = reply {
+                                reply.send(response)?;
                             }
+
+                            return Ok(());
                         }
 
-                        if id == TileId(self.resource_man.registry.none)
+                        if id == TileId(resource_man.registry.none)
                             && !map.tiles.contains_key(&coord)
                         {
+                            // with no tile there, removing clears the scenery instead
+                            let response = if map.scenery.remove(&coord).is_some() {
+                                state.scenery_version += 1;
+                                PlaceTileResponse::Removed
+                            } else {
+                                PlaceTileResponse::Ignored
+                            };
+
                             if let Some(reply) = reply {
-                                reply.send(PlaceTileResponse::Ignored)?;
+                                reply.send(response)?;
                             }
 
                             return Ok(());
                         }
 
+                        let old_owner = map.owners.get(&coord).copied();
+
                         let old_tile = insert_new_tile(
-                            self.resource_man.clone(),
+                            resource_man.clone(),
                             myself.clone(),
                             map,
                             &mut state.tile_entities,
+                            &mut state.sleeping,
+                            &mut state.sleep_timers,
                             &mut state.cleanup_render_commands,
                             coord,
                             id,
                             data,
+                            Some(player),
                         )
                         .await;
 
                         if let Some(reply) = reply {
                             if let (Some(_), ..) = &old_tile {
-                                if id == TileId(self.resource_man.registry.none) {
+                                if id == TileId(resource_man.registry.none) {
                                     reply.send(PlaceTileResponse::Removed)?;
                                 } else {
                                     reply.send(PlaceTileResponse::Placed)?;
@@ -387,6 +892,8 @@ impl Actor for GameSystem {
                                 state.undo_steps.push_back(vec![PlaceTile {
                                     coord,
                                     id,
+                                    // the old tile goes back to whoever owned it
+                                    player: old_owner.unwrap_or(player),
                                     record: false,
                                     reply: None,
                                     data,
@@ -394,12 +901,31 @@ impl Actor for GameSystem {
                             }
                         }
                     }
+                    CanModify(coord, player, reply) => {
+                        reply.send(!state.read_only && may_modify(map, coord, player).await)?;
+                    }
                     GetTile(coord, reply) => {
                         reply.send(map.tiles.get(&coord).cloned())?;
                     }
+                    GetTilesIn(bounds, reply) => {
+                        reply.send(
+                            map.tiles
+                                .in_bounds(bounds)
+                                .map(|(coord, id)| (coord, *id))
+                                .collect(),
+                        )?;
+                    }
                     GetTileEntity(coord, reply) => {
                         reply.send(state.tile_entities.get(&coord).cloned())?;
                     }
+                    PickTile(coord, reply) => {
+                        reply.send(
+                            map.tiles
+                                .get(&coord)
+                                .copied()
+                                .zip(state.tile_entities.get(&coord).cloned()),
+                        )?;
+                    }
                     ForwardMsgToTile {
                         source,
                         to,
@@ -413,9 +939,11 @@ impl Actor for GameSystem {
                                 OnFailAction::None => {}
                                 OnFailAction::RemoveTile => {
                                     remove_tile(
-                                        &self.resource_man,
+                                        &resource_man,
                                         map,
                                         &mut state.tile_entities,
+                                        &mut state.sleeping,
+                                        &mut state.sleep_timers,
                                         source,
                                     )
                                     .await;
@@ -442,6 +970,202 @@ impl Actor for GameSystem {
                             }
                         }
                     }
+                    SetScenery(tiles) => {
+                        if state.read_only {
+                            return Ok(());
+                        }
+
+                        let mut changed = false;
+
+                        for (coord, id) in tiles {
+                            changed |= match id {
+                                Some(id) if resource_man.scenery_model(id).is_some() => {
+                                    map.scenery.insert(coord, id) != Some(id)
+                                }
+                                Some(_) => false,
+                                None => map.scenery.remove(&coord).is_some(),
+                            };
+                        }
+
+                        if changed {
+                            state.scenery_version += 1;
+                        }
+                    }
+                    GetScenery { version, reply } => {
+                        if version == Some(state.scenery_version) {
+                            reply.send(None)?;
+                        } else {
+                            reply.send(Some((
+                                state.scenery_version,
+                                map.scenery
+                                    .iter()
+                                    .map(|(coord, id)| (*coord, *id))
+                                    .collect(),
+                            )))?;
+                        }
+                    }
+                    SpawnAgent { id, coord } => match resource_man.registry.agents.get(&id) {
+                        Some(_) if !state.budget.allows(BudgetLimit::Agents, map.agents.len()) => {
+                            log::debug!(
+                                "The map reached its agent budget, not spawning at {coord}"
+                            );
+                        }
+                        Some(def) => {
+                            map.agents.push(Agent::new(id, coord, def.data.clone()));
+                        }
+                        None => {
+                            log::warn!(
+                                "Cannot spawn an agent of the unknown kind {}",
+                                resource_man.interner.resolve(id).unwrap_or_default()
+                            );
+                        }
+                    },
+                    GetAgents {
+                        culling_range,
+                        reply,
+                    } => {
+                        reply.send((
+                            state.tick_count,
+                            agent::render_agents(&resource_man, &map.agents, culling_range),
+                        ))?;
+                    }
+                    GetStatistics(reply) => {
+                        let mut machines = HashMap::new();
+                        for (_, id) in map.tiles.iter() {
+                            *machines.entry(*id).or_default() += 1;
+                        }
+
+                        reply.send(Statistics {
+                            production: state.production.samples(),
+                            machines,
+                        })?;
+                    }
+                    ScriptPlaceTile { source, coord, id } => {
+                        if state.script_placements >= SCRIPT_PLACEMENT_BUDGET {
+                            log::debug!(
+                                "Script placement budget exhausted, ignoring request from {source}"
+                            );
+
+                            return Ok(());
+                        }
+
+                        // scripts act on behalf of the owner of their tile, and the tiles they place belong to it too
+                        let owner = map.owners.get(&source).copied();
+
+                        if let Some(response) = check_placement(
+                            &resource_man,
+                            map,
+                            state.read_only,
+                            &state.budget,
+                            state.tile_entities.len(),
+                            coord,
+                            id,
+                            owner,
+                        )
+                        .await
+                        {
+                            log::debug!(
+                                "Refused placing a tile at {coord} for {source}: {response:?}"
+                            );
+
+                            return Ok(());
+                        }
+
+                        if resource_man.scenery_model(id).is_some() {
+                            state.script_placements += 1;
+
+                            if map.scenery.insert(coord, id) != Some(id) {
+                                state.scenery_version += 1;
+                            }
+
+                            return Ok(());
+                        }
+
+                        // unlike players, scripts only place onto free spots, and only remove what's there
+                        let allowed = if id == TileId(resource_man.registry.none) {
+                            map.tiles.contains_key(&coord)
+                        } else {
+                            !map.tiles.contains_key(&coord)
+                                && resource_man.registry.tiles.contains_key(&id)
+                        };
+
+                        if !allowed {
+                            return Ok(());
+                        }
+
+                        state.script_placements += 1;
+
+                        insert_new_tile(
+                            resource_man.clone(),
+                            myself.clone(),
+                            map,
+                            &mut state.tile_entities,
+                            &mut state.sleeping,
+                            &mut state.sleep_timers,
+                            &mut state.cleanup_render_commands,
+                            coord,
+                            id,
+                            None,
+                            owner,
+                        )
+                        .await;
+                    }
+                    SendItems { channel, stacks } => {
+                        state.transfer_queues.send(channel, stacks);
+                    }
+                    RequestItems {
+                        channel,
+                        coord,
+                        max,
+                    } => {
+                        let stacks = state.transfer_queues.receive(channel, max);
+
+                        if !stacks.is_empty() {
+                            if let Some(entity) = state.tile_entities.get(&coord) {
+                                entity.send_message(TileEntityMsg::ReceiveItems {
+                                    channel,
+                                    stacks,
+                                })?;
+                            } else {
+                                state.transfer_queues.return_front(channel, stacks);
+                            }
+                        }
+                    }
+                    ReturnItems { channel, stacks } => {
+                        state.transfer_queues.return_front(channel, stacks);
+                    }
+                    SnapshotAll(reply) => {
+                        reply.send(snapshot_map(map, &state.tile_entities).await?)?;
+                    }
+                    GetProgress {
+                        culling_range,
+                        reply,
+                    } => {
+                        let in_range: TileEntities = map
+                            .tiles
+                            .in_bounds(culling_range)
+                            .flat_map(|(coord, _)| {
+                                state
+                                    .tile_entities
+                                    .get(&coord)
+                                    .map(|entity| (coord, entity.clone()))
+                            })
+                            .collect();
+
+                        let progress = multi_call_iter(
+                            &in_range,
+                            |reply, _| TileEntityMsg::GetProgress(reply),
+                            None,
+                        )
+                        .await?;
+
+                        reply.send(
+                            progress
+                                .into_iter()
+                                .flat_map(|(coord, fraction)| Some(coord).zip(fraction))
+                                .collect(),
+                        )?;
+                    }
                     GetTiles(coords, reply) => {
                         let mut tiles = vec![];
 
@@ -456,7 +1180,7 @@ impl Actor for GameSystem {
                                     tiles.push((
                                         coord,
                                         *id,
-                                        Some(copy_auxiliary_data(&self.resource_man, &mut data)),
+                                        Some(copy_auxiliary_data(&resource_man, &mut data)),
                                     ));
                                 }
                             } else {
@@ -467,6 +1191,7 @@ impl Actor for GameSystem {
                     }
                     PlaceTiles {
                         tiles,
+                        player,
                         reply,
                         place_over,
                         record,
@@ -474,16 +1199,65 @@ impl Actor for GameSystem {
                         let mut old = vec![];
 
                         for (coord, id, data) in tiles {
+                            if check_placement(
+                                &resource_man,
+                                map,
+                                state.read_only,
+                                &state.budget,
+                                state.tile_entities.len(),
+                                coord,
+                                id,
+                                Some(player),
+                            )
+                            .await
+                            .is_some()
+                            {
+                                continue;
+                            }
+
+                            if resource_man.scenery_model(id).is_some() {
+                                if !place_over && map.scenery.contains_key(&coord) {
+                                    continue;
+                                }
+
+                                let previous = map.scenery.insert(coord, id);
+                                if previous == Some(id) {
+                                    continue;
+                                }
+
+                                state.scenery_version += 1;
+                                if let Some(previous) = previous {
+                                    old.push((coord, previous, None));
+                                }
+
+                                continue;
+                            }
+
+                            if id == TileId(resource_man.registry.none)
+                                && !map.tiles.contains_key(&coord)
+                            {
+                                // with no tile there, removing clears the scenery instead
+                                if let Some(previous) = map.scenery.remove(&coord) {
+                                    state.scenery_version += 1;
+                                    old.push((coord, previous, None));
+                                }
+
+                                continue;
+                            }
+
                             if place_over || map.tiles.get(&coord).is_none() {
                                 if let (Some(old_id), old_data) = insert_new_tile(
-                                    self.resource_man.clone(),
+                                    resource_man.clone(),
                                     myself.clone(),
                                     map,
                                     &mut state.tile_entities,
+                                    &mut state.sleeping,
+                                    &mut state.sleep_timers,
                                     &mut state.cleanup_render_commands,
                                     coord,
                                     id,
                                     data,
+                                    Some(player),
                                 )
                                 .await
                                 {
@@ -491,10 +1265,7 @@ impl Actor for GameSystem {
                                         old.push((
                                             coord,
                                             old_id,
-                                            Some(copy_auxiliary_data(
-                                                &self.resource_man,
-                                                &mut old_data,
-                                            )),
+                                            Some(copy_auxiliary_data(&resource_man, &mut old_data)),
                                         ));
                                     } else {
                                         old.push((coord, old_id, None));
@@ -508,6 +1279,7 @@ impl Actor for GameSystem {
                         } else if record {
                             state.undo_steps.push_back(vec![PlaceTiles {
                                 tiles: old,
+                                player,
                                 reply: None,
                                 place_over: false,
                                 record: false,
@@ -520,19 +1292,23 @@ impl Actor for GameSystem {
                         let mut removed = Vec::new();
 
                         for coord in tiles {
+                            let owner = map.owners.get(&coord).copied();
+
                             if let Some(old) = remove_tile(
-                                &self.resource_man,
+                                &resource_man,
                                 map,
                                 &mut state.tile_entities,
+                                &mut state.sleeping,
+                                &mut state.sleep_timers,
                                 coord,
                             )
                             .await
                             {
-                                removed.push((coord, old));
+                                removed.push((coord, owner, old));
                             }
                         }
 
-                        for (coord, (id, data, mut cleanup)) in removed {
+                        for (coord, owner, (id, data, mut cleanup)) in removed {
                             let new_coord = coord + direction;
 
                             state
@@ -542,14 +1318,17 @@ impl Actor for GameSystem {
                                 .append(&mut cleanup);
 
                             insert_new_tile(
-                                self.resource_man.clone(),
+                                resource_man.clone(),
                                 myself.clone(),
                                 map,
                                 &mut state.tile_entities,
+                                &mut state.sleeping,
+                                &mut state.sleep_timers,
                                 &mut state.cleanup_render_commands,
                                 new_coord,
                                 id,
                                 data,
+                                owner,
                             )
                             .await;
 
@@ -569,6 +1348,41 @@ impl Actor for GameSystem {
 
         Ok(())
     }
+}
+
+#[async_trait::async_trait]
+impl Actor for GameSystem {
+    type Msg = GameSystemMessage;
+    type State = GameSystemState;
+    type Arguments = ();
+
+    async fn pre_start(
+        &self,
+        _myself: ActorRef<Self::Msg>,
+        _args: Self::Arguments,
+    ) -> Result<Self::State, ActorProcessingErr> {
+        Ok(Self::State {
+            transfer_queues: TransferQueues::load(&self.resource_man.interner),
+            ..Default::default()
+        })
+    }
+
+    async fn handle(
+        &self,
+        myself: ActorRef<Self::Msg>,
+        message: Self::Msg,
+        state: &mut Self::State,
+    ) -> Result<(), ActorProcessingErr> {
+        if matches!(message, Tick) {
+            GAME_MAILBOX.received();
+        }
+
+        let start = Instant::now();
+        let result = self.handle_message(myself, message, state).await;
+        GAME_MAILBOX.processed(start.elapsed());
+
+        result
+    }
 
     async fn handle_supervisor_evt(
         &self,
@@ -585,11 +1399,15 @@ impl Actor for GameSystem {
                 if let Ok(tile_error) = error.downcast::<Box<TileEntityError>>() {
                     match **tile_error {
                         TileEntityError::NonExistent(coord) => {
+                            let resource_man = self.resource_man(state);
+
                             if let Some(map) = state.map.as_mut() {
                                 remove_tile(
-                                    &self.resource_man,
+                                    &resource_man,
                                     map,
                                     &mut state.tile_entities,
+                                    &mut state.sleeping,
+                                    &mut state.sleep_timers,
                                     coord,
                                 )
                                 .await;
@@ -663,9 +1481,18 @@ async fn remove_tile(
     resource_man: &ResourceManager,
     map: &mut GameMap,
     tile_entities: &mut TileEntities,
+    sleeping: &mut HashSet<TileCoord>,
+    sleep_timers: &mut HashMap<TileCoord, u32>,
     coord: TileCoord,
 ) -> Option<(TileId, Option<DataMap>, Vec<RenderCommand>)> {
     if let Some((tile, tile_entity)) = map.tiles.remove(&coord).zip(tile_entities.remove(&coord)) {
+        // the tile isn't there to be ticked anymore, so it shouldn't count as sleeping either
+        sleeping.remove(&coord);
+        sleep_timers.remove(&coord);
+
+        update_map_tile(coord, None);
+        map.owners.remove(&coord);
+
         {
             let lock = &mut map.info.lock().await;
 
@@ -680,6 +1507,12 @@ async fn remove_tile(
             });
         }
 
+        // the tile still has its data while it's told it's being removed
+        if let Err(err) = tile_entity.send_message(TileEntityMsg::Lifecycle(LifecycleEvent::Remove))
+        {
+            log::error!("Could not tell the tile at {coord} it's removed! Error: {err:?}");
+        }
+
         let data = tile_entity
             .call(TileEntityMsg::TakeData, None)
             .await
@@ -714,15 +1547,19 @@ async fn remove_tile(
 }
 
 /// Makes a new tile and add it into both the map and the game
+#[allow(clippy::too_many_arguments)]
 async fn insert_new_tile(
     resource_man: Arc<ResourceManager>,
     game: ActorRef<GameSystemMessage>,
     map: &mut GameMap,
     tile_entities: &mut TileEntities,
+    sleeping: &mut HashSet<TileCoord>,
+    sleep_timers: &mut HashMap<TileCoord, u32>,
     cleanup_render_commands: &mut HashMap<TileCoord, Vec<RenderCommand>>,
     coord: TileCoord,
     tile_id: TileId,
     data: Option<DataMap>,
+    owner: Option<PlayerId>,
 ) -> (Option<TileId>, Option<DataMap>) {
     let mut skip = false;
 
@@ -748,11 +1585,25 @@ async fn insert_new_tile(
         return (None, None);
     }
 
+    // the tile and its neighbors might be able to work now that this spot changed
+    for coord in iter::once(coord).chain(coord.neighbors()) {
+        if let Err(err) = game.send_message(GameSystemMessage::WakeTile(coord)) {
+            log::error!("Could not wake up tile at {coord}! Error: {err:?}");
+        }
+    }
+
     let mut old_id = None;
     let mut old_data = None;
 
-    if let Some((id, data, mut cleanup)) =
-        remove_tile(&resource_man, map, tile_entities, coord).await
+    if let Some((id, data, mut cleanup)) = remove_tile(
+        &resource_man,
+        map,
+        tile_entities,
+        sleeping,
+        sleep_timers,
+        coord,
+    )
+    .await
     {
         cleanup_render_commands
             .entry(coord)
@@ -768,6 +1619,7 @@ async fn insert_new_tile(
     }
 
     let tile_entity = new_tile(resource_man.clone(), game, coord, tile_id).await;
+    let restored = data.is_some();
 
     if let Some(data) = data {
         tile_entity
@@ -775,6 +1627,14 @@ async fn insert_new_tile(
             .unwrap();
     }
 
+    // after the data is set, and before the render commands are collected, so that they show what the hook set up
+    tile_entity
+        .send_message(TileEntityMsg::Lifecycle(LifecycleEvent::Place {
+            replaced: old_id,
+            restored,
+        }))
+        .unwrap();
+
     cleanup_render_commands
         .entry(coord)
         .or_default()
@@ -801,57 +1661,573 @@ async fn insert_new_tile(
 
     tile_entities.insert(coord, tile_entity);
     map.tiles.insert(coord, tile_id);
+    update_map_tile(coord, Some(*tile_id));
+
+    if let Some(owner) = owner {
+        map.owners.insert(coord, owner);
+    }
 
     (old_id, old_data)
 }
 
-fn inner_tick(state: &mut GameSystemState) {
-    state.tile_entities.iter().for_each(|(_, tile_entity)| {
-        if let Err(e) = tile_entity.send_message(TileEntityMsg::Tick {
-            tick_count: state.tick_count,
-        }) {
-            log::error!("{e:?}");
+/// Checks if the player is allowed to change the tile at the position, under the map's protection rule.
+async fn may_modify(map: &GameMap, coord: TileCoord, player: PlayerId) -> bool {
+    map.info
+        .lock()
+        .await
+        .protection
+        .allows(map.owners.get(&coord).copied(), player)
+}
+
+/// Checks the rules every tile placement goes through, whether it's by a player, or by a script on behalf of its
+/// tile's owner, if it has one. Returns the response to refuse the placement with, if it's refused.
+#[allow(clippy::too_many_arguments)]
+async fn check_placement(
+    resource_man: &ResourceManager,
+    map: &GameMap,
+    read_only: bool,
+    budget: &MapBudget,
+    tile_count: usize,
+    coord: TileCoord,
+    id: TileId,
+    owner: Option<PlayerId>,
+) -> Option<PlaceTileResponse> {
+    let permitted = match owner {
+        Some(owner) => may_modify(map, coord, owner).await,
+        // without an owner, only what nobody owns can be changed
+        None => !map.owners.contains_key(&coord),
+    };
+
+    if read_only || !permitted {
+        return Some(PlaceTileResponse::Denied);
+    }
+
+    if resource_man.scenery_model(id).is_some() {
+        return None;
+    }
+
+    if map.tiles.get(&coord) == Some(&id) {
+        return Some(PlaceTileResponse::Ignored);
+    }
+
+    if id != TileId(resource_man.registry.none)
+        && !map.tiles.contains_key(&coord)
+        && !budget.allows(BudgetLimit::Tiles, tile_count)
+    {
+        return Some(PlaceTileResponse::OverBudget);
+    }
+
+    None
+}
+
+/// Sends random ticks to tiles at random spots of every loaded chunk.
+/// Spots without a tile, or with one that doesn't take random ticks, are skipped, so sparse chunks get fewer calls.
+fn random_tick(resource_man: &ResourceManager, state: &GameSystemState) {
+    let Some(map) = &state.map else {
+        return;
+    };
+
+    let mut rng = thread_rng();
+
+    for chunk in map.tiles.chunk_keys() {
+        let origin = chunk_origin(chunk);
+
+        for _ in 0..state.random_tick_speed {
+            let coord =
+                origin + TileCoord::new(rng.gen_range(0..CHUNK_SIZE), rng.gen_range(0..CHUNK_SIZE));
+
+            let Some(id) = map.tiles.get(&coord) else {
+                continue;
+            };
+
+            let has_random_tick = resource_man
+                .registry
+                .tiles
+                .get(id)
+                .and_then(|tile| tile.function.as_ref())
+                .and_then(|v| resource_man.functions.get(v))
+                .is_some_and(|(_, metadata)| metadata.has_random_tick);
+
+            if !has_random_tick {
+                continue;
+            }
+
+            if let Some(tile_entity) = state.tile_entities.get(&coord) {
+                if let Err(e) =
+                    tile_entity.send_message(TileEntityMsg::RandomTick(TILE_ENTITY_MAILBOX.queue()))
+                {
+                    log::error!("{e:?}");
+                }
+            }
+        }
+    }
+}
+
+/// Tells the tiles that take animation events which markers their animation passed.
+fn animation_events(
+    resource_man: &ResourceManager,
+    state: &GameSystemState,
+    events: Vec<(TileCoord, String)>,
+) {
+    let Some(map) = &state.map else {
+        return;
+    };
+
+    for (coord, event) in events {
+        let Some(id) = map.tiles.get(&coord) else {
+            continue;
+        };
+
+        let has_animation_event = resource_man
+            .registry
+            .tiles
+            .get(id)
+            .and_then(|tile| tile.function.as_ref())
+            .and_then(|v| resource_man.functions.get(v))
+            .is_some_and(|(_, metadata)| metadata.has_animation_event);
+
+        if !has_animation_event {
+            continue;
+        }
+
+        if let Some(tile_entity) = state.tile_entities.get(&coord) {
+            if let Err(e) = tile_entity.send_message(TileEntityMsg::AnimationEvent(
+                event,
+                TILE_ENTITY_MAILBOX.queue(),
+            )) {
+                log::error!("{e:?}");
+            }
+        }
+    }
+}
+
+/// Tells the tiles next to the ones that changed since the last tick about them, once for all the neighbors that
+/// changed. Only the tiles whose functions have the hook are told, so that the rest aren't woken up for nothing.
+fn notify_neighbors(resource_man: &ResourceManager, state: &mut GameSystemState) {
+    let Some(map) = &state.map else {
+        state.changed_tiles.clear();
+        return;
+    };
+
+    let mut changed_around = HashMap::<TileCoord, Vec<TileCoord>>::new();
+
+    for coord in state.changed_tiles.drain() {
+        for neighbor in coord.neighbors() {
+            changed_around.entry(neighbor).or_default().push(coord);
+        }
+    }
+
+    for (coord, changed) in changed_around {
+        let Some(id) = map.tiles.get(&coord) else {
+            continue;
+        };
+
+        let has_hook = resource_man
+            .registry
+            .tiles
+            .get(id)
+            .and_then(|tile| tile.function.as_ref())
+            .and_then(|v| resource_man.functions.get(v))
+            .is_some_and(|(_, metadata)| {
+                metadata
+                    .lifecycle_hooks
+                    .contains(&LifecycleHook::NeighborChanged)
+            });
+
+        if !has_hook {
+            continue;
+        }
+
+        if let Some(tile_entity) = state.tile_entities.get(&coord) {
+            if let Err(err) = tile_entity.send_message(TileEntityMsg::Lifecycle(
+                LifecycleEvent::NeighborsChanged(changed),
+            )) {
+                log::error!(
+                    "Could not tell the tile at {coord} about its neighbors! Error: {err:?}"
+                );
+            }
+        }
+    }
+}
+
+fn inner_tick(resource_man: &ResourceManager, state: &mut GameSystemState) {
+    // before the ticks, so that the tiles can react to their neighbors in the same tick
+    notify_neighbors(resource_man, state);
+
+    state.sleep_timers.retain(|coord, ticks| {
+        *ticks -= 1;
+
+        if *ticks == 0 {
+            state.sleeping.remove(coord);
         }
+
+        *ticks > 0
     });
 
+    state
+        .tile_entities
+        .iter()
+        .filter(|(coord, _)| !state.sleeping.contains(*coord))
+        .for_each(|(_, tile_entity)| {
+            if let Err(e) = tile_entity.send_message(TileEntityMsg::Tick {
+                tick_count: state.tick_count,
+                queued: TILE_ENTITY_MAILBOX.queue(),
+            }) {
+                log::error!("{e:?}");
+            }
+        });
+
+    random_tick(resource_man, state);
+
+    if let Some(interval) = state.field_diffusion_interval {
+        if state.tick_count as u32 % interval == 0 {
+            diffuse_fields();
+        }
+    }
+
+    if let Some(map) = &mut state.map {
+        agent::tick_agents(resource_man, &mut map.agents);
+    }
+
     state.tick_count = state.tick_count.wrapping_add(1);
+    state.map_ticks += 1;
+    state.script_placements = 0;
+}
+
+/// Saves the map into the next autosave slot, along with the transfer queues. Only gathering them is done here: they're
+/// written to disk in the background, so that the ticks don't wait on it.
+async fn autosave(resource_man: &ResourceManager, state: &mut GameSystemState, slots: u32) {
+    if state.read_only
+        || state
+            .autosave_task
+            .as_ref()
+            .is_some_and(|v| !v.is_finished())
+    {
+        return;
+    }
+
+    let Some(map) = &state.map else {
+        return;
+    };
+
+    // the main menu isn't saved
+    if GameMap::path(&map.opt).is_none() {
+        return;
+    }
+
+    let opt = next_autosave_slot(slots);
+    let (info_raw, map_raw) = map
+        .to_raw(
+            &resource_man.interner,
+            &resource_man.pack_versions(),
+            &state.tile_entities,
+        )
+        .await;
+    // written together with the map, so that restoring the autosave doesn't pair it with older queues
+    let queues_raw = state.transfer_queues.to_raw(&resource_man.interner);
+    let signing_key = state.signing_key;
+    let from = map.opt.clone();
+
+    state.autosave_task = Some(tokio::task::spawn_blocking(move || {
+        if let Err(err) = GameMap::write_raw(&opt, &info_raw, &map_raw) {
+            log::error!("Could not autosave the map! Error: {err:?}");
+            return;
+        }
+
+        if let Err(err) = signing::sign_map(signing_key.as_ref(), &opt) {
+            log::error!("Could not sign the autosave! Error: {err:?}");
+        }
+
+        if let Err(err) = TransferQueues::write_raw(&queues_raw) {
+            log::error!("Could not save transfer queues! Error: {err:?}");
+        }
+
+        log::info!("Autosaved {from} to {opt}");
+    }));
+}
+
+/// Warns about the limits of the map's budget it reached since the last check, once each until it goes back under.
+fn check_budget(resource_man: &ResourceManager, state: &mut GameSystemState) {
+    let Some(map) = &state.map else {
+        return;
+    };
+
+    for limit in BudgetLimit::ALL {
+        let count = match limit {
+            BudgetLimit::Tiles => state.tile_entities.len(),
+            BudgetLimit::Agents => map.agents.len(),
+            BudgetLimit::InFlightItems => state.transfer_queues.total().max(0) as usize,
+        };
+
+        if !state.budget.reached(limit, count) {
+            state.over_budget.remove(&limit);
+            continue;
+        }
+
+        if state.over_budget.insert(limit) {
+            push_err(
+                resource_man.registry.err_ids.map_over_budget,
+                ErrorSeverity::Warn,
+                &FormatContext::from(
+                    [
+                        ("limit", Formattable::display(&limit.name(resource_man))),
+                        ("count", Formattable::integer(&count)),
+                        (
+                            "max",
+                            Formattable::integer(&state.budget.limit(limit).unwrap_or_default()),
+                        ),
+                    ]
+                    .into_iter(),
+                ),
+                resource_man,
+            );
+        }
+    }
+}
+
+/// Calls the CONDITIONS_FUNCTION of the map's conditions function, if it has one, to decide whether the map was won or
+/// lost. The map's data is bound to `this`, so that the function can keep track of its progress in the save.
+async fn check_conditions(
+    resource_man: &ResourceManager,
+    state: &GameSystemState,
+) -> Option<MapOutcome> {
+    let map = state.map.as_ref()?;
+    let mut info = map.info.lock().await;
+
+    let Some(Data::Id(function)) = info.data.get(resource_man.registry.data_ids.conditions) else {
+        return None;
+    };
+    let (ast, metadata) = resource_man.functions.get(function)?;
+
+    let mut produced = Inventory::default();
+    for sample in state.production.samples() {
+        for (id, amount) in sample {
+            produced.add(id, amount);
+        }
+    }
+
+    let mut machines = Inventory::default();
+    for (_, id) in map.tiles.iter() {
+        machines.add(**id, 1);
+    }
+
+    let input = rhai::Map::from([
+        (
+            "ticks".into(),
+            Dynamic::from_int(state.map_ticks as rhai::INT),
+        ),
+        ("produced".into(), Dynamic::from(produced)),
+        ("machines".into(), Dynamic::from(machines)),
+    ]);
+
+    let mut rhai_state = Dynamic::from(mem::take(&mut info.data));
+
+    let profiled = SCRIPT_PROFILER.start(&metadata.str_id, CONDITIONS_FUNCTION);
+    let result = resource_man.engine.call_fn_with_options::<Dynamic>(
+        rhai_call_options(&mut rhai_state),
+        &mut Scope::new(),
+        ast,
+        CONDITIONS_FUNCTION,
+        (input,),
+    );
+    if let Some(profiled) = profiled {
+        profiled.finish();
+    }
+
+    info.data = rhai_state.cast::<DataMap>();
+
+    match result {
+        Ok(result) => result.try_cast::<MapOutcome>(),
+        Err(err) => {
+            rhai_log_err(CONDITIONS_FUNCTION, &metadata.str_id, &err, None);
+            None
+        }
+    }
+}
+
+/// Takes a snapshot of every tile's ID and data.
+async fn snapshot_map(
+    map: &GameMap,
+    tile_entities: &TileEntities,
+) -> Result<MapSnapshot, ractor::MessagingErr<TileEntityMsg>> {
+    let data = multi_call_iter(
+        tile_entities,
+        |reply, _| TileEntityMsg::GetData(reply),
+        None,
+    )
+    .await?;
+
+    Ok(MapSnapshot(
+        data.into_iter()
+            .flat_map(|(coord, data)| map.tiles.get(&coord).map(|id| (coord, (*id, data))))
+            .collect(),
+    ))
 }
 
-/// Runs the game for one tick, logging if the tick is too long.
-pub fn tick(state: &mut GameSystemState) {
+/// Keeps a snapshot of the map after the latest tick, if rewinding is turned on, dropping the oldest one when full.
+async fn record_rewind(state: &mut GameSystemState) {
+    let (Some((capacity, snapshots)), Some(map)) = (&mut state.rewind, &state.map) else {
+        return;
+    };
+
+    match snapshot_map(map, &state.tile_entities).await {
+        Ok(snapshot) => {
+            while snapshots.len() >= *capacity {
+                snapshots.pop_front();
+            }
+
+            snapshots.push_back((state.tick_count, snapshot));
+        }
+        Err(err) => log::error!("Could not take a rewind snapshot! Error: {err:?}"),
+    }
+}
+
+/// Runs the game for one tick, logging if the tick is longer than the interval between ticks.
+pub fn tick(resource_man: &ResourceManager, state: &mut GameSystemState, interval: Duration) {
     let start = Instant::now();
-    inner_tick(state);
+    inner_tick(resource_man, state);
     let finish = Instant::now();
 
     let tick_time = finish - start;
 
-    if tick_time >= MAX_ALLOWED_TICK_INTERVAL {
+    state.last_tick_time = tick_time;
+    state.recent_ticks.push_back(start);
+    while state
+        .recent_ticks
+        .front()
+        .is_some_and(|v| finish - *v > Duration::from_secs(1))
+    {
+        state.recent_ticks.pop_front();
+    }
+
+    if tick_time >= interval {
         log::warn!(
-            "Tick took longer than allowed maximum! tick_time: {:?}, maximum: {:?}",
+            "Tick took longer than the tick interval! tick_time: {:?}, interval: {:?}",
             tick_time,
-            MAX_ALLOWED_TICK_INTERVAL
+            interval
         );
     }
 }
 
-// TODO replace this with a scripted function
-pub fn copy_auxiliary_data(resource_man: &ResourceManager, data: &mut DataMap) -> DataMap {
-    let mut copied = DataMap::default();
+/// Numbers a delta as the next frame for the renderer.
+/// If the renderer didn't acknowledge the previous frame, its batches are sent again ahead of the new ones.
+fn next_render_frame(
+    state: &mut GameSystemState,
+    acknowledged: Option<u64>,
+    delta: RenderDelta,
+) -> (u64, RenderDelta) {
+    let delta = match state.unacked_render_delta.take() {
+        Some((frame, mut unacked)) if acknowledged != Some(frame) => {
+            unacked.extend(delta);
+            unacked
+        }
+        _ => delta,
+    };
+
+    state.render_frame += 1;
+    state.unacked_render_delta = Some((state.render_frame, delta.clone()));
+
+    (state.render_frame, delta)
+}
+
+/// Checks if the game can't keep up, and should defer non-critical requests.
+fn is_overloaded(state: &GameSystemState) -> bool {
+    let awake = state
+        .tile_entities
+        .len()
+        .saturating_sub(state.sleeping.len());
+
+    state.behind || TILE_ENTITY_MAILBOX.queued() > awake * OVERLOAD_QUEUED_TICKS_PER_TILE
+}
 
-    if let Some(v) = data.remove(resource_man.registry.data_ids.direction) {
-        copied.set(resource_man.registry.data_ids.direction, v);
+/// Gets the interval between ticks of the loaded map, or the one from the options if it doesn't set its own.
+async fn effective_tick_interval(
+    resource_man: &ResourceManager,
+    state: &GameSystemState,
+) -> Duration {
+    if let Some(map) = &state.map {
+        if let Some(tps) = map_tick_rate(resource_man, &map.info.lock().await.data) {
+            return tps_to_interval(tps);
+        }
     }
-    if let Some(v) = data.remove(resource_man.registry.data_ids.link) {
-        copied.set(resource_man.registry.data_ids.link, v);
+
+    state.options_tick_interval.unwrap_or(TICK_INTERVAL)
+}
+
+/// Runs every tick that has become due since the game was last woken up.
+/// If the game can't keep up, only a few ticks are run to catch up and the rest of the backlog is dropped, so that it doesn't spiral.
+fn run_due_ticks(resource_man: &ResourceManager, state: &mut GameSystemState, interval: Duration) {
+    let now = Instant::now();
+
+    if let Some(last_wake) = state.last_wake.replace(now) {
+        state.tick_debt += now - last_wake;
     }
-    if let Some(v) = data.remove(resource_man.registry.data_ids.script) {
-        copied.set(resource_man.registry.data_ids.script, v);
+
+    if state.paused {
+        state.tick_debt = Duration::ZERO;
+
+        return;
+    }
+
+    if let Some(until) = state.hit_stop_until {
+        if now < until {
+            state.tick_debt = Duration::ZERO;
+
+            return;
+        }
+
+        state.hit_stop_until = None;
+    }
+
+    let mut ran = 0;
+
+    while state.tick_debt >= interval {
+        if ran == MAX_CATCH_UP_TICKS {
+            if !state.behind {
+                log::warn!(
+                    "The game can't keep up! Dropping {:?} worth of ticks.",
+                    state.tick_debt
+                );
+
+                push_err(
+                    resource_man.registry.err_ids.simulation_behind,
+                    ErrorSeverity::Warn,
+                    &FormatContext::from([].into_iter()),
+                    resource_man,
+                );
+            }
+
+            state.behind = true;
+            state.tick_debt = Duration::ZERO;
+
+            return;
+        }
+
+        tick(resource_man, state, interval);
+
+        state.tick_debt -= interval;
+        ran += 1;
     }
-    if let Some(v) = data.remove(resource_man.registry.data_ids.capacity) {
-        copied.set(resource_man.registry.data_ids.capacity, v);
+
+    if ran > 0 {
+        state.behind = false;
     }
-    if let Some(v) = data.remove(resource_man.registry.data_ids.item) {
-        copied.set(resource_man.registry.data_ids.item, v);
+}
+
+/// Takes the data that should be carried over when copying a tile out of the data map, according to the declared data rules.
+pub fn copy_auxiliary_data(resource_man: &ResourceManager, data: &mut DataMap) -> DataMap {
+    let mut copied = DataMap::default();
+
+    let ids = data
+        .keys()
+        .filter(|id| resource_man.data_copyable(**id))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    for id in ids {
+        if let Some(v) = data.remove(id) {
+            copied.set(id, v);
+        }
     }
 
     copied
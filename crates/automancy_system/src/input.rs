@@ -4,7 +4,7 @@ use automancy_defs::{
     glam::vec2,
     math::{Float, Vec2},
 };
-use automancy_resources::ResourceManager;
+use automancy_resources::{registry::KeyIds, ResourceManager};
 use hashbrown::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 use std::{cell::Cell, mem};
@@ -36,20 +36,25 @@ pub fn get_default_keymap(resource_man: &ResourceManager) -> HashMap<Key, KeyAct
 }
 
 fn set_default_keymap(resource_man: &ResourceManager) {
+    DEFAULT_KEYMAP.set(Some(default_keymap(&resource_man.registry.key_ids)));
+}
+
+/// Builds the default keymap, named with the given key IDs. [get_default_keymap] keeps the one of the loaded resources.
+pub fn default_keymap(key_ids: &KeyIds) -> HashMap<Key, KeyAction> {
     let cancel: KeyAction = KeyAction {
         action: ActionType::Cancel,
         press_type: PressType::Tap,
-        name: Some(resource_man.registry.key_ids.cancel),
+        name: Some(key_ids.cancel),
     };
     let undo: KeyAction = KeyAction {
         action: ActionType::Undo,
         press_type: PressType::Tap,
-        name: Some(resource_man.registry.key_ids.undo),
+        name: Some(key_ids.undo),
     };
     let redo: KeyAction = KeyAction {
         action: ActionType::Redo,
         press_type: PressType::Tap,
-        name: Some(resource_man.registry.key_ids.redo),
+        name: Some(key_ids.redo),
     };
     let debug: KeyAction = KeyAction {
         action: ActionType::Debug,
@@ -69,51 +74,106 @@ fn set_default_keymap(resource_man: &ResourceManager) {
     let toggle_gui: KeyAction = KeyAction {
         action: ActionType::ToggleGui,
         press_type: PressType::Toggle,
-        name: Some(resource_man.registry.key_ids.toggle_gui),
+        name: Some(key_ids.toggle_gui),
     };
     let player: KeyAction = KeyAction {
         action: ActionType::Player,
         press_type: PressType::Toggle,
-        name: Some(resource_man.registry.key_ids.player_menu),
+        name: Some(key_ids.player_menu),
     };
     let delete: KeyAction = KeyAction {
         action: ActionType::Delete,
         press_type: PressType::Tap,
-        name: Some(resource_man.registry.key_ids.remove_tile),
+        name: Some(key_ids.remove_tile),
     };
     let select_mode: KeyAction = KeyAction {
         action: ActionType::SelectMode,
         press_type: PressType::Hold,
-        name: Some(resource_man.registry.key_ids.select_mode),
+        name: Some(key_ids.select_mode),
     };
     let hotkey: KeyAction = KeyAction {
         action: ActionType::HotkeyActive,
         press_type: PressType::Hold,
-        name: Some(resource_man.registry.key_ids.hotkey),
+        name: Some(key_ids.hotkey),
     };
     let cut: KeyAction = KeyAction {
         action: ActionType::Cut,
         press_type: PressType::Tap,
-        name: Some(resource_man.registry.key_ids.cut),
+        name: Some(key_ids.cut),
     };
     let copy: KeyAction = KeyAction {
         action: ActionType::Copy,
         press_type: PressType::Tap,
-        name: Some(resource_man.registry.key_ids.copy),
+        name: Some(key_ids.copy),
     };
     let paste: KeyAction = KeyAction {
         action: ActionType::Paste,
         press_type: PressType::Tap,
-        name: Some(resource_man.registry.key_ids.paste),
+        name: Some(key_ids.paste),
+    };
+    let deconstruct: KeyAction = KeyAction {
+        action: ActionType::Deconstruct,
+        press_type: PressType::Toggle,
+        name: Some(key_ids.deconstruct),
+    };
+    let deconstruct_filter: KeyAction = KeyAction {
+        action: ActionType::DeconstructFilter,
+        press_type: PressType::Tap,
+        name: Some(key_ids.deconstruct_filter),
+    };
+    let map_search: KeyAction = KeyAction {
+        action: ActionType::MapSearch,
+        press_type: PressType::Tap,
+        name: Some(key_ids.map_search),
+    };
+    let next_beacon: KeyAction = KeyAction {
+        action: ActionType::NextBeacon,
+        press_type: PressType::Tap,
+        name: Some(key_ids.next_beacon),
+    };
+    let statistics: KeyAction = KeyAction {
+        action: ActionType::Statistics,
+        press_type: PressType::Toggle,
+        name: Some(key_ids.statistics_menu),
+    };
+
+    let follow: KeyAction = KeyAction {
+        action: ActionType::Follow,
+        press_type: PressType::Tap,
+        name: Some(key_ids.follow),
+    };
+    let field_overlay: KeyAction = KeyAction {
+        action: ActionType::FieldOverlay,
+        press_type: PressType::Tap,
+        name: Some(key_ids.field_overlay),
+    };
+    let xray: KeyAction = KeyAction {
+        action: ActionType::XRay,
+        press_type: PressType::Toggle,
+        name: Some(key_ids.xray),
+    };
+    let blueprints: KeyAction = KeyAction {
+        action: ActionType::Blueprints,
+        press_type: PressType::Tap,
+        name: Some(key_ids.blueprints),
     };
 
-    DEFAULT_KEYMAP.set(Some(HashMap::from_iter([
+    HashMap::from_iter([
         (Key::Character(SmolStr::new_inline("z")), undo),
         (Key::Character(SmolStr::new_inline("r")), redo),
         (Key::Character(SmolStr::new_inline("e")), player),
         (Key::Character(SmolStr::new_inline("x")), cut),
         (Key::Character(SmolStr::new_inline("c")), copy),
         (Key::Character(SmolStr::new_inline("v")), paste),
+        (Key::Character(SmolStr::new_inline("d")), deconstruct),
+        (Key::Character(SmolStr::new_inline("q")), deconstruct_filter),
+        (Key::Character(SmolStr::new_inline("f")), map_search),
+        (Key::Character(SmolStr::new_inline("b")), next_beacon),
+        (Key::Character(SmolStr::new_inline("t")), statistics),
+        (Key::Character(SmolStr::new_inline("g")), follow),
+        (Key::Character(SmolStr::new_inline("h")), field_overlay),
+        (Key::Character(SmolStr::new_inline("o")), xray),
+        (Key::Character(SmolStr::new_inline("k")), blueprints),
         (Key::Named(NamedKey::Escape), cancel),
         (Key::Named(NamedKey::F1), toggle_gui),
         (Key::Named(NamedKey::F2), screenshot),
@@ -122,7 +182,7 @@ fn set_default_keymap(resource_man: &ResourceManager) {
         (Key::Named(NamedKey::Backspace), delete),
         (Key::Named(NamedKey::Shift), select_mode),
         (Key::Named(NamedKey::Control), hotkey),
-    ])));
+    ])
 }
 
 #[derive(Serialize, Deserialize, Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash)]
@@ -141,6 +201,15 @@ pub enum ActionType {
     Cut,
     Copy,
     Paste,
+    Deconstruct,
+    DeconstructFilter,
+    MapSearch,
+    NextBeacon,
+    Statistics,
+    Follow,
+    FieldOverlay,
+    XRay,
+    Blueprints,
 }
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize, Deserialize)]
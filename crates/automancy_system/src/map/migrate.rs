@@ -0,0 +1,53 @@
+use crate::map::MapRaw;
+use std::collections::BTreeMap;
+use std::mem;
+use thiserror::Error;
+
+/// The version of the map format this build writes. Whenever the format changes, bump it and add the step upgrading
+/// the previous version to [STEPS]. Changes to the shape of [MapRaw] have to keep the older shape readable, such as
+/// with `#[serde(default)]` or an alias, so that the step gets to see it.
+pub const MAP_VERSION: u32 = 1;
+
+/// Upgrades a map from the version at its index in [STEPS] to the next one.
+type Step = fn(&mut MapRaw);
+
+/// The steps upgrading a map to [MAP_VERSION], one for each version before it.
+const STEPS: [Step; MAP_VERSION as usize] = [v0_offset_coords];
+
+#[derive(Error, Debug)]
+pub enum MigrateError {
+    #[error("the map is of version {0}, which is newer than the version this build reads ({MAP_VERSION})")]
+    TooNew(u32),
+}
+
+/// Upgrades the map to [MAP_VERSION], running every step from its version on in order. Returns the version it was
+/// saved in, which is MAP_VERSION if nothing had to be done.
+pub fn migrate(map: &mut MapRaw) -> Result<u32, MigrateError> {
+    let from = map.version;
+
+    if from > MAP_VERSION {
+        return Err(MigrateError::TooNew(from));
+    }
+
+    for (version, step) in STEPS.iter().enumerate().skip(from as usize) {
+        log::info!("Migrating map from version {version} to {}", version + 1);
+
+        step(map);
+    }
+
+    map.version = MAP_VERSION;
+
+    Ok(from)
+}
+
+/// Version 0 could save lists of coordinates in the tile data as offset coordinates.
+fn v0_offset_coords(map: &mut MapRaw) {
+    for (_, _, data) in &mut map.tiles {
+        *data = mem::take(data)
+            .into_inner()
+            .into_iter()
+            .map(|(key, value)| (key, value.without_offset_coords()))
+            .collect::<BTreeMap<_, _>>()
+            .into();
+    }
+}
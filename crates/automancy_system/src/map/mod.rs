@@ -0,0 +1,826 @@
+use crate::agent::{Agent, AgentRaw};
+use crate::game;
+use crate::game::GameSystemMessage;
+use crate::ownership::{PlayerId, TileProtection};
+use crate::tile_entity::{LifecycleEvent, TileEntityMsg};
+use automancy_defs::id::{Id, Interner};
+use automancy_defs::{coord::TileCoord, id::TileId, spatial::SpatialIndex};
+use automancy_resources::{
+    data::{DataMap, DataMapRaw},
+    error::{push_err, ErrorSeverity},
+    format::Formattable,
+};
+use automancy_resources::{format::FormatContext, ResourceManager};
+use hashbrown::HashMap;
+use migrate::{migrate, MAP_VERSION};
+use ractor::ActorRef;
+use ron::error::SpannedResult;
+use serde::{Deserialize, Serialize};
+use std::io::{BufReader, BufWriter};
+use std::time::SystemTime;
+use std::{fmt, fs::File};
+use std::{fmt::Debug, io::Write};
+use std::{fs, path::PathBuf};
+use std::{io, sync::Arc};
+use tokio::sync::Mutex;
+use walkdir::WalkDir;
+use zstd::{Decoder, Encoder};
+
+pub mod migrate;
+
+pub static MAP_PATH: &str = "map";
+/// Where saves are copied to when exported, to be shared.
+pub static MAP_EXPORT_PATH: &str = "export";
+/// The folder of saves the autosaves are written to.
+pub static AUTOSAVE_FOLDER: &str = "autosave";
+pub static MAP_EXT: &str = "zst";
+pub static INFO_EXT: &str = "ron";
+
+static MAIN_MENU_INFO: &[u8] = include_bytes!("../assets/main_menu/info.ron");
+static MAIN_MENU_MAP: &[u8] = include_bytes!("../assets/main_menu/map.zst");
+
+const INFO_BUFFER_SIZE: usize = 1024;
+const MAP_BUFFER_SIZE: usize = 256 * 1024;
+
+pub type Tiles = SpatialIndex<TileId>;
+pub type TileEntities = HashMap<TileCoord, ActorRef<TileEntityMsg>>;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LoadMapOption {
+    FromSave(String),
+    MainMenu,
+    Debug, // TODO unused rn but can be useful to have a debug map
+}
+
+impl fmt::Display for LoadMapOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadMapOption::FromSave(v) => f.write_fmt(format_args!("Map {}", v)),
+            LoadMapOption::MainMenu => f.write_str("<main menu>"),
+            LoadMapOption::Debug => f.write_str("<debug map>"),
+        }
+    }
+}
+
+/// A named marker on a map, drawn as a colored beam and listed in the beacon sidebar.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Beacon {
+    pub coord: TileCoord,
+    pub name: String,
+    /// The color of the beam, as RGB.
+    pub color: [u8; 3],
+}
+
+/// Contains information about a map.
+#[derive(Debug, Clone, Default)]
+pub struct MapInfo {
+    /// The last save time as a UTC Unix timestamp.
+    pub save_time: Option<SystemTime>,
+    /// The map data.
+    pub data: DataMap,
+    /// The beacons placed on the map, in the order they were placed.
+    pub beacons: Vec<Beacon>,
+    /// Who may change the tiles of the map.
+    pub protection: TileProtection,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapInfoRaw {
+    /// The number of saved tiles.
+    #[serde(default)]
+    pub tile_count: u32,
+    #[serde(default)]
+    pub data: DataMapRaw,
+    #[serde(default)]
+    pub beacons: Vec<Beacon>,
+    /// The namespaces that were loaded when the map was last saved, with their versions. Empty for maps saved before
+    /// they were recorded.
+    #[serde(default)]
+    pub namespaces: Vec<(String, Option<String>)>,
+    #[serde(default)]
+    pub protection: TileProtection,
+}
+
+/// The namespaces that were added, removed or changed version since a map was last saved.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NamespaceChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// the namespaces whose version changed, with the version the map was saved with, and the loaded one
+    pub changed: Vec<(String, Option<String>, Option<String>)>,
+}
+
+impl NamespaceChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// The tile IDs a map references that no longer exist, mapped to a replacement chosen by the player. `None` drops the tile.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IdRemap(pub HashMap<String, Option<String>>);
+
+/// A map stores tiles and tile entities to disk.
+#[derive(Debug, Clone)]
+pub struct GameMap {
+    /// The name of the map, or a built-in map.
+    /// Name should be sanitized.
+    pub opt: LoadMapOption,
+    /// The list of tiles.
+    pub tiles: Tiles,
+    /// The decorative scenery tiles, which have no tile entities.
+    pub scenery: Tiles,
+    /// The mobile agents moving over the map.
+    pub agents: Vec<Agent>,
+    /// The players who placed the tiles. Tiles with no owner were placed by scripts or before ownership was recorded.
+    pub owners: SpatialIndex<PlayerId>,
+    /// The map's info.
+    pub info: Arc<Mutex<MapInfo>>,
+}
+
+/// A map stores tiles and tile entities to disk.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MapRaw {
+    /// The version of the format the map was saved in, which it's migrated from when read. 0 for maps saved before it
+    /// was recorded.
+    #[serde(default)]
+    pub version: u32,
+    pub tiles: Vec<(TileCoord, Id, DataMapRaw)>,
+    /// The scenery tiles, which have no data. Their IDs are in the same tile map.
+    #[serde(default)]
+    pub scenery: Vec<(TileCoord, Id)>,
+    /// The agents, which keep their IDs themselves, so that they aren't taken for missing tiles.
+    #[serde(default)]
+    pub agents: Vec<AgentRaw>,
+    /// The players who placed the tiles, for the tiles that have an owner.
+    #[serde(default)]
+    pub owners: Vec<(TileCoord, PlayerId)>,
+    pub tile_map: HashMap<Id, String>,
+}
+
+/// A snapshot of every tile's ID and data, taken without saving to disk.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MapSnapshot(pub HashMap<TileCoord, (TileId, DataMap)>);
+
+/// How a single tile differs between two snapshots.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TileDiff {
+    Added(TileId),
+    Removed(TileId),
+    Replaced(TileId, TileId),
+    /// the tile stayed the same, but these data keys were added, removed, or changed
+    DataChanged(TileId, Vec<Id>),
+}
+
+impl MapSnapshot {
+    /// Lists every tile that differs from `self` to `newer`.
+    pub fn diff(&self, newer: &MapSnapshot) -> Vec<(TileCoord, TileDiff)> {
+        let mut diffs = vec![];
+
+        for (coord, (id, data)) in &self.0 {
+            match newer.0.get(coord) {
+                None => diffs.push((*coord, TileDiff::Removed(*id))),
+                Some((new_id, _)) if new_id != id => {
+                    diffs.push((*coord, TileDiff::Replaced(*id, *new_id)))
+                }
+                Some((_, new_data)) => {
+                    let mut keys = data
+                        .keys()
+                        .chain(new_data.keys())
+                        .filter(|key| data.get(**key) != new_data.get(**key))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    keys.sort();
+                    keys.dedup();
+
+                    if !keys.is_empty() {
+                        diffs.push((*coord, TileDiff::DataChanged(*id, keys)));
+                    }
+                }
+            }
+        }
+
+        for (coord, (id, _)) in &newer.0 {
+            if !self.0.contains_key(coord) {
+                diffs.push((*coord, TileDiff::Added(*id)));
+            }
+        }
+
+        diffs
+    }
+
+    /// Converts the snapshot into the same compact form that maps are saved in.
+    pub fn to_raw(&self, interner: &Interner) -> MapRaw {
+        let mut raw = MapRaw {
+            version: MAP_VERSION,
+            tiles: Vec::with_capacity(self.0.len()),
+            scenery: vec![],
+            agents: vec![],
+            owners: vec![],
+            tile_map: Default::default(),
+        };
+
+        for (coord, (id, data)) in &self.0 {
+            if let Some(name) = interner.resolve(**id) {
+                raw.tile_map.entry(**id).or_insert_with(|| name.to_string());
+                raw.tiles.push((*coord, **id, data.to_raw(interner)));
+            }
+        }
+
+        raw
+    }
+}
+
+impl MapInfoRaw {
+    /// Parses map info from RON bytes. Never panics on malformed input.
+    pub fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+        Ok(ron::de::from_bytes(bytes)?)
+    }
+
+    /// Compares the namespaces the map was saved with, and their versions, to the loaded ones. Maps that didn't record
+    /// them have no changes.
+    pub fn namespace_changes(&self, loaded: &[(String, Option<String>)]) -> NamespaceChanges {
+        if self.namespaces.is_empty() {
+            return NamespaceChanges::default();
+        }
+
+        let saved_versions = self.namespaces.iter().cloned().collect::<HashMap<_, _>>();
+        let loaded_versions = loaded.iter().cloned().collect::<HashMap<_, _>>();
+
+        NamespaceChanges {
+            added: loaded
+                .iter()
+                .filter(|(v, _)| !saved_versions.contains_key(v))
+                .map(|(v, _)| v.clone())
+                .collect(),
+            removed: self
+                .namespaces
+                .iter()
+                .filter(|(v, _)| !loaded_versions.contains_key(v))
+                .map(|(v, _)| v.clone())
+                .collect(),
+            changed: loaded
+                .iter()
+                .filter_map(|(v, version)| {
+                    let old = saved_versions.get(v)?;
+
+                    (old != version).then(|| (v.clone(), old.clone(), version.clone()))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl MapRaw {
+    /// Decodes a zstd compressed map. Never panics on malformed input.
+    pub fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+        Ok(ron::de::from_reader(Decoder::with_buffer(bytes)?)?)
+    }
+}
+
+impl GameMap {
+    /// Creates a new empty map.
+    pub fn new_empty(opt: LoadMapOption) -> Self {
+        Self {
+            opt,
+            tiles: Default::default(),
+            scenery: Default::default(),
+            agents: Default::default(),
+            owners: Default::default(),
+            info: Arc::new(Default::default()),
+        }
+    }
+
+    /// Gets the path to a map from its name.
+    pub fn path(opt: &LoadMapOption) -> Option<PathBuf> {
+        match opt {
+            LoadMapOption::FromSave(map_name) => Some(PathBuf::from(MAP_PATH).join(map_name)),
+            _ => None,
+        }
+    }
+
+    /// Gets the path to a map's info from its name.
+    pub fn info(opt: &LoadMapOption) -> Option<PathBuf> {
+        GameMap::path(opt).map(|v| v.join("info").with_extension(INFO_EXT))
+    }
+
+    /// Gets the path to a map's tiles from its name.
+    pub fn map(opt: &LoadMapOption) -> Option<PathBuf> {
+        GameMap::path(opt).map(|v| v.join("map").with_extension(MAP_EXT))
+    }
+
+    /// Gets the path to a map's ID remapping from its name.
+    pub fn remap(opt: &LoadMapOption) -> Option<PathBuf> {
+        GameMap::path(opt).map(|v| v.join("remap").with_extension(INFO_EXT))
+    }
+
+    pub fn read_remap(opt: &LoadMapOption) -> IdRemap {
+        Self::remap(opt)
+            .and_then(|path| File::open(path).ok())
+            .and_then(|file| ron::de::from_reader(BufReader::new(file)).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn write_remap(opt: &LoadMapOption, remap: &IdRemap) -> io::Result<()> {
+        if let Some(path) = Self::remap(opt) {
+            let mut writer = BufWriter::new(File::create(path)?);
+            ron::ser::to_writer_pretty(&mut writer, remap, Default::default())
+                .map_err(io::Error::other)?;
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a map's tiles to disk as they are, without going through the tile entities.
+    pub fn write_map(opt: &LoadMapOption, map: &MapRaw) -> io::Result<()> {
+        if let Some(path) = Self::map(opt) {
+            let writer = BufWriter::with_capacity(MAP_BUFFER_SIZE, File::create(path)?);
+            let mut encoder = Encoder::new(writer, 0)?;
+            ron::ser::to_writer(&mut encoder, map).map_err(io::Error::other)?;
+            encoder.do_finish()?.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Lists the tile IDs the map references that are neither registered nor remapped.
+    pub fn missing_ids(resource_man: &ResourceManager, opt: &LoadMapOption) -> Vec<String> {
+        let Ok(map) = Self::read_map(resource_man, opt) else {
+            return vec![];
+        };
+        let remap = Self::read_remap(opt);
+
+        let mut missing = map
+            .tile_map
+            .into_values()
+            .filter(|id| !remap.0.contains_key(id) && find_tile(resource_man, id).is_none())
+            .collect::<Vec<_>>();
+        missing.sort();
+
+        missing
+    }
+
+    pub fn read_info(
+        resource_man: &ResourceManager,
+        opt: &LoadMapOption,
+    ) -> Result<(MapInfoRaw, Option<SystemTime>), bool> {
+        let mut time = None;
+
+        let decoded: SpannedResult<MapInfoRaw> = match opt {
+            LoadMapOption::FromSave(name) => {
+                log::debug!("Trying to read map info from {name}");
+
+                let path = Self::info(opt).unwrap();
+
+                let file = File::open(path).map_err(|_| false)?;
+                time = file
+                    .metadata()
+                    .and_then(|v| v.modified().or(v.accessed()))
+                    .ok();
+
+                ron::de::from_reader(BufReader::with_capacity(INFO_BUFFER_SIZE, file))
+            }
+            LoadMapOption::MainMenu => ron::de::from_bytes(MAIN_MENU_INFO),
+            LoadMapOption::Debug => unreachable!(),
+        };
+
+        match decoded {
+            Ok(v) => Ok((v, time)),
+            Err(e) => {
+                log::error!("Error loading map {opt}, in reading info: serde: {e:?}");
+
+                push_err(
+                    resource_man.registry.err_ids.invalid_map_data,
+                    ErrorSeverity::Warn,
+                    &FormatContext::from([("map_name", Formattable::display(&opt))].into_iter()),
+                    resource_man,
+                );
+
+                Err(true)
+            }
+        }
+    }
+
+    pub fn read_map(resource_man: &ResourceManager, opt: &LoadMapOption) -> Result<MapRaw, bool> {
+        let decoded: anyhow::Result<MapRaw> = match opt {
+            LoadMapOption::FromSave(name) => {
+                log::debug!("Trying to read map data from {name}");
+
+                let path = Self::map(opt).unwrap();
+
+                let file = File::open(path).map_err(|_| false)?;
+
+                Decoder::with_buffer(BufReader::with_capacity(MAP_BUFFER_SIZE, file))
+                    .map_err(anyhow::Error::from)
+                    .and_then(|decoder| Ok(ron::de::from_reader(decoder)?))
+            }
+            LoadMapOption::MainMenu => MapRaw::decode(MAIN_MENU_MAP),
+            LoadMapOption::Debug => unreachable!(),
+        };
+
+        // saves of older versions are upgraded as they are read, and written in the current one the next time
+        let decoded = decoded.and_then(|mut map| {
+            migrate(&mut map)?;
+
+            Ok(map)
+        });
+
+        match decoded {
+            Ok(v) => Ok(v),
+            Err(e) => {
+                log::error!("Error loading map {opt}, in reading map: serde: {e:?}");
+
+                push_err(
+                    resource_man.registry.err_ids.invalid_map_data,
+                    ErrorSeverity::Warn,
+                    &FormatContext::from([("map_name", Formattable::display(&opt))].into_iter()),
+                    resource_man,
+                );
+
+                Err(true)
+            }
+        }
+    }
+
+    /// Loads a map from disk.
+    pub async fn load(
+        game: ActorRef<GameSystemMessage>,
+        resource_man: Arc<ResourceManager>,
+        opt: &LoadMapOption,
+    ) -> Result<(Self, TileEntities), bool> {
+        if let Some(path) = GameMap::path(opt) {
+            fs::create_dir_all(path).map_err(|_| false)?;
+        }
+
+        let (info, save_time) = GameMap::read_info(&resource_man, opt)?;
+        let mut map = GameMap::read_map(&resource_man, opt)?;
+        let remap = GameMap::read_remap(opt);
+
+        map.tile_map.retain(|_, id| match remap.0.get(id) {
+            Some(Some(new_id)) => {
+                id.clone_from(new_id);
+                true
+            }
+            Some(None) => false,
+            None => true,
+        });
+
+        let mut tiles = Tiles::new();
+        let mut tile_entities = HashMap::new();
+
+        for (coord, id, data) in map.tiles.into_iter() {
+            if let Some(id) = map
+                .tile_map
+                .get(&id)
+                .and_then(|id| find_tile(&resource_man, id))
+            {
+                let tile_entity =
+                    game::new_tile(resource_man.clone(), game.clone(), coord, id).await;
+
+                for (key, value) in data.to_data(&resource_man.interner) {
+                    tile_entity
+                        .send_message(TileEntityMsg::SetDataValue(key, value))
+                        .unwrap();
+                }
+
+                tiles.insert(coord, id);
+                tile_entities.insert(coord, tile_entity);
+            }
+        }
+
+        // only once every tile was sent its data, so that a hook asking a neighbor for its data gets it
+        for (coord, tile_entity) in &tile_entities {
+            if let Err(err) =
+                tile_entity.send_message(TileEntityMsg::Lifecycle(LifecycleEvent::Load))
+            {
+                log::error!("Could not tell the tile at {coord} it's loaded! Error: {err:?}");
+            }
+        }
+
+        let scenery = map
+            .scenery
+            .into_iter()
+            .flat_map(|(coord, id)| {
+                map.tile_map
+                    .get(&id)
+                    .and_then(|id| find_tile(&resource_man, id))
+                    .filter(|id| resource_man.scenery_model(*id).is_some())
+                    .map(|id| (coord, id))
+            })
+            .collect();
+
+        let agents = map
+            .agents
+            .into_iter()
+            .flat_map(|raw| Agent::from_raw(&resource_man, raw))
+            .collect();
+
+        let owners = map
+            .owners
+            .into_iter()
+            .filter(|(coord, _)| tiles.contains_key(coord))
+            .collect();
+
+        Ok((
+            Self {
+                opt: opt.clone(),
+                tiles,
+                scenery,
+                agents,
+                owners,
+                info: Arc::new(Mutex::new(MapInfo {
+                    save_time,
+                    data: info.data.to_data(&resource_man.interner),
+                    beacons: info.beacons,
+                    protection: info.protection,
+                })),
+            },
+            tile_entities,
+        ))
+    }
+
+    /// Saves a map to disk.
+    pub async fn save(
+        &self,
+        interner: &Interner,
+        namespaces: &[(String, Option<String>)],
+        tile_entities: &TileEntities,
+    ) -> io::Result<()> {
+        if GameMap::path(&self.opt).is_some() {
+            let (info_raw, map_raw) = self.to_raw(interner, namespaces, tile_entities).await;
+
+            Self::write_raw(&self.opt, &info_raw, &map_raw)?;
+
+            log::info!("Saved map {}", self.opt);
+        }
+
+        Ok(())
+    }
+
+    /// Gathers everything that is saved of the map, asking each tile entity for its data.
+    pub async fn to_raw(
+        &self,
+        interner: &Interner,
+        namespaces: &[(String, Option<String>)],
+        tile_entities: &TileEntities,
+    ) -> (MapInfoRaw, MapRaw) {
+        let mut map_raw = MapRaw {
+            version: MAP_VERSION,
+            tiles: vec![],
+            scenery: Vec::with_capacity(self.scenery.len()),
+            agents: Vec::with_capacity(self.agents.len()),
+            owners: Vec::with_capacity(self.owners.len()),
+            tile_map: Default::default(),
+        };
+
+        for (coord, id) in self.tiles.iter() {
+            if let Some(tile_entity) = tile_entities.get(coord) {
+                if !map_raw.tile_map.contains_key(&**id) {
+                    map_raw
+                        .tile_map
+                        .insert(**id, interner.resolve(**id).unwrap().to_string());
+                }
+
+                let data = tile_entity
+                    .call(TileEntityMsg::GetData, None)
+                    .await
+                    .unwrap()
+                    .unwrap();
+                let data = data.to_raw(interner);
+
+                map_raw.tiles.push((*coord, **id, data));
+            }
+        }
+
+        for (coord, id) in self.scenery.iter() {
+            map_raw
+                .tile_map
+                .entry(**id)
+                .or_insert_with(|| interner.resolve(**id).unwrap().to_string());
+
+            map_raw.scenery.push((*coord, **id));
+        }
+
+        map_raw
+            .agents
+            .extend(self.agents.iter().flat_map(|agent| agent.to_raw(interner)));
+
+        map_raw.owners.extend(
+            self.owners
+                .iter()
+                .filter(|(coord, _)| self.tiles.contains_key(coord))
+                .map(|(coord, owner)| (*coord, *owner)),
+        );
+
+        let info_raw = {
+            let info = self.info.lock().await;
+
+            MapInfoRaw {
+                data: info.data.to_raw(interner),
+                tile_count: self.tiles.len() as u32,
+                beacons: info.beacons.clone(),
+                namespaces: namespaces.to_vec(),
+                protection: info.protection,
+            }
+        };
+
+        (info_raw, map_raw)
+    }
+
+    /// Writes a map gathered with [GameMap::to_raw] to disk, under the given name. This doesn't need the game, so
+    /// it can be done in the background.
+    pub fn write_raw(
+        opt: &LoadMapOption,
+        info_raw: &MapInfoRaw,
+        map_raw: &MapRaw,
+    ) -> io::Result<()> {
+        let Some(((path, info), map)) = GameMap::path(opt).zip(Self::info(opt)).zip(Self::map(opt))
+        else {
+            return Ok(());
+        };
+
+        fs::create_dir_all(path)?;
+
+        let info = File::create(info)?;
+
+        let mut info_writer = BufWriter::with_capacity(INFO_BUFFER_SIZE, info);
+
+        let map = File::create(map)?;
+
+        let map_writer = BufWriter::with_capacity(MAP_BUFFER_SIZE, map);
+        let mut map_encoder = Encoder::new(map_writer, 0)?;
+
+        ron::ser::to_writer(&mut info_writer, info_raw).map_err(io::Error::other)?;
+
+        ron::ser::to_writer(&mut map_encoder, map_raw).map_err(io::Error::other)?;
+
+        info_writer.flush()?;
+        // the buffered writer would drop any error while flushing on its own
+        map_encoder.do_finish()?.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Picks the autosave slot to write next: the first one that wasn't written yet, or else the one written the longest
+/// ago.
+pub fn next_autosave_slot(slots: u32) -> LoadMapOption {
+    (1..=slots.max(1))
+        .map(|n| LoadMapOption::FromSave(format!("{AUTOSAVE_FOLDER}/autosave-{n}")))
+        .min_by_key(|opt| {
+            GameMap::info(opt)
+                .and_then(|v| fs::metadata(v).ok())
+                .and_then(|v| v.modified().ok())
+        })
+        .unwrap()
+}
+
+/// Looks up a registered tile by its ID string.
+pub(crate) fn find_tile(resource_man: &ResourceManager, id: &str) -> Option<TileId> {
+    resource_man
+        .interner
+        .get(id)
+        .map(TileId)
+        .filter(|id| resource_man.registry.tiles.contains_key(id))
+}
+
+/// Sanitizes the name to ensure that the map can be used without problems on all platforms. This includes removing leading/trailing whitespace and periods, replacing non-alphanumeric characters, and replacing Windows disallowed names.
+pub fn sanitize_name(name: String) -> String {
+    if name.is_empty() {
+        return "empty".to_string();
+    }
+
+    let name = name.trim();
+    let name = name.trim_matches('.');
+    name.replace(|c: char| !c.is_alphanumeric(), "_")
+}
+
+/// Sanitizes every folder of a path to a folder of saves, with `/` between them, like [sanitize_name]. Empty folders
+/// and ones made only of periods are dropped, so that the path can't lead out of the map folder.
+pub fn sanitize_folder(path: &str) -> String {
+    path.split('/')
+        .map(|v| v.trim().trim_matches('.'))
+        .filter(|v| !v.is_empty())
+        .map(|v| sanitize_name(v.to_string()))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Splits the path of a save into the folder it is in, which is empty at the top of the map folder, and its name.
+pub fn split_save_path(path: &str) -> (&str, &str) {
+    path.rsplit_once('/').unwrap_or(("", path))
+}
+
+/// Joins a folder and a name into the path of a save or a folder.
+pub fn join_save_path(folder: &str, name: &str) -> String {
+    if folder.is_empty() {
+        name.to_string()
+    } else {
+        format!("{folder}/{name}")
+    }
+}
+
+/// Lists the saves in the map folder, and the folders they are organized into, by their paths relative to it with `/`
+/// between folders. A folder with a map info in it is a save, anything else is a folder of saves. Hidden ones are
+/// skipped.
+pub fn list_saves() -> (Vec<String>, Vec<String>) {
+    let mut saves = vec![];
+    let mut folders = vec![];
+
+    let mut walk = WalkDir::new(MAP_PATH).min_depth(1).into_iter();
+
+    while let Some(entry) = walk.next() {
+        let Ok(entry) = entry else {
+            continue;
+        };
+
+        if !entry.file_type().is_dir() {
+            continue;
+        }
+
+        let Some(path) = entry
+            .path()
+            .strip_prefix(MAP_PATH)
+            .ok()
+            .and_then(|v| v.to_str())
+            .map(|v| v.replace('\\', "/"))
+        else {
+            continue;
+        };
+
+        if split_save_path(&path).1.starts_with('.') {
+            walk.skip_current_dir();
+            continue;
+        }
+
+        if GameMap::info(&LoadMapOption::FromSave(path.clone())).is_some_and(|v| v.is_file()) {
+            // saves don't hold other saves
+            walk.skip_current_dir();
+            saves.push(path);
+        } else {
+            folders.push(path);
+        }
+    }
+
+    folders.sort();
+
+    (saves, folders)
+}
+
+/// Moves a save into the folder, keeping its name. Returns its new path.
+pub fn move_save(path: &str, folder: &str) -> io::Result<String> {
+    let new_path = join_save_path(folder, split_save_path(path).1);
+
+    if new_path == path {
+        return Ok(new_path);
+    }
+
+    let to = GameMap::path(&LoadMapOption::FromSave(new_path.clone())).unwrap();
+    if to.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("there is already a save at {new_path}"),
+        ));
+    }
+
+    fs::create_dir_all(PathBuf::from(MAP_PATH).join(folder))?;
+    fs::rename(
+        GameMap::path(&LoadMapOption::FromSave(path.to_string())).unwrap(),
+        to,
+    )?;
+
+    log::info!("Moved map {path} to {new_path}");
+
+    Ok(new_path)
+}
+
+/// Copies the saves into the export folder by their names, replacing earlier exports of the same name. Returns the
+/// folder they were copied to.
+pub fn export_saves(paths: &[String]) -> io::Result<PathBuf> {
+    let export = PathBuf::from(MAP_EXPORT_PATH);
+
+    for path in paths {
+        let from = GameMap::path(&LoadMapOption::FromSave(path.clone())).unwrap();
+        let to = export.join(split_save_path(path).1);
+
+        if to.exists() {
+            fs::remove_dir_all(&to)?;
+        }
+
+        for entry in WalkDir::new(&from) {
+            let entry = entry?;
+            let dest = to.join(entry.path().strip_prefix(&from).map_err(io::Error::other)?);
+
+            if entry.file_type().is_dir() {
+                fs::create_dir_all(dest)?;
+            } else {
+                fs::copy(entry.path(), dest)?;
+            }
+        }
+
+        log::info!("Exported map {path} to {}", to.display());
+    }
+
+    Ok(export)
+}
@@ -1,21 +1,67 @@
+use crate::game::TPS;
 use crate::input::{get_default_keymap, KeyAction};
 use automancy_resources::ResourceManager;
-use hashbrown::HashMap;
-use ron::ser::PrettyConfig;
+use hashbrown::{HashMap, HashSet};
+use ron::{error::SpannedResult, ser::PrettyConfig};
 use serde::{Deserialize, Serialize};
 use std::{
+    fs,
     fs::{read_to_string, File},
-    path::Path,
+    path::{Path, PathBuf},
 };
 use std::{io::Write, mem};
 use winit::keyboard::Key;
 
 static OPTIONS_PATH: &str = "options.ron";
 static MISC_OPTIONS_PATH: &str = "misc_options.ron";
+static OPTIONS_BACKUP_PATH: &str = "options_backups";
+
+/// How many of the previous versions of the options are kept.
+pub const MAX_OPTIONS_BACKUPS: usize = 5;
+
+/// Gets the path of a previous version of the options, the newest being 1.
+fn options_backup(index: usize) -> PathBuf {
+    Path::new(OPTIONS_BACKUP_PATH).join(format!("options.{index}.ron"))
+}
+
+/// Checks if there's a previous version of the options to restore.
+pub fn has_options_backup() -> bool {
+    options_backup(1).exists()
+}
+
+/// Keeps what the options file holds as the newest previous version, if it's about to be overwritten with something
+/// else. The oldest version is dropped if there are too many.
+fn backup_options(document: &str) -> anyhow::Result<()> {
+    let Ok(previous) = read_to_string(OPTIONS_PATH) else {
+        return Ok(());
+    };
+
+    // options that couldn't be read are no use to go back to
+    if previous == document || GameOptions::parse(&previous).is_err() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(OPTIONS_BACKUP_PATH)?;
+
+    for index in (1..MAX_OPTIONS_BACKUPS).rev() {
+        let path = options_backup(index);
+
+        if path.exists() {
+            fs::rename(path, options_backup(index + 1))?;
+        }
+    }
+
+    fs::write(options_backup(1), previous)?;
+
+    Ok(())
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MiscOptions {
     pub language: String,
+    /// the keys of the errors the player chose to not be shown again
+    #[serde(default)]
+    pub muted_errors: HashSet<String>,
 
     #[serde(skip)]
     pub synced: bool,
@@ -25,18 +71,24 @@ impl Default for MiscOptions {
     fn default() -> Self {
         Self {
             language: String::from("en_US"),
+            muted_errors: HashSet::new(),
             synced: false,
         }
     }
 }
 
 impl MiscOptions {
+    /// Parses the options from a string, without touching the disk.
+    pub fn parse(file: &str) -> SpannedResult<Self> {
+        ron::de::from_str(file)
+    }
+
     pub fn load() -> Self {
         log::info!("Loading options...");
 
         let file = read_to_string(Path::new(MISC_OPTIONS_PATH)).unwrap_or_default();
 
-        let mut this: MiscOptions = ron::de::from_str(&file)
+        let mut this: MiscOptions = Self::parse(&file)
             .inspect_err(|err| {
                 log::warn!("Error parsing misc options! A fresh one will be created. Error: {err}")
             })
@@ -75,6 +127,8 @@ pub struct GameOptions {
     pub graphics: GraphicsOptions,
     pub audio: AudioOptions,
     pub gui: GuiOptions,
+    #[serde(default)]
+    pub advanced: AdvancedOptions,
     pub keymap: HashMap<Key, KeyAction>,
 
     #[serde(skip)]
@@ -88,6 +142,7 @@ impl Default for GameOptions {
             graphics: Default::default(),
             audio: Default::default(),
             gui: Default::default(),
+            advanced: Default::default(),
             keymap: Default::default(),
             synced: false,
         }
@@ -95,21 +150,66 @@ impl Default for GameOptions {
 }
 
 impl GameOptions {
+    /// Parses the options from a string, without touching the disk. The keymap is not validated, see
+    /// [GameOptions::validate_keymap_against].
+    pub fn parse(file: &str) -> SpannedResult<Self> {
+        ron::de::from_str(file)
+    }
+
     pub fn load(resource_man: &ResourceManager) -> Self {
         log::info!("Loading options...");
 
         let file = read_to_string(Path::new(OPTIONS_PATH)).unwrap_or_default();
 
-        let mut this: GameOptions = ron::de::from_str(&file)
+        let mut this: GameOptions = Self::parse(&file)
             .inspect_err(|err| {
                 log::warn!("Error parsing options! A fresh one will be created. Error: {err}")
             })
             .unwrap_or_default();
-        let read_keymap = mem::take(&mut this.keymap);
+        this.validate_keymap(resource_man);
 
-        let mut default = get_default_keymap(resource_man);
+        if let Err(err) = this.save() {
+            log::error!("Error saving options! {err}");
+        }
+
+        this
+    }
+
+    /// Replaces the options with their newest previous version, which is then no longer kept as one.
+    pub fn restore_previous(resource_man: &ResourceManager) -> anyhow::Result<Self> {
+        let mut this = Self::parse(&read_to_string(options_backup(1))?)?;
+        this.validate_keymap(resource_man);
+
+        this.write(false)?;
+
+        fs::remove_file(options_backup(1))?;
+        for index in 2..=MAX_OPTIONS_BACKUPS {
+            let path = options_backup(index);
+
+            if path.exists() {
+                fs::rename(path, options_backup(index - 1))?;
+            }
+        }
+
+        Ok(this)
+    }
+
+    /// Fills in the keys missing from the keymap with their default actions, and resets it if an action is bound twice.
+    fn validate_keymap(&mut self, resource_man: &ResourceManager) {
+        self.validate_keymap_against(&get_default_keymap(resource_man));
+    }
+
+    /// Validates the keymap like [GameOptions::load] does, against the given default keymap, e.g. one built by
+    /// [crate::input::default_keymap] without loading the resources.
+    pub fn validate_keymap_against(&mut self, default_keymap: &HashMap<Key, KeyAction>) {
+        let read_keymap = mem::take(&mut self.keymap);
+
+        let mut default = default_keymap.clone();
         for (key, read_action) in read_keymap {
-            let mut modified_action = default[&key];
+            let Some(mut modified_action) = default.get(&key).copied() else {
+                log::warn!("Ignoring unknown key {key:?} in keymap");
+                continue;
+            };
             modified_action.action = read_action.action;
 
             default.insert(key, modified_action);
@@ -126,29 +226,42 @@ impl GameOptions {
                     original.0,
                     other.0
                 );
-                default = get_default_keymap(resource_man);
+                default = default_keymap.clone();
                 break;
             }
         }
 
-        this.keymap = default;
+        self.keymap = default;
+    }
 
-        if let Err(err) = this.save() {
-            log::error!("Error saving options! {err}");
+    /// Gets the default options with the default keymap, without reading or saving the options file.
+    pub fn defaults(resource_man: &ResourceManager) -> Self {
+        Self {
+            keymap: get_default_keymap(resource_man),
+            ..Default::default()
         }
-
-        this
     }
 
+    /// Saves the options, keeping what was saved before as a previous version.
     pub fn save(&mut self) -> anyhow::Result<()> {
-        let mut file = File::create(OPTIONS_PATH)?;
+        self.write(true)
+    }
 
+    fn write(&mut self, backup: bool) -> anyhow::Result<()> {
         log::info!("Serializing options...");
         log::debug!("{self:?}");
 
         let document = ron::ser::to_string_pretty(&self, PrettyConfig::default())
             .inspect_err(|err| log::warn!("Error writing options! Error: {err}"))?;
 
+        if backup {
+            if let Err(err) = backup_options(&document) {
+                log::warn!("Could not keep the previous options! Error: {err}");
+            }
+        }
+
+        let mut file = File::create(OPTIONS_PATH)?;
+
         log::info!("Saving options...");
 
         write!(&mut file, "{document}")?;
@@ -168,19 +281,38 @@ pub enum AAType {
     TAA,
 }
 
+/// The smallest and largest the UI can be scaled, on top of the scale factor of the monitor.
+pub const UI_SCALE_RANGE: (i32, i32) = (50, 200);
+/// The logical height of the monitor the UI is made for at the normal scale.
+const UI_SCALE_REFERENCE_HEIGHT: f64 = 1080.0;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UiScale {
     Small,
     Normal,
     Large,
+    /// picks the scale from the size of the monitor the window is on
+    Auto,
+    /// the scale in percent
+    Custom(i32),
 }
 
 impl UiScale {
-    pub const fn to_f64(self) -> f64 {
+    /// Gets how much to scale the UI by, on top of the scale factor of the monitor, which is `logical_height` tall after
+    /// that scale factor. Only [UiScale::Auto] uses the height.
+    pub fn to_f64(self, logical_height: Option<f64>) -> f64 {
+        let (min, max) = UI_SCALE_RANGE;
+
         match self {
             UiScale::Small => const { 2.0 / 3.0 },
             UiScale::Normal => const { 1.0 },
             UiScale::Large => const { 5.0 / 3.0 },
+            UiScale::Auto => logical_height
+                // rounded, so that the scale doesn't flicker between monitors of almost the same size
+                .map(|v| (v / UI_SCALE_REFERENCE_HEIGHT * 4.0).round() / 4.0)
+                .unwrap_or(1.0)
+                .clamp(min as f64 / 100.0, max as f64 / 100.0),
+            UiScale::Custom(percent) => percent.clamp(min, max) as f64 / 100.0,
         }
     }
 }
@@ -188,25 +320,58 @@ impl UiScale {
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct GraphicsOptions {
     pub fps_limit: i32,
+    /// whether to present in mailbox mode when vsync is off, if supported, for less tearing at the same latency
+    #[serde(default)]
+    pub mailbox: bool,
     pub fullscreen: bool,
     pub ui_scale: UiScale,
     pub anti_aliasing: AAType,
+    /// whether visual effects are cut back while frames are too slow, and brought back once they're fast again
+    #[serde(default = "adaptive_quality_default")]
+    pub adaptive_quality: bool,
+}
+
+fn adaptive_quality_default() -> bool {
+    true
 }
 
 impl Default for GraphicsOptions {
     fn default() -> Self {
         Self {
             fps_limit: 0,
+            mailbox: false,
             fullscreen: false,
-            ui_scale: UiScale::Normal,
+            ui_scale: UiScale::Auto,
             anti_aliasing: AAType::FXAA,
+            adaptive_quality: true,
         }
     }
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GuiOptions {
     font: Option<String>,
+    /// the font to fall back to for characters the main font doesn't have, such as CJK ones
+    cjk_font: Option<String>,
+    /// whether to draw progress bars above working machines
+    pub show_progress_bars: bool,
+    /// how strong camera shakes and screen flashes can be, from 0 to 1. 0 turns them and hit-stops off
+    pub screen_effects: f64,
+    /// whether menus and buttons appear and respond without animating
+    pub reduced_motion: bool,
+}
+
+impl Default for GuiOptions {
+    fn default() -> Self {
+        Self {
+            font: None,
+            cjk_font: None,
+            show_progress_bars: true,
+            screen_effects: 1.0,
+            reduced_motion: false,
+        }
+    }
 }
 
 impl GuiOptions {
@@ -226,6 +391,47 @@ impl GuiOptions {
             self.font = None
         }
     }
+
+    pub fn get_cjk_font(&self, resource_man: &ResourceManager) -> Option<String> {
+        self.cjk_font
+            .clone()
+            .filter(|font| resource_man.fonts.contains_key(font))
+    }
+
+    pub fn set_cjk_font(&mut self, resource_man: &ResourceManager, font: Option<String>) {
+        self.cjk_font = font.filter(|font| resource_man.fonts.contains_key(font));
+    }
+
+    /// Gets the fonts to render text with, in the order they are tried. The symbols font is not included.
+    ///
+    /// If the chosen fonts don't have glyphs for every character the language uses, the loaded font that has the most of
+    /// the rest is tried last, so that labels don't render blank.
+    pub fn font_chain(&self, resource_man: &ResourceManager) -> Vec<String> {
+        let mut chain = Vec::new();
+
+        chain.extend(self.get_font(resource_man));
+        chain.extend(
+            self.get_cjk_font(resource_man)
+                .filter(|font| !chain.contains(font)),
+        );
+
+        let missing = resource_man.missing_glyphs(&chain);
+        if !missing.is_empty() {
+            let fallback = resource_man
+                .glyph_fallback_font(&missing)
+                .filter(|font| !chain.contains(font));
+
+            log::warn!(
+                "The fonts {chain:?} have no glyphs for {} character(s) of the language: {}. Falling back to {fallback:?}",
+                missing.len(),
+                missing.iter().take(32).collect::<String>(),
+            );
+
+            chain.extend(fallback);
+        }
+
+        chain
+    }
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
@@ -242,3 +448,28 @@ impl Default for AudioOptions {
         }
     }
 }
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdvancedOptions {
+    /// the ticks per second of the game, unless the map overrides it
+    pub tick_rate: i32,
+    /// whether to sign saves with a key made for this install, so that saves changed outside of the game are flagged in the map menu
+    #[serde(default)]
+    pub sign_saves: bool,
+    /// how many minutes pass between autosaves of the map. 0 turns autosaving off
+    pub autosave_interval: i32,
+    /// how many autosaves are kept, the oldest one is overwritten once they are all taken
+    pub autosave_slots: i32,
+}
+
+impl Default for AdvancedOptions {
+    fn default() -> Self {
+        Self {
+            tick_rate: TPS as i32,
+            sign_saves: false,
+            autosave_interval: 5,
+            autosave_slots: 3,
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// Identifies a player. There is only the local player for now, but tiles already record who placed them, so that
+/// saves don't have to change once there are more.
+pub type PlayerId = u32;
+
+/// The player of a single player game, who also owns the tiles of maps saved before ownership was recorded.
+pub const LOCAL_PLAYER: PlayerId = 0;
+
+/// Who may change a tile, i.e. replace, remove, move, or configure it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TileProtection {
+    /// anyone may change any tile
+    #[default]
+    FreeForAll,
+    /// only the player who placed a tile may change it. Tiles with no owner can be changed by anyone
+    OwnerOnly,
+}
+
+impl TileProtection {
+    /// Checks if the player may change a tile with the owner.
+    pub fn allows(self, owner: Option<PlayerId>, player: PlayerId) -> bool {
+        match self {
+            TileProtection::FreeForAll => true,
+            TileProtection::OwnerOnly => owner.map_or(true, |owner| owner == player),
+        }
+    }
+}
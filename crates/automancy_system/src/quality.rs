@@ -0,0 +1,142 @@
+use std::time::{Duration, Instant};
+
+/// Frames slower than this on average are too slow, unless the frame rate is limited below it.
+const SLOW_FRAME_TIME: Duration = Duration::from_micros(33_333);
+/// Frames faster than this on average leave enough headroom to draw everything again.
+const FAST_FRAME_TIME: Duration = Duration::from_millis(20);
+/// How long frames have to be too slow before the quality is lowered.
+const LOWER_AFTER: Duration = Duration::from_secs(3);
+/// How long frames have to be fast before the quality is raised again. Longer than lowering it, so that it doesn't
+/// keep flipping back and forth around the threshold.
+const RAISE_AFTER: Duration = Duration::from_secs(10);
+/// How much of each frame goes into the average frame time.
+const SMOOTHING: f64 = 0.1;
+/// Frames longer than this, like while loading or dragging the window, only count as this long.
+const MAX_COUNTED_FRAME_TIME: Duration = Duration::from_millis(250);
+
+/// How much the visual effects are cut back to keep the frame rate up.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum QualityLevel {
+    /// everything is drawn
+    #[default]
+    Full,
+    /// the post processing effect, camera shakes and screen flashes are skipped, and the map is drawn at a lower
+    /// resolution
+    Reduced,
+    /// progress bars above machines are skipped as well, and the resolution is lowered further
+    Low,
+}
+
+impl QualityLevel {
+    fn lower(self) -> Self {
+        match self {
+            QualityLevel::Full => QualityLevel::Reduced,
+            QualityLevel::Reduced | QualityLevel::Low => QualityLevel::Low,
+        }
+    }
+
+    fn raise(self) -> Self {
+        match self {
+            QualityLevel::Full | QualityLevel::Reduced => QualityLevel::Full,
+            QualityLevel::Low => QualityLevel::Reduced,
+        }
+    }
+
+    /// Whether the post processing effect is drawn.
+    pub fn post_processing(self) -> bool {
+        self == QualityLevel::Full
+    }
+
+    /// Whether camera shakes and screen flashes are shown.
+    pub fn screen_effects(self) -> bool {
+        self == QualityLevel::Full
+    }
+
+    /// Whether progress bars are drawn above machines.
+    pub fn progress_bars(self) -> bool {
+        self < QualityLevel::Low
+    }
+
+    /// The scale of the resolution the map is drawn at, relative to the window's. The GUI is always drawn at full
+    /// resolution.
+    pub fn resolution_scale(self) -> f32 {
+        match self {
+            QualityLevel::Full => 1.0,
+            QualityLevel::Reduced => 0.85,
+            QualityLevel::Low => 0.7,
+        }
+    }
+}
+
+/// Lowers the quality one level at a time while frames stay too slow, and raises it back once they have headroom again.
+#[derive(Debug, Default)]
+pub struct QualityGovernor {
+    level: QualityLevel,
+    /// the average frame time, in seconds
+    average: f64,
+    /// since when frames have been too slow
+    slow_since: Option<Instant>,
+    /// since when frames have been fast
+    fast_since: Option<Instant>,
+}
+
+impl QualityGovernor {
+    pub fn level(&self) -> QualityLevel {
+        self.level
+    }
+
+    /// Goes back to full quality, and forgets how fast the frames were.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Counts a frame that took the given time. The frame time the frame rate is limited to, if it is, is never seen
+    /// as too slow. Returns the new level if it changed.
+    pub fn update(
+        &mut self,
+        now: Instant,
+        frame_time: Duration,
+        limited_to: Option<Duration>,
+    ) -> Option<QualityLevel> {
+        let frame_time = frame_time.min(MAX_COUNTED_FRAME_TIME).as_secs_f64();
+
+        if self.average == 0.0 {
+            self.average = frame_time;
+        } else {
+            self.average += (frame_time - self.average) * SMOOTHING;
+        }
+
+        let limited_to = limited_to.unwrap_or_default();
+        let slow = SLOW_FRAME_TIME.max(limited_to.mul_f64(1.5)).as_secs_f64();
+        let fast = FAST_FRAME_TIME.max(limited_to.mul_f64(1.1)).as_secs_f64();
+
+        if self.average > slow {
+            self.fast_since = None;
+
+            let since = *self.slow_since.get_or_insert(now);
+
+            if now - since >= LOWER_AFTER && self.level != QualityLevel::Low {
+                self.slow_since = None;
+                self.level = self.level.lower();
+
+                return Some(self.level);
+            }
+        } else if self.average < fast {
+            self.slow_since = None;
+
+            let since = *self.fast_since.get_or_insert(now);
+
+            if now - since >= RAISE_AFTER && self.level != QualityLevel::Full {
+                self.fast_since = None;
+                self.level = self.level.raise();
+
+                return Some(self.level);
+            }
+        } else {
+            self.slow_since = None;
+            self.fast_since = None;
+        }
+
+        None
+    }
+}
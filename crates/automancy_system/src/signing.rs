@@ -0,0 +1,110 @@
+use crate::map::{GameMap, LoadMapOption};
+use automancy_defs::hex;
+use hmac::{Hmac, Mac};
+use rand::{thread_rng, RngCore};
+use sha2::Sha256;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Where the key saves are signed with is kept. It is made once per install, and never leaves it.
+static KEY_PATH: &str = "signing_key";
+
+pub const KEY_SIZE: usize = 32;
+
+pub type SigningKey = [u8; KEY_SIZE];
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Whether a save was signed, and if it was, whether it was changed since.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveSignature {
+    Unsigned,
+    Valid,
+    /// the save doesn't match its signature, so it was changed outside of the game, or signed by another install
+    Modified,
+}
+
+/// Reads the key of this install, if saves were signed before.
+pub fn read_key() -> Option<SigningKey> {
+    let text = fs::read_to_string(KEY_PATH).ok()?;
+
+    hex::decode(text.trim()).ok()?.try_into().ok()
+}
+
+/// Reads the key of this install, making a new random one if there is none yet.
+pub fn read_or_create_key() -> io::Result<SigningKey> {
+    if let Some(key) = read_key() {
+        return Ok(key);
+    }
+
+    let mut key = [0; KEY_SIZE];
+    thread_rng().fill_bytes(&mut key);
+
+    fs::write(KEY_PATH, hex::encode(key))?;
+    log::info!("Created a new key to sign saves with");
+
+    Ok(key)
+}
+
+/// Computes the HMAC-SHA256 of the parts, each prefixed with its length so that they can't be shifted into each other.
+fn hmac(key: &SigningKey, parts: &[&[u8]]) -> HmacSha256 {
+    // HMAC takes keys of any length
+    let mut mac = HmacSha256::new_from_slice(key).unwrap();
+
+    for part in parts {
+        mac.update(&(part.len() as u64).to_le_bytes());
+        mac.update(part);
+    }
+
+    mac
+}
+
+/// Gets the path to a map's signature from its name.
+pub fn signature_path(opt: &LoadMapOption) -> Option<PathBuf> {
+    GameMap::path(opt).map(|v| v.join("signature"))
+}
+
+/// Signs the info and tiles of a saved map.
+fn compute(key: &SigningKey, opt: &LoadMapOption) -> io::Result<HmacSha256> {
+    let (Some(info), Some(map)) = (GameMap::info(opt), GameMap::map(opt)) else {
+        return Err(io::Error::other("the map has no files to sign"));
+    };
+
+    Ok(hmac(key, &[&fs::read(info)?, &fs::read(map)?]))
+}
+
+/// Writes the signature of a saved map next to it. Without a key, the signature is removed instead, as it would be outdated.
+pub fn sign_map(key: Option<&SigningKey>, opt: &LoadMapOption) -> io::Result<()> {
+    let Some(path) = signature_path(opt) else {
+        return Ok(());
+    };
+
+    match key {
+        Some(key) => fs::write(
+            path,
+            hex::encode(compute(key, opt)?.finalize().into_bytes()),
+        ),
+        None => match fs::remove_file(path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Checks a saved map against its signature.
+pub fn check_map(key: &SigningKey, opt: &LoadMapOption) -> SaveSignature {
+    let Some(signature) = signature_path(opt).and_then(|v| fs::read_to_string(v).ok()) else {
+        return SaveSignature::Unsigned;
+    };
+
+    let Ok(signature) = hex::decode(signature.trim()) else {
+        return SaveSignature::Modified;
+    };
+
+    // compared in constant time, so that the signature can't be guessed from how long checking it takes
+    match compute(key, opt).map(|v| v.verify_slice(&signature)) {
+        Ok(Ok(())) => SaveSignature::Valid,
+        _ => SaveSignature::Modified,
+    }
+}
@@ -0,0 +1,244 @@
+use crate::map::{GameMap, LoadMapOption};
+use automancy_defs::{
+    id::{Id, TileId},
+    stack::{ItemAmount, ItemStack},
+};
+use automancy_resources::ResourceManager;
+use hashbrown::HashMap;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// How long each sample of the production history covers.
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+/// How many finished samples are kept, so that the history covers the last hour.
+pub const MAX_SAMPLES: usize = 60;
+
+/// The items the tiles of the map have handed off, counted per sample.
+#[derive(Debug, Default)]
+pub struct ProductionHistory {
+    /// when the current sample started
+    sample_start: Option<Instant>,
+    current: HashMap<Id, ItemAmount>,
+    /// the finished samples, oldest first
+    samples: VecDeque<HashMap<Id, ItemAmount>>,
+}
+
+impl ProductionHistory {
+    /// Counts a stack a tile has successfully handed off.
+    pub fn record(&mut self, stack: ItemStack) {
+        *self.current.entry(stack.id).or_default() += stack.amount;
+    }
+
+    /// Starts a new sample if the current one has run for long enough.
+    pub fn roll(&mut self) {
+        let now = Instant::now();
+        let start = *self.sample_start.get_or_insert(now);
+
+        if now - start < SAMPLE_INTERVAL {
+            return;
+        }
+
+        self.samples.push_back(std::mem::take(&mut self.current));
+        while self.samples.len() > MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+
+        self.sample_start = Some(now);
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Gets every sample, oldest first. The last one is the sample still running.
+    pub fn samples(&self) -> Vec<HashMap<Id, ItemAmount>> {
+        self.samples
+            .iter()
+            .chain(std::iter::once(&self.current))
+            .cloned()
+            .collect()
+    }
+}
+
+/// The statistics of the loaded map, as shown in the statistics screen.
+#[derive(Debug, Clone, Default)]
+pub struct Statistics {
+    /// the items handed off in each sample, oldest first. The last one is the sample still running
+    pub production: Vec<HashMap<Id, ItemAmount>>,
+    /// how many of each tile are placed
+    pub machines: HashMap<TileId, usize>,
+}
+
+#[derive(Serialize)]
+struct ItemHistoryJson {
+    item: String,
+    name: String,
+    /// the amount handed off in each sample, oldest first
+    amounts: Vec<ItemAmount>,
+}
+
+#[derive(Serialize)]
+struct MachineCountJson {
+    tile: String,
+    name: String,
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct StatisticsJson {
+    sample_seconds: u64,
+    production: Vec<ItemHistoryJson>,
+    machines: Vec<MachineCountJson>,
+}
+
+impl Statistics {
+    /// Gets every item that shows up in the production history, sorted by ID.
+    pub fn items(&self) -> Vec<Id> {
+        let mut items = self
+            .production
+            .iter()
+            .flat_map(|sample| sample.keys().copied())
+            .collect::<Vec<_>>();
+
+        items.sort();
+        items.dedup();
+
+        items
+    }
+
+    /// Gets the amount of an item handed off in each sample, oldest first.
+    pub fn history(&self, item: Id) -> Vec<ItemAmount> {
+        self.production
+            .iter()
+            .map(|sample| sample.get(&item).copied().unwrap_or(0))
+            .collect()
+    }
+
+    /// Gets the machine counts, the most common first.
+    pub fn sorted_machines(&self) -> Vec<(TileId, usize)> {
+        let mut machines = self
+            .machines
+            .iter()
+            .map(|(id, count)| (*id, *count))
+            .collect::<Vec<_>>();
+
+        machines.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        machines
+    }
+
+    fn id_str(resource_man: &ResourceManager, id: Id) -> String {
+        resource_man
+            .interner
+            .resolve(id)
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// Writes the statistics as two CSV files in the map folder, one for the production history and one for the machine counts.
+    /// Returns the paths written to.
+    pub fn write_csv(
+        &self,
+        resource_man: &ResourceManager,
+        opt: &LoadMapOption,
+    ) -> io::Result<Vec<PathBuf>> {
+        let (Some(production_path), Some(machines_path)) = (
+            export_path(opt, "production", "csv"),
+            export_path(opt, "machines", "csv"),
+        ) else {
+            return Err(io::Error::other("the map has no folder to export to"));
+        };
+
+        let mut writer = BufWriter::new(File::create(&production_path)?);
+        write!(writer, "item,name")?;
+        for sample in 0..self.production.len() {
+            write!(writer, ",minute_{sample}")?;
+        }
+        writeln!(writer)?;
+
+        for item in self.items() {
+            write!(
+                writer,
+                "{},{}",
+                csv_field(&Self::id_str(resource_man, item)),
+                csv_field(&resource_man.item_name(item))
+            )?;
+            for amount in self.history(item) {
+                write!(writer, ",{amount}")?;
+            }
+            writeln!(writer)?;
+        }
+        writer.flush()?;
+
+        let mut writer = BufWriter::new(File::create(&machines_path)?);
+        writeln!(writer, "tile,name,count")?;
+        for (id, count) in self.sorted_machines() {
+            writeln!(
+                writer,
+                "{},{},{count}",
+                csv_field(&Self::id_str(resource_man, *id)),
+                csv_field(&resource_man.tile_name(id))
+            )?;
+        }
+        writer.flush()?;
+
+        Ok(vec![production_path, machines_path])
+    }
+
+    /// Writes the statistics as one JSON file in the map folder. Returns the path written to.
+    pub fn write_json(
+        &self,
+        resource_man: &ResourceManager,
+        opt: &LoadMapOption,
+    ) -> io::Result<PathBuf> {
+        let Some(path) = export_path(opt, "statistics", "json") else {
+            return Err(io::Error::other("the map has no folder to export to"));
+        };
+
+        let json = StatisticsJson {
+            sample_seconds: SAMPLE_INTERVAL.as_secs(),
+            production: self
+                .items()
+                .into_iter()
+                .map(|item| ItemHistoryJson {
+                    item: Self::id_str(resource_man, item),
+                    name: resource_man.item_name(item).to_string(),
+                    amounts: self.history(item),
+                })
+                .collect(),
+            machines: self
+                .sorted_machines()
+                .into_iter()
+                .map(|(id, count)| MachineCountJson {
+                    tile: Self::id_str(resource_man, *id),
+                    name: resource_man.tile_name(id).to_string(),
+                    count,
+                })
+                .collect(),
+        };
+
+        let mut writer = BufWriter::new(File::create(&path)?);
+        serde_json::to_writer_pretty(&mut writer, &json).map_err(io::Error::other)?;
+        writer.flush()?;
+
+        Ok(path)
+    }
+}
+
+/// Gets the path of an exported statistics file in the map's folder.
+pub fn export_path(opt: &LoadMapOption, name: &str, ext: &str) -> Option<PathBuf> {
+    GameMap::path(opt).map(|v| v.join(name).with_extension(ext))
+}
+
+/// Quotes a CSV field if it has to be.
+fn csv_field(v: &str) -> String {
+    if v.contains([',', '"', '\n']) {
+        format!("\"{}\"", v.replace('"', "\"\""))
+    } else {
+        v.to_string()
+    }
+}
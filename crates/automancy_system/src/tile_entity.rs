@@ -1,13 +1,23 @@
 use crate::game::{GameSystemMessage, TickUnit};
 use crate::tile_entity::TileEntityMsg::*;
-use automancy_defs::id::{Id, TileId};
-use automancy_defs::{coord::TileCoord, stack::ItemStack};
-use automancy_resources::types::function::{OnFailAction, TileResult, TileTransactionResult};
+use crate::util::actor::{MailboxMetrics, Queued};
+use automancy_defs::id::{Id, ModelId, RenderTagId, TileId};
+use automancy_defs::math::Float;
+use automancy_defs::{
+    coord::TileCoord,
+    stack::{ItemAmount, ItemStack},
+};
+use automancy_resources::profiler::SCRIPT_PROFILER;
+use automancy_resources::types::function::{
+    LifecycleHook, OnFailAction, TaskStep, TileResult, TileTransactionResult,
+    ANIMATION_EVENT_FUNCTION, RANDOM_TICK_FUNCTION, RECEIVE_ITEMS_FUNCTION,
+};
+use automancy_resources::types::tile::TileDef;
 use automancy_resources::{
     data::{Data, DataMap},
     FunctionInfo,
 };
-use automancy_resources::{rhai_call_options, rhai_log_err, ResourceManager};
+use automancy_resources::{rhai_call_options, rhai_log_err, rhai_map, ResourceManager};
 use automancy_resources::{rhai_render::RenderCommand, rhai_ui::RhaiUiUnit};
 use hashbrown::HashSet;
 use ractor::{Actor, ActorProcessingErr, ActorRef, RpcReplyPort};
@@ -15,6 +25,7 @@ use rand::{thread_rng, RngCore};
 use rhai::{Dynamic, Scope};
 use std::mem;
 use std::sync::Arc;
+use std::time::Instant;
 use thiserror::Error;
 
 pub type TileEntityWithId = (TileId, ActorRef<TileEntityMsg>);
@@ -27,7 +38,7 @@ fn run_tile_function<Result: 'static, const SIZE: usize>(
     field_changes: &mut HashSet<Id>,
     (ast, metadata): &FunctionInfo,
     args: [(&'static str, Dynamic); SIZE],
-    function: &'static str,
+    function: &str,
 ) -> Option<Result> {
     let tile_def = resource_man.registry.tiles.get(&id)?;
     let mut rhai_state = Dynamic::from(data.clone());
@@ -41,6 +52,7 @@ fn run_tile_function<Result: 'static, const SIZE: usize>(
 
     input.extend(args.into_iter().map(|(k, v)| (k.into(), v)));
 
+    let profiled = SCRIPT_PROFILER.start(&metadata.str_id, function);
     let result = resource_man.engine.call_fn_with_options::<Dynamic>(
         rhai_call_options(&mut rhai_state),
         &mut Scope::new(),
@@ -48,6 +60,9 @@ fn run_tile_function<Result: 'static, const SIZE: usize>(
         function,
         (input,),
     );
+    if let Some(profiled) = profiled {
+        profiled.finish();
+    }
 
     {
         let new_data = rhai_state.cast::<DataMap>();
@@ -77,17 +92,129 @@ fn run_tile_function<Result: 'static, const SIZE: usize>(
     }
 }
 
+/// Picks the model of a tile with connected models by the neighbors it connects to, and tracks it in place of the last
+/// one if it changed. The tile is drawn unconnected if it isn't on the map, such as in a preview, where there's no last
+/// model.
+fn connected_render_commands(
+    resource_man: &ResourceManager,
+    tile_def: &TileDef,
+    coord: TileCoord,
+    connected_model: Option<&mut Option<ModelId>>,
+    loading: bool,
+    unloading: bool,
+) -> Option<Vec<RenderCommand>> {
+    let connected = tile_def.connected.as_ref()?;
+    let tag = RenderTagId(resource_man.registry.data_ids.connected_model_render_tag);
+
+    let Some(current) = connected_model else {
+        let model = connected.model(0);
+
+        return Some(vec![
+            RenderCommand::Track { tag, model },
+            RenderCommand::Transform {
+                tag,
+                model,
+                model_matrix: coord.as_translation(),
+            },
+        ]);
+    };
+
+    if unloading {
+        return current
+            .take()
+            .map(|model| vec![RenderCommand::Untrack { tag, model }]);
+    }
+
+    // tiles out of view are only drawn once they're loaded
+    if current.is_none() && !loading {
+        return None;
+    }
+
+    let mask = rhai_map::neighbor_mask(coord, connected.sides, |id| {
+        connected.connects(&resource_man.registry, tile_def.id, id)
+    });
+    let model = connected.model(mask);
+
+    if !loading && *current == Some(model) {
+        return None;
+    }
+
+    let mut commands = vec![];
+
+    if let Some(last) = current.replace(model) {
+        commands.push(RenderCommand::Untrack { tag, model: last });
+    }
+    commands.push(RenderCommand::Track { tag, model });
+    commands.push(RenderCommand::Transform {
+        tag,
+        model,
+        model_matrix: coord.as_translation(),
+    });
+
+    Some(commands)
+}
+
+/// Collects the render commands of a tile: the ones of its connected models, if it has them, and the ones its function
+/// returns. Returns None if nothing changed.
+///
+/// The last connected model is kept by the tile entity, and is None for tiles that aren't on the map.
+#[allow(clippy::too_many_arguments)]
 pub fn collect_render_commands(
     resource_man: &ResourceManager,
     id: TileId,
     coord: TileCoord,
     data: &mut DataMap,
     field_changes: &mut HashSet<Id>,
+    connected_model: Option<&mut Option<ModelId>>,
     loading: bool,
     unloading: bool,
 ) -> Option<Vec<RenderCommand>> {
     let tile_def = resource_man.registry.tiles.get(&id)?;
 
+    let connected = connected_render_commands(
+        resource_man,
+        tile_def,
+        coord,
+        connected_model,
+        loading,
+        unloading,
+    );
+
+    match (
+        connected,
+        function_render_commands(
+            resource_man,
+            tile_def,
+            coord,
+            data,
+            field_changes,
+            loading,
+            unloading,
+        ),
+    ) {
+        (None, None) => None,
+        (connected, function) => Some(
+            connected
+                .into_iter()
+                .flatten()
+                .chain(function.into_iter().flatten())
+                .collect(),
+        ),
+    }
+}
+
+/// Calls the tile's function for its render commands, if the fields it listens to changed.
+fn function_render_commands(
+    resource_man: &ResourceManager,
+    tile_def: &TileDef,
+    coord: TileCoord,
+    data: &mut DataMap,
+    field_changes: &mut HashSet<Id>,
+    loading: bool,
+    unloading: bool,
+) -> Option<Vec<RenderCommand>> {
+    let id = tile_def.id;
+
     if let Some(function @ (_, metadata)) = tile_def
         .function
         .as_ref()
@@ -132,6 +259,23 @@ pub fn collect_render_commands(
     None
 }
 
+/// The most tasks a tile can run at once.
+const MAX_TASKS: usize = 16;
+
+/// A multi-tick procedure started by a script, which runs one of its steps each tick.
+/// Tasks are not saved with the map.
+#[derive(Debug, Clone)]
+struct ScriptTask {
+    /// The names of the functions to run, in order.
+    steps: Vec<String>,
+    /// The index of the step to run next.
+    step: usize,
+    /// The ticks left to wait before running the step.
+    wait: u32,
+    /// The values the steps pass on to each other.
+    locals: rhai::Map,
+}
+
 #[derive(Debug, Clone)]
 pub struct TileEntity {
     /// The ID of the tile entity.
@@ -153,6 +297,14 @@ pub struct TileEntityState {
 
     /// The field changed since last render request.
     field_changes: HashSet<Id>,
+    /// Whether the game has been told that the fields changed since the last render request.
+    render_dirty: bool,
+    /// Whether the game has been told that the tile has no work to do.
+    asleep: bool,
+    /// The tasks started by the tile's script.
+    tasks: Vec<ScriptTask>,
+    /// The connected model the tile is drawn with, if it has connected models and is in view.
+    connected_model: Option<ModelId>,
 }
 
 impl TileEntityState {
@@ -163,15 +315,43 @@ impl TileEntityState {
             data: Default::default(),
 
             field_changes: HashSet::new(),
+            render_dirty: false,
+            asleep: false,
+            tasks: Vec::new(),
+            connected_model: None,
         }
     }
 }
 
+/// A change in a tile's life, which calls the [LifecycleHook] of the same name.
+#[derive(Debug, Clone)]
+pub enum LifecycleEvent {
+    /// the tile was placed, over the tile it replaced if any. Restored is whether its data was given to it, such as
+    /// when it's moved, pasted or placed back by undo, in which case it shouldn't set itself up again
+    Place {
+        replaced: Option<TileId>,
+        restored: bool,
+    },
+    /// the tile is being removed. What its function returns is handled after it's gone
+    Remove,
+    /// the tile was loaded with the map
+    Load,
+    /// the tiles next to this one at the coordinates were placed, removed or reconfigured since the last tick
+    NeighborsChanged(Vec<TileCoord>),
+}
+
 #[derive(Debug)]
 pub enum TileEntityMsg {
     Tick {
         tick_count: TickUnit,
+        queued: Queued,
     },
+    /// the tile was picked for a random tick, which calls RANDOM_TICK_FUNCTION
+    RandomTick(Queued),
+    /// the tile's animation passed the named event marker, which calls ANIMATION_EVENT_FUNCTION
+    AnimationEvent(String, Queued),
+    /// the tile was placed, loaded, removed, or had a neighbor change, which calls the matching LifecycleHook
+    Lifecycle(LifecycleEvent),
     Transaction {
         stack: ItemStack,
         source_coord: TileCoord,
@@ -187,6 +367,10 @@ pub enum TileEntityMsg {
         requested_from_id: TileId,
         requested_from_coord: TileCoord,
     },
+    ReceiveItems {
+        channel: Id,
+        stacks: Vec<ItemStack>,
+    },
     CollectRenderCommands {
         reply: RpcReplyPort<Option<Vec<RenderCommand>>>,
         loading: bool,
@@ -194,14 +378,44 @@ pub enum TileEntityMsg {
     },
     SetData(DataMap),
     SetDataValue(Id, Data),
+    /// set the data value, or remove it if none, as the player configuring the tile. Unlike SetDataValue, the
+    /// neighbors are told about it
+    Configure(Id, Option<Data>),
+    /// add (positive amounts) or take (negative amounts) items from an inventory in the data, replying with what was actually applied
+    MutateInventory {
+        id: Id,
+        changes: Vec<(Id, ItemAmount)>,
+        reply: RpcReplyPort<Vec<(Id, ItemAmount)>>,
+    },
     RemoveData(Id),
     TakeData(RpcReplyPort<DataMap>),
     GetData(RpcReplyPort<DataMap>),
     GetDataValue(Id, RpcReplyPort<Option<Data>>),
     GetDataWithCoord(RpcReplyPort<(TileCoord, DataMap)>),
+    GetProgress(RpcReplyPort<Option<Float>>),
     GetTileConfigUi(RpcReplyPort<Option<RhaiUiUnit>>),
 }
 
+impl TileEntityMsg {
+    /// Whether the message can give a sleeping tile work to do, i.e. it changes the tile's items or data.
+    fn wakes(&self) -> bool {
+        matches!(
+            self,
+            Transaction { .. }
+                | TransactionResult { .. }
+                | ExtractRequest { .. }
+                | ReceiveItems { .. }
+                | SetData(_)
+                | SetDataValue(..)
+                | Configure(..)
+                | MutateInventory { .. }
+                | RemoveData(_)
+                | TakeData(_)
+                | Lifecycle(_)
+        )
+    }
+}
+
 impl TileEntity {
     fn handle_rhai_transaction_result(
         &self,
@@ -318,9 +532,142 @@ impl TileEntity {
                     on_fail_action,
                 );
             }
+            TileResult::SendItems { channel, stacks } => {
+                if state
+                    .game
+                    .send_message(GameSystemMessage::SendItems { channel, stacks })
+                    .is_err()
+                {
+                    log::error!("Could not send items from {}", self.coord);
+                }
+            }
+            TileResult::RequestItems { channel, max } => {
+                if state
+                    .game
+                    .send_message(GameSystemMessage::RequestItems {
+                        channel,
+                        coord: self.coord,
+                        max,
+                    })
+                    .is_err()
+                {
+                    log::error!("Could not request items for {}", self.coord);
+                }
+            }
+            TileResult::PlaceTile { coord, id } => {
+                if state
+                    .game
+                    .send_message(GameSystemMessage::ScriptPlaceTile {
+                        source: self.coord,
+                        coord,
+                        id,
+                    })
+                    .is_err()
+                {
+                    log::error!("Could not request placing a tile from {}", self.coord);
+                }
+            }
+            TileResult::Sleep { .. } if !state.tasks.is_empty() => {
+                // the tasks still need to be ticked
+            }
+            TileResult::Sleep { ticks } => {
+                state.asleep = true;
+
+                if state
+                    .game
+                    .send_message(GameSystemMessage::SleepTile {
+                        coord: self.coord,
+                        ticks,
+                    })
+                    .is_err()
+                {
+                    log::error!("Could not put {} to sleep", self.coord);
+                }
+            }
+            TileResult::StartTask { steps, locals } => {
+                if state.tasks.len() >= MAX_TASKS {
+                    log::warn!(
+                        "Tile at {} has too many tasks running, not starting another",
+                        self.coord
+                    );
+                } else if !steps.is_empty() {
+                    state.tasks.push(ScriptTask {
+                        steps,
+                        step: 0,
+                        wait: 0,
+                        locals,
+                    });
+                }
+            }
+            TileResult::RemoveTile { coord } => {
+                if state
+                    .game
+                    .send_message(GameSystemMessage::ScriptPlaceTile {
+                        source: self.coord,
+                        coord,
+                        id: TileId(self.resource_man.registry.none),
+                    })
+                    .is_err()
+                {
+                    log::error!("Could not request removing a tile from {}", self.coord);
+                }
+            }
+            TileResult::SpawnAgent { id, coord } => {
+                if state
+                    .game
+                    .send_message(GameSystemMessage::SpawnAgent { id, coord })
+                    .is_err()
+                {
+                    log::error!("Could not request spawning an agent from {}", self.coord);
+                }
+            }
         }
     }
 
+    /// Runs the current step of every task, dropping the ones that finished.
+    fn run_tasks(&self, state: &mut TileEntityState, function: &FunctionInfo) {
+        let mut tasks = mem::take(&mut state.tasks);
+
+        tasks.retain_mut(|task| {
+            if task.wait > 0 {
+                task.wait -= 1;
+
+                return true;
+            }
+
+            let result = run_tile_function(
+                &self.resource_man,
+                self.id,
+                self.coord,
+                &mut state.data,
+                &mut state.field_changes,
+                function,
+                [("task", Dynamic::from_map(mem::take(&mut task.locals)))],
+                &task.steps[task.step],
+            );
+
+            match result {
+                Some(TaskStep::Next(locals)) => {
+                    task.locals = locals;
+                    task.step += 1;
+                }
+                Some(TaskStep::Wait(ticks, locals)) => {
+                    task.locals = locals;
+                    task.wait = ticks;
+                    task.step += 1;
+                }
+                Some(TaskStep::Repeat(locals)) => {
+                    task.locals = locals;
+                }
+                Some(TaskStep::Done) | None => return false,
+            }
+
+            task.step < task.steps.len()
+        });
+
+        state.tasks = tasks;
+    }
+
     fn transaction(
         &self,
         state: &mut TileEntityState,
@@ -361,36 +708,73 @@ impl TileEntity {
     }
 }
 
+impl TileEntity {
+    /// Calls the lifecycle hook on the tile's function, if it defines it.
+    fn run_lifecycle_hook<const SIZE: usize>(
+        &self,
+        state: &mut TileEntityState,
+        hook: LifecycleHook,
+        args: [(&'static str, Dynamic); SIZE],
+    ) -> Result<(), ActorProcessingErr> {
+        let tile_def = self
+            .resource_man
+            .registry
+            .tiles
+            .get(&self.id)
+            .ok_or(Box::new(TileEntityError::NonExistent(self.coord)))?;
+
+        if let Some(function @ (_, metadata)) = tile_def
+            .function
+            .as_ref()
+            .and_then(|v| self.resource_man.functions.get(v))
+        {
+            if !metadata.lifecycle_hooks.contains(&hook) {
+                return Ok(());
+            }
+
+            if let Some(result) = run_tile_function(
+                &self.resource_man,
+                self.id,
+                self.coord,
+                &mut state.data,
+                &mut state.field_changes,
+                function,
+                args,
+                hook.function(),
+            ) {
+                self.handle_rhai_result(state, result);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The instrumentation of every tile entity's mailbox, together.
+pub static TILE_ENTITY_MAILBOX: MailboxMetrics = MailboxMetrics::new();
+
 #[derive(Error, Debug)]
 pub enum TileEntityError {
     #[error("the tile ID at {0} is no longer existent")]
     NonExistent(TileCoord),
 }
 
-#[async_trait::async_trait]
-impl Actor for TileEntity {
-    type Msg = TileEntityMsg;
-    type State = TileEntityState;
-    type Arguments = (ActorRef<GameSystemMessage>,);
-
-    async fn pre_start(
-        &self,
-        _myself: ActorRef<Self::Msg>,
-        args: Self::Arguments,
-    ) -> Result<Self::State, ActorProcessingErr> {
-        Ok(TileEntityState::new(args.0))
-    }
-
-    async fn handle(
+impl TileEntity {
+    async fn handle_message(
         &self,
-        _myself: ActorRef<Self::Msg>,
-        message: Self::Msg,
-        state: &mut Self::State,
+        _myself: ActorRef<TileEntityMsg>,
+        message: TileEntityMsg,
+        state: &mut TileEntityState,
     ) -> Result<(), ActorProcessingErr> {
         match message {
+            // the queued messages are only counted until dropped at the end of the arm
             Tick {
                 tick_count: _tick_count,
+                queued: _queued,
             } => {
+                // being ticked means the game considers the tile awake
+                state.asleep = false;
+
                 let tile_def = self
                     .resource_man
                     .registry
@@ -403,6 +787,8 @@ impl Actor for TileEntity {
                     .as_ref()
                     .and_then(|v| self.resource_man.functions.get(v))
                 {
+                    self.run_tasks(state, function);
+
                     if let Some(result) = run_tile_function(
                         &self.resource_man,
                         self.id,
@@ -417,6 +803,93 @@ impl Actor for TileEntity {
                     }
                 }
             }
+            RandomTick(_queued) => {
+                let tile_def = self
+                    .resource_man
+                    .registry
+                    .tiles
+                    .get(&self.id)
+                    .ok_or(Box::new(TileEntityError::NonExistent(self.coord)))?;
+
+                if let Some(function) = tile_def
+                    .function
+                    .as_ref()
+                    .and_then(|v| self.resource_man.functions.get(v))
+                {
+                    if let Some(result) = run_tile_function(
+                        &self.resource_man,
+                        self.id,
+                        self.coord,
+                        &mut state.data,
+                        &mut state.field_changes,
+                        function,
+                        [],
+                        RANDOM_TICK_FUNCTION,
+                    ) {
+                        self.handle_rhai_result(state, result);
+                    }
+                }
+            }
+            AnimationEvent(event, _queued) => {
+                let tile_def = self
+                    .resource_man
+                    .registry
+                    .tiles
+                    .get(&self.id)
+                    .ok_or(Box::new(TileEntityError::NonExistent(self.coord)))?;
+
+                if let Some(function) = tile_def
+                    .function
+                    .as_ref()
+                    .and_then(|v| self.resource_man.functions.get(v))
+                {
+                    if let Some(result) = run_tile_function(
+                        &self.resource_man,
+                        self.id,
+                        self.coord,
+                        &mut state.data,
+                        &mut state.field_changes,
+                        function,
+                        [("event", Dynamic::from(event))],
+                        ANIMATION_EVENT_FUNCTION,
+                    ) {
+                        self.handle_rhai_result(state, result);
+                    }
+                }
+            }
+            Lifecycle(event) => match event {
+                LifecycleEvent::Place { replaced, restored } => {
+                    self.run_lifecycle_hook(
+                        state,
+                        LifecycleHook::Place,
+                        [
+                            (
+                                "replaced",
+                                replaced.map(Dynamic::from).unwrap_or(Dynamic::UNIT),
+                            ),
+                            ("restored", Dynamic::from_bool(restored)),
+                        ],
+                    )?;
+
+                    // the neighbors are told after the hook, so that they see what it set up
+                    state
+                        .game
+                        .send_message(GameSystemMessage::TileChanged(self.coord))?;
+                }
+                LifecycleEvent::Remove => {
+                    self.run_lifecycle_hook(state, LifecycleHook::Remove, [])?;
+
+                    state
+                        .game
+                        .send_message(GameSystemMessage::TileChanged(self.coord))?;
+                }
+                LifecycleEvent::Load => self.run_lifecycle_hook(state, LifecycleHook::Load, [])?,
+                LifecycleEvent::NeighborsChanged(coords) => self.run_lifecycle_hook(
+                    state,
+                    LifecycleHook::NeighborChanged,
+                    [("changed", Dynamic::from_iter(coords))],
+                )?,
+            },
             Transaction {
                 stack,
                 source_coord,
@@ -434,6 +907,11 @@ impl Actor for TileEntity {
                 }
             }
             TransactionResult { result } => {
+                // the stack this tile sent out was taken in, so it counts as produced
+                state
+                    .game
+                    .send_message(GameSystemMessage::RecordProduction(result))?;
+
                 let tile_def = self
                     .resource_man
                     .registry
@@ -491,6 +969,49 @@ impl Actor for TileEntity {
                     }
                 }
             }
+            ReceiveItems { channel, stacks } => {
+                let tile_def = self
+                    .resource_man
+                    .registry
+                    .tiles
+                    .get(&self.id)
+                    .ok_or(Box::new(TileEntityError::NonExistent(self.coord)))?;
+
+                let function = tile_def
+                    .function
+                    .as_ref()
+                    .and_then(|v| self.resource_man.functions.get(v))
+                    .filter(|(_, metadata)| metadata.has_receive_items);
+
+                let Some(function) = function else {
+                    // nothing can take the items, so they go back to where they came from instead of being lost
+                    if state
+                        .game
+                        .send_message(GameSystemMessage::ReturnItems { channel, stacks })
+                        .is_err()
+                    {
+                        log::error!("Could not return the items received by {}", self.coord);
+                    }
+
+                    return Ok(());
+                };
+
+                if let Some(result) = run_tile_function(
+                    &self.resource_man,
+                    self.id,
+                    self.coord,
+                    &mut state.data,
+                    &mut state.field_changes,
+                    function,
+                    [
+                        ("channel", Dynamic::from(channel)),
+                        ("stacks", Dynamic::from_iter(stacks)),
+                    ],
+                    RECEIVE_ITEMS_FUNCTION,
+                ) {
+                    self.handle_rhai_result(state, result);
+                }
+            }
             GetTileConfigUi(reply) => {
                 let tile_def = self
                     .resource_man
@@ -525,12 +1046,15 @@ impl Actor for TileEntity {
                 loading,
                 unloading,
             } => {
+                state.render_dirty = false;
+
                 reply.send(collect_render_commands(
                     &self.resource_man,
                     self.id,
                     self.coord,
                     &mut state.data,
                     &mut state.field_changes,
+                    Some(&mut state.connected_model),
                     loading,
                     unloading,
                 ))?;
@@ -551,21 +1075,121 @@ impl Actor for TileEntity {
                 state.field_changes.insert(key);
                 state.data.remove(key);
             }
+            Configure(key, value) => {
+                state.field_changes.insert(key);
+
+                match value {
+                    Some(value) => state.data.set(key, value),
+                    None => {
+                        state.data.remove(key);
+                    }
+                }
+
+                state
+                    .game
+                    .send_message(GameSystemMessage::TileChanged(self.coord))?;
+            }
             GetData(reply) => {
                 reply.send(state.data.clone())?;
             }
+            MutateInventory { id, changes, reply } => {
+                let Data::Inventory(inventory) = state
+                    .data
+                    .entry(id)
+                    .or_insert_with(|| Data::Inventory(Default::default()))
+                else {
+                    reply.send(vec![])?;
+                    return Ok(());
+                };
+
+                let applied = changes
+                    .into_iter()
+                    .map(|(item, amount)| {
+                        if amount >= 0 {
+                            inventory.add(item, amount);
+
+                            (item, amount)
+                        } else {
+                            (item, -inventory.take(item, -amount))
+                        }
+                    })
+                    .collect();
+
+                state.field_changes.insert(id);
+
+                reply.send(applied)?;
+            }
             GetDataValue(key, reply) => {
                 reply.send(state.data.get(key).cloned())?;
             }
             GetDataWithCoord(reply) => {
                 reply.send((self.coord, state.data.clone()))?;
             }
+            GetProgress(reply) => {
+                reply.send(
+                    state
+                        .data
+                        .progress(&self.resource_man.registry.data_ids)
+                        .map(|(fraction, _)| fraction),
+                )?;
+            }
         }
 
         Ok(())
     }
 }
 
+#[async_trait::async_trait]
+impl Actor for TileEntity {
+    type Msg = TileEntityMsg;
+    type State = TileEntityState;
+    type Arguments = (ActorRef<GameSystemMessage>,);
+
+    async fn pre_start(
+        &self,
+        _myself: ActorRef<Self::Msg>,
+        args: Self::Arguments,
+    ) -> Result<Self::State, ActorProcessingErr> {
+        Ok(TileEntityState::new(args.0))
+    }
+
+    async fn handle(
+        &self,
+        myself: ActorRef<Self::Msg>,
+        message: Self::Msg,
+        state: &mut Self::State,
+    ) -> Result<(), ActorProcessingErr> {
+        if state.asleep && message.wakes() {
+            state.asleep = false;
+
+            if let Err(err) = state
+                .game
+                .send_message(GameSystemMessage::WakeTile(self.coord))
+            {
+                log::error!("Could not wake up {}! Error: {err:?}", self.coord);
+            }
+        }
+
+        let start = Instant::now();
+        let result = self.handle_message(myself, message, state).await;
+        TILE_ENTITY_MAILBOX.processed(start.elapsed());
+
+        // the game only asks the tiles that reported changes for their render commands
+        if !state.render_dirty && !state.field_changes.is_empty() {
+            state.render_dirty = true;
+
+            if let Err(err) = state
+                .game
+                .send_message(GameSystemMessage::MarkRenderDirty(self.coord))
+            {
+                log::error!("Could not report render changes to the game! Error: {err:?}");
+            }
+        }
+
+        result
+    }
+}
+
 fn send_to_tile(
     state: &mut TileEntityState,
     source: TileCoord,
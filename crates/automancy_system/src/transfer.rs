@@ -0,0 +1,166 @@
+use crate::map::MAP_PATH;
+use automancy_defs::{
+    id::{Id, Interner},
+    stack::{ItemAmount, ItemStack},
+};
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub static TRANSFER_FILE: &str = ".transfer.ron";
+
+/// Item queues that persist across maps, keyed by channel ID.
+#[derive(Debug, Default)]
+pub struct TransferQueues(HashMap<Id, VecDeque<ItemStack>>);
+
+/// The queues as written to disk, gathered by [TransferQueues::to_raw].
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TransferQueuesRaw(Vec<(String, Vec<(String, ItemAmount)>)>);
+
+impl TransferQueues {
+    fn path() -> PathBuf {
+        PathBuf::from(MAP_PATH).join(TRANSFER_FILE)
+    }
+
+    /// Reads the queues from disk, dropping any items or channels that no longer exist.
+    pub fn load(interner: &Interner) -> Self {
+        Self::load_from(&Self::path(), interner)
+    }
+
+    /// Reads the queues from the file, like [TransferQueues::load].
+    pub fn load_from(path: &Path, interner: &Interner) -> Self {
+        let Ok(file) = File::open(path) else {
+            return Self::default();
+        };
+
+        let raw = match ron::de::from_reader::<_, TransferQueuesRaw>(BufReader::new(file)) {
+            Ok(raw) => raw,
+            Err(err) => {
+                log::error!("Could not read transfer queues, starting empty. Error: {err:?}");
+
+                return Self::default();
+            }
+        };
+
+        Self(
+            raw.0
+                .into_iter()
+                .flat_map(|(channel, stacks)| {
+                    Some((
+                        Id::try_parse(&channel, interner)?,
+                        stacks
+                            .into_iter()
+                            .flat_map(|(id, amount)| {
+                                Some(ItemStack {
+                                    id: Id::try_parse(&id, interner)?,
+                                    amount,
+                                })
+                            })
+                            .collect(),
+                    ))
+                })
+                .collect(),
+        )
+    }
+
+    pub fn save(&self, interner: &Interner) -> io::Result<()> {
+        Self::write_raw(&self.to_raw(interner))
+    }
+
+    /// Writes the queues to the file, skipping the empty ones.
+    pub fn save_to(&self, path: &Path, interner: &Interner) -> io::Result<()> {
+        Self::write_raw_to(path, &self.to_raw(interner))
+    }
+
+    /// Gathers the queues to be written later, skipping the empty ones.
+    pub fn to_raw(&self, interner: &Interner) -> TransferQueuesRaw {
+        TransferQueuesRaw(
+            self.0
+                .iter()
+                .filter(|(_, stacks)| !stacks.is_empty())
+                .flat_map(|(channel, stacks)| {
+                    Some((
+                        interner.resolve(*channel)?.to_string(),
+                        stacks
+                            .iter()
+                            .flat_map(|stack| {
+                                Some((interner.resolve(stack.id)?.to_string(), stack.amount))
+                            })
+                            .collect(),
+                    ))
+                })
+                .collect(),
+        )
+    }
+
+    /// Writes queues gathered by [TransferQueues::to_raw] to where [TransferQueues::load] reads them from.
+    pub fn write_raw(raw: &TransferQueuesRaw) -> io::Result<()> {
+        fs::create_dir_all(MAP_PATH)?;
+
+        Self::write_raw_to(&Self::path(), raw)
+    }
+
+    fn write_raw_to(path: &Path, raw: &TransferQueuesRaw) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        ron::ser::to_writer(&mut writer, raw).map_err(io::Error::other)?;
+        writer.flush()
+    }
+
+    /// Counts the items waiting in every queue.
+    pub fn total(&self) -> ItemAmount {
+        self.0
+            .values()
+            .flat_map(|stacks| stacks.iter().map(|stack| stack.amount))
+            .sum()
+    }
+
+    /// Pushes the stacks to the back of the channel's queue.
+    pub fn send(&mut self, channel: Id, stacks: impl IntoIterator<Item = ItemStack>) {
+        self.0
+            .entry(channel)
+            .or_default()
+            .extend(stacks.into_iter().filter(|stack| stack.amount > 0));
+    }
+
+    /// Pops up to `max` items from the front of the channel's queue, splitting the last stack if needed.
+    pub fn receive(&mut self, channel: Id, max: ItemAmount) -> Vec<ItemStack> {
+        let Some(queue) = self.0.get_mut(&channel) else {
+            return vec![];
+        };
+
+        let mut remaining = max;
+        let mut taken = vec![];
+
+        while remaining > 0 {
+            let Some(front) = queue.front_mut() else {
+                break;
+            };
+
+            if front.amount <= remaining {
+                remaining -= front.amount;
+                taken.push(queue.pop_front().unwrap());
+            } else {
+                front.amount -= remaining;
+                taken.push(ItemStack {
+                    id: front.id,
+                    amount: remaining,
+                });
+                remaining = 0;
+            }
+        }
+
+        taken
+    }
+
+    /// Puts the stacks back to the front of the channel's queue, in order.
+    pub fn return_front(&mut self, channel: Id, stacks: Vec<ItemStack>) {
+        let queue = self.0.entry(channel).or_default();
+
+        for stack in stacks.into_iter().rev() {
+            queue.push_front(stack);
+        }
+    }
+}
@@ -1,14 +1,51 @@
+use crate::changelog::RegistryDiff;
+use crate::game::TickUnit;
+use crate::map::{MapSnapshot, NamespaceChanges};
+use crate::stats::Statistics;
+use crate::verify::MapReport;
 use automancy_defs::{
-    coord::TileCoord,
+    coord::{TileBounds, TileBoundsSet, TileCoord},
     glam::vec2,
-    id::{Id, ModelId, TileId},
-    math::Vec2,
+    id::{Id, ModelId, RenderTagId, SharedStr, TileId},
+    math::{Float, Vec2, Vec3},
+    stack::ItemAmount,
+};
+use automancy_resources::{
+    assets::AssetReport,
+    data::DataMap,
+    types::{function::MapOutcome, tile::TileAction, translate::TranslationCoverage},
+    ResourceManager,
 };
-use automancy_resources::data::DataMap;
 use enum_map::{enum_map, Enum, EnumMap};
 use fuzzy_matcher::skim::SkimMatcherV2;
 use hashbrown::{HashMap, HashSet};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 use std::{fmt::Debug, mem};
+use yakui::Color;
+
+/// A cutscene being played.
+#[derive(Debug, Clone)]
+pub struct CutscenePlayback {
+    pub id: Id,
+    /// the step being played
+    pub step: usize,
+    /// when the step started, or None if it's yet to start
+    pub step_start: Option<Instant>,
+    /// where the camera was when the step started, if the step moves it
+    pub camera_from: Option<Vec3>,
+}
+
+impl CutscenePlayback {
+    pub fn new(id: Id) -> Self {
+        Self {
+            id,
+            step: 0,
+            step_start: None,
+            camera_from: None,
+        }
+    }
+}
 
 /// The state of the main game GUI.
 #[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
@@ -34,16 +71,104 @@ pub enum OptionsMenuState {
     Audio,
     Gui,
     Controls,
+    Advanced,
 }
 
-/// The state of popups (which are on top of the main GUI), if any should be displayed.
-#[derive(Eq, PartialEq, Clone, Debug, Default)]
+/// What a generic dialog does once it is confirmed.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum PopupAction {
+    /// Does nothing besides closing the dialog.
+    Close,
+    CreateMap,
+    DeleteMap(String),
+    /// Deletes every one of the saves.
+    DeleteMaps(Vec<String>),
+    /// Renames the saves after the map renaming text field, numbering them if there are several.
+    RenameMaps(Vec<String>),
+    /// Creates a folder of saves named after the folder name text field, in the folder the map menu is showing.
+    CreateFolder,
+    /// Places a beacon named after the beacon name text field.
+    AddBeacon(TileCoord),
+    /// Resumes the simulation, after it was paused for the graphics device to be recovered.
+    ResumeSimulation,
+    /// Stops errors with the ID from being shown again, and remembers it in the options.
+    MuteError(Id),
+    /// Turns adaptive quality off, and brings every visual effect back.
+    DisableAdaptiveQuality,
+}
+
+/// A popup (which is on top of the main GUI). The popups are kept in a stack, and only the topmost one is displayed.
+#[derive(PartialEq, Clone, Debug)]
 pub enum PopupState {
-    #[default]
-    None,
-    MapCreate,
-    MapDeleteConfirmation(String),
-    InvalidName,
+    /// Shows a message that can only be acknowledged, which runs the action.
+    Message {
+        title: Id,
+        message: Id,
+        action: PopupAction,
+    },
+    /// Asks the player to confirm the action.
+    Confirm {
+        title: Id,
+        message: Id,
+        action: PopupAction,
+    },
+    /// Asks the player for a line of text, which is kept in the text field for the action to read.
+    TextInput {
+        title: Id,
+        label: Id,
+        field: TextField,
+        action: PopupAction,
+    },
+    /// Shows the progress of a long-running task. It is closed by whoever opened it.
+    Progress {
+        title: Id,
+        message: String,
+        fraction: Float,
+    },
+    /// Lists the namespaces added or removed since the named map was last saved, asking whether to load it anyway.
+    MapNamespaces(String, NamespaceChanges),
+    MapRemap(String),
+    /// Shows the report of verifying the named map, offering to repair it.
+    MapVerify(String),
+    /// Lists what the packs changed in the registry since the last launch.
+    RegistryChanges(RegistryDiff),
+    /// Shows that the map was won or lost, with a summary of how it went.
+    MapEnd(MapOutcome, MapEndSummary),
+}
+
+/// How many of the most produced items the end screen of a map lists.
+pub const MAP_END_TOP_ITEMS: usize = 5;
+
+/// The statistics shown on the end screen of a map.
+#[derive(PartialEq, Clone, Debug)]
+pub struct MapEndSummary {
+    /// the ticks run since the map was loaded
+    pub ticks: u64,
+    /// how many tiles are placed
+    pub tiles: usize,
+    /// the most produced items in the production history, most first
+    pub produced: Vec<(Id, ItemAmount)>,
+}
+
+impl MapEndSummary {
+    pub fn new(ticks: u64, statistics: &Statistics) -> Self {
+        let mut produced = HashMap::<Id, ItemAmount>::new();
+        for sample in &statistics.production {
+            for (id, amount) in sample {
+                *produced.entry(*id).or_default() += amount;
+            }
+        }
+
+        let mut produced = produced.into_iter().collect::<Vec<_>>();
+        produced.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        produced.truncate(MAP_END_TOP_ITEMS);
+
+        Self {
+            ticks,
+            tiles: statistics.machines.values().sum(),
+            produced,
+        }
+    }
 }
 
 #[derive(Eq, PartialEq, Ord, PartialOrd, Enum, Clone, Copy, Debug)]
@@ -51,6 +176,59 @@ pub enum TextField {
     Filter,
     MapRenaming,
     MapName,
+    BeaconName,
+    MapSearch,
+    FolderName,
+    BlueprintName,
+    ModelFilter,
+}
+
+/// Which of the marked tiles the deconstruct tool should remove.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub enum DeconstructFilter {
+    #[default]
+    All,
+    /// only tiles of the same type as the tile the drag started on
+    SameTile,
+    /// only tiles tagged as transfer tiles
+    Transfer,
+}
+
+impl DeconstructFilter {
+    pub fn next(self) -> Self {
+        match self {
+            DeconstructFilter::All => DeconstructFilter::SameTile,
+            DeconstructFilter::SameTile => DeconstructFilter::Transfer,
+            DeconstructFilter::Transfer => DeconstructFilter::All,
+        }
+    }
+
+    pub fn name(self, resource_man: &ResourceManager) -> SharedStr {
+        let gui_ids = &resource_man.registry.gui_ids;
+
+        resource_man.gui_str(match self {
+            DeconstructFilter::All => gui_ids.lbl_deconstruct_all,
+            DeconstructFilter::SameTile => gui_ids.lbl_deconstruct_same_tile,
+            DeconstructFilter::Transfer => gui_ids.lbl_deconstruct_transfer,
+        })
+    }
+
+    pub fn matches(
+        self,
+        resource_man: &ResourceManager,
+        origin: Option<TileId>,
+        id: TileId,
+    ) -> bool {
+        match self {
+            DeconstructFilter::All => true,
+            DeconstructFilter::SameTile => origin == Some(id),
+            DeconstructFilter::Transfer => resource_man
+                .registry
+                .tags
+                .get(&resource_man.registry.tag_ids.transfer)
+                .is_some_and(|tag| tag.of(&resource_man.registry, *id)),
+        }
+    }
 }
 
 pub struct TextFieldState {
@@ -73,7 +251,12 @@ impl Default for TextFieldState {
             fields: enum_map! {
                 TextField::Filter => Default::default(),
                 TextField::MapName => Default::default(),
-                TextField::MapRenaming => Default::default()
+                TextField::MapRenaming => Default::default(),
+                TextField::BeaconName => Default::default(),
+                TextField::MapSearch => Default::default(),
+                TextField::FolderName => Default::default(),
+                TextField::BlueprintName => Default::default(),
+                TextField::ModelFilter => Default::default()
             },
         }
     }
@@ -89,21 +272,94 @@ impl TextFieldState {
     }
 }
 
+/// What the map search matches its query against.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub enum MapSearchKind {
+    /// the name or ID of the tile
+    #[default]
+    Tile,
+    /// the name or ID of an item the tile holds, or has set in its data
+    Item,
+    /// the ID of a key in the tile's data
+    DataKey,
+}
+
+/// The map search dialog, and the tiles its last search found.
+#[derive(Debug, Clone, Default)]
+pub struct MapSearch {
+    pub kind: MapSearchKind,
+    pub results: Vec<(TileCoord, TileId)>,
+}
+
+/// An open radial menu of a tile's actions.
+#[derive(Debug, Clone)]
+pub struct RadialMenuState {
+    pub coord: TileCoord,
+    /// where on the screen the menu is centered, in UI units
+    pub center: Vec2,
+    pub actions: Vec<TileAction>,
+}
+
+/// A region of a tile being edited in-world.
+#[derive(Debug, Clone)]
+pub struct RegionEditState {
+    /// the tile the region belongs to
+    pub coord: TileCoord,
+    /// the data the region is stored in
+    pub id: Id,
+    pub region: TileBoundsSet,
+    /// where the current drag started, and whether it cuts the range out instead of adding it
+    pub drag: Option<(TileCoord, bool)>,
+}
+
 #[derive(Debug)]
 pub struct UiState {
     pub screen: Screen,
     pub previous: Option<Screen>,
     pub substate: SubState,
-    pub popup: PopupState,
+    /// the open popups, with the topmost one last
+    pub popups: Vec<PopupState>,
 
     pub debugger_open: bool,
+    /// whether the error history window is open
+    pub error_history_open: bool,
+    /// whether the model viewer window is open, and the model selected in it
+    pub model_viewer_open: bool,
+    pub viewed_model: Option<ModelId>,
+    /// the two snapshots compared by the debugger's diff view
+    pub debug_snapshots: [Option<MapSnapshot>; 2],
+    /// whether the game keeps snapshots of the latest ticks for the debugger
+    pub rewind_recording: bool,
+    /// the tick snapshots being stepped through in the debugger, and the index of the one shown
+    pub rewind: Option<(Vec<(TickUnit, MapSnapshot)>, usize)>,
+    /// the translation coverage checked from the debug menu
+    pub translation_coverage: Option<Vec<TranslationCoverage>>,
+    /// the unused assets and broken references checked from the debug menu
+    pub asset_report: Option<AssetReport>,
+    /// the fonts last chosen in the options, and the characters of the language they have no glyphs for
+    pub font_missing_glyphs: Option<(Vec<String>, Vec<char>)>,
+    /// the statistics shown in the statistics screen, and when they were fetched
+    pub statistics: Option<(Statistics, Instant)>,
+    /// the outcome of the last map verification or repair, to show in its popup
+    pub map_report: Option<MapReport>,
+    /// why the last map repair failed, to show in its popup
+    pub map_repair_error: Option<String>,
 
     pub text_field: TextFieldState,
 
     pub renaming_map: Option<String>,
+    /// the folder of saves the map menu is showing, which is empty at the top of the map folder
+    pub map_folder: String,
+    /// the saves picked for a batch operation in the map menu
+    pub selected_maps: HashSet<String>,
+    /// the replacements picked for the missing IDs of the map being loaded
+    pub id_remap_choices: Vec<(String, Option<TileId>)>,
 
     pub tile_selection_category: Option<Id>,
 
+    /// whether the map is open read-only, with everything that changes it disabled and hidden. Set before loading a map
+    pub read_only: bool,
+
     /// the currently selected tile.
     pub selected_tile_id: Option<TileId>,
     /// the currently selected tile's model ids.
@@ -112,14 +368,49 @@ pub struct UiState {
     pub already_placed_at: Option<TileCoord>,
     /// the tile that has its config menu open.
     pub config_open_at: Option<TileCoord>,
+    /// the tiles whose configuration windows are pinned open, and where the windows are
+    pub pinned_configs: Vec<(TileCoord, Vec2)>,
+    /// the moving object the camera follows: the tile tracking it, its render tag, its model, and its mesh index
+    pub following: Option<(TileCoord, RenderTagId, ModelId, usize)>,
+    /// the field drawn as a heatmap over the map
+    pub field_overlay: Option<Id>,
+    /// how many items a click moves in the storage transfer window
+    pub transfer_amount: ItemAmount,
     /// tile currently linking
     pub linking_tile: Option<(TileCoord, Id)>,
+    /// the region currently being edited
+    pub editing_region: Option<RegionEditState>,
+    /// the tile the alternate button was pressed on and when, until it is let go
+    pub radial_hold: Option<(TileCoord, Instant)>,
+    /// the radial menu open while the alternate button is held
+    pub radial_menu: Option<RadialMenuState>,
     /// the currently grouped tiles
     pub grouped_tiles: HashSet<TileCoord>,
     /// the stored initial cursor position, for moving/copying tiles
     pub paste_from: Option<TileCoord>,
     pub paste_content: Vec<(TileCoord, TileId, Option<DataMap>)>,
     pub paste_content_render_cache: HashMap<TileCoord, Option<(TileId, Vec<ModelId>)>>,
+    /// the range being dragged out by the deconstruct tool, centered where the drag started and reaching to the cursor
+    pub deconstruct_drag: Option<TileBounds>,
+    /// the tiles in the range dragged out by the deconstruct tool
+    pub deconstruct_marked: HashMap<TileCoord, TileId>,
+    /// the tile the deconstruct tool's drag started on
+    pub deconstruct_origin: Option<TileId>,
+    pub deconstruct_filter: DeconstructFilter,
+    /// the beacon the camera last jumped to
+    pub beacon_index: usize,
+    /// the map search, if it is open
+    pub map_search: Option<MapSearch>,
+    /// the names of the saved blueprints, if the blueprint panel is open
+    pub blueprints: Option<Vec<String>>,
+    /// how many of the ticks the loaded map catches up on for the time it was closed have run, out of how many
+    pub offline_progress: Option<(u64, u64)>,
+    /// the color of the current screen flash, how long it lasts, and when it started
+    pub flash: Option<(Color, Duration, Instant)>,
+    /// the cutscene being played, which holds back the player's input
+    pub cutscene: Option<CutscenePlayback>,
+    /// the cutscenes to play after the one being played
+    pub queued_cutscenes: VecDeque<Id>,
 
     pub tile_config_ui_position: Vec2,
     pub player_ui_position: Vec2,
@@ -137,22 +428,56 @@ impl Default for UiState {
             screen: Default::default(),
             previous: Default::default(),
             substate: Default::default(),
-            popup: Default::default(),
+            popups: Default::default(),
             debugger_open: Default::default(),
+            error_history_open: Default::default(),
+            model_viewer_open: false,
+            viewed_model: None,
+            debug_snapshots: Default::default(),
+            rewind_recording: false,
+            rewind: None,
+            translation_coverage: None,
+            asset_report: None,
+            font_missing_glyphs: None,
+            statistics: None,
+            map_report: None,
+            map_repair_error: None,
             text_field: Default::default(),
             renaming_map: Default::default(),
+            map_folder: Default::default(),
+            selected_maps: Default::default(),
+            id_remap_choices: Default::default(),
             tile_selection_category: Default::default(),
+            read_only: false,
 
             selected_tile_id: Default::default(),
             selected_tile_render_cache: Default::default(),
             already_placed_at: Default::default(),
             config_open_at: Default::default(),
+            pinned_configs: Default::default(),
+            following: None,
+            field_overlay: None,
+            transfer_amount: 1,
 
             linking_tile: Default::default(),
+            editing_region: None,
+            radial_hold: None,
+            radial_menu: None,
             grouped_tiles: Default::default(),
             paste_from: Default::default(),
             paste_content: Default::default(),
             paste_content_render_cache: HashMap::new(),
+            deconstruct_drag: None,
+            deconstruct_marked: Default::default(),
+            deconstruct_origin: Default::default(),
+            deconstruct_filter: Default::default(),
+            beacon_index: 0,
+            map_search: None,
+            blueprints: None,
+            offline_progress: None,
+            flash: None,
+            cutscene: None,
+            queued_cutscenes: Default::default(),
 
             tile_config_ui_position: vec2(0.1, 0.1), // TODO make default pos screen center?
             player_ui_position: vec2(0.1, 0.1),
@@ -167,6 +492,51 @@ impl Default for UiState {
 }
 
 impl UiState {
+    /// Returns the marked tiles that pass the current deconstruct filter.
+    pub fn deconstruct_targets(&self, resource_man: &ResourceManager) -> Vec<TileCoord> {
+        self.deconstruct_marked
+            .iter()
+            .filter(|(_, id)| {
+                self.deconstruct_filter
+                    .matches(resource_man, self.deconstruct_origin, **id)
+            })
+            .map(|(coord, _)| *coord)
+            .collect()
+    }
+
+    /// Opens a popup on top of the others.
+    pub fn push_popup(&mut self, popup: PopupState) {
+        self.popups.push(popup);
+    }
+
+    /// Closes the topmost popup.
+    pub fn close_popup(&mut self) -> Option<PopupState> {
+        self.popups.pop()
+    }
+
+    /// Gets the topmost popup, which is the one being displayed.
+    pub fn popup(&self) -> Option<&PopupState> {
+        self.popups.last()
+    }
+
+    /// Updates the progress popup with the given title, if it is open.
+    pub fn set_progress(&mut self, id: Id, new_message: String, new_fraction: Float) {
+        for popup in &mut self.popups {
+            if let PopupState::Progress {
+                title,
+                message,
+                fraction,
+            } = popup
+            {
+                if *title == id {
+                    *message = new_message;
+                    *fraction = new_fraction;
+                    return;
+                }
+            }
+        }
+    }
+
     pub fn return_screen(&mut self) {
         if let Some(prev) = self.previous {
             self.screen = prev;
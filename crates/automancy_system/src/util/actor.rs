@@ -1,6 +1,7 @@
 use hashbrown::HashMap;
 use ractor::rpc::CallResult;
 use ractor::{concurrency, ActorRef, Message, MessagingErr, RpcReplyPort};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::{hash::Hash, time::Duration};
 
 pub async fn multi_call_iter<Key, TMessage, TReply, TMsgBuilder>(
@@ -69,3 +70,96 @@ where
     // wait for the replies
     Ok(results)
 }
+
+/// A snapshot of the instrumentation of a kind of actor's mailboxes.
+#[derive(Debug, Clone, Copy)]
+pub struct MailboxStats {
+    /// the periodic messages waiting in the mailboxes
+    pub queued: usize,
+    /// the average time taken to process a message
+    pub average: Duration,
+    /// the longest time taken to process a message since the last snapshot
+    pub peak: Duration,
+}
+
+/// Instrumentation of a kind of actor's mailboxes. Only the periodic messages (the ticks) are counted as queued,
+/// as they are what piles up when the actors can't keep up.
+#[derive(Debug)]
+pub struct MailboxMetrics {
+    queued: AtomicUsize,
+    /// exponential moving average of the processing time, in nanoseconds
+    average_nanos: AtomicU64,
+    peak_nanos: AtomicU64,
+}
+
+impl MailboxMetrics {
+    pub const fn new() -> Self {
+        Self {
+            queued: AtomicUsize::new(0),
+            average_nanos: AtomicU64::new(0),
+            peak_nanos: AtomicU64::new(0),
+        }
+    }
+
+    /// Called when a periodic message is sent. Only for actors that live as long as the game, see [Queued] otherwise.
+    pub fn sent(&self) {
+        self.queued.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Called when a periodic message starts being processed.
+    pub fn received(&self) {
+        let _ = self
+            .queued
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| v.checked_sub(1));
+    }
+
+    /// Counts a periodic message as queued until the returned [Queued] that goes along with it is dropped.
+    pub fn queue(&'static self) -> Queued {
+        self.sent();
+
+        Queued(self)
+    }
+
+    /// Called when any message is done being processed.
+    pub fn processed(&self, time: Duration) {
+        let nanos = time.as_nanos() as u64;
+
+        let _ = self
+            .average_nanos
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |avg| {
+                Some(avg - avg / 16 + nanos / 16)
+            });
+        self.peak_nanos.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    /// Takes a snapshot of the metrics, resetting the peak.
+    pub fn take_stats(&self) -> MailboxStats {
+        MailboxStats {
+            queued: self.queued(),
+            average: Duration::from_nanos(self.average_nanos.load(Ordering::Relaxed)),
+            peak: Duration::from_nanos(self.peak_nanos.swap(0, Ordering::Relaxed)),
+        }
+    }
+}
+
+impl Default for MailboxMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Carried by a periodic message sent to an actor that can be stopped, and counts the message as queued for as long as
+/// it exists. Messages still in the mailbox of a stopped actor are dropped along with it, so they stop being counted
+/// the same as the ones that were processed.
+#[derive(Debug)]
+pub struct Queued(&'static MailboxMetrics);
+
+impl Drop for Queued {
+    fn drop(&mut self) {
+        self.0.received();
+    }
+}
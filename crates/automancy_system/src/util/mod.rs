@@ -1,12 +1,346 @@
-use automancy_defs::id::Id;
+use automancy_defs::colors;
+use automancy_defs::colors::Color;
+use automancy_defs::id::{Id, SharedStr, TileId};
+use automancy_defs::math::Float;
+use automancy_defs::stack::ItemAmount;
 use automancy_resources::data::{Data, DataMap};
 use automancy_resources::ResourceManager;
 use hashbrown::HashSet;
+use std::time::Duration;
 
 pub mod actor;
 pub mod num;
 pub mod round;
 
+/// How tiles that are still locked behind research appear in the tile selection. This is a rule of the map, stored in its data.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub enum LockedTileVisibility {
+    #[default]
+    Hidden,
+    /// shown, but cannot be placed
+    Greyed,
+    /// shown, and can be placed as if unlocked
+    Visible,
+}
+
+impl LockedTileVisibility {
+    pub const ALL: [Self; 3] = [Self::Hidden, Self::Greyed, Self::Visible];
+
+    pub fn of(resource_man: &ResourceManager, game_data: &DataMap) -> Self {
+        match game_data.get(resource_man.registry.data_ids.locked_tile_visibility) {
+            Some(Data::Amount(1)) => Self::Greyed,
+            Some(Data::Amount(2)) => Self::Visible,
+            _ => Self::Hidden,
+        }
+    }
+
+    pub fn set(self, resource_man: &ResourceManager, game_data: &mut DataMap) {
+        game_data.set(
+            resource_man.registry.data_ids.locked_tile_visibility,
+            Data::Amount(self as ItemAmount),
+        );
+    }
+
+    pub fn name(self, resource_man: &ResourceManager) -> SharedStr {
+        let gui_ids = &resource_man.registry.gui_ids;
+
+        resource_man.gui_str(match self {
+            Self::Hidden => gui_ids.lbl_locked_tiles_hidden,
+            Self::Greyed => gui_ids.lbl_locked_tiles_greyed,
+            Self::Visible => gui_ids.lbl_locked_tiles_visible,
+        })
+    }
+}
+
+/// Gets the tick rate the map runs at, if it overrides the one in the options. This is a rule of the map, stored in its data.
+pub fn map_tick_rate(resource_man: &ResourceManager, game_data: &DataMap) -> Option<u64> {
+    match game_data.get(resource_man.registry.data_ids.tick_rate) {
+        Some(Data::Amount(tps)) if *tps > 0 => Some(*tps as u64),
+        _ => None,
+    }
+}
+
+/// Sets the tick rate the map runs at. `None` makes it use the one in the options.
+pub fn set_map_tick_rate(
+    resource_man: &ResourceManager,
+    game_data: &mut DataMap,
+    tps: Option<u64>,
+) {
+    match tps {
+        Some(tps) => {
+            game_data.set(
+                resource_man.registry.data_ids.tick_rate,
+                Data::Amount(tps as ItemAmount),
+            );
+        }
+        None => {
+            game_data.remove(resource_man.registry.data_ids.tick_rate);
+        }
+    }
+}
+
+/// The number of random ticks each chunk receives every tick, unless the map sets its own.
+pub const DEFAULT_RANDOM_TICK_SPEED: u32 = 3;
+pub const MAX_RANDOM_TICK_SPEED: u32 = 64;
+
+/// Gets how many random tiles of each chunk receive a random tick every tick. This is a rule of the map, stored in its data.
+pub fn map_random_tick_speed(resource_man: &ResourceManager, game_data: &DataMap) -> u32 {
+    match game_data.get(resource_man.registry.data_ids.random_tick_speed) {
+        Some(Data::Amount(speed)) if *speed >= 0 => (*speed as u32).min(MAX_RANDOM_TICK_SPEED),
+        _ => DEFAULT_RANDOM_TICK_SPEED,
+    }
+}
+
+/// Sets how many random tiles of each chunk receive a random tick every tick.
+pub fn set_map_random_tick_speed(
+    resource_man: &ResourceManager,
+    game_data: &mut DataMap,
+    speed: u32,
+) {
+    game_data.set(
+        resource_man.registry.data_ids.random_tick_speed,
+        Data::Amount(speed as ItemAmount),
+    );
+}
+
+/// The most ticks a map can be set to leave between two diffusions of its fields.
+pub const MAX_FIELD_DIFFUSION_INTERVAL: u32 = 100;
+
+/// Gets how many ticks pass between two diffusions of the fields, like temperature. `None` if the map doesn't simulate
+/// fields, which is the default, as most packs don't use them.
+pub fn map_field_diffusion_interval(
+    resource_man: &ResourceManager,
+    game_data: &DataMap,
+) -> Option<u32> {
+    match game_data.get(resource_man.registry.data_ids.field_diffusion_interval) {
+        Some(Data::Amount(interval)) if *interval > 0 => {
+            Some((*interval as u32).min(MAX_FIELD_DIFFUSION_INTERVAL))
+        }
+        _ => None,
+    }
+}
+
+/// Sets how many ticks pass between two diffusions of the fields. `None` stops simulating them.
+pub fn set_map_field_diffusion_interval(
+    resource_man: &ResourceManager,
+    game_data: &mut DataMap,
+    interval: Option<u32>,
+) {
+    match interval {
+        Some(interval) => {
+            game_data.set(
+                resource_man.registry.data_ids.field_diffusion_interval,
+                Data::Amount(interval as ItemAmount),
+            );
+        }
+        None => {
+            game_data.remove(resource_man.registry.data_ids.field_diffusion_interval);
+        }
+    }
+}
+
+/// The most minutes a map can be set to catch up on when loaded.
+pub const MAX_OFFLINE_PROGRESS: u32 = 60;
+
+/// Gets the most minutes of the time the map was closed that are simulated when it is loaded again. `None` if the map
+/// doesn't catch up, which is the default.
+pub fn map_offline_progress(resource_man: &ResourceManager, game_data: &DataMap) -> Option<u32> {
+    match game_data.get(resource_man.registry.data_ids.offline_progress) {
+        Some(Data::Amount(minutes)) if *minutes > 0 => {
+            Some((*minutes as u32).min(MAX_OFFLINE_PROGRESS))
+        }
+        _ => None,
+    }
+}
+
+/// Sets the most minutes the map catches up on when loaded. `None` stops it from catching up.
+pub fn set_map_offline_progress(
+    resource_man: &ResourceManager,
+    game_data: &mut DataMap,
+    minutes: Option<u32>,
+) {
+    match minutes {
+        Some(minutes) => {
+            game_data.set(
+                resource_man.registry.data_ids.offline_progress,
+                Data::Amount(minutes as ItemAmount),
+            );
+        }
+        None => {
+            game_data.remove(resource_man.registry.data_ids.offline_progress);
+        }
+    }
+}
+
+/// A soft limit of a map, on how much of something it may have.
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub enum BudgetLimit {
+    /// the tile entities
+    Tiles,
+    Agents,
+    /// the items waiting in the transfer queues
+    InFlightItems,
+}
+
+impl BudgetLimit {
+    pub const ALL: [Self; 3] = [Self::Tiles, Self::Agents, Self::InFlightItems];
+
+    /// The highest the limit can be set to.
+    pub fn max(self) -> u32 {
+        match self {
+            Self::Tiles => 100_000,
+            Self::Agents => 1_000,
+            Self::InFlightItems => 100_000,
+        }
+    }
+
+    fn data_id(self, resource_man: &ResourceManager) -> Id {
+        let data_ids = &resource_man.registry.data_ids;
+
+        match self {
+            Self::Tiles => data_ids.budget_tiles,
+            Self::Agents => data_ids.budget_agents,
+            Self::InFlightItems => data_ids.budget_in_flight_items,
+        }
+    }
+
+    pub fn name(self, resource_man: &ResourceManager) -> SharedStr {
+        let gui_ids = &resource_man.registry.gui_ids;
+
+        resource_man.gui_str(match self {
+            Self::Tiles => gui_ids.lbl_map_budget_tiles,
+            Self::Agents => gui_ids.lbl_map_budget_agents,
+            Self::InFlightItems => gui_ids.lbl_map_budget_in_flight_items,
+        })
+    }
+}
+
+/// The soft limits of a map, which warn when reached, so that shared saves stay playable on low-end machines.
+/// This is a rule of the map, stored in its data. No limit is set by default.
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub struct MapBudget {
+    pub tiles: Option<u32>,
+    pub agents: Option<u32>,
+    pub in_flight_items: Option<u32>,
+    /// whether placing tiles and spawning agents is refused once their limit is reached, instead of only warned about
+    pub block: bool,
+}
+
+impl MapBudget {
+    pub fn of(resource_man: &ResourceManager, game_data: &DataMap) -> Self {
+        let limit = |limit: BudgetLimit| match game_data.get(limit.data_id(resource_man)) {
+            Some(Data::Amount(max)) if *max > 0 => Some((*max as u32).min(limit.max())),
+            _ => None,
+        };
+
+        Self {
+            tiles: limit(BudgetLimit::Tiles),
+            agents: limit(BudgetLimit::Agents),
+            in_flight_items: limit(BudgetLimit::InFlightItems),
+            block: matches!(
+                game_data.get(resource_man.registry.data_ids.budget_block),
+                Some(Data::Bool(true))
+            ),
+        }
+    }
+
+    pub fn limit(&self, limit: BudgetLimit) -> Option<u32> {
+        match limit {
+            BudgetLimit::Tiles => self.tiles,
+            BudgetLimit::Agents => self.agents,
+            BudgetLimit::InFlightItems => self.in_flight_items,
+        }
+    }
+
+    /// Checks if the count is at or over the limit.
+    pub fn reached(&self, limit: BudgetLimit, count: usize) -> bool {
+        self.limit(limit).is_some_and(|max| count >= max as usize)
+    }
+
+    /// Checks if one more can be added to the count, which is always allowed unless the map blocks going over its budget.
+    pub fn allows(&self, limit: BudgetLimit, count: usize) -> bool {
+        !self.block || !self.reached(limit, count)
+    }
+}
+
+/// Sets a limit of the map's budget. `None` removes it.
+pub fn set_map_budget(
+    resource_man: &ResourceManager,
+    game_data: &mut DataMap,
+    limit: BudgetLimit,
+    max: Option<u32>,
+) {
+    match max {
+        Some(max) => {
+            game_data.set(
+                limit.data_id(resource_man),
+                Data::Amount(max.min(limit.max()) as ItemAmount),
+            );
+        }
+        None => {
+            game_data.remove(limit.data_id(resource_man));
+        }
+    }
+}
+
+/// Sets whether the map refuses placing tiles and spawning agents once their limit is reached.
+pub fn set_map_budget_block(resource_man: &ResourceManager, game_data: &mut DataMap, block: bool) {
+    game_data.set(
+        resource_man.registry.data_ids.budget_block,
+        Data::Bool(block),
+    );
+}
+
+/// Gets the colors of the map's background gradient, from the top of the screen to the bottom.
+pub fn map_background(resource_man: &ResourceManager, game_data: &DataMap) -> (Color, Color) {
+    let color = |id, default| match game_data.get(id) {
+        Some(Data::Color(color)) => *color,
+        _ => default,
+    };
+
+    (
+        color(
+            resource_man.registry.data_ids.background_top,
+            colors::SKY_TOP,
+        ),
+        color(
+            resource_man.registry.data_ids.background_bottom,
+            colors::SKY_BOTTOM,
+        ),
+    )
+}
+
+/// Gets how far through its day cycle the map is after the given time, from 0 to 1. Maps without a day cycle are always at 0.
+pub fn map_time_of_day(
+    resource_man: &ResourceManager,
+    game_data: &DataMap,
+    time: Duration,
+) -> Float {
+    match game_data.get(resource_man.registry.data_ids.day_length) {
+        Some(Data::Amount(secs)) if *secs > 0 => (time.as_secs_f32() / *secs as Float).fract(),
+        _ => 0.0,
+    }
+}
+
+/// Checks if a tile still needs to be unlocked through research. Default tiles are never locked.
+pub fn is_tile_locked(id: TileId, resource_man: &ResourceManager, game_data: &mut DataMap) -> bool {
+    let is_default_tile = resource_man.registry.tiles[&id]
+        .data
+        .get(resource_man.registry.data_ids.default_tile)
+        .cloned()
+        .and_then(|v| v.into_bool())
+        .unwrap_or(false);
+
+    if is_default_tile {
+        return false;
+    }
+
+    match resource_man.get_research_by_unlock(id) {
+        Some(research) => !is_research_unlocked(research.id, resource_man, game_data),
+        None => true,
+    }
+}
+
 pub fn is_research_unlocked(
     research: Id,
     resource_man: &ResourceManager,
@@ -33,10 +367,22 @@ pub fn should_category_show(
         return false;
     };
 
+    if resource_man
+        .get_category_children(category.id)
+        .iter()
+        .any(|child| should_category_show(*child, resource_man, game_data))
+    {
+        return true;
+    }
+
     let Some(tiles) = resource_man.get_tiles_by_category(category.id) else {
         return false;
     };
 
+    if LockedTileVisibility::of(resource_man, game_data) != LockedTileVisibility::Hidden {
+        return !tiles.is_empty();
+    }
+
     if tiles.iter().any(|id| {
         resource_man.registry.tiles[id]
             .data
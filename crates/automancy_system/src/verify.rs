@@ -0,0 +1,275 @@
+use crate::map::{GameMap, LoadMapOption, MapRaw, MAP_EXT};
+use automancy_defs::{
+    coord::{TileCoord, TileUnit},
+    id::Id,
+    stack::ItemAmount,
+};
+use automancy_resources::{data::DataRaw, ResourceManager};
+use hashbrown::HashSet;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::mem;
+use std::path::PathBuf;
+
+/// Tiles further than this from the center of the map are out of bounds. Past it, world positions lose too much precision to be drawn properly.
+pub const MAP_RADIUS: TileUnit = 1 << 20;
+
+/// A problem found in a saved map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapIssue {
+    /// a tile links to a coordinate with no tile on it
+    OrphanedLink {
+        coord: TileCoord,
+        key: String,
+        target: TileCoord,
+    },
+    /// a tile is set to run a script that isn't registered, usually because the mod providing it was updated or removed
+    MissingScript { coord: TileCoord, script: String },
+    /// an inventory holds less than none of an item
+    NegativeInventory {
+        coord: TileCoord,
+        key: String,
+        item: String,
+        amount: ItemAmount,
+    },
+    /// a tile is further than [`MAP_RADIUS`] from the center
+    OutOfBounds { coord: TileCoord, scenery: bool },
+}
+
+impl MapIssue {
+    /// Describes what repairing the issue does.
+    pub fn repair(&self) -> &'static str {
+        match self {
+            MapIssue::OrphanedLink { .. } => "removed the link",
+            MapIssue::MissingScript { .. } => "removed the script",
+            MapIssue::NegativeInventory { .. } => "removed the item from the inventory",
+            MapIssue::OutOfBounds { .. } => "removed the tile",
+        }
+    }
+}
+
+impl fmt::Display for MapIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapIssue::OrphanedLink { coord, key, target } => write!(
+                f,
+                "the tile at {coord} links {key} to {target}, which has no tile"
+            ),
+            MapIssue::MissingScript { coord, script } => {
+                write!(f, "the tile at {coord} runs the missing script {script}")
+            }
+            MapIssue::NegativeInventory {
+                coord,
+                key,
+                item,
+                amount,
+            } => write!(f, "the tile at {coord} holds {amount} {item} in {key}"),
+            MapIssue::OutOfBounds { coord, scenery } => {
+                if *scenery {
+                    write!(f, "the scenery at {coord} is out of bounds")
+                } else {
+                    write!(f, "the tile at {coord} is out of bounds")
+                }
+            }
+        }
+    }
+}
+
+/// The problems found in a saved map, and where the map was backed up to if they were repaired.
+#[derive(Debug, Clone, Default)]
+pub struct MapReport {
+    pub issues: Vec<MapIssue>,
+    /// the copy of the map from before it was repaired, or None if it wasn't
+    pub backup: Option<PathBuf>,
+    /// where the report was written to
+    pub path: Option<PathBuf>,
+}
+
+impl MapReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Gets a line for each issue, saying how it was repaired if it was.
+    pub fn lines(&self) -> Vec<String> {
+        self.issues
+            .iter()
+            .map(|issue| {
+                if self.backup.is_some() {
+                    format!("{issue}: {}", issue.repair())
+                } else {
+                    issue.to_string()
+                }
+            })
+            .collect()
+    }
+
+    /// Writes the report as text into the map folder. Returns the path written to.
+    pub fn write(&self, opt: &LoadMapOption) -> io::Result<PathBuf> {
+        let Some(path) = GameMap::path(opt).map(|v| v.join("verify_report").with_extension("txt"))
+        else {
+            return Err(io::Error::other("the map has no folder to write to"));
+        };
+
+        let mut writer = BufWriter::new(File::create(&path)?);
+        writeln!(writer, "{opt}: {} problem(s) found", self.issues.len())?;
+        if let Some(backup) = &self.backup {
+            writeln!(writer, "repaired, backed up to {}", backup.display())?;
+        }
+        for line in self.lines() {
+            writeln!(writer, "- {line}")?;
+        }
+        writer.flush()?;
+
+        Ok(path)
+    }
+}
+
+fn in_bounds(coord: TileCoord) -> bool {
+    // widened, so that the third axis can't overflow
+    let (q, r) = (coord.x as i64, coord.y as i64);
+
+    q.abs().max(r.abs()).max((q + r).abs()) <= MAP_RADIUS as i64
+}
+
+fn script_exists(resource_man: &ResourceManager, script: &str) -> bool {
+    Id::try_parse(script, &resource_man.interner)
+        .is_some_and(|id| resource_man.registry.scripts.contains_key(&id))
+}
+
+/// Checks every tile of a map, removing what is broken if `repair` is set. Returns the problems found.
+fn check(resource_man: &ResourceManager, map: &mut MapRaw, repair: bool) -> Vec<MapIssue> {
+    let data_ids = &resource_man.registry.data_ids;
+    let key_str = |id| {
+        resource_man
+            .interner
+            .resolve(id)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let (link_key, script_key) = (key_str(data_ids.link), key_str(data_ids.script));
+
+    let mut issues = vec![];
+
+    map.tiles.retain(|(coord, ..)| {
+        if in_bounds(*coord) {
+            return true;
+        }
+
+        issues.push(MapIssue::OutOfBounds {
+            coord: *coord,
+            scenery: false,
+        });
+
+        !repair
+    });
+    map.scenery.retain(|(coord, _)| {
+        if in_bounds(*coord) {
+            return true;
+        }
+
+        issues.push(MapIssue::OutOfBounds {
+            coord: *coord,
+            scenery: true,
+        });
+
+        !repair
+    });
+
+    // out of bounds tiles are left out even when not repairing, as links to them won't work either way
+    let coords = map
+        .tiles
+        .iter()
+        .map(|(coord, ..)| *coord)
+        .filter(|coord| in_bounds(*coord))
+        .collect::<HashSet<_>>();
+
+    for (coord, _, data) in &mut map.tiles {
+        let coord = *coord;
+        let mut entries = mem::take(data).into_inner();
+
+        entries.retain(|key, value| match value {
+            DataRaw::Coord(target) if *key == link_key && !coords.contains(&*target) => {
+                issues.push(MapIssue::OrphanedLink {
+                    coord,
+                    key: key.clone(),
+                    target: *target,
+                });
+
+                !repair
+            }
+            DataRaw::Id(script) if *key == script_key && !script_exists(resource_man, script) => {
+                issues.push(MapIssue::MissingScript {
+                    coord,
+                    script: script.clone(),
+                });
+
+                !repair
+            }
+            DataRaw::Inventory(inventory) => {
+                inventory.retain(|(item, amount)| {
+                    if *amount >= 0 {
+                        return true;
+                    }
+
+                    issues.push(MapIssue::NegativeInventory {
+                        coord,
+                        key: key.clone(),
+                        item: item.clone(),
+                        amount: *amount,
+                    });
+
+                    !repair
+                });
+
+                true
+            }
+            _ => true,
+        });
+
+        *data = entries.into();
+    }
+
+    issues
+}
+
+/// Checks a saved map for orphaned links, missing scripts, negative inventories and out of bounds tiles, without loading it.
+/// Returns None if the map couldn't be read.
+pub fn verify_map(resource_man: &ResourceManager, opt: &LoadMapOption) -> Option<MapReport> {
+    let mut map = GameMap::read_map(resource_man, opt).ok()?;
+
+    Some(MapReport {
+        issues: check(resource_man, &mut map, false),
+        backup: None,
+        path: None,
+    })
+}
+
+/// Repairs a saved map, after backing it up next to itself, and writes the report into the map folder.
+///
+/// The map must not be loaded, or the repairs will be saved over. Load it again afterward.
+pub fn repair_map(resource_man: &ResourceManager, opt: &LoadMapOption) -> io::Result<MapReport> {
+    let (Some(path), Ok(mut map)) = (GameMap::map(opt), GameMap::read_map(resource_man, opt))
+    else {
+        return Err(io::Error::other("the map could not be read"));
+    };
+
+    let mut report = MapReport {
+        issues: check(resource_man, &mut map, true),
+        backup: None,
+        path: None,
+    };
+
+    if !report.is_ok() {
+        let backup = path.with_extension(format!("{MAP_EXT}.bak"));
+        fs::copy(&path, &backup)?;
+        report.backup = Some(backup);
+
+        GameMap::write_map(opt, &map)?;
+    }
+
+    report.path = Some(report.write(opt)?);
+
+    Ok(report)
+}
@@ -0,0 +1,79 @@
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::{Id, Interner, TileId};
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::types::tile::TileDef;
+use automancy_system::changelog::{RegistryDiff, RegistryExport, TileExport};
+use hashbrown::{HashMap, HashSet};
+
+fn tile(interner: &mut Interner, ids: &[Id], capacity: usize) -> TileDef {
+    let mut set = HashSet::with_capacity(capacity);
+    let mut tiles = HashMap::with_capacity(capacity);
+    let mut sets = HashMap::with_capacity(capacity);
+
+    for (i, id) in ids.iter().enumerate() {
+        set.insert(*id);
+        tiles.insert(TileCoord::new(i as i32, -(i as i32)), *id);
+        sets.insert(*id, ids.iter().cloned().collect::<HashSet<_>>());
+    }
+
+    let mut data = DataMap::default();
+    data.set(interner.get_or_intern("test:set"), Data::SetId(set));
+    data.set(interner.get_or_intern("test:tiles"), Data::TileMap(tiles));
+    data.set(interner.get_or_intern("test:sets"), Data::MapSetId(sets));
+
+    TileDef {
+        id: TileId(interner.get_or_intern("test:tile")),
+        function: None,
+        category: None,
+        scenery: None,
+        data,
+        actions: vec![],
+        connected: None,
+    }
+}
+
+fn export(interner: &Interner, tile: &TileDef) -> RegistryExport {
+    RegistryExport {
+        tiles: [("test:tile".to_string(), TileExport::new(tile, interner))]
+            .into_iter()
+            .collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_registry_export_twice_is_unchanged() {
+    let mut interner = Interner::new();
+    let ids = (0..64)
+        .map(|i| interner.get_or_intern(format!("test:item_{i}")))
+        .collect::<Vec<_>>();
+    let reversed = ids.iter().rev().cloned().collect::<Vec<_>>();
+
+    // the same data, hashed in a different order
+    let first = tile(&mut interner, &ids, 0);
+    let second = tile(&mut interner, &reversed, 1024);
+    assert_eq!(first.data, second.data);
+
+    let first = export(&interner, &first);
+    let second = export(&interner, &second);
+
+    assert_eq!(first, second);
+    assert!(RegistryDiff::between(&first, &second).is_empty());
+}
+
+#[test]
+fn test_registry_export_diff() {
+    let mut interner = Interner::new();
+    let ids = (0..4)
+        .map(|i| interner.get_or_intern(format!("test:item_{i}")))
+        .collect::<Vec<_>>();
+
+    let first = tile(&mut interner, &ids, 0);
+    let second = tile(&mut interner, &ids[1..], 0);
+
+    let diff = RegistryDiff::between(&export(&interner, &first), &export(&interner, &second));
+
+    assert_eq!(diff.tiles.changed, vec!["test:tile".to_string()]);
+    assert!(diff.tiles.added.is_empty() && diff.tiles.removed.is_empty());
+    assert!(diff.items.is_empty() && diff.scripts.is_empty());
+}
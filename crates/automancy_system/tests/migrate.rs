@@ -0,0 +1,93 @@
+use automancy_defs::coord::TileCoord;
+use automancy_defs::glam::IVec2;
+use automancy_defs::hexx::{Hex, OffsetHexMode};
+use automancy_defs::id::Interner;
+use automancy_resources::data::{DataMapRaw, DataRaw};
+use automancy_system::map::migrate::{migrate, MigrateError, MAP_VERSION};
+use automancy_system::map::MapRaw;
+use std::collections::BTreeMap;
+
+fn map(version: u32, data: Vec<(&str, DataRaw)>) -> MapRaw {
+    let mut interner = Interner::new();
+    let id = interner.get_or_intern("test:tile");
+
+    let data = data
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect::<BTreeMap<_, _>>();
+
+    MapRaw {
+        version,
+        tiles: vec![(TileCoord::ZERO, id, DataMapRaw::from(data))],
+        scenery: vec![],
+        agents: vec![],
+        owners: vec![],
+        tile_map: [(id, "test:tile".to_string())].into_iter().collect(),
+    }
+}
+
+fn data_of(map: MapRaw) -> BTreeMap<String, DataRaw> {
+    map.tiles.into_iter().next().unwrap().2.into_inner()
+}
+
+#[test]
+fn test_migrate_v0_offset_coords() {
+    let offsets = vec![IVec2::new(0, 0), IVec2::new(2, 1), IVec2::new(-1, 3)];
+    let mut map = map(
+        0,
+        vec![
+            ("test:path", DataRaw::VecOffsetCoord(offsets.clone())),
+            ("test:target", DataRaw::Coord(TileCoord::new(1, 2))),
+        ],
+    );
+
+    assert_eq!(migrate(&mut map).unwrap(), 0);
+    assert_eq!(map.version, MAP_VERSION);
+
+    let data = data_of(map);
+
+    let Some(DataRaw::VecCoord(coords)) = data.get("test:path") else {
+        panic!("the offset coordinates weren't converted: {data:?}");
+    };
+    let expected = offsets
+        .iter()
+        .map(|v| {
+            TileCoord::from(Hex::from_offset_coordinates(
+                v.to_array(),
+                OffsetHexMode::EvenRows,
+            ))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(coords, &expected);
+
+    assert!(matches!(
+        data.get("test:target"),
+        Some(DataRaw::Coord(v)) if *v == TileCoord::new(1, 2)
+    ));
+}
+
+#[test]
+fn test_migrate_current_unchanged() {
+    let mut map = map(
+        MAP_VERSION,
+        vec![("test:path", DataRaw::VecCoord(vec![TileCoord::new(3, -1)]))],
+    );
+
+    assert_eq!(migrate(&mut map).unwrap(), MAP_VERSION);
+    assert_eq!(map.version, MAP_VERSION);
+    assert!(matches!(
+        data_of(map).get("test:path"),
+        Some(DataRaw::VecCoord(v)) if v == &[TileCoord::new(3, -1)]
+    ));
+}
+
+#[test]
+fn test_migrate_too_new() {
+    let mut map = map(MAP_VERSION + 1, vec![]);
+
+    assert!(matches!(
+        migrate(&mut map),
+        Err(MigrateError::TooNew(v)) if v == MAP_VERSION + 1
+    ));
+    assert_eq!(map.version, MAP_VERSION + 1);
+}
@@ -0,0 +1,55 @@
+use automancy_system::map::{MapInfoRaw, NamespaceChanges};
+
+fn loaded(namespaces: &[(&str, Option<&str>)]) -> Vec<(String, Option<String>)> {
+    namespaces
+        .iter()
+        .map(|(v, version)| (v.to_string(), version.map(str::to_string)))
+        .collect()
+}
+
+#[test]
+fn test_namespace_changes() {
+    let info = MapInfoRaw::decode(
+        br#"(namespaces: [("core", Some("1.0.0")), ("old", None), ("mod", Some("0.1"))])"#,
+    )
+    .unwrap();
+
+    let changes = info.namespace_changes(&loaded(&[
+        ("core", Some("1.1.0")),
+        ("mod", Some("0.1")),
+        ("new", None),
+    ]));
+
+    assert_eq!(
+        changes,
+        NamespaceChanges {
+            added: vec!["new".to_string()],
+            removed: vec!["old".to_string()],
+            changed: vec![(
+                "core".to_string(),
+                Some("1.0.0".to_string()),
+                Some("1.1.0".to_string())
+            )],
+        }
+    );
+}
+
+#[test]
+fn test_namespace_changes_unchanged() {
+    let info =
+        MapInfoRaw::decode(br#"(namespaces: [("core", Some("1.0.0")), ("mod", None)])"#).unwrap();
+
+    assert!(info
+        .namespace_changes(&loaded(&[("core", Some("1.0.0")), ("mod", None)]))
+        .is_empty());
+    // a version added to a pack that had none is a change too
+    assert!(!info
+        .namespace_changes(&loaded(&[("core", Some("1.0.0")), ("mod", Some("0.1"))]))
+        .is_empty());
+
+    // maps saved before the namespaces were recorded
+    let info = MapInfoRaw::decode(b"()").unwrap();
+    assert!(info
+        .namespace_changes(&loaded(&[("core", Some("1.0.0"))]))
+        .is_empty());
+}
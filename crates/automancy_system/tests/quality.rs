@@ -0,0 +1,119 @@
+use automancy_system::quality::{QualityGovernor, QualityLevel};
+use std::time::{Duration, Instant};
+
+/// Counts frames of the given time until the duration has passed, returning the levels changed to.
+fn run(
+    governor: &mut QualityGovernor,
+    now: &mut Instant,
+    frame_time: Duration,
+    duration: Duration,
+    limited_to: Option<Duration>,
+) -> Vec<QualityLevel> {
+    let end = *now + duration;
+    let mut changes = vec![];
+
+    while *now < end {
+        *now += frame_time;
+        changes.extend(governor.update(*now, frame_time, limited_to));
+    }
+
+    changes
+}
+
+const SLOW: Duration = Duration::from_millis(50);
+const FAST: Duration = Duration::from_millis(10);
+const BETWEEN: Duration = Duration::from_millis(25);
+
+fn secs(secs: u64) -> Duration {
+    Duration::from_secs(secs)
+}
+
+#[test]
+fn test_quality_lowers_while_slow() {
+    let mut governor = QualityGovernor::default();
+    let mut now = Instant::now();
+
+    assert_eq!(governor.level(), QualityLevel::Full);
+    assert!(run(&mut governor, &mut now, SLOW, secs(2), None).is_empty());
+    assert_eq!(
+        run(&mut governor, &mut now, SLOW, secs(2), None),
+        vec![QualityLevel::Reduced]
+    );
+    assert!(run(&mut governor, &mut now, SLOW, secs(2), None).is_empty());
+    assert_eq!(
+        run(&mut governor, &mut now, SLOW, secs(1), None),
+        vec![QualityLevel::Low]
+    );
+    assert!(run(&mut governor, &mut now, SLOW, secs(10), None).is_empty());
+    assert_eq!(governor.level(), QualityLevel::Low);
+}
+
+#[test]
+fn test_quality_raises_after_headroom() {
+    let mut governor = QualityGovernor::default();
+    let mut now = Instant::now();
+
+    run(&mut governor, &mut now, SLOW, secs(7), None);
+    assert_eq!(governor.level(), QualityLevel::Low);
+
+    assert!(run(&mut governor, &mut now, FAST, secs(5), None).is_empty());
+    assert_eq!(
+        run(&mut governor, &mut now, FAST, secs(6), None),
+        vec![QualityLevel::Reduced]
+    );
+    assert_eq!(
+        run(&mut governor, &mut now, FAST, secs(10), None),
+        vec![QualityLevel::Full]
+    );
+    assert!(run(&mut governor, &mut now, FAST, secs(10), None).is_empty());
+}
+
+#[test]
+fn test_quality_holds_between_thresholds() {
+    let mut governor = QualityGovernor::default();
+    let mut now = Instant::now();
+
+    run(&mut governor, &mut now, SLOW, secs(4), None);
+    assert_eq!(governor.level(), QualityLevel::Reduced);
+
+    assert!(run(&mut governor, &mut now, BETWEEN, secs(30), None).is_empty());
+    assert_eq!(governor.level(), QualityLevel::Reduced);
+}
+
+#[test]
+fn test_quality_ignores_frame_limit() {
+    let mut governor = QualityGovernor::default();
+    let mut now = Instant::now();
+
+    assert!(run(&mut governor, &mut now, SLOW, secs(20), Some(SLOW)).is_empty());
+    assert_eq!(governor.level(), QualityLevel::Full);
+}
+
+#[test]
+fn test_quality_reset() {
+    let mut governor = QualityGovernor::default();
+    let mut now = Instant::now();
+
+    run(&mut governor, &mut now, SLOW, secs(4), None);
+    assert_eq!(governor.level(), QualityLevel::Reduced);
+
+    governor.reset();
+    assert_eq!(governor.level(), QualityLevel::Full);
+    assert!(run(&mut governor, &mut now, SLOW, secs(2), None).is_empty());
+}
+
+#[test]
+fn test_quality_levels() {
+    assert!(QualityLevel::Full.post_processing());
+    assert!(QualityLevel::Full.screen_effects());
+    assert!(QualityLevel::Full.progress_bars());
+    assert_eq!(QualityLevel::Full.resolution_scale(), 1.0);
+
+    assert!(!QualityLevel::Reduced.post_processing());
+    assert!(!QualityLevel::Reduced.screen_effects());
+    assert!(QualityLevel::Reduced.progress_bars());
+
+    assert!(!QualityLevel::Low.progress_bars());
+    assert!(QualityLevel::Low.resolution_scale() < QualityLevel::Reduced.resolution_scale());
+    assert!(QualityLevel::Reduced.resolution_scale() < 1.0);
+}
@@ -0,0 +1,36 @@
+use automancy_system::map::{join_save_path, sanitize_folder, split_save_path};
+
+#[test]
+fn test_sanitize_folder_keeps_folders() {
+    assert_eq!(sanitize_folder("a"), "a");
+    assert_eq!(sanitize_folder("a/b/c"), "a/b/c");
+    assert_eq!(sanitize_folder(" my saves / old "), "my_saves/old");
+    assert_eq!(sanitize_folder("Über/ß"), "Über/ß");
+}
+
+#[test]
+fn test_sanitize_folder_drops_empty() {
+    assert_eq!(sanitize_folder(""), "");
+    assert_eq!(sanitize_folder("/"), "");
+    assert_eq!(sanitize_folder("/a//b/"), "a/b");
+    assert_eq!(sanitize_folder("  /a"), "a");
+}
+
+#[test]
+fn test_sanitize_folder_stays_inside() {
+    assert_eq!(sanitize_folder("."), "");
+    assert_eq!(sanitize_folder(".."), "");
+    assert_eq!(sanitize_folder("../x"), "x");
+    assert_eq!(sanitize_folder("a/../../b"), "a/b");
+    assert_eq!(sanitize_folder("..hidden/."), "hidden");
+    assert_eq!(sanitize_folder("a\\..\\b"), "a____b");
+    assert_eq!(sanitize_folder("C:/x"), "C_/x");
+}
+
+#[test]
+fn test_save_path_round_trip() {
+    assert_eq!(split_save_path("a/b/save"), ("a/b", "save"));
+    assert_eq!(split_save_path("save"), ("", "save"));
+    assert_eq!(join_save_path("a/b", "save"), "a/b/save");
+    assert_eq!(join_save_path("", "save"), "save");
+}
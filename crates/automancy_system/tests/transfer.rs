@@ -0,0 +1,156 @@
+use automancy_defs::id::Interner;
+use automancy_defs::stack::ItemStack;
+use automancy_system::transfer::TransferQueues;
+use std::env;
+use std::fs;
+
+#[test]
+fn test_transfer_receive_splits() {
+    let mut interner = Interner::new();
+    let channel = interner.get_or_intern("test:channel");
+    let iron = interner.get_or_intern("test:iron");
+    let copper = interner.get_or_intern("test:copper");
+
+    let mut queues = TransferQueues::default();
+    queues.send(
+        channel,
+        [
+            ItemStack {
+                id: iron,
+                amount: 3,
+            },
+            ItemStack {
+                id: copper,
+                amount: 0,
+            },
+            ItemStack {
+                id: copper,
+                amount: 5,
+            },
+        ],
+    );
+    assert_eq!(queues.total(), 8);
+
+    let taken = queues.receive(channel, 4);
+    assert_eq!(
+        taken,
+        vec![
+            ItemStack {
+                id: iron,
+                amount: 3
+            },
+            ItemStack {
+                id: copper,
+                amount: 1
+            },
+        ]
+    );
+    assert_eq!(queues.total(), 4);
+
+    queues.return_front(channel, taken);
+    assert_eq!(queues.total(), 8);
+    assert_eq!(
+        queues.receive(channel, 100),
+        vec![
+            ItemStack {
+                id: iron,
+                amount: 3
+            },
+            ItemStack {
+                id: copper,
+                amount: 1
+            },
+            ItemStack {
+                id: copper,
+                amount: 4
+            },
+        ]
+    );
+    assert_eq!(queues.total(), 0);
+    assert!(queues
+        .receive(interner.get_or_intern("test:other"), 1)
+        .is_empty());
+}
+
+#[test]
+fn test_transfer_round_trip() {
+    let mut interner = Interner::new();
+    let channel = interner.get_or_intern("test:channel");
+    let empty = interner.get_or_intern("test:empty");
+    let iron = interner.get_or_intern("test:iron");
+    let copper = interner.get_or_intern("test:copper");
+
+    let mut queues = TransferQueues::default();
+    queues.send(
+        channel,
+        [
+            ItemStack {
+                id: iron,
+                amount: 3,
+            },
+            ItemStack {
+                id: copper,
+                amount: 5,
+            },
+        ],
+    );
+    queues.send(empty, []);
+
+    let path = env::temp_dir().join(format!("automancy-transfer-{}.ron", std::process::id()));
+    queues.save_to(&path, &interner).unwrap();
+
+    let mut loaded = TransferQueues::load_from(&path, &interner);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.total(), 8);
+    assert_eq!(
+        loaded.receive(channel, 100),
+        vec![
+            ItemStack {
+                id: iron,
+                amount: 3
+            },
+            ItemStack {
+                id: copper,
+                amount: 5
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_transfer_load_drops_unknown() {
+    let mut interner = Interner::new();
+    let channel = interner.get_or_intern("test:channel");
+    let iron = interner.get_or_intern("test:iron");
+
+    let path = env::temp_dir().join(format!(
+        "automancy-transfer-unknown-{}.ron",
+        std::process::id()
+    ));
+    fs::write(
+        &path,
+        r#"([("test:channel", [("test:iron", 2), ("test:gone", 4)]), ("test:gone", [("test:iron", 1)])])"#,
+    )
+    .unwrap();
+
+    let mut loaded = TransferQueues::load_from(&path, &interner);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.total(), 2);
+    assert_eq!(
+        loaded.receive(channel, 100),
+        vec![ItemStack {
+            id: iron,
+            amount: 2
+        }]
+    );
+}
+
+#[test]
+fn test_transfer_load_missing() {
+    let interner = Interner::new();
+    let path = env::temp_dir().join("automancy-transfer-missing.ron");
+
+    assert_eq!(TransferQueues::load_from(&path, &interner).total(), 0);
+}
@@ -0,0 +1,245 @@
+use crate::{RoundedRectLerpedColor, ROUNDED_MEDIUM};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use yakui::{
+    util::widget_children,
+    widget::{LayoutContext, PaintContext, Widget},
+    Color, Constraints, Response, Vec2,
+};
+
+/// How long panels take to slide and fade in.
+pub const PANEL_TRANSITION: Duration = Duration::from_millis(160);
+/// How far, in pixels, panels slide from as they come in.
+pub const PANEL_SLIDE: Vec2 = Vec2::new(0.0, 16.0);
+
+static REDUCED_MOTION: AtomicBool = AtomicBool::new(false);
+
+/// Turns every UI animation off, so that they jump straight to their end.
+pub fn set_reduced_motion(reduced: bool) {
+    REDUCED_MOTION.store(reduced, Ordering::Relaxed);
+}
+
+pub fn reduced_motion() -> bool {
+    REDUCED_MOTION.load(Ordering::Relaxed)
+}
+
+/// How an animation speeds up and slows down over its duration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    /// starts fast and slows down toward the end, which suits things coming in
+    #[default]
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    /// Maps how far along the animation is, from 0 to 1, to how far along its value is.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Easing::Linear => t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t.powi(3)
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// Gets how far along an animation that started at `start` is, from 0 to 1, eased. Always 1 with reduced motion.
+pub fn tween_progress(start: Instant, duration: Duration, easing: Easing) -> f32 {
+    if reduced_motion() || duration.is_zero() {
+        return 1.0;
+    }
+
+    easing.apply(start.elapsed().as_secs_f32() / duration.as_secs_f32())
+}
+
+/// A value that moves to its target over time, instead of jumping to it.
+#[derive(Debug, Clone, Copy)]
+pub struct Tween {
+    from: f32,
+    to: f32,
+    start: Instant,
+    pub duration: Duration,
+    pub easing: Easing,
+}
+
+impl Tween {
+    pub fn new(value: f32, duration: Duration, easing: Easing) -> Self {
+        Self {
+            from: value,
+            to: value,
+            start: Instant::now(),
+            duration,
+            easing,
+        }
+    }
+
+    /// Gets the value at this moment.
+    pub fn value(&self) -> f32 {
+        let t = tween_progress(self.start, self.duration, self.easing);
+
+        self.from + (self.to - self.from) * t
+    }
+
+    pub fn target(&self) -> f32 {
+        self.to
+    }
+
+    /// Starts moving to a new target from the current value. Does nothing if it is already the target.
+    pub fn retarget(&mut self, to: f32) {
+        if self.to == to {
+            return;
+        }
+
+        self.from = self.value();
+        self.to = to;
+        self.start = Instant::now();
+    }
+
+    pub fn done(&self) -> bool {
+        self.value() == self.to
+    }
+}
+
+/// How the children of a [Transition] come in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransitionKind {
+    /// the children slide in from this offset, in pixels
+    Slide(Vec2),
+    /// the children fade in from the color, which should be what is behind them
+    Fade(Color),
+    /// both of the above
+    SlideFade(Vec2, Color),
+}
+
+/**
+Animates its children coming in, when it is first shown, and again whenever its key changes. This only moves and
+covers what the children paint, so it doesn't change the layout around it.
+
+Responds with [TransitionResponse].
+*/
+#[derive(Debug, Clone)]
+pub struct Transition {
+    pub kind: TransitionKind,
+    pub duration: Duration,
+    pub easing: Easing,
+    /// changing the key restarts the transition, for when the same spot starts showing something else
+    pub key: u64,
+}
+
+impl Transition {
+    pub fn new(kind: TransitionKind, duration: Duration) -> Self {
+        Self {
+            kind,
+            duration,
+            easing: Easing::default(),
+            key: 0,
+        }
+    }
+
+    #[track_caller]
+    pub fn show<F: FnOnce()>(self, children: F) -> Response<TransitionResponse> {
+        widget_children::<TransitionWidget, F>(children, self)
+    }
+}
+
+#[derive(Debug)]
+pub struct TransitionWidget {
+    props: Transition,
+    start: Option<(u64, Instant)>,
+    /// how far along the transition is this frame
+    t: Cell<f32>,
+}
+
+#[derive(Debug)]
+pub struct TransitionResponse {
+    pub done: bool,
+}
+
+impl Widget for TransitionWidget {
+    type Props<'a> = Transition;
+    type Response = TransitionResponse;
+
+    fn new() -> Self {
+        Self {
+            props: Transition::new(TransitionKind::Slide(Vec2::ZERO), Duration::ZERO),
+            start: None,
+            t: Cell::new(1.0),
+        }
+    }
+
+    fn update(&mut self, props: Self::Props<'_>) -> Self::Response {
+        if self.start.map(|(key, _)| key) != Some(props.key) {
+            self.start = Some((props.key, Instant::now()));
+        }
+
+        let (_, start) = self.start.unwrap();
+        let t = tween_progress(start, props.duration, props.easing);
+
+        self.props = props;
+        self.t.set(t);
+
+        Self::Response { done: t >= 1.0 }
+    }
+
+    fn layout(&self, mut ctx: LayoutContext<'_>, input: Constraints) -> Vec2 {
+        let node = ctx.dom.get_current();
+
+        let offset = match self.props.kind {
+            TransitionKind::Slide(offset) | TransitionKind::SlideFade(offset, _) => {
+                offset * (1.0 - self.t.get())
+            }
+            TransitionKind::Fade(_) => Vec2::ZERO,
+        };
+
+        let mut size = Vec2::ZERO;
+        for &child in &node.children {
+            size = size.max(ctx.calculate_layout(child, input));
+            ctx.layout.set_pos(child, offset);
+        }
+
+        input.constrain_min(size)
+    }
+
+    fn paint(&self, mut ctx: PaintContext<'_>) {
+        let node = ctx.dom.get_current();
+
+        let t = self.t.get();
+
+        for &child in &node.children {
+            ctx.paint(child);
+
+            // the cover follows the child as it slides, with the corners of a window
+            if let TransitionKind::Fade(color) | TransitionKind::SlideFade(_, color) =
+                self.props.kind
+            {
+                if t < 1.0 {
+                    let child_rect = ctx.layout.get(child).unwrap().rect;
+                    let cover = color.with_alpha(color.a as f32 / 255.0 * (1.0 - t));
+
+                    let mut rect = RoundedRectLerpedColor::new(child_rect, ROUNDED_MEDIUM);
+                    rect.color = (cover, cover, cover, cover);
+                    rect.add(ctx.paint);
+                }
+            }
+        }
+    }
+}
+
+#[track_caller]
+pub fn transition(
+    kind: TransitionKind,
+    duration: Duration,
+    children: impl FnOnce(),
+) -> Response<TransitionResponse> {
+    Transition::new(kind, duration).show(children)
+}
@@ -1,11 +1,17 @@
-use crate::{colored_label_text, label_text, symbol_text, ROUNDED_MEDIUM};
+use crate::{colored_label_text, label_text, symbol_text, tween_progress, Easing, ROUNDED_MEDIUM};
 use automancy_defs::colors;
+use std::time::{Duration, Instant};
 use yakui::{
-    opaque,
+    opaque, use_state,
     widgets::{Button, ButtonResponse, DynamicButtonStyle, Pad, Text},
     Color, Response,
 };
 
+/// How long a clicked button takes to come back up.
+const BUTTON_PRESS: Duration = Duration::from_millis(120);
+/// How far, in pixels, a clicked button dips.
+const BUTTON_PRESS_DEPTH: f32 = 2.0;
+
 pub fn button_styled(text: Text, padding: Pad) -> Button {
     let mut button = Button::unstyled(text.text);
 
@@ -73,11 +79,28 @@ pub fn inactive_button(text: &str) -> Response<ButtonResponse> {
 
 #[track_caller]
 pub fn button(text: &str) -> Response<ButtonResponse> {
+    let clicked_at = use_state(|| None::<Instant>);
+
+    // dips the button down when clicked, moving the padding instead of resizing it so the layout around stays put
+    let dip = clicked_at.get().map_or(0.0, |start| {
+        1.0 - tween_progress(start, BUTTON_PRESS, Easing::EaseOut)
+    }) * BUTTON_PRESS_DEPTH;
+
     let mut r = None;
 
-    Pad::all(2.0).show(|| {
+    let mut pad = Pad::all(2.0);
+    pad.top += dip;
+    pad.bottom -= dip;
+
+    pad.show(|| {
         r = Some(button_text(label_text(text)).show());
     });
 
-    r.unwrap()
+    let r = r.unwrap();
+
+    if r.clicked {
+        clicked_at.set(Some(Instant::now()));
+    }
+
+    r
 }
@@ -0,0 +1,495 @@
+use crate::{label, row, PaintQuad, RoundedRectLerpedColor, ROUNDED_MEDIUM};
+use automancy_defs::colors;
+use std::cell::RefCell;
+use yakui::{
+    colored_box,
+    event::{EventInterest, EventResponse, WidgetEvent},
+    paint::PaintDom,
+    util::widget_children,
+    widget::{EventContext, LayoutContext, PaintContext, Widget},
+    Color, Constraints, Rect, Response, Vec2,
+};
+
+/// The colors the series and nodes of charts cycle through.
+pub const CHART_COLORS: [Color; 6] = [
+    colors::INPUT,
+    colors::OUTPUT,
+    colors::RED,
+    colors::LIGHT_BLUE,
+    colors::DARK_GRAY,
+    colors::ORANGE,
+];
+
+const CHART_PADDING: f32 = 6.0;
+const CHART_LINE_WIDTH: f32 = 2.0;
+const CHART_AXIS_WIDTH: f32 = 1.0;
+/// How much one step of the mouse wheel zooms a line chart.
+const CHART_ZOOM_STEP: f32 = 1.25;
+/// The fewest samples a line chart can be zoomed in to.
+const CHART_MIN_SAMPLES: usize = 2;
+
+const BAR_HEIGHT: f32 = 12.0;
+
+const SANKEY_NODE_WIDTH: f32 = 8.0;
+const SANKEY_NODE_GAP: f32 = 8.0;
+const SANKEY_LABEL_OFFSET: f32 = 4.0;
+
+fn chart_background(output: &mut PaintDom, rect: Rect) {
+    let mut background = RoundedRectLerpedColor::new(rect, ROUNDED_MEDIUM);
+    background.color = (
+        colors::BACKGROUND_1,
+        colors::BACKGROUND_1,
+        colors::BACKGROUND_1,
+        colors::BACKGROUND_1,
+    );
+    background.add(output);
+}
+
+/// One line of a [LineChart].
+#[derive(Debug, Clone)]
+pub struct Series {
+    pub color: Color,
+    /// the values, oldest first
+    pub values: Vec<f32>,
+}
+
+/**
+A time-series chart, with a line per series. Scrolling the mouse wheel over it zooms in on the latest samples.
+
+Responds with [LineChartResponse].
+*/
+#[derive(Debug, Clone, Default)]
+pub struct LineChart {
+    pub series: Vec<Series>,
+    pub size: Vec2,
+}
+
+impl LineChart {
+    pub fn new(series: Vec<Series>, size: Vec2) -> Self {
+        Self { series, size }
+    }
+
+    #[track_caller]
+    pub fn show(self) -> Response<LineChartResponse> {
+        yakui::util::widget::<LineChartWidget>(self)
+    }
+
+    fn len(&self) -> usize {
+        self.series
+            .iter()
+            .map(|v| v.values.len())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug)]
+pub struct LineChartWidget {
+    props: LineChart,
+    /// how many of the latest samples are shown. None shows all of them
+    shown: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct LineChartResponse {
+    /// how many of the latest samples are shown
+    pub shown: usize,
+}
+
+impl LineChartWidget {
+    fn shown(&self) -> usize {
+        let len = self.props.len();
+
+        self.shown
+            .map_or(len, |v| v.clamp(CHART_MIN_SAMPLES.min(len), len))
+    }
+}
+
+impl Widget for LineChartWidget {
+    type Props<'a> = LineChart;
+    type Response = LineChartResponse;
+
+    fn new() -> Self {
+        Self {
+            props: LineChart::default(),
+            shown: None,
+        }
+    }
+
+    fn update(&mut self, props: Self::Props<'_>) -> Self::Response {
+        self.props = props;
+
+        Self::Response {
+            shown: self.shown(),
+        }
+    }
+
+    fn layout(&self, _ctx: LayoutContext<'_>, constraints: Constraints) -> Vec2 {
+        constraints.constrain(self.props.size)
+    }
+
+    fn paint(&self, ctx: PaintContext<'_>) {
+        let rect = ctx.layout.get(ctx.dom.current()).unwrap().rect;
+
+        chart_background(ctx.paint, rect);
+
+        let inner = Rect::from_pos_size(
+            rect.pos() + CHART_PADDING,
+            (rect.size() - CHART_PADDING * 2.0).max(Vec2::ZERO),
+        );
+
+        PaintQuad::line(
+            Vec2::new(inner.pos().x, inner.max().y),
+            inner.max(),
+            CHART_AXIS_WIDTH,
+            colors::GRAY,
+        )
+        .add(ctx.paint);
+
+        let shown = self.shown();
+        if shown < 2 {
+            return;
+        }
+
+        let len = self.props.len();
+        let visible = |values: &[f32]| -> Vec<f32> {
+            // series shorter than the longest are aligned to the latest sample
+            let skip = (values.len() + shown).saturating_sub(len);
+
+            values.iter().skip(skip).copied().collect()
+        };
+
+        let max = self
+            .props
+            .series
+            .iter()
+            .flat_map(|v| visible(&v.values))
+            .fold(0.0f32, f32::max)
+            .max(1.0);
+
+        let step = inner.size().x / (shown - 1) as f32;
+
+        for series in &self.props.series {
+            let values = visible(&series.values);
+            let offset = shown - values.len();
+
+            let points = values
+                .iter()
+                .enumerate()
+                .map(|(index, value)| {
+                    Vec2::new(
+                        inner.pos().x + (offset + index) as f32 * step,
+                        inner.max().y - value.max(0.0) / max * inner.size().y,
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            for pair in points.windows(2) {
+                PaintQuad::line(pair[0], pair[1], CHART_LINE_WIDTH, series.color).add(ctx.paint);
+            }
+        }
+    }
+
+    fn event_interest(&self) -> EventInterest {
+        EventInterest::MOUSE_INSIDE
+    }
+
+    fn event(&mut self, _ctx: EventContext<'_>, event: &WidgetEvent) -> EventResponse {
+        match *event {
+            WidgetEvent::MouseScroll { delta, .. } if delta.y.abs() > 0.01 => {
+                let shown = self.shown() as f32;
+
+                let shown = if delta.y > 0.0 {
+                    shown * CHART_ZOOM_STEP
+                } else {
+                    shown / CHART_ZOOM_STEP
+                };
+
+                self.shown = Some(shown.round() as usize);
+
+                EventResponse::Sink
+            }
+            _ => EventResponse::Bubble,
+        }
+    }
+}
+
+/// Draws a time-series chart of the series, zoomed with the mouse wheel.
+#[track_caller]
+pub fn line_chart(series: Vec<Series>, size: Vec2) -> Response<LineChartResponse> {
+    LineChart::new(series, size).show()
+}
+
+/// Draws a bar per entry, as long as its value relative to the largest one, followed by its name and value.
+pub fn bar_chart(entries: &[(String, f32)], width: f32) {
+    let max = entries.iter().map(|v| v.1).fold(0.0f32, f32::max).max(1.0);
+
+    for (index, (name, value)) in entries.iter().enumerate() {
+        row(|| {
+            colored_box(
+                CHART_COLORS[index % CHART_COLORS.len()],
+                Vec2::new((value.max(0.0) / max * width).max(1.0), BAR_HEIGHT),
+            );
+
+            label(&format!("{name}: {value}"));
+        });
+    }
+}
+
+/// A flow between two nodes of a [Sankey] chart.
+#[derive(Debug, Clone, Copy)]
+pub struct SankeyLink {
+    pub from: usize,
+    pub to: usize,
+    pub amount: f32,
+}
+
+/**
+A flow chart, with each node as tall as the most that flows in or out of it, and a band per link as thick as its
+amount. Nodes are put in columns by how many links away from a node with nothing flowing in they are. Its children
+are the labels of the nodes, in the same order.
+
+Responds with [SankeyResponse].
+*/
+#[derive(Debug, Clone, Default)]
+pub struct Sankey {
+    pub node_colors: Vec<Color>,
+    pub links: Vec<SankeyLink>,
+    pub size: Vec2,
+}
+
+impl Sankey {
+    pub fn new(node_colors: Vec<Color>, links: Vec<SankeyLink>, size: Vec2) -> Self {
+        Self {
+            node_colors,
+            links,
+            size,
+        }
+    }
+
+    #[track_caller]
+    pub fn show<F: FnOnce()>(self, children: F) -> Response<SankeyResponse> {
+        widget_children::<SankeyWidget, F>(children, self)
+    }
+
+    /// Gets the column of each node. Links going back to an earlier column, as in loops, are ignored.
+    fn columns(&self) -> Vec<usize> {
+        let count = self.node_colors.len();
+        let mut columns = vec![0; count];
+
+        // a node can be pushed right at most once per other node
+        for _ in 0..count {
+            let mut changed = false;
+
+            for link in &self.links {
+                if link.from < count
+                    && link.to < count
+                    && link.from != link.to
+                    && columns[link.to] <= columns[link.from]
+                    && columns[link.from] + 1 < count
+                {
+                    columns[link.to] = columns[link.from] + 1;
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        columns
+    }
+
+    /// Lays out the nodes and bands in the rect.
+    fn geometry(&self, rect: Rect) -> SankeyGeometry {
+        let count = self.node_colors.len();
+        let columns = self.columns();
+        let column_count = columns.iter().max().map_or(0, |v| v + 1);
+
+        let mut inflow = vec![0.0f32; count];
+        let mut outflow = vec![0.0f32; count];
+        for link in self.links.iter().filter(|v| v.from < count && v.to < count) {
+            outflow[link.from] += link.amount.max(0.0);
+            inflow[link.to] += link.amount.max(0.0);
+        }
+        let value = |node: usize| inflow[node].max(outflow[node]);
+
+        // every column is scaled the same, so that the fullest one fits
+        let mut scale = f32::INFINITY;
+        for column in 0..column_count {
+            let nodes = (0..count).filter(|v| columns[*v] == column);
+            let gaps = nodes.clone().count().saturating_sub(1) as f32 * SANKEY_NODE_GAP;
+            let total = nodes.map(value).sum::<f32>();
+
+            if total > 0.0 {
+                scale = scale.min((rect.size().y - gaps).max(0.0) / total);
+            }
+        }
+        if !scale.is_finite() {
+            scale = 0.0;
+        }
+
+        let column_step = if column_count > 1 {
+            (rect.size().x - SANKEY_NODE_WIDTH) / (column_count - 1) as f32
+        } else {
+            0.0
+        };
+
+        let mut nodes = vec![Rect::ZERO; count];
+        let mut column_y = vec![rect.pos().y; column_count];
+        for (node, &column) in columns.iter().enumerate() {
+            let height = value(node) * scale;
+
+            nodes[node] = Rect::from_pos_size(
+                Vec2::new(rect.pos().x + column as f32 * column_step, column_y[column]),
+                Vec2::new(SANKEY_NODE_WIDTH, height),
+            );
+            column_y[column] += height + SANKEY_NODE_GAP;
+        }
+
+        // how far down each node's side the next band starts
+        let mut out_y = nodes.iter().map(|v| v.pos().y).collect::<Vec<_>>();
+        let mut in_y = out_y.clone();
+
+        let mut bands = Vec::new();
+        for link in self.links.iter().filter(|v| v.from < count && v.to < count) {
+            let thickness = link.amount.max(0.0) * scale;
+            let from = nodes[link.from];
+            let to = nodes[link.to];
+
+            bands.push((
+                link.from,
+                [
+                    Vec2::new(from.max().x, out_y[link.from]),
+                    Vec2::new(from.max().x, out_y[link.from] + thickness),
+                    Vec2::new(to.pos().x, in_y[link.to] + thickness),
+                    Vec2::new(to.pos().x, in_y[link.to]),
+                ],
+            ));
+
+            out_y[link.from] += thickness;
+            in_y[link.to] += thickness;
+        }
+
+        SankeyGeometry { nodes, bands }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SankeyGeometry {
+    nodes: Vec<Rect>,
+    /// the node each band comes from, and its corners
+    bands: Vec<(usize, [Vec2; 4])>,
+}
+
+#[derive(Debug)]
+pub struct SankeyWidget {
+    props: Sankey,
+    /// the layout of the nodes and bands, relative to the chart
+    geometry: RefCell<SankeyGeometry>,
+}
+
+pub type SankeyResponse = ();
+
+impl Widget for SankeyWidget {
+    type Props<'a> = Sankey;
+    type Response = SankeyResponse;
+
+    fn new() -> Self {
+        Self {
+            props: Sankey::default(),
+            geometry: RefCell::default(),
+        }
+    }
+
+    fn update(&mut self, props: Self::Props<'_>) -> Self::Response {
+        self.props = props;
+    }
+
+    fn layout(&self, mut ctx: LayoutContext<'_>, constraints: Constraints) -> Vec2 {
+        let node = ctx.dom.get_current();
+        let size = constraints.constrain(self.props.size);
+
+        // laid out relative to the chart, as the children are positioned relative to it
+        let geometry = self.props.geometry(Rect::from_pos_size(
+            Vec2::splat(CHART_PADDING),
+            (size - CHART_PADDING * 2.0).max(Vec2::ZERO),
+        ));
+
+        for (index, &child) in node.children.iter().enumerate() {
+            let label_size = ctx.calculate_layout(child, Constraints::none());
+
+            let Some(node) = geometry.nodes.get(index) else {
+                continue;
+            };
+
+            // labels go right of their node, unless that would run past the chart
+            let mut pos = Vec2::new(
+                node.max().x + SANKEY_LABEL_OFFSET,
+                node.pos().y + (node.size().y - label_size.y) / 2.0,
+            );
+            if pos.x + label_size.x > size.x {
+                pos.x = node.pos().x - SANKEY_LABEL_OFFSET - label_size.x;
+            }
+
+            ctx.layout.set_pos(child, pos);
+        }
+
+        *self.geometry.borrow_mut() = geometry;
+
+        size
+    }
+
+    fn paint(&self, mut ctx: PaintContext<'_>) {
+        let rect = ctx.layout.get(ctx.dom.current()).unwrap().rect;
+
+        chart_background(ctx.paint, rect);
+
+        let geometry = self.geometry.borrow();
+        let color = |node: usize| {
+            self.props
+                .node_colors
+                .get(node)
+                .copied()
+                .unwrap_or(colors::GRAY)
+        };
+
+        for (from, corners) in &geometry.bands {
+            PaintQuad {
+                corners: corners.map(|v| v + rect.pos()),
+                color: color(*from).with_alpha(0.4),
+            }
+            .add(ctx.paint);
+        }
+
+        for (index, node) in geometry.nodes.iter().enumerate() {
+            PaintQuad {
+                corners: [
+                    node.pos(),
+                    Vec2::new(node.pos().x, node.max().y),
+                    node.max(),
+                    Vec2::new(node.max().x, node.pos().y),
+                ]
+                .map(|v| v + rect.pos()),
+                color: color(index),
+            }
+            .add(ctx.paint);
+        }
+
+        let node = ctx.dom.get_current();
+        for &child in &node.children {
+            ctx.paint(child);
+        }
+    }
+}
+
+/// Draws a flow chart of the links between the nodes, each node given as its name and color.
+#[track_caller]
+pub fn sankey(nodes: &[(String, Color)], links: Vec<SankeyLink>, size: Vec2) {
+    Sankey::new(nodes.iter().map(|v| v.1).collect(), links, size).show(|| {
+        for (name, _) in nodes {
+            label(name);
+        }
+    });
+}
@@ -1,8 +1,9 @@
 use crate::{
-    center_col, col, heading, pad_y, RoundedRectLerpedColor, PADDING_LARGE, PADDING_MEDIUM,
-    ROUNDED_MEDIUM,
+    center_col, col, heading, pad_y, RoundedRectLerpedColor, Transition, TransitionKind,
+    PADDING_LARGE, PADDING_MEDIUM, PANEL_SLIDE, PANEL_TRANSITION, ROUNDED_MEDIUM,
 };
 use automancy_defs::colors;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use yakui::geometry::{Color, Constraints, Vec2};
 use yakui::widget::{LayoutContext, PaintContext, Widget};
 use yakui::Response;
@@ -129,15 +130,27 @@ pub fn group(children: impl FnOnce()) {
     });
 }
 
+/// Draws a window with a title. It slides and fades in when it is first shown, or when its title changes.
 pub fn window_box(title: String, children: impl FnOnce()) {
-    RoundRect::new(ROUNDED_MEDIUM, colors::BACKGROUND_1).show_children(|| {
-        Pad::all(PADDING_LARGE).show(|| {
-            center_col(|| {
-                pad_y(0.0, PADDING_MEDIUM).show(|| {
-                    heading(&title);
+    let mut hasher = DefaultHasher::new();
+    title.hash(&mut hasher);
+
+    let mut transition = Transition::new(
+        TransitionKind::SlideFade(PANEL_SLIDE, colors::BACKGROUND_1),
+        PANEL_TRANSITION,
+    );
+    transition.key = hasher.finish();
+
+    transition.show(|| {
+        RoundRect::new(ROUNDED_MEDIUM, colors::BACKGROUND_1).show_children(|| {
+            Pad::all(PADDING_LARGE).show(|| {
+                center_col(|| {
+                    pad_y(0.0, PADDING_MEDIUM).show(|| {
+                        heading(&title);
+                    });
+
+                    children();
                 });
-
-                children();
             });
         });
     });
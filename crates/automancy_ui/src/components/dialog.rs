@@ -0,0 +1,95 @@
+use crate::{button, label, progress_bar, row, row_max, textbox, window};
+use yakui::{spacer, Vec2};
+
+const PROGRESS_DIALOG_WIDTH: f32 = 240.0;
+const PROGRESS_DIALOG_HEIGHT: f32 = 8.0;
+
+/// How the player answered a dialog this frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogResponse {
+    None,
+    Confirm,
+    Cancel,
+}
+
+fn dialog_buttons(confirm: &str, cancel: Option<&str>) -> DialogResponse {
+    let mut response = DialogResponse::None;
+
+    row_max(|| {
+        spacer(1);
+
+        if let Some(cancel) = cancel {
+            if button(cancel).clicked {
+                response = DialogResponse::Cancel;
+            }
+        }
+
+        if button(confirm).clicked {
+            response = DialogResponse::Confirm;
+        }
+    });
+
+    response
+}
+
+/// Draws a dialog with a message, and returns true once it is acknowledged.
+pub fn message_dialog(title: String, message: &str, confirm: &str) -> bool {
+    let mut response = DialogResponse::None;
+
+    window(title, || {
+        label(message);
+
+        response = dialog_buttons(confirm, None);
+    });
+
+    response == DialogResponse::Confirm
+}
+
+/// Draws a dialog asking the player to confirm or cancel.
+pub fn confirm_dialog(title: String, message: &str, confirm: &str, cancel: &str) -> DialogResponse {
+    let mut response = DialogResponse::None;
+
+    window(title, || {
+        label(message);
+
+        response = dialog_buttons(confirm, Some(cancel));
+    });
+
+    response
+}
+
+/// Draws a dialog asking the player for a line of text, edited in place.
+pub fn text_input_dialog(
+    title: String,
+    text_label: &str,
+    text: &mut String,
+    placeholder: Option<&str>,
+    confirm: &str,
+    cancel: &str,
+) -> DialogResponse {
+    let mut response = DialogResponse::None;
+
+    window(title, || {
+        row(|| {
+            label(text_label);
+
+            textbox(text, None, placeholder);
+        });
+
+        response = dialog_buttons(confirm, Some(cancel));
+    });
+
+    response
+}
+
+/// Draws a dialog showing the progress of a task. It has no buttons, and should be closed by the task itself.
+pub fn progress_dialog(title: String, message: &str, fraction: f32) {
+    window(title, || {
+        label(message);
+
+        progress_bar(
+            fraction,
+            Vec2::new(PROGRESS_DIALOG_WIDTH, PROGRESS_DIALOG_HEIGHT),
+        );
+    });
+}
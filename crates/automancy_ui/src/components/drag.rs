@@ -0,0 +1,295 @@
+use crate::hover_tip;
+use std::any::Any;
+use std::cell::RefCell;
+use yakui::{
+    event::{EventInterest, EventResponse, WidgetEvent},
+    input::MouseButton,
+    util::widget_children,
+    widget::{EventContext, Widget},
+    widgets::Layer,
+    Response, Vec2,
+};
+
+/// How far the mouse has to move while held before a press turns into a drag, so that clicking still works.
+pub const DRAG_THRESHOLD: f32 = 4.0;
+
+/// What is being dragged.
+#[derive(Default)]
+struct DragState {
+    payload: Option<Box<dyn Any>>,
+    /// whether the source was drawn this frame. A drag whose source is gone is dropped
+    alive: bool,
+    /// whether the mouse was let go, so the drag ends this frame unless a drop target takes it
+    released: bool,
+}
+
+thread_local! {
+    static DRAG: RefCell<DragState> = RefCell::default();
+}
+
+#[derive(Debug, Default)]
+pub struct DragSource {}
+
+impl DragSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[track_caller]
+    pub fn show<F: FnOnce()>(self, children: F) -> Response<DragSourceResponse> {
+        widget_children::<DragSourceWidget, F>(children, self)
+    }
+}
+
+#[derive(Debug)]
+pub struct DragSourceWidget {
+    props: DragSource,
+    hovering: bool,
+    /// where the mouse was pressed, until it is let go
+    pressed_at: Option<Vec2>,
+    dragging: bool,
+    started: bool,
+    released: bool,
+    clicked: bool,
+}
+
+#[derive(Debug)]
+pub struct DragSourceResponse {
+    pub hovering: bool,
+    /// whether it was pressed and let go without being dragged
+    pub clicked: bool,
+    pub dragging: bool,
+    started: bool,
+    released: bool,
+}
+
+impl Widget for DragSourceWidget {
+    type Props<'a> = DragSource;
+    type Response = DragSourceResponse;
+
+    fn new() -> Self {
+        Self {
+            props: DragSource::new(),
+            hovering: false,
+            pressed_at: None,
+            dragging: false,
+            started: false,
+            released: false,
+            clicked: false,
+        }
+    }
+
+    fn update(&mut self, props: Self::Props<'_>) -> Self::Response {
+        self.props = props;
+
+        Self::Response {
+            hovering: self.hovering,
+            clicked: std::mem::take(&mut self.clicked),
+            dragging: self.dragging,
+            started: std::mem::take(&mut self.started),
+            released: std::mem::take(&mut self.released),
+        }
+    }
+
+    fn event_interest(&self) -> EventInterest {
+        EventInterest::MOUSE_ALL
+    }
+
+    fn event(&mut self, _ctx: EventContext<'_>, event: &WidgetEvent) -> EventResponse {
+        match *event {
+            WidgetEvent::MouseEnter => {
+                self.hovering = true;
+                EventResponse::Bubble
+            }
+            WidgetEvent::MouseLeave => {
+                self.hovering = false;
+                EventResponse::Bubble
+            }
+            WidgetEvent::MouseMoved(Some(position)) => {
+                if let Some(pressed_at) = self.pressed_at {
+                    if !self.dragging && pressed_at.distance(position) >= DRAG_THRESHOLD {
+                        self.dragging = true;
+                        self.started = true;
+                    }
+                }
+
+                EventResponse::Bubble
+            }
+            WidgetEvent::MouseButtonChanged {
+                button: MouseButton::One,
+                down,
+                inside,
+                position,
+                ..
+            } => {
+                if down {
+                    if inside {
+                        self.pressed_at = Some(position);
+
+                        return EventResponse::Sink;
+                    }
+                } else if self.pressed_at.take().is_some() {
+                    if self.dragging {
+                        self.dragging = false;
+                        self.released = true;
+                    } else if inside {
+                        self.clicked = true;
+                    }
+                }
+
+                // the release has to reach the drop target under the mouse
+                EventResponse::Bubble
+            }
+            _ => EventResponse::Bubble,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DropTarget {}
+
+impl DropTarget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[track_caller]
+    pub fn show<F: FnOnce()>(self, children: F) -> Response<DropTargetResponse> {
+        widget_children::<DropTargetWidget, F>(children, self)
+    }
+}
+
+#[derive(Debug)]
+pub struct DropTargetWidget {
+    props: DropTarget,
+    hovering: bool,
+    released: bool,
+}
+
+#[derive(Debug)]
+pub struct DropTargetResponse {
+    pub hovering: bool,
+    released: bool,
+}
+
+impl Widget for DropTargetWidget {
+    type Props<'a> = DropTarget;
+    type Response = DropTargetResponse;
+
+    fn new() -> Self {
+        Self {
+            props: DropTarget::new(),
+            hovering: false,
+            released: false,
+        }
+    }
+
+    fn update(&mut self, props: Self::Props<'_>) -> Self::Response {
+        self.props = props;
+
+        Self::Response {
+            hovering: self.hovering,
+            released: std::mem::take(&mut self.released),
+        }
+    }
+
+    fn event_interest(&self) -> EventInterest {
+        EventInterest::MOUSE_INSIDE | EventInterest::MOUSE_OUTSIDE
+    }
+
+    fn event(&mut self, _ctx: EventContext<'_>, event: &WidgetEvent) -> EventResponse {
+        match *event {
+            WidgetEvent::MouseEnter => {
+                self.hovering = true;
+            }
+            WidgetEvent::MouseLeave => {
+                self.hovering = false;
+            }
+            WidgetEvent::MouseButtonChanged {
+                button: MouseButton::One,
+                down: false,
+                inside: true,
+                ..
+            } => {
+                self.released = true;
+            }
+            _ => {}
+        }
+
+        EventResponse::Bubble
+    }
+}
+
+/// Draws the children as something that can be dragged, carrying the payload. It still reports clicks, as long as the
+/// mouse didn't move far while held.
+#[track_caller]
+pub fn drag_source<T: 'static>(
+    payload: T,
+    children: impl FnOnce(),
+) -> Response<DragSourceResponse> {
+    let response = DragSource::new().show(children);
+
+    DRAG.with_borrow_mut(|drag| {
+        if response.started {
+            drag.payload = Some(Box::new(payload));
+        }
+
+        if response.dragging || response.released {
+            drag.alive = true;
+        }
+
+        if response.released {
+            drag.released = true;
+        }
+    });
+
+    response
+}
+
+/// Draws the children as a place things can be dropped on. Returns the payload of the drag that was let go over it,
+/// if it is of the expected type.
+#[track_caller]
+pub fn drop_target<T: 'static>(children: impl FnOnce()) -> Option<T> {
+    let response = DropTarget::new().show(children);
+
+    if !response.released {
+        return None;
+    }
+
+    DRAG.with_borrow_mut(|drag| {
+        if !drag.payload.as_ref().is_some_and(|v| v.is::<T>()) {
+            return None;
+        }
+
+        drag.payload
+            .take()
+            .and_then(|v| v.downcast::<T>().ok())
+            .map(|v| *v)
+    })
+}
+
+/// Gets the payload being dragged, if it is of the type.
+pub fn dragged<T: Clone + 'static>() -> Option<T> {
+    DRAG.with_borrow(|drag| drag.payload.as_ref()?.downcast_ref::<T>().cloned())
+}
+
+/// Draws the children next to the mouse, as the ghost of what is being dragged. Draws nothing if there is no drag.
+pub fn drag_ghost(children: impl FnOnce()) {
+    if DRAG.with_borrow(|drag| drag.payload.is_some()) {
+        Layer::new().show(|| {
+            hover_tip(children);
+        });
+    }
+}
+
+/// Ends the drag if it was let go, or its source is no longer drawn. Call once per frame, after the GUI is drawn.
+pub fn finish_drag() {
+    DRAG.with_borrow_mut(|drag| {
+        if drag.released || !drag.alive {
+            drag.payload = None;
+            drag.released = false;
+        }
+
+        drag.alive = false;
+    });
+}
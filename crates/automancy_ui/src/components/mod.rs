@@ -13,15 +13,21 @@ pub const PADDING_MEDIUM: f32 = 8.0;
 pub const PADDING_LARGE: f32 = 12.0;
 pub const PADDING_XLARGE: f32 = 16.0;
 
+mod animation;
 mod button;
+mod chart;
 mod checkbox;
 mod container;
+mod dialog;
+mod drag;
 mod game_object;
 mod hover;
 mod interactive;
 mod layout;
 mod movable;
 mod position;
+mod progress;
+mod radial;
 mod scrollable;
 mod select;
 mod shapes;
@@ -29,17 +35,25 @@ mod slider;
 mod text;
 mod textbox;
 mod tip;
+mod toast;
 mod util;
+mod virtual_list;
 
+pub use self::animation::*;
 pub use self::button::*;
+pub use self::chart::*;
 pub use self::checkbox::*;
 pub use self::container::*;
+pub use self::dialog::*;
+pub use self::drag::*;
 pub use self::game_object::*;
 pub use self::hover::*;
 pub use self::interactive::*;
 pub use self::layout::*;
 pub use self::movable::*;
 pub use self::position::*;
+pub use self::progress::*;
+pub use self::radial::*;
 pub use self::scrollable::*;
 pub use self::select::*;
 pub use self::shapes::*;
@@ -47,4 +61,6 @@ pub use self::slider::*;
 pub use self::text::*;
 pub use self::textbox::*;
 pub use self::tip::*;
+pub use self::toast::*;
 pub use self::util::*;
+pub use self::virtual_list::*;
@@ -0,0 +1,18 @@
+use crate::RoundRect;
+use automancy_defs::colors;
+use yakui::Vec2;
+
+/// Draws a bar of the given size, filled from the left up to `fraction` (clamped to 0..1).
+pub fn progress_bar(fraction: f32, size: Vec2) {
+    let radius = size.y / 2.0;
+
+    let mut background = RoundRect::new(radius, colors::BACKGROUND_3);
+    background.min_size = size;
+
+    background.show_children(|| {
+        let mut fill = RoundRect::new(radius, colors::ORANGE);
+        fill.min_size = Vec2::new(size.x * fraction.clamp(0.0, 1.0), size.y);
+
+        fill.show();
+    });
+}
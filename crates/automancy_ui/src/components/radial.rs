@@ -0,0 +1,75 @@
+use crate::{label, RoundRect, PADDING_MEDIUM, ROUNDED_MEDIUM};
+use automancy_defs::colors;
+use std::f32::consts::{FRAC_PI_2, TAU};
+use yakui::{
+    colored_circle,
+    widgets::{Absolute, Layer, Pad},
+    Alignment, Dim2, Pivot, Vec2,
+};
+
+/// How far from the center of a radial menu its entries are, in UI units.
+pub const RADIAL_RADIUS: f32 = 80.0;
+/// How far the mouse has to move from the center of a radial menu before an entry is picked.
+pub const RADIAL_DEAD_ZONE: f32 = 16.0;
+
+/// Gets the direction of an entry of a radial menu. The first entry is at the top, and the rest go clockwise.
+fn radial_direction(index: usize, count: usize) -> Vec2 {
+    let angle = index as f32 / count as f32 * TAU - FRAC_PI_2;
+
+    Vec2::new(angle.cos(), angle.sin())
+}
+
+/// Gets the entry of a radial menu the mouse points toward, or None if it is still near the center.
+pub fn radial_pick(center: Vec2, mouse: Vec2, count: usize) -> Option<usize> {
+    let offset = mouse - center;
+
+    if count == 0 || offset.length() < RADIAL_DEAD_ZONE {
+        return None;
+    }
+
+    let angle = (offset.y.atan2(offset.x) + FRAC_PI_2).rem_euclid(TAU);
+    let index = (angle / TAU * count as f32).round() as usize;
+
+    Some(index % count)
+}
+
+/// Draws a radial menu around the center, with the entry the mouse points toward highlighted, and returns it.
+pub fn radial_menu(center: Vec2, mouse: Vec2, entries: &[String]) -> Option<usize> {
+    let picked = radial_pick(center, mouse, entries.len());
+
+    Layer::new().show(|| {
+        Absolute::new(
+            Alignment::TOP_LEFT,
+            Pivot::CENTER,
+            Dim2::pixels(center.x, center.y),
+        )
+        .show(|| {
+            colored_circle(colors::BACKGROUND_3, RADIAL_DEAD_ZONE);
+        });
+
+        for (index, entry) in entries.iter().enumerate() {
+            let pos = center + radial_direction(index, entries.len()) * RADIAL_RADIUS;
+
+            Absolute::new(
+                Alignment::TOP_LEFT,
+                Pivot::CENTER,
+                Dim2::pixels(pos.x, pos.y),
+            )
+            .show(|| {
+                let color = if picked == Some(index) {
+                    colors::BACKGROUND_3
+                } else {
+                    colors::BACKGROUND_1
+                };
+
+                RoundRect::new(ROUNDED_MEDIUM, color).show_children(|| {
+                    Pad::all(PADDING_MEDIUM).show(|| {
+                        label(entry);
+                    });
+                });
+            });
+        }
+    });
+
+    picked
+}
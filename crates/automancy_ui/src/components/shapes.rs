@@ -205,3 +205,29 @@ impl RoundedRectLerpedColor {
         output.add_mesh(mesh);
     }
 }
+
+/// A filled four-sided shape, with its corners in order around it.
+pub struct PaintQuad {
+    pub corners: [Vec2; 4],
+    pub color: Color,
+}
+
+impl PaintQuad {
+    /// Creates a quad covering a line from one point to another.
+    pub fn line(from: Vec2, to: Vec2, width: f32, color: Color) -> Self {
+        let normal = (to - from).normalize_or_zero().perp() * (width / 2.0);
+
+        Self {
+            corners: [from + normal, from - normal, to - normal, to + normal],
+            color,
+        }
+    }
+
+    pub fn add(&self, output: &mut PaintDom) {
+        let color = self.color.to_linear();
+
+        let vertices = self.corners.map(|pos| Vertex::new(pos, Vec2::ZERO, color));
+
+        output.add_mesh(PaintMesh::new(vertices, RECT_INDEX));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{
+    button, col, colored_label, group, label, pad_y, row_max, PADDING_LARGE, PADDING_SMALL,
+};
+use automancy_defs::colors;
+use std::time::{Duration, Instant};
+use yakui::widgets::{Absolute, Layer, Pad};
+use yakui::{spacer, Alignment, Dim2, Pivot};
+
+/// The most toasts shown at once. The rest wait in the queue until there is room.
+pub const MAX_VISIBLE_TOASTS: usize = 4;
+
+/// How urgent a toast is. Higher priority toasts are shown first, above the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ToastPriority {
+    Low,
+    Normal,
+    High,
+}
+
+/// A short notification, shown in the corner of the screen until it times out or is dismissed.
+#[derive(Debug, Clone)]
+pub struct Toast<A> {
+    pub message: String,
+    pub priority: ToastPriority,
+    /// how long the toast stays once shown. None keeps it until it is dismissed
+    pub timeout: Option<Duration>,
+    /// the label of the action button, and the action returned when it is clicked
+    pub action: Option<(String, A)>,
+    /// when the toast was first shown, as waiting in the queue doesn't count toward its timeout
+    shown: Option<Instant>,
+}
+
+impl<A> Toast<A> {
+    pub fn new(message: impl Into<String>, priority: ToastPriority) -> Self {
+        Self {
+            message: message.into(),
+            priority,
+            timeout: None,
+            action: None,
+            shown: None,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_action(mut self, label: impl Into<String>, action: A) -> Self {
+        self.action = Some((label.into(), action));
+        self
+    }
+
+    fn expired(&self) -> bool {
+        self.shown
+            .zip(self.timeout)
+            .is_some_and(|(shown, timeout)| shown.elapsed() >= timeout)
+    }
+}
+
+/// The toasts waiting to be shown, highest priority first, then oldest first.
+#[derive(Debug, Clone)]
+pub struct ToastQueue<A> {
+    toasts: Vec<Toast<A>>,
+}
+
+impl<A> Default for ToastQueue<A> {
+    fn default() -> Self {
+        Self { toasts: Vec::new() }
+    }
+}
+
+impl<A> ToastQueue<A> {
+    /// Adds a toast after the others of the same or higher priority.
+    pub fn push(&mut self, toast: Toast<A>) {
+        let index = self
+            .toasts
+            .iter()
+            .position(|v| v.priority < toast.priority)
+            .unwrap_or(self.toasts.len());
+
+        self.toasts.insert(index, toast);
+    }
+
+    pub fn len(&self) -> usize {
+        self.toasts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.toasts.is_empty()
+    }
+
+    /// Removes the toasts that don't match the predicate, shown or not.
+    pub fn retain(&mut self, f: impl FnMut(&Toast<A>) -> bool) {
+        self.toasts.retain(f);
+    }
+}
+
+/// Draws the first toasts of the queue in the top right corner, and removes the ones that timed out or were
+/// dismissed. Returns the action of the toast whose action button was clicked, which dismisses it too.
+pub fn toasts<A: Clone>(queue: &mut ToastQueue<A>, dismiss: &str) -> Option<A> {
+    queue.toasts.retain(|v| !v.expired());
+
+    if queue.is_empty() {
+        return None;
+    }
+
+    let mut action = None;
+    let mut dismissed = None;
+
+    Absolute::new(Alignment::TOP_RIGHT, Pivot::TOP_RIGHT, Dim2::ZERO).show(|| {
+        Layer::new().show(|| {
+            Pad::all(PADDING_LARGE).show(|| {
+                col(|| {
+                    for (index, toast) in
+                        queue.toasts.iter_mut().take(MAX_VISIBLE_TOASTS).enumerate()
+                    {
+                        toast.shown.get_or_insert_with(Instant::now);
+
+                        pad_y(0.0, PADDING_SMALL).show(|| {
+                            group(|| {
+                                if toast.priority == ToastPriority::High {
+                                    colored_label(&toast.message, colors::RED);
+                                } else {
+                                    label(&toast.message);
+                                }
+
+                                row_max(|| {
+                                    spacer(1);
+
+                                    if let Some((text, value)) = &toast.action {
+                                        if button(text).clicked {
+                                            action = Some(value.clone());
+                                            dismissed = Some(index);
+                                        }
+                                    }
+
+                                    if button(dismiss).clicked {
+                                        dismissed = Some(index);
+                                    }
+                                });
+                            });
+                        });
+                    }
+                });
+            });
+        });
+    });
+
+    if let Some(index) = dismissed {
+        queue.toasts.remove(index);
+    }
+
+    action
+}
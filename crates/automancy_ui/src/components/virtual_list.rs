@@ -0,0 +1,163 @@
+use std::cell::Cell;
+use std::ops::Range;
+use yakui::{
+    use_state,
+    util::widget_children,
+    widget::{LayoutContext, PaintContext, Widget},
+    Constraints, Response, Vec2,
+};
+
+/// How many rows a virtual list draws before it knows how tall they are.
+const VIRTUAL_LIST_FIRST_ROWS: usize = 16;
+/// How many rows past each edge of the view are drawn too, as the rows drawn lag a frame behind the scrolling.
+const VIRTUAL_LIST_OVERSCAN: usize = 2;
+
+/**
+A column of rows of the same height, of which only the ones in view are drawn. It is meant to be put in a scrollable,
+which it reports its full height to, and uses the clip of the scrollable to tell which rows are in view.
+
+Responds with [VirtualListResponse].
+*/
+#[derive(Debug, Clone, Default)]
+pub struct VirtualList {
+    /// how many rows there are in total
+    pub count: usize,
+    /// the index of the first of the rows drawn as children
+    pub first: usize,
+}
+
+impl VirtualList {
+    pub fn new(count: usize, first: usize) -> Self {
+        Self { count, first }
+    }
+
+    #[track_caller]
+    pub fn show<F: FnOnce()>(self, children: F) -> Response<VirtualListResponse> {
+        widget_children::<VirtualListWidget, F>(children, self)
+    }
+}
+
+#[derive(Debug)]
+pub struct VirtualListWidget {
+    props: VirtualList,
+    /// the height of the tallest row laid out so far, which every row is spaced by
+    row_height: Cell<f32>,
+    /// the rows in view as of the last paint
+    visible: Cell<Option<(usize, usize)>>,
+}
+
+#[derive(Debug)]
+pub struct VirtualListResponse {
+    /// the rows to draw next frame
+    pub visible: Range<usize>,
+}
+
+impl Widget for VirtualListWidget {
+    type Props<'a> = VirtualList;
+    type Response = VirtualListResponse;
+
+    fn new() -> Self {
+        Self {
+            props: VirtualList::default(),
+            row_height: Cell::new(0.0),
+            visible: Cell::new(None),
+        }
+    }
+
+    fn update(&mut self, props: Self::Props<'_>) -> Self::Response {
+        self.props = props;
+
+        let count = self.props.count;
+        let (start, end) = self.visible.get().unwrap_or((0, VIRTUAL_LIST_FIRST_ROWS));
+
+        Self::Response {
+            visible: start.min(count)..end.min(count),
+        }
+    }
+
+    fn layout(&self, mut ctx: LayoutContext<'_>, constraints: Constraints) -> Vec2 {
+        let node = ctx.dom.get_current();
+
+        let row_constraints = Constraints {
+            min: Vec2::new(constraints.min.x, 0.0),
+            max: Vec2::new(constraints.max.x, f32::INFINITY),
+        };
+
+        let mut width: f32 = 0.0;
+        let mut row_height = self.row_height.get();
+
+        for &child in &node.children {
+            let size = ctx.calculate_layout(child, row_constraints);
+
+            width = width.max(size.x);
+            row_height = row_height.max(size.y);
+        }
+
+        self.row_height.set(row_height);
+
+        for (index, &child) in node.children.iter().enumerate() {
+            let row = self.props.first + index;
+
+            ctx.layout
+                .set_pos(child, Vec2::new(0.0, row as f32 * row_height));
+        }
+
+        constraints.constrain(Vec2::new(width, self.props.count as f32 * row_height))
+    }
+
+    fn paint(&self, mut ctx: PaintContext<'_>) {
+        let node = ctx.dom.get_current();
+
+        for &child in &node.children {
+            ctx.paint(child);
+        }
+
+        let row_height = self.row_height.get();
+        if row_height <= 0.0 {
+            return;
+        }
+
+        let Some(rect) = ctx.layout.get(ctx.dom.current()).map(|v| v.rect) else {
+            return;
+        };
+
+        // the clip is in physical pixels, while the layout is scaled
+        let Some(clip) = ctx.paint.get_current_clip() else {
+            self.visible.set(Some((0, self.props.count)));
+            return;
+        };
+        let scale_factor = ctx.layout.scale_factor();
+
+        let top = (clip.pos().y / scale_factor - rect.pos().y).max(0.0);
+        let bottom = top + clip.size().y / scale_factor;
+
+        let start = ((top / row_height).floor() as usize).saturating_sub(VIRTUAL_LIST_OVERSCAN);
+        let end = (bottom / row_height).ceil() as usize + VIRTUAL_LIST_OVERSCAN;
+
+        self.visible.set(Some((start, end)));
+    }
+}
+
+/// Draws `count` rows of the same height, only calling `draw_row` for the rows in view. Put it in a vertical scroll,
+/// so that lists with thousands of entries stay cheap to draw.
+#[track_caller]
+pub fn virtual_list(
+    count: usize,
+    mut draw_row: impl FnMut(usize),
+) -> Response<VirtualListResponse> {
+    let visible = use_state(|| (0, VIRTUAL_LIST_FIRST_ROWS));
+
+    let (start, end) = visible.get();
+    let start = start.min(count);
+    let end = end.min(count);
+
+    let res = VirtualList::new(count, start).show(|| {
+        for index in start..end {
+            draw_row(index);
+        }
+    });
+
+    visible.set((res.visible.start, res.visible.end));
+
+    res
+}
@@ -0,0 +1,37 @@
+// The background drawn behind the game when the resource packs don't provide their own "background" shader.
+// A pack's background shader gets the same uniform, and is drawn as one triangle covering the screen.
+
+struct Uniform {
+    top_color: vec4<f32>,
+    bottom_color: vec4<f32>,
+    // 0 and 1 are midday, 0.5 is midnight
+    time_of_day: f32,
+    time: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> ubo: Uniform;
+
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var out: VertexOutput;
+    out.pos = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(uv.x, 1.0 - uv.y);
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let daylight = 0.7 + 0.3 * cos(ubo.time_of_day * 6.28318530718);
+    let color = mix(ubo.top_color.rgb, ubo.bottom_color.rgb, in.uv.y);
+
+    return vec4<f32>(color * daylight, 1.0);
+}
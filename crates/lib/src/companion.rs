@@ -0,0 +1,289 @@
+//! A local HTTP endpoint for companion tools, like stream overlays, enabled with the `companion` feature.
+//!
+//! It only listens on the loopback interface. The queries are read-only, and the only commands are taking a screenshot and saving the map.
+//! Requests from web pages are refused: the `Host` header has to name the loopback address the endpoint listens on,
+//! which keeps DNS rebinding out, and requests that carry an `Origin` header, which browsers add, are refused too.
+//!
+//! - `GET /stats`: the tick rate, and the production and tile counts of the map
+//! - `GET /map`: the name, save time and beacons of the loaded map
+//! - `GET /alerts`: the recent errors, oldest first
+//! - `POST /screenshot`: takes a screenshot on the next frame
+//! - `POST /save`: saves the loaded map
+
+use automancy_defs::log;
+use automancy_resources::error::{err_history, error_to_key, ErrorSeverity};
+use automancy_resources::ResourceManager;
+use automancy_system::game::GameSystemMessage;
+use automancy_system::map::LoadMapOption;
+use ractor::rpc::CallResult;
+use ractor::ActorRef;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Runtime;
+
+/// The address listened on, unless `AUTOMANCY_COMPANION_ADDR` overrides it.
+pub const DEFAULT_COMPANION_ADDR: &str = "127.0.0.1:7878";
+/// The largest request accepted. Requests have no bodies, so this only needs to fit the headers.
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+/// How long a request may take to arrive, so that idle connections can't pile up.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+static SCREENSHOT_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Returns true if a companion asked for a screenshot since the last call, otherwise false.
+pub fn take_screenshot_request() -> bool {
+    SCREENSHOT_REQUESTED.swap(false, Ordering::Relaxed)
+}
+
+/// Starts the endpoint on the runtime. Addresses that aren't on the loopback interface are refused.
+pub fn spawn(
+    tokio: &Runtime,
+    game: ActorRef<GameSystemMessage>,
+    resource_man: Arc<ResourceManager>,
+) {
+    let addr =
+        std::env::var("AUTOMANCY_COMPANION_ADDR").unwrap_or(DEFAULT_COMPANION_ADDR.to_string());
+
+    let addr = match addr.parse::<SocketAddr>() {
+        Ok(addr) if addr.ip().is_loopback() => addr,
+        Ok(_) => {
+            log::error!("Refusing to start the companion endpoint on {addr}, which isn't a loopback address");
+            return;
+        }
+        Err(err) => {
+            log::error!("Invalid companion endpoint address {addr}: {err}");
+            return;
+        }
+    };
+
+    tokio.spawn(async move {
+        let listener = match TcpListener::bind(addr).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                log::error!("Could not start the companion endpoint on {addr}: {err}");
+                return;
+            }
+        };
+
+        log::info!("Companion endpoint listening on {addr}");
+
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                continue;
+            };
+
+            let game = game.clone();
+            let resource_man = resource_man.clone();
+
+            tokio::spawn(async move {
+                if let Err(err) = handle_connection(stream, addr, &game, &resource_man).await {
+                    log::debug!("Companion request failed: {err}");
+                }
+            });
+        }
+    });
+}
+
+/// Checks that the request was sent by a local tool, and not by a web page open in the player's browser.
+fn is_local_request<'a>(addr: SocketAddr, headers: impl Iterator<Item = &'a str>) -> bool {
+    let port = addr.port();
+    let allowed_hosts = [
+        addr.to_string(),
+        format!("localhost:{port}"),
+        format!("127.0.0.1:{port}"),
+        format!("[::1]:{port}"),
+    ];
+
+    let mut host_allowed = false;
+
+    for (name, value) in headers.filter_map(|v| v.split_once(':')) {
+        let value = value.trim();
+
+        if name.eq_ignore_ascii_case("origin") {
+            return false;
+        }
+
+        if name.eq_ignore_ascii_case("host") {
+            host_allowed = allowed_hosts.iter().any(|v| v.eq_ignore_ascii_case(value));
+
+            if !host_allowed {
+                return false;
+            }
+        }
+    }
+
+    host_allowed
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    addr: SocketAddr,
+    game: &ActorRef<GameSystemMessage>,
+    resource_man: &ResourceManager,
+) -> anyhow::Result<()> {
+    let mut buf = Vec::new();
+    let mut chunk = [0; 1024];
+
+    tokio::time::timeout(REQUEST_TIMEOUT, async {
+        while !buf.windows(4).any(|v| v == b"\r\n\r\n") && buf.len() < MAX_REQUEST_SIZE {
+            let read = stream.read(&mut chunk).await?;
+            if read == 0 {
+                break;
+            }
+
+            buf.extend_from_slice(&chunk[..read]);
+        }
+
+        anyhow::Ok(())
+    })
+    .await??;
+
+    let request = String::from_utf8_lossy(&buf);
+    let mut lines = request.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line.next().unwrap_or_default();
+
+    let local = is_local_request(addr, lines.take_while(|v| !v.is_empty()));
+
+    let (status, body) = match (method, path) {
+        _ if !local => (403, json!({ "error": "forbidden" })),
+        ("GET", "/stats") => (200, stats(game, resource_man).await),
+        ("GET", "/map") => (200, map(game).await),
+        ("GET", "/alerts") => (200, alerts(resource_man)),
+        ("POST", "/screenshot") => {
+            SCREENSHOT_REQUESTED.store(true, Ordering::Relaxed);
+
+            (200, json!({ "ok": true }))
+        }
+        ("POST", "/save") => (200, save(game).await),
+        ("GET" | "POST", _) => (404, json!({ "error": "not found" })),
+        _ => (405, json!({ "error": "method not allowed" })),
+    };
+
+    let body = body.to_string();
+    let reason = match status {
+        200 => "OK",
+        403 => "Forbidden",
+        404 => "Not Found",
+        _ => "Method Not Allowed",
+    };
+
+    stream
+        .write_all(
+            format!(
+                "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .as_bytes(),
+        )
+        .await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
+
+async fn stats(game: &ActorRef<GameSystemMessage>, resource_man: &ResourceManager) -> Value {
+    let mut value = json!({});
+
+    if let Ok(CallResult::Success(tick)) = game.call(GameSystemMessage::GetTickStats, None).await {
+        value["tick"] = json!({
+            "tps": tick.tps,
+            "target_tps": tick.target_tps,
+            "last_tick_ms": tick.last_tick_time.as_secs_f64() * 1000.0,
+            "behind": tick.behind,
+            "overloaded": tick.overloaded,
+            "sleeping": tick.sleeping,
+        });
+    }
+
+    if let Ok(CallResult::Success(statistics)) =
+        game.call(GameSystemMessage::GetStatistics, None).await
+    {
+        // the last sample is still running, so report the last finished one if there is one
+        let sample = statistics.production.len().saturating_sub(2);
+        let id_str = |id| resource_man.interner.resolve(id).unwrap_or_default();
+
+        value["production_per_minute"] = statistics
+            .items()
+            .into_iter()
+            .map(|item| {
+                (
+                    id_str(item).to_string(),
+                    json!(statistics.history(item)[sample]),
+                )
+            })
+            .collect();
+        value["machines"] = statistics
+            .sorted_machines()
+            .into_iter()
+            .map(|(id, count)| (id_str(*id).to_string(), json!(count)))
+            .collect();
+    }
+
+    value
+}
+
+async fn map(game: &ActorRef<GameSystemMessage>) -> Value {
+    let Ok(CallResult::Success(Some((info, opt)))) =
+        game.call(GameSystemMessage::GetMapInfoAndName, None).await
+    else {
+        return json!({ "loaded": false });
+    };
+
+    let info = info.lock().await;
+
+    json!({
+        "loaded": true,
+        "name": match &opt {
+            LoadMapOption::FromSave(name) => Some(name.as_str()),
+            _ => None,
+        },
+        "save_time": info
+            .save_time
+            .and_then(|v| v.duration_since(UNIX_EPOCH).ok())
+            .map(|v| v.as_secs()),
+        "beacons": info.beacons.iter().map(|beacon| json!({
+            "name": beacon.name,
+            "coord": [beacon.coord.x, beacon.coord.y],
+        })).collect::<Vec<_>>(),
+    })
+}
+
+// the error queue is emptied into toasts as soon as the GUI draws, so the history is reported instead
+fn alerts(resource_man: &ResourceManager) -> Value {
+    err_history()
+        .into_iter()
+        .map(|err| {
+            json!({
+                "key": error_to_key(err.id, resource_man),
+                "severity": match err.severity {
+                    ErrorSeverity::Info => "info",
+                    ErrorSeverity::Warn => "warn",
+                    ErrorSeverity::Fatal => "fatal",
+                },
+                "message": err.message,
+                "time": err.time.duration_since(UNIX_EPOCH).map(|v| v.as_secs()).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// Saves the map, if it is a saved map and not the main menu.
+async fn save(game: &ActorRef<GameSystemMessage>) -> Value {
+    let Ok(CallResult::Success(Some((_, LoadMapOption::FromSave(_))))) =
+        game.call(GameSystemMessage::GetMapInfoAndName, None).await
+    else {
+        return json!({ "ok": false, "error": "no saved map is loaded" });
+    };
+
+    match game.call(GameSystemMessage::SaveMap, None).await {
+        Ok(CallResult::Success(())) => json!({ "ok": true }),
+        _ => json!({ "ok": false, "error": "the map could not be saved" }),
+    }
+}
@@ -1,19 +1,33 @@
+use crate::gui::toast::{push_toast, SHORT_TOAST};
 use crate::GameState;
-use crate::{gui, renderer};
+use crate::{gpu, gui, renderer};
 use automancy_defs::id::Id;
-use automancy_defs::{coord::TileCoord, id::TileId};
-use automancy_defs::{log, window};
+use automancy_defs::math::Float;
+use automancy_defs::{
+    coord::{TileBounds, TileCoord},
+    id::TileId,
+};
+use automancy_defs::{log, math, window};
 use automancy_resources::data::Data;
+use automancy_resources::error;
+use automancy_resources::feedback::{take_screen_effects, ScreenEffect};
+use automancy_resources::format::Formattable;
+use automancy_resources::rhai_field;
+use automancy_resources::types::tile::TileAction;
+use automancy_system::blueprint;
 use automancy_system::game::{GameSystemMessage, PlaceTileResponse};
 use automancy_system::input::{self, ActionType};
-use automancy_system::map::{GameMap, LoadMapOption, MAP_PATH};
+use automancy_system::map::{self, GameMap, LoadMapOption, MAP_PATH};
+use automancy_system::ownership::LOCAL_PLAYER;
+use automancy_system::signing::{self, SaveSignature};
 use automancy_system::tile_entity::{TileEntityMsg, TileEntityWithId};
-use automancy_system::ui_state::{Screen, TextField};
+use automancy_system::ui_state::{MapEndSummary, PopupAction, PopupState, Screen, TextField};
+use automancy_ui::{radial_pick, Toast, ToastPriority};
 use ractor::rpc::CallResult;
 use ractor::ActorRef;
 use std::sync::atomic::Ordering;
-use std::time::{Instant, SystemTime};
-use std::{fs, mem};
+use std::time::{Duration, Instant, SystemTime};
+use std::{env, fs, mem};
 use tokio::task::JoinHandle;
 use wgpu::SurfaceError;
 use winit::{
@@ -21,15 +35,18 @@ use winit::{
     event_loop::ActiveEventLoop,
 };
 
+/// How close to the cursor, in world units, a moving object has to be to be followed.
+const FOLLOW_PICK_RADIUS: Float = 0.5;
+
 /// Refreshes the list of maps on the filesystem. Should be done every time the list of maps could have changed (on map creation/delete and on game load).
 pub fn refresh_maps(state: &mut GameState) {
     fs::create_dir_all(MAP_PATH).unwrap();
 
-    state.loop_store.map_infos_cache = fs::read_dir(MAP_PATH)
-        .expect("Map folder doesn't exist- is the disk full?")
-        .flatten()
-        .map(|f| f.file_name().to_str().unwrap().to_string())
-        .filter(|f| !f.starts_with('.'))
+    let (saves, folders) = map::list_saves();
+
+    state.loop_store.map_folders_cache = folders;
+    state.loop_store.map_infos_cache = saves
+        .into_iter()
         .flat_map(|name| {
             GameMap::read_info(&state.resource_man, &LoadMapOption::FromSave(name.clone()))
                 .ok()
@@ -47,6 +64,36 @@ pub fn refresh_maps(state: &mut GameState) {
             .cmp(&b.0 .1.unwrap_or(SystemTime::UNIX_EPOCH))
     });
     state.loop_store.map_infos_cache.reverse();
+
+    // saves can be checked even with signing turned off, as long as this install has signed before
+    let key = signing::read_key();
+    state.loop_store.map_signatures = state
+        .loop_store
+        .map_infos_cache
+        .iter()
+        .map(|(_, name)| {
+            let signature = key.as_ref().map_or(SaveSignature::Unsigned, |key| {
+                signing::check_map(key, &LoadMapOption::FromSave(name.clone()))
+            });
+
+            (name.clone(), signature)
+        })
+        .collect();
+
+    // saves and folders can be moved or deleted from outside the game too
+    let signatures = &state.loop_store.map_signatures;
+    state
+        .ui_state
+        .selected_maps
+        .retain(|name| signatures.contains_key(name));
+    if !state.ui_state.map_folder.is_empty()
+        && !state
+            .loop_store
+            .map_folders_cache
+            .contains(&state.ui_state.map_folder)
+    {
+        state.ui_state.map_folder.clear();
+    }
 }
 
 pub async fn shutdown_graceful(
@@ -123,16 +170,9 @@ fn render(
             updating.store(true, Ordering::Relaxed);
 
             state.tokio.spawn(async move {
-                let Ok(CallResult::Success(tile)) = game
-                    .call(|reply| GameSystemMessage::GetTile(pointing_at, reply), None)
-                    .await
-                else {
-                    return;
-                };
-
-                let Ok(CallResult::Success(entity)) = game
+                let Ok(CallResult::Success(picked)) = game
                     .call(
-                        |reply| GameSystemMessage::GetTileEntity(pointing_at, reply),
+                        |reply| GameSystemMessage::PickTile(pointing_at, reply),
                         None,
                     )
                     .await
@@ -140,7 +180,7 @@ fn render(
                     return;
                 };
 
-                *cache.lock().await = tile.zip(entity);
+                *cache.lock().await = picked;
 
                 updating.store(false, Ordering::Relaxed);
             });
@@ -156,7 +196,8 @@ fn render(
             state.gui.as_mut().unwrap().yak.finish();
         }
 
-        if !matches!(result, Ok(true)) {
+        // nothing can be drawn until the device has been recreated
+        if !matches!(result, Ok(true)) && !gpu::is_device_lost() {
             match renderer::render(state, screenshotting) {
                 Ok(_) => {}
                 Err(SurfaceError::Lost) => {
@@ -183,11 +224,120 @@ fn render(
     result
 }
 
+/// Applies the screen effects queued since the last frame, scaled by the screen effects option.
+/// Effects from tiles out of view are dropped, and so are shakes and flashes while the quality is lowered.
+fn apply_screen_effects(state: &mut GameState) -> anyhow::Result<()> {
+    let effects = take_screen_effects();
+    let scale = state.options.gui.screen_effects.clamp(0.0, 1.0);
+    let visible = state.loop_store.quality.level().screen_effects();
+
+    if scale <= 0.0 || state.ui_state.screen != Screen::Ingame {
+        return Ok(());
+    }
+
+    for (origin, effect) in effects {
+        if origin.is_some_and(|coord| !state.camera.culling_range.contains(coord)) {
+            continue;
+        }
+
+        match effect {
+            ScreenEffect::Shake { .. } | ScreenEffect::Flash { .. } if !visible => {}
+            ScreenEffect::Shake { strength, duration } => {
+                state.camera.shake(strength * scale as f32, duration);
+            }
+            ScreenEffect::Flash {
+                mut color,
+                duration,
+            } => {
+                color.a = (color.a as f64 * scale) as u8;
+
+                state.ui_state.flash = Some((color, duration, Instant::now()));
+            }
+            ScreenEffect::HitStop(duration) => {
+                state
+                    .game
+                    .send_message(GameSystemMessage::HitStop(duration.mul_f64(scale)))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Lowers or raises the quality of the visual effects following how fast the last frames were, if adaptive quality is
+/// on, and lets the player know with a toast that can turn it off.
+fn update_quality(state: &mut GameState, now: Instant) {
+    if !state.options.graphics.adaptive_quality {
+        state.loop_store.quality.reset();
+        return;
+    }
+
+    // loading and menus don't say much about how fast the map draws
+    if state.ui_state.screen != Screen::Ingame {
+        return;
+    }
+
+    // 0 is vsync, and 250 is unlimited
+    let fps_limit = state.options.graphics.fps_limit;
+    let limited_to =
+        (fps_limit > 0 && fps_limit < 250).then(|| Duration::from_secs_f64(1.0 / fps_limit as f64));
+
+    let last = state.loop_store.quality.level();
+    let Some(level) = state
+        .loop_store
+        .quality
+        .update(now, state.loop_store.elapsed, limited_to)
+    else {
+        return;
+    };
+
+    log::info!("Quality changed from {last:?} to {level:?}, following the frame time");
+
+    let gui_ids = &state.resource_man.registry.gui_ids;
+
+    push_toast(
+        Toast::new(
+            state
+                .resource_man
+                .gui_str(if level > last {
+                    gui_ids.lbl_quality_lowered
+                } else {
+                    gui_ids.lbl_quality_restored
+                })
+                .to_string(),
+            ToastPriority::Low,
+        )
+        .with_timeout(SHORT_TOAST)
+        .with_action(
+            state
+                .resource_man
+                .gui_str(gui_ids.btn_disable_adaptive_quality)
+                .to_string(),
+            PopupAction::DisableAdaptiveQuality,
+        ),
+    );
+}
+
+/// Checks if the local player is allowed to change or configure the tile at the position.
+fn can_modify(state: &GameState, coord: TileCoord) -> bool {
+    matches!(
+        state.tokio.block_on(state.game.call(
+            |reply| GameSystemMessage::CanModify(coord, LOCAL_PLAYER, reply),
+            None,
+        )),
+        Ok(CallResult::Success(true))
+    )
+}
+
 fn link_tile(state: &mut GameState, entity: Option<TileEntityWithId>, link_to: TileCoord, id: Id) {
     let Some((_, entity)) = entity else {
         return;
     };
 
+    if !can_modify(state, state.camera.pointing_at) {
+        return;
+    }
+
     let Ok(CallResult::Success(old)) = state
         .tokio
         .block_on(entity.call(|reply| TileEntityMsg::GetDataValue(id, reply), None))
@@ -196,23 +346,70 @@ fn link_tile(state: &mut GameState, entity: Option<TileEntityWithId>, link_to: T
     };
 
     if old.is_some() {
-        entity.send_message(TileEntityMsg::RemoveData(id)).unwrap();
-
-        state
-            .audio_man
-            .play(state.resource_man.audio["click"].clone())
+        entity
+            .send_message(TileEntityMsg::Configure(id, None))
             .unwrap();
+
+        state.play_sound("click");
         // TODO click2
     } else {
         entity
-            .send_message(TileEntityMsg::SetDataValue(id, Data::Coord(link_to)))
+            .send_message(TileEntityMsg::Configure(id, Some(Data::Coord(link_to))))
             .unwrap();
 
-        state
-            .audio_man
-            .play(state.resource_man.audio["click"].clone())
-            .unwrap();
+        state.play_sound("click");
+    }
+}
+
+/// Adds the range dragged over to the region being edited, or cuts it out, and stores the region in the tile's data.
+fn finish_region_drag(state: &mut GameState) -> anyhow::Result<()> {
+    let Some(editing) = &mut state.ui_state.editing_region else {
+        return Ok(());
+    };
+    let Some((start, subtract)) = editing.drag.take() else {
+        return Ok(());
+    };
+
+    // the range is centered where the drag started, and reaches to where it ended
+    let bounds = TileBounds::new(start, start.unsigned_distance_to(*state.camera.pointing_at));
+
+    let changed = if subtract {
+        editing.region.subtract(bounds)
+    } else {
+        editing.region.add(bounds)
+    };
+
+    if !changed {
+        log::warn!("The region is made of too many areas to be changed any further");
+        return Ok(());
     }
+
+    let (coord, id, region) = (editing.coord, editing.id, editing.region.clone());
+
+    let Some(entity) = state
+        .tokio
+        .block_on(
+            state
+                .game
+                .call(|reply| GameSystemMessage::GetTileEntity(coord, reply), None),
+        )?
+        .unwrap()
+    else {
+        // the tile is gone
+        state.ui_state.editing_region = None;
+        return Ok(());
+    };
+
+    entity
+        .send_message(TileEntityMsg::Configure(
+            id,
+            Some(Data::TileBoundsSet(region)),
+        ))
+        .unwrap();
+
+    state.play_sound("click");
+
+    Ok(())
 }
 
 fn place_tile(id: TileId, coord: TileCoord, state: &mut GameState) -> anyhow::Result<()> {
@@ -222,6 +419,7 @@ fn place_tile(id: TileId, coord: TileCoord, state: &mut GameState) -> anyhow::Re
             |reply| GameSystemMessage::PlaceTile {
                 coord,
                 id,
+                player: LOCAL_PLAYER,
                 record: true,
                 reply: Some(reply),
                 data: None,
@@ -232,18 +430,24 @@ fn place_tile(id: TileId, coord: TileCoord, state: &mut GameState) -> anyhow::Re
 
     match response {
         PlaceTileResponse::Placed => {
-            state
-                .audio_man
-                .play(state.resource_man.audio["tile_placement"].clone())
-                .unwrap();
+            state.play_sound_at("tile_placement", coord);
             state.ui_state.config_open_at = Some(coord);
             state.ui_state.already_placed_at = Some(coord);
         }
         PlaceTileResponse::Removed => {
-            state
-                .audio_man
-                .play(state.resource_man.audio["tile_removal"].clone())
-                .unwrap();
+            state.play_sound_at("tile_removal", coord);
+        }
+        PlaceTileResponse::OverBudget => {
+            push_toast(
+                Toast::new(
+                    state
+                        .resource_man
+                        .gui_str(state.resource_man.registry.gui_ids.lbl_over_budget)
+                        .to_string(),
+                    ToastPriority::Normal,
+                )
+                .with_timeout(SHORT_TOAST),
+            );
         }
         _ => {}
     }
@@ -251,6 +455,127 @@ fn place_tile(id: TileId, coord: TileCoord, state: &mut GameState) -> anyhow::Re
     Ok(())
 }
 
+/// Runs the next batch of the ticks the loaded map catches up on, showing how far along it is in the progress popup,
+/// which is closed once they have all run.
+fn offline_progress(state: &mut GameState) -> anyhow::Result<()> {
+    let Some((done, total)) = state.ui_state.offline_progress else {
+        return Ok(());
+    };
+
+    let ran = state
+        .tokio
+        .block_on(state.game.call(
+            |reply| GameSystemMessage::CatchUpTicks(total - done, reply),
+            None,
+        ))?
+        .unwrap();
+    let done = done + ran;
+
+    let title = state.resource_man.registry.gui_ids.offline_progress;
+
+    if done >= total {
+        state.ui_state.offline_progress = None;
+        state
+            .ui_state
+            .popups
+            .retain(|v| !matches!(v, PopupState::Progress { title: id, .. } if *id == title));
+
+        return Ok(());
+    }
+
+    state.ui_state.offline_progress = Some((done, total));
+    state.ui_state.set_progress(
+        title,
+        state.resource_man.gui_fmt(
+            state.resource_man.registry.gui_ids.lbl_offline_progress,
+            [
+                ("done", Formattable::integer(&done)),
+                ("total", Formattable::integer(&total)),
+            ],
+        ),
+        done as Float / total as Float,
+    );
+
+    Ok(())
+}
+
+/// Shows the end screen of the map, once its conditions decided it was won or lost.
+fn map_outcome(state: &mut GameState) -> anyhow::Result<()> {
+    let Some((outcome, ticks)) = state
+        .tokio
+        .block_on(state.game.call(GameSystemMessage::TakeMapOutcome, None))?
+        .unwrap()
+    else {
+        return Ok(());
+    };
+
+    let statistics = state
+        .tokio
+        .block_on(state.game.call(GameSystemMessage::GetStatistics, None))?
+        .unwrap();
+
+    state.ui_state.push_popup(PopupState::MapEnd(
+        outcome,
+        MapEndSummary::new(ticks, &statistics),
+    ));
+
+    Ok(())
+}
+
+/// Runs an action picked from the radial menu of the tile at the coordinate.
+fn run_tile_action(
+    state: &mut GameState,
+    coord: TileCoord,
+    action: TileAction,
+) -> anyhow::Result<()> {
+    match action {
+        TileAction::Configure => {
+            if can_modify(state, coord) {
+                state.ui_state.config_open_at = Some(coord);
+                state.ui_state.text_field.get(TextField::Filter).clear();
+            }
+        }
+        TileAction::CopySettings => {
+            // the same as copying a selection of just the tile
+            state.ui_state.paste_content = state
+                .tokio
+                .block_on(state.game.call(
+                    |reply| GameSystemMessage::GetTiles(vec![coord], reply),
+                    None,
+                ))?
+                .unwrap();
+            state.ui_state.paste_from = Some(coord);
+
+            state.play_sound("click");
+        }
+        TileAction::Delete => {
+            place_tile(TileId(state.resource_man.registry.none), coord, state)?;
+        }
+        TileAction::JumpLink(id) => {
+            let Some(entity) = state
+                .tokio
+                .block_on(
+                    state
+                        .game
+                        .call(|reply| GameSystemMessage::GetTileEntity(coord, reply), None),
+                )?
+                .unwrap()
+            else {
+                return Ok(());
+            };
+
+            if let Ok(CallResult::Success(Some(Data::Coord(link_to)))) = state
+                .tokio
+                .block_on(entity.call(|reply| TileEntityMsg::GetDataValue(id, reply), None))
+            {
+                state.camera.jump_to(link_to);
+            }
+        }
+    }
+
+    Ok(())
+}
+
 /// Triggers every time the event loop is run once.
 pub fn on_event(
     state: &mut GameState,
@@ -281,14 +606,53 @@ pub fn on_event(
 
                     state.loop_store.elapsed = now - state.loop_store.frame_start.take().unwrap();
 
-                    state.camera.update_pointing_at(
-                        state.input_handler.main_pos,
-                        window::window_size_double(&state.renderer.as_ref().unwrap().gpu.window),
-                    );
+                    update_quality(state, now);
+
+                    // the tick snapshot is taken first, so that the pointer and camera updates below are applied to the frame
+                    // that shows it, instead of lagging a frame behind in the hover highlight and the placement ghost
+                    renderer::take_tick_snapshot(state);
+
+                    apply_screen_effects(state)?;
+
+                    if let Some(key) = state.ui_state.following {
+                        match state.renderer.as_ref().unwrap().object_position(&key) {
+                            Some(pos) => state
+                                .camera
+                                .follow(pos.truncate(), state.loop_store.elapsed.as_secs_f32()),
+                            // the object is gone, like an item that arrived
+                            None => state.ui_state.following = None,
+                        }
+                    }
+
+                    #[cfg(feature = "companion")]
+                    if crate::companion::take_screenshot_request() {
+                        state.screenshotting = true;
+                    }
+
+                    offline_progress(state)?;
+                    map_outcome(state)?;
+
+                    // saved next to the crash reports, to show what was going on
+                    if error::take_screenshot_request() {
+                        let time = SystemTime::now()
+                            .duration_since(SystemTime::UNIX_EPOCH)
+                            .unwrap_or_default()
+                            .as_secs();
+
+                        state.screenshot_path =
+                            Some(env::temp_dir().join(format!("automancy-screenshot-{time}.png")));
+                        state.screenshotting = true;
+                    }
+
+                    // the camera moves first, so that the pointer is projected through where it is drawn this frame
                     state.camera.update_pos(
                         window::window_size_double(&state.renderer.as_ref().unwrap().gpu.window),
                         state.loop_store.elapsed.as_secs_f32(),
                     );
+                    state.camera.update_pointing_at(
+                        state.input_handler.main_pos,
+                        window::window_size_double(&state.renderer.as_ref().unwrap().gpu.window),
+                    );
 
                     state.loop_store.frame_start = Some(now);
 
@@ -311,10 +675,14 @@ pub fn on_event(
 
                     return Ok(false);
                 }
-                WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
-                    state.gui.as_mut().unwrap().yak.set_scale_factor(
-                        (*scale_factor * state.options.graphics.ui_scale.to_f64()) as f32,
-                    );
+                WindowEvent::ScaleFactorChanged { .. } => {
+                    gui::apply_ui_scale(state);
+                }
+                // the window may have moved to a monitor of another size
+                WindowEvent::Moved(_) => {
+                    gui::apply_ui_scale(state);
+
+                    window_event = Some(event);
                 }
                 event => {
                     window_event = Some(event);
@@ -341,16 +709,35 @@ pub fn on_event(
             1.0, //TODO sensitivity option
         ));
 
+        // a cutscene holds back the player's input, until it's over or skipped
+        if state.ui_state.screen == Screen::Ingame && state.ui_state.cutscene.is_some() {
+            if state.input_handler.key_active(ActionType::Cancel) {
+                gui::cutscene::skip_cutscene(state);
+            }
+
+            return Ok(false);
+        }
+
         state.camera.handle_input(&state.input_handler);
 
+        // moving the camera by hand stops following
+        if state.input_handler.tertiary_held && state.input_handler.main_move.is_some() {
+            state.ui_state.following = None;
+        }
+
         state.input_hints.clear();
 
         state.input_hints.push(vec![ActionType::Cancel]);
         if state.input_handler.key_active(ActionType::Cancel) {
             // one by one
-            if state.ui_state.selected_tile_id.take().is_none()
+            if state.ui_state.radial_menu.take().is_none()
+                && state.ui_state.map_search.take().is_none()
+                && state.ui_state.selected_tile_id.take().is_none()
                 && state.ui_state.linking_tile.take().is_none()
+                && state.ui_state.editing_region.take().is_none()
                 && state.ui_state.paste_from.take().is_none()
+                && state.ui_state.following.take().is_none()
+                && mem::take(&mut state.ui_state.deconstruct_marked).is_empty()
             {
                 if state
                     .ui_state
@@ -360,6 +747,19 @@ pub fn on_event(
                         .tokio
                         .block_on(state.game.call(GameSystemMessage::SaveMap, None))?
                         .unwrap();
+
+                    if !state.ui_state.read_only {
+                        push_toast(
+                            Toast::new(
+                                state
+                                    .resource_man
+                                    .gui_str(state.resource_man.registry.gui_ids.lbl_map_saved)
+                                    .to_string(),
+                                ToastPriority::Low,
+                            )
+                            .with_timeout(SHORT_TOAST),
+                        );
+                    }
                 } else {
                     state
                         .ui_state
@@ -370,10 +770,132 @@ pub fn on_event(
 
         state.input_hints.push(vec![ActionType::Player]);
 
+        if state.ui_state.screen == Screen::Ingame
+            && state.input_handler.key_active(ActionType::NextBeacon)
+        {
+            if let Some(map_info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) {
+                let beacons = map_info.blocking_lock().beacons.clone();
+
+                gui::beacon::jump_to_next_beacon(state, &beacons);
+            }
+        }
+
+        state.input_hints.push(vec![ActionType::Follow]);
+        if state.ui_state.screen == Screen::Ingame
+            && state.input_handler.key_active(ActionType::Follow)
+        {
+            state.ui_state.following = if state.ui_state.following.is_some() {
+                None
+            } else {
+                let cursor = math::screen_to_world(
+                    window::window_size_double(&state.renderer.as_ref().unwrap().gpu.window),
+                    state.input_handler.main_pos,
+                    state.camera.get_pos(),
+                );
+
+                state
+                    .renderer
+                    .as_ref()
+                    .unwrap()
+                    .moving_object_near(cursor.truncate(), FOLLOW_PICK_RADIUS)
+            };
+        }
+
+        // overlays are hidden behind tall models, unless they are seen through
+        state.input_hints.push(vec![ActionType::XRay]);
+        state.input_hints.push(vec![ActionType::FieldOverlay]);
+        if state.ui_state.screen == Screen::Ingame
+            && state.input_handler.key_active(ActionType::FieldOverlay)
+        {
+            // goes through the fields that have any value, then turns the overlay off
+            let fields = rhai_field::field_ids();
+
+            state.ui_state.field_overlay = match state.ui_state.field_overlay {
+                None => fields.first().copied(),
+                Some(current) => fields.into_iter().find(|id| *id > current),
+            };
+        }
+
+        // nothing can be changed on a map opened read-only, so the tools that would are left out
+        let read_only = state.ui_state.read_only;
+
+        if !read_only {
+            state.input_hints.push(vec![ActionType::Deconstruct]);
+        }
+        if state.input_handler.key_active(ActionType::Deconstruct)
+            && state.ui_state.screen == Screen::Ingame
+            && !read_only
+        {
+            state.input_hints.push(vec![ActionType::DeconstructFilter]);
+            if state
+                .input_handler
+                .key_active(ActionType::DeconstructFilter)
+            {
+                state.ui_state.deconstruct_filter = state.ui_state.deconstruct_filter.next();
+            }
+
+            if state.input_handler.main_held {
+                let start = state
+                    .ui_state
+                    .deconstruct_drag
+                    .map_or(state.camera.pointing_at, |v| v.center());
+
+                // the range is centered where the drag started, and reaches to the cursor, like a region being edited
+                let bounds =
+                    TileBounds::new(start, start.unsigned_distance_to(*state.camera.pointing_at));
+
+                // the tiles are only asked for again once the range changes
+                if state.ui_state.deconstruct_drag != Some(bounds) {
+                    let marked = state
+                        .tokio
+                        .block_on(
+                            state
+                                .game
+                                .call(|reply| GameSystemMessage::GetTilesIn(bounds, reply), None),
+                        )?
+                        .unwrap();
+
+                    state.ui_state.deconstruct_origin = marked.get(&start).copied();
+                    state.ui_state.deconstruct_marked = marked;
+                    state.ui_state.deconstruct_drag = Some(bounds);
+                }
+            } else if state.ui_state.deconstruct_drag.take().is_some() {
+                let none = state.resource_man.registry.none;
+
+                let tiles = state
+                    .ui_state
+                    .deconstruct_targets(&state.resource_man)
+                    .into_iter()
+                    .map(|coord| (coord, TileId(none), None))
+                    .collect::<Vec<_>>();
+
+                state.ui_state.deconstruct_marked.clear();
+                state.ui_state.deconstruct_origin = None;
+
+                if !tiles.is_empty() {
+                    state.game.send_message(GameSystemMessage::PlaceTiles {
+                        tiles,
+                        player: LOCAL_PLAYER,
+                        reply: None,
+                        place_over: true,
+                        record: true,
+                    })?;
+
+                    state.play_sound("click");
+                }
+            }
+        } else {
+            state.ui_state.deconstruct_drag = None;
+            state.ui_state.deconstruct_marked.clear();
+            state.ui_state.deconstruct_origin = None;
+        }
+
         // TODO hint this
-        if (state.input_handler.main_pressed
-            || (state.input_handler.key_active(ActionType::SelectMode)
-                && state.input_handler.main_held))
+        if !read_only
+            && !state.input_handler.key_active(ActionType::Deconstruct)
+            && (state.input_handler.main_pressed
+                || (state.input_handler.key_active(ActionType::SelectMode)
+                    && state.input_handler.main_held))
             && state.ui_state.already_placed_at != Some(state.camera.pointing_at)
         {
             if let Some(id) = state.ui_state.selected_tile_id {
@@ -381,8 +903,10 @@ pub fn on_event(
             }
         }
 
-        state.input_hints.push(vec![ActionType::Delete]);
-        if state.input_handler.key_active(ActionType::Delete) {
+        if !read_only {
+            state.input_hints.push(vec![ActionType::Delete]);
+        }
+        if !read_only && state.input_handler.key_active(ActionType::Delete) {
             place_tile(
                 TileId(state.resource_man.registry.none),
                 state.camera.pointing_at,
@@ -390,16 +914,54 @@ pub fn on_event(
             )?;
         }
 
-        if !state.input_handler.key_active(ActionType::SelectMode) {
+        if let Some(editing) = &mut state.ui_state.editing_region {
+            // the main button drags out a range to add, and the alternate one a range to cut out
+            if editing.drag.is_none() {
+                if state.input_handler.main_pressed {
+                    editing.drag = Some((state.camera.pointing_at, false));
+                } else if state.input_handler.alternate_pressed {
+                    editing.drag = Some((state.camera.pointing_at, true));
+                }
+            } else if !state.input_handler.main_held && !state.input_handler.alternate_held {
+                finish_region_drag(state)?;
+            }
+        } else if !state.input_handler.key_active(ActionType::SelectMode) {
             // TODO hint this
             if state.input_handler.alternate_pressed {
                 if let Some((link_to, id)) = state.ui_state.linking_tile {
                     link_tile(state, pointing_at_entity, link_to, id);
-                } else if Some(state.camera.pointing_at) == state.ui_state.config_open_at {
+                } else {
+                    // a click toggles the config when let go, as holding opens the radial menu instead
+                    state.ui_state.radial_hold = Some((state.camera.pointing_at, Instant::now()));
+                }
+            }
+        }
+
+        if let Some((coord, since)) = state.ui_state.radial_hold {
+            if state.input_handler.alternate_held {
+                if state.ui_state.radial_menu.is_none()
+                    && since.elapsed() >= gui::radial::RADIAL_HOLD
+                {
+                    gui::radial::open_radial_menu(state, coord);
+                }
+            } else {
+                state.ui_state.radial_hold = None;
+
+                if let Some(menu) = state.ui_state.radial_menu.take() {
+                    if let Some(index) = radial_pick(
+                        menu.center,
+                        gui::radial::mouse_ui_pos(state),
+                        menu.actions.len(),
+                    ) {
+                        run_tile_action(state, menu.coord, menu.actions[index])?;
+                    }
+                } else if since.elapsed() >= gui::radial::RADIAL_HOLD {
+                    // held on a tile with no radial menu, or the menu was cancelled
+                } else if Some(coord) == state.ui_state.config_open_at {
                     state.ui_state.config_open_at = None;
                     state.ui_state.text_field.get(TextField::Filter).clear();
-                } else {
-                    state.ui_state.config_open_at = Some(state.camera.pointing_at);
+                } else if can_modify(state, coord) {
+                    state.ui_state.config_open_at = Some(coord);
                     state.ui_state.text_field.get(TextField::Filter).clear();
                 }
             }
@@ -419,7 +981,32 @@ pub fn on_event(
             state.ui_state.grouped_tiles.clear();
         }
 
-        if state.input_handler.key_active(ActionType::HotkeyActive) {
+        // searching doesn't change the map, so it works on maps opened read-only too
+        if state.ui_state.screen == Screen::Ingame
+            && state.input_handler.key_active(ActionType::HotkeyActive)
+            && state.input_handler.key_active(ActionType::MapSearch)
+        {
+            state
+                .ui_state
+                .map_search
+                .get_or_insert_with(Default::default);
+        }
+
+        // blueprints are placed by pasting them, so they can't be opened on maps opened read-only
+        if !read_only {
+            state.input_hints.push(vec![ActionType::Blueprints]);
+
+            if state.ui_state.screen == Screen::Ingame
+                && state.input_handler.key_active(ActionType::Blueprints)
+                && state.ui_state.blueprints.take().is_none()
+            {
+                state.ui_state.blueprints = Some(blueprint::list_blueprints());
+            }
+        }
+
+        if read_only {
+            // undo, cut and paste change the map, and copying is only for pasting
+        } else if state.input_handler.key_active(ActionType::HotkeyActive) {
             state
                 .input_hints
                 .push(vec![ActionType::HotkeyActive, ActionType::Undo]);
@@ -446,9 +1033,7 @@ pub fn on_event(
                     || state.input_handler.key_active(ActionType::Copy)
                 {
                     state.ui_state.paste_from = Some(state.camera.pointing_at);
-                    state
-                        .audio_man
-                        .play(state.resource_man.audio["click"].clone())?;
+                    state.play_sound("click");
 
                     let coords = Vec::from_iter(mem::take(&mut state.ui_state.grouped_tiles));
 
@@ -464,6 +1049,7 @@ pub fn on_event(
                                             .into_iter()
                                             .map(|coord| (coord, TileId(none), None))
                                             .collect::<Vec<_>>(),
+                                        player: LOCAL_PLAYER,
                                         reply: Some(reply),
                                         place_over: true,
                                         record: true,
@@ -503,14 +1089,13 @@ pub fn on_event(
 
                     state.game.send_message(GameSystemMessage::PlaceTiles {
                         tiles,
+                        player: LOCAL_PLAYER,
                         reply: None,
                         place_over: false,
                         record: true,
                     })?;
 
-                    state
-                        .audio_man
-                        .play(state.resource_man.audio["click"].clone())?;
+                    state.play_sound("click");
                     // TODO click2
                 }
             }
@@ -1,18 +1,19 @@
+use anyhow::Context;
 use automancy_defs::rendering::{AnimationMatrixData, GameUBO, GpuInstance, MatrixData, Vertex};
-use automancy_defs::rendering::{PostProcessingUBO, WorldMatrixData};
+use automancy_defs::rendering::{BackgroundUBO, PostProcessingUBO, WorldMatrixData};
 use automancy_defs::{rendering::IntermediateUBO, slice_group_by::GroupBy};
 use automancy_macros::OptionGetter;
 use automancy_resources::ResourceManager;
 use bytemuck::Pod;
 use ordermap::OrderMap;
 use std::mem;
-use std::{num::NonZero, sync::Arc};
-use wgpu::{util::StagingBelt, CommandEncoder};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use wgpu::{
     util::{backend_bits_from_env, power_preference_from_env, BufferInitDescriptor, DeviceExt},
     BufferAddress, InstanceFlags, PipelineCompilationOptions, COPY_BUFFER_ALIGNMENT,
 };
-use wgpu::{AdapterInfo, Face, Surface};
+use wgpu::{AdapterInfo, DeviceLostReason, Face, Surface};
 use wgpu::{
     AddressMode, Backends, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
     BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType, BlendState,
@@ -26,6 +27,7 @@ use wgpu::{
     TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
     VertexState,
 };
+use wgpu::{BufferDescriptor, CommandEncoder, MapMode, MAP_ALIGNMENT};
 use winit::dpi::PhysicalSize;
 use winit::window::Window;
 use yakui::UVec2;
@@ -42,9 +44,14 @@ pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
 pub const MODEL_DEPTH_FORMAT: TextureFormat = TextureFormat::R32Float;
 pub const SCREENSHOT_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
 pub const NORMAL_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+/// The background shader used when no resource pack provides one.
+static DEFAULT_BACKGROUND_SHADER: &str = include_str!("assets/background.wgsl");
 
-fn align_to_copy_alignment(add: BufferAddress) -> BufferAddress {
-    add + (COPY_BUFFER_ALIGNMENT - (add % COPY_BUFFER_ALIGNMENT))
+/// The most scenery meshes that are drawn. Any more are left out of the batch.
+pub const SCENERY_MATRIX_DATA_SIZE: usize = 65536;
+
+fn align_up(add: BufferAddress, alignment: BufferAddress) -> BufferAddress {
+    add.div_ceil(alignment) * alignment
 }
 
 fn ordered_map_write_to_buffer<K, V>(data: &OrderMap<K, V>) -> Vec<u8>
@@ -69,96 +76,234 @@ where
     queue.write_buffer(buffer, 0, &ordered_map_write_to_buffer(data));
 }
 
-#[must_use]
-pub fn update_buffer_with_changes<V>(
-    encoder: &mut CommandEncoder,
-    device: &Device,
-    buffer: &Buffer,
-    changes: &[usize],
-    data: &[V],
-) -> Option<StagingBelt>
-where
-    V: Pod + Default,
-{
-    debug_assert!(changes.windows(2).all(|v| v[0] < v[1]));
-    if data.is_empty() {
-        return None;
-    }
+/// How big each staging buffer of the upload ring is. Uploads larger than this get a staging buffer of their own size.
+const UPLOAD_CHUNK_SIZE: BufferAddress = 1 << 20;
+/// What the offsets into the staging buffers are aligned to, so that they can be both mapped and copied from.
+const UPLOAD_ALIGNMENT: BufferAddress = if MAP_ALIGNMENT > COPY_BUFFER_ALIGNMENT {
+    MAP_ALIGNMENT
+} else {
+    COPY_BUFFER_ALIGNMENT
+};
 
-    let byte_size = size_of::<V>();
-
-    let entire_size = changes.len();
-    if let Some(max_batch) = changes
-        .linear_group_by(|a, b| b - a == 1)
-        .map(|v| v.len())
-        .max()
-    {
-        // TODO test a good multiplier for the size
-        let mut belt = StagingBelt::new(align_to_copy_alignment(
-            (byte_size * entire_size / 4).max(byte_size * max_batch) as BufferAddress,
-        ));
+#[derive(Debug)]
+struct UploadChunk {
+    buffer: Buffer,
+    /// how much of the buffer was written to this frame
+    offset: BufferAddress,
+    /// set once the buffer is mapped again after the frame it was last written in, which fences reusing it
+    mapped: Arc<AtomicBool>,
+    /// whether the buffer is written to this frame, and still has to be unmapped before submitting
+    in_use: bool,
+}
 
-        for batch in changes.linear_group_by(|a, b| b - a == 1) {
-            if !batch.is_empty() {
-                let start = batch[0];
-                if start >= data.len() {
-                    continue;
-                }
-                let end = (start + batch.len()).min(data.len());
-
-                let size = end - start;
-
-                let mut view = belt.write_buffer(
-                    encoder,
-                    buffer,
-                    (byte_size * start) as BufferAddress,
-                    unsafe { NonZero::new_unchecked((byte_size * size) as BufferAddress) },
-                    device,
-                );
-
-                view.copy_from_slice(bytemuck::cast_slice(&data[start..end]))
+/**
+A ring of mapped staging buffers the per-frame uploads are written into and copied from, in the frame's command encoder.
+Unlike writing to the queue, or a staging belt made every frame, the staging buffers are kept across frames.
+
+Each frame goes around the ring, taking the chunks the GPU is done with. A chunk is taken again only once it is mapped
+again after the frame that used it; if the next chunk still isn't, a new one is added to the ring instead of waiting.
+
+Call [UploadRing::finish] before submitting the encoder, and [UploadRing::recall] after.
+*/
+#[derive(Debug, Default)]
+pub struct UploadRing {
+    chunks: Vec<UploadChunk>,
+    /// the chunk being written into
+    current: Option<usize>,
+}
+
+impl UploadRing {
+    /// Finds a mapped chunk with room for `size` bytes, going around the ring from the current one, or adds one.
+    fn chunk_for(&mut self, device: &Device, size: BufferAddress) -> usize {
+        if let Some(current) = self.current {
+            let chunk = &self.chunks[current];
+
+            if chunk.offset + size <= chunk.buffer.size() {
+                return current;
             }
         }
 
-        belt.finish();
+        let start = self.current.map(|v| v + 1).unwrap_or(0);
+        let len = self.chunks.len();
 
-        return Some(belt);
+        let free = (0..len).map(|i| (start + i) % len).find(|&i| {
+            let chunk = &self.chunks[i];
+
+            !chunk.in_use && chunk.mapped.load(Ordering::Acquire) && chunk.buffer.size() >= size
+        });
+
+        let index = free.unwrap_or_else(|| {
+            // the ring wrapped around onto chunks still in flight, so it grows
+            self.chunks.push(UploadChunk {
+                buffer: device.create_buffer(&BufferDescriptor {
+                    label: Some("Upload Ring Chunk"),
+                    size: size.max(UPLOAD_CHUNK_SIZE),
+                    usage: BufferUsages::MAP_WRITE | BufferUsages::COPY_SRC,
+                    mapped_at_creation: true,
+                }),
+                offset: 0,
+                mapped: Arc::new(AtomicBool::new(true)),
+                in_use: false,
+            });
+
+            self.chunks.len() - 1
+        });
+
+        self.chunks[index].in_use = true;
+        self.current = Some(index);
+
+        index
     }
 
-    None
-}
+    /// Writes the data into the buffer at the byte offset, as a copy recorded into the encoder.
+    pub fn write<V: Pod>(
+        &mut self,
+        encoder: &mut CommandEncoder,
+        device: &Device,
+        target: &Buffer,
+        target_offset: BufferAddress,
+        data: &[V],
+    ) {
+        let bytes = bytemuck::cast_slice::<V, u8>(data);
+        if bytes.is_empty() {
+            return;
+        }
+        debug_assert!(bytes.len() as BufferAddress % COPY_BUFFER_ALIGNMENT == 0);
 
-#[must_use]
-pub fn resize_update_buffer_with_changes<V>(
-    encoder: &mut CommandEncoder,
-    device: &Device,
-    buffer: &mut Buffer,
-    changes: &[usize],
-    data: &[V],
-) -> Option<StagingBelt>
-where
-    V: Pod + Default,
-{
-    debug_assert!(changes.windows(2).all(|v| v[0] < v[1]));
+        let size = bytes.len() as BufferAddress;
+        let index = self.chunk_for(device, align_up(size, UPLOAD_ALIGNMENT));
+        let chunk = &mut self.chunks[index];
 
-    let byte_size = size_of::<V>();
+        chunk
+            .buffer
+            .slice(chunk.offset..chunk.offset + size)
+            .get_mapped_range_mut()
+            .copy_from_slice(bytes);
+        encoder.copy_buffer_to_buffer(&chunk.buffer, chunk.offset, target, target_offset, size);
 
-    let max_index = changes.last().cloned().unwrap_or_default();
-    let size = max_index + 1;
+        chunk.offset += align_up(size, UPLOAD_ALIGNMENT);
+    }
 
-    if (buffer.size() as usize) < byte_size * size {
-        let usage = buffer.usage();
+    /// Writes the data into the buffer, recreating the buffer if it is too small.
+    pub fn resize_write<V: Pod>(
+        &mut self,
+        encoder: &mut CommandEncoder,
+        device: &Device,
+        buffer: &mut Buffer,
+        data: &[V],
+    ) {
+        if (buffer.size() as usize) < std::mem::size_of_val(data) {
+            let usage = buffer.usage();
 
-        *buffer = device.create_buffer_init(&BufferInitDescriptor {
-            label: None,
-            contents: bytemuck::cast_slice(data),
-            usage,
-        });
-    } else {
-        return update_buffer_with_changes(encoder, device, buffer, changes, data);
+            *buffer = device.create_buffer_init(&BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(data),
+                usage,
+            });
+        } else {
+            self.write(encoder, device, buffer, 0, data);
+        }
+    }
+
+    /// Writes the changed elements into the buffer, one copy per run of consecutive indices.
+    pub fn write_changes<V: Pod>(
+        &mut self,
+        encoder: &mut CommandEncoder,
+        device: &Device,
+        buffer: &Buffer,
+        changes: &[usize],
+        data: &[V],
+    ) {
+        debug_assert!(changes.windows(2).all(|v| v[0] < v[1]));
+
+        let byte_size = size_of::<V>();
+
+        for batch in changes.linear_group_by(|a, b| b - a == 1) {
+            let start = batch[0];
+            if start >= data.len() {
+                continue;
+            }
+            let end = (start + batch.len()).min(data.len());
+
+            self.write(
+                encoder,
+                device,
+                buffer,
+                (byte_size * start) as BufferAddress,
+                &data[start..end],
+            );
+        }
+    }
+
+    /// Writes the changed elements into the buffer, recreating it with all the data if it is too small for them.
+    pub fn resize_write_changes<V: Pod>(
+        &mut self,
+        encoder: &mut CommandEncoder,
+        device: &Device,
+        buffer: &mut Buffer,
+        changes: &[usize],
+        data: &[V],
+    ) {
+        let max_index = changes.last().cloned().unwrap_or_default();
+
+        if (buffer.size() as usize) < size_of::<V>() * (max_index + 1) {
+            let usage = buffer.usage();
+
+            *buffer = device.create_buffer_init(&BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(data),
+                usage,
+            });
+        } else {
+            self.write_changes(encoder, device, buffer, changes, data);
+        }
     }
 
-    None
+    /// Writes the values of the map into the buffer, in order.
+    pub fn write_ordered_map<K, V: Pod + Default>(
+        &mut self,
+        encoder: &mut CommandEncoder,
+        device: &Device,
+        buffer: &Buffer,
+        data: &OrderMap<K, V>,
+    ) {
+        self.write(
+            encoder,
+            device,
+            buffer,
+            0,
+            &ordered_map_write_to_buffer(data),
+        );
+    }
+
+    /// Unmaps the chunks written to this frame, so that the copies from them can run. Call it before submitting.
+    pub fn finish(&mut self) {
+        for chunk in self.chunks.iter().filter(|v| v.in_use) {
+            chunk.buffer.unmap();
+        }
+    }
+
+    /// Maps the chunks written to this frame again, which makes them free to write to once the GPU is done with them.
+    /// Call it after submitting.
+    pub fn recall(&mut self) {
+        for chunk in self.chunks.iter_mut().filter(|v| v.in_use) {
+            chunk.in_use = false;
+            chunk.offset = 0;
+            chunk.mapped.store(false, Ordering::Release);
+
+            let mapped = chunk.mapped.clone();
+            chunk
+                .buffer
+                .slice(..)
+                .map_async(MapMode::Write, move |result| {
+                    if result.is_ok() {
+                        mapped.store(true, Ordering::Release);
+                    }
+                });
+        }
+
+        self.current = None;
+    }
 }
 
 pub fn resize_update_buffer<V>(device: &Device, queue: &Queue, buffer: &mut Buffer, data: &[V])
@@ -270,6 +415,14 @@ pub struct OverlayObjectsResources {
     pub bind_group: BindGroup,
 }
 
+/// The static batch of scenery tiles, rebuilt only when the scenery changes.
+/// It shares the uniform, animation and world matrix buffers of the game.
+pub struct SceneryResources {
+    pub instance_buffer: Buffer,
+    pub matrix_data_buffer: Buffer,
+    pub bind_group: BindGroup,
+}
+
 #[derive(OptionGetter)]
 pub struct GuiResources {
     pub instance_buffer: Buffer,
@@ -453,6 +606,11 @@ impl GuiResources {
     }
 }
 
+pub struct BackgroundResources {
+    pub uniform_buffer: Buffer,
+    pub bind_group: BindGroup,
+}
+
 pub struct PostProcessingResources {
     pub bind_group_uniform: BindGroup,
     pub uniform_buffer: Buffer,
@@ -461,6 +619,8 @@ pub struct PostProcessingResources {
 pub struct RenderResources {
     pub overlay_objects_resources: OverlayObjectsResources,
     pub game_resources: GameResources,
+    pub scenery_resources: SceneryResources,
+    pub background_resources: BackgroundResources,
 
     pub gui_resources: Option<GuiResources>,
 
@@ -476,6 +636,7 @@ pub struct GlobalResources {
     pub index_buffer: Buffer,
 
     pub game_pipeline: RenderPipeline,
+    pub background_pipeline: RenderPipeline,
 
     pub intermediate_bind_group_layout: BindGroupLayout,
     pub screenshot_uniform_buffer: Buffer,
@@ -535,9 +696,28 @@ pub struct SharedResources {
     present_bind_group: Option<BindGroup>,
     #[getters(get)]
     screenshot_bind_group: Option<BindGroup>,
+
+    /// the scale of the resolution the map is drawn at, relative to the window's
+    game_scale: f32,
 }
 
 impl SharedResources {
+    pub fn game_scale(&self) -> f32 {
+        self.game_scale
+    }
+
+    /// Draws the map at the given scale of the window's resolution from now on, recreating the textures it's drawn to.
+    pub fn set_game_scale(
+        &mut self,
+        device: &Device,
+        config: &SurfaceConfiguration,
+        global_resources: &GlobalResources,
+        scale: f32,
+    ) {
+        self.game_scale = scale;
+        self.create(device, config, global_resources);
+    }
+
     pub fn create(
         &mut self,
         device: &Device,
@@ -549,12 +729,18 @@ impl SharedResources {
             height: config.height,
             depth_or_array_layers: 1,
         };
+        // the map is drawn at its own resolution, and scaled up to the window's when combined with the GUI
+        let game_extent = Extent3d {
+            width: ((config.width as f32 * self.game_scale) as u32).max(1),
+            height: ((config.height as f32 * self.game_scale) as u32).max(1),
+            depth_or_array_layers: 1,
+        };
 
         self.game_texture = Some(create_texture_and_view(
             device,
             &TextureDescriptor {
                 label: None,
-                size: extent,
+                size: game_extent,
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
@@ -593,7 +779,7 @@ impl SharedResources {
             device,
             &TextureDescriptor {
                 label: None,
-                size: extent,
+                size: game_extent,
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
@@ -606,7 +792,7 @@ impl SharedResources {
             device,
             &TextureDescriptor {
                 label: None,
-                size: extent,
+                size: game_extent,
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
@@ -619,7 +805,7 @@ impl SharedResources {
             device,
             &TextureDescriptor {
                 label: None,
-                size: extent,
+                size: game_extent,
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
@@ -664,11 +850,7 @@ impl SharedResources {
             device,
             &TextureDescriptor {
                 label: None,
-                size: Extent3d {
-                    width: config.width,
-                    height: config.height,
-                    ..Default::default()
-                },
+                size: game_extent,
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
@@ -688,11 +870,7 @@ impl SharedResources {
             device,
             &TextureDescriptor {
                 label: None,
-                size: Extent3d {
-                    width: config.width,
-                    height: config.height,
-                    ..Default::default()
-                },
+                size: game_extent,
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
@@ -706,7 +884,7 @@ impl SharedResources {
             device,
             &TextureDescriptor {
                 label: None,
-                size: extent,
+                size: game_extent,
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: TextureDimension::D2,
@@ -785,6 +963,14 @@ impl SharedResources {
     }
 }
 
+/// Set when the graphics device is lost, e.g. after a driver reset, until it has been recreated.
+pub static DEVICE_LOST: AtomicBool = AtomicBool::new(false);
+
+/// Returns true if the graphics device was lost and hasn't been recreated yet, otherwise false.
+pub fn is_device_lost() -> bool {
+    DEVICE_LOST.load(Ordering::Relaxed)
+}
+
 pub fn init_gpu_resources(
     device: &Device,
     config: &SurfaceConfiguration,
@@ -817,6 +1003,18 @@ pub fn init_gpu_resources(
         source: ShaderSource::Wgsl(resource_man.shaders["intermediate"].to_string().into()),
     });
 
+    let background_shader = device.create_shader_module(ShaderModuleDescriptor {
+        label: Some("Background Shader"),
+        source: ShaderSource::Wgsl(
+            resource_man
+                .shaders
+                .get("background")
+                .map_or(DEFAULT_BACKGROUND_SHADER, |v| v.as_ref())
+                .to_string()
+                .into(),
+        ),
+    });
+
     let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
         label: Some("Vertex Buffer"),
         contents: bytemuck::cast_slice(vertices.as_slice()),
@@ -1150,6 +1348,49 @@ pub fn init_gpu_resources(
         }
     };
 
+    let scenery_resources = {
+        let matrix_data_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Scenery Matrix Data Buffer"),
+            contents: &vec![0; mem::size_of::<MatrixData>() * SCENERY_MATRIX_DATA_SIZE],
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout: &game_bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: game_resources.uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: matrix_data_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: game_resources
+                        .animation_matrix_data_buffer
+                        .as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: game_resources.world_matrix_data_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("scenery_bind_group"),
+        });
+
+        SceneryResources {
+            instance_buffer: device.create_buffer_init(&BufferInitDescriptor {
+                label: None,
+                contents: &[],
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            }),
+            matrix_data_buffer,
+            bind_group,
+        }
+    };
+
     let gui_resources = {
         let uniform_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("Gui Uniform Buffer"),
@@ -1453,6 +1694,85 @@ pub fn init_gpu_resources(
         )
     };
 
+    let (background_resources, background_pipeline) = {
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX_FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("background_bind_group_layout"),
+        });
+
+        let uniform_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Background Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[BackgroundUBO::default()]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("background_bind_group"),
+            layout: &bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Background Render Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Background Render Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &background_shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &background_shader,
+                entry_point: "fs_main",
+                targets: &[Some(ColorTargetState {
+                    format: config.format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                front_face: FrontFace::Ccw,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        (
+            BackgroundResources {
+                uniform_buffer,
+                bind_group,
+            },
+            pipeline,
+        )
+    };
+
     let intermediate_bind_group_layout =
         device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: None,
@@ -1624,11 +1944,15 @@ pub fn init_gpu_resources(
 
         present_bind_group: None,
         screenshot_bind_group: None,
+
+        game_scale: 1.0,
     };
 
     let render = RenderResources {
         overlay_objects_resources,
         game_resources,
+        scenery_resources,
+        background_resources,
         gui_resources: Some(gui_resources),
         post_processing_resources,
     };
@@ -1642,6 +1966,7 @@ pub fn init_gpu_resources(
         intermediate_shader,
 
         game_pipeline,
+        background_pipeline,
 
         intermediate_bind_group_layout,
         screenshot_uniform_buffer,
@@ -1672,6 +1997,9 @@ pub fn init_gpu_resources(
 
 pub struct Gpu {
     vsync: bool,
+    mailbox: bool,
+    /// the present modes the surface supports
+    present_modes: Vec<PresentMode>,
 
     pub window: Arc<Window>,
 
@@ -1684,18 +2012,23 @@ pub struct Gpu {
 }
 
 impl Gpu {
-    fn pick_present_mode(vsync: bool) -> PresentMode {
+    /// Picks the present mode. Without vsync, mailbox is preferred if asked for and supported,
+    /// as it presents the latest frame on the next refresh without tearing.
+    fn pick_present_mode(vsync: bool, mailbox: bool, supported: &[PresentMode]) -> PresentMode {
         if vsync {
             PresentMode::AutoVsync
+        } else if mailbox && supported.contains(&PresentMode::Mailbox) {
+            PresentMode::Mailbox
         } else {
             PresentMode::AutoNoVsync
         }
     }
 
-    pub fn set_vsync(&mut self, vsync: bool) {
-        if self.vsync != vsync {
+    pub fn set_present_mode(&mut self, vsync: bool, mailbox: bool) {
+        if self.vsync != vsync || self.mailbox != mailbox {
             self.vsync = vsync;
-            self.config.present_mode = Self::pick_present_mode(vsync);
+            self.mailbox = mailbox;
+            self.config.present_mode = Self::pick_present_mode(vsync, mailbox, &self.present_modes);
 
             self.surface.configure(&self.device, &self.config);
         }
@@ -1714,7 +2047,7 @@ impl Gpu {
         shared_resources.create(&self.device, &self.config, global_resources);
     }
 
-    pub async fn new(window: Arc<Window>, vsync: bool) -> Self {
+    pub async fn new(window: Arc<Window>, vsync: bool, mailbox: bool) -> anyhow::Result<Self> {
         let size = window.inner_size();
 
         // The instance is a handle to our GPU
@@ -1725,7 +2058,7 @@ impl Gpu {
             ..Default::default()
         });
 
-        let surface = instance.create_surface(window.clone()).unwrap();
+        let surface = instance.create_surface(window.clone())?;
 
         let adapter = instance
             .request_adapter(&RequestAdapterOptions {
@@ -1735,7 +2068,7 @@ impl Gpu {
                 force_fallback_adapter: false,
             })
             .await
-            .unwrap();
+            .context("no compatible graphics adapter")?;
 
         let (device, queue) = adapter
             .request_device(
@@ -1753,8 +2086,19 @@ impl Gpu {
                 },
                 None,
             )
-            .await
-            .unwrap();
+            .await?;
+
+        device.set_device_lost_callback(|reason, message| {
+            // the device is also lost when it is destroyed or dropped on purpose
+            if matches!(
+                reason,
+                DeviceLostReason::Unknown | DeviceLostReason::DeviceInvalid
+            ) {
+                log::error!("The graphics device was lost ({reason:?}): {message}");
+
+                DEVICE_LOST.store(true, Ordering::Relaxed);
+            }
+        });
 
         let surface_caps = surface.get_capabilities(&adapter);
         let surface_format = surface_caps
@@ -1769,7 +2113,7 @@ impl Gpu {
             format: surface_format,
             width: size.width,
             height: size.height,
-            present_mode: Self::pick_present_mode(vsync),
+            present_mode: Self::pick_present_mode(vsync, mailbox, &surface_caps.present_modes),
             alpha_mode: surface_caps.alpha_modes[0],
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
@@ -1777,8 +2121,10 @@ impl Gpu {
 
         surface.configure(&device, &config);
 
-        Gpu {
+        Ok(Gpu {
             vsync,
+            mailbox,
+            present_modes: surface_caps.present_modes,
 
             window,
 
@@ -1788,6 +2134,6 @@ impl Gpu {
             queue,
             surface,
             config,
-        }
+        })
     }
 }
@@ -0,0 +1,144 @@
+use crate::GameState;
+use automancy_defs::colors;
+use automancy_defs::coord::TileCoord;
+use automancy_defs::glam::vec3;
+use automancy_defs::id::ModelId;
+use automancy_defs::math::{Float, Matrix4, FAR, HEX_GRID_LAYOUT};
+use automancy_defs::rendering::{GameMatrix, InstanceData};
+use automancy_system::map::Beacon;
+use automancy_system::ui_state::{PopupAction, PopupState, TextField};
+use automancy_ui::{button, col, colored_label, label, row, window_box, PADDING_LARGE};
+use yakui::{
+    widgets::{Absolute, Layer, Pad},
+    Alignment, Color, Dim2, Pivot,
+};
+
+const BEACON_WIDTH: Float = 0.15;
+const BEACON_HEIGHT: Float = 1.5;
+
+/// The colors new beacons cycle through.
+const BEACON_COLORS: [Color; 4] = [
+    colors::RED,
+    colors::ORANGE,
+    colors::INPUT,
+    colors::LIGHT_BLUE,
+];
+
+fn beacon_color(beacon: &Beacon) -> Color {
+    let [r, g, b] = beacon.color;
+
+    Color { r, g, b, a: 255 }
+}
+
+/// Adds a beacon at the coordinate, named after the beacon name text field.
+pub fn add_beacon(state: &mut GameState, coord: TileCoord) {
+    let mut name = state.ui_state.text_field.take(TextField::BeaconName);
+
+    let Some((map_info, _)) = &state.loop_store.map_info else {
+        return;
+    };
+    let mut lock = map_info.blocking_lock();
+    let beacons = &mut lock.beacons;
+
+    if name.trim().is_empty() {
+        name = coord.to_string();
+    }
+
+    let color = BEACON_COLORS[beacons.len() % BEACON_COLORS.len()];
+
+    beacons.push(Beacon {
+        coord,
+        name,
+        color: [color.r, color.g, color.b],
+    });
+}
+
+/// Moves the camera to the beacon after the one it last jumped to.
+pub fn jump_to_next_beacon(state: &mut GameState, beacons: &[Beacon]) {
+    if beacons.is_empty() {
+        return;
+    }
+
+    state.ui_state.beacon_index = (state.ui_state.beacon_index + 1) % beacons.len();
+    state
+        .camera
+        .jump_to(beacons[state.ui_state.beacon_index].coord);
+}
+
+/// Draws a beam over every beacon. They aren't culled, so that they can be seen from anywhere on the map.
+pub fn render_beacons(state: &mut GameState, beacons: &[Beacon]) {
+    for beacon in beacons {
+        let p = HEX_GRID_LAYOUT.hex_to_world_pos(*beacon.coord);
+
+        state.renderer.as_mut().unwrap().overlay_instances.push((
+            InstanceData::default().with_color_offset(beacon_color(beacon).to_linear()),
+            ModelId(state.resource_man.registry.model_ids.cube1x1),
+            GameMatrix::<true>::new(
+                Matrix4::from_translation(vec3(p.x, p.y, FAR))
+                    * Matrix4::from_scale(vec3(BEACON_WIDTH, BEACON_WIDTH, BEACON_HEIGHT)),
+                state.camera.get_matrix(),
+                Matrix4::IDENTITY,
+            ),
+            0,
+        ));
+    }
+}
+
+/// Draws the sidebar listing the beacons of the map.
+pub fn beacon_sidebar(state: &mut GameState, beacons: &mut Vec<Beacon>) {
+    let gui_ids = state.resource_man.registry.gui_ids;
+    // the beacons of a map opened read-only can be jumped to, but not changed
+    let read_only = state.ui_state.read_only;
+
+    Absolute::new(Alignment::CENTER_LEFT, Pivot::CENTER_LEFT, Dim2::ZERO).show(|| {
+        Layer::new().show(|| {
+            Pad::all(PADDING_LARGE).show(|| {
+                window_box(
+                    state.resource_man.gui_str(gui_ids.beacons).to_string(),
+                    || {
+                        col(|| {
+                            let mut removed = None;
+
+                            for (index, beacon) in beacons.iter().enumerate() {
+                                row(|| {
+                                    colored_label(&beacon.name, beacon_color(beacon));
+                                    label(&beacon.coord.to_string());
+
+                                    if button(&state.resource_man.gui_str(gui_ids.btn_go_to))
+                                        .clicked
+                                    {
+                                        state.ui_state.beacon_index = index;
+                                        state.camera.jump_to(beacon.coord);
+                                    }
+
+                                    if !read_only
+                                        && button(&state.resource_man.gui_str(gui_ids.btn_delete))
+                                            .clicked
+                                    {
+                                        removed = Some(index);
+                                    }
+                                });
+                            }
+
+                            if let Some(index) = removed {
+                                beacons.remove(index);
+                            }
+
+                            if !read_only
+                                && button(&state.resource_man.gui_str(gui_ids.btn_add_beacon))
+                                    .clicked
+                            {
+                                state.ui_state.push_popup(PopupState::TextInput {
+                                    title: gui_ids.btn_add_beacon,
+                                    label: gui_ids.lbl_beacon_name,
+                                    field: TextField::BeaconName,
+                                    action: PopupAction::AddBeacon(state.camera.pointing_at),
+                                });
+                            }
+                        });
+                    },
+                );
+            });
+        });
+    });
+}
@@ -0,0 +1,158 @@
+use crate::gui::toast::{push_toast, SHORT_TOAST};
+use crate::GameState;
+use automancy_resources::format::Formattable;
+use automancy_system::blueprint::{self, load_blueprint, save_blueprint};
+use automancy_system::map;
+use automancy_system::ui_state::TextField;
+use automancy_ui::{
+    button, col, label, row, scroll_vertical, textbox, virtual_list, window, Toast, ToastPriority,
+};
+use yakui::Vec2;
+
+/// Saves what was last copied as a blueprint, named after what was typed in.
+fn save_copied(state: &mut GameState) {
+    let gui_ids = state.resource_man.registry.gui_ids;
+
+    let Some(origin) = state.ui_state.paste_from else {
+        return;
+    };
+
+    let name = map::sanitize_name(state.ui_state.text_field.take(TextField::BlueprintName));
+
+    push_toast(
+        match save_blueprint(
+            &state.resource_man.interner,
+            &name,
+            origin,
+            &state.ui_state.paste_content,
+        ) {
+            Ok(()) => Toast::new(
+                state.resource_man.gui_fmt(
+                    gui_ids.lbl_blueprint_saved,
+                    [("name", Formattable::display(&name))],
+                ),
+                ToastPriority::Normal,
+            )
+            .with_timeout(SHORT_TOAST),
+            Err(err) => {
+                log::error!("Could not save blueprint {name}: {err}");
+
+                Toast::new(
+                    state.resource_man.gui_fmt(
+                        gui_ids.lbl_blueprint_save_failed,
+                        [("error", Formattable::display(&err))],
+                    ),
+                    ToastPriority::High,
+                )
+            }
+        },
+    );
+}
+
+/// Picks up the blueprint to be placed the same way as pasting, from where the camera is pointing at.
+fn place(state: &mut GameState, name: &str) {
+    match load_blueprint(&state.resource_man, name) {
+        Ok(tiles) => {
+            let origin = state.camera.pointing_at;
+
+            state.ui_state.paste_content = tiles
+                .into_iter()
+                .map(|(coord, id, data)| (coord + origin, id, data))
+                .collect();
+            state.ui_state.paste_from = Some(origin);
+
+            state.play_sound("click");
+        }
+        Err(err) => {
+            log::error!("Could not load blueprint {name}: {err}");
+
+            push_toast(Toast::new(
+                state.resource_man.gui_fmt(
+                    state
+                        .resource_man
+                        .registry
+                        .gui_ids
+                        .lbl_blueprint_load_failed,
+                    [("error", Formattable::display(&err))],
+                ),
+                ToastPriority::High,
+            ));
+        }
+    }
+}
+
+/// Draws the blueprint panel, which saves what was copied as a blueprint, and lists the saved ones to be placed.
+pub fn blueprints_ui(state: &mut GameState) {
+    let Some(mut names) = state.ui_state.blueprints.take() else {
+        return;
+    };
+
+    let gui_ids = state.resource_man.registry.gui_ids;
+    let mut open = true;
+
+    window(
+        state.resource_man.gui_str(gui_ids.blueprints).to_string(),
+        || {
+            col(|| {
+                if state.ui_state.paste_from.is_some() && !state.ui_state.paste_content.is_empty() {
+                    row(|| {
+                        let activated = textbox(
+                            state.ui_state.text_field.get(TextField::BlueprintName),
+                            None,
+                            None,
+                        )
+                        .activated;
+
+                        if button(&state.resource_man.gui_str(gui_ids.btn_save_blueprint)).clicked
+                            || activated
+                        {
+                            save_copied(state);
+
+                            names = blueprint::list_blueprints();
+                        }
+                    });
+                } else {
+                    label(&state.resource_man.gui_str(gui_ids.lbl_blueprint_copy_first));
+                }
+
+                let mut deleted = false;
+
+                scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 240.0), || {
+                    virtual_list(names.len(), |index| {
+                        let name = &names[index];
+
+                        row(|| {
+                            label(name);
+
+                            if button(&state.resource_man.gui_str(gui_ids.btn_place_blueprint))
+                                .clicked
+                            {
+                                place(state, name);
+                            }
+
+                            if button(&state.resource_man.gui_str(gui_ids.btn_delete)).clicked {
+                                if let Err(err) = blueprint::delete_blueprint(name) {
+                                    log::error!("Could not delete blueprint {name}: {err}");
+                                }
+
+                                deleted = true;
+                            }
+                        });
+                    });
+                });
+
+                if deleted {
+                    names = blueprint::list_blueprints();
+                }
+
+                if button(&state.resource_man.gui_str(gui_ids.btn_exit)).clicked {
+                    open = false;
+                }
+            });
+        },
+    );
+
+    if open {
+        state.ui_state.blueprints = Some(names);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::GameState;
+use automancy_defs::colors;
+use automancy_defs::glam::vec3;
+use automancy_defs::id::Id;
+use automancy_defs::math::HEX_GRID_LAYOUT;
+use automancy_resources::feedback::take_cutscenes;
+use automancy_resources::types::cutscene::CutsceneStep;
+use automancy_system::ui_state::CutscenePlayback;
+use automancy_ui::{colored_label, group, heading, PADDING_LARGE};
+use std::time::Instant;
+use yakui::widgets::{Absolute, Layer, Pad};
+use yakui::{Alignment, Dim2, Pivot};
+
+/// Plays the cutscene, or queues it if one is already playing.
+pub fn play_cutscene(state: &mut GameState, id: Id) {
+    if !state.resource_man.registry.cutscenes.contains_key(&id) {
+        log::warn!(
+            "Not playing the cutscene {}, as it isn't registered",
+            state.resource_man.interner.resolve(id).unwrap_or("<?>")
+        );
+        return;
+    }
+
+    if state.ui_state.cutscene.is_some() {
+        state.ui_state.queued_cutscenes.push_back(id);
+    } else {
+        state.ui_state.cutscene = Some(CutscenePlayback::new(id));
+    }
+}
+
+/// Skips the cutscene playing, if it can be skipped. The camera stays where it is.
+pub fn skip_cutscene(state: &mut GameState) {
+    let skippable = state
+        .ui_state
+        .cutscene
+        .as_ref()
+        .and_then(|v| state.resource_man.registry.cutscenes.get(&v.id))
+        .is_none_or(|v| v.skippable);
+
+    if skippable {
+        next_cutscene(state);
+    }
+}
+
+fn next_cutscene(state: &mut GameState) {
+    state.ui_state.cutscene = state
+        .ui_state
+        .queued_cutscenes
+        .pop_front()
+        .map(CutscenePlayback::new);
+}
+
+/// Draws a text card at the bottom of the screen.
+fn text_card(state: &GameState, text: Id, skippable: bool) {
+    Absolute::new(Alignment::BOTTOM_CENTER, Pivot::BOTTOM_CENTER, Dim2::ZERO).show(|| {
+        Layer::new().show(|| {
+            Pad::all(PADDING_LARGE).show(|| {
+                group(|| {
+                    heading(&state.resource_man.gui_str(text));
+
+                    if skippable {
+                        colored_label(
+                            &state
+                                .resource_man
+                                .gui_str(state.resource_man.registry.gui_ids.lbl_cutscene_skip),
+                            colors::GRAY,
+                        );
+                    }
+                });
+            });
+        });
+    });
+}
+
+/// Plays the cutscenes scripts asked for, and the step of the one playing: moves the camera, plays sounds, and draws
+/// text cards. Returns whether a cutscene is playing.
+pub fn cutscene_ui(state: &mut GameState) -> bool {
+    for id in take_cutscenes() {
+        play_cutscene(state, id);
+    }
+
+    let resource_man = state.resource_man.clone();
+
+    loop {
+        let Some(mut playback) = state.ui_state.cutscene.clone() else {
+            return false;
+        };
+
+        let Some(def) = resource_man.registry.cutscenes.get(&playback.id) else {
+            next_cutscene(state);
+            continue;
+        };
+
+        let Some(step) = def.steps.get(playback.step) else {
+            next_cutscene(state);
+            continue;
+        };
+
+        let start = match playback.step_start {
+            Some(start) => start,
+            None => {
+                match step {
+                    CutsceneStep::Camera { .. } => {
+                        playback.camera_from = Some(state.camera.raw_pos());
+                    }
+                    CutsceneStep::Sound(name) => {
+                        state.play_sound(name);
+                    }
+                    _ => {}
+                }
+
+                *playback.step_start.insert(Instant::now())
+            }
+        };
+
+        let duration = step.duration();
+        let t = if duration.is_zero() {
+            1.0
+        } else {
+            start.elapsed().as_secs_f32() / duration.as_secs_f32()
+        };
+
+        match step {
+            CutsceneStep::Camera { to, zoom, .. } => {
+                if let Some(from) = playback.camera_from {
+                    let p = HEX_GRID_LAYOUT.hex_to_world_pos(**to);
+
+                    state
+                        .camera
+                        .glide(from, vec3(p.x, p.y, zoom.unwrap_or(from.z)), t);
+                }
+            }
+            CutsceneStep::Text { text, .. } if t < 1.0 => {
+                text_card(state, *text, def.skippable);
+            }
+            _ => {}
+        }
+
+        if t < 1.0 {
+            state.ui_state.cutscene = Some(playback);
+
+            return true;
+        }
+
+        playback.step += 1;
+        playback.step_start = None;
+        playback.camera_from = None;
+
+        state.ui_state.cutscene = Some(playback);
+    }
+}
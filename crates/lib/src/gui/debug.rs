@@ -1,9 +1,312 @@
 use crate::GameState;
-use automancy_defs::colors::BACKGROUND_3;
-use automancy_ui::{col, label, movable, window, DIVIER_HEIGHT, DIVIER_THICKNESS};
+use automancy_defs::{
+    colors::{self, BACKGROUND_3},
+    coord::TileCoord,
+    id::Id,
+};
+use automancy_resources::profiler::SCRIPT_PROFILER;
+use automancy_resources::{ResourceManager, RESOURCES_PATH};
+use automancy_system::game::{GameSystemMessage, GAME_MAILBOX};
+use automancy_system::map::{TileDiff, MAP_EXPORT_PATH};
+use automancy_system::tile_entity::TILE_ENTITY_MAILBOX;
+use automancy_ui::{
+    button, checkbox, col, colored_label, label, movable, row, window, DIVIER_HEIGHT,
+    DIVIER_THICKNESS,
+};
+use ractor::rpc::CallResult;
 use ron::ser::PrettyConfig;
+use std::fs;
+use std::path::Path;
 use yakui::{divider, widgets::Layer};
 
+/// The maximum amount of changed tiles listed in the diff view.
+const MAX_DIFF_ENTRIES: usize = 32;
+/// The maximum amount of missing keys listed per language in the translation coverage view, and of broken references
+/// and unused assets in the asset report. The rest are only logged.
+const MAX_MISSING_ENTRIES: usize = 8;
+/// How many of the latest ticks the game keeps snapshots of while rewinding is turned on.
+const REWIND_TICKS: usize = 120;
+/// The maximum amount of script functions listed in the script profiler, the ones that took the longest.
+const MAX_PROFILE_ENTRIES: usize = 16;
+/// The file the asset report is exported to, in the export folder.
+const ASSET_REPORT_FILE: &str = "asset_report.md";
+
+/// Draws the snapshot diff panel, comparing two captured snapshots of the map.
+fn snapshot_diff(state: &mut GameState) {
+    row(|| {
+        for (idx, name) in ["A", "B"].into_iter().enumerate() {
+            if button(&format!("Capture {name}")).clicked {
+                if let Ok(CallResult::Success(snapshot)) = state
+                    .tokio
+                    .block_on(state.game.call(GameSystemMessage::SnapshotAll, None))
+                {
+                    state.ui_state.debug_snapshots[idx] = Some(snapshot);
+                }
+            }
+        }
+    });
+
+    let [Some(a), Some(b)] = &state.ui_state.debug_snapshots else {
+        label("Capture two snapshots to compare them.");
+        return;
+    };
+
+    let diffs = a.diff(b);
+
+    label(&format!("Changed tiles: {}", diffs.len()));
+
+    diff_list(&state.resource_man, &diffs);
+}
+
+/// Lists the tiles that changed between two snapshots.
+fn diff_list(resource_man: &ResourceManager, diffs: &[(TileCoord, TileDiff)]) {
+    let key_names = |keys: &[Id]| {
+        keys.iter()
+            .map(|key| resource_man.interner.resolve(*key).unwrap_or("<?>"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    for (coord, diff) in diffs.iter().take(MAX_DIFF_ENTRIES) {
+        label(&match diff {
+            TileDiff::Added(id) => format!("{coord}: added {}", resource_man.tile_name(*id)),
+            TileDiff::Removed(id) => format!("{coord}: removed {}", resource_man.tile_name(*id)),
+            TileDiff::Replaced(old, new) => format!(
+                "{coord}: {} -> {}",
+                resource_man.tile_name(*old),
+                resource_man.tile_name(*new)
+            ),
+            TileDiff::DataChanged(id, keys) => format!(
+                "{coord}: {} changed [{}]",
+                resource_man.tile_name(*id),
+                key_names(keys)
+            ),
+        });
+    }
+
+    if diffs.len() > MAX_DIFF_ENTRIES {
+        label(&format!("... and {} more", diffs.len() - MAX_DIFF_ENTRIES));
+    }
+}
+
+/// Draws the rewind panel, stepping through snapshots of the latest ticks. The live game isn't affected.
+fn rewind(state: &mut GameState) {
+    let mut recording = state.ui_state.rewind_recording;
+
+    row(|| {
+        label("Keep Tick Snapshots:");
+        checkbox(&mut recording);
+    });
+
+    if recording != state.ui_state.rewind_recording {
+        state.ui_state.rewind_recording = recording;
+        state.ui_state.rewind = None;
+
+        state
+            .game
+            .send_message(GameSystemMessage::SetRewind(
+                recording.then_some(REWIND_TICKS),
+            ))
+            .unwrap();
+    }
+
+    if !recording {
+        return;
+    }
+
+    row(|| {
+        if button("Inspect").clicked {
+            if let Ok(CallResult::Success(snapshots)) = state
+                .tokio
+                .block_on(state.game.call(GameSystemMessage::GetRewind, None))
+            {
+                let last = snapshots.len().saturating_sub(1);
+
+                state.ui_state.rewind = Some((snapshots, last));
+            }
+        }
+
+        if let Some((snapshots, step)) = &mut state.ui_state.rewind {
+            if button("< Back").clicked {
+                *step = step.saturating_sub(1);
+            }
+
+            if button("Forward >").clicked && *step + 1 < snapshots.len() {
+                *step += 1;
+            }
+        }
+    });
+
+    let Some((snapshots, step)) = &state.ui_state.rewind else {
+        label("Inspect to step through the kept ticks. The game keeps running.");
+        return;
+    };
+
+    let Some((tick, snapshot)) = snapshots.get(*step) else {
+        label("No ticks were kept yet.");
+        return;
+    };
+
+    label(&format!("Tick {tick} ({}/{})", step + 1, snapshots.len()));
+
+    let resource_man = &state.resource_man;
+    let coord = state.camera.pointing_at;
+
+    if let Some((id, data)) = snapshot.0.get(&coord) {
+        label(&format!("{coord}: {}", resource_man.tile_name(*id)));
+
+        for key in data.keys() {
+            label(&format!(
+                "  {} = {:?}",
+                resource_man.interner.resolve(*key).unwrap_or("<?>"),
+                data.get(*key)
+            ));
+        }
+    } else {
+        label(&format!("{coord}: no tile"));
+    }
+
+    if let Some((_, previous)) = step.checked_sub(1).and_then(|v| snapshots.get(v)) {
+        let diffs = previous.diff(snapshot);
+
+        label(&format!("Changed since the tick before: {}", diffs.len()));
+
+        diff_list(resource_man, &diffs);
+    }
+}
+
+/// Draws the asset report panel, listing the assets nothing references and the references to assets that aren't
+/// loaded.
+fn asset_report(state: &mut GameState) {
+    row(|| {
+        if button("Check Assets").clicked {
+            let report = state.resource_man.asset_report();
+
+            for (from, to) in &report.broken {
+                log::warn!("Broken reference: {from} references {to}, which isn't there");
+            }
+            for asset in &report.unused {
+                log::info!("Unused asset: {asset}");
+            }
+
+            state.ui_state.asset_report = Some(report);
+        }
+
+        if let Some(report) = &state.ui_state.asset_report {
+            if button("Export Report").clicked {
+                let path = Path::new(MAP_EXPORT_PATH).join(ASSET_REPORT_FILE);
+
+                match fs::create_dir_all(MAP_EXPORT_PATH)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|_| report.write(&path))
+                {
+                    Ok(()) => log::info!("Wrote the asset report to {path:?}"),
+                    Err(err) => log::error!("Could not write the asset report to {path:?}: {err}"),
+                }
+            }
+        }
+    });
+
+    let Some(report) = &state.ui_state.asset_report else {
+        return;
+    };
+
+    label(&format!("Broken References: {}", report.broken.len()));
+    for (from, to) in report.broken.iter().take(MAX_MISSING_ENTRIES) {
+        colored_label(&format!("  {from} -> {to}"), colors::RED);
+    }
+    if report.broken.len() > MAX_MISSING_ENTRIES {
+        label(&format!(
+            "  ... and {} more",
+            report.broken.len() - MAX_MISSING_ENTRIES
+        ));
+    }
+
+    label(&format!("Unused Assets: {}", report.unused.len()));
+    for asset in report.unused.iter().take(MAX_MISSING_ENTRIES) {
+        label(&format!("  {asset}"));
+    }
+    if report.unused.len() > MAX_MISSING_ENTRIES {
+        label(&format!(
+            "  ... and {} more",
+            report.unused.len() - MAX_MISSING_ENTRIES
+        ));
+    }
+}
+
+/// Draws the script profiler panel, listing the script functions that took the longest while recording.
+fn script_profiler() {
+    if !SCRIPT_PROFILER.available() {
+        label("Start the game with --profile-scripts to profile the scripts.");
+        return;
+    }
+
+    let mut recording = SCRIPT_PROFILER.recording();
+
+    row(|| {
+        label("Profile Scripts:");
+        checkbox(&mut recording);
+
+        if button("Clear").clicked {
+            SCRIPT_PROFILER.clear();
+        }
+    });
+
+    SCRIPT_PROFILER.set_recording(recording);
+
+    for ((script, function), profile) in SCRIPT_PROFILER.slowest(MAX_PROFILE_ENTRIES) {
+        label(&format!(
+            "{script} {function}: Calls={} Total={:?} Average={:?} Peak={:?} Operations/Call={}",
+            profile.calls,
+            profile.total,
+            profile.average(),
+            profile.peak,
+            profile.operations / profile.calls.max(1)
+        ));
+    }
+}
+
+/// Draws the translation coverage panel, listing the keys each language is missing.
+fn translation_coverage(state: &mut GameState) {
+    if button("Check Translations").clicked {
+        let coverage = state
+            .resource_man
+            .translation_coverage(Path::new(RESOURCES_PATH));
+
+        for language in &coverage {
+            for key in &language.missing {
+                log::info!("Untranslated in {}: {key}", language.language);
+            }
+        }
+
+        state.ui_state.translation_coverage = Some(coverage);
+    }
+
+    let Some(coverage) = &state.ui_state.translation_coverage else {
+        return;
+    };
+
+    for language in coverage {
+        label(&format!(
+            "{}: {}/{} translated",
+            language.language,
+            language.total - language.missing.len(),
+            language.total
+        ));
+
+        for key in language.missing.iter().take(MAX_MISSING_ENTRIES) {
+            label(&format!("  missing {key}"));
+        }
+
+        if language.missing.len() > MAX_MISSING_ENTRIES {
+            label(&format!(
+                "  ... and {} more",
+                language.missing.len() - MAX_MISSING_ENTRIES
+            ));
+        }
+    }
+}
+
 /// Draws the debug menu (F3).
 pub fn debugger(state: &mut GameState) {
     let fps = 1.0 / state.loop_store.elapsed.as_secs_f64();
@@ -14,9 +317,18 @@ pub fn debugger(state: &mut GameState) {
     let functions = state.resource_man.functions.len();
     let scripts = state.resource_man.registry.scripts.len();
     let audio = state.resource_man.audio.len();
+    let audio_decoded = state.resource_man.audio.decoded_len();
     let meshes = state.resource_man.all_meshes_anims.len();
 
-    let Some((info, map_name)) = &state.loop_store.map_info else {
+    let tick_stats = match state
+        .tokio
+        .block_on(state.game.call(GameSystemMessage::GetTickStats, None))
+    {
+        Ok(CallResult::Success(stats)) => Some(stats),
+        _ => None,
+    };
+
+    let Some((info, map_name)) = state.loop_store.map_info.clone() else {
         return;
     };
 
@@ -32,6 +344,34 @@ pub fn debugger(state: &mut GameState) {
                 || {
                     col(|| {
                         label(&format!("FPS: {fps:.1}"));
+                        if let Some(stats) = tick_stats {
+                            let text = format!(
+                                "TPS: {}/{:.0} Tick Time: {:?}",
+                                stats.tps, stats.target_tps, stats.last_tick_time
+                            );
+
+                            if stats.behind {
+                                colored_label(&text, colors::RED);
+                            } else {
+                                label(&text);
+                            }
+
+                            label(&format!("Sleeping Tiles: {}", stats.sleeping));
+                        }
+
+                        for (name, stats) in [
+                            ("Game", GAME_MAILBOX.take_stats()),
+                            ("Tile Entities", TILE_ENTITY_MAILBOX.take_stats()),
+                        ] {
+                            label(&format!(
+                                "{name} Mailbox: Queued Ticks={} Average={:?} Peak={:?}",
+                                stats.queued, stats.average, stats.peak
+                            ));
+                        }
+
+                        if tick_stats.is_some_and(|v| v.overloaded) {
+                            colored_label("Overloaded, deferring render updates", colors::RED);
+                        }
                         label(&format!(
                             "WGPU: {}",
                             ron::ser::to_string_pretty(
@@ -43,7 +383,11 @@ pub fn debugger(state: &mut GameState) {
 
                         divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
 
-                        label(&format!("ResourceMan: Tiles={reg_tiles} Items={reg_items} Tags={tags} Functions={functions} Scripts={scripts} Audio={audio} Meshes={meshes}"));
+                        label(&format!("ResourceMan: Tiles={reg_tiles} Items={reg_items} Tags={tags} Functions={functions} Scripts={scripts} Audio={audio} (decoded {audio_decoded}) Meshes={meshes}"));
+
+                        if button("Model Viewer").clicked {
+                            state.ui_state.model_viewer_open = !state.ui_state.model_viewer_open;
+                        }
 
                         divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
 
@@ -57,6 +401,26 @@ pub fn debugger(state: &mut GameState) {
                             )
                             .unwrap_or("could not format map info".to_string()),
                         ));
+
+                        divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
+
+                        snapshot_diff(state);
+
+                        divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
+
+                        rewind(state);
+
+                        divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
+
+                        translation_coverage(state);
+
+                        divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
+
+                        asset_report(state);
+
+                        divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
+
+                        script_profiler();
                     });
                 }
             );
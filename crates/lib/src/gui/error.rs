@@ -1,37 +1,73 @@
 use crate::GameState;
-use automancy_resources::error::{error_to_key, peek_err, pop_err};
-use automancy_ui::{button, label, row_max, window};
-use yakui::{spacer, widgets::Layer};
-
-/// Draws an error popup. Can only be called when there are errors in the queue!
-pub fn error_popup(state: &mut GameState) {
-    if let Some((id, err)) = peek_err() {
-        Layer::new().show(|| {
-            window(
-                state
-                    .resource_man
-                    .gui_str(state.resource_man.registry.gui_ids.error_popup)
-                    .to_string(),
-                || {
-                    label(&format!("ID: {}", error_to_key(id, &state.resource_man)));
-
-                    label(&err);
-
-                    row_max(|| {
-                        spacer(1);
-
-                        if button(
-                            &state
-                                .resource_man
-                                .gui_str(state.resource_man.registry.gui_ids.btn_confirm),
-                        )
-                        .clicked
-                        {
-                            pop_err();
-                        }
-                    });
-                },
-            );
-        });
-    }
+use automancy_defs::colors;
+use automancy_resources::{
+    error::{err_history, error_to_key, ErrorSeverity, GameError},
+    format_time, ResourceManager,
+};
+use automancy_ui::{
+    button, col, colored_label, label, row, row_max, scroll_vertical, virtual_list, window,
+};
+use yakui::{spacer, widgets::Layer, Vec2};
+
+fn severity_label(resource_man: &ResourceManager, err: &GameError) {
+    let gui_ids = &resource_man.registry.gui_ids;
+
+    let (id, color) = match err.severity {
+        ErrorSeverity::Info => (gui_ids.lbl_error_info, colors::INPUT),
+        ErrorSeverity::Warn => (gui_ids.lbl_error_warn, colors::ORANGE),
+        ErrorSeverity::Fatal => (gui_ids.lbl_error_fatal, colors::RED),
+    };
+
+    colored_label(&resource_man.gui_str(id), color);
+}
+
+/// Draws the history of recent errors, newest first.
+pub fn error_history(state: &mut GameState) {
+    let gui_ids = state.resource_man.registry.gui_ids;
+
+    Layer::new().show(|| {
+        window(
+            state
+                .resource_man
+                .gui_str(gui_ids.error_history)
+                .to_string(),
+            || {
+                scroll_vertical(
+                    Vec2::ZERO,
+                    Vec2::new(state.ui_viewport().x * 0.5, 260.0),
+                    || {
+                        let errs = err_history();
+
+                        virtual_list(errs.len(), |index| {
+                            let err = &errs[errs.len() - 1 - index];
+
+                            col(|| {
+                                row(|| {
+                                    severity_label(&state.resource_man, err);
+                                    label(&format_time(
+                                        err.time,
+                                        &state.resource_man.gui_str(gui_ids.time_fmt),
+                                    ));
+                                    label(&format!(
+                                        "ID: {}",
+                                        error_to_key(err.id, &state.resource_man)
+                                    ));
+                                });
+
+                                label(&err.message);
+                            });
+                        });
+                    },
+                );
+
+                row_max(|| {
+                    spacer(1);
+
+                    if button(&state.resource_man.gui_str(gui_ids.btn_confirm)).clicked {
+                        state.ui_state.error_history_open = false;
+                    }
+                });
+            },
+        );
+    });
 }
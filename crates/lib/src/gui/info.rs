@@ -1,6 +1,7 @@
 use crate::GameState;
 use automancy_defs::{colors, glam::vec2, id::TileId, rendering::InstanceData};
 use automancy_resources::{data::DataMap, types::IconMode};
+use automancy_system::input::ActionType;
 use automancy_ui::{
     col, col_align_end, colored_label, colored_sized_text, group, label, row, ui_game_object,
     window_box, UiGameObjectType, LABEL_SIZE, LARGE_ICON_SIZE, PADDING_LARGE,
@@ -25,10 +26,12 @@ fn input_hint_names(state: &mut GameState) {
             })
             .and_then(|v| v.name);
 
-        if let Some(name) = name.and_then(|name| state.resource_man.translates.keys.get(&name)) {
+        let translates = state.resource_man.translates();
+
+        if let Some(name) = name.and_then(|name| translates.keys.get(&name)) {
             label(name);
         } else {
-            label(&state.resource_man.translates.unnamed);
+            label(&translates.unnamed);
         }
     }
 }
@@ -126,6 +129,13 @@ pub fn info_ui(state: &mut GameState) {
                     || {
                         colored_label(&state.camera.pointing_at.to_string(), colors::DARK_GRAY);
 
+                        if state.input_handler.key_active(ActionType::Deconstruct) {
+                            colored_label(
+                                &state.ui_state.deconstruct_filter.name(&state.resource_man),
+                                colors::RED,
+                            );
+                        }
+
                         let Some((tile, _entity)) =
                             state.loop_store.pointing_cache.blocking_lock().clone()
                         else {
@@ -1,8 +1,20 @@
+use crate::GameState;
+use automancy_defs::id::{Id, SharedStr};
 use automancy_defs::math::Float;
 use automancy_defs::rendering::InstanceData;
 use automancy_defs::{glam::vec2, stack::ItemStack};
-use automancy_resources::{types::IconMode, ResourceManager};
-use automancy_ui::{center_row, label, ui_game_object, UiGameObjectType};
+use automancy_resources::{format::Formattable, types::IconMode, ResourceManager};
+use automancy_system::util::is_tile_locked;
+use automancy_ui::{
+    center_row, drag_ghost, dragged, interactive, label, label_text, ui_game_object,
+    UiGameObjectType, HOVER_TIP, MEDIUM_ICON_SIZE,
+};
+use std::cell::Cell;
+
+thread_local! {
+    /// The item whose icon is being hovered this frame, if any.
+    static HOVERED_ITEM: Cell<Option<Id>> = Cell::default();
+}
 
 /// Draws an Item's icon.
 pub fn draw_item(
@@ -15,13 +27,19 @@ pub fn draw_item(
     center_row(|| {
         prefix();
 
-        ui_game_object(
-            InstanceData::default(),
-            UiGameObjectType::Model(resource_man.item_model_or_missing(&stack.id)),
-            vec2(size, size),
-            Some(IconMode::Item.model_matrix()),
-            Some(IconMode::Item.world_matrix()),
-        );
+        let icon = interactive(|| {
+            ui_game_object(
+                InstanceData::default(),
+                UiGameObjectType::Model(resource_man.item_model_or_missing(&stack.id)),
+                vec2(size, size),
+                Some(IconMode::Item.model_matrix()),
+                Some(IconMode::Item.world_matrix()),
+            );
+        });
+
+        if icon.hovering {
+            HOVERED_ITEM.set(Some(stack.id));
+        }
 
         if add_label {
             if stack.amount > 0 {
@@ -36,3 +54,114 @@ pub fn draw_item(
         }
     });
 }
+
+/// A stack of items being dragged out of an inventory pane.
+#[derive(Debug, Clone, Copy)]
+pub struct DraggedStack {
+    /// the title of the pane it was dragged out of
+    pub from: Id,
+    pub stack: ItemStack,
+}
+
+/// Draws the stack being dragged next to the mouse, if there is one.
+pub fn dragged_stack_ghost(state: &mut GameState) {
+    if let Some(dragged) = dragged::<DraggedStack>() {
+        drag_ghost(|| {
+            draw_item(
+                &state.resource_man,
+                || {},
+                dragged.stack,
+                MEDIUM_ICON_SIZE,
+                true,
+            );
+        });
+    }
+}
+
+fn join_names(names: impl Iterator<Item = SharedStr>) -> String {
+    names.map(|v| v.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// Shows the tooltip of the item hovered this frame, listing its tags, the scripts that produce and use it,
+/// and whether it can currently be produced with the unlocked research.
+pub fn item_tooltip(state: &mut GameState) {
+    let Some(id) = HOVERED_ITEM.take() else {
+        return;
+    };
+
+    let resource_man = state.resource_man.clone();
+    let gui_ids = &resource_man.registry.gui_ids;
+
+    let mut lines = vec![resource_man.item_name(id).to_string()];
+
+    let tags = resource_man.get_item_tags(id);
+    if !tags.is_empty() {
+        lines.push(resource_man.gui_fmt(
+            gui_ids.lbl_item_tags,
+            [(
+                "tags",
+                Formattable::display(&join_names(
+                    tags.iter().map(|tag| resource_man.item_name(*tag)),
+                )),
+            )],
+        ));
+    }
+
+    let producers = resource_man.get_item_producers(id);
+    if !producers.is_empty() {
+        lines.push(
+            resource_man.gui_fmt(
+                gui_ids.lbl_item_produced_by,
+                [(
+                    "scripts",
+                    Formattable::display(&join_names(
+                        producers
+                            .iter()
+                            .map(|script| resource_man.script_name(*script)),
+                    )),
+                )],
+            ),
+        );
+    }
+
+    let consumers = resource_man.get_item_consumers(id);
+    if !consumers.is_empty() {
+        lines.push(
+            resource_man.gui_fmt(
+                gui_ids.lbl_item_used_by,
+                [(
+                    "scripts",
+                    Formattable::display(&join_names(
+                        consumers
+                            .iter()
+                            .map(|script| resource_man.script_name(*script)),
+                    )),
+                )],
+            ),
+        );
+    }
+
+    if let Some(map_info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) {
+        let mut lock = map_info.blocking_lock();
+        let game_data = &mut lock.data;
+
+        let producible = producers.iter().any(|script| {
+            resource_man
+                .get_script_tiles(*script)
+                .iter()
+                .any(|tile| !is_tile_locked(*tile, &resource_man, game_data))
+        });
+
+        lines.push(
+            resource_man
+                .gui_str(if producible {
+                    gui_ids.lbl_item_producible
+                } else {
+                    gui_ids.lbl_item_not_producible
+                })
+                .to_string(),
+        );
+    }
+
+    HOVER_TIP.set(Some(label_text(&lines.join("\n"))));
+}
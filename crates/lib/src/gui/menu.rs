@@ -1,27 +1,67 @@
 use crate::event::{refresh_maps, shutdown_graceful};
-use crate::{GameState, VERSION};
-use automancy_defs::{colors::BACKGROUND_3, glam::vec2, log};
+use crate::gui::toast::{push_toast, LONG_TOAST, SHORT_TOAST};
+use crate::{gui, GameState, VERSION};
+use automancy_defs::{
+    colors::{self, BACKGROUND_3},
+    glam::vec2,
+    log,
+};
 use automancy_resources::{
-    error::push_err,
+    error::{push_err, ErrorSeverity},
     format::{FormatContext, Formattable},
     format_time,
 };
-use automancy_system::map::{GameMap, LoadMapOption};
-use automancy_system::ui_state::{OptionsMenuState, PopupState, Screen, SubState, TextField};
+use automancy_system::map::{self, GameMap, LoadMapOption, MAP_PATH};
+use automancy_system::ui_state::{
+    OptionsMenuState, PopupAction, PopupState, Screen, SubState, TextField,
+};
 use automancy_system::{
-    game::{GameSystemMessage, COULD_NOT_LOAD_ANYTHING},
-    options::UiScale,
+    game::{GameSystemMessage, COULD_NOT_LOAD_ANYTHING, MAX_TPS},
+    options::{self, GameOptions, UiScale, UI_SCALE_RANGE},
+    signing::SaveSignature,
+    verify,
 };
 use automancy_system::{game_load_map, game_load_map_inner, GameLoadResult};
 use automancy_ui::{
-    button, center_col, center_row, checkbox, col, group, heading, label, pad_x, row,
-    scroll_horizontal_bar_alignment, scroll_vertical, selection_box, slider, stretch_col, textbox,
-    window, DIVIER_HEIGHT, DIVIER_THICKNESS, PADDING_LARGE, PADDING_MEDIUM, PADDING_SMALL,
+    button, center_col, center_row, checkbox, col, colored_label, drag_ghost, drag_source, dragged,
+    drop_target, group, heading, label, pad_x, row, scroll_horizontal_bar_alignment,
+    scroll_vertical, selection_box, slider, stretch_col, textbox, virtual_list, window, Toast,
+    ToastPriority, DIVIER_HEIGHT, DIVIER_THICKNESS, PADDING_LARGE, PADDING_MEDIUM, PADDING_SMALL,
 };
 use std::{fs, mem};
 use winit::event_loop::ActiveEventLoop;
 use yakui::{constrained, divider, image, spacer, widgets::Pad, Constraints, Vec2};
 
+/// Loads the map and switches to the matching screen.
+pub fn load_map(state: &mut GameState, map_name: String) {
+    match game_load_map(state, map_name) {
+        GameLoadResult::Loaded => {
+            state.ui_state.switch_screen(Screen::Ingame);
+        }
+        GameLoadResult::LoadedMainMenu => {
+            state.ui_state.switch_screen(Screen::MainMenu);
+        }
+        GameLoadResult::Failed => {
+            panic!("{}", COULD_NOT_LOAD_ANYTHING)
+        }
+    }
+}
+
+/// Loads the map, first asking the player to remap the tile IDs it references that no longer exist.
+pub fn try_load_map(state: &mut GameState, map_name: String) {
+    let missing = GameMap::missing_ids(
+        &state.resource_man,
+        &LoadMapOption::FromSave(map_name.clone()),
+    );
+
+    if missing.is_empty() {
+        load_map(state, map_name);
+    } else {
+        state.ui_state.id_remap_choices = missing.into_iter().map(|id| (id, None)).collect();
+        state.ui_state.push_popup(PopupState::MapRemap(map_name));
+    }
+}
+
 /// Draws the main menu.
 pub fn main_menu(state: &mut GameState, event_loop: &ActiveEventLoop) -> anyhow::Result<bool> {
     let mut result = Ok(false);
@@ -113,6 +153,41 @@ pub fn pause_menu(state: &mut GameState) {
             state.ui_state.switch_screen(Screen::Options)
         };
 
+        if button(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.btn_error_history),
+        )
+        .clicked
+        {
+            state.ui_state.error_history_open = !state.ui_state.error_history_open;
+        };
+
+        if let Some((_, LoadMapOption::FromSave(map_name))) = state.loop_store.map_info.clone() {
+            if button(
+                &state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.btn_verify_map),
+            )
+            .clicked
+            {
+                // the saved map is what gets verified, so it has to be up to date
+                state
+                    .tokio
+                    .block_on(state.game.call(GameSystemMessage::SaveMap, None))
+                    .unwrap()
+                    .unwrap();
+
+                let opt = LoadMapOption::FromSave(map_name.clone());
+
+                if let Some(report) = verify::verify_map(&state.resource_man, &opt) {
+                    state.ui_state.map_report = Some(report);
+                    state.ui_state.map_repair_error = None;
+                    state.ui_state.push_popup(PopupState::MapVerify(map_name));
+                }
+            };
+        }
+
         if button(
             &state
                 .resource_man
@@ -139,6 +214,162 @@ pub fn pause_menu(state: &mut GameState) {
     });
 }
 
+/// A save being dragged in the map menu, by its path in the map folder.
+#[derive(Debug, Clone)]
+struct DraggedMap(String);
+
+/// Draws the folder the map menu is showing, with buttons that open its parent and the folders in it. Saves dragged
+/// onto one are moved there, along with the other selected saves if the dragged one is selected.
+///
+/// Returns true if any save was moved.
+fn map_folders(state: &mut GameState) -> bool {
+    let gui_ids = state.resource_man.registry.gui_ids;
+    let current = state.ui_state.map_folder.clone();
+
+    let mut targets = vec![];
+    if !current.is_empty() {
+        targets.push((
+            map::split_save_path(&current).0.to_string(),
+            state
+                .resource_man
+                .gui_str(gui_ids.btn_parent_folder)
+                .to_string(),
+        ));
+    }
+    for folder in &state.loop_store.map_folders_cache {
+        let (parent, name) = map::split_save_path(folder);
+
+        if parent == current {
+            targets.push((folder.clone(), name.to_string()));
+        }
+    }
+
+    let mut moved = false;
+    let mut open = None;
+
+    label(&state.resource_man.gui_fmt(
+        gui_ids.lbl_map_folder,
+        [(
+            "folder",
+            Formattable::display(&map::join_save_path(MAP_PATH, &current)),
+        )],
+    ));
+
+    row(|| {
+        for (folder, name) in targets {
+            let dropped = drop_target::<DraggedMap>(|| {
+                if button(&name).clicked {
+                    open = Some(folder.clone());
+                }
+            });
+
+            if let Some(DraggedMap(map_name)) = dropped {
+                let map_names = if state.ui_state.selected_maps.contains(&map_name) {
+                    state.ui_state.selected_maps.drain().collect::<Vec<_>>()
+                } else {
+                    vec![map_name]
+                };
+
+                for map_name in map_names {
+                    if let Err(err) = map::move_save(&map_name, &folder) {
+                        log::error!("Could not move map {map_name} to {folder}: {err}");
+                    }
+                }
+
+                moved = true;
+            }
+        }
+
+        if button(&state.resource_man.gui_str(gui_ids.btn_new_folder)).clicked {
+            state.ui_state.push_popup(PopupState::TextInput {
+                title: gui_ids.create_folder,
+                label: gui_ids.lbl_folder_name,
+                field: TextField::FolderName,
+                action: PopupAction::CreateFolder,
+            });
+        }
+    });
+
+    if let Some(folder) = open {
+        state.ui_state.map_folder = folder;
+        state.ui_state.selected_maps.clear();
+    }
+
+    moved
+}
+
+/// Draws the batch operations on the saves selected in the map menu, if any are.
+fn selected_maps_actions(state: &mut GameState) {
+    if state.ui_state.selected_maps.is_empty() {
+        return;
+    }
+
+    let gui_ids = state.resource_man.registry.gui_ids;
+    let mut selected = state
+        .ui_state
+        .selected_maps
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    selected.sort();
+
+    row(|| {
+        label(&state.resource_man.gui_fmt(
+            gui_ids.lbl_maps_selected,
+            [("maps_number", Formattable::integer(&selected.len()))],
+        ));
+
+        if button(&state.resource_man.gui_str(gui_ids.btn_rename_selected)).clicked {
+            state
+                .ui_state
+                .text_field
+                .get(TextField::MapRenaming)
+                .clear();
+            state.ui_state.push_popup(PopupState::TextInput {
+                title: gui_ids.rename_maps,
+                label: gui_ids.lbl_map_name,
+                field: TextField::MapRenaming,
+                action: PopupAction::RenameMaps(selected.clone()),
+            });
+        }
+
+        if button(&state.resource_man.gui_str(gui_ids.btn_export_selected)).clicked {
+            push_toast(match map::export_saves(&selected) {
+                Ok(path) => Toast::new(
+                    state.resource_man.gui_fmt(
+                        gui_ids.lbl_maps_exported,
+                        [
+                            ("maps_number", Formattable::integer(&selected.len())),
+                            ("path", Formattable::display(&path.display())),
+                        ],
+                    ),
+                    ToastPriority::Normal,
+                )
+                .with_timeout(LONG_TOAST),
+                Err(err) => {
+                    log::error!("Could not export the maps: {err}");
+
+                    Toast::new(
+                        state.resource_man.gui_fmt(
+                            gui_ids.lbl_maps_export_failed,
+                            [("error", Formattable::display(&err))],
+                        ),
+                        ToastPriority::High,
+                    )
+                }
+            });
+        }
+
+        if button(&state.resource_man.gui_str(gui_ids.btn_delete_selected)).clicked {
+            state.ui_state.push_popup(PopupState::Confirm {
+                title: gui_ids.delete_maps,
+                message: gui_ids.lbl_delete_maps_confirm,
+                action: PopupAction::DeleteMaps(selected.clone()),
+            });
+        }
+    });
+}
+
 /// Draws the map loading menu.
 pub fn map_menu(state: &mut GameState) {
     window(
@@ -147,6 +378,10 @@ pub fn map_menu(state: &mut GameState) {
             .gui_str(state.resource_man.registry.gui_ids.load_map)
             .to_string(),
         || {
+            if map_folders(state) {
+                refresh_maps(state);
+            }
+
             scroll_vertical(
                 Vec2::ZERO,
                 Vec2::new(state.ui_viewport().x * 0.7, 260.0),
@@ -156,9 +391,31 @@ pub fn map_menu(state: &mut GameState) {
 
                         {
                             let infos = mem::take(&mut state.loop_store.map_infos_cache);
-                            for ((_, save_time), map_name) in &infos {
+                            // only the saves in the folder being shown
+                            let shown = infos
+                                .iter()
+                                .enumerate()
+                                .filter(|(_, (_, map_name))| {
+                                    map::split_save_path(map_name).0 == state.ui_state.map_folder
+                                })
+                                .map(|(index, _)| index)
+                                .collect::<Vec<_>>();
+
+                            virtual_list(shown.len(), |index| {
+                                let ((info, save_time), map_name) = &infos[shown[index]];
+                                let (folder, base_name) = map::split_save_path(map_name);
+
                                 group(|| {
                                     row(|| {
+                                        let mut selected =
+                                            state.ui_state.selected_maps.contains(map_name);
+                                        checkbox(&mut selected);
+                                        if selected {
+                                            state.ui_state.selected_maps.insert(map_name.clone());
+                                        } else {
+                                            state.ui_state.selected_maps.remove(map_name);
+                                        }
+
                                         Pad::vertical(PADDING_SMALL).show(|| {
                                             if Some(map_name)
                                                 == state.ui_state.renaming_map.as_ref()
@@ -172,10 +429,13 @@ pub fn map_menu(state: &mut GameState) {
                                                 if res.lost_focus || res.activated {
                                                     state.ui_state.renaming_map = None;
 
-                                                    let new_name = mem::take(renaming)
-                                                        .chars()
-                                                        .filter(|v| v.is_alphanumeric())
-                                                        .collect::<String>();
+                                                    let new_name = map::join_save_path(
+                                                        folder,
+                                                        &mem::take(renaming)
+                                                            .chars()
+                                                            .filter(|v| v.is_alphanumeric())
+                                                            .collect::<String>(),
+                                                    );
 
                                                     if fs::rename(
                                                         GameMap::path(&LoadMapOption::FromSave(
@@ -195,16 +455,36 @@ pub fn map_menu(state: &mut GameState) {
 
                                                         dirty = true;
                                                     } else {
-                                                        state.ui_state.popup =
-                                                            PopupState::InvalidName;
+                                                        state.ui_state.push_popup(
+                                                            PopupState::Message {
+                                                                title: state
+                                                                    .resource_man
+                                                                    .registry
+                                                                    .gui_ids
+                                                                    .invalid_name,
+                                                                message: state
+                                                                    .resource_man
+                                                                    .registry
+                                                                    .gui_ids
+                                                                    .lbl_pick_another_name,
+                                                                action: PopupAction::Close,
+                                                            },
+                                                        );
                                                     }
                                                 }
-                                            } else if button(map_name).clicked {
-                                                state
+                                            } else if drag_source(
+                                                DraggedMap(map_name.clone()),
+                                                || {
+                                                    label(base_name);
+                                                },
+                                            )
+                                            .clicked
+                                            {
+                                                *state
                                                     .ui_state
                                                     .text_field
-                                                    .get(TextField::MapRenaming)
-                                                    .clone_from(map_name);
+                                                    .get(TextField::MapRenaming) =
+                                                    base_name.to_string();
                                                 state.ui_state.renaming_map =
                                                     Some(map_name.clone());
                                             }
@@ -221,28 +501,67 @@ pub fn map_menu(state: &mut GameState) {
                                             ));
                                         }
 
+                                        match state.loop_store.map_signatures.get(map_name) {
+                                            Some(SaveSignature::Valid) => {
+                                                label(
+                                                    &state.resource_man.gui_str(
+                                                        state
+                                                            .resource_man
+                                                            .registry
+                                                            .gui_ids
+                                                            .lbl_save_signed,
+                                                    ),
+                                                );
+                                            }
+                                            Some(SaveSignature::Modified) => {
+                                                colored_label(
+                                                    &state.resource_man.gui_str(
+                                                        state
+                                                            .resource_man
+                                                            .registry
+                                                            .gui_ids
+                                                            .lbl_save_modified,
+                                                    ),
+                                                    colors::RED,
+                                                );
+                                            }
+                                            _ => {}
+                                        }
+
                                         spacer(1);
 
                                         row(|| {
-                                            if button(&state.resource_man.gui_str(
+                                            let load = button(&state.resource_man.gui_str(
                                                 state.resource_man.registry.gui_ids.btn_load,
                                             ))
-                                            .clicked
-                                            {
-                                                match game_load_map(state, map_name.clone()) {
-                                                    GameLoadResult::Loaded => {
-                                                        state
-                                                            .ui_state
-                                                            .switch_screen(Screen::Ingame);
-                                                    }
-                                                    GameLoadResult::LoadedMainMenu => {
-                                                        state
-                                                            .ui_state
-                                                            .switch_screen(Screen::MainMenu);
-                                                    }
-                                                    GameLoadResult::Failed => {
-                                                        panic!("{}", COULD_NOT_LOAD_ANYTHING)
-                                                    }
+                                            .clicked;
+                                            let load_read_only = button(
+                                                &state.resource_man.gui_str(
+                                                    state
+                                                        .resource_man
+                                                        .registry
+                                                        .gui_ids
+                                                        .btn_load_read_only,
+                                                ),
+                                            )
+                                            .clicked;
+
+                                            if load || load_read_only {
+                                                state.ui_state.read_only = load_read_only;
+
+                                                let changes = info.namespace_changes(
+                                                    &state.resource_man.pack_versions(),
+                                                );
+
+                                                if changes.is_empty() {
+                                                    try_load_map(state, map_name.clone());
+                                                } else {
+                                                    state.ui_state.push_popup(
+                                                        PopupState::MapNamespaces(
+                                                            map_name.clone(),
+                                                            changes,
+                                                        ),
+                                                    );
                                                 }
                                             }
                                             if button(&state.resource_man.gui_str(
@@ -250,17 +569,28 @@ pub fn map_menu(state: &mut GameState) {
                                             ))
                                             .clicked
                                             {
-                                                state.ui_state.popup =
-                                                    PopupState::MapDeleteConfirmation(
+                                                state.ui_state.push_popup(PopupState::Confirm {
+                                                    title: state
+                                                        .resource_man
+                                                        .registry
+                                                        .gui_ids
+                                                        .delete_map,
+                                                    message: state
+                                                        .resource_man
+                                                        .registry
+                                                        .gui_ids
+                                                        .lbl_delete_map_confirm,
+                                                    action: PopupAction::DeleteMap(
                                                         map_name.clone(),
-                                                    );
+                                                    ),
+                                                });
 
                                                 dirty = true;
                                             }
                                         });
                                     });
                                 });
-                            }
+                            });
                             state.loop_store.map_infos_cache = infos;
                         }
 
@@ -271,6 +601,14 @@ pub fn map_menu(state: &mut GameState) {
                 },
             );
 
+            if let Some(DraggedMap(map_name)) = dragged::<DraggedMap>() {
+                drag_ghost(|| {
+                    label(map::split_save_path(&map_name).1);
+                });
+            }
+
+            selected_maps_actions(state);
+
             label(&state.resource_man.gui_fmt(
                 state.resource_man.registry.gui_ids.lbl_maps_loaded,
                 [(
@@ -287,7 +625,12 @@ pub fn map_menu(state: &mut GameState) {
                 )
                 .clicked
                 {
-                    state.ui_state.popup = PopupState::MapCreate
+                    state.ui_state.push_popup(PopupState::TextInput {
+                        title: state.resource_man.registry.gui_ids.create_map,
+                        label: state.resource_man.registry.gui_ids.lbl_map_name,
+                        field: TextField::MapName,
+                        action: PopupAction::CreateMap,
+                    });
                 }
 
                 if button(
@@ -304,6 +647,46 @@ pub fn map_menu(state: &mut GameState) {
     );
 }
 
+/// Warns under the font options if the chosen fonts have no glyphs for some characters of the language, which are then
+/// drawn with the fallback font.
+fn font_glyphs_warning(state: &mut GameState) {
+    let chain = [
+        state.options.gui.get_font(&state.resource_man),
+        state.options.gui.get_cjk_font(&state.resource_man),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+
+    if !matches!(&state.ui_state.font_missing_glyphs, Some((fonts, _)) if *fonts == chain) {
+        let missing = state.resource_man.missing_glyphs(&chain);
+        state.ui_state.font_missing_glyphs = Some((chain, missing));
+    }
+
+    let Some((_, missing)) = &state.ui_state.font_missing_glyphs else {
+        return;
+    };
+    if missing.is_empty() {
+        return;
+    }
+
+    center_col(|| {
+        colored_label(
+            &state.resource_man.gui_fmt(
+                state.resource_man.registry.gui_ids.lbl_font_missing_glyphs,
+                [
+                    ("count", Formattable::display(&missing.len())),
+                    (
+                        "chars",
+                        Formattable::display(&missing.iter().take(16).collect::<String>()),
+                    ),
+                ],
+            ),
+            colors::ORANGE,
+        );
+    });
+}
+
 pub fn options_menu_item(state: &mut GameState, menu: OptionsMenuState) {
     match menu {
         OptionsMenuState::Graphics => {
@@ -319,45 +702,73 @@ pub fn options_menu_item(state: &mut GameState, menu: OptionsMenuState) {
                     ),
                 );
 
+                let gui_ids = state.resource_man.registry.gui_ids;
+
+                // switching to a custom scale starts from the current one
+                let custom = match state.options.graphics.ui_scale {
+                    UiScale::Custom(percent) => UiScale::Custom(percent),
+                    _ => UiScale::Custom(
+                        (state.gui.as_ref().unwrap().yak.layout_dom().scale_factor() as f64
+                            / state.renderer.as_ref().unwrap().gpu.window.scale_factor()
+                            * 100.0)
+                            .round() as i32,
+                    ),
+                };
+
                 let new_scale = selection_box(
-                    [UiScale::Small, UiScale::Normal, UiScale::Large],
+                    [
+                        UiScale::Auto,
+                        UiScale::Small,
+                        UiScale::Normal,
+                        UiScale::Large,
+                        custom,
+                    ],
                     state.options.graphics.ui_scale,
                     &|v| match v {
-                        UiScale::Small => state.resource_man.gui_str(
-                            state
-                                .resource_man
-                                .registry
-                                .gui_ids
-                                .options_graphics_ui_scale_small,
-                        ),
-                        UiScale::Normal => state.resource_man.gui_str(
-                            state
-                                .resource_man
-                                .registry
-                                .gui_ids
-                                .options_graphics_ui_scale_normal,
-                        ),
-                        UiScale::Large => state.resource_man.gui_str(
-                            state
-                                .resource_man
-                                .registry
-                                .gui_ids
-                                .options_graphics_ui_scale_large,
-                        ),
+                        UiScale::Small => state
+                            .resource_man
+                            .gui_str(gui_ids.options_graphics_ui_scale_small),
+                        UiScale::Normal => state
+                            .resource_man
+                            .gui_str(gui_ids.options_graphics_ui_scale_normal),
+                        UiScale::Large => state
+                            .resource_man
+                            .gui_str(gui_ids.options_graphics_ui_scale_large),
+                        UiScale::Auto => state
+                            .resource_man
+                            .gui_str(gui_ids.options_graphics_ui_scale_auto),
+                        UiScale::Custom(_) => state
+                            .resource_man
+                            .gui_str(gui_ids.options_graphics_ui_scale_custom),
                     },
                 );
 
                 if new_scale != state.options.graphics.ui_scale {
                     state.options.graphics.ui_scale = new_scale;
 
-                    state.gui.as_mut().unwrap().yak.set_scale_factor(
-                        (state.renderer.as_ref().unwrap().gpu.window.scale_factor()
-                            * state.options.graphics.ui_scale.to_f64())
-                            as f32,
-                    );
+                    gui::apply_ui_scale(state);
                 }
             });
 
+            if let UiScale::Custom(mut percent) = state.options.graphics.ui_scale {
+                center_col(|| {
+                    let (min, max) = UI_SCALE_RANGE;
+
+                    // previewed as it is dragged
+                    if slider(
+                        &mut percent,
+                        min..=max,
+                        Some(5),
+                        |v| v.parse().ok(),
+                        |v| format!("{: >3}%", v),
+                    ) {
+                        state.options.graphics.ui_scale = UiScale::Custom(percent);
+
+                        gui::apply_ui_scale(state);
+                    }
+                });
+            }
+
             center_col(|| {
                 label(&format!(
                     "Max FPS: {: >3}",
@@ -379,12 +790,24 @@ pub fn options_menu_item(state: &mut GameState, menu: OptionsMenuState) {
                 );
             });
 
+            center_col(|| {
+                label("Mailbox Present: ");
+
+                checkbox(&mut state.options.graphics.mailbox);
+            });
+
             center_col(|| {
                 label("Fullscreen: ");
 
                 checkbox(&mut state.options.graphics.fullscreen);
             });
 
+            center_col(|| {
+                label("Adaptive Quality: ");
+
+                checkbox(&mut state.options.graphics.adaptive_quality);
+            });
+
             /*
             row(|| {
                 label("Antialiasing: ");
@@ -445,14 +868,150 @@ pub fn options_menu_item(state: &mut GameState, menu: OptionsMenuState) {
                 state.options.gui.set_font(&state.resource_man, new_font);
             });
 
+            center_col(|| {
+                label("CJK fallback font:");
+
+                let new_font = selection_box(
+                    [None]
+                        .into_iter()
+                        .chain(state.resource_man.fonts.keys().cloned().map(Some)),
+                    state.options.gui.get_cjk_font(&state.resource_man),
+                    &|font| font.clone().unwrap_or_else(|| "None".to_string()),
+                );
+
+                state
+                    .options
+                    .gui
+                    .set_cjk_font(&state.resource_man, new_font);
+            });
+
+            font_glyphs_warning(state);
+
+            center_col(|| {
+                label("Progress bars:");
+
+                checkbox(&mut state.options.gui.show_progress_bars);
+            });
+
+            center_col(|| {
+                label(&format!(
+                    "Screen Effects: {: >3}%",
+                    (state.options.gui.screen_effects * 100.0) as i32
+                ));
+
+                slider(
+                    &mut state.options.gui.screen_effects,
+                    0.0..=1.0,
+                    Some(0.01),
+                    |v| v.parse::<f64>().ok().map(|v| v / 100.0),
+                    |v| format!("{: >3}", (v * 100.0) as i32),
+                );
+            });
+
+            center_col(|| {
+                label("Reduced motion:");
+
+                checkbox(&mut state.options.gui.reduced_motion);
+            });
+
             center_col(|| {
                 label("Language:");
 
-                label("TODO: UNIMPLEMENTED");
+                let new_language = selection_box(
+                    state.resource_man.languages.iter().cloned(),
+                    state.misc_options.language.clone(),
+                    &|language| language.clone(),
+                );
+
+                if new_language != state.misc_options.language {
+                    switch_language(state, new_language);
+                }
             });
         }
         OptionsMenuState::Controls => {}
+        OptionsMenuState::Advanced => {
+            center_col(|| {
+                label(&format!("Tick rate: {}", state.options.advanced.tick_rate));
+
+                slider(
+                    &mut state.options.advanced.tick_rate,
+                    1..=MAX_TPS as i32,
+                    None,
+                    |v| v.parse().ok(),
+                    |v| format!("{: >3}", v),
+                );
+            });
+
+            center_col(|| {
+                label("Sign saves:");
+
+                checkbox(&mut state.options.advanced.sign_saves);
+            });
+
+            center_col(|| {
+                if state.options.advanced.autosave_interval > 0 {
+                    label(&format!(
+                        "Autosave every: {} min",
+                        state.options.advanced.autosave_interval
+                    ));
+                } else {
+                    label("Autosave every: off");
+                }
+
+                slider(
+                    &mut state.options.advanced.autosave_interval,
+                    0..=60,
+                    None,
+                    |v| v.parse().ok(),
+                    |v| format!("{: >2}", v),
+                );
+            });
+
+            center_col(|| {
+                label(&format!(
+                    "Autosave slots: {}",
+                    state.options.advanced.autosave_slots
+                ));
+
+                slider(
+                    &mut state.options.advanced.autosave_slots,
+                    1..=10,
+                    None,
+                    |v| v.parse().ok(),
+                    |v| format!("{: >2}", v),
+                );
+            });
+        }
+    }
+}
+
+/// Switches the language the game is shown in, without restarting. The fonts are checked again, as the new language
+/// may need glyphs the chosen ones don't have.
+fn switch_language(state: &mut GameState, language: String) {
+    if let Err(err) = state.resource_man.reload_translations(&language) {
+        log::error!("Could not switch the language to {language}! Error: {err:?}");
+
+        push_toast(
+            Toast::new(
+                state.resource_man.gui_fmt(
+                    state
+                        .resource_man
+                        .registry
+                        .gui_ids
+                        .lbl_language_switch_failed,
+                    [("error", Formattable::display(&err))],
+                ),
+                ToastPriority::High,
+            )
+            .with_timeout(LONG_TOAST),
+        );
+
+        return;
     }
+
+    state.misc_options.language = language;
+    state.options.synced = false;
+    state.ui_state.font_missing_glyphs = None;
 }
 
 /// Draws the options menu.
@@ -505,6 +1064,16 @@ pub fn options_menu(state: &mut GameState) {
                         {
                             state.ui_state.substate = SubState::Options(OptionsMenuState::Controls)
                         }
+
+                        if button(
+                            &state
+                                .resource_man
+                                .gui_str(state.resource_man.registry.gui_ids.options_advanced),
+                        )
+                        .clicked
+                        {
+                            state.ui_state.substate = SubState::Options(OptionsMenuState::Advanced)
+                        }
                     });
                 });
 
@@ -526,6 +1095,9 @@ pub fn options_menu(state: &mut GameState) {
                                         OptionsMenuState::Controls => {
                                             state.resource_man.registry.gui_ids.options_controls
                                         }
+                                        OptionsMenuState::Advanced => {
+                                            state.resource_man.registry.gui_ids.options_advanced
+                                        }
                                     }));
 
                                     divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
@@ -560,6 +1132,7 @@ pub fn options_menu(state: &mut GameState) {
                         if state.options.save().is_err() {
                             push_err(
                                 state.resource_man.registry.err_ids.unwritable_options,
+                                ErrorSeverity::Warn,
                                 &FormatContext::from([].into_iter()),
                                 &state.resource_man,
                             );
@@ -568,6 +1141,7 @@ pub fn options_menu(state: &mut GameState) {
                         if state.misc_options.save().is_err() {
                             push_err(
                                 state.resource_man.registry.err_ids.unwritable_options,
+                                ErrorSeverity::Warn,
                                 &FormatContext::from([].into_iter()),
                                 &state.resource_man,
                             );
@@ -575,8 +1149,51 @@ pub fn options_menu(state: &mut GameState) {
 
                         state.ui_state.return_screen();
                     }
+
+                    if options::has_options_backup()
+                        && button(
+                            &state
+                                .resource_man
+                                .gui_str(state.resource_man.registry.gui_ids.btn_restore_options),
+                        )
+                        .clicked
+                    {
+                        restore_options(state);
+                    }
                 });
             });
         },
     );
 }
+
+/// Goes back to the options as they were before they were last saved.
+fn restore_options(state: &mut GameState) {
+    let gui_ids = state.resource_man.registry.gui_ids;
+
+    push_toast(match GameOptions::restore_previous(&state.resource_man) {
+        Ok(options) => {
+            state.input_handler.key_map = options.keymap.clone();
+            state.options = options;
+
+            Toast::new(
+                state
+                    .resource_man
+                    .gui_str(gui_ids.lbl_options_restored)
+                    .to_string(),
+                ToastPriority::Normal,
+            )
+            .with_timeout(SHORT_TOAST)
+        }
+        Err(err) => {
+            log::error!("Could not restore the previous options: {err}");
+
+            Toast::new(
+                state.resource_man.gui_fmt(
+                    gui_ids.lbl_options_restore_failed,
+                    [("error", Formattable::display(&err))],
+                ),
+                ToastPriority::High,
+            )
+        }
+    });
+}
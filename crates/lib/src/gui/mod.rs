@@ -1,66 +1,237 @@
 use crate::GameState;
-use automancy_defs::glam::vec3;
+use automancy_defs::glam::{vec2, vec3};
 use automancy_defs::id::ModelId;
 use automancy_defs::rendering::InstanceData;
 use automancy_defs::{colors, math, rendering::make_line, window};
 use automancy_defs::{
+    coord::TileBounds,
     math::{Float, Matrix4, FAR, HEX_GRID_LAYOUT},
     rendering::GameMatrix,
 };
-use automancy_resources::data::DataMap;
+use automancy_resources::{data::DataMap, rhai_field, rhai_map};
+use automancy_system::game::{tick_interpolation, GameSystemMessage};
 use automancy_system::input::ActionType;
-use automancy_system::ui_state::{PopupState, Screen};
+use automancy_system::ui_state::Screen;
+use automancy_ui::{colored_label, PADDING_LARGE};
+use ractor::rpc::CallResult;
+use std::time::Instant;
 use tokio::sync::oneshot;
 use util::render_overlay_cached;
 use winit::event_loop::ActiveEventLoop;
+use yakui::widgets::{Absolute, Layer, Pad};
+use yakui::{colored_box, Alignment, Dim2, Pivot};
 
+pub mod beacon;
+pub mod blueprint;
+pub mod cutscene;
 pub mod debug;
 pub mod error;
 pub mod info;
 pub mod item;
 pub mod menu;
+pub mod model_viewer;
 pub mod player;
 pub mod popup;
+pub mod radial;
+pub mod search;
+pub mod statistics;
 pub mod tile_config;
 pub mod tile_selection;
+pub mod toast;
 pub mod util;
 
+const PROGRESS_BAR_WIDTH: Float = 0.8;
+const PROGRESS_BAR_OFFSET: Float = 0.6;
+/// How much of a pinned window is kept on the screen when the UI is rescaled, in UI units.
+const PINNED_ON_SCREEN: Float = 64.0;
+
+/// Scales the UI by the scale factor of the monitor the window is on and the UI scale option, if either changed. Pinned
+/// windows are moved to stay where they were on the screen, and are kept on it.
+pub fn apply_ui_scale(state: &mut GameState) {
+    let (Some(gui), Some(renderer)) = (state.gui.as_mut(), state.renderer.as_ref()) else {
+        return;
+    };
+    let window = &renderer.gpu.window;
+
+    let logical_height = window
+        .current_monitor()
+        .map(|monitor| monitor.size().height as f64 / monitor.scale_factor());
+    let new =
+        (window.scale_factor() * state.options.graphics.ui_scale.to_f64(logical_height)) as Float;
+    let old = gui.yak.layout_dom().scale_factor();
+
+    if (new - old).abs() < Float::EPSILON {
+        return;
+    }
+
+    gui.yak.set_scale_factor(new);
+
+    let size = window.inner_size();
+    let max = (vec2(size.width as Float, size.height as Float) / new - PINNED_ON_SCREEN)
+        .max(vec2(0.0, 0.0));
+
+    for (_, pos) in &mut state.ui_state.pinned_configs {
+        *pos = (*pos * old / new).clamp(vec2(0.0, 0.0), max);
+    }
+}
+
+/// Draws a bar above every tile in view that is currently processing something.
+fn progress_bars(state: &mut GameState) {
+    let culling_range = state.camera.culling_range;
+
+    let Ok(CallResult::Success(progress)) = state.tokio.block_on(state.game.call(
+        |reply| GameSystemMessage::GetProgress {
+            culling_range,
+            reply,
+        },
+        None,
+    )) else {
+        return;
+    };
+
+    let interpolations = &mut state.loop_store.progress_interpolations;
+    interpolations.retain(|coord, _| progress.contains_key(coord));
+
+    for (coord, fraction) in progress {
+        let (from, to, since) =
+            interpolations
+                .entry(coord)
+                .or_insert((fraction, fraction, Instant::now()));
+
+        let current = *from + (*to - *from) * tick_interpolation(*since);
+
+        if fraction != *to {
+            // a finished cycle starts over from empty instead of draining backwards
+            *from = if fraction < *to { fraction } else { current };
+            *to = fraction;
+            *since = Instant::now();
+        }
+
+        let fraction = *from + (*to - *from) * tick_interpolation(*since);
+
+        let p = HEX_GRID_LAYOUT.hex_to_world_pos(*coord);
+
+        let start = vec2(p.x - PROGRESS_BAR_WIDTH / 2.0, p.y + PROGRESS_BAR_OFFSET);
+        let end = start + vec2(PROGRESS_BAR_WIDTH, 0.0);
+        let mid = start.lerp(end, fraction.clamp(0.0, 1.0));
+
+        for (a, b, color) in [(start, mid, colors::ORANGE), (mid, end, colors::DARK_GRAY)] {
+            state.renderer.as_mut().unwrap().overlay_instances.push((
+                InstanceData::default().with_color_offset(color.to_linear()),
+                ModelId(state.resource_man.registry.model_ids.cube1x1),
+                GameMatrix::<true>::new(
+                    make_line(a, b, FAR),
+                    state.camera.get_matrix(),
+                    Matrix4::IDENTITY,
+                ),
+                0,
+            ));
+        }
+    }
+}
+
+/// Shows that the map is open read-only, in place of the tile selection.
+fn read_only_notice(state: &mut GameState) {
+    Absolute::new(Alignment::BOTTOM_CENTER, Pivot::BOTTOM_CENTER, Dim2::ZERO).show(|| {
+        Layer::new().show(|| {
+            Pad::all(PADDING_LARGE).show(|| {
+                colored_label(
+                    &state
+                        .resource_man
+                        .gui_str(state.resource_man.registry.gui_ids.lbl_read_only),
+                    colors::ORANGE,
+                );
+            });
+        });
+    });
+}
+
+/// Covers the screen with the color of the current flash, fading it out.
+fn screen_flash(state: &mut GameState) {
+    let Some((color, duration, start)) = state.ui_state.flash else {
+        return;
+    };
+
+    if start.elapsed() >= duration {
+        state.ui_state.flash = None;
+
+        return;
+    }
+
+    let t = start.elapsed().as_secs_f32() / duration.as_secs_f32();
+    let size = state.ui_viewport();
+
+    Absolute::new(Alignment::TOP_LEFT, Pivot::TOP_LEFT, Dim2::ZERO).show(|| {
+        Layer::new().show(|| {
+            colored_box(color.with_alpha(color.a as f32 / 255.0 * (1.0 - t)), size);
+        });
+    });
+}
+
 pub fn render_ui(
     state: &mut GameState,
     result: &mut anyhow::Result<bool>,
     event_loop: &ActiveEventLoop,
 ) {
-    if state.ui_state.popup == PopupState::None {
+    automancy_ui::set_reduced_motion(state.options.gui.reduced_motion);
+
+    if state.ui_state.popups.is_empty() {
         match state.ui_state.screen {
             Screen::Ingame => {
+                screen_flash(state);
+
+                if let Some(map_info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone()) {
+                    beacon::render_beacons(state, &map_info.blocking_lock().beacons);
+                }
+
+                // the rest of the GUI is hidden while a cutscene plays
+                let playing_cutscene = cutscene::cutscene_ui(state);
+
                 // tile_info
-                info::info_ui(state);
+                if !playing_cutscene {
+                    info::info_ui(state);
+                }
 
-                if !state.input_handler.key_active(ActionType::ToggleGui) {
+                if !playing_cutscene && !state.input_handler.key_active(ActionType::ToggleGui) {
                     if let Some(map_info) = state.loop_store.map_info.as_ref().map(|v| v.0.clone())
                     {
                         let mut lock = map_info.blocking_lock();
                         let game_data = &mut lock.data;
 
-                        let (selection_send, selection_recv) = oneshot::channel();
+                        if state.ui_state.read_only {
+                            read_only_notice(state);
+                        } else {
+                            let (selection_send, selection_recv) = oneshot::channel();
 
-                        // tile_selections
-                        tile_selection::tile_selections(state, game_data, selection_send);
+                            // tile_selections
+                            tile_selection::tile_selections(state, game_data, selection_send);
 
-                        if let Ok(id) = selection_recv.blocking_recv() {
-                            state.ui_state.already_placed_at = None;
+                            if let Ok(id) = selection_recv.blocking_recv() {
+                                state.ui_state.already_placed_at = None;
 
-                            if state.ui_state.selected_tile_id == Some(id) {
-                                state.ui_state.selected_tile_id = None;
-                            } else {
-                                state.ui_state.selected_tile_id = Some(id);
+                                if state.ui_state.selected_tile_id == Some(id) {
+                                    state.ui_state.selected_tile_id = None;
+                                } else {
+                                    state.ui_state.selected_tile_id = Some(id);
+                                }
                             }
+
+                            player::player(state, game_data);
+
+                            // tile_config
+                            tile_config::tile_config_ui(state, game_data);
                         }
 
-                        player::player(state, game_data);
+                        beacon::beacon_sidebar(state, &mut lock.beacons);
+                    }
+
+                    search::map_search_ui(state);
+                    blueprint::blueprints_ui(state);
 
-                        // tile_config
-                        tile_config::tile_config_ui(state, game_data);
+                    if state.options.gui.show_progress_bars
+                        && state.loop_store.quality.level().progress_bars()
+                    {
+                        progress_bars(state);
                     }
 
                     let cursor_pos = math::screen_to_world(
@@ -84,20 +255,30 @@ pub fn render_ui(
                     );
 
                     if let Some((coord, ..)) = state.ui_state.linking_tile {
-                        state.renderer.as_mut().unwrap().overlay_instances.push((
-                            InstanceData::default().with_color_offset(colors::RED.to_linear()),
-                            ModelId(state.resource_man.registry.model_ids.cube1x1),
-                            GameMatrix::<true>::new(
-                                make_line(
-                                    HEX_GRID_LAYOUT.hex_to_world_pos(*coord),
-                                    cursor_pos.truncate(),
-                                    FAR,
+                        // follows the route along the tiles in between if there is one, instead of a straight line
+                        let points = match rhai_map::find_path(coord, state.camera.pointing_at) {
+                            Some(path) if path.len() > 1 => path
+                                .into_iter()
+                                .map(|v| HEX_GRID_LAYOUT.hex_to_world_pos(*v))
+                                .collect::<Vec<_>>(),
+                            _ => vec![
+                                HEX_GRID_LAYOUT.hex_to_world_pos(*coord),
+                                cursor_pos.truncate(),
+                            ],
+                        };
+
+                        for segment in points.windows(2) {
+                            state.renderer.as_mut().unwrap().overlay_instances.push((
+                                InstanceData::default().with_color_offset(colors::RED.to_linear()),
+                                ModelId(state.resource_man.registry.model_ids.cube1x1),
+                                GameMatrix::<true>::new(
+                                    make_line(segment[0], segment[1], FAR),
+                                    state.camera.get_matrix(),
+                                    Matrix4::IDENTITY,
                                 ),
-                                state.camera.get_matrix(),
-                                Matrix4::IDENTITY,
-                            ),
-                            0,
-                        ));
+                                0,
+                            ));
+                        }
                     }
                 }
             }
@@ -114,23 +295,32 @@ pub fn render_ui(
         }
     }
 
-    match state.ui_state.popup.clone() {
-        PopupState::None => {}
-        PopupState::MapCreate => popup::map_create_popup(state),
-        PopupState::MapDeleteConfirmation(map_name) => {
-            popup::map_delete_popup(state, &map_name);
-        }
-        PopupState::InvalidName => {
-            popup::invalid_name_popup(state);
-        }
-    }
+    popup::popup(state);
+
+    radial::radial_menu(state);
 
+    item::item_tooltip(state);
     util::render_info_tip(state);
 
-    state.renderer.as_mut().unwrap().tile_tints.insert(
-        state.camera.pointing_at,
-        colors::RED.with_alpha(0.2).to_linear(),
-    );
+    {
+        // fades the highlight over to the newly pointed at tile
+        let (last_pointing_at, since) = state.camera.last_pointing_at;
+        let t = tick_interpolation(since);
+
+        let tile_tints = &mut state.renderer.as_mut().unwrap().tile_tints;
+
+        if t < 1.0 && last_pointing_at != state.camera.pointing_at {
+            tile_tints.insert(
+                last_pointing_at,
+                colors::RED.with_alpha(0.2 * (1.0 - t)).to_linear(),
+            );
+        }
+
+        tile_tints.insert(
+            state.camera.pointing_at,
+            colors::RED.with_alpha(0.2 * t).to_linear(),
+        );
+    }
 
     for coord in &state.ui_state.grouped_tiles {
         state
@@ -141,6 +331,73 @@ pub fn render_ui(
             .insert(*coord, colors::ORANGE.with_alpha(0.4).to_linear());
     }
 
+    if let Some(field) = state.ui_state.field_overlay {
+        let values = rhai_field::field_values(field);
+        // scaled to the strongest value, with negative values in a cold color
+        let max = values
+            .values()
+            .fold(0.0 as Float, |max, v| max.max(v.abs()));
+
+        if max > 0.0 {
+            let tile_tints = &mut state.renderer.as_mut().unwrap().tile_tints;
+
+            for (coord, value) in values {
+                let color = if value >= 0.0 {
+                    colors::RED
+                } else {
+                    colors::LIGHT_BLUE
+                };
+
+                tile_tints.insert(coord, color.with_alpha(0.6 * value.abs() / max).to_linear());
+            }
+        }
+    }
+
+    if let Some(editing) = &state.ui_state.editing_region {
+        let tile_tints = &mut state.renderer.as_mut().unwrap().tile_tints;
+
+        for coord in editing.region.coords() {
+            tile_tints.insert(coord, colors::LIGHT_BLUE.with_alpha(0.4).to_linear());
+        }
+
+        // the range being dragged out, as it would be added or cut out
+        if let Some((start, subtract)) = editing.drag {
+            let color = if subtract { colors::RED } else { colors::INPUT };
+
+            for coord in
+                TileBounds::new(start, start.unsigned_distance_to(*state.camera.pointing_at))
+            {
+                tile_tints.insert(coord, color.with_alpha(0.4).to_linear());
+            }
+        }
+    }
+
+    if let Some(search) = &state.ui_state.map_search {
+        let tile_tints = &mut state.renderer.as_mut().unwrap().tile_tints;
+
+        for (coord, _) in &search.results {
+            tile_tints.insert(*coord, colors::LIGHT_BLUE.with_alpha(0.5).to_linear());
+        }
+    }
+
+    // the range being dragged out, under the tiles in it that will be removed
+    if let Some(bounds) = state.ui_state.deconstruct_drag {
+        let tile_tints = &mut state.renderer.as_mut().unwrap().tile_tints;
+
+        for coord in bounds {
+            tile_tints.insert(coord, colors::RED.with_alpha(0.2).to_linear());
+        }
+    }
+
+    for coord in state.ui_state.deconstruct_targets(&state.resource_man) {
+        state
+            .renderer
+            .as_mut()
+            .unwrap()
+            .tile_tints
+            .insert(coord, colors::RED.with_alpha(0.6).to_linear());
+    }
+
     if let Some(start) = state.ui_state.paste_from {
         if start != state.camera.pointing_at {
             state.renderer.as_mut().unwrap().overlay_instances.push((
@@ -190,5 +447,22 @@ pub fn render_ui(
         debug::debugger(state);
     }
 
-    error::error_popup(state);
+    if state.ui_state.screen == Screen::Ingame
+        && state.input_handler.key_active(ActionType::Statistics)
+    {
+        statistics::statistics_ui(state);
+    }
+
+    if state.ui_state.error_history_open {
+        error::error_history(state);
+    }
+
+    if state.ui_state.model_viewer_open {
+        model_viewer::model_viewer(state);
+    }
+
+    toast::toast_ui(state);
+
+    item::dragged_stack_ghost(state);
+    automancy_ui::finish_drag();
 }
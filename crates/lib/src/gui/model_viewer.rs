@@ -0,0 +1,166 @@
+use crate::gui::util::searchable_id;
+use crate::GameState;
+use automancy_defs::colors::{self, BACKGROUND_3};
+use automancy_defs::glam::vec2;
+use automancy_defs::id::ModelId;
+use automancy_defs::math::Matrix4;
+use automancy_defs::rendering::InstanceData;
+use automancy_resources::types::IconMode;
+use automancy_system::ui_state::TextField;
+use automancy_ui::{
+    button, col, colored_label, label, row, row_max, scroll_vertical, ui_game_object, window,
+    UiGameObjectType, DIVIER_HEIGHT, DIVIER_THICKNESS,
+};
+use yakui::{divider, spacer, widgets::Layer, Vec2};
+
+/// The size of the selected model's preview.
+const PREVIEW_SIZE: f32 = 192.0;
+/// How fast the preview turns, in radians per second.
+const PREVIEW_TURN_SPEED: f32 = 0.5;
+
+/// Draws the details of a model: its meshes with their vertex and index counts and index ranges, and its animations.
+fn model_details(state: &GameState, model: ModelId) {
+    let resource_man = &state.resource_man;
+
+    let Some((meshes, anims)) = resource_man.all_meshes_anims.get(&model) else {
+        colored_label("Not loaded, shown as the missing model", colors::RED);
+        return;
+    };
+
+    // the vertices and indices are moved into the shared buffers once the models are compiled, so they're counted
+    // from the index ranges
+    let ranges = resource_man.all_index_ranges.get(&model);
+    let range_of = |index| ranges.and_then(|v| v.get(&index));
+
+    let vertices = meshes
+        .iter()
+        .flatten()
+        .flat_map(|v| range_of(v.index))
+        .map(|v| v.vertex_count)
+        .sum::<u32>();
+    let indices = meshes
+        .iter()
+        .flatten()
+        .flat_map(|v| range_of(v.index))
+        .map(|v| v.count)
+        .sum::<u32>();
+
+    label(&format!(
+        "Meshes={} Vertices={vertices} Indices={indices} Triangles={}",
+        meshes.iter().flatten().count(),
+        indices / 3
+    ));
+
+    for mesh in meshes.iter().flatten() {
+        let range = range_of(mesh.index)
+            .map(|v| {
+                format!(
+                    "Vertices={} Indices={} Pos={} Base Vertex={}",
+                    v.vertex_count, v.count, v.pos, v.base_vertex
+                )
+            })
+            .unwrap_or("no index range".to_string());
+
+        label(&format!(
+            "Mesh #{}: {} | {range}",
+            mesh.index,
+            if mesh.opaque { "Opaque" } else { "Transparent" },
+        ));
+    }
+
+    if anims.is_empty() {
+        label("No animations");
+    }
+
+    for anim in anims {
+        label(&format!(
+            "Animation on Mesh #{}: {:?} Keyframes={} Length={:.2}s",
+            anim.target,
+            anim.interpolation,
+            anim.inputs.len(),
+            anim.inputs.last().copied().unwrap_or_default()
+        ));
+    }
+
+    if let Some(events) = resource_man.animation_events.get(&model) {
+        for (time, name) in &events.markers {
+            label(&format!(
+                "Event \"{name}\" at {time:.2}s of {:.2}s",
+                events.length
+            ));
+        }
+    }
+}
+
+/// Draws the model viewer, listing every loaded model, and showing the selected one turning with its animations
+/// playing, along with its details.
+pub fn model_viewer(state: &mut GameState) {
+    let mut models = state
+        .resource_man
+        .all_meshes_anims
+        .keys()
+        .map(|v| **v)
+        .collect::<Vec<_>>();
+    models.sort_by_cached_key(|v| state.resource_man.interner.resolve(*v).map(str::to_string));
+
+    Layer::new().show(|| {
+        window("Model Viewer".to_string(), || {
+            row(|| {
+                col(|| {
+                    let mut selected = state.ui_state.viewed_model.map(|v| *v);
+
+                    searchable_id(
+                        state,
+                        &models,
+                        &mut selected,
+                        TextField::ModelFilter,
+                        None,
+                        |state, id| {
+                            label(state.resource_man.interner.resolve(id).unwrap_or("<?>"));
+                        },
+                        |state, id| {
+                            state
+                                .resource_man
+                                .interner
+                                .resolve(id)
+                                .unwrap_or_default()
+                                .into()
+                        },
+                    );
+
+                    state.ui_state.viewed_model = selected.map(ModelId);
+                });
+
+                if let Some(model) = state.ui_state.viewed_model {
+                    col(|| {
+                        let t = state.start_instant.elapsed().as_secs_f32() * PREVIEW_TURN_SPEED;
+
+                        ui_game_object(
+                            InstanceData::default(),
+                            UiGameObjectType::Model(model),
+                            vec2(PREVIEW_SIZE, PREVIEW_SIZE),
+                            Some(Matrix4::from_rotation_z(t) * IconMode::Tile.model_matrix()),
+                            Some(IconMode::Tile.world_matrix()),
+                        );
+
+                        divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
+
+                        scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 240.0), || {
+                            col(|| {
+                                model_details(state, model);
+                            });
+                        });
+                    });
+                }
+            });
+
+            row_max(|| {
+                spacer(1);
+
+                if button("Close").clicked {
+                    state.ui_state.model_viewer_open = false;
+                }
+            });
+        });
+    });
+}
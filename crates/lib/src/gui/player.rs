@@ -8,31 +8,43 @@ use automancy_defs::{
     id::ModelId,
 };
 use automancy_resources::data::{Data, DataMap};
+use automancy_resources::feedback::{push_screen_effect, ScreenEffect};
 use automancy_resources::petgraph::visit::Topo;
 use automancy_resources::types::IconMode;
 use automancy_resources::{rhai_call_options, rhai_log_err};
+use automancy_system::game::MAX_TPS;
 use automancy_system::input::ActionType;
-use automancy_system::util::is_research_unlocked;
+use automancy_system::util::{
+    is_research_unlocked, map_field_diffusion_interval, map_offline_progress,
+    map_random_tick_speed, map_tick_rate, set_map_budget, set_map_budget_block,
+    set_map_field_diffusion_interval, set_map_offline_progress, set_map_random_tick_speed,
+    set_map_tick_rate, BudgetLimit, LockedTileVisibility, MapBudget, MAX_FIELD_DIFFUSION_INTERVAL,
+    MAX_OFFLINE_PROGRESS, MAX_RANDOM_TICK_SPEED,
+};
 use automancy_ui::{
-    button, centered_horizontal, col, group, heading, inactive_button, interactive, label,
-    list_row, movable, row, scroll_horizontal, scroll_horizontal_bar_alignment, scroll_vertical,
-    scroll_vertical_bar_alignment, ui_game_object, window_box, PositionRecord, RoundRect,
-    UiGameObjectType, DIVIER_HEIGHT, DIVIER_THICKNESS, MEDIUM_ICON_SIZE, PADDING_MEDIUM,
-    SMALL_ICON_SIZE, TINY_ICON_SIZE,
+    button, centered_horizontal, checkbox, col, group, heading, inactive_button, interactive,
+    label, list_row, movable, row, scroll_horizontal, scroll_horizontal_bar_alignment,
+    scroll_vertical, scroll_vertical_bar_alignment, selection_box, slider, ui_game_object,
+    virtual_list, window_box, PositionRecord, RoundRect, UiGameObjectType, DIVIER_HEIGHT,
+    DIVIER_THICKNESS, MEDIUM_ICON_SIZE, PADDING_MEDIUM, SMALL_ICON_SIZE, TINY_ICON_SIZE,
 };
 use rhai::{Array, Dynamic, Scope};
 use std::mem;
+use std::time::Duration;
 use yakui::{
     constrained, divider, reflow,
     widgets::{Absolute, Layer, Pad},
     Alignment, Constraints, Dim2, Pivot, Rect,
 };
 
+use crate::gui::cutscene;
 use crate::GameState;
 
 use super::item::draw_item;
 use super::util::take_item_animation;
 
+const RESEARCH_FLASH_DURATION: Duration = Duration::from_millis(400);
+
 const PUZZLE_HEX_GRID_LAYOUT: HexLayout = HexLayout {
     orientation: HexOrientation::Pointy,
     origin: vec2(TINY_ICON_SIZE, 0.0),
@@ -41,6 +53,252 @@ const PUZZLE_HEX_GRID_LAYOUT: HexLayout = HexLayout {
     invert_y: true,
 };
 
+fn locked_tile_visibility(state: &mut GameState, game_data: &mut DataMap) {
+    row(|| {
+        label(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.lbl_locked_tiles),
+        );
+
+        let current = LockedTileVisibility::of(&state.resource_man, game_data);
+
+        let new = selection_box(LockedTileVisibility::ALL, current, &|v| {
+            v.name(&state.resource_man)
+        });
+
+        if new != current {
+            new.set(&state.resource_man, game_data);
+        }
+    });
+}
+
+fn tick_rate_rule(state: &mut GameState, game_data: &mut DataMap) {
+    row(|| {
+        label(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.lbl_map_tick_rate),
+        );
+
+        let current = map_tick_rate(&state.resource_man, game_data).unwrap_or(0) as i32;
+        let mut new = current;
+
+        // 0 stands for using the tick rate from the options
+        slider(
+            &mut new,
+            0..=MAX_TPS as i32,
+            None,
+            |v| v.parse().ok(),
+            |v| {
+                if *v == 0 {
+                    state
+                        .resource_man
+                        .gui_str(
+                            state
+                                .resource_man
+                                .registry
+                                .gui_ids
+                                .lbl_map_tick_rate_default,
+                        )
+                        .to_string()
+                } else {
+                    format!("{: >3}", v)
+                }
+            },
+        );
+
+        if new != current {
+            set_map_tick_rate(
+                &state.resource_man,
+                game_data,
+                (new > 0).then_some(new as u64),
+            );
+        }
+    });
+}
+
+fn random_tick_speed_rule(state: &mut GameState, game_data: &mut DataMap) {
+    row(|| {
+        label(
+            &state.resource_man.gui_str(
+                state
+                    .resource_man
+                    .registry
+                    .gui_ids
+                    .lbl_map_random_tick_speed,
+            ),
+        );
+
+        let current = map_random_tick_speed(&state.resource_man, game_data) as i32;
+        let mut new = current;
+
+        slider(
+            &mut new,
+            0..=MAX_RANDOM_TICK_SPEED as i32,
+            None,
+            |v| v.parse().ok(),
+            |v| format!("{: >2}", v),
+        );
+
+        if new != current {
+            set_map_random_tick_speed(&state.resource_man, game_data, new as u32);
+        }
+    });
+}
+
+fn field_diffusion_rule(state: &mut GameState, game_data: &mut DataMap) {
+    row(|| {
+        label(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.lbl_map_field_diffusion),
+        );
+
+        let current =
+            map_field_diffusion_interval(&state.resource_man, game_data).unwrap_or(0) as i32;
+        let mut new = current;
+
+        // 0 stands for not simulating the fields
+        slider(
+            &mut new,
+            0..=MAX_FIELD_DIFFUSION_INTERVAL as i32,
+            None,
+            |v| v.parse().ok(),
+            |v| {
+                if *v == 0 {
+                    state
+                        .resource_man
+                        .gui_str(
+                            state
+                                .resource_man
+                                .registry
+                                .gui_ids
+                                .lbl_map_field_diffusion_off,
+                        )
+                        .to_string()
+                } else {
+                    format!("{: >3}", v)
+                }
+            },
+        );
+
+        if new != current {
+            set_map_field_diffusion_interval(
+                &state.resource_man,
+                game_data,
+                (new > 0).then_some(new as u32),
+            );
+        }
+    });
+}
+
+fn offline_progress_rule(state: &mut GameState, game_data: &mut DataMap) {
+    row(|| {
+        label(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.lbl_map_offline_progress),
+        );
+
+        let current = map_offline_progress(&state.resource_man, game_data).unwrap_or(0) as i32;
+        let mut new = current;
+
+        // 0 stands for not catching up
+        slider(
+            &mut new,
+            0..=MAX_OFFLINE_PROGRESS as i32,
+            None,
+            |v| v.parse().ok(),
+            |v| {
+                if *v == 0 {
+                    state
+                        .resource_man
+                        .gui_str(
+                            state
+                                .resource_man
+                                .registry
+                                .gui_ids
+                                .lbl_map_offline_progress_off,
+                        )
+                        .to_string()
+                } else {
+                    format!("{: >2}", v)
+                }
+            },
+        );
+
+        if new != current {
+            set_map_offline_progress(
+                &state.resource_man,
+                game_data,
+                (new > 0).then_some(new as u32),
+            );
+        }
+    });
+}
+
+fn budget_rules(state: &mut GameState, game_data: &mut DataMap) {
+    label(
+        &state
+            .resource_man
+            .gui_str(state.resource_man.registry.gui_ids.lbl_map_budget),
+    );
+
+    let budget = MapBudget::of(&state.resource_man, game_data);
+
+    for limit in BudgetLimit::ALL {
+        row(|| {
+            label(&limit.name(&state.resource_man));
+
+            let current = budget.limit(limit).unwrap_or(0) as i32;
+            let mut new = current;
+
+            // 0 stands for no limit
+            slider(
+                &mut new,
+                0..=limit.max() as i32,
+                None,
+                |v| v.parse().ok(),
+                |v| {
+                    if *v == 0 {
+                        state
+                            .resource_man
+                            .gui_str(state.resource_man.registry.gui_ids.lbl_map_budget_off)
+                            .to_string()
+                    } else {
+                        format!("{: >6}", v)
+                    }
+                },
+            );
+
+            if new != current {
+                set_map_budget(
+                    &state.resource_man,
+                    game_data,
+                    limit,
+                    (new > 0).then_some(new as u32),
+                );
+            }
+        });
+    }
+
+    row(|| {
+        let mut block = budget.block;
+        checkbox(&mut block);
+
+        label(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.lbl_map_budget_block),
+        );
+
+        if block != budget.block {
+            set_map_budget_block(&state.resource_man, game_data, block);
+        }
+    });
+}
+
 fn player_inventory(state: &mut GameState, game_data: &mut DataMap) {
     heading(
         &state
@@ -99,22 +357,30 @@ fn research_selection(state: &mut GameState, game_data: &mut DataMap) {
 
     let mut visitor = Topo::new(&state.resource_man.registry.researches);
 
+    // only the researches whose prerequisite is done are listed
+    let mut shown = Vec::new();
+    while let Some(idx) = visitor.next(&state.resource_man.registry.researches) {
+        let research = &state.resource_man.registry.researches[idx];
+
+        if let Some(prev) = research.depends_on {
+            if !is_research_unlocked(prev, &state.resource_man, game_data) {
+                continue;
+            }
+        }
+
+        shown.push(idx);
+    }
+
     scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 200.0), || {
         group(|| {
             col(|| {
-                while let Some(idx) = visitor.next(&state.resource_man.registry.researches) {
-                    let research = &state.resource_man.registry.researches[idx];
+                virtual_list(shown.len(), |index| {
+                    let research = &state.resource_man.registry.researches[shown[index]];
                     let icon = match research.icon_mode {
                         IconMode::Tile => state.resource_man.model_or_missing_tile(&research.icon),
                         IconMode::Item => state.resource_man.model_or_missing_item(&research.icon),
                     };
 
-                    if let Some(prev) = research.depends_on {
-                        if !is_research_unlocked(prev, &state.resource_man, game_data) {
-                            continue;
-                        }
-                    }
-
                     let interact = interactive(|| {
                         centered_horizontal(|| {
                             ui_game_object(
@@ -136,7 +402,7 @@ fn research_selection(state: &mut GameState, game_data: &mut DataMap) {
                         state.puzzle_state = None; // TODO have a better save system for this
                         state.ui_state.force_show_puzzle = false;
                     };
-                }
+                });
             });
         });
     });
@@ -483,6 +749,18 @@ pub fn player(state: &mut GameState, game_data: &mut DataMap) {
                 set.insert(research.id);
             }
 
+            push_screen_effect(
+                None,
+                ScreenEffect::Flash {
+                    color: colors::ORANGE.with_alpha(0.3),
+                    duration: RESEARCH_FLASH_DURATION,
+                },
+            );
+
+            if let Some(cutscene) = research.cutscene {
+                cutscene::play_cutscene(state, cutscene);
+            }
+
             state.ui_state.selected_research_puzzle_tile = None;
             state.ui_state.research_puzzle_selections = None;
             state.ui_state.force_show_puzzle = true;
@@ -524,6 +802,13 @@ pub fn player(state: &mut GameState, game_data: &mut DataMap) {
                             current_research(state, game_data);
                         });
 
+                        locked_tile_visibility(state, game_data);
+                        tick_rate_rule(state, game_data);
+                        random_tick_speed_rule(state, game_data);
+                        field_diffusion_rule(state, game_data);
+                        offline_progress_rule(state, game_data);
+                        budget_rules(state, game_data);
+
                         row(|| {
                             col(|| {
                                 board_pos = research_puzzle(state, game_data);
@@ -1,54 +1,455 @@
-use automancy_system::game::COULD_NOT_LOAD_ANYTHING;
-use automancy_system::map::{self, GameMap, LoadMapOption};
-use automancy_system::ui_state::{PopupState, Screen, TextField};
-use automancy_system::{game_load_map, GameLoadResult};
+use automancy_system::changelog::{RegistryDiff, RELEASE_NOTES_PATH};
+use automancy_system::game::{GameSystemMessage, COULD_NOT_LOAD_ANYTHING};
+use automancy_system::map::{self, GameMap, LoadMapOption, NamespaceChanges, MAP_PATH};
+use automancy_system::ui_state::{MapEndSummary, PopupAction, PopupState, Screen, TextField};
+use automancy_system::verify;
+use automancy_system::{game_load_map, game_load_map_inner, GameLoadResult};
 
 use crate::event::refresh_maps;
+use crate::gui::{
+    beacon, cutscene,
+    menu::{load_map, try_load_map},
+    toast::{push_toast, LONG_TOAST},
+};
 use crate::GameState;
-use automancy_ui::{button, label, row, textbox, window};
-use std::fs;
+use automancy_defs::{glam::Vec2, id::TileId};
+use automancy_resources::error::{mute_err, push_err, ErrorSeverity};
+use automancy_resources::format::{FormatContext, Formattable};
+use automancy_resources::types::function::MapOutcome;
+use automancy_ui::{
+    button, col, confirm_dialog, label, message_dialog, progress_dialog, row, scroll_vertical,
+    selection_box, text_input_dialog, window, DialogResponse, Toast, ToastPriority,
+};
+use std::path::PathBuf;
+use std::{fs, iter, mem};
+
+/// Runs the action of a confirmed dialog or a toast.
+pub fn run_action(state: &mut GameState, action: PopupAction) {
+    match action {
+        PopupAction::Close => {}
+        PopupAction::CreateMap => {
+            let name = map::join_save_path(
+                &state.ui_state.map_folder,
+                &map::sanitize_name(state.ui_state.text_field.take(TextField::MapName)),
+            );
+            state.ui_state.read_only = false;
+
+            match game_load_map(state, name) {
+                GameLoadResult::Loaded => {
+                    state.ui_state.switch_screen(Screen::Ingame);
+
+                    // new maps start with the intro, if a pack has one
+                    let intro = state.resource_man.registry.data_ids.intro_cutscene;
+                    if state.resource_man.registry.cutscenes.contains_key(&intro) {
+                        cutscene::play_cutscene(state, intro);
+                    }
+                }
+                GameLoadResult::LoadedMainMenu => {
+                    state.ui_state.switch_screen(Screen::MainMenu);
+                }
+                GameLoadResult::Failed => {
+                    panic!("{}", COULD_NOT_LOAD_ANYTHING)
+                }
+            }
+        }
+        PopupAction::DeleteMap(map_name) => {
+            fs::remove_dir_all(GameMap::path(&LoadMapOption::FromSave(map_name.clone())).unwrap())
+                .unwrap();
+            log::info!("Deleted map {map_name}!");
+
+            refresh_maps(state);
+        }
+        PopupAction::DeleteMaps(map_names) => {
+            for map_name in map_names {
+                if let Err(err) = fs::remove_dir_all(
+                    GameMap::path(&LoadMapOption::FromSave(map_name.clone())).unwrap(),
+                ) {
+                    log::error!("Could not delete map {map_name}: {err}");
+                } else {
+                    log::info!("Deleted map {map_name}!");
+                }
+            }
+
+            refresh_maps(state);
+        }
+        PopupAction::RenameMaps(map_names) => {
+            let name = map::sanitize_name(state.ui_state.text_field.take(TextField::MapRenaming));
+            let numbered = map_names.len() > 1;
+            let mut failed = false;
+
+            for (index, map_name) in map_names.iter().enumerate() {
+                let new_name = if numbered {
+                    format!("{name}_{}", index + 1)
+                } else {
+                    name.clone()
+                };
+                let new_name = map::join_save_path(map::split_save_path(map_name).0, &new_name);
+
+                let to = GameMap::path(&LoadMapOption::FromSave(new_name.clone())).unwrap();
+
+                if to.exists()
+                    || fs::rename(
+                        GameMap::path(&LoadMapOption::FromSave(map_name.clone())).unwrap(),
+                        to,
+                    )
+                    .is_err()
+                {
+                    failed = true;
+                } else {
+                    log::info!("Renamed map {map_name} to {new_name}");
+                }
+            }
+
+            if failed {
+                state.ui_state.push_popup(PopupState::Message {
+                    title: state.resource_man.registry.gui_ids.invalid_name,
+                    message: state.resource_man.registry.gui_ids.lbl_pick_another_name,
+                    action: PopupAction::Close,
+                });
+            }
+
+            state.ui_state.selected_maps.clear();
+            refresh_maps(state);
+        }
+        PopupAction::CreateFolder => {
+            let folder = map::sanitize_folder(&map::join_save_path(
+                &state.ui_state.map_folder,
+                &state.ui_state.text_field.take(TextField::FolderName),
+            ));
+
+            if let Err(err) = fs::create_dir_all(PathBuf::from(MAP_PATH).join(&folder)) {
+                log::error!("Could not create the folder {folder}: {err}");
+            } else {
+                state.ui_state.map_folder = folder;
+            }
+
+            refresh_maps(state);
+        }
+        PopupAction::AddBeacon(coord) => {
+            beacon::add_beacon(state, coord);
+        }
+        PopupAction::ResumeSimulation => {
+            if let Err(err) = state.game.send_message(GameSystemMessage::SetPaused(false)) {
+                log::error!("Could not resume the simulation! Error: {err:?}");
+            }
+        }
+        PopupAction::MuteError(id) => {
+            let key = mute_err(id, &state.resource_man);
+            state.misc_options.muted_errors.insert(key);
+
+            if state.misc_options.save().is_err() {
+                push_err(
+                    state.resource_man.registry.err_ids.unwritable_options,
+                    ErrorSeverity::Warn,
+                    &FormatContext::from([].into_iter()),
+                    &state.resource_man,
+                );
+            }
+        }
+        PopupAction::DisableAdaptiveQuality => {
+            state.options.graphics.adaptive_quality = false;
+            state.loop_store.quality.reset();
+
+            if state.options.save().is_err() {
+                push_err(
+                    state.resource_man.registry.err_ids.unwritable_options,
+                    ErrorSeverity::Warn,
+                    &FormatContext::from([].into_iter()),
+                    &state.resource_man,
+                );
+            }
+        }
+    }
+}
+
+/// Draws the topmost popup, if there is one.
+pub fn popup(state: &mut GameState) {
+    let Some(popup) = state.ui_state.popup().cloned() else {
+        return;
+    };
+
+    let resource_man = state.resource_man.clone();
+    let gui_ids = &resource_man.registry.gui_ids;
+
+    match popup {
+        PopupState::Message {
+            title,
+            message,
+            action,
+        } => {
+            if message_dialog(
+                resource_man.gui_str(title).to_string(),
+                &resource_man.gui_str(message),
+                &resource_man.gui_str(gui_ids.btn_confirm),
+            ) {
+                state.ui_state.close_popup();
+                run_action(state, action);
+            }
+        }
+        PopupState::Confirm {
+            title,
+            message,
+            action,
+        } => match confirm_dialog(
+            resource_man.gui_str(title).to_string(),
+            &resource_man.gui_str(message),
+            &resource_man.gui_str(gui_ids.btn_confirm),
+            &resource_man.gui_str(gui_ids.btn_cancel),
+        ) {
+            DialogResponse::None => {}
+            DialogResponse::Confirm => {
+                state.ui_state.close_popup();
+                run_action(state, action);
+            }
+            DialogResponse::Cancel => {
+                state.ui_state.close_popup();
+            }
+        },
+        PopupState::TextInput {
+            title,
+            label,
+            field,
+            action,
+        } => match text_input_dialog(
+            resource_man.gui_str(title).to_string(),
+            &resource_man.gui_str(label),
+            state.ui_state.text_field.get(field),
+            None,
+            &resource_man.gui_str(gui_ids.btn_confirm),
+            &resource_man.gui_str(gui_ids.btn_cancel),
+        ) {
+            DialogResponse::None => {}
+            DialogResponse::Confirm => {
+                state.ui_state.close_popup();
+                run_action(state, action);
+                state.ui_state.text_field.get(field).clear();
+            }
+            DialogResponse::Cancel => {
+                state.ui_state.close_popup();
+                state.ui_state.text_field.get(field).clear();
+            }
+        },
+        PopupState::Progress {
+            title,
+            message,
+            fraction,
+        } => {
+            progress_dialog(resource_man.gui_str(title).to_string(), &message, fraction);
+        }
+        PopupState::MapNamespaces(map_name, changes) => {
+            map_namespaces_popup(state, &map_name, &changes);
+        }
+        PopupState::MapRemap(map_name) => {
+            map_remap_popup(state, &map_name);
+        }
+        PopupState::MapVerify(map_name) => {
+            map_verify_popup(state, &map_name);
+        }
+        PopupState::RegistryChanges(diff) => {
+            registry_changes_popup(state, &diff);
+        }
+        PopupState::MapEnd(outcome, summary) => {
+            map_end_popup(state, &outcome, &summary);
+        }
+    }
+}
+
+/// Draws the end screen of a map that was won or lost, with a summary of how it went. Scenarios that name a next map
+/// offer to continue on it.
+pub fn map_end_popup(state: &mut GameState, outcome: &MapOutcome, summary: &MapEndSummary) {
+    let gui_ids = state.resource_man.registry.gui_ids;
+
+    let (title, message, next_map) = match outcome {
+        MapOutcome::Victory { message, next_map } => (gui_ids.victory, message, next_map.as_ref()),
+        MapOutcome::Defeat { message } => (gui_ids.defeat, message, None),
+    };
+
+    window(state.resource_man.gui_str(title).to_string(), || {
+        col(|| {
+            if !message.is_empty() {
+                label(message);
+            }
+
+            label(&state.resource_man.gui_fmt(
+                gui_ids.lbl_map_end_ticks,
+                [("ticks", Formattable::integer(&summary.ticks))],
+            ));
+            label(&state.resource_man.gui_fmt(
+                gui_ids.lbl_map_end_tiles,
+                [("count", Formattable::integer(&summary.tiles))],
+            ));
+
+            if !summary.produced.is_empty() {
+                label(&state.resource_man.gui_str(gui_ids.lbl_map_end_produced));
+
+                for (id, amount) in &summary.produced {
+                    row(|| {
+                        label(&state.resource_man.item_name(*id));
+                        label(&amount.to_string());
+                    });
+                }
+            }
+
+            row(|| {
+                if let Some(next_map) = next_map {
+                    if button(&state.resource_man.gui_str(gui_ids.btn_next_map)).clicked {
+                        state.ui_state.close_popup();
+
+                        if GameMap::path(&LoadMapOption::FromSave(next_map.clone()))
+                            .is_some_and(|path| path.exists())
+                        {
+                            state
+                                .tokio
+                                .block_on(state.game.call(GameSystemMessage::SaveMap, None))
+                                .unwrap()
+                                .unwrap();
+
+                            try_load_map(state, next_map.clone());
+                        } else {
+                            push_toast(
+                                Toast::new(
+                                    state.resource_man.gui_fmt(
+                                        gui_ids.lbl_next_map_missing,
+                                        [("map", Formattable::display(next_map))],
+                                    ),
+                                    ToastPriority::Normal,
+                                )
+                                .with_timeout(LONG_TOAST),
+                            );
+                        }
+                    }
+                }
+
+                if button(&state.resource_man.gui_str(gui_ids.btn_continue)).clicked {
+                    state.ui_state.close_popup();
+                }
+
+                if button(&state.resource_man.gui_str(gui_ids.btn_exit)).clicked {
+                    state.ui_state.close_popup();
+
+                    state
+                        .tokio
+                        .block_on(state.game.call(GameSystemMessage::SaveMap, None))
+                        .unwrap()
+                        .unwrap();
+
+                    assert!(
+                        game_load_map_inner(state, LoadMapOption::MainMenu)
+                            != GameLoadResult::Failed,
+                        "{}",
+                        COULD_NOT_LOAD_ANYTHING
+                    );
+
+                    state.ui_state.switch_screen(Screen::MainMenu)
+                }
+            });
+        });
+    });
+}
+
+/// Draws the popup warning that the namespaces changed since a map was last saved, before loading it.
+pub fn map_namespaces_popup(state: &mut GameState, map_name: &str, changes: &NamespaceChanges) {
+    let gui_ids = state.resource_man.registry.gui_ids;
 
-pub fn invalid_name_popup(state: &mut GameState) {
     window(
         state
             .resource_man
-            .gui_str(state.resource_man.registry.gui_ids.invalid_name)
+            .gui_str(gui_ids.namespaces_changed)
             .to_string(),
         || {
-            label(
-                &state
-                    .resource_man
-                    .gui_str(state.resource_man.registry.gui_ids.lbl_pick_another_name),
-            );
+            col(|| {
+                label(&state.resource_man.gui_str(gui_ids.lbl_namespaces_changed));
 
-            if button(
-                &state
-                    .resource_man
-                    .gui_str(state.resource_man.registry.gui_ids.btn_confirm),
-            )
-            .clicked
-            {
-                state.ui_state.popup = PopupState::None;
-            }
+                scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 200.0), || {
+                    col(|| {
+                        for namespace in &changes.added {
+                            label(&state.resource_man.gui_fmt(
+                                gui_ids.lbl_namespace_added,
+                                [("namespace", Formattable::display(namespace))],
+                            ));
+                        }
+
+                        for namespace in &changes.removed {
+                            label(&state.resource_man.gui_fmt(
+                                gui_ids.lbl_namespace_removed,
+                                [("namespace", Formattable::display(namespace))],
+                            ));
+                        }
+
+                        for (namespace, old, new) in &changes.changed {
+                            // a pack without a version in its manifest
+                            let old = old.as_deref().unwrap_or("?");
+                            let new = new.as_deref().unwrap_or("?");
+
+                            label(&state.resource_man.gui_fmt(
+                                gui_ids.lbl_namespace_version_changed,
+                                [
+                                    ("namespace", Formattable::display(namespace)),
+                                    ("old", Formattable::display(&old)),
+                                    ("new", Formattable::display(&new)),
+                                ],
+                            ));
+                        }
+                    });
+                });
+
+                row(|| {
+                    if button(&state.resource_man.gui_str(gui_ids.btn_continue)).clicked {
+                        state.ui_state.close_popup();
+
+                        try_load_map(state, map_name.to_string());
+                    }
+
+                    if button(&state.resource_man.gui_str(gui_ids.btn_cancel)).clicked {
+                        state.ui_state.close_popup();
+                    }
+                });
+            });
         },
     );
 }
 
-pub fn map_delete_popup(state: &mut GameState, map_name: &str) {
-    let mut dirty = false;
-
+/// Draws the popup for remapping the missing tile IDs of a map before loading it.
+pub fn map_remap_popup(state: &mut GameState, map_name: &str) {
     window(
         state
             .resource_man
-            .gui_str(state.resource_man.registry.gui_ids.delete_map)
+            .gui_str(state.resource_man.registry.gui_ids.remap_ids)
             .to_string(),
         || {
             label(
                 &state
                     .resource_man
-                    .gui_str(state.resource_man.registry.gui_ids.lbl_delete_map_confirm),
+                    .gui_str(state.resource_man.registry.gui_ids.lbl_remap_ids),
             );
 
+            let none = TileId(state.resource_man.registry.none);
+
+            for (id, choice) in &mut state.ui_state.id_remap_choices {
+                row(|| {
+                    label(id);
+
+                    *choice = selection_box(
+                        iter::once(None).chain(
+                            state
+                                .resource_man
+                                .ordered_tiles
+                                .load()
+                                .iter()
+                                .filter(|tile| **tile != none)
+                                .map(|tile| Some(*tile)),
+                        ),
+                        *choice,
+                        &|choice| match choice {
+                            Some(tile) => state.resource_man.tile_name(*tile),
+                            None => state
+                                .resource_man
+                                .gui_str(state.resource_man.registry.gui_ids.btn_drop),
+                        },
+                    );
+                });
+            }
+
             if button(
                 &state
                     .resource_man
@@ -56,13 +457,29 @@ pub fn map_delete_popup(state: &mut GameState, map_name: &str) {
             )
             .clicked
             {
-                fs::remove_dir_all(
-                    GameMap::path(&LoadMapOption::FromSave(map_name.to_string())).unwrap(),
-                )
-                .unwrap();
-                dirty = true;
-                state.ui_state.popup = PopupState::None;
-                log::info!("Deleted map {map_name}!");
+                let opt = LoadMapOption::FromSave(map_name.to_string());
+
+                let mut remap = GameMap::read_remap(&opt);
+                for (id, choice) in mem::take(&mut state.ui_state.id_remap_choices) {
+                    remap.0.insert(
+                        id,
+                        choice.and_then(|tile| {
+                            state
+                                .resource_man
+                                .interner
+                                .resolve(*tile)
+                                .map(str::to_string)
+                        }),
+                    );
+                }
+
+                if let Err(err) = GameMap::write_remap(&opt, &remap) {
+                    log::error!("Could not save the ID remapping of {opt}! Error: {err:?}");
+                }
+
+                state.ui_state.close_popup();
+
+                load_map(state, map_name.to_string());
             }
 
             if button(
@@ -72,66 +489,164 @@ pub fn map_delete_popup(state: &mut GameState, map_name: &str) {
             )
             .clicked
             {
-                state.ui_state.popup = PopupState::None
+                state.ui_state.id_remap_choices.clear();
+                state.ui_state.close_popup();
             }
         },
     );
-
-    if dirty {
-        refresh_maps(state);
-    }
 }
 
-/// Draws the map creation popup.
-pub fn map_create_popup(state: &mut GameState) {
+/// Draws the report of verifying a map, with a button to repair it if anything was found.
+pub fn map_verify_popup(state: &mut GameState, map_name: &str) {
+    let Some(report) = state.ui_state.map_report.clone() else {
+        state.ui_state.close_popup();
+        return;
+    };
+
+    let gui_ids = state.resource_man.registry.gui_ids;
+
     window(
-        state
-            .resource_man
-            .gui_str(state.resource_man.registry.gui_ids.create_map)
-            .to_string(),
+        state.resource_man.gui_str(gui_ids.verify_map).to_string(),
         || {
-            let name = state.ui_state.text_field.get(TextField::MapName);
+            col(|| {
+                if report.is_ok() {
+                    label(&state.resource_man.gui_str(gui_ids.lbl_map_verified));
+                } else if let Some(backup) = &report.backup {
+                    label(&state.resource_man.gui_fmt(
+                        gui_ids.lbl_map_repaired,
+                        [
+                            ("count", Formattable::display(&report.issues.len())),
+                            ("backup", Formattable::display(&backup.display())),
+                        ],
+                    ));
+                } else {
+                    label(&state.resource_man.gui_fmt(
+                        gui_ids.lbl_map_issues,
+                        [("count", Formattable::display(&report.issues.len()))],
+                    ));
+                }
 
-            row(|| {
-                label("Name:"); //TODO add this to translation
+                if !report.is_ok() {
+                    scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 200.0), || {
+                        col(|| {
+                            for line in report.lines() {
+                                label(&line);
+                            }
+                        });
+                    });
+                }
 
-                textbox(name, None, Some("Name your world here..."));
-            });
+                if let Some(err) = &state.ui_state.map_repair_error {
+                    label(&state.resource_man.gui_fmt(
+                        gui_ids.lbl_map_repair_failed,
+                        [("error", Formattable::display(err))],
+                    ));
+                }
 
-            if button(
-                &state
-                    .resource_man
-                    .gui_str(state.resource_man.registry.gui_ids.btn_confirm),
-            )
-            .clicked
-            {
-                let name = map::sanitize_name(name.clone());
+                row(|| {
+                    if !report.is_ok()
+                        && report.backup.is_none()
+                        && button(&state.resource_man.gui_str(gui_ids.btn_repair)).clicked
+                    {
+                        let opt = LoadMapOption::FromSave(map_name.to_string());
 
-                state.ui_state.text_field.get(TextField::MapName).clear();
-                state.ui_state.popup = PopupState::None;
+                        match verify::repair_map(&state.resource_man, &opt) {
+                            Ok(report) => {
+                                state.ui_state.map_report = Some(report);
+                                state.ui_state.map_repair_error = None;
 
-                match game_load_map(state, name) {
-                    GameLoadResult::Loaded => {
-                        state.ui_state.switch_screen(Screen::Ingame);
+                                // the loaded map still has the problems, and would save them back
+                                load_map(state, map_name.to_string());
+                            }
+                            Err(err) => {
+                                log::error!("Could not repair {opt}! Error: {err:?}");
+
+                                state.ui_state.map_repair_error = Some(err.to_string());
+                            }
+                        }
                     }
-                    GameLoadResult::LoadedMainMenu => {
-                        state.ui_state.switch_screen(Screen::MainMenu);
+
+                    if button(&state.resource_man.gui_str(gui_ids.btn_confirm)).clicked {
+                        state.ui_state.map_report = None;
+                        state.ui_state.map_repair_error = None;
+                        state.ui_state.close_popup();
                     }
-                    GameLoadResult::Failed => {
-                        panic!("{}", COULD_NOT_LOAD_ANYTHING)
+                });
+            });
+        },
+    );
+}
+
+/// Draws the popup listing what the packs changed since the last launch, which can be exported as release notes.
+pub fn registry_changes_popup(state: &mut GameState, diff: &RegistryDiff) {
+    let gui_ids = state.resource_man.registry.gui_ids;
+
+    window(
+        state.resource_man.gui_str(gui_ids.whats_new).to_string(),
+        || {
+            col(|| {
+                label(&state.resource_man.gui_str(gui_ids.lbl_whats_new));
+
+                scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 300.0), || {
+                    col(|| {
+                        for (heading, changes) in [
+                            (gui_ids.lbl_registry_tiles, &diff.tiles),
+                            (gui_ids.lbl_registry_items, &diff.items),
+                            (gui_ids.lbl_registry_scripts, &diff.scripts),
+                        ] {
+                            if changes.is_empty() {
+                                continue;
+                            }
+
+                            label(&state.resource_man.gui_str(heading));
+
+                            for (change, ids) in [
+                                (gui_ids.lbl_registry_added, &changes.added),
+                                (gui_ids.lbl_registry_removed, &changes.removed),
+                                (gui_ids.lbl_registry_changed, &changes.changed),
+                            ] {
+                                for id in ids {
+                                    label(
+                                        &state
+                                            .resource_man
+                                            .gui_fmt(change, [("id", Formattable::display(id))]),
+                                    );
+                                }
+                            }
+                        }
+                    });
+                });
+
+                row(|| {
+                    if button(&state.resource_man.gui_str(gui_ids.btn_export_notes)).clicked {
+                        push_toast(match fs::write(RELEASE_NOTES_PATH, diff.to_markdown()) {
+                            Ok(()) => Toast::new(
+                                state.resource_man.gui_fmt(
+                                    gui_ids.lbl_notes_exported,
+                                    [("path", Formattable::display(&RELEASE_NOTES_PATH))],
+                                ),
+                                ToastPriority::Normal,
+                            )
+                            .with_timeout(LONG_TOAST),
+                            Err(err) => {
+                                log::error!("Could not export the release notes: {err}");
+
+                                Toast::new(
+                                    state.resource_man.gui_fmt(
+                                        gui_ids.lbl_notes_export_failed,
+                                        [("error", Formattable::display(&err))],
+                                    ),
+                                    ToastPriority::High,
+                                )
+                            }
+                        });
                     }
-                }
-            }
 
-            if button(
-                &state
-                    .resource_man
-                    .gui_str(state.resource_man.registry.gui_ids.btn_cancel),
-            )
-            .clicked
-            {
-                state.ui_state.popup = PopupState::None
-            }
+                    if button(&state.resource_man.gui_str(gui_ids.btn_confirm)).clicked {
+                        state.ui_state.close_popup();
+                    }
+                });
+            });
         },
     );
 }
@@ -0,0 +1,87 @@
+use crate::GameState;
+use automancy_defs::coord::TileCoord;
+use automancy_defs::math::Vec2;
+use automancy_resources::types::tile::TileAction;
+use automancy_system::game::GameSystemMessage;
+use automancy_system::ui_state::RadialMenuState;
+use ractor::rpc::CallResult;
+use std::time::Duration;
+
+/// How long the alternate button has to be held on a tile to open its radial menu.
+pub const RADIAL_HOLD: Duration = Duration::from_millis(350);
+
+/// Gets the mouse position in UI units, which differ from the window's pixels by the UI scale.
+pub fn mouse_ui_pos(state: &GameState) -> Vec2 {
+    state.input_handler.main_pos / state.gui.as_ref().unwrap().yak.layout_dom().scale_factor()
+}
+
+/// Opens the radial menu of the tile at the coordinate, at the mouse. Does nothing if there is no tile, or it has no
+/// actions that can be taken.
+pub fn open_radial_menu(state: &mut GameState, coord: TileCoord) {
+    let Ok(CallResult::Success(Some(id))) = state.tokio.block_on(
+        state
+            .game
+            .call(|reply| GameSystemMessage::GetTile(coord, reply), None),
+    ) else {
+        return;
+    };
+
+    let Some(tile) = state.resource_man.registry.tiles.get(&id) else {
+        return;
+    };
+
+    let read_only = state.ui_state.read_only;
+    // scenery has no tile entity to configure or hold links
+    let scenery = tile.scenery.is_some();
+
+    let actions = tile
+        .actions
+        .iter()
+        .copied()
+        .filter(|action| match action {
+            TileAction::Configure => !read_only && !scenery,
+            TileAction::CopySettings | TileAction::Delete => !read_only,
+            TileAction::JumpLink(_) => !scenery,
+        })
+        .collect::<Vec<_>>();
+
+    if actions.is_empty() {
+        return;
+    }
+
+    state.ui_state.radial_menu = Some(RadialMenuState {
+        coord,
+        center: mouse_ui_pos(state),
+        actions,
+    });
+
+    state.play_sound("click");
+}
+
+fn action_name(state: &GameState, action: TileAction) -> String {
+    let gui_ids = &state.resource_man.registry.gui_ids;
+
+    let id = match action {
+        TileAction::Configure => gui_ids.lbl_action_configure,
+        TileAction::CopySettings => gui_ids.lbl_action_copy_settings,
+        TileAction::Delete => gui_ids.lbl_action_delete,
+        TileAction::JumpLink(_) => gui_ids.lbl_action_jump_link,
+    };
+
+    state.resource_man.gui_str(id).to_string()
+}
+
+/// Draws the open radial menu, if there is one.
+pub fn radial_menu(state: &mut GameState) {
+    let Some(menu) = &state.ui_state.radial_menu else {
+        return;
+    };
+
+    let entries = menu
+        .actions
+        .iter()
+        .map(|action| action_name(state, *action))
+        .collect::<Vec<_>>();
+
+    automancy_ui::radial_menu(menu.center, mouse_ui_pos(state), &entries);
+}
@@ -0,0 +1,152 @@
+use crate::GameState;
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::{Id, TileId};
+use automancy_resources::data::{Data, DataMap};
+use automancy_resources::format::Formattable;
+use automancy_resources::ResourceManager;
+use automancy_system::game::GameSystemMessage;
+use automancy_system::ui_state::{MapSearchKind, TextField};
+use automancy_ui::{
+    button, col, label, radio, row, scroll_vertical, textbox, virtual_list, window,
+};
+use ractor::rpc::CallResult;
+use yakui::Vec2;
+
+/// Checks if the name or the ID string of the ID contains the query, which is lowercase.
+fn id_matches(resource_man: &ResourceManager, id: Id, name: &str, query: &str) -> bool {
+    name.to_lowercase().contains(query)
+        || resource_man
+            .interner
+            .resolve(id)
+            .is_some_and(|v| v.to_lowercase().contains(query))
+}
+
+/// Checks if an item the data holds in an inventory, or has set, matches the query.
+fn data_has_item(resource_man: &ResourceManager, data: &DataMap, query: &str) -> bool {
+    let item_matches = |id: &Id| {
+        resource_man.registry.items.contains_key(id)
+            && id_matches(resource_man, *id, &resource_man.item_name(*id), query)
+    };
+
+    data.keys().any(|key| match data.get(*key) {
+        Some(Data::Inventory(inventory)) => inventory
+            .iter()
+            .any(|(id, amount)| *amount > 0 && item_matches(id)),
+        Some(Data::Id(id)) => item_matches(id),
+        Some(Data::VecId(ids)) => ids.iter().any(item_matches),
+        Some(Data::SetId(ids)) => ids.iter().any(item_matches),
+        _ => false,
+    })
+}
+
+/// Finds every tile of the map that matches the query, nearest to where the camera is pointing at first.
+fn search_map(state: &mut GameState, kind: MapSearchKind, query: &str) -> Vec<(TileCoord, TileId)> {
+    let query = query.trim().to_lowercase();
+
+    if query.is_empty() {
+        return vec![];
+    }
+
+    let Ok(CallResult::Success(snapshot)) = state
+        .tokio
+        .block_on(state.game.call(GameSystemMessage::SnapshotAll, None))
+    else {
+        return vec![];
+    };
+
+    let resource_man = &state.resource_man;
+
+    let mut results = snapshot
+        .0
+        .into_iter()
+        .filter(|(_, (id, data))| match kind {
+            MapSearchKind::Tile => {
+                id_matches(resource_man, **id, &resource_man.tile_name(*id), &query)
+            }
+            MapSearchKind::Item => data_has_item(resource_man, data, &query),
+            MapSearchKind::DataKey => data.keys().any(|key| {
+                resource_man
+                    .interner
+                    .resolve(*key)
+                    .is_some_and(|v| v.to_lowercase().contains(&query))
+            }),
+        })
+        .map(|(coord, (id, _))| (coord, id))
+        .collect::<Vec<_>>();
+
+    let center = state.camera.pointing_at;
+    results.sort_by_key(|(coord, _)| center.unsigned_distance_to(**coord));
+
+    results
+}
+
+/// Draws the map search dialog, which finds every tile of a type, holding an item, or with a data key, and jumps the
+/// camera to them.
+pub fn map_search_ui(state: &mut GameState) {
+    let Some(mut search) = state.ui_state.map_search.take() else {
+        return;
+    };
+
+    let gui_ids = state.resource_man.registry.gui_ids;
+    let mut open = true;
+
+    window(
+        state.resource_man.gui_str(gui_ids.map_search).to_string(),
+        || {
+            col(|| {
+                row(|| {
+                    for (kind, name) in [
+                        (MapSearchKind::Tile, gui_ids.lbl_search_tile),
+                        (MapSearchKind::Item, gui_ids.lbl_search_item),
+                        (MapSearchKind::DataKey, gui_ids.lbl_search_data_key),
+                    ] {
+                        radio(&mut search.kind, kind, || {
+                            label(&state.resource_man.gui_str(name));
+                        });
+                    }
+                });
+
+                row(|| {
+                    let query = state.ui_state.text_field.get(TextField::MapSearch);
+
+                    let activated = textbox(query, None, None).activated;
+
+                    if button(&state.resource_man.gui_str(gui_ids.btn_search)).clicked || activated
+                    {
+                        let query = query.clone();
+
+                        search.results = search_map(state, search.kind, &query);
+                    }
+                });
+
+                label(&state.resource_man.gui_fmt(
+                    gui_ids.lbl_search_results,
+                    [("count", Formattable::display(&search.results.len()))],
+                ));
+
+                scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 240.0), || {
+                    virtual_list(search.results.len(), |index| {
+                        let (coord, id) = search.results[index];
+
+                        row(|| {
+                            label(&state.resource_man.tile_name(id));
+                            label(&coord.to_string());
+
+                            if button(&state.resource_man.gui_str(gui_ids.btn_go_to)).clicked {
+                                state.camera.jump_to(coord);
+                            }
+                        });
+                    });
+                });
+
+                if button(&state.resource_man.gui_str(gui_ids.btn_exit)).clicked {
+                    open = false;
+                }
+            });
+        },
+    );
+
+    if open {
+        state.ui_state.map_search = Some(search);
+    }
+}
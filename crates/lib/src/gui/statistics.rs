@@ -0,0 +1,189 @@
+use crate::gui::toast::{push_toast, LONG_TOAST};
+use crate::GameState;
+use automancy_defs::{colors::BACKGROUND_3, glam::Vec2, log};
+use automancy_resources::format::Formattable;
+use automancy_system::game::GameSystemMessage;
+use automancy_system::stats::Statistics;
+use automancy_ui::{
+    bar_chart, button, col, colored_label, heading, label, line_chart, movable, row,
+    scroll_vertical, window, Series, Toast, ToastPriority, CHART_COLORS, DIVIER_HEIGHT,
+    DIVIER_THICKNESS,
+};
+use ractor::rpc::CallResult;
+use std::time::{Duration, Instant};
+use yakui::{divider, widgets::Layer};
+
+/// How often the statistics screen fetches the statistics again while it is open.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// How many of the most produced items the production chart shows.
+const CHARTED_ITEMS: usize = CHART_COLORS.len();
+
+/// Gets the statistics of the map, fetching them again if the cached ones are too old.
+fn statistics(state: &mut GameState) -> Option<Statistics> {
+    if let Some((statistics, fetched)) = &state.ui_state.statistics {
+        if fetched.elapsed() < REFRESH_INTERVAL {
+            return Some(statistics.clone());
+        }
+    }
+
+    let Ok(CallResult::Success(statistics)) = state
+        .tokio
+        .block_on(state.game.call(GameSystemMessage::GetStatistics, None))
+    else {
+        return None;
+    };
+
+    state.ui_state.statistics = Some((statistics.clone(), Instant::now()));
+
+    Some(statistics)
+}
+
+/// Exports the statistics into the map folder, and shows the outcome in a toast.
+fn export(state: &mut GameState, statistics: &Statistics, json: bool) {
+    let Some((_, opt)) = &state.loop_store.map_info else {
+        return;
+    };
+
+    let result = if json {
+        statistics
+            .write_json(&state.resource_man, opt)
+            .map(|path| path.display().to_string())
+    } else {
+        statistics.write_csv(&state.resource_man, opt).map(|paths| {
+            paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+    };
+
+    let gui_ids = state.resource_man.registry.gui_ids;
+
+    push_toast(match result {
+        Ok(paths) => Toast::new(
+            state.resource_man.gui_fmt(
+                gui_ids.lbl_statistics_exported,
+                [("paths", Formattable::display(&paths))],
+            ),
+            ToastPriority::Normal,
+        )
+        .with_timeout(LONG_TOAST),
+        Err(err) => {
+            log::error!("Could not export the statistics: {err}");
+
+            Toast::new(
+                state.resource_man.gui_fmt(
+                    gui_ids.lbl_statistics_export_failed,
+                    [("error", Formattable::display(&err))],
+                ),
+                ToastPriority::High,
+            )
+        }
+    });
+}
+
+/// Draws the statistics screen, listing what the factory produces and the machines it is made of.
+pub fn statistics_ui(state: &mut GameState) {
+    let Some(statistics) = statistics(state) else {
+        return;
+    };
+
+    let gui_ids = state.resource_man.registry.gui_ids;
+
+    Layer::new().show(|| {
+        let mut pos = state.ui_state.player_ui_position;
+        movable(&mut pos, || {
+            window(
+                state.resource_man.gui_str(gui_ids.statistics).to_string(),
+                || {
+                    col(|| {
+                        heading(
+                            &state
+                                .resource_man
+                                .gui_str(gui_ids.lbl_production_per_minute),
+                        );
+
+                        // the last sample is still running, so show the last finished one if there is one
+                        let sample = statistics.production.len().saturating_sub(2);
+
+                        let mut items = statistics
+                            .items()
+                            .into_iter()
+                            .map(|item| (item, statistics.history(item)))
+                            .collect::<Vec<_>>();
+                        items.sort_by_key(|(_, history)| std::cmp::Reverse(history[sample]));
+
+                        // the most produced items are charted, and colored the same in the list
+                        line_chart(
+                            items
+                                .iter()
+                                .take(CHARTED_ITEMS)
+                                .zip(CHART_COLORS)
+                                .map(|((_, history), color)| Series {
+                                    color,
+                                    values: history[..=sample].iter().map(|v| *v as f32).collect(),
+                                })
+                                .collect(),
+                            Vec2::new(360.0, 120.0),
+                        );
+
+                        scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 200.0), || {
+                            col(|| {
+                                for (index, (item, history)) in items.iter().enumerate() {
+                                    let text = format!(
+                                        "{}: {}",
+                                        state.resource_man.item_name(*item),
+                                        history[sample]
+                                    );
+
+                                    if let Some(color) = CHART_COLORS.get(index) {
+                                        colored_label(&text, *color);
+                                    } else {
+                                        label(&text);
+                                    }
+                                }
+                            });
+                        });
+
+                        divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
+
+                        heading(&state.resource_man.gui_str(gui_ids.lbl_machine_counts));
+
+                        scroll_vertical(Vec2::ZERO, Vec2::new(f32::INFINITY, 200.0), || {
+                            col(|| {
+                                bar_chart(
+                                    &statistics
+                                        .sorted_machines()
+                                        .into_iter()
+                                        .map(|(id, count)| {
+                                            (
+                                                state.resource_man.tile_name(id).to_string(),
+                                                count as f32,
+                                            )
+                                        })
+                                        .collect::<Vec<_>>(),
+                                    240.0,
+                                );
+                            });
+                        });
+
+                        divider(BACKGROUND_3, DIVIER_HEIGHT, DIVIER_THICKNESS);
+
+                        row(|| {
+                            if button(&state.resource_man.gui_str(gui_ids.btn_export_csv)).clicked {
+                                export(state, &statistics, false);
+                            }
+
+                            if button(&state.resource_man.gui_str(gui_ids.btn_export_json)).clicked
+                            {
+                                export(state, &statistics, true);
+                            }
+                        });
+                    });
+                },
+            );
+        });
+        state.ui_state.player_ui_position = pos;
+    });
+}
@@ -1,18 +1,27 @@
 use crate::GameState;
 use automancy_defs::id::Id;
-use automancy_defs::{colors, coord::TileCoord, stack::ItemStack};
+use automancy_defs::{
+    colors,
+    coord::TileCoord,
+    math::Float,
+    stack::{ItemAmount, ItemStack},
+};
 use automancy_resources::rhai_ui::RhaiUiUnit;
 use automancy_resources::{
     data::{Data, DataMap},
+    format::Formattable,
     inventory::Inventory,
 };
+use automancy_system::game::{tick_interval, GameSystemMessage};
+use automancy_system::input::ActionType;
 use automancy_system::tile_entity::TileEntityMsg;
-use automancy_system::ui_state::TextField;
+use automancy_system::ui_state::{RegionEditState, TextField};
 use automancy_ui::{
-    button, center_col, center_row, col, group, info_tip, interactive, label, list_col, movable,
-    num_input, row, scroll_vertical_bar_alignment, selectable_symbol_button, selection_button,
-    slider, spaced_col, spaced_row, symbol, symbol_button, window_box, PositionRecord,
-    MEDIUM_ICON_SIZE, PADDING_MEDIUM, PADDING_XSMALL, SMALL_ICON_SIZE,
+    button, center_col, center_row, col, drag_source, drop_target, group, info_tip, interactive,
+    label, list_col, movable, num_input, progress_bar, row, scroll_vertical_bar_alignment,
+    selectable_symbol_button, selection_button, slider, spaced_col, spaced_row, symbol,
+    symbol_button, window_box, PositionRecord, MEDIUM_ICON_SIZE, PADDING_MEDIUM, PADDING_XSMALL,
+    SMALL_ICON_SIZE,
 };
 use ractor::rpc::CallResult;
 use ractor::ActorRef;
@@ -23,9 +32,14 @@ use yakui::{
     Constraints, Rect, Vec2,
 };
 
-use super::item::draw_item;
+use super::item::{draw_item, DraggedStack};
 use super::util::searchable_id;
 
+/// The most items a single click can move in the storage transfer window.
+const MAX_TRANSFER_AMOUNT: ItemAmount = 100;
+/// The most tile configuration windows that can be pinned open at once.
+const MAX_PINNED_CONFIGS: usize = 8;
+
 /// Draws the direction selector.
 fn add_direction(target_coord: &mut Option<TileCoord>, n: u8) {
     let coord = match n {
@@ -122,6 +136,138 @@ fn takeable_items(
     }
 }
 
+/// Draws one pane of the storage transfer window. Returns the item that was clicked, which moves out of this pane,
+/// and the item dragged in from the other pane.
+fn transfer_pane(
+    state: &mut GameState,
+    title: Id,
+    inventory: &Inventory,
+) -> (Option<(Id, ItemAmount)>, Option<(Id, ItemAmount)>) {
+    let mut clicked = None;
+
+    let dropped = drop_target::<DraggedStack>(|| {
+        col(|| {
+            label(&state.resource_man.gui_str(title));
+
+            for (id, amount) in inventory.iter() {
+                if *amount <= 0 {
+                    continue;
+                }
+
+                let stack = ItemStack {
+                    id: *id,
+                    amount: *amount,
+                };
+
+                let interact = drag_source(DraggedStack { from: title, stack }, || {
+                    draw_item(&state.resource_man, || {}, stack, MEDIUM_ICON_SIZE, true);
+                });
+
+                if interact.clicked {
+                    clicked = Some((*id, *amount));
+                }
+            }
+        });
+    });
+
+    // dropping a stack back onto its own pane does nothing
+    let dropped = dropped
+        .filter(|v| v.from != title)
+        .map(|v| (v.stack.id, v.stack.amount));
+
+    (clicked, dropped)
+}
+
+/// Draws the window for moving items between the player's inventory and the tile's. Clicking a stack, or dragging it
+/// onto the other side, moves the selected amount, and holding shift moves the whole stack.
+fn storage_transfer(
+    state: &mut GameState,
+    game_data: &mut DataMap,
+    data: &DataMap,
+    id: Id,
+    tile_entity: ActorRef<TileEntityMsg>,
+) {
+    let Data::Inventory(player) = game_data
+        .entry(state.resource_man.registry.data_ids.player_inventory)
+        .or_insert_with(|| Data::Inventory(Default::default()))
+    else {
+        return;
+    };
+
+    let storage = match data.get(id) {
+        Some(Data::Inventory(inventory)) => inventory.clone(),
+        _ => Inventory::default(),
+    };
+
+    let gui_ids = state.resource_man.registry.gui_ids;
+    let whole_stack = state.input_handler.key_active(ActionType::SelectMode);
+    let transfer_amount = state.ui_state.transfer_amount;
+
+    let moving = |amount: ItemAmount| {
+        if whole_stack {
+            amount
+        } else {
+            amount.min(transfer_amount)
+        }
+    };
+
+    // changes to the tile's inventory
+    let mut changes = vec![];
+
+    spaced_row(|| {
+        let (clicked, dropped) = transfer_pane(state, gui_ids.player_inventory_title, player);
+
+        if let Some((item, amount)) = clicked {
+            changes.push((item, moving(amount)));
+        }
+        if let Some((item, amount)) = dropped {
+            changes.push((item, -moving(amount)));
+        }
+
+        let (clicked, dropped) = transfer_pane(state, gui_ids.storage_title, &storage);
+
+        if let Some((item, amount)) = clicked {
+            changes.push((item, -moving(amount)));
+        }
+        if let Some((item, amount)) = dropped {
+            changes.push((item, moving(amount)));
+        }
+    });
+
+    row(|| {
+        label(
+            &state
+                .resource_man
+                .gui_str(state.resource_man.registry.gui_ids.lbl_transfer_amount),
+        );
+
+        slider(
+            &mut state.ui_state.transfer_amount,
+            1..=MAX_TRANSFER_AMOUNT,
+            None,
+            |v| v.parse().ok(),
+            |v| format!("{v: >3}"),
+        );
+    });
+
+    if changes.is_empty() {
+        return;
+    }
+
+    if let Ok(CallResult::Success(applied)) = state.tokio.block_on(tile_entity.call(
+        |reply| TileEntityMsg::MutateInventory { id, changes, reply },
+        None,
+    )) {
+        for (item, amount) in applied {
+            if amount >= 0 {
+                player.take(item, amount);
+            } else {
+                player.add(item, -amount);
+            }
+        }
+    }
+}
+
 fn draw_item_plain(state: &mut GameState, id: Id) {
     draw_item(
         &state.resource_man,
@@ -177,12 +323,32 @@ fn draw_script_info(state: &mut GameState, data: &DataMap, id: Id) {
                 true,
             );
         }
+
+        for condition in &script.instructions.conditions {
+            let field = state
+                .resource_man
+                .interner
+                .resolve(condition.field)
+                .unwrap_or_default();
+            let bound = |v: Option<Float>| v.map_or("-".to_string(), |v| v.to_string());
+            let (min, max) = (bound(condition.min), bound(condition.max));
+
+            label(&state.resource_man.gui_fmt(
+                state.resource_man.registry.gui_ids.lbl_field_condition,
+                [
+                    ("field", Formattable::display(&field)),
+                    ("min", Formattable::display(&min)),
+                    ("max", Formattable::display(&max)),
+                ],
+            ));
+        }
     });
 }
 
 fn rhai_ui(
     state: &mut GameState,
     tile_entity: ActorRef<TileEntityMsg>,
+    coord: TileCoord,
     data: &DataMap,
     game_data: &mut DataMap,
     ui: RhaiUiUnit,
@@ -221,7 +387,7 @@ fn rhai_ui(
 
             if new_amount != current_amount {
                 tile_entity
-                    .send_message(TileEntityMsg::SetDataValue(id, Data::Amount(new_amount)))
+                    .send_message(TileEntityMsg::Configure(id, Some(Data::Amount(new_amount))))
                     .unwrap();
             }
         }
@@ -249,7 +415,7 @@ fn rhai_ui(
 
             if new_amount != current_amount {
                 tile_entity
-                    .send_message(TileEntityMsg::SetDataValue(id, Data::Amount(new_amount)))
+                    .send_message(TileEntityMsg::Configure(id, Some(Data::Amount(new_amount))))
                     .unwrap();
             }
         }
@@ -285,11 +451,11 @@ fn rhai_ui(
             if new_dir != current_dir {
                 if let Some(coord) = new_dir {
                     tile_entity
-                        .send_message(TileEntityMsg::SetDataValue(id, Data::Coord(coord)))
+                        .send_message(TileEntityMsg::Configure(id, Some(Data::Coord(coord))))
                         .unwrap();
                 } else {
                     tile_entity
-                        .send_message(TileEntityMsg::RemoveData(id))
+                        .send_message(TileEntityMsg::Configure(id, None))
                         .unwrap();
                 }
             }
@@ -317,7 +483,7 @@ fn rhai_ui(
             if new_id != current_id {
                 if let Some(id) = new_id {
                     tile_entity
-                        .send_message(TileEntityMsg::SetDataValue(data_id, Data::Id(id)))
+                        .send_message(TileEntityMsg::Configure(data_id, Some(Data::Id(id))))
                         .unwrap();
                 }
             }
@@ -345,7 +511,7 @@ fn rhai_ui(
             if new_id != current_id {
                 if let Some(id) = new_id {
                     tile_entity
-                        .send_message(TileEntityMsg::SetDataValue(data_id, Data::Id(id)))
+                        .send_message(TileEntityMsg::Configure(data_id, Some(Data::Id(id))))
                         .unwrap();
                 }
             }
@@ -361,22 +527,42 @@ fn rhai_ui(
                 }
             });
         }
+        RhaiUiUnit::Storage { id } => {
+            storage_transfer(state, game_data, data, id, tile_entity.clone());
+        }
         RhaiUiUnit::Linkage { id, button_text } => {
             if button(&state.resource_man.gui_str(button_text)).clicked {
-                state.ui_state.linking_tile = state.ui_state.config_open_at.zip(Some(id));
+                state.ui_state.linking_tile = Some((coord, id));
+            };
+        }
+        RhaiUiUnit::Region { id, button_text } => {
+            if button(&state.resource_man.gui_str(button_text)).clicked {
+                let region = match data.get(id) {
+                    Some(Data::TileBoundsSet(region)) => region.clone(),
+                    _ => Default::default(),
+                };
+
+                // the buttons edit the region instead of placing tiles
+                state.ui_state.selected_tile_id = None;
+                state.ui_state.editing_region = Some(RegionEditState {
+                    coord,
+                    id,
+                    region,
+                    drag: None,
+                });
             };
         }
         RhaiUiUnit::Row { e } => {
             row(|| {
                 for ui in e {
-                    rhai_ui(state, tile_entity.clone(), data, game_data, ui);
+                    rhai_ui(state, tile_entity.clone(), coord, data, game_data, ui);
                 }
             });
         }
         RhaiUiUnit::CenterRow { e } => {
             center_row(|| {
                 for ui in e {
-                    rhai_ui(state, tile_entity.clone(), data, game_data, ui);
+                    rhai_ui(state, tile_entity.clone(), coord, data, game_data, ui);
                 }
             });
         }
@@ -388,70 +574,182 @@ fn rhai_ui(
             }
             .show(|| {
                 for ui in e {
-                    rhai_ui(state, tile_entity.clone(), data, game_data, ui);
+                    rhai_ui(state, tile_entity.clone(), coord, data, game_data, ui);
                 }
             });
         }
     }
 }
 
-/// Draws the tile configuration menu.
+/// Draws the progress of the tile's current processing, and the estimated time until it is done.
+fn progress(state: &mut GameState, data: &DataMap) {
+    let Some((fraction, ticks_left)) = data.progress(&state.resource_man.registry.data_ids) else {
+        return;
+    };
+
+    let eta = tick_interval() * ticks_left.max(0) as u32;
+
+    col(|| {
+        progress_bar(fraction, Vec2::new(200.0, 8.0));
+
+        label(&state.resource_man.gui_fmt(
+            state.resource_man.registry.gui_ids.lbl_progress_eta,
+            [(
+                "eta",
+                Formattable::display(&format!("{:.1}s", eta.as_secs_f32())),
+            )],
+        ));
+    });
+}
+
+/// Draws a tile's configuration window, with a button to pin or unpin it. Returns true if the button was clicked.
+fn config_window(
+    state: &mut GameState,
+    game_data: &mut DataMap,
+    coord: TileCoord,
+    tile_entity: ActorRef<TileEntityMsg>,
+    pos: &mut Vec2,
+    pinned: bool,
+) -> bool {
+    let Ok(CallResult::Success(data)) = state
+        .tokio
+        .block_on(tile_entity.call(TileEntityMsg::GetData, None))
+    else {
+        return false;
+    };
+
+    let tile_config_ui;
+    if let Ok(CallResult::Success(ui)) = state
+        .tokio
+        .block_on(tile_entity.call(TileEntityMsg::GetTileConfigUi, None))
+    {
+        tile_config_ui = ui;
+    } else {
+        tile_config_ui = None;
+    }
+
+    let gui_ids = state.resource_man.registry.gui_ids;
+    let mut toggled = false;
+
+    movable(pos, || {
+        window_box(
+            format!(
+                "{} {coord}",
+                state.resource_man.gui_str(gui_ids.tile_config)
+            ),
+            || {
+                scroll_vertical_bar_alignment(
+                    Vec2::ZERO,
+                    Vec2::new(f32::INFINITY, 360.0),
+                    None,
+                    || {
+                        group(|| {
+                            Pad::horizontal(PADDING_MEDIUM).show(|| {
+                                col(|| {
+                                    let pin = if pinned {
+                                        gui_ids.btn_unpin
+                                    } else {
+                                        gui_ids.btn_pin
+                                    };
+
+                                    if (pinned
+                                        || state.ui_state.pinned_configs.len() < MAX_PINNED_CONFIGS)
+                                        && button(&state.resource_man.gui_str(pin)).clicked
+                                    {
+                                        toggled = true;
+                                    }
+
+                                    progress(state, &data);
+
+                                    if let Some(ui) = tile_config_ui {
+                                        rhai_ui(
+                                            state,
+                                            tile_entity.clone(),
+                                            coord,
+                                            &data,
+                                            game_data,
+                                            ui,
+                                        );
+                                    }
+                                });
+                            });
+                        });
+                    },
+                );
+            },
+        );
+    });
+
+    toggled
+}
+
+/// Draws the tile configuration menus, the pinned ones and the one of the selected tile.
 pub fn tile_config_ui(state: &mut GameState, game_data: &mut DataMap) {
-    Layer::new().show(|| {
-        let Some(tile_entity) = state.loop_store.config_open_cache.blocking_lock().clone() else {
-            return;
+    for (coord, mut pos) in state.ui_state.pinned_configs.clone() {
+        let tile_entity = match state.tokio.block_on(
+            state
+                .game
+                .call(|reply| GameSystemMessage::GetTileEntity(coord, reply), None),
+        ) {
+            Ok(CallResult::Success(Some(tile_entity))) => tile_entity,
+            Ok(CallResult::Success(None)) => {
+                // the tile was removed
+                state.ui_state.pinned_configs.retain(|(v, _)| *v != coord);
+                continue;
+            }
+            _ => continue,
         };
 
-        let Ok(CallResult::Success(data)) = state
-            .tokio
-            .block_on(tile_entity.call(TileEntityMsg::GetData, None))
-        else {
-            return;
-        };
+        let mut unpin = false;
+        Layer::new().show(|| {
+            unpin = config_window(state, game_data, coord, tile_entity.clone(), &mut pos, true);
+        });
 
-        let tile_config_ui;
-        if let Ok(CallResult::Success(ui)) = state
-            .tokio
-            .block_on(tile_entity.call(TileEntityMsg::GetTileConfigUi, None))
+        if unpin {
+            state.ui_state.pinned_configs.retain(|(v, _)| *v != coord);
+        } else if let Some(pinned) = state
+            .ui_state
+            .pinned_configs
+            .iter_mut()
+            .find(|(v, _)| *v == coord)
         {
-            tile_config_ui = ui;
-        } else {
-            tile_config_ui = None;
+            pinned.1 = pos;
         }
+    }
+
+    let Some(coord) = state.ui_state.config_open_at else {
+        return;
+    };
+
+    // a pinned tile already has its window open
+    if state
+        .ui_state
+        .pinned_configs
+        .iter()
+        .any(|(v, _)| *v == coord)
+    {
+        return;
+    }
+
+    let Some(tile_entity) = state.loop_store.config_open_cache.blocking_lock().clone() else {
+        return;
+    };
 
+    Layer::new().show(|| {
         let mut pos = state.ui_state.tile_config_ui_position;
-        movable(&mut pos, || {
-            window_box(
-                state
-                    .resource_man
-                    .gui_str(state.resource_man.registry.gui_ids.tile_config)
-                    .to_string(),
-                || {
-                    scroll_vertical_bar_alignment(
-                        Vec2::ZERO,
-                        Vec2::new(f32::INFINITY, 360.0),
-                        None,
-                        || {
-                            group(|| {
-                                Pad::horizontal(PADDING_MEDIUM).show(|| {
-                                    col(|| {
-                                        if let Some(ui) = tile_config_ui {
-                                            rhai_ui(
-                                                state,
-                                                tile_entity.clone(),
-                                                &data,
-                                                game_data,
-                                                ui,
-                                            );
-                                        }
-                                    });
-                                });
-                            });
-                        },
-                    );
-                },
-            );
-        });
+
+        if config_window(
+            state,
+            game_data,
+            coord,
+            tile_entity.clone(),
+            &mut pos,
+            false,
+        ) {
+            state.ui_state.pinned_configs.push((coord, pos));
+            state.ui_state.config_open_at = None;
+        }
+
         state.ui_state.tile_config_ui_position = pos;
     });
 }
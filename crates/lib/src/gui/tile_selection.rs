@@ -9,15 +9,15 @@ use automancy_resources::{
     format::Formattable,
     types::IconMode,
 };
-use automancy_system::util::{is_research_unlocked, should_category_show};
+use automancy_system::util::{is_tile_locked, should_category_show, LockedTileVisibility};
 use automancy_ui::{
-    center_col, col, hover_tip, interactive, label, row, scroll_horizontal_bar_alignment,
+    center_col, col, hover_tip, interactive, label, row, scroll_horizontal_bar_alignment, symbol,
     ui_game_object, viewport_constrained, RoundRect, UiGameObjectType, LARGE_ICON_SIZE,
     MEDIUM_ICON_SIZE,
 };
 use tokio::sync::oneshot;
 use yakui::{
-    use_state,
+    reflow, use_state,
     widgets::{Absolute, Layer},
     Alignment, Dim2, Pivot, Vec2,
 };
@@ -64,13 +64,15 @@ fn has_category_item(state: &mut GameState, game_data: &mut DataMap, id: Id) ->
 }
 
 /// Draws the tile selection.
+///
+/// Returns the hovered tile, whether it can be placed, and whether it is still locked.
 fn draw_tile_selection(
     state: &mut GameState,
     game_data: &mut DataMap,
     selection_send: &mut Option<oneshot::Sender<TileId>>,
     current_category: Option<Id>,
     size: Float,
-) -> Option<(TileId, bool)> {
+) -> Option<(TileId, bool, bool)> {
     let world_matrix = IconMode::Tile.world_matrix();
 
     let has_item = if let Some(category) = current_category {
@@ -79,9 +81,11 @@ fn draw_tile_selection(
         true
     };
 
+    let visibility = LockedTileVisibility::of(&state.resource_man, game_data);
+
     let mut hovered = None;
 
-    for id in &state.resource_man.ordered_tiles {
+    for id in state.resource_man.ordered_tiles.load().iter() {
         if let Some(category) = state.resource_man.registry.tiles[id].category {
             if Some(category) != current_category {
                 continue;
@@ -96,17 +100,14 @@ fn draw_tile_selection(
             _ => false,
         };
 
-        if !is_default_tile {
-            if let Some(research) = state.resource_man.get_research_by_unlock(*id) {
-                if !is_research_unlocked(research.id, &state.resource_man, game_data) {
-                    continue;
-                }
-            } else {
-                continue;
-            }
+        let locked = is_tile_locked(*id, &state.resource_man, game_data);
+
+        if locked && visibility == LockedTileVisibility::Hidden {
+            continue;
         }
 
-        let active = is_default_tile || has_item;
+        let active = (is_default_tile || has_item)
+            && !(locked && visibility == LockedTileVisibility::Greyed);
 
         let hover_anim_active = use_state(|| false);
 
@@ -129,12 +130,18 @@ fn draw_tile_selection(
                 Some(rotate),
                 Some(world_matrix),
             );
+
+            if locked && visibility == LockedTileVisibility::Greyed {
+                reflow(Alignment::TOP_RIGHT, Pivot::TOP_RIGHT, Dim2::ZERO, || {
+                    symbol("\u{f023}", colors::DARK_GRAY);
+                });
+            }
         });
 
         hover_anim_active.set(response.hovering);
 
         if response.hovering {
-            hovered = Some((*id, active));
+            hovered = Some((*id, active, locked));
         }
 
         if active && response.clicked {
@@ -147,14 +154,74 @@ fn draw_tile_selection(
     hovered
 }
 
+/// Draws a row of category icons.
+fn draw_category_row(
+    state: &mut GameState,
+    game_data: &mut DataMap,
+    ids: &[Id],
+    hovered_category: &mut Option<Id>,
+) {
+    let world_matrix = IconMode::Tile.world_matrix();
+    let model_matrix = IconMode::Tile.model_matrix();
+
+    RoundRect::new(8.0, colors::BACKGROUND_1).show_children(|| {
+        scroll_horizontal_bar_alignment(Vec2::ZERO, Vec2::INFINITY, None, || {
+            row(|| {
+                for id in ids {
+                    if !should_category_show(*id, &state.resource_man, game_data) {
+                        continue;
+                    }
+
+                    let category = state.resource_man.registry.categories[id];
+
+                    let ty = match category.icon_mode {
+                        IconMode::Item => UiGameObjectType::Model(
+                            state
+                                .resource_man
+                                .model_or_missing_item(&ModelId(category.icon)),
+                        ),
+                        IconMode::Tile => {
+                            UiGameObjectType::Tile(TileId(category.icon), DataMap::default())
+                        }
+                    };
+
+                    let response = interactive(|| {
+                        ui_game_object(
+                            InstanceData::default(),
+                            ty,
+                            vec2(MEDIUM_ICON_SIZE, MEDIUM_ICON_SIZE),
+                            Some(model_matrix),
+                            Some(world_matrix),
+                        );
+                    });
+
+                    if response.clicked {
+                        state.ui_state.tile_selection_category = Some(*id);
+                    }
+
+                    if response.hovering {
+                        *hovered_category = Some(*id);
+                    }
+                }
+            });
+        });
+    });
+}
+
 /// Creates the tile selection GUI.
 pub fn tile_selections(
     state: &mut GameState,
     game_data: &mut DataMap,
     selection_send: oneshot::Sender<TileId>,
 ) {
-    let world_matrix = IconMode::Tile.world_matrix();
-    let model_matrix = IconMode::Tile.model_matrix();
+    let resource_man = state.resource_man.clone();
+
+    // the selected category and all of its parents are expanded
+    let expanded = state
+        .ui_state
+        .tile_selection_category
+        .map(|id| resource_man.get_category_path(id))
+        .unwrap_or_default();
 
     let mut hovered_category = None;
     let mut hovered_tile = None;
@@ -163,49 +230,20 @@ pub fn tile_selections(
         Absolute::new(Alignment::BOTTOM_CENTER, Pivot::BOTTOM_CENTER, Dim2::ZERO).show(|| {
             viewport_constrained(|| {
                 center_col(|| {
-                    RoundRect::new(8.0, colors::BACKGROUND_1).show_children(|| {
-                        scroll_horizontal_bar_alignment(Vec2::ZERO, Vec2::INFINITY, None, || {
-                            row(|| {
-                                for id in &state.resource_man.ordered_categories {
-                                    if !should_category_show(*id, &state.resource_man, game_data) {
-                                        continue;
-                                    }
-
-                                    let category = state.resource_man.registry.categories[id];
-
-                                    let ty = match category.icon_mode {
-                                        IconMode::Item => UiGameObjectType::Model(
-                                            state
-                                                .resource_man
-                                                .model_or_missing_item(&ModelId(category.icon)),
-                                        ),
-                                        IconMode::Tile => UiGameObjectType::Tile(
-                                            TileId(category.icon),
-                                            DataMap::default(),
-                                        ),
-                                    };
-
-                                    let response = interactive(|| {
-                                        ui_game_object(
-                                            InstanceData::default(),
-                                            ty,
-                                            vec2(MEDIUM_ICON_SIZE, MEDIUM_ICON_SIZE),
-                                            Some(model_matrix),
-                                            Some(world_matrix),
-                                        );
-                                    });
-
-                                    if response.clicked {
-                                        state.ui_state.tile_selection_category = Some(*id);
-                                    }
-
-                                    if response.hovering {
-                                        hovered_category = Some(*id);
-                                    }
-                                }
-                            });
-                        });
-                    });
+                    draw_category_row(
+                        state,
+                        game_data,
+                        &resource_man.ordered_categories,
+                        &mut hovered_category,
+                    );
+
+                    for id in &expanded {
+                        let children = resource_man.get_category_children(*id);
+
+                        if !children.is_empty() {
+                            draw_category_row(state, game_data, children, &mut hovered_category);
+                        }
+                    }
 
                     RoundRect::new(8.0, colors::BACKGROUND_1).show_children(|| {
                         scroll_horizontal_bar_alignment(Vec2::ZERO, Vec2::INFINITY, None, || {
@@ -232,12 +270,24 @@ pub fn tile_selections(
             });
         }
 
-        if let Some((id, active)) = hovered_tile {
+        if let Some((id, active, locked)) = hovered_tile {
             hover_tip(|| {
                 col(|| {
                     label(&state.resource_man.tile_name(id));
 
-                    if !active {
+                    if locked {
+                        if let Some(research) = state.resource_man.get_research_by_unlock(id) {
+                            label(&state.resource_man.gui_fmt(
+                                state.resource_man.registry.gui_ids.lbl_unlock_hint,
+                                [(
+                                    "research_name",
+                                    Formattable::display(
+                                        &state.resource_man.research_str(research.name),
+                                    ),
+                                )],
+                            ));
+                        }
+                    } else if !active {
                         if let Some(item) = state
                             .ui_state
                             .tile_selection_category
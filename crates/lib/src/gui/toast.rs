@@ -0,0 +1,67 @@
+use crate::gui::popup::run_action;
+use crate::GameState;
+use automancy_resources::error::{take_errs, ErrorSeverity};
+use automancy_system::ui_state::PopupAction;
+use automancy_ui::{toasts, Toast, ToastPriority, ToastQueue};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+/// How long toasts that only confirm something stay on screen.
+pub const SHORT_TOAST: Duration = Duration::from_secs(3);
+/// How long toasts with something worth reading stay on screen.
+pub const LONG_TOAST: Duration = Duration::from_secs(10);
+
+// toasts can be pushed from any thread, the same as errors
+static TOASTS: LazyLock<Mutex<ToastQueue<PopupAction>>> = LazyLock::new(Default::default);
+
+/// Queues a toast to be shown. Its action is run like the action of a confirmed dialog.
+pub fn push_toast(toast: Toast<PopupAction>) {
+    TOASTS.lock().unwrap().push(toast);
+}
+
+/// Moves the errors waiting to be displayed into toasts. Fatal errors stay until they are dismissed.
+fn toast_errors(state: &GameState) {
+    let gui_ids = &state.resource_man.registry.gui_ids;
+
+    for err in take_errs() {
+        let toast = match err.severity {
+            ErrorSeverity::Info => {
+                Toast::new(err.message, ToastPriority::Low).with_timeout(SHORT_TOAST)
+            }
+            ErrorSeverity::Warn => {
+                Toast::new(err.message, ToastPriority::Normal).with_timeout(LONG_TOAST)
+            }
+            ErrorSeverity::Fatal => Toast::new(err.message, ToastPriority::High),
+        };
+
+        let toast = if err.severity == ErrorSeverity::Fatal {
+            toast
+        } else {
+            toast.with_action(
+                state
+                    .resource_man
+                    .gui_str(gui_ids.btn_dont_show_again)
+                    .to_string(),
+                PopupAction::MuteError(err.id),
+            )
+        };
+
+        push_toast(toast);
+    }
+}
+
+/// Draws the queued toasts, and runs the action of the one clicked.
+pub fn toast_ui(state: &mut GameState) {
+    toast_errors(state);
+
+    let action = toasts(
+        &mut TOASTS.lock().unwrap(),
+        &state
+            .resource_man
+            .gui_str(state.resource_man.registry.gui_ids.btn_dismiss),
+    );
+
+    if let Some(action) = action {
+        run_action(state, action);
+    }
+}
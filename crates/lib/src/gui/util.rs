@@ -16,8 +16,8 @@ use automancy_system::game::TAKE_ITEM_ANIMATION_SPEED;
 use automancy_system::tile_entity::collect_render_commands;
 use automancy_system::ui_state::TextField;
 use automancy_ui::{
-    col, group, hover_tip, radio, scroll_vertical, textbox, ui_game_object, UiGameObjectType,
-    HOVER_TIP,
+    col, group, hover_tip, radio, scroll_vertical, textbox, ui_game_object, virtual_list,
+    UiGameObjectType, HOVER_TIP,
 };
 use fuzzy_matcher::FuzzyMatcher;
 use hashbrown::{HashMap, HashSet};
@@ -50,6 +50,7 @@ pub fn render_overlay_cached(
                 TileCoord::ZERO,
                 &mut data,
                 &mut HashSet::default(),
+                None,
                 true,
                 false,
             ) {
@@ -142,11 +143,11 @@ pub fn searchable_id(
                     ids.to_vec()
                 };
 
-                for id in ids.iter() {
-                    radio(new_id, Some(*id), || {
-                        draw(state, *id);
+                virtual_list(ids.len(), |index| {
+                    radio(new_id, Some(ids[index]), || {
+                        draw(state, ids[index]);
                     });
-                }
+                });
             });
         });
     });
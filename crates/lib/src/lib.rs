@@ -25,9 +25,12 @@ pub use yakui;
 pub use yakui_wgpu;
 pub use yakui_winit;
 
+#[cfg(feature = "companion")]
+pub mod companion;
 pub mod event;
 pub mod gpu;
 pub mod gui;
+pub mod render_backend;
 pub mod renderer;
 pub mod ui_game_object;
 pub mod util;
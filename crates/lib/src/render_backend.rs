@@ -0,0 +1,503 @@
+use crate::renderer::{
+    AnimationMatrixDataMap, OverlayInstance, YakuiRenderResources,
+    WE_ONLY_USE_1_WORLD_MATRIX_IN_GAME_LOL,
+};
+use automancy_defs::coord::TileCoord;
+use automancy_defs::id::{ModelId, RenderTagId};
+use automancy_defs::math::{lerp_matrix, Float, Matrix4, Vec2, Vec3, Vec4};
+use automancy_defs::rendering::{AnimationMatrixData, BackgroundUBO, GpuInstance, MatrixData};
+use automancy_resources::rhai_render::RenderCommand;
+use automancy_resources::ResourceManager;
+use automancy_system::game::{tick_interpolation, RenderDelta};
+use automancy_system::GameGui;
+use hashbrown::{HashMap, HashSet};
+use ordermap::OrderMap;
+use range_set_blaze::RangeSetBlaze;
+use std::collections::BTreeMap;
+use std::mem;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+use wgpu::SurfaceError;
+
+/// A draw of the instances of a mesh that sit next to each other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrawCall {
+    pub model: ModelId,
+    pub mesh_index: usize,
+    pub instances: Range<u32>,
+    pub opaque: bool,
+}
+
+/// Everything a frame draws, described without anything that lives on the GPU.
+pub struct Frame<'a> {
+    pub background: BackgroundUBO,
+    pub camera_pos: Vec3,
+    pub camera_matrix: Matrix4,
+    /// the draws of the objects in the scene, in the order they are made
+    pub draws: Vec<DrawCall>,
+    pub overlay_instances: Vec<OverlayInstance>,
+    /// the instances and matrices of the scene changed since the last frame
+    pub instances_changes: Vec<usize>,
+    pub matrix_data_changes: Vec<usize>,
+    pub xray: bool,
+    /// whether the post processing effect is drawn, as it can be skipped to keep the frame rate up
+    pub post_processing: bool,
+    /// the scale of the resolution the map is drawn at, relative to the window's, as it can be lowered to keep the
+    /// frame rate up
+    pub resolution_scale: f32,
+
+    /// the GUI drawn over the game, if there is one
+    pub gui: Option<&'a mut GameGui<YakuiRenderResources>>,
+    pub screenshotting: bool,
+    pub screenshot_path: Option<PathBuf>,
+}
+
+/// Draws the frames the renderer prepares. The game draws on the GPU through [crate::renderer::GameRenderer], and tests
+/// can put in their own, e.g. [RecordingBackend], to check what gets drawn.
+pub trait RenderBackend {
+    fn draw_frame(
+        &mut self,
+        resource_man: Arc<ResourceManager>,
+        frame: Frame<'_>,
+    ) -> Result<(), SurfaceError>;
+}
+
+/// A frame recorded by [RecordingBackend].
+#[derive(Debug, Clone)]
+pub struct RecordedFrame {
+    pub camera_pos: Vec3,
+    pub camera_matrix: Matrix4,
+    pub draws: Vec<DrawCall>,
+    /// the model, the mesh, and the model matrix of each overlay
+    pub overlays: Vec<(ModelId, usize, Matrix4)>,
+    pub xray: bool,
+}
+
+/// A backend that records the draw calls of each frame instead of making them.
+#[derive(Debug, Default)]
+pub struct RecordingBackend {
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl RecordingBackend {
+    pub fn last_frame(&self) -> Option<&RecordedFrame> {
+        self.frames.last()
+    }
+
+    /// Gets how many instances of the model were drawn in the last frame.
+    pub fn drawn_instances(&self, model: ModelId) -> u32 {
+        self.last_frame()
+            .into_iter()
+            .flat_map(|v| &v.draws)
+            .filter(|v| v.model == model)
+            .map(|v| v.instances.len() as u32)
+            .sum()
+    }
+}
+
+impl RenderBackend for RecordingBackend {
+    fn draw_frame(
+        &mut self,
+        _resource_man: Arc<ResourceManager>,
+        frame: Frame<'_>,
+    ) -> Result<(), SurfaceError> {
+        self.frames.push(RecordedFrame {
+            camera_pos: frame.camera_pos,
+            camera_matrix: frame.camera_matrix,
+            draws: frame.draws,
+            overlays: frame
+                .overlay_instances
+                .iter()
+                .map(|(_, model, matrix, mesh_index)| (*model, *mesh_index, matrix.model_matrix()))
+                .collect(),
+            xray: frame.xray,
+        });
+
+        Ok(())
+    }
+}
+
+/// The objects the game told the renderer to draw, and where they are. This is kept apart from the GPU, so it can be
+/// built from the game and drawn with any [RenderBackend].
+#[derive(Default)]
+pub struct RenderScene {
+    object_ids: OrderMap<(TileCoord, RenderTagId, ModelId, usize), ()>,
+    /// the model matrix each object is currently displayed with
+    model_matrices: HashMap<(TileCoord, RenderTagId, ModelId, usize), Matrix4>,
+    /// the objects moving from their previous model matrix to the latest one, with their mesh matrix and when the latest one arrived
+    transform_interpolations:
+        HashMap<(TileCoord, RenderTagId, ModelId, usize), (Matrix4, Matrix4, Matrix4, Instant)>,
+    coord_to_keys: HashMap<TileCoord, HashSet<(RenderTagId, ModelId, usize)>>,
+
+    instance_ranges: BTreeMap<(ModelId, usize), RangeSetBlaze<usize>>,
+    pub(crate) instances: Vec<GpuInstance>,
+    pub(crate) matrix_data_map: Vec<MatrixData>,
+    pub(crate) animation_matrix_data_map: AnimationMatrixDataMap,
+
+    /// the instances and matrices changed since the last frame
+    instances_changes: HashSet<usize>,
+    matrix_data_changes: HashSet<usize>,
+}
+
+impl RenderScene {
+    /// Applies the render commands received from the game.
+    pub fn apply(&mut self, resource_man: &ResourceManager, render_commands: RenderDelta) {
+        for batch in render_commands {
+            let mut batch = batch.into_iter().collect::<Vec<_>>();
+            batch.sort_by_key(|v| v.0.ulength());
+
+            let mut untrack_commands = BTreeMap::new();
+            let mut track_commands = BTreeMap::new();
+            let mut transform_commands = BTreeMap::new();
+
+            for (coord, commands) in batch {
+                for command in commands {
+                    match command {
+                        RenderCommand::Untrack { tag, model } => {
+                            untrack_commands
+                                .entry(model)
+                                .or_insert_with(Vec::new)
+                                .push((coord, tag));
+                        }
+                        RenderCommand::Track { tag, model } => {
+                            track_commands
+                                .entry(model)
+                                .or_insert_with(Vec::new)
+                                .push((coord, tag));
+                        }
+                        RenderCommand::Transform {
+                            tag,
+                            model,
+                            model_matrix,
+                        } => {
+                            transform_commands
+                                .entry(model)
+                                .or_insert_with(Vec::new)
+                                .push((coord, tag, model_matrix));
+                        }
+                    }
+                }
+            }
+
+            for (model, commands) in untrack_commands {
+                let (model, (meshes, ..)) = resource_man.mesh_or_missing_tile_mesh(&model);
+
+                for mesh in meshes.iter().flatten() {
+                    for (coord, tag) in commands.iter().cloned() {
+                        let swapping_index = self.object_ids.last_entry().map(|v| v.index());
+                        let swapping_key = self.object_ids.last_entry().map(|v| *v.key());
+
+                        let (removed_index, ..) = self
+                            .object_ids
+                            .swap_remove_full(&(coord, tag, model, mesh.index))
+                            .expect("render object id wasn't tracked");
+
+                        self.model_matrices.remove(&(coord, tag, model, mesh.index));
+                        self.transform_interpolations
+                            .remove(&(coord, tag, model, mesh.index));
+
+                        let swapping_index = swapping_index.unwrap_or(removed_index);
+
+                        if let Some(keys) = self.coord_to_keys.get_mut(&coord) {
+                            assert!(
+                                keys.remove(&(tag, model, mesh.index)),
+                                "key set in 'coord to keys map' didn't have this key"
+                            );
+                        }
+
+                        {
+                            self.matrix_data_map[removed_index] = Default::default();
+                            self.matrix_data_map.swap(removed_index, swapping_index);
+
+                            self.matrix_data_changes.insert(swapping_index);
+                            self.matrix_data_changes.insert(removed_index);
+                        }
+                        {
+                            self.instances[removed_index] = Default::default();
+                            self.instances.swap(removed_index, swapping_index);
+
+                            self.instances_changes.insert(swapping_index);
+                            self.instances_changes.insert(removed_index);
+                        }
+
+                        {
+                            self.instance_ranges
+                                .entry((model, mesh.index))
+                                .or_default()
+                                .remove(removed_index);
+
+                            if swapping_index != removed_index {
+                                if let Some((.., model, mesh_index)) = swapping_key {
+                                    self.instance_ranges
+                                        .entry((model, mesh_index))
+                                        .or_default()
+                                        .insert(removed_index);
+                                    self.instance_ranges
+                                        .entry((model, mesh_index))
+                                        .or_default()
+                                        .remove(swapping_index);
+                                }
+                            }
+                        }
+
+                        if swapping_index != removed_index {
+                            self.instances[removed_index].matrix_index = removed_index as u32;
+                        }
+                    }
+                }
+            }
+
+            for (model, commands) in track_commands {
+                let (model, (meshes, ..)) = resource_man.mesh_or_missing_tile_mesh(&model);
+
+                for mesh in meshes.iter().flatten() {
+                    for (coord, tag) in commands.iter().cloned() {
+                        if !self
+                            .animation_matrix_data_map
+                            .contains_key(&(model, mesh.index))
+                        {
+                            self.animation_matrix_data_map
+                                .insert((model, mesh.index), AnimationMatrixData::default());
+                        }
+                        let animation_matrix_index = self
+                            .animation_matrix_data_map
+                            .get_index_of(&(model, mesh.index))
+                            .unwrap();
+
+                        let (index, prev_id_slot) = self
+                            .object_ids
+                            .insert_full((coord, tag, model, mesh.index), ());
+                        assert!(
+                            prev_id_slot.is_none(),
+                            "render object id was already tracked"
+                        );
+
+                        assert!(
+                            self.coord_to_keys
+                                .entry(coord)
+                                .or_default()
+                                .insert((tag, model, mesh.index)),
+                            "coord to keys map already has the same key"
+                        );
+
+                        self.matrix_data_map
+                            .resize_with(index + 1, Default::default);
+                        self.matrix_data_map[index] = MatrixData::default();
+                        self.matrix_data_changes.insert(index);
+
+                        self.instances.resize_with(index + 1, Default::default);
+                        self.instances[index] = GpuInstance {
+                            matrix_index: index as u32,
+                            animation_matrix_index: animation_matrix_index as u32,
+                            world_matrix_index: WE_ONLY_USE_1_WORLD_MATRIX_IN_GAME_LOL,
+                            color_offset: [0.0; 4],
+                            alpha: 1.0,
+                        };
+                        self.instances_changes.insert(index);
+
+                        self.instance_ranges
+                            .entry((model, mesh.index))
+                            .or_default()
+                            .insert(index);
+                    }
+                }
+            }
+
+            for (model, commands) in transform_commands {
+                let (model, (meshes, ..)) = resource_man.mesh_or_missing_tile_mesh(&model);
+
+                for mesh in meshes.iter().flatten() {
+                    for (coord, tag, model_matrix) in commands.iter().cloned() {
+                        let key = (coord, tag, model, mesh.index);
+
+                        let Some(index) = self.object_ids.get_index_of(&key) else {
+                            continue;
+                        };
+
+                        if let Some(&from) = self.model_matrices.get(&key) {
+                            // moves smoothly from where it is displayed now, instead of jumping once per tick
+                            self.transform_interpolations
+                                .insert(key, (from, model_matrix, mesh.matrix, Instant::now()));
+                        } else if let Some(matrix) = self.matrix_data_map.get_mut(index) {
+                            *matrix = MatrixData::new(model_matrix, mesh.matrix);
+                            self.model_matrices.insert(key, model_matrix);
+
+                            self.matrix_data_changes.insert(index);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Moves the objects further along from their previous model matrix to the latest one.
+    pub fn interpolate(&mut self) {
+        self.transform_interpolations
+            .retain(|key, &mut (from, to, mesh_matrix, since)| {
+                let Some(index) = self.object_ids.get_index_of(key) else {
+                    return false;
+                };
+
+                let t = tick_interpolation(since);
+                let model_matrix = lerp_matrix(from, to, t);
+
+                self.matrix_data_map[index] = MatrixData::new(model_matrix, mesh_matrix);
+                self.model_matrices.insert(*key, model_matrix);
+                self.matrix_data_changes.insert(index);
+
+                t < 1.0
+            });
+    }
+
+    /// Tints the objects on the tiles, and takes the tint off the ones that were last tinted but no longer are.
+    pub fn tint(
+        &mut self,
+        last_tile_tints: HashMap<TileCoord, Vec4>,
+        tile_tints: &HashMap<TileCoord, Vec4>,
+    ) {
+        for (coord, _) in last_tile_tints {
+            if tile_tints.contains_key(&coord) {
+                continue;
+            };
+
+            let Some(keys) = self.coord_to_keys.get(&coord) else {
+                continue;
+            };
+
+            for &key in keys {
+                let index = self
+                    .object_ids
+                    .get_index_of(&(coord, key.0, key.1, key.2))
+                    .unwrap();
+
+                self.instances[index].color_offset = [0.0; 4];
+                self.instances_changes.insert(index);
+            }
+        }
+
+        for (coord, tint) in tile_tints {
+            let Some(keys) = self.coord_to_keys.get(coord) else {
+                continue;
+            };
+
+            for &key in keys {
+                let index = self
+                    .object_ids
+                    .get_index_of(&(*coord, key.0, key.1, key.2))
+                    .unwrap();
+
+                self.instances[index].color_offset = tint.to_array();
+                self.instances_changes.insert(index);
+            }
+        }
+    }
+
+    /// Marks everything as changed, so that it's all uploaded again.
+    pub fn reupload(&mut self) {
+        self.instances_changes.extend(0..self.instances.len());
+        self.matrix_data_changes
+            .extend(0..self.matrix_data_map.len());
+    }
+
+    /// Gets the draws of every tracked object, the opaque meshes first.
+    pub fn draw_calls(&self, resource_man: &ResourceManager) -> Vec<DrawCall> {
+        let mut draws = vec![];
+
+        for opaque in [true, false] {
+            for (&(model, mesh_index), ranges) in &self.instance_ranges {
+                let Some((meshes, ..)) = resource_man.all_meshes_anims.get(&model) else {
+                    continue;
+                };
+
+                if meshes[mesh_index]
+                    .as_ref()
+                    .is_some_and(|v| v.opaque == opaque)
+                {
+                    for range in ranges.ranges() {
+                        draws.push(DrawCall {
+                            model,
+                            mesh_index,
+                            instances: (*range.start() as u32)..(*range.end() as u32 + 1),
+                            opaque,
+                        });
+                    }
+                }
+            }
+        }
+
+        draws
+    }
+
+    /// Prepares the next frame, taking the changes made since the last one.
+    #[allow(clippy::too_many_arguments)]
+    pub fn frame<'a>(
+        &mut self,
+        resource_man: &ResourceManager,
+        background: BackgroundUBO,
+        camera_pos: Vec3,
+        camera_matrix: Matrix4,
+        overlay_instances: Vec<OverlayInstance>,
+        xray: bool,
+        gui: Option<&'a mut GameGui<YakuiRenderResources>>,
+    ) -> Frame<'a> {
+        let mut instances_changes = mem::take(&mut self.instances_changes)
+            .into_iter()
+            .collect::<Vec<_>>();
+        instances_changes.sort();
+        let mut matrix_data_changes = mem::take(&mut self.matrix_data_changes)
+            .into_iter()
+            .collect::<Vec<_>>();
+        matrix_data_changes.sort();
+
+        Frame {
+            background,
+            camera_pos,
+            camera_matrix,
+            draws: self.draw_calls(resource_man),
+            overlay_instances,
+            instances_changes,
+            matrix_data_changes,
+            xray,
+            post_processing: true,
+            resolution_scale: 1.0,
+
+            gui,
+            screenshotting: false,
+            screenshot_path: None,
+        }
+    }
+
+    /// Gets every object tracked, by the tile it's on, its render tag, its model and the index of its mesh.
+    pub fn objects(&self) -> impl Iterator<Item = &(TileCoord, RenderTagId, ModelId, usize)> {
+        self.object_ids.keys()
+    }
+
+    /// Gets where an object is displayed right now, including the movement not yet applied this frame.
+    pub fn object_position(&self, key: &(TileCoord, RenderTagId, ModelId, usize)) -> Option<Vec3> {
+        let matrix = match self.transform_interpolations.get(key) {
+            Some(&(from, to, _, since)) => lerp_matrix(from, to, tick_interpolation(since)),
+            None => *self.model_matrices.get(key)?,
+        };
+
+        Some(matrix.w_axis.truncate())
+    }
+
+    /// Finds the moving object closest to a world position, if there is one within the radius.
+    pub fn moving_object_near(
+        &self,
+        pos: Vec2,
+        radius: Float,
+    ) -> Option<(TileCoord, RenderTagId, ModelId, usize)> {
+        self.transform_interpolations
+            .keys()
+            .flat_map(|key| {
+                self.object_position(key)
+                    .map(|v| (*key, v.truncate().distance(pos)))
+            })
+            .filter(|(_, distance)| *distance <= radius)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(key, _)| key)
+    }
+}
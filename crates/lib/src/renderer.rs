@@ -1,36 +1,47 @@
-use crate::gpu;
 use crate::gpu::{
-    GlobalResources, Gpu, GuiResources, RenderResources, SharedResources, MODEL_DEPTH_CLEAR,
-    NORMAL_CLEAR, SCREENSHOT_FORMAT,
+    GlobalResources, Gpu, GuiResources, RenderResources, SharedResources, UploadRing,
+    MODEL_DEPTH_CLEAR, NORMAL_CLEAR, SCENERY_MATRIX_DATA_SIZE, SCREENSHOT_FORMAT,
 };
+use crate::render_backend::{Frame, RenderBackend, RenderScene};
 use crate::GameState;
 use arboard::{Clipboard, ImageData};
-use automancy_defs::math::Matrix4;
-use automancy_defs::rendering::{GameUBO, InstanceData};
+use automancy_defs::colors;
+use automancy_defs::math::{Float, Matrix4};
+use automancy_defs::rendering::{BackgroundUBO, GameUBO, InstanceData};
 use automancy_defs::{
-    coord::TileCoord,
+    coord::{TileBounds, TileCoord},
     math::{Vec2, Vec4},
     rendering::AnimationMatrixData,
 };
-use automancy_defs::{id::Id, rendering::GameMatrix};
 use automancy_defs::{id::ModelId, math::Vec3};
-use automancy_defs::{id::RenderTagId, rendering::PostProcessingUBO};
+use automancy_defs::{
+    id::RenderTagId,
+    rendering::{PostProcessingUBO, FLAG_SCREEN_EFFECT},
+};
+use automancy_defs::{
+    id::{Id, TileId},
+    rendering::GameMatrix,
+};
 use automancy_defs::{
     rendering::{GpuInstance, MatrixData, WorldMatrixData},
     slice_group_by::GroupBy,
 };
-use automancy_resources::rhai_render::RenderCommand;
 use automancy_resources::ResourceManager;
-use automancy_system::game::GameSystemMessage;
+use automancy_system::agent::AgentRender;
+use automancy_system::game::{tick_interpolation, GameSystemMessage, RenderDelta, TickUnit};
+use automancy_system::input::ActionType;
+use automancy_system::util::{map_background, map_time_of_day};
 use automancy_system::GameGui;
 use automancy_ui::{GameElementPaint, UiGameObjectType};
 use hashbrown::{HashMap, HashSet};
 use image::{EncodableLayout, RgbaImage};
 use ordermap::OrderMap;
-use range_set_blaze::RangeSetBlaze;
+use ractor::rpc::CallResult;
 use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::mem;
+use std::ops::Range;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
 use std::{collections::VecDeque, ops::Mul};
@@ -38,7 +49,7 @@ use tokio::sync::oneshot;
 use wgpu::{
     util::DrawIndexedIndirectArgs, BufferAddress, BufferDescriptor, BufferUsages, Color,
     CommandEncoderDescriptor, ImageCopyBuffer, ImageDataLayout, IndexFormat, LoadOp, Maintain,
-    MapMode, Operations, RenderPassColorAttachment, RenderPassDepthStencilAttachment,
+    MapMode, Operations, RenderPass, RenderPassColorAttachment, RenderPassDepthStencilAttachment,
     RenderPassDescriptor, SurfaceError, TextureDescriptor, TextureDimension, TextureFormat,
     TextureUsages, TextureViewDescriptor, COPY_BUFFER_ALIGNMENT, COPY_BYTES_PER_ROW_ALIGNMENT,
 };
@@ -75,7 +86,10 @@ pub struct YakuiRenderResources {
     pub animation_cache: AnimationCache,
 }
 
-const WE_ONLY_USE_1_WORLD_MATRIX_IN_GAME_LOL: u32 = 0;
+pub(crate) const WE_ONLY_USE_1_WORLD_MATRIX_IN_GAME_LOL: u32 = 0;
+/// How far overlays are pulled toward the camera when they are occluded by the models, so that they aren't hidden by
+/// the tile they are on, only by models taller than this in front of them.
+const OVERLAY_OCCLUSION_TOLERANCE: Float = 1.0;
 
 pub struct GameRenderer {
     pub gpu: Gpu,
@@ -90,13 +104,24 @@ pub struct GameRenderer {
 
     pub take_item_animations: HashMap<Id, VecDeque<(Instant, Rect)>>,
 
-    object_ids: OrderMap<(TileCoord, RenderTagId, ModelId, usize), ()>,
-    coord_to_keys: HashMap<TileCoord, HashSet<(RenderTagId, ModelId, usize)>>,
-
-    instance_ranges: BTreeMap<(ModelId, usize), RangeSetBlaze<usize>>,
-    instances: Vec<GpuInstance>,
-    matrix_data_map: Vec<MatrixData>,
-    animation_matrix_data_map: AnimationMatrixDataMap,
+    /// the last frame of render commands received from the game, acknowledged in the next request
+    last_render_frame: Option<u64>,
+    /// the render commands taken at the start of the frame, waiting to be drawn
+    tick_snapshot: Option<RenderDelta>,
+    /// the objects being drawn, apart from the GPU
+    pub scene: RenderScene,
+
+    /// the version of the scenery the scenery batch was last built from
+    scenery_version: Option<u64>,
+    /// the rebuilt scenery batch, waiting to be uploaded
+    scenery_upload: Option<(Vec<GpuInstance>, Vec<MatrixData>)>,
+    /// the instances of each mesh in the scenery batch
+    scenery_ranges: Vec<((ModelId, usize), Range<u32>)>,
+
+    /// the agents in view, moving from where they were to where they are
+    agents: Vec<AgentRender>,
+    /// the tick the agents were last moved on, and when it arrived
+    agents_tick: Option<(TickUnit, Instant)>,
 
     gui_opaque_draws: Option<Vec<(DrawIndexedIndirectArgs, usize)>>,
     gui_non_opaque_draws: Option<Vec<(DrawIndexedIndirectArgs, usize)>>,
@@ -106,7 +131,14 @@ pub struct GameRenderer {
     gui_rects: Vec<Option<crunch::Rect>>,
 
     animation_cache: AnimationCache,
+    /// the time on the animation clock of the last frame, to find the animation events passed since
+    last_animation_time: Option<Float>,
     screenshot_clipboard: Clipboard,
+
+    /// whether every instance and matrix has to be uploaded again, as the buffers were recreated
+    reupload: bool,
+    /// the staging buffers the per-frame uploads go through
+    upload_ring: UploadRing,
 }
 
 impl GameRenderer {
@@ -128,13 +160,16 @@ impl GameRenderer {
 
             take_item_animations: Default::default(),
 
-            object_ids: Default::default(),
-            coord_to_keys: Default::default(),
+            last_render_frame: None,
+            tick_snapshot: None,
+            scene: Default::default(),
+
+            scenery_version: None,
+            scenery_upload: None,
+            scenery_ranges: Default::default(),
 
-            instance_ranges: Default::default(),
-            instances: Default::default(),
-            matrix_data_map: Default::default(),
-            animation_matrix_data_map: Default::default(),
+            agents: Default::default(),
+            agents_tick: None,
 
             gui_opaque_draws: Some(Default::default()),
             gui_non_opaque_draws: Some(Default::default()),
@@ -144,9 +179,57 @@ impl GameRenderer {
             gui_rects: Default::default(),
 
             animation_cache: AnimationCache::new(),
+            last_animation_time: None,
             screenshot_clipboard: Clipboard::new().unwrap(),
+
+            reupload: false,
+            upload_ring: Default::default(),
         }
     }
+
+    /// Replaces the GPU and everything created on it, after the old device was lost.
+    /// What was received from the game is kept, and uploaded again on the next frame.
+    pub fn replace_gpu(
+        &mut self,
+        gpu: Gpu,
+        shared_resources: SharedResources,
+        render_resources: RenderResources,
+        global_resources: Arc<GlobalResources>,
+    ) {
+        self.gpu = gpu;
+        self.replace_resources(shared_resources, render_resources, global_resources);
+
+        // the staging buffers were made on the old device
+        self.upload_ring = Default::default();
+    }
+
+    /// Replaces the resources made from the models and shaders, e.g. after the resource packs were reloaded.
+    pub fn replace_resources(
+        &mut self,
+        shared_resources: SharedResources,
+        render_resources: RenderResources,
+        global_resources: Arc<GlobalResources>,
+    ) {
+        self.shared_resources = shared_resources;
+        self.render_resources = render_resources;
+        self.global_resources = global_resources;
+
+        self.scenery_version = None;
+        self.gui_packed_size = None;
+        self.reupload = true;
+    }
+}
+
+/// Gets the translation that moves an object along the line toward the camera by [OVERLAY_OCCLUSION_TOLERANCE], which
+/// leaves where it is on the screen as is, but brings it in front of what is just behind it.
+fn pull_toward_camera(matrix: Matrix4, camera_pos: Vec3) -> Matrix4 {
+    let center = matrix.transform_point3(Vec3::ZERO);
+    let to_camera = camera_pos - center;
+
+    // never past the camera
+    let distance = OVERLAY_OCCLUSION_TOLERANCE.min(to_camera.length() / 2.0);
+
+    Matrix4::from_translation(to_camera.normalize_or_zero() * distance)
 }
 
 pub fn try_add_animation(
@@ -188,224 +271,248 @@ pub fn try_add_animation(
     }
 }
 
-pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), SurfaceError> {
-    let Some(renderer) = state.renderer.as_mut() else {
-        return Ok(());
-    };
+/// Gets the animation event markers passed between two times on the animation clock, for each tile in view that
+/// shows a model with them.
+fn crossed_animation_events(
+    resource_man: &ResourceManager,
+    scene: &RenderScene,
+    culling_range: TileBounds,
+    from: Float,
+    to: Float,
+) -> Vec<(TileCoord, String)> {
+    let crossed = resource_man
+        .animation_events
+        .iter()
+        .map(|(model, events)| (*model, events.crossed(from, to).collect::<Vec<_>>()))
+        .filter(|(_, names)| !names.is_empty())
+        .collect::<HashMap<_, _>>();
+
+    if crossed.is_empty() {
+        return vec![];
+    }
 
-    let size = renderer.gpu.window.inner_size();
+    let mut seen = HashSet::new();
+
+    scene
+        .objects()
+        .filter(|(coord, _, model, _)| {
+            culling_range.contains(*coord) && seen.insert((*coord, *model))
+        })
+        .flat_map(|(coord, _, model, _)| {
+            crossed
+                .get(model)
+                .into_iter()
+                .flatten()
+                .map(|name| (*coord, name.to_string()))
+        })
+        .collect()
+}
 
-    if size.width == 0 || size.height == 0 {
-        return Ok(());
+/// Rebuilds the static scenery batch, with the instances of each mesh next to each other so that they are drawn together.
+fn rebuild_scenery(
+    resource_man: &ResourceManager,
+    renderer: &mut GameRenderer,
+    scenery: Vec<(TileCoord, TileId)>,
+) {
+    let mut grouped = BTreeMap::<(ModelId, usize), Vec<MatrixData>>::new();
+
+    for (coord, id) in scenery {
+        let Some(model) = resource_man.scenery_model(id) else {
+            continue;
+        };
+        let (model, (meshes, ..)) = resource_man.mesh_or_missing_tile_mesh(&model);
+
+        for mesh in meshes.iter().flatten() {
+            grouped
+                .entry((model, mesh.index))
+                .or_default()
+                .push(MatrixData::new(coord.as_translation(), mesh.matrix));
+        }
     }
 
-    renderer.animation_cache.clear();
+    let mut instances = vec![];
+    let mut matrix_data = vec![];
+    renderer.scenery_ranges.clear();
 
-    let last_tile_tints = mem::take(&mut renderer.last_tile_tints);
-    let tile_tints = mem::take(&mut renderer.tile_tints);
+    for (key, matrices) in grouped {
+        let start = matrix_data.len();
+        let count = matrices.len().min(SCENERY_MATRIX_DATA_SIZE - start);
 
-    let camera_pos = state.camera.get_pos();
-    let culling_range = state.camera.culling_range;
+        if count < matrices.len() {
+            log::warn!(
+                "Too much scenery to draw! Only {SCENERY_MATRIX_DATA_SIZE} meshes are drawn."
+            );
+        }
 
-    let render_commands = {
-        let game = state.game.clone();
+        if !renderer.scene.animation_matrix_data_map.contains_key(&key) {
+            renderer
+                .scene
+                .animation_matrix_data_map
+                .insert(key, AnimationMatrixData::default());
+        }
+        let animation_matrix_index = renderer
+            .scene
+            .animation_matrix_data_map
+            .get_index_of(&key)
+            .unwrap();
+
+        for (index, matrix) in (start..).zip(matrices.into_iter().take(count)) {
+            instances.push(GpuInstance {
+                matrix_index: index as u32,
+                animation_matrix_index: animation_matrix_index as u32,
+                world_matrix_index: WE_ONLY_USE_1_WORLD_MATRIX_IN_GAME_LOL,
+                color_offset: [0.0; 4],
+                alpha: 1.0,
+            });
+            matrix_data.push(matrix);
+        }
 
-        state
-            .tokio
-            .block_on(game.call(
-                |reply| GameSystemMessage::GetAllRenderCommands {
-                    reply,
-                    culling_range,
-                },
-                None,
-            ))
-            .unwrap()
-            .unwrap()
+        if count > 0 {
+            renderer
+                .scenery_ranges
+                .push((key, start as u32..(start + count) as u32));
+        }
+    }
+
+    renderer.scenery_upload = Some((instances, matrix_data));
+}
+
+impl GameRenderer {
+    /// Gets where an object is displayed right now, including the movement not yet applied this frame.
+    pub fn object_position(&self, key: &(TileCoord, RenderTagId, ModelId, usize)) -> Option<Vec3> {
+        self.scene.object_position(key)
+    }
+
+    /// Finds the moving object closest to a world position, if there is one within the radius.
+    pub fn moving_object_near(
+        &self,
+        pos: Vec2,
+        radius: Float,
+    ) -> Option<(TileCoord, RenderTagId, ModelId, usize)> {
+        self.scene.moving_object_near(pos, radius)
+    }
+}
+
+/// Takes the changes the game made since the last frame, to be drawn by the next call to render.
+/// This is done at the start of the frame, so that the input that arrived while waiting for the game is still applied to this frame.
+pub fn take_tick_snapshot(state: &mut GameState) {
+    let Some(renderer) = state.renderer.as_mut() else {
+        return;
     };
 
-    let mut instances_changes = HashSet::new();
-    let mut matrix_data_changes = HashSet::new();
-
-    for batch in render_commands {
-        let mut batch = batch.into_iter().collect::<Vec<_>>();
-        batch.sort_by_key(|v| v.0.ulength());
-
-        let mut untrack_commands = BTreeMap::new();
-        let mut track_commands = BTreeMap::new();
-        let mut transform_commands = BTreeMap::new();
-
-        for (coord, commands) in batch {
-            for command in commands {
-                match command {
-                    RenderCommand::Untrack { tag, model } => {
-                        untrack_commands
-                            .entry(model)
-                            .or_insert_with(Vec::new)
-                            .push((coord, tag));
-                    }
-                    RenderCommand::Track { tag, model } => {
-                        track_commands
-                            .entry(model)
-                            .or_insert_with(Vec::new)
-                            .push((coord, tag));
-                    }
-                    RenderCommand::Transform {
-                        tag,
-                        model,
-                        model_matrix,
-                    } => {
-                        transform_commands
-                            .entry(model)
-                            .or_insert_with(Vec::new)
-                            .push((coord, tag, model_matrix));
-                    }
-                }
-            }
+    let game = state.game.clone();
+    let culling_range = state.camera.culling_range;
+    let acknowledged = renderer.last_render_frame;
+
+    let (frame, render_commands) = state
+        .tokio
+        .block_on(game.call(
+            |reply| GameSystemMessage::GetRenderDelta {
+                culling_range,
+                acknowledged,
+                reply,
+            },
+            None,
+        ))
+        .unwrap()
+        .unwrap();
+
+    renderer.last_render_frame = Some(frame);
+
+    // a frame that wasn't drawn still has to have its changes applied
+    match &mut renderer.tick_snapshot {
+        Some(pending) => pending.extend(render_commands),
+        None => renderer.tick_snapshot = Some(render_commands),
+    }
+
+    let version = renderer.scenery_version;
+
+    if let Ok(CallResult::Success(Some((version, scenery)))) = state.tokio.block_on(game.call(
+        |reply| GameSystemMessage::GetScenery { version, reply },
+        None,
+    )) {
+        renderer.scenery_version = Some(version);
+        rebuild_scenery(&state.resource_man, renderer, scenery);
+    }
+
+    if let Ok(CallResult::Success((tick, agents))) = state.tokio.block_on(game.call(
+        |reply| GameSystemMessage::GetAgents {
+            culling_range,
+            reply,
+        },
+        None,
+    )) {
+        if renderer.agents_tick.map(|v| v.0) != Some(tick) {
+            renderer.agents_tick = Some((tick, Instant::now()));
         }
 
-        for (model, commands) in untrack_commands {
-            let (model, (meshes, ..)) = state.resource_man.mesh_or_missing_tile_mesh(&model);
+        renderer.agents = agents;
+    }
+}
 
-            for mesh in meshes.iter().flatten() {
-                for (coord, tag) in commands.iter().cloned() {
-                    let swapping_index = renderer.object_ids.last_entry().map(|v| v.index());
-                    let swapping_key = renderer.object_ids.last_entry().map(|v| *v.key());
+pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), SurfaceError> {
+    let Some(renderer) = state.renderer.as_mut() else {
+        return Ok(());
+    };
 
-                    let (removed_index, ..) = renderer
-                        .object_ids
-                        .swap_remove_full(&(coord, tag, model, mesh.index))
-                        .expect("render object id wasn't tracked");
+    let size = renderer.gpu.window.inner_size();
 
-                    let swapping_index = swapping_index.unwrap_or(removed_index);
+    if size.width == 0 || size.height == 0 {
+        return Ok(());
+    }
 
-                    if let Some(keys) = renderer.coord_to_keys.get_mut(&coord) {
-                        assert!(
-                            keys.remove(&(tag, model, mesh.index)),
-                            "key set in 'coord to keys map' didn't have this key"
-                        );
-                    }
+    renderer.animation_cache.clear();
 
-                    {
-                        renderer.matrix_data_map[removed_index] = Default::default();
-                        renderer.matrix_data_map.swap(removed_index, swapping_index);
+    let last_tile_tints = mem::take(&mut renderer.last_tile_tints);
+    let tile_tints = mem::take(&mut renderer.tile_tints);
 
-                        matrix_data_changes.insert(swapping_index);
-                        matrix_data_changes.insert(removed_index);
-                    }
-                    {
-                        renderer.instances[removed_index] = Default::default();
-                        renderer.instances.swap(removed_index, swapping_index);
+    let camera_pos = state.camera.get_pos();
 
-                        instances_changes.insert(swapping_index);
-                        instances_changes.insert(removed_index);
-                    }
+    let background = {
+        let time = state.start_instant.elapsed();
 
-                    {
-                        renderer
-                            .instance_ranges
-                            .entry((model, mesh.index))
-                            .or_default()
-                            .remove(removed_index);
-
-                        if swapping_index != removed_index {
-                            if let Some((.., model, mesh_index)) = swapping_key {
-                                renderer
-                                    .instance_ranges
-                                    .entry((model, mesh_index))
-                                    .or_default()
-                                    .insert(removed_index);
-                                renderer
-                                    .instance_ranges
-                                    .entry((model, mesh_index))
-                                    .or_default()
-                                    .remove(swapping_index);
-                            }
-                        }
-                    }
+        let (top, bottom, time_of_day) = match &state.loop_store.map_info {
+            Some((map_info, _)) => {
+                let info = map_info.blocking_lock();
+                let (top, bottom) = map_background(&state.resource_man, &info.data);
 
-                    if swapping_index != removed_index {
-                        renderer.instances[removed_index].matrix_index = removed_index as u32;
-                    }
-                }
+                (
+                    top,
+                    bottom,
+                    map_time_of_day(&state.resource_man, &info.data, time),
+                )
             }
-        }
+            None => (colors::SKY_TOP, colors::SKY_BOTTOM, 0.0),
+        };
 
-        for (model, commands) in track_commands {
-            let (model, (meshes, ..)) = state.resource_man.mesh_or_missing_tile_mesh(&model);
+        BackgroundUBO::new(
+            top.to_linear(),
+            bottom.to_linear(),
+            time_of_day,
+            time.as_secs_f32(),
+        )
+    };
 
-            for mesh in meshes.iter().flatten() {
-                for (coord, tag) in commands.iter().cloned() {
-                    if !renderer
-                        .animation_matrix_data_map
-                        .contains_key(&(model, mesh.index))
-                    {
-                        renderer
-                            .animation_matrix_data_map
-                            .insert((model, mesh.index), AnimationMatrixData::default());
-                    }
-                    let animation_matrix_index = renderer
-                        .animation_matrix_data_map
-                        .get_index_of(&(model, mesh.index))
-                        .unwrap();
-
-                    let (index, prev_id_slot) = renderer
-                        .object_ids
-                        .insert_full((coord, tag, model, mesh.index), ());
-                    assert!(
-                        prev_id_slot.is_none(),
-                        "render object id was already tracked"
-                    );
+    let render_commands = renderer.tick_snapshot.take().unwrap_or_default();
 
-                    assert!(
-                        renderer
-                            .coord_to_keys
-                            .entry(coord)
-                            .or_default()
-                            .insert((tag, model, mesh.index)),
-                        "coord to keys map already has the same key"
-                    );
+    renderer.scene.apply(&state.resource_man, render_commands);
+    renderer.scene.interpolate();
 
-                    renderer
-                        .matrix_data_map
-                        .resize_with(index + 1, Default::default);
-                    renderer.matrix_data_map[index] = MatrixData::default();
-                    matrix_data_changes.insert(index);
-
-                    renderer.instances.resize_with(index + 1, Default::default);
-                    renderer.instances[index] = GpuInstance {
-                        matrix_index: index as u32,
-                        animation_matrix_index: animation_matrix_index as u32,
-                        world_matrix_index: WE_ONLY_USE_1_WORLD_MATRIX_IN_GAME_LOL,
-                        color_offset: [0.0; 4],
-                        alpha: 1.0,
-                    };
-                    instances_changes.insert(index);
-
-                    renderer
-                        .instance_ranges
-                        .entry((model, mesh.index))
-                        .or_default()
-                        .insert(index);
-                }
-            }
-        }
+    if let Some((_, since)) = renderer.agents_tick {
+        let t = tick_interpolation(since);
+        let world_matrix = state.camera.get_matrix();
 
-        for (model, commands) in transform_commands {
+        for &(model, from, to) in &renderer.agents {
+            let model_matrix = Matrix4::from_translation(from.lerp(to, t).extend(0.0));
             let (model, (meshes, ..)) = state.resource_man.mesh_or_missing_tile_mesh(&model);
 
             for mesh in meshes.iter().flatten() {
-                for (coord, tag, model_matrix) in commands.iter().cloned() {
-                    if let Some(index) = renderer
-                        .object_ids
-                        .get_index_of(&(coord, tag, model, mesh.index))
-                    {
-                        if let Some(matrix) = renderer.matrix_data_map.get_mut(index) {
-                            *matrix = MatrixData::new(model_matrix, mesh.matrix);
-
-                            matrix_data_changes.insert(index);
-                        }
-                    }
-                }
+                renderer.overlay_instances.push((
+                    InstanceData::default(),
+                    model,
+                    GameMatrix::<true>::new(model_matrix, world_matrix, mesh.matrix),
+                    mesh.index,
+                ));
             }
         }
     }
@@ -413,16 +520,18 @@ pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), Surface
     let overlay_instances = mem::take(&mut renderer.overlay_instances);
     for &(_, model, _, mesh_index) in &overlay_instances {
         if !renderer
+            .scene
             .animation_matrix_data_map
             .contains_key(&(model, mesh_index))
         {
             renderer
+                .scene
                 .animation_matrix_data_map
                 .insert((model, mesh_index), AnimationMatrixData::default());
         }
     }
 
-    for (model, _) in renderer.animation_matrix_data_map.keys() {
+    for (model, _) in renderer.scene.animation_matrix_data_map.keys() {
         try_add_animation(
             &state.resource_man,
             state.start_instant,
@@ -431,9 +540,33 @@ pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), Surface
         );
     }
 
+    {
+        let animation_time = state.start_instant.elapsed().as_secs_f32();
+
+        if let Some(last) = renderer.last_animation_time.replace(animation_time) {
+            let events = crossed_animation_events(
+                &state.resource_man,
+                &renderer.scene,
+                state.camera.culling_range,
+                last,
+                animation_time,
+            );
+
+            if !events.is_empty() {
+                if let Err(err) = state
+                    .game
+                    .send_message(GameSystemMessage::AnimationEvents(events))
+                {
+                    log::error!("Could not send animation events: {err:?}");
+                }
+            }
+        }
+    }
+
     for (&model, anim) in &renderer.animation_cache {
         for (&mesh_id, &matrix) in anim {
             if let Some(data) = renderer
+                .scene
                 .animation_matrix_data_map
                 .get_mut(&(model, mesh_id))
             {
@@ -442,59 +575,27 @@ pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), Surface
         }
     }
 
-    {
-        for (coord, _) in last_tile_tints {
-            if tile_tints.contains_key(&coord) {
-                continue;
-            };
-
-            let Some(keys) = renderer.coord_to_keys.get(&coord) else {
-                continue;
-            };
-
-            for &key in keys {
-                let index = renderer
-                    .object_ids
-                    .get_index_of(&(coord, key.0, key.1, key.2))
-                    .unwrap();
-
-                renderer.instances[index].color_offset = [0.0; 4];
-                instances_changes.insert(index);
-            }
-        }
-
-        for (coord, tint) in &tile_tints {
-            let Some(keys) = renderer.coord_to_keys.get(coord) else {
-                continue;
-            };
-
-            for &key in keys {
-                let index = renderer
-                    .object_ids
-                    .get_index_of(&(*coord, key.0, key.1, key.2))
-                    .unwrap();
+    renderer.scene.tint(last_tile_tints, &tile_tints);
 
-                renderer.instances[index].color_offset = tint.to_array();
-                instances_changes.insert(index);
-            }
-        }
+    if mem::take(&mut renderer.reupload) {
+        renderer.scene.reupload();
     }
 
-    let mut instances_changes = instances_changes.into_iter().collect::<Vec<_>>();
-    instances_changes.sort();
-    let mut matrix_data_changes = matrix_data_changes.into_iter().collect::<Vec<_>>();
-    matrix_data_changes.sort();
-
-    let r = renderer.inner_render(
-        state.resource_man.clone(),
-        state.gui.as_mut().unwrap(),
+    let mut frame = renderer.scene.frame(
+        &state.resource_man,
+        background,
         camera_pos,
         state.camera.get_matrix(),
-        instances_changes,
-        matrix_data_changes,
         overlay_instances,
-        screenshotting,
+        state.input_handler.key_active(ActionType::XRay),
+        state.gui.as_mut(),
     );
+    frame.post_processing = state.loop_store.quality.level().post_processing();
+    frame.resolution_scale = state.loop_store.quality.level().resolution_scale();
+    frame.screenshotting = screenshotting;
+    frame.screenshot_path = state.screenshot_path.take();
+
+    let r = renderer.draw_frame(state.resource_man.clone(), frame);
 
     automancy_ui::reset_custom_paint_state();
     renderer.last_tile_tints = tile_tints;
@@ -503,17 +604,66 @@ pub fn render(state: &mut GameState, screenshotting: bool) -> Result<(), Surface
 }
 
 impl GameRenderer {
-    fn inner_render(
+    /// Draws either the opaque or the non-opaque meshes of the scenery batch.
+    fn draw_scenery(
+        &self,
+        render_pass: &mut RenderPass<'_>,
+        resource_man: &ResourceManager,
+        opaque: bool,
+    ) {
+        if self.scenery_ranges.is_empty() {
+            return;
+        }
+
+        render_pass.set_bind_group(0, &self.render_resources.scenery_resources.bind_group, &[]);
+        render_pass.set_vertex_buffer(
+            1,
+            self.render_resources
+                .scenery_resources
+                .instance_buffer
+                .slice(..),
+        );
+
+        for ((model, mesh_index), range) in &self.scenery_ranges {
+            let (meshes, ..) = resource_man.all_meshes_anims.get(model).unwrap();
+
+            if let Some(mesh) = &meshes[*mesh_index] {
+                if mesh.opaque == opaque {
+                    let index_range = &resource_man.all_index_ranges[model][&mesh.index];
+
+                    render_pass.draw_indexed(
+                        index_range.pos..(index_range.pos + index_range.count),
+                        index_range.base_vertex,
+                        range.clone(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl RenderBackend for GameRenderer {
+    fn draw_frame(
         &mut self,
         resource_man: Arc<ResourceManager>,
-        gui: &mut GameGui<YakuiRenderResources>,
-        camera_pos: Vec3,
-        camera_matrix: Matrix4,
-        instances_changes: Vec<usize>,
-        matrix_data_changes: Vec<usize>,
-        overlay_instances: Vec<OverlayInstance>,
-        screenshotting: bool,
+        frame: Frame<'_>,
     ) -> Result<(), SurfaceError> {
+        let Frame {
+            background,
+            camera_pos,
+            camera_matrix,
+            draws,
+            overlay_instances,
+            instances_changes,
+            matrix_data_changes,
+            xray,
+            post_processing,
+            resolution_scale,
+            gui,
+            screenshotting,
+            screenshot_path,
+        } = frame;
+
         let size = self.gpu.window.inner_size();
 
         let output = self.gpu.surface.get_current_texture()?;
@@ -526,6 +676,15 @@ impl GameRenderer {
             }
         }
 
+        if self.shared_resources.game_scale() != resolution_scale {
+            self.shared_resources.set_game_scale(
+                &self.gpu.device,
+                &self.gpu.config,
+                &self.global_resources,
+                resolution_scale,
+            );
+        }
+
         let mut encoder = self
             .gpu
             .device
@@ -533,44 +692,98 @@ impl GameRenderer {
                 label: Some("Render Encoder"),
             });
 
-        let mut game_staging_belts = [None, None];
+        if let Some((instances, matrix_data)) = self.scenery_upload.take() {
+            if !instances.is_empty() {
+                self.upload_ring.resize_write(
+                    &mut encoder,
+                    &self.gpu.device,
+                    &mut self.render_resources.scenery_resources.instance_buffer,
+                    &instances,
+                );
+                self.upload_ring.write(
+                    &mut encoder,
+                    &self.gpu.device,
+                    &self.render_resources.scenery_resources.matrix_data_buffer,
+                    0,
+                    &matrix_data,
+                );
+            }
+        }
 
         {
-            if !self.instances.is_empty() {
-                game_staging_belts[0] = gpu::resize_update_buffer_with_changes(
+            self.upload_ring.write(
+                &mut encoder,
+                &self.gpu.device,
+                &self.render_resources.background_resources.uniform_buffer,
+                0,
+                &[background],
+            );
+
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Background Render Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &self.shared_resources.game_texture().1,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_pipeline(&self.global_resources.background_pipeline);
+            render_pass.set_bind_group(
+                0,
+                &self.render_resources.background_resources.bind_group,
+                &[],
+            );
+            render_pass.draw(0..3, 0..1);
+        }
+
+        {
+            if !self.scene.instances.is_empty() {
+                self.upload_ring.resize_write_changes(
                     &mut encoder,
                     &self.gpu.device,
                     &mut self.render_resources.game_resources.instance_buffer,
                     &instances_changes,
-                    &self.instances,
+                    &self.scene.instances,
                 );
-                game_staging_belts[1] = gpu::update_buffer_with_changes(
+                self.upload_ring.write_changes(
                     &mut encoder,
                     &self.gpu.device,
                     &self.render_resources.game_resources.matrix_data_buffer,
                     &matrix_data_changes,
-                    &self.matrix_data_map,
+                    &self.scene.matrix_data_map,
                 );
-                gpu::ordered_map_update_buffer(
-                    &self.gpu.queue,
+                self.upload_ring.write_ordered_map(
+                    &mut encoder,
+                    &self.gpu.device,
                     &self
                         .render_resources
                         .game_resources
                         .animation_matrix_data_buffer,
-                    &self.animation_matrix_data_map,
+                    &self.scene.animation_matrix_data_map,
                 );
-                self.gpu.queue.write_buffer(
+                self.upload_ring.write(
+                    &mut encoder,
+                    &self.gpu.device,
                     &self
                         .render_resources
                         .game_resources
                         .world_matrix_data_buffer,
                     0,
-                    bytemuck::cast_slice(&[WorldMatrixData::new(camera_matrix)]),
+                    &[WorldMatrixData::new(camera_matrix)],
                 );
-                self.gpu.queue.write_buffer(
+                self.upload_ring.write(
+                    &mut encoder,
+                    &self.gpu.device,
                     &self.render_resources.game_resources.uniform_buffer,
                     0,
-                    bytemuck::cast_slice(&[GameUBO::new(camera_pos, None)]),
+                    &[GameUBO::new(camera_pos, None)],
                 );
 
                 {
@@ -581,7 +794,8 @@ impl GameRenderer {
                                 view: &self.shared_resources.game_texture().1,
                                 resolve_target: None,
                                 ops: Operations {
-                                    load: LoadOp::Clear(Color::BLACK),
+                                    // drawn over the background
+                                    load: LoadOp::Load,
                                     store: StoreOp::Store,
                                 },
                             }),
@@ -633,47 +847,127 @@ impl GameRenderer {
                         IndexFormat::Uint16,
                     );
 
-                    for (&(model, mesh_index), ranges) in &self.instance_ranges {
-                        let (meshes, ..) = resource_man.all_meshes_anims.get(&model).unwrap();
-
-                        if let Some(mesh) = &meshes[mesh_index] {
-                            if mesh.opaque {
-                                let index_range =
-                                    &resource_man.all_index_ranges[&model][&mesh.index];
-
-                                for range in ranges.ranges() {
-                                    render_pass.draw_indexed(
-                                        index_range.pos..(index_range.pos + index_range.count),
-                                        index_range.base_vertex,
-                                        (*range.start() as u32)..(*range.end() as u32 + 1),
-                                    );
-                                }
-                            }
-                        }
+                    for draw in draws.iter().filter(|v| v.opaque) {
+                        let index_range =
+                            &resource_man.all_index_ranges[&draw.model][&draw.mesh_index];
+
+                        render_pass.draw_indexed(
+                            index_range.pos..(index_range.pos + index_range.count),
+                            index_range.base_vertex,
+                            draw.instances.clone(),
+                        );
                     }
 
-                    for (&(model, mesh_index), ranges) in &self.instance_ranges {
-                        let (meshes, ..) = resource_man.all_meshes_anims.get(&model).unwrap();
-
-                        if let Some(mesh) = &meshes[mesh_index] {
-                            if !mesh.opaque {
-                                let index_range =
-                                    &resource_man.all_index_ranges[&model][&mesh.index];
-
-                                for range in ranges.ranges() {
-                                    render_pass.draw_indexed(
-                                        index_range.pos..(index_range.pos + index_range.count),
-                                        index_range.base_vertex,
-                                        (*range.start() as u32)..(*range.end() as u32 + 1),
-                                    );
-                                }
-                            }
-                        }
+                    self.draw_scenery(&mut render_pass, &resource_man, true);
+
+                    render_pass.set_bind_group(
+                        0,
+                        &self.render_resources.game_resources.bind_group,
+                        &[],
+                    );
+                    render_pass.set_vertex_buffer(
+                        1,
+                        self.render_resources
+                            .game_resources
+                            .instance_buffer
+                            .slice(..),
+                    );
+
+                    for draw in draws.iter().filter(|v| !v.opaque) {
+                        let index_range =
+                            &resource_man.all_index_ranges[&draw.model][&draw.mesh_index];
+
+                        render_pass.draw_indexed(
+                            index_range.pos..(index_range.pos + index_range.count),
+                            index_range.base_vertex,
+                            draw.instances.clone(),
+                        );
                     }
+
+                    self.draw_scenery(&mut render_pass, &resource_man, false);
                 }
             }
         }
 
+        // the depth of the models is only there if the game pass drew them this frame
+        let occlude = !xray && !self.scene.instances.is_empty();
+
+        // uploaded before the pass, as the copies are recorded into the same encoder
+        if !overlay_instances.is_empty() {
+            self.upload_ring.resize_write(
+                &mut encoder,
+                &self.gpu.device,
+                &mut self
+                    .render_resources
+                    .overlay_objects_resources
+                    .instance_buffer,
+                &overlay_instances
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, (v, model_id, _, mesh_index))| {
+                        let animation_index = self
+                            .scene
+                            .animation_matrix_data_map
+                            .get_index_of(&(*model_id, *mesh_index))
+                            .unwrap();
+
+                        GpuInstance {
+                            color_offset: v.color_offset,
+                            alpha: v.alpha,
+                            matrix_index: idx as u32,
+                            world_matrix_index: idx as u32,
+                            animation_matrix_index: animation_index as u32,
+                        }
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            self.upload_ring.write(
+                &mut encoder,
+                &self.gpu.device,
+                &self
+                    .render_resources
+                    .overlay_objects_resources
+                    .matrix_data_buffer,
+                0,
+                &overlay_instances
+                    .iter()
+                    .map(|v| {
+                        let model_matrix = if occlude {
+                            pull_toward_camera(v.2.model_matrix() * v.2.mesh_matrix(), camera_pos)
+                                * v.2.model_matrix()
+                        } else {
+                            v.2.model_matrix()
+                        };
+
+                        MatrixData::new(model_matrix, v.2.mesh_matrix())
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            self.upload_ring.write(
+                &mut encoder,
+                &self.gpu.device,
+                &self
+                    .render_resources
+                    .overlay_objects_resources
+                    .world_matrix_data_buffer,
+                0,
+                &overlay_instances
+                    .iter()
+                    .map(|v| WorldMatrixData::new(v.2.world_matrix()))
+                    .collect::<Vec<_>>(),
+            );
+            self.upload_ring.write(
+                &mut encoder,
+                &self.gpu.device,
+                &self
+                    .render_resources
+                    .overlay_objects_resources
+                    .uniform_buffer,
+                0,
+                &[GameUBO::new(camera_pos, None)],
+            );
+        }
+
         {
             let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
                 label: Some("Overlay Objects Render Pass"),
@@ -703,10 +997,19 @@ impl GameRenderer {
                         },
                     }),
                 ],
+                // tested against the depth of the models, unless they are seen through
                 depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
-                    view: &self.shared_resources.overlay_depth_texture().1,
+                    view: if occlude {
+                        &self.shared_resources.depth_texture().1
+                    } else {
+                        &self.shared_resources.overlay_depth_texture().1
+                    },
                     depth_ops: Some(Operations {
-                        load: LoadOp::Clear(1.0),
+                        load: if occlude {
+                            LoadOp::Load
+                        } else {
+                            LoadOp::Clear(1.0)
+                        },
                         store: StoreOp::Store,
                     }),
                     stencil_ops: None,
@@ -716,67 +1019,6 @@ impl GameRenderer {
             });
 
             if !overlay_instances.is_empty() {
-                gpu::resize_update_buffer(
-                    &self.gpu.device,
-                    &self.gpu.queue,
-                    &mut self
-                        .render_resources
-                        .overlay_objects_resources
-                        .instance_buffer,
-                    &overlay_instances
-                        .iter()
-                        .enumerate()
-                        .map(|(idx, (v, model_id, _, mesh_index))| {
-                            let animation_index = self
-                                .animation_matrix_data_map
-                                .get_index_of(&(*model_id, *mesh_index))
-                                .unwrap();
-
-                            GpuInstance {
-                                color_offset: v.color_offset,
-                                alpha: v.alpha,
-                                matrix_index: idx as u32,
-                                world_matrix_index: idx as u32,
-                                animation_matrix_index: animation_index as u32,
-                            }
-                        })
-                        .collect::<Vec<_>>(),
-                );
-                self.gpu.queue.write_buffer(
-                    &self
-                        .render_resources
-                        .overlay_objects_resources
-                        .matrix_data_buffer,
-                    0,
-                    bytemuck::cast_slice(
-                        &overlay_instances
-                            .iter()
-                            .map(|v| MatrixData::new(v.2.model_matrix(), v.2.mesh_matrix()))
-                            .collect::<Vec<_>>(),
-                    ),
-                );
-                self.gpu.queue.write_buffer(
-                    &self
-                        .render_resources
-                        .overlay_objects_resources
-                        .world_matrix_data_buffer,
-                    0,
-                    bytemuck::cast_slice(
-                        &overlay_instances
-                            .iter()
-                            .map(|v| WorldMatrixData::new(v.2.world_matrix()))
-                            .collect::<Vec<_>>(),
-                    ),
-                );
-                self.gpu.queue.write_buffer(
-                    &self
-                        .render_resources
-                        .overlay_objects_resources
-                        .uniform_buffer,
-                    0,
-                    bytemuck::cast_slice(&[GameUBO::new(camera_pos, None)]),
-                );
-
                 render_pass.set_pipeline(&self.global_resources.game_pipeline);
                 render_pass.set_bind_group(
                     0,
@@ -811,15 +1053,22 @@ impl GameRenderer {
         }
 
         {
-            self.gpu.queue.write_buffer(
+            self.upload_ring.write(
+                &mut encoder,
+                &self.gpu.device,
                 &self
                     .render_resources
                     .post_processing_resources
                     .uniform_buffer,
                 0,
-                bytemuck::cast_slice(&[PostProcessingUBO {
+                &[PostProcessingUBO {
+                    flags: if post_processing {
+                        FLAG_SCREEN_EFFECT
+                    } else {
+                        0
+                    },
                     ..Default::default()
-                }]),
+                }],
             );
 
             let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
@@ -879,8 +1128,8 @@ impl GameRenderer {
             render_pass.draw(0..3, 0..1);
         }
 
-        let custom_gui_commands: CommandBuffer;
-        {
+        let mut custom_gui_commands: Option<CommandBuffer> = None;
+        if let Some(gui) = gui {
             let surface = SurfaceInfo {
                 format: self.gpu.config.format,
                 sample_count: 4,
@@ -923,14 +1172,14 @@ impl GameRenderer {
                     ..Default::default()
                 });
 
-                custom_gui_commands = gui.renderer.paint_with::<GameElementPaint>(
+                custom_gui_commands = Some(gui.renderer.paint_with::<GameElementPaint>(
                     &mut gui.yak,
                     &self.gpu.device,
                     &self.gpu.queue,
                     &mut render_pass,
                     surface,
                     &mut resources,
-                );
+                ));
             }
 
             self.render_resources.gui_resources = resources.gui_resources.take();
@@ -943,7 +1192,7 @@ impl GameRenderer {
             self.gui_rects = resources.rects;
 
             self.animation_cache = resources.animation_cache;
-        };
+        }
 
         {
             let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
@@ -1064,13 +1313,11 @@ impl GameRenderer {
             None
         };
 
+        self.upload_ring.finish();
         self.gpu
             .queue
-            .submit([custom_gui_commands, encoder.finish()]);
-
-        for mut belt in game_staging_belts.into_iter().flatten() {
-            belt.recall();
-        }
+            .submit(custom_gui_commands.into_iter().chain([encoder.finish()]));
+        self.upload_ring.recall();
 
         if let Some(buffer) = screenshot_buffer {
             {
@@ -1096,13 +1343,20 @@ impl GameRenderer {
                 if let Some(image) =
                     RgbaImage::from_vec(texture_dim.width, texture_dim.height, result)
                 {
-                    self.screenshot_clipboard
-                        .set_image(ImageData {
-                            width: image.width() as usize,
-                            height: image.height() as usize,
-                            bytes: Cow::from(image.as_bytes()),
-                        })
-                        .unwrap();
+                    if let Some(path) = screenshot_path {
+                        match image.save(&path) {
+                            Ok(()) => log::info!("Saved a screenshot to {}", path.display()),
+                            Err(err) => log::error!("Could not save a screenshot: {err}"),
+                        }
+                    } else {
+                        self.screenshot_clipboard
+                            .set_image(ImageData {
+                                width: image.width() as usize,
+                                height: image.height() as usize,
+                                bytes: Cow::from(image.as_bytes()),
+                            })
+                            .unwrap();
+                    }
                 }
             }
 
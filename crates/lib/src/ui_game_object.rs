@@ -105,6 +105,7 @@ impl CallbackTrait<YakuiRenderResources> for GameElementPaint {
                                 TileCoord::ZERO,
                                 &mut data,
                                 &mut Default::default(),
+                                None,
                                 true,
                                 false,
                             ) {
@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+/// How much earlier than a frame is due the event loop wakes up, before it has measured how late it actually wakes up.
+const INITIAL_WAKE_MARGIN: Duration = Duration::from_micros(1500);
+const MIN_WAKE_MARGIN: Duration = Duration::from_micros(500);
+const MAX_WAKE_MARGIN: Duration = Duration::from_millis(4);
+
+/// What the event loop should do until the next frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacerAction {
+    /// start the next frame now
+    Render,
+    /// sleep until the instant, which is a bit before the next frame is due
+    Sleep(Instant),
+    /// the next frame is due too soon to sleep reliably, so keep polling until it is
+    Spin,
+}
+
+/// Paces frames to a fixed frame time.
+///
+/// Each frame is scheduled one frame time after the previous frame was due, not after it actually started,
+/// so that waking up late doesn't delay every frame after it. The event loop sleeps until shortly before a frame is due,
+/// and polls for the rest, as sleeps tend to overshoot by about a millisecond.
+#[derive(Debug)]
+pub struct FramePacer {
+    /// the time between frames, or None to not limit them
+    frame_time: Option<Duration>,
+    /// when the next frame is due
+    next_frame: Option<Instant>,
+    /// when the event loop was last asked to wake up, to measure how late it actually did
+    requested_wake: Option<Instant>,
+    /// how long before a frame is due to wake up, following how late wake-ups have been
+    wake_margin: Duration,
+}
+
+impl Default for FramePacer {
+    fn default() -> Self {
+        Self {
+            frame_time: None,
+            next_frame: None,
+            requested_wake: None,
+            wake_margin: INITIAL_WAKE_MARGIN,
+        }
+    }
+}
+
+impl FramePacer {
+    /// Sets the frame rate to pace to, or None to not limit it.
+    pub fn set_fps(&mut self, fps: Option<f64>) {
+        let frame_time = fps
+            .filter(|fps| *fps > 0.0)
+            .map(|fps| Duration::from_secs_f64(1.0 / fps));
+
+        if self.frame_time != frame_time {
+            self.frame_time = frame_time;
+            self.next_frame = None;
+        }
+    }
+
+    /// Decides what to do until the next frame. Once this returns Render, the frame after is scheduled.
+    pub fn poll(&mut self, now: Instant) -> PacerAction {
+        let Some(frame_time) = self.frame_time else {
+            return PacerAction::Render;
+        };
+
+        if let Some(requested) = self.requested_wake.take() {
+            // wake-ups before the requested time were caused by other events, and say nothing about the timer
+            if now >= requested {
+                let late = now - requested;
+
+                // settles at about twice the usual lateness, so that most wake-ups are still early enough
+                self.wake_margin =
+                    ((self.wake_margin * 7 + late * 2) / 8).clamp(MIN_WAKE_MARGIN, MAX_WAKE_MARGIN);
+            }
+        }
+
+        let due = *self.next_frame.get_or_insert(now);
+
+        if now >= due {
+            let next = due + frame_time;
+
+            // more than a frame behind, so start over instead of rushing frames out to catch up
+            self.next_frame = Some(if next <= now { now + frame_time } else { next });
+
+            return PacerAction::Render;
+        }
+
+        match due.checked_sub(self.wake_margin) {
+            Some(wake) if now < wake => {
+                self.requested_wake = Some(wake);
+
+                PacerAction::Sleep(wake)
+            }
+            _ => PacerAction::Spin,
+        }
+    }
+}
@@ -1,31 +1,40 @@
 #![windows_subsystem = "windows"]
 use automancy_lib::*;
 
+use anyhow::Context;
+use archive::{archive_namespace, unpack_archive};
+use automancy_lib::util::pacing::{FramePacer, PacerAction};
 use camera::GameCamera;
 use color_eyre::config::HookBuilder;
 use cosmic_text::fontdb::Source;
-use game::{GameSystem, GameSystemMessage, TICK_INTERVAL};
+use error::set_muted_errs;
+use format::Formattable;
+use game::{GameSystem, GameSystemMessage, GAME_MAILBOX, WAKE_INTERVAL};
 use glam::uvec2;
-use gpu::Gpu;
+use gpu::{GlobalResources, Gpu, RenderResources, SharedResources};
+use id::Interner;
 use input::InputHandler;
 use kira::manager::{AudioManager, AudioManagerSettings};
 use kira::track::{TrackBuilder, TrackHandle};
 use kira::tween::Tween;
 use map::LoadMapOption;
 use options::{GameOptions, MiscOptions};
+use profiler::SCRIPT_PROFILER;
 use ractor::Actor;
 use renderer::GameRenderer;
 use rendering::Vertex;
-use rfd::{MessageButtons, MessageDialog, MessageLevel};
+use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
 use std::fmt::Write;
 use std::fs::File;
 use std::path::Path;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::{env, fs, panic};
 use tokio::runtime::Runtime;
-use ui_state::UiState;
+use ui_state::{PopupAction, PopupState, UiState};
 use uuid::Uuid;
+use watch::PackWatcher;
 use winit::{
     application::ApplicationHandler,
     event::{DeviceEvent, DeviceId, WindowEvent},
@@ -41,67 +50,84 @@ use yakui::paint::{Texture, TextureFilter};
 
 pub static LOGO: &[u8] = include_bytes!("logo.png");
 
-/// Initialize the Resource Manager system, and loads all the resources in all namespaces.
+/// How long to wait between attempts at recreating a lost graphics device.
+const DEVICE_RECOVERY_INTERVAL: Duration = Duration::from_secs(1);
+/// How many times recreating a lost graphics device is attempted, before the game saves and quits.
+const MAX_DEVICE_RECOVERY_ATTEMPTS: u32 = 5;
+
+/// Where the number of launches in a row that crashed before the first frame was drawn is kept.
+static STARTUP_CRASHES_PATH: &str = "startup_crashes";
+/// How many launches in a row have to crash while starting, before safe mode is offered.
+const SAFE_MODE_PROMPT_CRASHES: u32 = 2;
+/// The only namespace loaded in safe mode.
+static SAFE_MODE_NAMESPACE: &str = "core";
+
+/// Initialize the Resource Manager system, and loads all the resources in all namespaces, or only the core one in safe
+/// mode. If the packs are being reloaded, the interner of the old resource manager is given, so that the IDs stay the
+/// same.
 fn load_resources(
     selected_language: &str,
     track: TrackHandle,
-) -> (Arc<ResourceManager>, Vec<Vertex>, Vec<u16>) {
-    let mut resource_man = ResourceManager::new(track);
+    interner: Option<Interner>,
+    safe_mode: bool,
+) -> anyhow::Result<(Arc<ResourceManager>, Vec<Vertex>, Vec<u16>)> {
+    let mut resource_man = match interner {
+        Some(interner) => ResourceManager::with_interner(track, interner),
+        None => ResourceManager::new(track),
+    };
 
-    fs::read_dir(RESOURCES_PATH)
+    let paths = fs::read_dir(RESOURCES_PATH)
         .expect("The resources folder doesn't exist- this is very wrong")
         .flatten()
         .map(|v| v.path())
+        .filter(|v| !safe_mode || v.file_name().is_some_and(|v| v == SAFE_MODE_NAMESPACE))
+        .collect::<Vec<_>>();
+
+    let mut packs = paths
+        .iter()
         .filter(|v| v.is_dir())
-        .for_each(|dir| {
+        .map(|dir| {
             let namespace = dir.file_name().unwrap().to_str().unwrap().trim();
-            log::info!("Loading namespace {namespace}...");
-
-            resource_man
-                .load_models(&dir, namespace)
-                .expect("Error loading models");
-
-            resource_man.load_audio(&dir).expect("Error loading audio");
 
-            resource_man
-                .load_tiles(&dir, namespace)
-                .expect("Error loading tiles");
+            (namespace.to_string(), dir.clone())
+        })
+        .collect::<Vec<_>>();
 
-            resource_man
-                .load_items(&dir, namespace)
-                .expect("Error loading items");
+    for archive in paths.iter().filter(|v| v.is_file()) {
+        let Some(namespace) = archive_namespace(archive) else {
+            continue;
+        };
 
-            resource_man
-                .load_tags(&dir, namespace)
-                .expect("Error loading tags");
-            resource_man
-                .load_categories(&dir, namespace)
-                .expect("Error loading categories");
-
-            resource_man
-                .load_scripts(&dir, namespace)
-                .expect("Error loading scripts");
+        if packs.iter().any(|(v, _)| v == namespace) {
+            log::warn!(
+                "Skipping {}, as the namespace {namespace} is already loaded from a folder.",
+                archive.display()
+            );
+            continue;
+        }
 
-            resource_man
-                .load_translates(&dir, namespace, selected_language)
-                .expect("Error loading translates");
+        log::info!(
+            "Unpacking namespace {namespace} from {}...",
+            archive.display()
+        );
 
-            resource_man
-                .load_shaders(&dir)
-                .expect("Error loading shaders");
+        let dir = unpack_archive(archive, namespace)
+            .with_context(|| format!("Error unpacking {}", archive.display()))?;
 
-            resource_man.load_fonts(&dir).expect("Error loading fonts");
+        packs.push((namespace.to_string(), dir));
+    }
 
-            resource_man
-                .load_functions(&dir, namespace)
-                .expect("Error loading functions");
+    for (namespace, dir) in
+        ResourceManager::sort_namespaces(packs).context("Error sorting the namespaces")?
+    {
+        log::info!("Loading namespace {namespace}...");
 
-            resource_man
-                .load_researches(&dir, namespace)
-                .expect("Error loading researches");
+        resource_man
+            .load_namespace(&dir, &namespace, selected_language)
+            .with_context(|| format!("Error loading namespace {namespace}"))?;
 
-            log::info!("Loaded namespace {namespace}.");
-        });
+        log::info!("Loaded namespace {namespace}.");
+    }
 
     resource_man
         .engine
@@ -114,11 +140,13 @@ fn load_resources(
     resource_man.compile_researches();
     resource_man.ordered_tiles();
     resource_man.ordered_items();
+    resource_man.compile_item_indexes();
     resource_man.compile_categories();
+    resource_man.compile_asset_graph();
 
     let (vertices, indices) = resource_man.compile_models();
 
-    (Arc::new(resource_man), vertices, indices)
+    Ok((Arc::new(resource_man), vertices, indices))
 }
 
 /// Gets the game icon.
@@ -164,41 +192,263 @@ fn write_msg<P: AsRef<Path>>(buffer: &mut impl Write, file_path: P) -> std::fmt:
 struct Automancy {
     state: GameState,
     window: Option<Arc<Window>>,
-    fps_limit: Option<i32>,
+    pacer: FramePacer,
     closed: bool,
+    /// the attempts made at recreating the lost graphics device, and when the last one was
+    device_recovery: Option<(u32, Instant)>,
+    /// watches the resource packs for changes to reload them, if started with `--watch-packs`
+    pack_watcher: Option<PackWatcher>,
+    /// whether only the core pack is loaded, with the default options
+    safe_mode: bool,
+    /// whether the first frame was drawn, after which the launch no longer counts as crashing while starting
+    started: bool,
 }
 
 impl Automancy {
-    fn try_sync_options(&mut self) {
-        if !self.state.options.synced {
-            {
-                let font = self
+    /// Creates the GPU for the window, and everything that is rendered with it.
+    fn create_gpu(
+        &self,
+    ) -> anyhow::Result<(Gpu, SharedResources, RenderResources, Arc<GlobalResources>)> {
+        let gpu = self.state.tokio.block_on(Gpu::new(
+            self.window.as_ref().unwrap().clone(),
+            self.state.options.graphics.fps_limit == 0,
+            self.state.options.graphics.mailbox,
+        ))?;
+
+        let (shared_resources, render_resources, global_resources) = gpu::init_gpu_resources(
+            &gpu.device,
+            &gpu.config,
+            &self.state.resource_man,
+            self.state.vertices_init.clone().unwrap(),
+            self.state.indices_init.clone().unwrap(),
+        );
+
+        Ok((
+            gpu,
+            shared_resources,
+            render_resources,
+            Arc::new(global_resources),
+        ))
+    }
+
+    /// Tries to recreate the lost graphics device, with the simulation paused until the player acknowledges the recovery.
+    /// Gives up, saving the map and quitting, if it keeps failing.
+    fn recover_device(&mut self, event_loop: &ActiveEventLoop) {
+        let attempts = match self.device_recovery {
+            Some((_, last)) if last.elapsed() < DEVICE_RECOVERY_INTERVAL => return,
+            Some((attempts, _)) => attempts,
+            None => {
+                log::warn!("Recovering the graphics device...");
+
+                if let Err(err) = self
                     .state
+                    .game
+                    .send_message(GameSystemMessage::SetPaused(true))
+                {
+                    log::error!("Could not pause the simulation! Error: {err:?}");
+                }
+
+                0
+            }
+        };
+
+        if attempts >= MAX_DEVICE_RECOVERY_ATTEMPTS {
+            log::error!(
+                "Could not recover the graphics device after {attempts} attempts, quitting."
+            );
+
+            self.closed = self
+                .state
+                .tokio
+                .block_on(event::shutdown_graceful(
+                    &self.state.game,
+                    &mut self.state.game_handle,
+                    event_loop,
+                ))
+                .unwrap_or(true);
+
+            return;
+        }
+
+        self.device_recovery = Some((attempts + 1, Instant::now()));
+
+        let (gpu, shared_resources, render_resources, global_resources) = match self.create_gpu() {
+            Ok(v) => v,
+            Err(err) => {
+                log::error!("Could not recreate the graphics device! Error: {err:?}");
+                return;
+            }
+        };
+
+        let renderer = self.state.renderer.as_mut().unwrap();
+        renderer.replace_gpu(gpu, shared_resources, render_resources, global_resources);
+
+        // the textures the GUI has are uploaded again by the new renderer
+        let gui = self.state.gui.as_mut().unwrap();
+        gui.renderer =
+            yakui_wgpu::YakuiWgpu::new(&mut gui.yak, &renderer.gpu.device, &renderer.gpu.queue);
+
+        gpu::DEVICE_LOST.store(false, Ordering::Relaxed);
+        self.device_recovery = None;
+
+        let gui_ids = &self.state.resource_man.registry.gui_ids;
+
+        // the simulation stays paused until the player resumes it, so the toast never times out
+        gui::toast::push_toast(
+            Toast::new(
+                self.state
+                    .resource_man
+                    .gui_str(gui_ids.lbl_device_recovered)
+                    .to_string(),
+                ToastPriority::High,
+            )
+            .with_action(
+                self.state
                     .resource_man
-                    .fonts
-                    .get(
-                        &self
-                            .state
-                            .options
-                            .gui
-                            .get_font(&self.state.resource_man)
-                            .expect("no fonts loaded!"),
+                    .gui_str(gui_ids.btn_unpause)
+                    .to_string(),
+                PopupAction::ResumeSimulation,
+            ),
+        );
+
+        log::info!("Recovered the graphics device.");
+    }
+
+    /// Loads the resource packs again, and swaps them in for the old ones: the game, the renderer and the GUI use the
+    /// new ones right away, and the map is saved and loaded again so that its tiles pick up the new scripts.
+    /// The old resources are kept if the packs can't be loaded.
+    fn reload_resources(&mut self, namespaces: &[String]) {
+        log::info!("Reloading the resource packs, as {namespaces:?} changed...");
+
+        let result = self
+            .state
+            .audio_man
+            .add_sub_track(TrackBuilder::new())
+            .map_err(anyhow::Error::from)
+            .and_then(|track| {
+                load_resources(
+                    &self.state.misc_options.language,
+                    track,
+                    Some(self.state.resource_man.interner.clone()),
+                    self.safe_mode,
+                )
+            });
+
+        let (resource_man, vertices, indices) = match result {
+            Ok(v) => v,
+            Err(err) => {
+                log::error!("Could not reload the resource packs! Error: {err:?}");
+
+                gui::toast::push_toast(
+                    Toast::new(
+                        self.state.resource_man.gui_fmt(
+                            self.state
+                                .resource_man
+                                .registry
+                                .gui_ids
+                                .lbl_packs_reload_failed,
+                            [("error", Formattable::display(&err))],
+                        ),
+                        ToastPriority::High,
                     )
-                    .or_else(|| {
-                        self.state
-                            .options
-                            .gui
-                            .set_font(&self.state.resource_man, None);
+                    .with_timeout(gui::toast::LONG_TOAST),
+                );
 
-                        self.state.resource_man.fonts.values().next()
-                    })
-                    .expect("no fonts loaded! at all! put one in there!");
+                return;
+            }
+        };
+
+        if let Err(err) = self
+            .state
+            .tokio
+            .block_on(self.state.game.call(GameSystemMessage::SaveMap, None))
+        {
+            log::error!("Could not save the map before reloading! Error: {err:?}");
+        }
+
+        RESOURCE_MAN.write().unwrap().replace(resource_man.clone());
+        self.state.resource_man = resource_man.clone();
+        self.state.vertices_init = Some(vertices.clone());
+        self.state.indices_init = Some(indices.clone());
+
+        if let Err(err) = self
+            .state
+            .game
+            .send_message(GameSystemMessage::ReloadResources(resource_man.clone()))
+        {
+            log::error!("Could not hand the reloaded resources to the game! Error: {err:?}");
+        }
+
+        if let Some(renderer) = self.state.renderer.as_mut() {
+            let (shared_resources, render_resources, global_resources) = gpu::init_gpu_resources(
+                &renderer.gpu.device,
+                &renderer.gpu.config,
+                &resource_man,
+                vertices,
+                indices,
+            );
+
+            renderer.replace_resources(
+                shared_resources,
+                render_resources,
+                Arc::new(global_resources),
+            );
+        }
+
+        if let Some(gui) = self.state.gui.as_mut() {
+            for (name, font) in resource_man.fonts.iter() {
+                gui.fonts
+                    .insert(name.clone(), Source::Binary(font.data.clone()));
+            }
+        }
+        // the options hold IDs and fonts that may have changed
+        self.state.options.synced = false;
+
+        if let Some((_, opt)) = self.state.loop_store.map_info.clone() {
+            game_load_map_inner(&mut self.state, opt);
+        }
+
+        gui::toast::push_toast(
+            Toast::new(
+                resource_man.gui_fmt(
+                    resource_man.registry.gui_ids.lbl_packs_reloaded,
+                    [("namespaces", Formattable::display(&namespaces.join(", ")))],
+                ),
+                ToastPriority::Normal,
+            )
+            .with_timeout(gui::toast::SHORT_TOAST),
+        );
 
-                self.state.gui.as_mut().unwrap().set_font(
-                    SYMBOLS_FONT_KEY,
-                    &font.name,
-                    Source::Binary(font.data.clone()),
+        log::info!("Reloaded the resource packs.");
+    }
+
+    fn try_sync_options(&mut self) {
+        if !self.state.options.synced {
+            {
+                if self
+                    .state
+                    .options
+                    .gui
+                    .get_font(&self.state.resource_man)
+                    .is_some_and(|font| !self.state.resource_man.fonts.contains_key(&font))
+                {
+                    self.state
+                        .options
+                        .gui
+                        .set_font(&self.state.resource_man, None);
+                }
+
+                let chain = self.state.options.gui.font_chain(&self.state.resource_man);
+                assert!(
+                    !chain.is_empty(),
+                    "no fonts loaded! at all! put one in there!"
                 );
+
+                self.state
+                    .gui
+                    .as_mut()
+                    .unwrap()
+                    .set_fonts(SYMBOLS_FONT_KEY, &chain);
             }
 
             self.state
@@ -206,14 +456,50 @@ impl Automancy {
                 .main_track()
                 .set_volume(self.state.options.audio.sfx_volume, Tween::default());
 
-            self.state
-                .renderer
-                .as_mut()
-                .unwrap()
-                .gpu
-                .set_vsync(self.state.options.graphics.fps_limit == 0);
+            self.state.renderer.as_mut().unwrap().gpu.set_present_mode(
+                self.state.options.graphics.fps_limit == 0,
+                self.state.options.graphics.mailbox,
+            );
 
-            self.fps_limit = Some(self.state.options.graphics.fps_limit);
+            // 0 is vsync, and 250 is unlimited
+            let fps_limit = self.state.options.graphics.fps_limit;
+            self.pacer
+                .set_fps((fps_limit > 0 && fps_limit < 250).then_some(fps_limit as f64));
+
+            if let Err(err) = self.state.game.send_message(GameSystemMessage::SetTickRate(
+                self.state.options.advanced.tick_rate.max(1) as u64,
+            )) {
+                log::error!("Could not set the tick rate! Error: {err:?}");
+            }
+
+            let signing_key = if self.state.options.advanced.sign_saves {
+                signing::read_or_create_key()
+                    .inspect_err(|err| {
+                        log::error!("Could not make a key to sign saves with! Error: {err:?}")
+                    })
+                    .ok()
+            } else {
+                None
+            };
+            if let Err(err) = self
+                .state
+                .game
+                .send_message(GameSystemMessage::SetSigningKey(signing_key))
+            {
+                log::error!("Could not set the signing key! Error: {err:?}");
+            }
+
+            let advanced = self.state.options.advanced;
+            if let Err(err) = self.state.game.send_message(GameSystemMessage::SetAutosave(
+                (advanced.autosave_interval > 0).then(|| {
+                    (
+                        Duration::from_secs(advanced.autosave_interval as u64 * 60),
+                        advanced.autosave_slots.max(1) as u32,
+                    )
+                }),
+            )) {
+                log::error!("Could not set the autosave interval! Error: {err:?}");
+            }
 
             if self.state.options.graphics.fullscreen {
                 self.state
@@ -261,20 +547,10 @@ impl ApplicationHandler for Automancy {
         ));
         log::info!("Window created.");
 
-        let gpu = self.state.tokio.block_on(Gpu::new(
-            self.window.as_ref().unwrap().clone(),
-            self.state.options.graphics.fps_limit == 0,
-        ));
-
         log::info!("Setting up rendering...");
-        let (shared_resources, render_resources, global_resources) = gpu::init_gpu_resources(
-            &gpu.device,
-            &gpu.config,
-            &self.state.resource_man,
-            self.state.vertices_init.take().unwrap(),
-            self.state.indices_init.take().unwrap(),
-        );
-        let global_resources = Arc::new(global_resources);
+        let (gpu, shared_resources, render_resources, global_resources) = self
+            .create_gpu()
+            .expect("Failed to set up the graphics device");
         let renderer = GameRenderer::new(
             gpu,
             shared_resources,
@@ -291,20 +567,14 @@ impl ApplicationHandler for Automancy {
         );
 
         gui.window.set_automatic_scale_factor(false);
-        gui.yak.set_scale_factor(
-            (renderer.gpu.window.scale_factor() * self.state.options.graphics.ui_scale.to_f64())
-                as f32,
-        );
 
         gui.fonts.insert(
             SYMBOLS_FONT_KEY.to_string(),
-            cosmic_text::fontdb::Source::Binary(Arc::from(&SYMBOLS_FONT)),
+            Source::Binary(Arc::from(&SYMBOLS_FONT)),
         );
         for (name, font) in self.state.resource_man.fonts.iter() {
-            gui.fonts.insert(
-                name.clone(),
-                cosmic_text::fontdb::Source::Binary(font.data.clone()),
-            );
+            gui.fonts
+                .insert(name.clone(), Source::Binary(font.data.clone()));
         }
         log::info!("Gui setup.");
 
@@ -321,6 +591,7 @@ impl ApplicationHandler for Automancy {
         self.state.logo = Some(logo);
         self.state.gui = Some(gui);
         self.state.renderer = Some(renderer);
+        gui::apply_ui_scale(&mut self.state);
 
         self.try_sync_options();
     }
@@ -341,6 +612,8 @@ impl ApplicationHandler for Automancy {
                 return;
             }
 
+            let redraw = matches!(event, WindowEvent::RedrawRequested);
+
             match event::on_event(
                 &mut self.state,
                 event_loop,
@@ -354,6 +627,11 @@ impl ApplicationHandler for Automancy {
                 }
             }
 
+            if redraw && !self.started {
+                self.started = true;
+                finish_startup();
+            }
+
             self.try_sync_options();
         }
     }
@@ -381,30 +659,193 @@ impl ApplicationHandler for Automancy {
     }
 
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
-        let fps_limit = self.fps_limit.unwrap_or(0);
+        if gpu::is_device_lost() && !self.closed {
+            self.recover_device(event_loop);
+        }
 
-        if fps_limit != 0 {
-            let frame_time = if fps_limit >= 250 {
-                Duration::ZERO
-            } else {
-                Duration::from_secs_f64(1.0 / fps_limit as f64)
-            };
+        if let Some(namespaces) = self
+            .pack_watcher
+            .as_mut()
+            .map(PackWatcher::poll)
+            .filter(|v| !v.is_empty())
+        {
+            if !self.closed {
+                self.reload_resources(&namespaces);
+            }
+        }
 
-            let elapsed = self.state.loop_store.frame_start.unwrap().elapsed();
-            if elapsed < frame_time {
-                let time_left = frame_time - elapsed;
+        match self.pacer.poll(Instant::now()) {
+            PacerAction::Render => {
+                event_loop.set_control_flow(ControlFlow::Poll);
 
-                event_loop.set_control_flow(ControlFlow::wait_duration(time_left));
-                return;
+                self.window.as_ref().unwrap().request_redraw();
+            }
+            PacerAction::Sleep(until) => {
+                event_loop.set_control_flow(ControlFlow::WaitUntil(until));
+            }
+            PacerAction::Spin => {
+                event_loop.set_control_flow(ControlFlow::Poll);
             }
-        } else {
-            event_loop.set_control_flow(ControlFlow::Poll);
         }
+    }
+}
+
+/// Gets how many launches in a row crashed before the first frame was drawn.
+fn startup_crashes() -> u32 {
+    fs::read_to_string(STARTUP_CRASHES_PATH)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(0)
+}
 
-        self.window.as_ref().unwrap().request_redraw();
+/// Counts this launch as crashed, until [finish_startup] is called once the first frame is drawn.
+fn begin_startup(crashes: u32) {
+    if let Err(err) = fs::write(STARTUP_CRASHES_PATH, (crashes + 1).to_string()) {
+        log::warn!("Could not write the startup crash count! Error: {err}");
     }
 }
 
+fn finish_startup() {
+    _ = fs::remove_file(STARTUP_CRASHES_PATH);
+}
+
+/// Asks if the game should start in safe mode, after it crashed while starting too many times in a row.
+fn offer_safe_mode(crashes: u32) -> bool {
+    if crashes < SAFE_MODE_PROMPT_CRASHES {
+        return false;
+    }
+
+    let result = MessageDialog::new()
+        .set_level(MessageLevel::Warning)
+        .set_buttons(MessageButtons::YesNo)
+        .set_title("automancy safe mode")
+        .set_description(format!(
+            "automancy crashed while starting the last {crashes} times. This can be caused by a broken resource pack, or broken options.\n\nStart in safe mode, with only the core pack and the default options? Your options aren't changed unless you save them."
+        ))
+        .show();
+
+    result == MessageDialogResult::Yes
+}
+
+/// Reads `--verify-map <name>`, and `--repair`, from the command line.
+fn verify_map_args() -> Option<(String, bool)> {
+    let args = env::args().collect::<Vec<_>>();
+
+    let map_name = args
+        .iter()
+        .position(|v| v == "--verify-map")
+        .and_then(|i| args.get(i + 1))?;
+
+    Some((map_name.clone(), args.iter().any(|v| v == "--repair")))
+}
+
+/// Verifies a saved map, repairing it if asked to, and prints the report instead of starting the game.
+fn verify_map_cli(
+    resource_man: &ResourceManager,
+    map_name: String,
+    repair: bool,
+) -> anyhow::Result<()> {
+    let opt = LoadMapOption::FromSave(map::sanitize_name(map_name));
+
+    let report = if repair {
+        verify::repair_map(resource_man, &opt)?
+    } else {
+        verify::verify_map(resource_man, &opt)
+            .ok_or_else(|| anyhow::anyhow!("{opt} could not be read"))?
+    };
+
+    println!("{opt}: {} problem(s) found", report.issues.len());
+    for line in report.lines() {
+        println!("- {line}");
+    }
+    if let Some(backup) = &report.backup {
+        println!("Backed up the map to {}", backup.display());
+    }
+    if let Some(path) = &report.path {
+        println!("Wrote the report to {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Runs the script tests of every pack, and prints which passed, instead of starting the game.
+fn test_scripts_cli(resource_man: &ResourceManager) -> anyhow::Result<()> {
+    let mut results = Vec::new();
+
+    for (namespace, file) in resource_man.find_script_tests(Path::new(RESOURCES_PATH)) {
+        results.extend(resource_man.run_script_test(&file, &namespace));
+    }
+
+    for result in &results {
+        match &result.error {
+            None => println!("PASS {}::{}", result.file.display(), result.name),
+            Some(error) => println!("FAIL {}::{}\n  {error}", result.file.display(), result.name),
+        }
+    }
+
+    let failed = results.iter().filter(|v| v.error.is_some()).count();
+    println!("{} passed, {failed} failed", results.len() - failed);
+
+    if failed > 0 {
+        anyhow::bail!("{failed} script test(s) failed");
+    }
+
+    Ok(())
+}
+
+/// Reads `--export-registry <path>`, which writes the registry of the loaded packs as JSON.
+fn export_registry_args() -> Option<String> {
+    let args = env::args().collect::<Vec<_>>();
+
+    args.iter()
+        .position(|v| v == "--export-registry")
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
+
+/// Reads `--asset-report <path>`, which writes the unused assets and broken references of the loaded packs as Markdown.
+fn asset_report_args() -> Option<String> {
+    let args = env::args().collect::<Vec<_>>();
+
+    args.iter()
+        .position(|v| v == "--asset-report")
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
+
+/// Reads `--diff-registry <old> <new>`, and `--notes <path>`, from the command line.
+fn diff_registry_args() -> Option<(String, String, Option<String>)> {
+    let args = env::args().collect::<Vec<_>>();
+
+    let i = args.iter().position(|v| v == "--diff-registry")?;
+    let notes = args
+        .iter()
+        .position(|v| v == "--notes")
+        .and_then(|i| args.get(i + 1))
+        .cloned();
+
+    Some((args.get(i + 1)?.clone(), args.get(i + 2)?.clone(), notes))
+}
+
+/// Compares two registry exports, and prints the changes as Markdown, or writes them to the notes file, instead of
+/// starting the game. Needs no resources, so that packs can be compared without loading either.
+fn diff_registry_cli(old: &str, new: &str, notes: Option<String>) -> anyhow::Result<()> {
+    let diff = changelog::RegistryDiff::between(
+        &changelog::RegistryExport::read(Path::new(old))?,
+        &changelog::RegistryExport::read(Path::new(new))?,
+    );
+
+    match notes {
+        Some(path) => {
+            fs::write(&path, diff.to_markdown())?;
+            println!("Wrote the changes to {path}");
+        }
+        None => print!("{}", diff.to_markdown()),
+    }
+
+    Ok(())
+}
+
 fn main() -> anyhow::Result<()> {
     env::set_var("RUST_BACKTRACE", "full");
 
@@ -482,7 +923,43 @@ fn main() -> anyhow::Result<()> {
         }));
     }
 
-    let event_loop = EventLoop::new()?;
+    if let Some((old, new, notes)) = diff_registry_args() {
+        return diff_registry_cli(&old, &new, notes);
+    }
+
+    let verify_args = verify_map_args();
+    let test_scripts = env::args().any(|v| v == "--test-scripts");
+    // also made available before the resources are loaded, as the test API is only registered on their engine then
+    if test_scripts {
+        rhai_test::make_script_tests_available();
+    }
+    let watch_packs = env::args().any(|v| v == "--watch-packs");
+    // made available before the resources are loaded, so that their engine counts the operations of the scripts
+    if env::args().any(|v| v == "--profile-scripts") {
+        SCRIPT_PROFILER.make_available();
+    }
+    let export_registry = export_registry_args();
+    let asset_report = asset_report_args();
+
+    // the command line tools start no window, so they don't count as launches that could crash while starting
+    let launching = verify_args.is_none()
+        && !test_scripts
+        && export_registry.is_none()
+        && asset_report.is_none();
+
+    let safe_mode = env::args().any(|v| v == "--safe-mode") || {
+        let crashes = if launching { startup_crashes() } else { 0 };
+        let offered = offer_safe_mode(crashes);
+
+        if launching {
+            begin_startup(crashes);
+        }
+
+        offered
+    };
+    if safe_mode {
+        log::warn!("Starting in safe mode, with only the {SAFE_MODE_NAMESPACE} pack and the default options.");
+    }
 
     let mut state = {
         let tokio = Runtime::new().unwrap();
@@ -498,13 +975,50 @@ fn main() -> anyhow::Result<()> {
             builder
         })?;
 
-        let misc_options = MiscOptions::load();
+        let misc_options = if safe_mode {
+            MiscOptions::default()
+        } else {
+            MiscOptions::load()
+        };
+        set_muted_errs(misc_options.muted_errors.iter().cloned());
 
-        let (resource_man, vertices, indices) = load_resources(&misc_options.language, track);
+        let (resource_man, vertices, indices) =
+            load_resources(&misc_options.language, track, None, safe_mode)
+                .expect("Error loading resources");
         RESOURCE_MAN.write().unwrap().replace(resource_man.clone());
+        // the GUI sounds play right as the player clicks, so they shouldn't wait to be decoded
+        resource_man.preload_sounds(&["click"]);
         log::info!("Loaded resources.");
 
-        let options = GameOptions::load(&resource_man);
+        if let Some((map_name, repair)) = verify_args {
+            return verify_map_cli(&resource_man, map_name, repair);
+        }
+        if test_scripts {
+            return test_scripts_cli(&resource_man);
+        }
+        if let Some(path) = export_registry {
+            changelog::RegistryExport::new(&resource_man).write(Path::new(&path))?;
+            println!("Wrote the registry to {path}");
+
+            return Ok(());
+        }
+        if let Some(path) = asset_report {
+            let report = resource_man.asset_report();
+            report.write(Path::new(&path))?;
+            println!(
+                "Found {} broken reference(s) and {} unused asset(s), wrote the report to {path}",
+                report.broken.len(),
+                report.unused.len()
+            );
+
+            return Ok(());
+        }
+
+        let options = if safe_mode {
+            GameOptions::defaults(&resource_man)
+        } else {
+            GameOptions::load(&resource_man)
+        };
         let input_handler = InputHandler::new(&options);
 
         let mut loop_store = EventLoopStorage::default();
@@ -521,11 +1035,18 @@ fn main() -> anyhow::Result<()> {
         {
             let game = game.clone();
             tokio.spawn(async move {
-                game.send_interval(TICK_INTERVAL, || GameSystemMessage::Tick);
+                game.send_interval(WAKE_INTERVAL, || {
+                    GAME_MAILBOX.sent();
+
+                    GameSystemMessage::Tick
+                });
             });
         }
         log::info!("Game created.");
 
+        #[cfg(feature = "companion")]
+        automancy_lib::companion::spawn(&tokio, game.clone(), resource_man.clone());
+
         let start_instant = Instant::now();
         ui_game_object::init_custom_paint_state(start_instant);
         loop_store.frame_start = Some(start_instant);
@@ -546,6 +1067,7 @@ fn main() -> anyhow::Result<()> {
             gui: None,
             renderer: None,
             screenshotting: false,
+            screenshot_path: None,
 
             logo: Default::default(),
             input_hints: Default::default(),
@@ -558,14 +1080,35 @@ fn main() -> anyhow::Result<()> {
         }
     };
 
+    let event_loop = EventLoop::new()?;
+
     // load the main menu
     game_load_map_inner(&mut state, LoadMapOption::MainMenu);
 
+    if safe_mode {
+        gui::toast::push_toast(
+            Toast::new(
+                state
+                    .resource_man
+                    .gui_str(state.resource_man.registry.gui_ids.lbl_safe_mode)
+                    .to_string(),
+                ToastPriority::Normal,
+            )
+            .with_timeout(gui::toast::LONG_TOAST),
+        );
+    } else if let Some(diff) = changelog::registry_changes(&state.resource_man) {
+        state.ui_state.push_popup(PopupState::RegistryChanges(diff));
+    }
+
     let mut automancy = Automancy {
         state,
         window: None,
-        fps_limit: None,
+        pacer: FramePacer::default(),
         closed: false,
+        device_recovery: None,
+        pack_watcher: watch_packs.then(|| PackWatcher::new(Path::new(RESOURCES_PATH))),
+        safe_mode,
+        started: false,
     };
 
     event_loop.run_app(&mut automancy)?;
@@ -0,0 +1,14 @@
+#![no_main]
+
+use automancy_defs::id::Interner;
+use automancy_resources::data::DataMapRaw;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(raw) = DataMapRaw::parse(data) {
+        let mut interner = Interner::new();
+
+        raw.to_data(&interner);
+        raw.intern_to_data(&mut interner, Some("automancy"));
+    }
+});
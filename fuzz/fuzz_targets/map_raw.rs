@@ -0,0 +1,19 @@
+#![no_main]
+
+use automancy_defs::id::Interner;
+use automancy_system::map::{MapInfoRaw, MapRaw};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let interner = Interner::new();
+
+    if let Ok(map) = MapRaw::decode(data) {
+        for (_, _, data) in map.tiles {
+            data.to_data(&interner);
+        }
+    }
+
+    if let Ok(info) = MapInfoRaw::decode(data) {
+        info.data.to_data(&interner);
+    }
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use automancy_defs::id::Interner;
+use automancy_resources::registry::KeyIds;
+use automancy_system::input::default_keymap;
+use automancy_system::options::{GameOptions, MiscOptions};
+use libfuzzer_sys::fuzz_target;
+use std::sync::LazyLock;
+
+// the key IDs only name the actions, so a fresh interner stands in for the loaded resources
+static KEY_IDS: LazyLock<KeyIds> = LazyLock::new(|| KeyIds::new(&mut Interner::new()));
+
+fuzz_target!(|data: &str| {
+    if let Ok(mut options) = GameOptions::parse(data) {
+        options.validate_keymap_against(&default_keymap(&KEY_IDS));
+    }
+    let _ = MiscOptions::parse(data);
+});